@@ -1,13 +1,23 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use code_furnace_events::{Event, EventBus};
+use code_furnace_utils::ModelSettings;
 use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::mpsc;
 use uuid::Uuid;
 
 pub mod memory;
+pub mod requests;
+pub mod retry;
 pub mod specialized;
+pub mod tools;
 
 pub use memory::*;
+pub use requests::*;
+pub use retry::*;
 pub use specialized::*;
+pub use tools::*;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AgentRequest {
@@ -16,6 +26,16 @@ pub struct AgentRequest {
     pub prompt: String,
     pub context: HashMap<String, serde_json::Value>,
     pub files: Vec<String>,
+    /// Tools the model may call; only sent on non-streaming requests.
+    #[serde(default)]
+    pub tools: Vec<ToolDefinition>,
+    /// Earlier turns of this request that called tools, replayed to the model
+    /// after the prompt.
+    #[serde(default)]
+    pub tool_turns: Vec<ToolTurn>,
+    /// Model to use instead of the provider's configured one.
+    #[serde(default)]
+    pub model: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -24,6 +44,86 @@ pub struct AgentResponse {
     pub content: String,
     pub metadata: HashMap<String, serde_json::Value>,
     pub error: Option<String>,
+    /// Calls the model made in this turn that still need a result.
+    #[serde(default)]
+    pub tool_calls: Vec<ToolCall>,
+    /// Every tool call run while answering the request, in order.
+    #[serde(default)]
+    pub tool_invocations: Vec<ToolInvocation>,
+}
+
+impl AgentResponse {
+    pub fn failed(request_id: Uuid, error: impl Into<String>) -> Self {
+        Self {
+            request_id,
+            content: String::new(),
+            metadata: HashMap::new(),
+            error: Some(error.into()),
+            tool_calls: Vec::new(),
+            tool_invocations: Vec::new(),
+        }
+    }
+    
+    /// Response for a request stopped with `AgentBridge::cancel_request`.
+    pub fn cancelled(request_id: Uuid) -> Self {
+        let mut response = Self::failed(request_id, "Request cancelled");
+        response.metadata.insert("cancelled".to_string(), true.into());
+        response
+    }
+    
+    pub fn is_cancelled(&self) -> bool {
+        self.metadata.get("cancelled").and_then(|v| v.as_bool()).unwrap_or(false)
+    }
+}
+
+/// Token counts for a request, normalized across providers and stored in
+/// `AgentResponse::metadata` under the same field names.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub total_tokens: u64,
+}
+
+impl TokenUsage {
+    pub fn new(prompt_tokens: u64, completion_tokens: u64) -> Self {
+        Self {
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: prompt_tokens + completion_tokens,
+        }
+    }
+    
+    /// Claude reports `input_tokens` and `output_tokens`.
+    fn from_claude(usage: &serde_json::Value) -> Option<Self> {
+        Some(Self::new(usage["input_tokens"].as_u64()?, usage["output_tokens"].as_u64()?))
+    }
+    
+    fn from_openai(usage: &serde_json::Value) -> Option<Self> {
+        Some(Self::new(usage["prompt_tokens"].as_u64()?, usage["completion_tokens"].as_u64()?))
+    }
+    
+    pub fn from_metadata(metadata: &HashMap<String, serde_json::Value>) -> Option<Self> {
+        Some(Self {
+            prompt_tokens: metadata.get("prompt_tokens")?.as_u64()?,
+            completion_tokens: metadata.get("completion_tokens")?.as_u64()?,
+            total_tokens: metadata.get("total_tokens")?.as_u64()?,
+        })
+    }
+    
+    pub fn insert_into(&self, metadata: &mut HashMap<String, serde_json::Value>) {
+        metadata.insert("prompt_tokens".to_string(), self.prompt_tokens.into());
+        metadata.insert("completion_tokens".to_string(), self.completion_tokens.into());
+        metadata.insert("total_tokens".to_string(), self.total_tokens.into());
+    }
+}
+
+impl std::ops::AddAssign for TokenUsage {
+    fn add_assign(&mut self, other: Self) {
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+        self.total_tokens += other.total_tokens;
+    }
 }
 
 #[async_trait::async_trait]
@@ -31,11 +131,62 @@ pub trait AgentProvider: Send + Sync {
     async fn process_request(&self, request: &AgentRequest) -> Result<AgentResponse>;
     fn provider_name(&self) -> &str;
     fn supports_streaming(&self) -> bool { false }
+    
+    /// Embedding vectors for `texts`, in order, or `None` if the provider has
+    /// no embeddings endpoint.
+    async fn embed(&self, _texts: &[String]) -> Result<Option<Vec<Vec<f32>>>> {
+        Ok(None)
+    }
+    
+    /// Like `process_request`, but sends response text to `chunk_tx` as it
+    /// arrives. Providers without streaming send the whole response as one chunk.
+    async fn process_request_streaming(
+        &self,
+        request: &AgentRequest,
+        chunk_tx: mpsc::UnboundedSender<String>,
+    ) -> Result<AgentResponse> {
+        let response = self.process_request(request).await?;
+        if !response.content.is_empty() {
+            let _ = chunk_tx.send(response.content.clone());
+        }
+        Ok(response)
+    }
+}
+
+/// Calls `on_data` with the payload of each `data:` line of a server-sent event
+/// stream until the stream ends or `on_data` returns false.
+async fn read_sse_stream(
+    mut response: reqwest::Response,
+    mut on_data: impl FnMut(&str) -> Result<bool>,
+) -> Result<()> {
+    let mut pending = Vec::new();
+    
+    while let Some(bytes) = response.chunk().await? {
+        pending.extend_from_slice(&bytes);
+        
+        while let Some(newline) = pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = pending.drain(..=newline).collect();
+            let line = String::from_utf8_lossy(&line);
+            if let Some(data) = line.trim_end().strip_prefix("data:") {
+                if !on_data(data.trim_start())? {
+                    return Ok(());
+                }
+            }
+        }
+    }
+    
+    Ok(())
 }
 
 pub struct ClaudeProvider {
     api_key: String,
     client: reqwest::Client,
+    model: String,
+    max_tokens: u32,
+    temperature: Option<f64>,
+    base_url: String,
+    retry_policy: RetryPolicy,
+    event_bus: Option<EventBus>,
 }
 
 impl ClaudeProvider {
@@ -43,13 +194,58 @@ impl ClaudeProvider {
         Self {
             api_key,
             client: reqwest::Client::new(),
+            model: "claude-3-5-sonnet-20241022".to_string(),
+            max_tokens: 4000,
+            temperature: None,
+            base_url: "https://api.anthropic.com".to_string(),
+            retry_policy: RetryPolicy::default(),
+            event_bus: None,
         }
     }
+    
+    /// Overrides the defaults with whichever settings are given.
+    pub fn with_model_settings(mut self, settings: &ModelSettings) -> Self {
+        if let Some(model) = &settings.model {
+            self.model = model.clone();
+        }
+        if let Some(max_tokens) = settings.max_tokens {
+            self.max_tokens = max_tokens;
+        }
+        if settings.temperature.is_some() {
+            self.temperature = settings.temperature;
+        }
+        self
+    }
+    
+    /// Sends requests to another API host, e.g. a proxy or a local mock.
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+    
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+    
+    /// Publishes `agent.request.retrying` events while retrying.
+    pub fn with_event_bus(mut self, event_bus: EventBus) -> Self {
+        self.event_bus = Some(event_bus);
+        self
+    }
 }
 
+const OPENAI_EMBEDDING_MODEL: &str = "text-embedding-3-small";
+
 pub struct OpenAIProvider {
     api_key: String,
     client: reqwest::Client,
+    model: String,
+    max_tokens: u32,
+    temperature: Option<f64>,
+    base_url: String,
+    retry_policy: RetryPolicy,
+    event_bus: Option<EventBus>,
 }
 
 impl OpenAIProvider {
@@ -57,13 +253,49 @@ impl OpenAIProvider {
         Self {
             api_key,
             client: reqwest::Client::new(),
+            model: "gpt-4o".to_string(),
+            max_tokens: 4000,
+            temperature: Some(0.7),
+            base_url: "https://api.openai.com".to_string(),
+            retry_policy: RetryPolicy::default(),
+            event_bus: None,
+        }
+    }
+    
+    /// Overrides the defaults with whichever settings are given.
+    pub fn with_model_settings(mut self, settings: &ModelSettings) -> Self {
+        if let Some(model) = &settings.model {
+            self.model = model.clone();
         }
+        if let Some(max_tokens) = settings.max_tokens {
+            self.max_tokens = max_tokens;
+        }
+        if settings.temperature.is_some() {
+            self.temperature = settings.temperature;
+        }
+        self
+    }
+    
+    /// Sends requests to another API host, e.g. a proxy or a local mock.
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+    
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+    
+    /// Publishes `agent.request.retrying` events while retrying.
+    pub fn with_event_bus(mut self, event_bus: EventBus) -> Self {
+        self.event_bus = Some(event_bus);
+        self
     }
 }
 
-#[async_trait::async_trait]
-impl AgentProvider for ClaudeProvider {
-    async fn process_request(&self, request: &AgentRequest) -> Result<AgentResponse> {
+impl ClaudeProvider {
+    async fn send(&self, request: &AgentRequest, stream: bool) -> Result<reqwest::Response> {
         let mut prompt = request.prompt.clone();
         
         // Add file context if provided
@@ -76,57 +308,177 @@ impl AgentProvider for ClaudeProvider {
             }
         }
         
-        let payload = serde_json::json!({
-            "model": "claude-3-5-sonnet-20241022",
-            "messages": [
-                {
-                    "role": "user",
-                    "content": prompt
-                }
-            ],
-            "max_tokens": 4000
+        let mut messages = vec![
+            serde_json::json!({
+                "role": "user",
+                "content": prompt
+            }),
+        ];
+        
+        // Replay earlier tool calls and their results
+        for turn in &request.tool_turns {
+            let mut content = Vec::new();
+            if !turn.content.is_empty() {
+                content.push(serde_json::json!({ "type": "text", "text": turn.content }));
+            }
+            content.extend(turn.invocations.iter().map(|invocation| {
+                serde_json::json!({
+                    "type": "tool_use",
+                    "id": invocation.call.id,
+                    "name": invocation.call.name,
+                    "input": invocation.call.input
+                })
+            }));
+            messages.push(serde_json::json!({ "role": "assistant", "content": content }));
+            
+            let results: Vec<serde_json::Value> = turn
+                .invocations
+                .iter()
+                .map(|invocation| {
+                    serde_json::json!({
+                        "type": "tool_result",
+                        "tool_use_id": invocation.call.id,
+                        "content": invocation.output,
+                        "is_error": invocation.is_error
+                    })
+                })
+                .collect();
+            messages.push(serde_json::json!({ "role": "user", "content": results }));
+        }
+        
+        let mut payload = serde_json::json!({
+            "model": request.model.as_deref().unwrap_or(&self.model),
+            "messages": messages,
+            "max_tokens": self.max_tokens,
+            "stream": stream
         });
+        if let Some(temperature) = self.temperature {
+            payload["temperature"] = temperature.into();
+        }
+        if !request.tools.is_empty() {
+            payload["tools"] = serde_json::to_value(&request.tools)?;
+        }
         
-        let response = self.client
-            .post("https://api.anthropic.com/v1/messages")
-            .header("x-api-key", &self.api_key)
-            .header("anthropic-version", "2023-06-01")
-            .json(&payload)
-            .send()
-            .await?;
+        let url = format!("{}/v1/messages", self.base_url);
+        send_with_retry(&self.retry_policy, self.event_bus.as_ref(), "claude", request.id, || {
+            self.client
+                .post(&url)
+                .header("x-api-key", &self.api_key)
+                .header("anthropic-version", "2023-06-01")
+                .json(&payload)
+        }).await
+    }
+}
+
+#[async_trait::async_trait]
+impl AgentProvider for ClaudeProvider {
+    async fn process_request(&self, request: &AgentRequest) -> Result<AgentResponse> {
+        let response = self.send(request, false).await?;
         
         if response.status().is_success() {
             let claude_response: serde_json::Value = response.json().await?;
-            let content = claude_response["content"][0]["text"]
-                .as_str()
-                .unwrap_or("No response")
-                .to_string();
+            let mut content = String::new();
+            let mut tool_calls = Vec::new();
+            for block in claude_response["content"].as_array().into_iter().flatten() {
+                match block["type"].as_str() {
+                    Some("text") => content.push_str(block["text"].as_str().unwrap_or_default()),
+                    Some("tool_use") => tool_calls.push(ToolCall {
+                        id: block["id"].as_str().unwrap_or_default().to_string(),
+                        name: block["name"].as_str().unwrap_or_default().to_string(),
+                        input: block["input"].clone(),
+                    }),
+                    _ => {}
+                }
+            }
+            if content.is_empty() && tool_calls.is_empty() {
+                content = "No response".to_string();
+            }
+            
+            let mut metadata = HashMap::new();
+            if let Some(usage) = TokenUsage::from_claude(&claude_response["usage"]) {
+                usage.insert_into(&mut metadata);
+            }
             
             Ok(AgentResponse {
                 request_id: request.id,
                 content,
-                metadata: HashMap::new(),
+                metadata,
                 error: None,
+                tool_calls,
+                tool_invocations: Vec::new(),
             })
         } else {
             let error_text = response.text().await.unwrap_or_default();
-            Ok(AgentResponse {
-                request_id: request.id,
-                content: String::new(),
-                metadata: HashMap::new(),
-                error: Some(format!("API Error: {}", error_text)),
-            })
+            Ok(AgentResponse::failed(request.id, format!("API Error: {}", error_text)))
         }
     }
     
     fn provider_name(&self) -> &str {
         "claude"
     }
+    
+    fn supports_streaming(&self) -> bool {
+        true
+    }
+    
+    async fn process_request_streaming(
+        &self,
+        request: &AgentRequest,
+        chunk_tx: mpsc::UnboundedSender<String>,
+    ) -> Result<AgentResponse> {
+        let response = self.send(request, true).await?;
+        
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Ok(AgentResponse::failed(request.id, format!("API Error: {}", error_text)));
+        }
+        
+        let mut content = String::new();
+        let mut prompt_tokens = None;
+        let mut completion_tokens = None;
+        read_sse_stream(response, |data| {
+            let event: serde_json::Value = serde_json::from_str(data)?;
+            match event["type"].as_str() {
+                Some("message_start") => {
+                    prompt_tokens = event["message"]["usage"]["input_tokens"].as_u64();
+                    Ok(true)
+                }
+                // Carries the cumulative output token count
+                Some("message_delta") => {
+                    completion_tokens = event["usage"]["output_tokens"].as_u64().or(completion_tokens);
+                    Ok(true)
+                }
+                Some("content_block_delta") => {
+                    if let Some(text) = event["delta"]["text"].as_str() {
+                        content.push_str(text);
+                        let _ = chunk_tx.send(text.to_string());
+                    }
+                    Ok(true)
+                }
+                Some("error") => Err(anyhow::anyhow!("API Error: {}", event["error"])),
+                Some("message_stop") => Ok(false),
+                _ => Ok(true),
+            }
+        }).await?;
+        
+        let mut metadata = HashMap::new();
+        if let (Some(prompt_tokens), Some(completion_tokens)) = (prompt_tokens, completion_tokens) {
+            TokenUsage::new(prompt_tokens, completion_tokens).insert_into(&mut metadata);
+        }
+        
+        Ok(AgentResponse {
+            request_id: request.id,
+            content,
+            metadata,
+            error: None,
+            tool_calls: Vec::new(),
+            tool_invocations: Vec::new(),
+        })
+    }
 }
 
-#[async_trait::async_trait]
-impl AgentProvider for OpenAIProvider {
-    async fn process_request(&self, request: &AgentRequest) -> Result<AgentResponse> {
+impl OpenAIProvider {
+    async fn send(&self, request: &AgentRequest, stream: bool) -> Result<reqwest::Response> {
         let mut messages = vec![
             serde_json::json!({
                 "role": "system",
@@ -154,76 +506,494 @@ impl AgentProvider for OpenAIProvider {
             }));
         }
         
-        let payload = serde_json::json!({
-            "model": "gpt-4o",
+        // Replay earlier tool calls and their results
+        for turn in &request.tool_turns {
+            let tool_calls: Vec<serde_json::Value> = turn
+                .invocations
+                .iter()
+                .map(|invocation| {
+                    serde_json::json!({
+                        "id": invocation.call.id,
+                        "type": "function",
+                        "function": {
+                            "name": invocation.call.name,
+                            "arguments": invocation.call.input.to_string()
+                        }
+                    })
+                })
+                .collect();
+            let content = (!turn.content.is_empty()).then(|| turn.content.clone());
+            messages.push(serde_json::json!({
+                "role": "assistant",
+                "content": content,
+                "tool_calls": tool_calls
+            }));
+            
+            for invocation in &turn.invocations {
+                messages.push(serde_json::json!({
+                    "role": "tool",
+                    "tool_call_id": invocation.call.id,
+                    "content": invocation.output
+                }));
+            }
+        }
+        
+        let mut payload = serde_json::json!({
+            "model": request.model.as_deref().unwrap_or(&self.model),
             "messages": messages,
-            "max_tokens": 4000,
-            "temperature": 0.7
+            "max_tokens": self.max_tokens,
+            "stream": stream
         });
+        if let Some(temperature) = self.temperature {
+            payload["temperature"] = temperature.into();
+        }
+        if !request.tools.is_empty() {
+            let tools: Vec<serde_json::Value> = request
+                .tools
+                .iter()
+                .map(|tool| {
+                    serde_json::json!({
+                        "type": "function",
+                        "function": {
+                            "name": tool.name,
+                            "description": tool.description,
+                            "parameters": tool.input_schema
+                        }
+                    })
+                })
+                .collect();
+            payload["tools"] = tools.into();
+        }
+        if stream {
+            // The final chunk then reports usage for the whole response
+            payload["stream_options"] = serde_json::json!({ "include_usage": true });
+        }
         
-        let response = self.client
-            .post("https://api.openai.com/v1/chat/completions")
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .header("Content-Type", "application/json")
-            .json(&payload)
-            .send()
-            .await?;
+        let url = format!("{}/v1/chat/completions", self.base_url);
+        send_with_retry(&self.retry_policy, self.event_bus.as_ref(), "openai", request.id, || {
+            self.client
+                .post(&url)
+                .header("Authorization", format!("Bearer {}", self.api_key))
+                .header("Content-Type", "application/json")
+                .json(&payload)
+        }).await
+    }
+}
+
+#[async_trait::async_trait]
+impl AgentProvider for OpenAIProvider {
+    async fn process_request(&self, request: &AgentRequest) -> Result<AgentResponse> {
+        let response = self.send(request, false).await?;
         
         if response.status().is_success() {
             let openai_response: serde_json::Value = response.json().await?;
-            let content = openai_response["choices"][0]["message"]["content"]
-                .as_str()
-                .unwrap_or("No response")
-                .to_string();
+            let message = &openai_response["choices"][0]["message"];
+            let tool_calls: Vec<ToolCall> = message["tool_calls"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(|call| {
+                    // Arguments arrive as a JSON-encoded string
+                    let arguments = call["function"]["arguments"].as_str().unwrap_or_default();
+                    ToolCall {
+                        id: call["id"].as_str().unwrap_or_default().to_string(),
+                        name: call["function"]["name"].as_str().unwrap_or_default().to_string(),
+                        input: serde_json::from_str(arguments).unwrap_or_else(|_| arguments.into()),
+                    }
+                })
+                .collect();
+            let content = match message["content"].as_str() {
+                Some(content) => content.to_string(),
+                None if !tool_calls.is_empty() => String::new(),
+                None => "No response".to_string(),
+            };
+            
+            let mut metadata = HashMap::new();
+            if let Some(usage) = TokenUsage::from_openai(&openai_response["usage"]) {
+                usage.insert_into(&mut metadata);
+            }
             
             Ok(AgentResponse {
                 request_id: request.id,
                 content,
-                metadata: HashMap::new(),
+                metadata,
                 error: None,
+                tool_calls,
+                tool_invocations: Vec::new(),
             })
         } else {
             let error_text = response.text().await.unwrap_or_default();
-            Ok(AgentResponse {
-                request_id: request.id,
-                content: String::new(),
-                metadata: HashMap::new(),
-                error: Some(format!("OpenAI API Error: {}", error_text)),
-            })
+            Ok(AgentResponse::failed(request.id, format!("OpenAI API Error: {}", error_text)))
         }
     }
     
     fn provider_name(&self) -> &str {
         "openai"
     }
+    
+    async fn embed(&self, texts: &[String]) -> Result<Option<Vec<Vec<f32>>>> {
+        let payload = serde_json::json!({
+            "model": OPENAI_EMBEDDING_MODEL,
+            "input": texts
+        });
+        
+        let url = format!("{}/v1/embeddings", self.base_url);
+        let response = send_with_retry(&self.retry_policy, self.event_bus.as_ref(), "openai", Uuid::new_v4(), || {
+            self.client
+                .post(&url)
+                .header("Authorization", format!("Bearer {}", self.api_key))
+                .json(&payload)
+        }).await?;
+        
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(anyhow::anyhow!("OpenAI API Error: {}", error_text));
+        }
+        
+        let body: serde_json::Value = response.json().await?;
+        let mut data = body["data"]
+            .as_array()
+            .ok_or_else(|| anyhow::anyhow!("Embeddings response has no data"))?
+            .clone();
+        data.sort_by_key(|item| item["index"].as_u64());
+        
+        let embeddings = data
+            .iter()
+            .map(|item| serde_json::from_value::<Vec<f32>>(item["embedding"].clone()))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Some(embeddings))
+    }
+    
+    fn supports_streaming(&self) -> bool {
+        true
+    }
+    
+    async fn process_request_streaming(
+        &self,
+        request: &AgentRequest,
+        chunk_tx: mpsc::UnboundedSender<String>,
+    ) -> Result<AgentResponse> {
+        let response = self.send(request, true).await?;
+        
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Ok(AgentResponse::failed(request.id, format!("OpenAI API Error: {}", error_text)));
+        }
+        
+        let mut content = String::new();
+        let mut usage = None;
+        read_sse_stream(response, |data| {
+            if data == "[DONE]" {
+                return Ok(false);
+            }
+            
+            let event: serde_json::Value = serde_json::from_str(data)?;
+            if let Some(error) = event.get("error") {
+                return Err(anyhow::anyhow!("OpenAI API Error: {}", error));
+            }
+            if let Some(text) = event["choices"][0]["delta"]["content"].as_str() {
+                content.push_str(text);
+                let _ = chunk_tx.send(text.to_string());
+            }
+            usage = TokenUsage::from_openai(&event["usage"]).or(usage);
+            Ok(true)
+        }).await?;
+        
+        let mut metadata = HashMap::new();
+        if let Some(usage) = usage {
+            usage.insert_into(&mut metadata);
+        }
+        
+        Ok(AgentResponse {
+            request_id: request.id,
+            content,
+            metadata,
+            error: None,
+            tool_calls: Vec::new(),
+            tool_invocations: Vec::new(),
+        })
+    }
+}
+
+const EMBEDDING_BATCH_SIZE: usize = 100;
+
+/// Embedding work for a semantic search, gathered by
+/// `AgentBridge::begin_semantic_search` so it can run without the bridge.
+pub struct SemanticSearch {
+    query: String,
+    providers: Vec<Arc<dyn AgentProvider>>,
+    missing: Vec<(Uuid, String)>,
+}
+
+/// The query's embedding and those of the messages that were missing one.
+pub struct SearchEmbeddings {
+    query: Vec<f32>,
+    messages: Vec<(Uuid, Vec<f32>)>,
+}
+
+impl SemanticSearch {
+    /// Embeds the query and the uncached messages with the first provider
+    /// that has embeddings. `None` if no provider has them.
+    pub async fn embed(&self) -> Result<Option<SearchEmbeddings>> {
+        for provider in &self.providers {
+            let Some(mut query) = provider.embed(std::slice::from_ref(&self.query)).await? else {
+                continue;
+            };
+            let query = query.pop().ok_or_else(|| anyhow::anyhow!("Provider returned no embedding for the query"))?;
+            
+            let mut messages = Vec::with_capacity(self.missing.len());
+            for batch in self.missing.chunks(EMBEDDING_BATCH_SIZE) {
+                let texts: Vec<String> = batch.iter().map(|(_, text)| text.clone()).collect();
+                let embeddings = provider.embed(&texts).await?.unwrap_or_default();
+                if embeddings.len() != batch.len() {
+                    return Err(anyhow::anyhow!("Provider returned {} embeddings for {} messages", embeddings.len(), batch.len()));
+                }
+                messages.extend(batch.iter().map(|(id, _)| *id).zip(embeddings));
+            }
+            
+            return Ok(Some(SearchEmbeddings { query, messages }));
+        }
+        Ok(None)
+    }
+}
+
+/// Default token budget for a request's prompt, history and file context.
+pub const DEFAULT_CONTEXT_TOKEN_BUDGET: usize = 100_000;
+
+/// Staged diff attached to commit requests is cut to this many characters.
+pub const MAX_GIT_DIFF_CHARS: usize = 8_000;
+
+/// Supplies the staged diff of a repository. Implemented outside this crate
+/// so the bridge does not depend on the workspace's git integration.
+pub trait StagedDiffSource: Send + Sync {
+    fn staged_diff(&self, repo_path: &std::path::Path) -> Result<String>;
 }
 
 pub struct AgentBridge {
-    providers: HashMap<String, Box<dyn AgentProvider>>,
+    providers: HashMap<String, Arc<dyn AgentProvider>>,
     default_provider: String,
     memory: AgentMemory,
     router: AgentRouter,
+    requests: AgentRequestRegistry,
+    context_token_budget: usize,
+    summarize_dropped_context: bool,
+    tools: ToolSet,
+    diff_source: Option<Arc<dyn StagedDiffSource>>,
+    event_bus: Option<EventBus>,
 }
 
 impl AgentBridge {
+    /// Restores conversations saved under the app data directory, falling back
+    /// to in-memory history if it cannot be read.
     pub fn new() -> Self {
+        let memory = code_furnace_utils::paths::get_app_data_dir()
+            .and_then(|dir| AgentMemory::with_storage(dir.join("conversations")))
+            .unwrap_or_else(|e| {
+                tracing::warn!("Conversation history will not be saved: {}", e);
+                AgentMemory::new()
+            });
+        Self::with_memory(memory)
+    }
+    
+    /// A bridge keeping its conversations in `memory`, such as one stored in a
+    /// directory other than the app data directory.
+    pub fn with_memory(memory: AgentMemory) -> Self {
         Self {
             providers: HashMap::new(),
             default_provider: "claude".to_string(),
-            memory: AgentMemory::new(),
+            memory,
             router: AgentRouter::new(),
+            requests: AgentRequestRegistry::new(),
+            context_token_budget: DEFAULT_CONTEXT_TOKEN_BUDGET,
+            summarize_dropped_context: true,
+            tools: ToolSet::new(),
+            diff_source: None,
+            event_bus: None,
         }
     }
     
+    /// Publishes `agent.context.truncated` when a request had to be trimmed.
+    pub fn with_event_bus(mut self, event_bus: EventBus) -> Self {
+        self.event_bus = Some(event_bus);
+        self
+    }
+    
+    /// Attaches the staged diff to commit requests routed to `git-assistant`.
+    pub fn with_diff_source(mut self, diff_source: Arc<dyn StagedDiffSource>) -> Self {
+        self.diff_source = Some(diff_source);
+        self
+    }
+    
+    pub fn set_context_budget(&mut self, token_budget: usize, summarize_dropped: bool) {
+        self.context_token_budget = token_budget;
+        self.summarize_dropped_context = summarize_dropped;
+    }
+    
     pub fn register_provider(&mut self, name: String, provider: Box<dyn AgentProvider>) {
-        self.providers.insert(name, provider);
+        self.providers.insert(name, Arc::from(provider));
+    }
+    
+    /// Drops every registered provider, specialized agents included, so they
+    /// can be registered afresh. Requests already running keep the provider
+    /// they started with.
+    pub fn clear_providers(&mut self) {
+        self.providers.clear();
     }
     
     pub fn set_default_provider(&mut self, name: String) {
         self.default_provider = name;
     }
     
+    /// Offers `tool` to the model on every non-streaming request.
+    pub fn register_tool(&mut self, tool: Tool) {
+        self.tools.register(tool);
+    }
+    
+    /// Grants or revokes the user's permission for a tool that requires it.
+    pub fn set_tool_allowed(&mut self, name: &str, allowed: bool) -> Result<()> {
+        self.tools.set_allowed(name, allowed)
+    }
+    
+    pub fn is_tool_allowed(&self, name: &str) -> bool {
+        self.tools.is_allowed(name)
+    }
+    
+    pub fn list_tools(&self) -> Vec<ToolDefinition> {
+        self.tools.definitions()
+    }
+    
     pub async fn process_request(&mut self, request: AgentRequest) -> Result<AgentResponse> {
+        let pending = self.begin_request(request)?;
+        let response = pending.execute(&self.requests).await?;
+        self.finish_request(&pending, &response)?;
+        Ok(response)
+    }
+    
+    /// See `PendingRequest::execute_streaming` for the events published.
+    pub async fn process_request_streaming(&mut self, request: AgentRequest, event_bus: &EventBus) -> Result<AgentResponse> {
+        let pending = self.begin_request(request)?;
+        let response = pending.execute_streaming(&self.requests, event_bus).await?;
+        self.finish_request(&pending, &response)?;
+        Ok(response)
+    }
+    
+    /// First half of `process_request`: records the prompt and picks a
+    /// provider. Callers sharing the bridge behind a lock can release it while
+    /// the returned request runs, then call `finish_request`.
+    pub fn begin_request(&mut self, request: AgentRequest) -> Result<PendingRequest> {
+        // The tools and staged diff go out with the prompt, so the history
+        // budget leaves room for them
+        let tools = self.tools.definitions();
+        let staged_diff = self.staged_diff_section(&request).unwrap_or_default();
+        let reserved_tokens = estimate_tokens(&serde_json::to_string(&tools)?) + estimate_tokens(&staged_diff);
+        let (context, mut enhanced_request) = self.prepare_request(&request, reserved_tokens)?;
+        enhanced_request.tools = tools;
+        enhanced_request.prompt.push_str(&staged_diff);
+        let conversation = self
+            .memory
+            .get_active_conversation()
+            .ok_or_else(|| anyhow::anyhow!("No active conversation"))?;
+        let provider = self.select_provider(&request, conversation.provider.as_deref());
+        // The conversation's model is only meaningful to its own provider, the
+        // default one if it has none; another provider keeps its own model
+        let conversation_provider = conversation.provider.as_ref().unwrap_or(&self.default_provider);
+        let same_provider = match (&provider, self.providers.get(conversation_provider)) {
+            (Some(selected), Some(pinned)) => Arc::ptr_eq(selected, pinned),
+            _ => false,
+        };
+        if enhanced_request.model.is_none() && same_provider {
+            enhanced_request.model = conversation.model.clone();
+        }
+        // Without a provider the request fails without running, so there is
+        // nothing to cancel
+        if provider.is_some() {
+            self.requests.register(request.id);
+        }
+        
+        Ok(PendingRequest {
+            request_id: request.id,
+            conversation_id: conversation.id,
+            context,
+            request: enhanced_request,
+            provider,
+            tools: self.tools.clone(),
+        })
+    }
+    
+    /// Like `begin_request`, for one-off requests such as commit messages:
+    /// nothing is recorded in a conversation, no history is added and no
+    /// tools are offered. The result must not be passed to `finish_request`.
+    pub fn begin_detached_request(&self, request: AgentRequest) -> PendingRequest {
+        let mut detached_request = request.clone();
+        if let Some(staged_diff) = self.staged_diff_section(&request) {
+            detached_request.prompt.push_str(&staged_diff);
+        }
+        let provider = self.select_provider(&request, None);
+        if provider.is_some() {
+            self.requests.register(request.id);
+        }
+        
+        PendingRequest {
+            request_id: request.id,
+            conversation_id: Uuid::nil(),
+            context: memory::MessageContext::default(),
+            request: detached_request,
+            provider,
+            tools: ToolSet::new(),
+        }
+    }
+    
+    /// Stores the response in the conversation the request started in, after
+    /// a tool message for each tool call made. Text from a failed stream is
+    /// kept; cancelled requests leave no reply.
+    pub fn finish_request(&mut self, pending: &PendingRequest, response: &AgentResponse) -> Result<()> {
+        if let Some(usage) = TokenUsage::from_metadata(&response.metadata) {
+            self.memory.record_usage(pending.conversation_id, usage);
+        }
+        
+        for invocation in &response.tool_invocations {
+            let context = memory::MessageContext {
+                project_path: pending.context.project_path.clone(),
+                metadata: HashMap::from([
+                    ("tool".to_string(), invocation.call.name.clone().into()),
+                    ("tool_input".to_string(), invocation.call.input.clone()),
+                    ("is_error".to_string(), invocation.is_error.into()),
+                ]),
+                ..Default::default()
+            };
+            self.memory.add_message(
+                pending.conversation_id,
+                memory::MessageRole::Tool,
+                format!("{} {}\n{}", invocation.call.name, invocation.call.input, invocation.output),
+                context,
+            )?;
+        }
+        
+        if response.is_cancelled() || response.content.is_empty() {
+            return Ok(());
+        }
+        
+        self.memory.add_message(
+            pending.conversation_id,
+            memory::MessageRole::Assistant,
+            response.content.clone(),
+            pending.context.clone(),
+        )
+    }
+    
+    pub fn request_registry(&self) -> AgentRequestRegistry {
+        self.requests.clone()
+    }
+    
+    pub fn cancel_request(&self, request_id: Uuid) -> bool {
+        self.requests.cancel(request_id)
+    }
+    
+    /// Records the user message and returns the request with conversation
+    /// history folded into its prompt. History is trimmed oldest first to fit
+    /// the context budget, less `reserved_tokens` for what is sent alongside,
+    /// then the largest attached files are dropped.
+    fn prepare_request(&mut self, request: &AgentRequest, reserved_tokens: usize) -> Result<(memory::MessageContext, AgentRequest)> {
         // Add user message to memory with context
         let context = memory::MessageContext {
             files: request.files.clone(),
@@ -250,56 +1020,134 @@ impl AgentBridge {
             context.clone(),
         )?;
         
+        let budget = self.context_token_budget;
+        let prompt_tokens = estimate_tokens(&request.prompt) + reserved_tokens;
+        
+        // Providers inline whole files, so budget them by size on disk
+        let mut files: Vec<(String, usize)> = request
+            .files
+            .iter()
+            .map(|file| {
+                let bytes = std::fs::metadata(file).map(|m| m.len() as usize).unwrap_or(0);
+                (file.clone(), bytes.div_ceil(4))
+            })
+            .collect();
+        let mut file_tokens: usize = files.iter().map(|(_, tokens)| tokens).sum();
+        let mut dropped_files = Vec::new();
+        while prompt_tokens + file_tokens > budget && !files.is_empty() {
+            let largest = files
+                .iter()
+                .enumerate()
+                .max_by_key(|(_, (_, tokens))| *tokens)
+                .map(|(index, _)| index)
+                .unwrap_or(0);
+            let (file, tokens) = files.remove(largest);
+            file_tokens -= tokens;
+            dropped_files.push(file);
+        }
+        
         // Build conversation context for the request
-        let conversation_context = self.memory.build_conversation_context(None, 10);
+        let history_budget = budget.saturating_sub(prompt_tokens + file_tokens);
+        let conversation_context = self.memory.build_budgeted_context(10, history_budget, self.summarize_dropped_context);
         
         // Enhanced request with conversation history
         let mut enhanced_request = request.clone();
-        if !conversation_context.is_empty() {
+        enhanced_request.files = files.into_iter().map(|(file, _)| file).collect();
+        if !conversation_context.text.is_empty() {
             enhanced_request.prompt = format!(
                 "Conversation History:\n{}\n\n---\n\nCurrent Request:\n{}",
-                conversation_context,
+                conversation_context.text,
                 request.prompt
             );
         }
         
+        if conversation_context.dropped_messages > 0 || !dropped_files.is_empty() {
+            tracing::info!(
+                "Trimmed context for request {}: {} messages and {} files dropped",
+                request.id, conversation_context.dropped_messages, dropped_files.len()
+            );
+            
+            if let Some(event_bus) = &self.event_bus {
+                let event = Event::new(
+                    "agent.context.truncated",
+                    "agent-bridge",
+                    serde_json::json!({
+                        "request_id": request.id,
+                        "token_budget": budget,
+                        "dropped_messages": conversation_context.dropped_messages,
+                        "summarized": conversation_context.summarized,
+                        "dropped_files": dropped_files
+                    }),
+                );
+                let _ = event_bus.publish(event);
+            }
+        }
+        
+        Ok((context, enhanced_request))
+    }
+    
+    /// The staged diff of the request's repository, to append to its prompt,
+    /// when it asks the git assistant about a commit. The repository is taken
+    /// from the `repo_path` context entry, falling back to `project_path`.
+    fn staged_diff_section(&self, request: &AgentRequest) -> Option<String> {
+        let diff_source = self.diff_source.as_ref()?;
+        
+        let agent_name = if request.agent_type.is_empty() {
+            self.router.determine_agent_for_request(request)
+        } else {
+            request.agent_type.clone()
+        };
+        if agent_name != "git-assistant" || !request.prompt.to_lowercase().contains("commit") {
+            return None;
+        }
+        
+        let repo_path = request
+            .context
+            .get("repo_path")
+            .or_else(|| request.context.get("project_path"))
+            .and_then(|v| v.as_str())?;
+        
+        let diff = match diff_source.staged_diff(std::path::Path::new(repo_path)) {
+            Ok(diff) if !diff.trim().is_empty() => diff,
+            Ok(_) => return None,
+            Err(e) => {
+                tracing::warn!("Could not read staged diff for {}: {}", repo_path, e);
+                return None;
+            }
+        };
+        
+        let diff = match diff.char_indices().nth(MAX_GIT_DIFF_CHARS) {
+            Some((end, _)) => {
+                // Cut at a line boundary so the model never sees half a line
+                let end = diff[..end].rfind('\n').map(|newline| newline + 1).unwrap_or(end);
+                format!("{}[diff truncated]\n", &diff[..end])
+            }
+            None => diff,
+        };
+        Some(format!("\n\nStaged changes:\n```diff\n{}```", diff))
+    }
+    
+    /// An explicit `agent_type` wins, then the conversation's `preferred`
+    /// provider, then the router's choice and finally the default provider.
+    fn select_provider(&self, request: &AgentRequest, preferred: Option<&str>) -> Option<Arc<dyn AgentProvider>> {
         // Use router to determine the best agent, fallback to specified or default
         let provider = if !request.agent_type.is_empty() {
             // Use specified agent type
             self.providers.get(&request.agent_type)
+        } else if let Some(preferred) = preferred.filter(|name| self.providers.contains_key(*name)) {
+            self.providers.get(preferred)
         } else {
             // Use router to auto-select agent
-            self.router.route_request(&request)
+            self.router.route_request(request)
                 .ok()
                 .and_then(|_| {
-                    let agent_name = self.router.determine_agent_for_request(&request);
+                    let agent_name = self.router.determine_agent_for_request(request);
                     self.providers.get(&agent_name)
                 })
                 .or_else(|| self.providers.get(&self.default_provider))
         };
         
-        if let Some(provider) = provider {
-            let response = provider.process_request(&enhanced_request).await?;
-            
-            // Add assistant response to memory
-            if response.error.is_none() {
-                self.memory.add_message_to_active(
-                    memory::MessageRole::Assistant,
-                    response.content.clone(),
-                    context,
-                )?;
-            }
-            
-            Ok(response)
-        } else {
-            let error_msg = format!("No suitable agent provider found for request");
-            Ok(AgentResponse {
-                request_id: request.id,
-                content: String::new(),
-                metadata: HashMap::new(),
-                error: Some(error_msg),
-            })
-        }
+        provider.cloned()
     }
     
     // Memory management methods
@@ -323,15 +1171,83 @@ impl AgentBridge {
         self.memory.list_conversations()
     }
     
+    /// Overrides the provider and model for requests made while conversation
+    /// `id` is active; `None` restores the default.
+    pub fn set_conversation_provider(&mut self, id: Uuid, provider: Option<String>, model: Option<String>) -> Result<()> {
+        if let Some(provider) = &provider {
+            if !self.providers.contains_key(provider) {
+                return Err(anyhow::anyhow!("Unknown agent provider: {}", provider));
+            }
+        }
+        self.memory.set_conversation_provider(id, provider, model)
+    }
+    
     pub fn search_conversations(&self, query: &str) -> Vec<&ConversationThread> {
         self.memory.search_conversations(query)
     }
     
+    /// Ranks conversations by embedding similarity to `query`, keeping those
+    /// scoring at least `similarity` (cosine, -1 to 1). Callers sharing the
+    /// bridge behind a lock should use `begin_semantic_search` and
+    /// `finish_semantic_search` instead, so the embedding calls run without it.
+    pub async fn search_conversations_semantic(&mut self, query: &str, similarity: f32) -> Vec<&ConversationThread> {
+        let search = self.begin_semantic_search(query);
+        let embeddings = search.embed().await;
+        self.finish_semantic_search(query, similarity, embeddings)
+    }
+    
+    /// First half of `search_conversations_semantic`: gathers the providers
+    /// to try, the default first and then the others by name, and the
+    /// messages whose embeddings are not cached yet.
+    pub fn begin_semantic_search(&self, query: &str) -> SemanticSearch {
+        let mut providers: Vec<(&String, &Arc<dyn AgentProvider>)> = self.providers.iter().collect();
+        providers.sort_by_key(|(name, _)| (**name != self.default_provider, *name));
+        
+        SemanticSearch {
+            query: query.to_string(),
+            providers: providers.into_iter().map(|(_, provider)| provider.clone()).collect(),
+            missing: self.memory.messages_missing_embeddings(),
+        }
+    }
+    
+    /// Caches the embeddings from `SemanticSearch::embed` and ranks by them,
+    /// or falls back to substring search if there are none.
+    pub fn finish_semantic_search(
+        &mut self,
+        query: &str,
+        similarity: f32,
+        embeddings: Result<Option<SearchEmbeddings>>,
+    ) -> Vec<&ConversationThread> {
+        match embeddings {
+            Ok(Some(embeddings)) => {
+                self.memory.cache_embeddings(embeddings.messages);
+                self.memory.rank_by_embedding(&embeddings.query, similarity)
+            }
+            Ok(None) => self.memory.search_conversations(query),
+            Err(e) => {
+                tracing::warn!("Semantic search unavailable, using substring search: {}", e);
+                self.memory.search_conversations(query)
+            }
+        }
+    }
+    
+    pub fn export_conversation(&self, id: Uuid, format: ExportFormat) -> Result<String> {
+        self.memory.export_conversation(id, format)
+    }
+    
+    pub fn save_conversations(&self) -> Result<()> {
+        self.memory.save_all()
+    }
+    
+    pub fn get_conversation_usage(&self, id: Uuid) -> Option<TokenUsage> {
+        self.memory.get_conversation(id).map(|conversation| conversation.usage)
+    }
+    
     // Router management
     pub fn register_specialized_agent(&mut self, agent_type: AgentType, base_provider: Box<dyn AgentProvider>) {
         let specialized = SpecializedAgent::new(agent_type.clone(), base_provider);
         let name = specialized.provider_name().to_string();
-        self.providers.insert(name.clone(), Box::new(specialized));
+        self.providers.insert(name.clone(), Arc::new(specialized));
         // Note: Router will determine agent by name during routing
     }
     
@@ -344,4 +1260,166 @@ impl Default for AgentBridge {
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// Answers nothing; with `topics`, embeds text as one dimension per topic
+    /// it mentions.
+    struct StubProvider {
+        name: &'static str,
+        topics: Option<Vec<&'static str>>,
+    }
+    
+    #[async_trait::async_trait]
+    impl AgentProvider for StubProvider {
+        async fn process_request(&self, request: &AgentRequest) -> Result<AgentResponse> {
+            Ok(AgentResponse::failed(request.id, "stub"))
+        }
+        
+        fn provider_name(&self) -> &str {
+            self.name
+        }
+        
+        async fn embed(&self, texts: &[String]) -> Result<Option<Vec<Vec<f32>>>> {
+            let Some(topics) = &self.topics else {
+                return Ok(None);
+            };
+            Ok(Some(
+                texts
+                    .iter()
+                    .map(|text| {
+                        let text = text.to_lowercase();
+                        topics.iter().map(|topic| if text.contains(topic) { 1.0 } else { 0.0 }).collect()
+                    })
+                    .collect(),
+            ))
+        }
+    }
+    
+    fn conversation(bridge: &mut AgentBridge, name: &str, message: &str) -> Uuid {
+        let id = bridge.create_conversation(name.to_string());
+        bridge
+            .memory
+            .add_message(id, MessageRole::User, message.to_string(), MessageContext::default())
+            .unwrap();
+        id
+    }
+    
+    fn request(prompt: &str) -> AgentRequest {
+        AgentRequest {
+            id: Uuid::new_v4(),
+            agent_type: String::new(),
+            prompt: prompt.to_string(),
+            context: HashMap::new(),
+            files: Vec::new(),
+            tools: Vec::new(),
+            tool_turns: Vec::new(),
+            model: None,
+        }
+    }
+    
+    struct StubDiff(String);
+    
+    impl StagedDiffSource for StubDiff {
+        fn staged_diff(&self, _repo_path: &std::path::Path) -> Result<String> {
+            Ok(self.0.clone())
+        }
+    }
+    
+    #[test]
+    fn the_staged_diff_counts_against_the_history_budget() {
+        let diff = "+ added line\n".repeat(100);
+        let mut bridge = AgentBridge::with_memory(AgentMemory::new()).with_diff_source(Arc::new(StubDiff(diff.clone())));
+        conversation(&mut bridge, "Commit", &"x".repeat(400));
+        let commit_request = || AgentRequest {
+            agent_type: "git-assistant".to_string(),
+            context: HashMap::from([("repo_path".to_string(), "/repo".into())]),
+            ..request("Write a commit message")
+        };
+        
+        bridge.set_context_budget(1_000, false);
+        let pending = bridge.begin_request(commit_request()).unwrap();
+        assert!(pending.request.prompt.contains("Conversation History"));
+        assert!(pending.request.prompt.ends_with(&format!("```diff\n{}```", diff)));
+        
+        // The history alone would fit in this budget, but not next to the diff
+        bridge.set_context_budget(300, false);
+        let pending = bridge.begin_request(commit_request()).unwrap();
+        assert!(!pending.request.prompt.contains("Conversation History"));
+        assert!(pending.request.prompt.contains("Staged changes"));
+    }
+    
+    #[test]
+    fn the_conversation_model_only_goes_to_the_conversation_provider() {
+        let mut bridge = AgentBridge::with_memory(AgentMemory::new());
+        bridge.register_provider("claude".to_string(), Box::new(StubProvider { name: "claude", topics: None }));
+        bridge.register_provider("openai".to_string(), Box::new(StubProvider { name: "openai", topics: None }));
+        let id = bridge.create_conversation("Pinned".to_string());
+        bridge
+            .set_conversation_provider(id, Some("openai".to_string()), Some("gpt-4o".to_string()))
+            .unwrap();
+        
+        let pending = bridge.begin_request(request("Hello")).unwrap();
+        assert_eq!(pending.provider.unwrap().provider_name(), "openai");
+        assert_eq!(pending.request.model.as_deref(), Some("gpt-4o"));
+        
+        let explicit = AgentRequest {
+            agent_type: "claude".to_string(),
+            ..request("Hello")
+        };
+        let pending = bridge.begin_request(explicit).unwrap();
+        assert_eq!(pending.provider.unwrap().provider_name(), "claude");
+        assert_eq!(pending.request.model, None);
+    }
+    
+    #[tokio::test]
+    async fn requests_without_a_provider_leave_nothing_registered() {
+        let bridge = AgentBridge::with_memory(AgentMemory::new());
+        let pending = bridge.begin_detached_request(request("Write a commit message"));
+        assert!(!bridge.cancel_request(pending.request_id));
+        
+        let response = pending.execute(&bridge.request_registry()).await.unwrap();
+        assert!(response.error.is_some());
+        assert!(!bridge.cancel_request(pending.request_id));
+    }
+    
+    #[tokio::test]
+    async fn semantic_search_uses_a_provider_with_embeddings_when_the_default_has_none() {
+        let mut bridge = AgentBridge::with_memory(AgentMemory::new());
+        bridge.register_provider("claude".to_string(), Box::new(StubProvider { name: "claude", topics: None }));
+        bridge.register_provider(
+            "openai".to_string(),
+            Box::new(StubProvider { name: "openai", topics: Some(vec!["rust", "bread"]) }),
+        );
+        let rust = conversation(&mut bridge, "Lifetimes", "Why does Rust reject this borrow?");
+        conversation(&mut bridge, "Baking", "How long should bread rise?");
+        
+        // No message contains the query text, so only embeddings can find it
+        let found: Vec<Uuid> = bridge
+            .search_conversations_semantic("rust compiler", 0.9)
+            .await
+            .into_iter()
+            .map(|conversation| conversation.id)
+            .collect();
+        assert_eq!(found, vec![rust]);
+        assert!(bridge.memory.messages_missing_embeddings().is_empty());
+    }
+    
+    #[tokio::test]
+    async fn semantic_search_falls_back_to_substring_search_without_embeddings() {
+        let mut bridge = AgentBridge::with_memory(AgentMemory::new());
+        bridge.register_provider("claude".to_string(), Box::new(StubProvider { name: "claude", topics: None }));
+        let baking = conversation(&mut bridge, "Baking", "How long should bread rise?");
+        
+        let found: Vec<Uuid> = bridge
+            .search_conversations_semantic("bread", 0.9)
+            .await
+            .into_iter()
+            .map(|conversation| conversation.id)
+            .collect();
+        assert_eq!(found, vec![baking]);
+    }
+}
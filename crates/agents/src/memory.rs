@@ -1,8 +1,49 @@
 use anyhow::Result;
+use code_furnace_utils::ResourceError;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use uuid::Uuid;
 
+use crate::TokenUsage;
+
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S UTC";
+/// Characters of each dropped user message quoted in a truncation summary.
+const SUMMARY_SNIPPET_CHARS: usize = 80;
+
+/// Rough token count (about four bytes per token), close enough to keep
+/// requests inside a model's context window without a tokenizer.
+pub fn estimate_tokens(text: &str) -> usize {
+    text.len().div_ceil(4)
+}
+
+/// Roughly the 8k-token input limit of common embedding models.
+const MAX_EMBEDDING_CHARS: usize = 8000;
+
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a * norm_b)
+    }
+}
+
+/// Conversation history trimmed to a token budget.
+#[derive(Debug, Clone, Default)]
+pub struct BudgetedContext {
+    pub text: String,
+    /// Recent messages left out because they did not fit.
+    pub dropped_messages: usize,
+    pub summarized: bool,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConversationMessage {
     pub id: Uuid,
@@ -17,9 +58,11 @@ pub enum MessageRole {
     User,
     Assistant,
     System,
+    /// A tool call made by the assistant and its result.
+    Tool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct MessageContext {
     pub files: Vec<String>,
     pub project_path: Option<String>,
@@ -28,18 +71,6 @@ pub struct MessageContext {
     pub metadata: HashMap<String, serde_json::Value>,
 }
 
-impl Default for MessageContext {
-    fn default() -> Self {
-        Self {
-            files: Vec::new(),
-            project_path: None,
-            terminal_session: None,
-            canvas_state: None,
-            metadata: HashMap::new(),
-        }
-    }
-}
-
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConversationThread {
     pub id: Uuid,
@@ -48,6 +79,15 @@ pub struct ConversationThread {
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
     pub archived: bool,
+    /// Tokens used by every request made in this conversation.
+    #[serde(default)]
+    pub usage: TokenUsage,
+    /// Provider used instead of the default while this conversation is active.
+    #[serde(default)]
+    pub provider: Option<String>,
+    /// Model used instead of the provider's configured one.
+    #[serde(default)]
+    pub model: Option<String>,
 }
 
 impl ConversationThread {
@@ -60,6 +100,9 @@ impl ConversationThread {
             created_at: now,
             updated_at: now,
             archived: false,
+            usage: TokenUsage::default(),
+            provider: None,
+            model: None,
         }
     }
     
@@ -107,12 +150,70 @@ impl ConversationThread {
     pub fn get_recent_messages(&self, limit: usize) -> Vec<&ConversationMessage> {
         self.messages.iter().rev().take(limit).collect()
     }
+    
+    /// Every file attached to a message, in the order first referenced.
+    pub fn referenced_files(&self) -> Vec<String> {
+        let mut seen = std::collections::HashSet::new();
+        self.messages
+            .iter()
+            .flat_map(|message| message.context.files.iter())
+            .filter(|file| seen.insert(file.as_str()))
+            .cloned()
+            .collect()
+    }
+    
+    /// Markdown transcript with a header of referenced files and token usage.
+    /// Message text is copied verbatim so fenced code blocks survive intact.
+    pub fn to_markdown(&self) -> String {
+        let mut output = format!("# {}\n\n", self.name);
+        output.push_str(&format!("- Conversation: `{}`\n", self.id));
+        output.push_str(&format!("- Created: {}\n", self.created_at.format(TIMESTAMP_FORMAT)));
+        output.push_str(&format!("- Updated: {}\n", self.updated_at.format(TIMESTAMP_FORMAT)));
+        output.push_str(&format!(
+            "- Tokens: {} prompt, {} completion, {} total\n",
+            self.usage.prompt_tokens, self.usage.completion_tokens, self.usage.total_tokens
+        ));
+        
+        let files = self.referenced_files();
+        if !files.is_empty() {
+            output.push_str("- Files:\n");
+            for file in files {
+                output.push_str(&format!("  - `{}`\n", file));
+            }
+        }
+        
+        for message in &self.messages {
+            let role = match message.role {
+                MessageRole::User => "User",
+                MessageRole::Assistant => "Assistant",
+                MessageRole::System => "System",
+                MessageRole::Tool => "Tool",
+            };
+            output.push_str(&format!("\n## {} ({})\n\n", role, message.timestamp.format(TIMESTAMP_FORMAT)));
+            output.push_str(message.content.trim_end());
+            output.push('\n');
+        }
+        
+        output
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum ExportFormat {
+    #[serde(alias = "markdown", alias = "md")]
+    Markdown,
+    #[serde(alias = "json")]
+    Json,
 }
 
 #[derive(Debug, Clone)]
 pub struct AgentMemory {
     conversations: HashMap<Uuid, ConversationThread>,
     active_conversation: Option<Uuid>,
+    /// Directory holding one `<id>.json` file per conversation, if persisted.
+    storage_dir: Option<PathBuf>,
+    /// Embeddings by message id, filled in lazily by semantic search.
+    embeddings: HashMap<Uuid, Vec<f32>>,
 }
 
 impl AgentMemory {
@@ -120,7 +221,78 @@ impl AgentMemory {
         Self {
             conversations: HashMap::new(),
             active_conversation: None,
+            storage_dir: None,
+            embeddings: HashMap::new(),
+        }
+    }
+    
+    /// Loads the conversations saved in `storage_dir` and keeps it up to date
+    /// from then on. Files that fail to parse are skipped with a warning. The
+    /// most recently updated conversation becomes active.
+    pub fn with_storage(storage_dir: PathBuf) -> Result<Self> {
+        std::fs::create_dir_all(&storage_dir)?;
+        
+        let mut conversations = HashMap::new();
+        for entry in std::fs::read_dir(&storage_dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+            
+            let loaded = std::fs::read_to_string(&path)
+                .map_err(anyhow::Error::from)
+                .and_then(|content| Ok(serde_json::from_str::<ConversationThread>(&content)?));
+            match loaded {
+                Ok(conversation) => {
+                    conversations.insert(conversation.id, conversation);
+                }
+                Err(e) => tracing::warn!("Skipping unreadable conversation file {}: {}", path.display(), e),
+            }
+        }
+        
+        let active_conversation = conversations
+            .values()
+            .max_by_key(|conversation| conversation.updated_at)
+            .map(|conversation| conversation.id);
+        
+        Ok(Self {
+            conversations,
+            active_conversation,
+            storage_dir: Some(storage_dir),
+            embeddings: HashMap::new(),
+        })
+    }
+    
+    fn conversation_path(storage_dir: &Path, id: Uuid) -> PathBuf {
+        storage_dir.join(format!("{}.json", id))
+    }
+    
+    /// Writes one conversation through a temporary file so a crash mid-write
+    /// never leaves a truncated thread behind.
+    fn persist(&self, id: Uuid) -> Result<()> {
+        let (Some(storage_dir), Some(conversation)) = (&self.storage_dir, self.conversations.get(&id)) else {
+            return Ok(());
+        };
+        
+        let path = Self::conversation_path(storage_dir, id);
+        let temp_path = path.with_extension("json.tmp");
+        std::fs::write(&temp_path, serde_json::to_string_pretty(conversation)?)?;
+        std::fs::rename(&temp_path, &path)?;
+        Ok(())
+    }
+    
+    /// Persistence failures are logged rather than failing the chat itself.
+    fn persist_or_warn(&self, id: Uuid) {
+        if let Err(e) = self.persist(id) {
+            tracing::warn!("Failed to save conversation {}: {}", id, e);
+        }
+    }
+    
+    pub fn save_all(&self) -> Result<()> {
+        for id in self.conversations.keys() {
+            self.persist(*id)?;
         }
+        Ok(())
     }
     
     pub fn create_conversation(&mut self, name: String) -> Uuid {
@@ -128,6 +300,7 @@ impl AgentMemory {
         let id = conversation.id;
         self.conversations.insert(id, conversation);
         self.active_conversation = Some(id);
+        self.persist_or_warn(id);
         id
     }
     
@@ -152,22 +325,68 @@ impl AgentMemory {
             self.active_conversation = Some(id);
             Ok(())
         } else {
-            Err(anyhow::anyhow!("Conversation not found: {}", id))
+            Err(ResourceError::NotFound(format!("Conversation not found: {}", id)).into())
         }
     }
     
     pub fn list_conversations(&self) -> Vec<&ConversationThread> {
         let mut conversations: Vec<_> = self.conversations.values().collect();
-        conversations.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+        conversations.sort_by_key(|conversation| std::cmp::Reverse(conversation.updated_at));
         conversations
     }
     
+    pub fn set_conversation_provider(&mut self, id: Uuid, provider: Option<String>, model: Option<String>) -> Result<()> {
+        let conversation = self
+            .conversations
+            .get_mut(&id)
+            .ok_or_else(|| ResourceError::NotFound(format!("Conversation not found: {}", id)))?;
+        conversation.provider = provider;
+        conversation.model = model;
+        self.persist_or_warn(id);
+        Ok(())
+    }
+    
     pub fn add_message_to_active(&mut self, role: MessageRole, content: String, context: MessageContext) -> Result<()> {
-        if let Some(conversation) = self.get_active_conversation_mut() {
-            conversation.add_message(role, content, context);
-            Ok(())
-        } else {
-            Err(anyhow::anyhow!("No active conversation"))
+        let id = self.active_conversation.ok_or_else(|| anyhow::anyhow!("No active conversation"))?;
+        self.add_message(id, role, content, context)
+    }
+    
+    pub fn add_message(&mut self, id: Uuid, role: MessageRole, content: String, context: MessageContext) -> Result<()> {
+        let conversation = self
+            .conversations
+            .get_mut(&id)
+            .ok_or_else(|| ResourceError::NotFound(format!("Conversation not found: {}", id)))?;
+        conversation.add_message(role, content, context);
+        self.persist_or_warn(id);
+        Ok(())
+    }
+    
+    pub fn record_usage_for_active(&mut self, usage: TokenUsage) {
+        if let Some(id) = self.active_conversation {
+            self.record_usage(id, usage);
+        }
+    }
+    
+    pub fn record_usage(&mut self, id: Uuid, usage: TokenUsage) {
+        if let Some(conversation) = self.conversations.get_mut(&id) {
+            conversation.usage += usage;
+            self.persist_or_warn(id);
+        }
+    }
+    
+    pub fn export_conversation(&self, id: Uuid, format: ExportFormat) -> Result<String> {
+        let conversation = self
+            .conversations
+            .get(&id)
+            .ok_or_else(|| ResourceError::NotFound(format!("Conversation not found: {}", id)))?;
+        
+        match format {
+            ExportFormat::Markdown => Ok(conversation.to_markdown()),
+            ExportFormat::Json => Ok(serde_json::to_string_pretty(&serde_json::json!({
+                "referenced_files": conversation.referenced_files(),
+                "usage": conversation.usage,
+                "conversation": conversation
+            }))?),
         }
     }
     
@@ -187,6 +406,7 @@ impl AgentMemory {
                     MessageRole::User => "User",
                     MessageRole::Assistant => "Assistant", 
                     MessageRole::System => "System",
+                    MessageRole::Tool => "Tool",
                 };
                 
                 context.push_str(&format!("\n{}: {}\n", role_str, message.content));
@@ -203,6 +423,124 @@ impl AgentMemory {
         }
     }
     
+    /// Like `build_conversation_context` for the active conversation, but keeps
+    /// only the newest messages that fit in `token_budget`. With
+    /// `summarize_dropped`, the omitted messages are replaced by a one-line
+    /// preamble quoting the start of each dropped user message.
+    pub fn build_budgeted_context(&self, max_messages: usize, token_budget: usize, summarize_dropped: bool) -> BudgetedContext {
+        let Some(conv) = self.get_active_conversation() else {
+            return BudgetedContext::default();
+        };
+        
+        let header = format!("=== Conversation: {} ===\n", conv.name);
+        let mut used = estimate_tokens(&header);
+        if used > token_budget {
+            return BudgetedContext::default();
+        }
+        
+        let recent_messages = conv.get_recent_messages(max_messages);
+        let mut kept = Vec::new();
+        for message in &recent_messages {
+            let role_str = match message.role {
+                MessageRole::User => "User",
+                MessageRole::Assistant => "Assistant",
+                MessageRole::System => "System",
+                MessageRole::Tool => "Tool",
+            };
+            
+            let mut entry = format!("\n{}: {}\n", role_str, message.content);
+            if !message.context.files.is_empty() {
+                entry.push_str(&format!("Files: {}\n", message.context.files.join(", ")));
+            }
+            
+            let tokens = estimate_tokens(&entry);
+            if used + tokens > token_budget {
+                break;
+            }
+            used += tokens;
+            kept.push((entry, tokens));
+        }
+        
+        let mut context = BudgetedContext {
+            text: header,
+            dropped_messages: recent_messages.len() - kept.len(),
+            summarized: false,
+        };
+        
+        // Give up the oldest kept messages if that is what it takes to fit the summary
+        while summarize_dropped && context.dropped_messages > 0 {
+            let dropped = &recent_messages[kept.len()..];
+            let topics: Vec<String> = dropped
+                .iter()
+                .rev()
+                .filter(|message| matches!(message.role, MessageRole::User))
+                .map(|message| {
+                    let first_line = message.content.lines().next().unwrap_or_default();
+                    first_line.chars().take(SUMMARY_SNIPPET_CHARS).collect()
+                })
+                .collect();
+            let summary = format!(
+                "\n[{} earlier messages omitted to fit the context window. Earlier requests: {}]\n",
+                dropped.len(),
+                topics.join("; ")
+            );
+            
+            if used + estimate_tokens(&summary) <= token_budget {
+                context.text.push_str(&summary);
+                context.summarized = true;
+                break;
+            }
+            
+            let Some((_, tokens)) = kept.pop() else {
+                break;
+            };
+            used -= tokens;
+            context.dropped_messages += 1;
+        }
+        
+        for (entry, _) in kept.iter().rev() {
+            context.text.push_str(entry);
+        }
+        
+        context
+    }
+    
+    /// Messages without a cached embedding, with their text clipped to what
+    /// embedding models accept.
+    pub fn messages_missing_embeddings(&self) -> Vec<(Uuid, String)> {
+        self.conversations
+            .values()
+            .flat_map(|conv| conv.messages.iter())
+            .filter(|message| !message.content.trim().is_empty() && !self.embeddings.contains_key(&message.id))
+            .map(|message| (message.id, message.content.chars().take(MAX_EMBEDDING_CHARS).collect()))
+            .collect()
+    }
+    
+    pub fn cache_embeddings(&mut self, embeddings: impl IntoIterator<Item = (Uuid, Vec<f32>)>) {
+        self.embeddings.extend(embeddings);
+    }
+    
+    /// Conversations whose best-matching message scores at least `threshold`,
+    /// most similar first.
+    pub fn rank_by_embedding(&self, query: &[f32], threshold: f32) -> Vec<&ConversationThread> {
+        let mut scored: Vec<(&ConversationThread, f32)> = self
+            .conversations
+            .values()
+            .filter_map(|conv| {
+                conv.messages
+                    .iter()
+                    .filter_map(|message| self.embeddings.get(&message.id))
+                    .map(|embedding| cosine_similarity(query, embedding))
+                    .max_by(|a, b| a.total_cmp(b))
+                    .map(|score| (conv, score))
+            })
+            .filter(|(_, score)| *score >= threshold)
+            .collect();
+        
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+        scored.into_iter().map(|(conv, _)| conv).collect()
+    }
+    
     pub fn search_conversations(&self, query: &str) -> Vec<&ConversationThread> {
         self.conversations
             .values()
@@ -220,4 +558,108 @@ impl Default for AgentMemory {
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("code-furnace-memory-{}", Uuid::new_v4()))
+    }
+    
+    /// An in-memory conversation of user messages about 100 tokens each,
+    /// oldest first.
+    fn long_conversation(topics: &[&str]) -> AgentMemory {
+        let mut memory = AgentMemory::new();
+        let id = memory.create_conversation("T".to_string());
+        for topic in topics {
+            let content = format!("{} {}", topic, "x".repeat(390));
+            memory.add_message(id, MessageRole::User, content, MessageContext::default()).unwrap();
+        }
+        memory
+    }
+    
+    #[test]
+    fn budgeted_context_keeps_the_newest_messages_that_fit() {
+        let memory = long_conversation(&["first", "second", "third", "fourth"]);
+        
+        let context = memory.build_budgeted_context(10, 260, false);
+        assert_eq!(context.dropped_messages, 2);
+        assert!(!context.summarized);
+        assert!(context.text.starts_with("=== Conversation: T ===\n"));
+        assert!(!context.text.contains("first") && !context.text.contains("second"));
+        assert!(context.text.find("third").unwrap() < context.text.find("fourth").unwrap());
+        assert!(estimate_tokens(&context.text) <= 260);
+        
+        let everything = memory.build_budgeted_context(10, 10_000, true);
+        assert_eq!(everything.dropped_messages, 0);
+        assert!(!everything.summarized);
+        
+        assert_eq!(memory.build_budgeted_context(10, 2, true).text, "");
+    }
+    
+    #[test]
+    fn dropped_messages_are_summarized_within_the_budget() {
+        let memory = long_conversation(&["first", "second", "third", "fourth"]);
+        
+        // The summary does not fit next to the two newest messages, so the
+        // older of them is summarized too
+        let context = memory.build_budgeted_context(10, 260, true);
+        assert!(context.summarized);
+        assert_eq!(context.dropped_messages, 3);
+        assert!(estimate_tokens(&context.text) <= 260);
+        
+        let summary_start = context.text.find("[3 earlier messages omitted").unwrap();
+        let summary_end = summary_start + context.text[summary_start..].find("]\n").unwrap();
+        let summary = &context.text[summary_start..summary_end];
+        let topics: Vec<usize> = ["first", "second", "third"].iter().map(|topic| summary.find(topic).unwrap()).collect();
+        assert!(topics.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(context.text[summary_end..].contains(&format!("User: fourth {}", "x".repeat(390))));
+    }
+    
+    #[test]
+    fn conversations_survive_a_reload() {
+        let dir = temp_dir();
+        let mut memory = AgentMemory::with_storage(dir.clone()).unwrap();
+        let older = memory.create_conversation("Older".to_string());
+        let newer = memory.create_conversation("Newer".to_string());
+        memory
+            .add_message(newer, MessageRole::User, "Hello".to_string(), MessageContext::default())
+            .unwrap();
+        
+        let mut files: Vec<String> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        files.sort();
+        let mut expected = vec![format!("{}.json", older), format!("{}.json", newer)];
+        expected.sort();
+        // Every write went through a temporary file that was renamed into place
+        assert_eq!(files, expected);
+        
+        let reloaded = AgentMemory::with_storage(dir.clone()).unwrap();
+        assert_eq!(reloaded.list_conversations().len(), 2);
+        let active = reloaded.get_active_conversation().unwrap();
+        assert_eq!(active.id, newer);
+        assert_eq!(active.messages.len(), 1);
+        assert_eq!(active.messages[0].content, "Hello");
+        
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+    
+    #[test]
+    fn unreadable_files_are_skipped_on_load() {
+        let dir = temp_dir();
+        let mut memory = AgentMemory::with_storage(dir.clone()).unwrap();
+        let id = memory.create_conversation("Kept".to_string());
+        std::fs::write(dir.join(format!("{}.json", Uuid::new_v4())), "{ not json").unwrap();
+        std::fs::write(dir.join("notes.txt"), "ignored").unwrap();
+        
+        let reloaded = AgentMemory::with_storage(dir.clone()).unwrap();
+        let conversations = reloaded.list_conversations();
+        assert_eq!(conversations.len(), 1);
+        assert_eq!(conversations[0].id, id);
+        
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,235 @@
+use anyhow::Result;
+use code_furnace_events::{Event, EventBus};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
+use tokio::task::AbortHandle;
+use uuid::Uuid;
+
+use crate::{AgentProvider, AgentRequest, AgentResponse, MessageContext, TokenUsage, ToolSet, ToolTurn, MAX_TOOL_ROUNDS};
+
+/// In-flight provider calls, so a request can be cancelled without holding
+/// the `AgentBridge` lock.
+#[derive(Clone, Default)]
+pub struct AgentRequestRegistry {
+    tasks: Arc<Mutex<HashMap<Uuid, RequestTask>>>,
+}
+
+enum RequestTask {
+    /// Handed out by `AgentBridge::begin_request` but not yet running.
+    Pending,
+    /// Cancelled before it started; `run` will not start it.
+    Cancelled,
+    Running(AbortHandle),
+}
+
+impl AgentRequestRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Records a request that will run later, so a cancel that arrives before
+    /// `run` starts it is kept rather than lost.
+    pub(crate) fn register(&self, request_id: Uuid) {
+        self.tasks.lock().unwrap().insert(request_id, RequestTask::Pending);
+    }
+    
+    /// Runs `future` on its own task so `cancel` can abort it, which drops the
+    /// underlying HTTP request. Returns `None` if it was cancelled, including
+    /// before it started.
+    pub async fn run<T: Send + 'static>(
+        &self,
+        request_id: Uuid,
+        future: impl Future<Output = T> + Send + 'static,
+    ) -> Option<T> {
+        let task = {
+            let mut tasks = self.tasks.lock().unwrap();
+            if let Some(RequestTask::Cancelled) = tasks.get(&request_id) {
+                tasks.remove(&request_id);
+                return None;
+            }
+            let task = tokio::spawn(future);
+            tasks.insert(request_id, RequestTask::Running(task.abort_handle()));
+            task
+        };
+        
+        let result = task.await;
+        self.tasks.lock().unwrap().remove(&request_id);
+        
+        match result {
+            Ok(value) => Some(value),
+            Err(e) if e.is_cancelled() => None,
+            Err(e) => std::panic::resume_unwind(e.into_panic()),
+        }
+    }
+    
+    /// Returns false if no request with this id is pending or running.
+    pub fn cancel(&self, request_id: Uuid) -> bool {
+        let mut tasks = self.tasks.lock().unwrap();
+        match tasks.get_mut(&request_id) {
+            Some(RequestTask::Running(handle)) => {
+                handle.abort();
+                tasks.remove(&request_id);
+                true
+            }
+            Some(task) => {
+                *task = RequestTask::Cancelled;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// A request whose prompt has been recorded and whose provider has been
+/// chosen, ready to run without access to the `AgentBridge`. Pass it back to
+/// `AgentBridge::finish_request` afterwards to store the response.
+pub struct PendingRequest {
+    pub request_id: Uuid,
+    pub(crate) conversation_id: Uuid,
+    pub(crate) context: MessageContext,
+    pub(crate) request: AgentRequest,
+    pub(crate) provider: Option<Arc<dyn AgentProvider>>,
+    pub(crate) tools: ToolSet,
+}
+
+impl PendingRequest {
+    /// Runs the request, calling tools and sending their results back to the
+    /// model until it answers without calling any.
+    pub async fn execute(&self, registry: &AgentRequestRegistry) -> Result<AgentResponse> {
+        let Some(provider) = self.provider.clone() else {
+            return Ok(AgentResponse::failed(self.request_id, "No suitable agent provider found for request"));
+        };
+        
+        let request = self.request.clone();
+        let tools = self.tools.clone();
+        let call = async move { run_with_tools(provider.as_ref(), request, &tools).await };
+        
+        match registry.run(self.request_id, call).await {
+            Some(result) => result,
+            None => Ok(AgentResponse::cancelled(self.request_id)),
+        }
+    }
+    
+    /// Streams the response as `agent.response.chunk` events followed by one
+    /// `agent.response.done`, all carrying the request id. If the stream fails
+    /// part way the response keeps the text that arrived; if it is cancelled
+    /// the partial text is discarded. Tools are not offered when streaming.
+    pub async fn execute_streaming(&self, registry: &AgentRequestRegistry, event_bus: &EventBus) -> Result<AgentResponse> {
+        let response = if let Some(provider) = self.provider.clone() {
+            let (chunk_tx, mut chunk_rx) = mpsc::unbounded_channel::<String>();
+            let mut request = self.request.clone();
+            request.tools.clear();
+            let call = async move { provider.process_request_streaming(&request, chunk_tx).await };
+            
+            let forward_chunks = async {
+                let mut streamed = String::new();
+                while let Some(chunk) = chunk_rx.recv().await {
+                    let event = Event::new(
+                        "agent.response.chunk",
+                        "agent-bridge",
+                        serde_json::json!({
+                            "request_id": self.request_id,
+                            "content": chunk
+                        }),
+                    );
+                    let _ = event_bus.publish(event);
+                    streamed.push_str(&chunk);
+                }
+                streamed
+            };
+            
+            let (result, streamed) = tokio::join!(registry.run(self.request_id, call), forward_chunks);
+            
+            match result {
+                Some(Ok(response)) => response,
+                Some(Err(e)) => AgentResponse {
+                    content: streamed,
+                    ..AgentResponse::failed(self.request_id, e.to_string())
+                },
+                None => AgentResponse::cancelled(self.request_id),
+            }
+        } else {
+            AgentResponse::failed(self.request_id, "No suitable agent provider found for request")
+        };
+        
+        let event = Event::new(
+            "agent.response.done",
+            "agent-bridge",
+            serde_json::json!({
+                "request_id": self.request_id,
+                "response": response
+            }),
+        );
+        let _ = event_bus.publish(event);
+        
+        Ok(response)
+    }
+}
+
+async fn run_with_tools(provider: &dyn AgentProvider, mut request: AgentRequest, tools: &ToolSet) -> Result<AgentResponse> {
+    let mut usage: Option<TokenUsage> = None;
+    let mut invocations = Vec::new();
+    
+    loop {
+        let mut response = provider.process_request(&request).await?;
+        if let Some(turn_usage) = TokenUsage::from_metadata(&response.metadata) {
+            *usage.get_or_insert_with(TokenUsage::default) += turn_usage;
+        }
+        
+        if response.error.is_none() && !response.tool_calls.is_empty() {
+            if request.tool_turns.len() < MAX_TOOL_ROUNDS {
+                let mut turn = ToolTurn {
+                    content: std::mem::take(&mut response.content),
+                    invocations: Vec::new(),
+                };
+                for call in response.tool_calls {
+                    tracing::debug!("Agent request {} calling tool {}", request.id, call.name);
+                    turn.invocations.push(tools.invoke(call).await);
+                }
+                invocations.extend(turn.invocations.iter().cloned());
+                request.tool_turns.push(turn);
+                continue;
+            }
+            
+            response = AgentResponse::failed(
+                request.id,
+                format!("Stopped after {} rounds of tool calls", MAX_TOOL_ROUNDS),
+            );
+        }
+        
+        if let Some(usage) = usage {
+            usage.insert_into(&mut response.metadata);
+        }
+        response.tool_invocations = invocations;
+        return Ok(response);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[tokio::test]
+    async fn a_cancel_before_the_request_runs_is_kept() {
+        let registry = AgentRequestRegistry::new();
+        let request_id = Uuid::new_v4();
+        registry.register(request_id);
+        
+        assert!(registry.cancel(request_id));
+        assert_eq!(registry.run(request_id, async { 42 }).await, None);
+        
+        // The cancel applies once; the id is forgotten afterwards
+        assert!(!registry.cancel(request_id));
+    }
+    
+    #[tokio::test]
+    async fn an_unknown_request_cannot_be_cancelled() {
+        let registry = AgentRequestRegistry::new();
+        let request_id = Uuid::new_v4();
+        
+        assert!(!registry.cancel(request_id));
+        assert_eq!(registry.run(request_id, async { 42 }).await, Some(42));
+    }
+}
@@ -0,0 +1,240 @@
+use anyhow::Result;
+use code_furnace_events::{Event, EventBus};
+use reqwest::{RequestBuilder, Response, StatusCode};
+use std::time::Duration;
+use uuid::Uuid;
+
+/// How failed provider requests are retried. Only rate limits (429), server
+/// errors (5xx) and connection failures are retried; other 4xx responses such
+/// as auth errors are returned immediately.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt; 0 disables retrying.
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn with_max_retries(max_retries: u32) -> Self {
+        Self {
+            max_retries,
+            ..Self::default()
+        }
+    }
+    
+    /// Exponential backoff with jitter, so concurrent clients don't retry in lockstep.
+    fn backoff(&self, retry: u32) -> Duration {
+        let delay = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(self.max_delay);
+        let half = delay.as_millis() as u64 / 2;
+        let jitter = (Uuid::new_v4().as_u128() as u64) % (half + 1);
+        Duration::from_millis(half + jitter)
+    }
+}
+
+fn is_retryable(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// `Retry-After` given in seconds; HTTP-date values fall back to backoff.
+fn retry_after(response: &Response) -> Option<Duration> {
+    let value = response.headers().get(reqwest::header::RETRY_AFTER)?.to_str().ok()?;
+    value.trim().parse::<u64>().ok().map(Duration::from_secs)
+}
+
+/// Sends the request built by `build` until it succeeds, fails with a
+/// non-retryable status, or the policy runs out of retries. Each retry is
+/// announced as an `agent.request.retrying` event.
+pub(crate) async fn send_with_retry(
+    policy: &RetryPolicy,
+    event_bus: Option<&EventBus>,
+    provider: &str,
+    request_id: Uuid,
+    build: impl Fn() -> RequestBuilder,
+) -> Result<Response> {
+    let mut retry = 0;
+    
+    loop {
+        let (delay, reason) = match build().send().await {
+            Ok(response) if is_retryable(response.status()) && retry < policy.max_retries => {
+                let delay = retry_after(&response)
+                    .map(|delay| delay.min(policy.max_delay))
+                    .unwrap_or_else(|| policy.backoff(retry));
+                (delay, format!("HTTP {}", response.status()))
+            }
+            Err(e) if (e.is_connect() || e.is_timeout()) && retry < policy.max_retries => {
+                (policy.backoff(retry), e.to_string())
+            }
+            result => return Ok(result?),
+        };
+        
+        retry += 1;
+        tracing::warn!(
+            "{} request {} failed ({}); retry {}/{} in {:?}",
+            provider, request_id, reason, retry, policy.max_retries, delay
+        );
+        
+        if let Some(event_bus) = event_bus {
+            let event = Event::new(
+                "agent.request.retrying",
+                "agent-bridge",
+                serde_json::json!({
+                    "request_id": request_id,
+                    "provider": provider,
+                    "reason": reason,
+                    "attempt": retry,
+                    "max_retries": policy.max_retries,
+                    "delay_ms": delay.as_millis() as u64
+                }),
+            );
+            let _ = event_bus.publish(event);
+        }
+        
+        tokio::time::sleep(delay).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AgentProvider, AgentRequest, ClaudeProvider};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    
+    /// Serves each canned response to one connection, in order.
+    async fn mock_server(responses: Vec<String>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        
+        tokio::spawn(async move {
+            for response in responses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut request = Vec::new();
+                let mut buffer = [0u8; 4096];
+                // Read the headers and the body they announce
+                loop {
+                    let read = socket.read(&mut buffer).await.unwrap();
+                    request.extend_from_slice(&buffer[..read]);
+                    let text = String::from_utf8_lossy(&request);
+                    if let Some(end) = text.find("\r\n\r\n") {
+                        let length = text[..end]
+                            .lines()
+                            .find_map(|line| line.to_lowercase().strip_prefix("content-length:").map(|v| v.trim().to_string()))
+                            .and_then(|v| v.parse::<usize>().ok())
+                            .unwrap_or(0);
+                        if request.len() >= end + 4 + length {
+                            break;
+                        }
+                    }
+                    if read == 0 {
+                        break;
+                    }
+                }
+                socket.write_all(response.as_bytes()).await.unwrap();
+                socket.shutdown().await.unwrap();
+            }
+        });
+        
+        format!("http://{}", address)
+    }
+    
+    fn http_response(status: &str, extra_headers: &str, body: &str) -> String {
+        format!(
+            "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n{}\r\n{}",
+            status,
+            body.len(),
+            extra_headers,
+            body
+        )
+    }
+    
+    fn request() -> AgentRequest {
+        AgentRequest {
+            id: Uuid::new_v4(),
+            agent_type: String::new(),
+            prompt: "hello".to_string(),
+            context: Default::default(),
+            files: Vec::new(),
+            tools: Vec::new(),
+            tool_turns: Vec::new(),
+            model: None,
+        }
+    }
+    
+    fn fast_policy(max_retries: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_retries,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(10),
+        }
+    }
+    
+    #[tokio::test]
+    async fn retries_rate_limits_until_success() {
+        let success = r#"{"content":[{"type":"text","text":"done"}],"usage":{"input_tokens":3,"output_tokens":2}}"#;
+        let base_url = mock_server(vec![
+            http_response("429 Too Many Requests", "Retry-After: 0\r\n", "{}"),
+            http_response("429 Too Many Requests", "", "{}"),
+            http_response("200 OK", "", success),
+        ]).await;
+        
+        let event_bus = EventBus::new();
+        let mut events = event_bus.subscribe(Some("agent.request.retrying"));
+        let provider = ClaudeProvider::new("sk-ant-test".to_string())
+            .with_base_url(base_url)
+            .with_retry_policy(fast_policy(3))
+            .with_event_bus(event_bus.clone());
+        
+        let response = provider.process_request(&request()).await.unwrap();
+        assert_eq!(response.error, None);
+        assert_eq!(response.content, "done");
+        
+        let first = events.try_recv().unwrap();
+        assert_eq!(first.data["attempt"], 1);
+        assert_eq!(first.data["delay_ms"], 0);
+        assert_eq!(events.try_recv().unwrap().data["attempt"], 2);
+        assert!(events.try_recv().is_err());
+    }
+    
+    #[tokio::test]
+    async fn does_not_retry_auth_errors() {
+        let base_url = mock_server(vec![
+            http_response("401 Unauthorized", "", r#"{"error":"invalid x-api-key"}"#),
+        ]).await;
+        
+        let provider = ClaudeProvider::new("sk-ant-test".to_string())
+            .with_base_url(base_url)
+            .with_retry_policy(fast_policy(3));
+        
+        let response = provider.process_request(&request()).await.unwrap();
+        assert!(response.error.unwrap().contains("invalid x-api-key"));
+    }
+    
+    #[tokio::test]
+    async fn gives_up_after_max_retries() {
+        let base_url = mock_server(vec![
+            http_response("503 Service Unavailable", "", "{}"),
+            http_response("503 Service Unavailable", "", r#"{"error":"overloaded"}"#),
+        ]).await;
+        
+        let provider = ClaudeProvider::new("sk-ant-test".to_string())
+            .with_base_url(base_url)
+            .with_retry_policy(fast_policy(1));
+        
+        let response = provider.process_request(&request()).await.unwrap();
+        assert!(response.error.unwrap().contains("overloaded"));
+    }
+}
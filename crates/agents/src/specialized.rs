@@ -1,8 +1,10 @@
 use crate::{AgentProvider, AgentRequest, AgentResponse};
 use anyhow::Result;
+use code_furnace_utils::ResourceError;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use tokio::sync::mpsc;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum AgentType {
@@ -14,6 +16,8 @@ pub enum AgentType {
     SystemArchitect,
     DocumentationWriter,
     Debugger,
+    SecurityAuditor,
+    PerformanceOptimizer,
 }
 
 impl AgentType {
@@ -85,6 +89,22 @@ impl AgentType {
                 - Performance bottleneck identification\n\
                 - Monitoring and observability recommendations"
             }
+            AgentType::SecurityAuditor => {
+                "You are an application security specialist. Your role is to find and help fix security weaknesses. Focus on:\n\
+                - Injection, authentication and authorization flaws\n\
+                - Unsafe handling of secrets, credentials and user input\n\
+                - Vulnerable or outdated dependencies and known CVEs\n\
+                - Insecure defaults, configuration and file permissions\n\
+                - Severity assessment and concrete remediation steps"
+            }
+            AgentType::PerformanceOptimizer => {
+                "You are a performance optimization specialist. Your role is to make code faster and leaner. Focus on:\n\
+                - Algorithmic complexity and hot path analysis\n\
+                - Memory allocation, copying and data structure choices\n\
+                - I/O, concurrency and caching opportunities\n\
+                - Profiling and benchmarking strategies to measure changes\n\
+                - Trade-offs between performance, readability and correctness"
+            }
         }
     }
     
@@ -114,6 +134,12 @@ impl AgentType {
             AgentType::Debugger => vec![
                 "error_analysis", "troubleshooting", "performance_debugging", "log_analysis"
             ],
+            AgentType::SecurityAuditor => vec![
+                "vulnerability_detection", "dependency_audit", "secret_scanning", "threat_modeling"
+            ],
+            AgentType::PerformanceOptimizer => vec![
+                "profiling", "complexity_analysis", "memory_optimization", "benchmarking"
+            ],
         }
     }
 }
@@ -158,6 +184,18 @@ impl SpecializedAgent {
         
         enhanced_prompt
     }
+    
+    fn add_agent_metadata(&self, response: &mut AgentResponse) -> Result<()> {
+        response.metadata.insert(
+            "agent_type".to_string(),
+            serde_json::to_value(&self.agent_type)?,
+        );
+        response.metadata.insert(
+            "capabilities".to_string(),
+            serde_json::to_value(self.agent_type.get_capabilities())?,
+        );
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -167,16 +205,8 @@ impl AgentProvider for SpecializedAgent {
         let mut enhanced_request = request.clone();
         enhanced_request.prompt = self.enhance_prompt(request);
         
-        // Add agent type to metadata
         let mut response = self.base_provider.process_request(&enhanced_request).await?;
-        response.metadata.insert(
-            "agent_type".to_string(),
-            serde_json::to_value(&self.agent_type)?,
-        );
-        response.metadata.insert(
-            "capabilities".to_string(),
-            serde_json::to_value(self.agent_type.get_capabilities())?,
-        );
+        self.add_agent_metadata(&mut response)?;
         
         Ok(response)
     }
@@ -191,12 +221,32 @@ impl AgentProvider for SpecializedAgent {
             AgentType::SystemArchitect => "system-architect",
             AgentType::DocumentationWriter => "doc-writer",
             AgentType::Debugger => "debugger",
+            AgentType::SecurityAuditor => "security-auditor",
+            AgentType::PerformanceOptimizer => "performance-optimizer",
         }
     }
     
     fn supports_streaming(&self) -> bool {
         self.base_provider.supports_streaming()
     }
+    
+    async fn embed(&self, texts: &[String]) -> Result<Option<Vec<Vec<f32>>>> {
+        self.base_provider.embed(texts).await
+    }
+    
+    async fn process_request_streaming(
+        &self,
+        request: &AgentRequest,
+        chunk_tx: mpsc::UnboundedSender<String>,
+    ) -> Result<AgentResponse> {
+        let mut enhanced_request = request.clone();
+        enhanced_request.prompt = self.enhance_prompt(request);
+        
+        let mut response = self.base_provider.process_request_streaming(&enhanced_request, chunk_tx).await?;
+        self.add_agent_metadata(&mut response)?;
+        
+        Ok(response)
+    }
 }
 
 pub struct AgentRouter {
@@ -227,7 +277,7 @@ impl AgentRouter {
         self.agents
             .get(&agent_name)
             .map(|agent| agent.as_ref())
-            .ok_or_else(|| anyhow::anyhow!("Agent not found: {}", agent_name))
+            .ok_or_else(|| ResourceError::NotFound(format!("Agent not found: {}", agent_name)).into())
     }
     
     pub fn determine_agent_for_request(&self, request: &AgentRequest) -> String {
@@ -235,10 +285,20 @@ impl AgentRouter {
         let files = &request.files;
         
         // Check for specific keywords and context to route to appropriate agent
-        if prompt_lower.contains("explain") || prompt_lower.contains("how does") || prompt_lower.contains("what is") {
-            if !files.is_empty() {
-                return "code-explainer".to_string();
-            }
+        if (prompt_lower.contains("explain") || prompt_lower.contains("how does") || prompt_lower.contains("what is"))
+            && !files.is_empty()
+        {
+            return "code-explainer".to_string();
+        }
+        
+        if prompt_lower.contains("security") || prompt_lower.contains("vulnerability") || prompt_lower.contains("cve") {
+            return "security-auditor".to_string();
+        }
+        
+        if prompt_lower.contains("performance") || prompt_lower.contains("slow") || prompt_lower.contains("optimize")
+            || prompt_lower.contains("benchmark")
+        {
+            return "performance-optimizer".to_string();
         }
         
         if prompt_lower.contains("review") || prompt_lower.contains("check") || prompt_lower.contains("improve") {
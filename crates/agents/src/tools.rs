@@ -0,0 +1,323 @@
+use anyhow::Result;
+use code_furnace_utils::ResourceError;
+use code_furnace_terminal::TerminalManager;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Tool output longer than this is cut before it is sent back to the model.
+pub const MAX_TOOL_OUTPUT_CHARS: usize = 20_000;
+
+/// Model turns allowed to call tools before a request is stopped.
+pub const MAX_TOOL_ROUNDS: usize = 8;
+
+/// What the model sees of a tool: its name, what it does and a JSON schema
+/// for its input.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolDefinition {
+    pub name: String,
+    pub description: String,
+    pub input_schema: serde_json::Value,
+}
+
+/// A tool call requested by the model. `id` is the provider's call id and is
+/// echoed back with the result.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolCall {
+    pub id: String,
+    pub name: String,
+    pub input: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolInvocation {
+    pub call: ToolCall,
+    pub output: String,
+    pub is_error: bool,
+}
+
+/// One model turn that called tools: the text it returned alongside the
+/// calls, and the result of each call.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ToolTurn {
+    pub content: String,
+    pub invocations: Vec<ToolInvocation>,
+}
+
+#[async_trait::async_trait]
+pub trait ToolHandler: Send + Sync {
+    /// Runs the tool and returns the text passed back to the model. Errors are
+    /// reported to the model as failed calls rather than failing the request.
+    async fn call(&self, input: serde_json::Value) -> Result<String>;
+}
+
+#[derive(Clone)]
+pub struct Tool {
+    pub definition: ToolDefinition,
+    /// Refuse calls until the user has allowed this tool.
+    pub requires_permission: bool,
+    handler: Arc<dyn ToolHandler>,
+}
+
+impl Tool {
+    pub fn new(
+        name: impl Into<String>,
+        description: impl Into<String>,
+        input_schema: serde_json::Value,
+        handler: impl ToolHandler + 'static,
+    ) -> Self {
+        Self {
+            definition: ToolDefinition {
+                name: name.into(),
+                description: description.into(),
+                input_schema,
+            },
+            requires_permission: true,
+            handler: Arc::new(handler),
+        }
+    }
+    
+    /// Lets the model call this tool without the user allowing it first.
+    pub fn without_permission(mut self) -> Self {
+        self.requires_permission = false;
+        self
+    }
+    
+    pub fn name(&self) -> &str {
+        &self.definition.name
+    }
+}
+
+/// The tools available to a request and which of them the user has allowed.
+#[derive(Clone, Default)]
+pub struct ToolSet {
+    tools: HashMap<String, Tool>,
+    allowed: HashSet<String>,
+}
+
+impl ToolSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    pub fn register(&mut self, tool: Tool) {
+        self.tools.insert(tool.name().to_string(), tool);
+    }
+    
+    pub fn set_allowed(&mut self, name: &str, allowed: bool) -> Result<()> {
+        if !self.tools.contains_key(name) {
+            return Err(anyhow::anyhow!("Unknown tool: {}", name));
+        }
+        if allowed {
+            self.allowed.insert(name.to_string());
+        } else {
+            self.allowed.remove(name);
+        }
+        Ok(())
+    }
+    
+    pub fn is_allowed(&self, name: &str) -> bool {
+        self.tools
+            .get(name)
+            .is_some_and(|tool| !tool.requires_permission || self.allowed.contains(name))
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.tools.is_empty()
+    }
+    
+    pub fn definitions(&self) -> Vec<ToolDefinition> {
+        let mut definitions: Vec<ToolDefinition> = self.tools.values().map(|tool| tool.definition.clone()).collect();
+        definitions.sort_by(|a, b| a.name.cmp(&b.name));
+        definitions
+    }
+    
+    /// Runs `call`, turning unknown tools, refused permissions and handler
+    /// errors into failed invocations the model can react to.
+    pub async fn invoke(&self, call: ToolCall) -> ToolInvocation {
+        let result = match self.tools.get(&call.name) {
+            None => Err(anyhow::anyhow!("Unknown tool: {}", call.name)),
+            Some(_) if !self.is_allowed(&call.name) => Err(anyhow::anyhow!(
+                "Permission denied: the user has not allowed the {} tool",
+                call.name
+            )),
+            Some(tool) => tool.handler.call(call.input.clone()).await,
+        };
+        
+        let (output, is_error) = match result {
+            Ok(output) => (output, false),
+            Err(e) => (e.to_string(), true),
+        };
+        
+        ToolInvocation {
+            call,
+            output: truncate_output(output),
+            is_error,
+        }
+    }
+}
+
+fn truncate_output(output: String) -> String {
+    match output.char_indices().nth(MAX_TOOL_OUTPUT_CHARS) {
+        Some((end, _)) => format!("{}\n[truncated]", &output[..end]),
+        None => output,
+    }
+}
+
+fn string_input<'a>(input: &'a serde_json::Value, field: &str) -> Result<&'a str> {
+    input[field]
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("Missing string field '{}'", field))
+}
+
+/// `read_file`: returns the contents of a text file. Relative paths are
+/// resolved against `root`, and nothing outside `root` can be read, whether
+/// through an absolute path, `..` or a symlink.
+pub struct ReadFileTool {
+    root: PathBuf,
+}
+
+impl ReadFileTool {
+    pub fn tool(root: PathBuf) -> Tool {
+        Tool::new(
+            "read_file",
+            "Read the contents of a text file in the user's workspace.",
+            serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Path to a file inside the workspace, absolute or relative to the workspace root"
+                    }
+                },
+                "required": ["path"]
+            }),
+            Self { root },
+        )
+    }
+}
+
+#[async_trait::async_trait]
+impl ToolHandler for ReadFileTool {
+    async fn call(&self, input: serde_json::Value) -> Result<String> {
+        let requested = self.root.join(string_input(&input, "path")?);
+        let root = tokio::fs::canonicalize(&self.root)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to resolve the workspace root {}: {}", self.root.display(), e))?;
+        let path = tokio::fs::canonicalize(&requested)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", requested.display(), e))?;
+        if !path.starts_with(&root) {
+            return Err(ResourceError::AccessDenied(format!("{} is outside the workspace", requested.display())).into());
+        }
+        
+        tokio::fs::read_to_string(&path)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))
+    }
+}
+
+/// `run_command`: runs a shell command in a dedicated terminal session, so
+/// agent commands show up in the terminal history like the user's own.
+pub struct RunCommandTool {
+    terminal_manager: Arc<TerminalManager>,
+    working_directory: PathBuf,
+    session_id: tokio::sync::Mutex<Option<Uuid>>,
+}
+
+impl RunCommandTool {
+    pub fn tool(terminal_manager: Arc<TerminalManager>, working_directory: PathBuf) -> Tool {
+        Tool::new(
+            "run_command",
+            "Run a shell command in the user's workspace and return its output and exit code.",
+            serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "command": {
+                        "type": "string",
+                        "description": "The command line to run"
+                    }
+                },
+                "required": ["command"]
+            }),
+            Self {
+                terminal_manager,
+                working_directory,
+                session_id: tokio::sync::Mutex::new(None),
+            },
+        )
+    }
+    
+    async fn session(&self) -> Result<Uuid> {
+        let mut session_id = self.session_id.lock().await;
+        if let Some(id) = *session_id {
+            if self.terminal_manager.get_session(id).await.is_some() {
+                return Ok(id);
+            }
+        }
+        
+        let id = self
+            .terminal_manager
+            .create_session("Agent".to_string(), self.working_directory.clone(), None, HashMap::new())
+            .await?;
+        *session_id = Some(id);
+        Ok(id)
+    }
+}
+
+#[async_trait::async_trait]
+impl ToolHandler for RunCommandTool {
+    async fn call(&self, input: serde_json::Value) -> Result<String> {
+        let command = string_input(&input, "command")?.to_string();
+        let session_id = self.session().await?;
+        let block_id = self
+            .terminal_manager
+            .execute_command(session_id, command, HashMap::new(), None)
+            .await?;
+        
+        let block = self
+            .terminal_manager
+            .get_session(session_id)
+            .await
+            .and_then(|session| session.blocks.into_iter().find(|block| block.id == block_id))
+            .ok_or_else(|| ResourceError::NotFound("Command output not found".to_string()))?;
+        
+        let exit_code = block
+            .exit_code
+            .map(|code| code.to_string())
+            .unwrap_or_else(|| "unknown".to_string());
+        Ok(format!("Exit code: {}\n{}", exit_code, block.output))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[tokio::test]
+    async fn read_file_stays_inside_the_root() {
+        let dir = std::env::temp_dir().join(format!("code-furnace-tools-{}", Uuid::new_v4()));
+        let root = dir.join("workspace");
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(root.join("src/lib.rs"), "inside").unwrap();
+        std::fs::write(dir.join("secret.txt"), "outside").unwrap();
+        let tool = ReadFileTool { root: root.clone() };
+        
+        let read = |path: PathBuf| tool.call(serde_json::json!({ "path": path }));
+        assert_eq!(read(PathBuf::from("src/lib.rs")).await.unwrap(), "inside");
+        assert_eq!(read(root.join("src/lib.rs")).await.unwrap(), "inside");
+        assert!(read(PathBuf::from("../secret.txt")).await.is_err());
+        assert!(read(PathBuf::from("src/../../secret.txt")).await.is_err());
+        assert!(read(dir.join("secret.txt")).await.is_err());
+        
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(dir.join("secret.txt"), root.join("link.txt")).unwrap();
+            assert!(read(PathBuf::from("link.txt")).await.is_err());
+        }
+        
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,143 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use code_furnace_agents::AgentRequest;
+
+use crate::{Canvas, CanvasElement, CanvasMode};
+
+/// Markup that can be generated from a wireframe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CodeFramework {
+    React,
+    Html,
+    Svelte,
+}
+
+impl CodeFramework {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            CodeFramework::React => "React",
+            CodeFramework::Html => "HTML",
+            CodeFramework::Svelte => "Svelte",
+        }
+    }
+    
+    pub fn file_extension(&self) -> &'static str {
+        match self {
+            CodeFramework::React => "jsx",
+            CodeFramework::Html => "html",
+            CodeFramework::Svelte => "svelte",
+        }
+    }
+    
+    fn instructions(&self) -> &'static str {
+        match self {
+            CodeFramework::React => "Write a single React function component as the default export, styled with inline styles or plain CSS class names.",
+            CodeFramework::Html => "Write a single standalone HTML document with the CSS in a <style> element.",
+            CodeFramework::Svelte => "Write a single Svelte component with its styles in a <style> block.",
+        }
+    }
+}
+
+fn layout_node(
+    element: &CanvasElement,
+    children: &HashMap<Option<Uuid>, Vec<&CanvasElement>>,
+    origin: (f64, f64),
+) -> serde_json::Value {
+    let nested: Vec<serde_json::Value> = children
+        .get(&Some(element.id))
+        .into_iter()
+        .flatten()
+        .map(|child| layout_node(child, children, origin))
+        .collect();
+    
+    serde_json::json!({
+        "type": format!("{:?}", element.element_type),
+        "x": element.position.x - origin.0,
+        "y": element.position.y - origin.1,
+        "width": element.size.width,
+        "height": element.size.height,
+        "properties": element.properties,
+        "children": nested,
+    })
+}
+
+/// Removes the Markdown fence agents often wrap code in.
+pub(crate) fn strip_code_fence(text: &str) -> String {
+    let text = text.trim();
+    let Some(rest) = text.strip_prefix("```") else {
+        return text.to_string();
+    };
+    let body = rest.split_once('\n').map(|(_, body)| body).unwrap_or_default();
+    body.trim_end()
+        .strip_suffix("```")
+        .unwrap_or(body)
+        .trim_end()
+        .to_string()
+}
+
+impl Canvas {
+    /// The boxes of a wireframe as nested JSON, with positions relative to
+    /// the top-left corner of the layout and each box holding the boxes
+    /// drawn inside it.
+    pub fn wireframe_layout(&self) -> serde_json::Value {
+        let children = self.wireframe_tree();
+        let boxes = children.values().flatten();
+        let origin = boxes.fold((f64::MAX, f64::MAX), |(x, y), element| {
+            (x.min(element.position.x), y.min(element.position.y))
+        });
+        
+        let roots: Vec<serde_json::Value> = children
+            .get(&None)
+            .into_iter()
+            .flatten()
+            .map(|element| layout_node(element, &children, origin))
+            .collect();
+        serde_json::json!({
+            "name": self.name,
+            "elements": roots,
+        })
+    }
+    
+    /// Builds a request asking the `ui-designer` agent to turn this
+    /// wireframe into `framework` markup.
+    pub fn code_generation_request(&self, framework: CodeFramework) -> Result<AgentRequest> {
+        if !matches!(self.mode, CanvasMode::Wireframe) {
+            return Err(anyhow::anyhow!("Code can only be generated from wireframe canvases"));
+        }
+        if self.elements.is_empty() {
+            return Err(anyhow::anyhow!("Canvas '{}' has no elements to generate code from", self.name));
+        }
+        
+        let layout = serde_json::to_string_pretty(&self.wireframe_layout())?;
+        let prompt = format!(
+            "Turn this wireframe into {} code. {}\n\
+            The layout below is JSON: each element has a type, a position and size in pixels \
+            relative to the top-left corner, its properties (such as `label` or `text`) and the \
+            elements nested inside it. Keep the structure and relative placement, and use \
+            semantic, accessible markup.\n\n\
+            Wireframe layout:\n{}\n\n\
+            Respond with ONLY the code, no explanation.",
+            framework.display_name(),
+            framework.instructions(),
+            layout,
+        );
+        
+        Ok(AgentRequest {
+            id: Uuid::new_v4(),
+            agent_type: "ui-designer".to_string(),
+            prompt,
+            context: HashMap::from([(
+                "framework".to_string(),
+                serde_json::Value::String(framework.display_name().to_string()),
+            )]),
+            files: Vec::new(),
+            tools: Vec::new(),
+            tool_turns: Vec::new(),
+            model: None,
+        })
+    }
+}
@@ -0,0 +1,177 @@
+use anyhow::Result;
+use uuid::Uuid;
+
+use crate::{Canvas, CanvasElement, ElementType};
+
+/// Blank space kept around the elements in exported images.
+const EXPORT_PADDING: f64 = 20.0;
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn string_property<'a>(element: &'a CanvasElement, key: &str) -> Option<&'a str> {
+    element.get_property(key).and_then(|v| v.as_str())
+}
+
+fn center(element: &CanvasElement) -> (f64, f64) {
+    (
+        element.position.x + element.size.width / 2.0,
+        element.position.y + element.size.height / 2.0,
+    )
+}
+
+impl Canvas {
+    /// Elements from bottom to top by `z_index`, with ties broken by creation
+    /// time and id so exports are identical for the same canvas.
+    pub(crate) fn elements_in_draw_order(&self) -> Vec<&CanvasElement> {
+        let mut elements: Vec<&CanvasElement> = self.elements.values().collect();
+        elements.sort_by_key(|element| (element.z_index, element.created_at, element.id));
+        elements
+    }
+    
+    /// Renders the canvas as a standalone SVG document cropped to its
+    /// elements. Shapes take their colour from a `fill` or `color` property
+    /// and show their `label`; edges join the centres of the elements named by
+    /// their `from`/`to` properties.
+    pub fn export_to_svg(&self) -> Result<String> {
+        self.render_svg(None)
+    }
+    
+    fn render_svg(&self, background: Option<&str>) -> Result<String> {
+        let elements = self.elements_in_draw_order();
+        
+        let (min_x, min_y, max_x, max_y) = elements.iter().fold(
+            (f64::MAX, f64::MAX, f64::MIN, f64::MIN),
+            |(min_x, min_y, max_x, max_y), element| {
+                (
+                    min_x.min(element.position.x),
+                    min_y.min(element.position.y),
+                    max_x.max(element.position.x + element.size.width),
+                    max_y.max(element.position.y + element.size.height),
+                )
+            },
+        );
+        let (origin_x, origin_y, width, height) = if elements.is_empty() {
+            (0.0, 0.0, 2.0 * EXPORT_PADDING, 2.0 * EXPORT_PADDING)
+        } else {
+            (
+                min_x - EXPORT_PADDING,
+                min_y - EXPORT_PADDING,
+                max_x - min_x + 2.0 * EXPORT_PADDING,
+                max_y - min_y + 2.0 * EXPORT_PADDING,
+            )
+        };
+        
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"{x} {y} {w} {h}\">\n",
+            x = origin_x,
+            y = origin_y,
+            w = width,
+            h = height,
+        );
+        if let Some(background) = background {
+            svg.push_str(&format!(
+                "  <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>\n",
+                origin_x, origin_y, width, height, escape_xml(background)
+            ));
+        }
+        
+        for element in elements {
+            self.render_element(element, &mut svg);
+        }
+        
+        svg.push_str("</svg>\n");
+        Ok(svg)
+    }
+    
+    fn render_element(&self, element: &CanvasElement, svg: &mut String) {
+        let (x, y) = (element.position.x, element.position.y);
+        let (width, height) = (element.size.width, element.size.height);
+        let default_fill = match element.element_type {
+            ElementType::StickyNote => "#fff3a0",
+            _ => "#ffffff",
+        };
+        let fill = string_property(element, "fill")
+            .or_else(|| string_property(element, "color"))
+            .unwrap_or(default_fill);
+        let fill = escape_xml(fill);
+        
+        match element.element_type {
+            ElementType::Circle => svg.push_str(&format!(
+                "  <ellipse cx=\"{}\" cy=\"{}\" rx=\"{}\" ry=\"{}\" fill=\"{}\" stroke=\"#333333\"/>\n",
+                x + width / 2.0, y + height / 2.0, width / 2.0, height / 2.0, fill
+            )),
+            ElementType::Text => {
+                let text = string_property(element, "text")
+                    .or_else(|| string_property(element, "label"))
+                    .unwrap_or_default();
+                svg.push_str(&format!(
+                    "  <text x=\"{}\" y=\"{}\" font-family=\"Arial, Helvetica, DejaVu Sans, sans-serif\" font-size=\"14\" dominant-baseline=\"hanging\">{}</text>\n",
+                    x, y, escape_xml(text)
+                ));
+                return;
+            }
+            ElementType::FlowchartEdge => {
+                let endpoint = |key: &str| {
+                    string_property(element, key)
+                        .and_then(|id| Uuid::parse_str(id).ok())
+                        .and_then(|id| self.elements.get(&id))
+                        .map(center)
+                };
+                let ((x1, y1), (x2, y2)) = match (endpoint("from"), endpoint("to")) {
+                    (Some(from), Some(to)) => (from, to),
+                    _ => ((x, y), (x + width, y + height)),
+                };
+                svg.push_str(&format!(
+                    "  <line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"#333333\" stroke-width=\"2\"/>\n",
+                    x1, y1, x2, y2
+                ));
+                return;
+            }
+            _ => svg.push_str(&format!(
+                "  <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"4\" fill=\"{}\" stroke=\"#333333\"/>\n",
+                x, y, width, height, fill
+            )),
+        }
+        
+        if let Some(label) = string_property(element, "label") {
+            let (cx, cy) = center(element);
+            svg.push_str(&format!(
+                "  <text x=\"{}\" y=\"{}\" font-family=\"Arial, Helvetica, DejaVu Sans, sans-serif\" font-size=\"14\" text-anchor=\"middle\" dominant-baseline=\"central\">{}</text>\n",
+                cx, cy, escape_xml(label)
+            ));
+        }
+    }
+    
+    /// Rasterizes the SVG export to PNG bytes. `scale` multiplies the output
+    /// size for high-DPI screens; `background` is any SVG colour, and the
+    /// image is transparent without one.
+    pub fn export_to_png(&self, scale: f32, background: Option<&str>) -> Result<Vec<u8>> {
+        if self.elements.is_empty() {
+            return Err(anyhow::anyhow!("Canvas '{}' has no elements to export", self.name));
+        }
+        if !(scale.is_finite() && scale > 0.0) {
+            return Err(anyhow::anyhow!("Invalid PNG export scale: {}", scale));
+        }
+        
+        let svg = self.render_svg(background)?;
+        let mut options = resvg::usvg::Options::default();
+        options.fontdb_mut().load_system_fonts();
+        let tree = resvg::usvg::Tree::from_str(&svg, &options)?;
+        
+        let size = tree
+            .size()
+            .to_int_size()
+            .scale_by(scale)
+            .ok_or_else(|| anyhow::anyhow!("Canvas is too small to export at scale {}", scale))?;
+        let mut pixmap = resvg::tiny_skia::Pixmap::new(size.width(), size.height())
+            .ok_or_else(|| anyhow::anyhow!("Canvas is too large to export at scale {}", scale))?;
+        resvg::render(&tree, resvg::tiny_skia::Transform::from_scale(scale, scale), &mut pixmap.as_mut());
+        
+        Ok(pixmap.encode_png()?)
+    }
+}
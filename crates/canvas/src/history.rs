@@ -0,0 +1,229 @@
+use std::collections::VecDeque;
+
+use crate::{Canvas, CanvasElement};
+
+/// Undo steps kept per canvas; the oldest are dropped first.
+pub const MAX_UNDO_STEPS: usize = 100;
+
+/// A reversible change to one element.
+#[derive(Debug, Clone)]
+pub enum ElementChange {
+    Added(CanvasElement),
+    Removed(CanvasElement),
+    Updated { before: CanvasElement, after: CanvasElement },
+}
+
+impl ElementChange {
+    fn inverse(&self) -> Self {
+        match self {
+            ElementChange::Added(element) => ElementChange::Removed(element.clone()),
+            ElementChange::Removed(element) => ElementChange::Added(element.clone()),
+            ElementChange::Updated { before, after } => ElementChange::Updated {
+                before: after.clone(),
+                after: before.clone(),
+            },
+        }
+    }
+}
+
+/// Undo and redo stacks of a canvas. Each step holds the changes made by one
+/// edit, which may touch several elements. History is not saved with the
+/// canvas.
+#[derive(Debug, Clone, Default)]
+pub struct CanvasHistory {
+    undo: VecDeque<Vec<ElementChange>>,
+    redo: Vec<Vec<ElementChange>>,
+    /// Changes collected between `Canvas::begin_undo_group` and
+    /// `Canvas::end_undo_group`.
+    group: Option<Vec<ElementChange>>,
+}
+
+impl CanvasHistory {
+    /// Records a new edit, which discards anything that could be redone.
+    pub fn record(&mut self, step: Vec<ElementChange>) {
+        if step.is_empty() {
+            return;
+        }
+        if let Some(group) = &mut self.group {
+            group.extend(step);
+            return;
+        }
+        self.redo.clear();
+        self.undo.push_back(step);
+        if self.undo.len() > MAX_UNDO_STEPS {
+            self.undo.pop_front();
+        }
+    }
+    
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+    
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+}
+
+impl Canvas {
+    fn apply_change(&mut self, change: &ElementChange) {
+        match change {
+            ElementChange::Added(element) => {
+                self.elements.insert(element.id, element.clone());
+            }
+            ElementChange::Removed(element) => {
+                self.elements.remove(&element.id);
+            }
+            ElementChange::Updated { after, .. } => {
+                self.elements.insert(after.id, after.clone());
+            }
+        }
+    }
+    
+    /// Starts collecting edits into one undo step, until `end_undo_group`.
+    /// Starting a group while one is open keeps the open one.
+    pub fn begin_undo_group(&mut self) {
+        self.history.group.get_or_insert_with(Vec::new);
+    }
+    
+    /// Records the edits made since `begin_undo_group` as a single step.
+    pub fn end_undo_group(&mut self) {
+        if let Some(step) = self.history.group.take() {
+            self.history.record(step);
+        }
+    }
+    
+    /// Reverts the most recent edit. Returns false if there was none.
+    pub fn undo(&mut self) -> bool {
+        self.end_undo_group();
+        let Some(step) = self.history.undo.pop_back() else {
+            return false;
+        };
+        for change in step.iter().rev() {
+            self.apply_change(&change.inverse());
+        }
+        self.history.redo.push(step);
+        self.updated_at = chrono::Utc::now();
+        true
+    }
+    
+    /// Reapplies the most recently undone edit. Returns false if there was none.
+    pub fn redo(&mut self) -> bool {
+        self.end_undo_group();
+        let Some(step) = self.history.redo.pop() else {
+            return false;
+        };
+        for change in &step {
+            self.apply_change(change);
+        }
+        self.history.undo.push_back(step);
+        self.updated_at = chrono::Utc::now();
+        true
+    }
+    
+    pub fn history(&self) -> &CanvasHistory {
+        &self.history
+    }
+    
+    /// Takes the name, mode, viewport, metadata and elements of `other`,
+    /// keeping this canvas's id, creation time and history. The element
+    /// changes are recorded as a single undo step.
+    pub fn replace_with(&mut self, other: Canvas) {
+        let mut step = Vec::new();
+        for (id, before) in &self.elements {
+            match other.elements.get(id) {
+                Some(after) if after != before => step.push(ElementChange::Updated {
+                    before: before.clone(),
+                    after: after.clone(),
+                }),
+                Some(_) => {}
+                None => step.push(ElementChange::Removed(before.clone())),
+            }
+        }
+        for (id, after) in &other.elements {
+            if !self.elements.contains_key(id) {
+                step.push(ElementChange::Added(after.clone()));
+            }
+        }
+        
+        self.name = other.name;
+        self.mode = other.mode;
+        self.viewport = other.viewport;
+        self.metadata = other.metadata;
+        self.elements = other.elements;
+        self.history.record(step);
+        self.updated_at = chrono::Utc::now();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CanvasMode, ElementType, Point, Size};
+    
+    fn element(x: f64) -> CanvasElement {
+        CanvasElement::new(ElementType::Rectangle, Point { x, y: 0.0 }, Size { width: 10.0, height: 10.0 })
+    }
+    
+    fn canvas() -> Canvas {
+        Canvas::new("History".to_string(), CanvasMode::Freeform)
+    }
+    
+    #[test]
+    fn only_the_latest_steps_are_kept() {
+        let mut canvas = canvas();
+        for i in 0..MAX_UNDO_STEPS + 5 {
+            canvas.add_element(element(i as f64));
+        }
+        
+        let mut undone = 0;
+        while canvas.undo() {
+            undone += 1;
+        }
+        assert_eq!(undone, MAX_UNDO_STEPS);
+        // The five oldest additions can no longer be undone
+        assert_eq!(canvas.elements.len(), 5);
+    }
+    
+    #[test]
+    fn a_new_edit_clears_redo() {
+        let mut canvas = canvas();
+        canvas.add_element(element(0.0));
+        assert!(canvas.undo());
+        assert!(canvas.history().can_redo());
+        
+        canvas.add_element(element(1.0));
+        assert!(!canvas.history().can_redo());
+        assert!(!canvas.redo());
+    }
+    
+    #[test]
+    fn grouped_edits_undo_and_redo_together() {
+        let mut canvas = canvas();
+        canvas.add_element(element(0.0));
+        
+        canvas.begin_undo_group();
+        canvas.add_element(element(1.0));
+        canvas.add_element(element(2.0));
+        canvas.begin_undo_group();
+        canvas.add_element(element(3.0));
+        canvas.end_undo_group();
+        assert_eq!(canvas.elements.len(), 4);
+        
+        assert!(canvas.undo());
+        assert_eq!(canvas.elements.len(), 1);
+        assert!(canvas.redo());
+        assert_eq!(canvas.elements.len(), 4);
+    }
+    
+    #[test]
+    fn undo_closes_an_open_group() {
+        let mut canvas = canvas();
+        canvas.begin_undo_group();
+        canvas.add_element(element(0.0));
+        canvas.add_element(element(1.0));
+        
+        assert!(canvas.undo());
+        assert!(canvas.elements.is_empty());
+        assert!(!canvas.history().can_undo());
+    }
+}
@@ -3,30 +3,42 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use uuid::Uuid;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+mod codegen;
+mod export;
+pub mod history;
+pub mod manager;
+mod mermaid;
+pub use codegen::CodeFramework;
+pub use history::*;
+pub use manager::*;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Point {
     pub x: f64,
     pub y: f64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Size {
     pub width: f64,
     pub height: f64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct CanvasElement {
     pub id: Uuid,
     pub element_type: ElementType,
     pub position: Point,
     pub size: Size,
     pub properties: HashMap<String, serde_json::Value>,
+    /// Stacking order; higher values are drawn on top.
+    #[serde(default)]
+    pub z_index: i64,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum ElementType {
     Rectangle,
     Circle,
@@ -48,6 +60,7 @@ impl CanvasElement {
             position,
             size,
             properties: HashMap::new(),
+            z_index: 0,
             created_at: now,
             updated_at: now,
         }
@@ -73,6 +86,8 @@ pub struct Canvas {
     pub metadata: HashMap<String, serde_json::Value>,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
+    #[serde(skip)]
+    history: CanvasHistory,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -83,6 +98,16 @@ pub enum CanvasMode {
     SystemDesign,
 }
 
+/// Ways to move an element in the stacking order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Reorder {
+    Front,
+    Back,
+    Forward,
+    Backward,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Viewport {
     pub x: f64,
@@ -112,17 +137,27 @@ impl Canvas {
             metadata: HashMap::new(),
             created_at: now,
             updated_at: now,
+            history: CanvasHistory::default(),
         }
     }
     
-    pub fn add_element(&mut self, element: CanvasElement) {
+    /// Adds `element` on top of the existing ones.
+    pub fn add_element(&mut self, mut element: CanvasElement) {
+        element.z_index = self
+            .elements
+            .values()
+            .map(|existing| existing.z_index + 1)
+            .max()
+            .unwrap_or(0);
+        self.history.record(vec![ElementChange::Added(element.clone())]);
         self.elements.insert(element.id, element);
         self.updated_at = chrono::Utc::now();
     }
     
     pub fn remove_element(&mut self, element_id: Uuid) -> Option<CanvasElement> {
         let element = self.elements.remove(&element_id);
-        if element.is_some() {
+        if let Some(element) = &element {
+            self.history.record(vec![ElementChange::Removed(element.clone())]);
             self.updated_at = chrono::Utc::now();
         }
         element
@@ -130,11 +165,69 @@ impl Canvas {
     
     pub fn update_element(&mut self, element_id: Uuid, updater: impl FnOnce(&mut CanvasElement)) {
         if let Some(element) = self.elements.get_mut(&element_id) {
+            let before = element.clone();
             updater(element);
+            let after = element.clone();
+            self.history.record(vec![ElementChange::Updated { before, after }]);
             self.updated_at = chrono::Utc::now();
         }
     }
     
+    pub fn bring_to_front(&mut self, element_id: Uuid) -> bool {
+        self.reorder_element(element_id, Reorder::Front)
+    }
+    
+    pub fn send_to_back(&mut self, element_id: Uuid) -> bool {
+        self.reorder_element(element_id, Reorder::Back)
+    }
+    
+    pub fn move_forward(&mut self, element_id: Uuid) -> bool {
+        self.reorder_element(element_id, Reorder::Forward)
+    }
+    
+    pub fn move_backward(&mut self, element_id: Uuid) -> bool {
+        self.reorder_element(element_id, Reorder::Backward)
+    }
+    
+    /// Moves an element in the stacking order and renumbers every `z_index`
+    /// from zero, recording the change as one undo step. Returns false if the
+    /// element does not exist or is already in place.
+    pub fn reorder_element(&mut self, element_id: Uuid, reorder: Reorder) -> bool {
+        let mut order: Vec<Uuid> = self
+            .elements_in_draw_order()
+            .into_iter()
+            .map(|element| element.id)
+            .collect();
+        let Some(index) = order.iter().position(|id| *id == element_id) else {
+            return false;
+        };
+        let target = match reorder {
+            Reorder::Front => order.len() - 1,
+            Reorder::Back => 0,
+            Reorder::Forward => (index + 1).min(order.len() - 1),
+            Reorder::Backward => index.saturating_sub(1),
+        };
+        if target == index {
+            return false;
+        }
+        let id = order.remove(index);
+        order.insert(target, id);
+        
+        let mut step = Vec::new();
+        for (z_index, id) in order.iter().enumerate() {
+            if let Some(element) = self.elements.get_mut(id) {
+                if element.z_index != z_index as i64 {
+                    let before = element.clone();
+                    element.z_index = z_index as i64;
+                    step.push(ElementChange::Updated { before, after: element.clone() });
+                }
+            }
+        }
+        self.history.record(step);
+        self.updated_at = chrono::Utc::now();
+        true
+    }
+    
     pub fn export_to_json(&self) -> Result<String> {
         Ok(serde_json::to_string_pretty(self)?)
     }
@@ -142,56 +235,93 @@ impl Canvas {
     pub fn import_from_json(json: &str) -> Result<Self> {
         Ok(serde_json::from_str(json)?)
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn element() -> CanvasElement {
+        CanvasElement::new(ElementType::Rectangle, Point { x: 0.0, y: 0.0 }, Size { width: 10.0, height: 10.0 })
+    }
     
-    pub fn export_to_mermaid(&self) -> Result<String> {
-        match self.mode {
-            CanvasMode::Flowchart => self.export_flowchart_to_mermaid(),
-            CanvasMode::SystemDesign => self.export_system_design_to_mermaid(),
-            _ => Err(anyhow::anyhow!("Mermaid export not supported for this canvas mode")),
-        }
+    /// A canvas with three elements added bottom to top, and their ids.
+    fn stacked() -> (Canvas, [Uuid; 3]) {
+        let mut canvas = Canvas::new("Stack".to_string(), CanvasMode::Freeform);
+        let ids = [(); 3].map(|_| {
+            let element = element();
+            let id = element.id;
+            canvas.add_element(element);
+            id
+        });
+        (canvas, ids)
     }
     
-    fn export_flowchart_to_mermaid(&self) -> Result<String> {
-        let mut mermaid = String::from("flowchart TD\n");
+    fn draw_order(canvas: &Canvas) -> Vec<Uuid> {
+        canvas.elements_in_draw_order().into_iter().map(|element| element.id).collect()
+    }
+    
+    #[test]
+    fn bring_to_front_and_send_to_back() {
+        let (mut canvas, [a, b, c]) = stacked();
         
-        for element in self.elements.values() {
-            match element.element_type {
-                ElementType::FlowchartNode => {
-                    let label = element
-                        .get_property("label")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("Node");
-                    mermaid.push_str(&format!("    {}[\"{}\"]\n", element.id, label));
-                }
-                ElementType::FlowchartEdge => {
-                    let from = element
-                        .get_property("from")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("");
-                    let to = element
-                        .get_property("to")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("");
-                    if !from.is_empty() && !to.is_empty() {
-                        mermaid.push_str(&format!("    {} --> {}\n", from, to));
-                    }
-                }
-                _ => {}
-            }
-        }
+        assert!(canvas.bring_to_front(a));
+        assert_eq!(draw_order(&canvas), vec![b, c, a]);
+        assert!(!canvas.bring_to_front(a));
+        
+        assert!(canvas.send_to_back(c));
+        assert_eq!(draw_order(&canvas), vec![c, b, a]);
+        assert!(!canvas.send_to_back(c));
         
-        Ok(mermaid)
+        let z_indexes: Vec<i64> = [c, b, a].iter().map(|id| canvas.elements[id].z_index).collect();
+        assert_eq!(z_indexes, vec![0, 1, 2]);
     }
     
-    fn export_system_design_to_mermaid(&self) -> Result<String> {
-        let mut mermaid = String::from("graph TB\n");
+    #[test]
+    fn move_forward_and_backward_by_one() {
+        let (mut canvas, [a, b, c]) = stacked();
         
-        for element in self.elements.values() {
-            if let Some(label) = element.get_property("label").and_then(|v| v.as_str()) {
-                mermaid.push_str(&format!("    {}[{}]\n", element.id, label));
-            }
+        assert!(canvas.move_forward(a));
+        assert_eq!(draw_order(&canvas), vec![b, a, c]);
+        assert!(canvas.move_backward(c));
+        assert_eq!(draw_order(&canvas), vec![b, c, a]);
+        
+        assert!(!canvas.move_backward(b));
+        assert!(!canvas.move_forward(a));
+        assert!(!canvas.move_forward(Uuid::new_v4()));
+    }
+    
+    #[test]
+    fn a_reorder_is_one_undo_step() {
+        let (mut canvas, [a, b, c]) = stacked();
+        
+        assert!(canvas.bring_to_front(a));
+        assert!(canvas.undo());
+        assert_eq!(draw_order(&canvas), vec![a, b, c]);
+        assert_eq!(canvas.elements.len(), 3);
+    }
+    
+    #[test]
+    fn draw_order_breaks_ties_by_creation_time_then_id() {
+        let mut canvas = Canvas::new("Ties".to_string(), CanvasMode::Freeform);
+        let created_at = chrono::Utc::now();
+        let mut elements: Vec<CanvasElement> = (0..3).map(|_| element()).collect();
+        elements[0].created_at = created_at + chrono::Duration::seconds(1);
+        elements[1].created_at = created_at;
+        elements[2].created_at = created_at;
+        for element in &elements {
+            // Inserted directly so that every element keeps `z_index` 0
+            canvas.elements.insert(element.id, element.clone());
         }
         
-        Ok(mermaid)
+        let (first, second) = if elements[1].id < elements[2].id {
+            (elements[1].id, elements[2].id)
+        } else {
+            (elements[2].id, elements[1].id)
+        };
+        assert_eq!(draw_order(&canvas), vec![first, second, elements[0].id]);
+        
+        canvas.elements.get_mut(&first).unwrap().z_index = 1;
+        assert_eq!(draw_order(&canvas), vec![second, elements[0].id, first]);
     }
-}
\ No newline at end of file
+}
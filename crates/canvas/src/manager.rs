@@ -0,0 +1,296 @@
+use anyhow::Result;
+use code_furnace_utils::ResourceError;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use crate::codegen::strip_code_fence;
+use crate::{Canvas, CanvasMode, CodeFramework, Reorder};
+
+/// Canvases are saved one JSON file each under this directory of the project.
+pub const CANVAS_DIR: &str = ".code-furnace/canvases";
+
+pub struct CanvasManager {
+    canvases: Arc<RwLock<HashMap<Uuid, Canvas>>>,
+    storage_dir: Arc<RwLock<Option<PathBuf>>>,
+    agent_bridge: Option<Arc<RwLock<code_furnace_agents::AgentBridge>>>,
+    event_bus: code_furnace_events::EventBus,
+}
+
+impl CanvasManager {
+    pub fn new(event_bus: code_furnace_events::EventBus) -> Self {
+        Self {
+            canvases: Arc::new(RwLock::new(HashMap::new())),
+            storage_dir: Arc::new(RwLock::new(None)),
+            agent_bridge: None,
+            event_bus,
+        }
+    }
+    
+    /// Lets `generate_code` ask the `ui-designer` agent.
+    pub fn with_agent_bridge(mut self, agent_bridge: Arc<RwLock<code_furnace_agents::AgentBridge>>) -> Self {
+        self.agent_bridge = Some(agent_bridge);
+        self
+    }
+    
+    /// Switches to the canvases of the project at `project_dir`, replacing
+    /// those of the previous project. Returns how many were loaded; files
+    /// that fail to parse are skipped.
+    pub async fn set_project_dir(&self, project_dir: &Path) -> Result<usize> {
+        let storage_dir = project_dir.join(CANVAS_DIR);
+        let mut loaded = HashMap::new();
+        
+        if storage_dir.is_dir() {
+            for entry in std::fs::read_dir(&storage_dir)? {
+                let path = entry?.path();
+                if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                    continue;
+                }
+                
+                let canvas = std::fs::read_to_string(&path)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|json| Canvas::import_from_json(&json));
+                match canvas {
+                    Ok(canvas) => {
+                        loaded.insert(canvas.id, canvas);
+                    }
+                    Err(e) => tracing::warn!("Skipping unreadable canvas {}: {}", path.display(), e),
+                }
+            }
+        }
+        
+        let count = loaded.len();
+        *self.canvases.write().await = loaded;
+        *self.storage_dir.write().await = Some(storage_dir);
+        Ok(count)
+    }
+    
+    pub async fn create_canvas(&self, name: String, mode: CanvasMode) -> Result<Uuid> {
+        self.add_canvas(Canvas::new(name, mode)).await
+    }
+    
+    /// Stores an already built canvas, such as one imported from Mermaid.
+    pub async fn add_canvas(&self, canvas: Canvas) -> Result<Uuid> {
+        let canvas_id = canvas.id;
+        
+        self.persist(&canvas).await?;
+        self.canvases.write().await.insert(canvas_id, canvas);
+        
+        let event = code_furnace_events::Event::new(
+            "canvas.created",
+            "canvas-manager",
+            serde_json::json!({
+                "canvas_id": canvas_id
+            }),
+        );
+        self.event_bus.publish(event)?;
+        
+        Ok(canvas_id)
+    }
+    
+    pub async fn get_canvas(&self, canvas_id: Uuid) -> Option<Canvas> {
+        let canvases = self.canvases.read().await;
+        canvases.get(&canvas_id).cloned()
+    }
+    
+    /// Most recently updated first.
+    pub async fn list_canvases(&self) -> Vec<Canvas> {
+        let canvases = self.canvases.read().await;
+        let mut list: Vec<Canvas> = canvases.values().cloned().collect();
+        list.sort_by_key(|canvas| std::cmp::Reverse(canvas.updated_at));
+        list
+    }
+    
+    /// Replaces the stored canvas with `canvas_data`, keeping its id,
+    /// creation time and undo history. Changes stay in memory until
+    /// `save_canvas`.
+    pub async fn update_canvas(&self, canvas_id: Uuid, canvas_data: serde_json::Value) -> Result<()> {
+        let mut canvases = self.canvases.write().await;
+        let canvas = canvases
+            .get_mut(&canvas_id)
+            .ok_or_else(|| ResourceError::NotFound(format!("Canvas not found: {}", canvas_id)))?;
+        
+        let updated: Canvas = serde_json::from_value(canvas_data.clone())?;
+        canvas.replace_with(updated);
+        drop(canvases);
+        
+        let event = code_furnace_events::Event::new(
+            "canvas.updated",
+            "canvas-manager",
+            serde_json::json!({
+                "canvas_id": canvas_id,
+                "data": canvas_data
+            }),
+        );
+        self.event_bus.publish(event)?;
+        
+        Ok(())
+    }
+    
+    /// Reverts the last edit of the stored canvas. Returns false if there
+    /// was nothing to undo.
+    pub async fn undo_canvas(&self, canvas_id: Uuid) -> Result<bool> {
+        self.step_history(canvas_id, Canvas::undo).await
+    }
+    
+    /// Reapplies the last undone edit of the stored canvas. Returns false if
+    /// there was nothing to redo.
+    pub async fn redo_canvas(&self, canvas_id: Uuid) -> Result<bool> {
+        self.step_history(canvas_id, Canvas::redo).await
+    }
+    
+    /// Moves an element of the stored canvas in the stacking order. Returns
+    /// false if it was already in place.
+    pub async fn reorder_element(&self, canvas_id: Uuid, element_id: Uuid, reorder: Reorder) -> Result<bool> {
+        self.modify_canvas(canvas_id, |canvas| {
+            if !canvas.elements.contains_key(&element_id) {
+                return Err(ResourceError::NotFound(format!("Element not found: {}", element_id)).into());
+            }
+            Ok(canvas.reorder_element(element_id, reorder))
+        })
+        .await
+    }
+    
+    async fn step_history(&self, canvas_id: Uuid, step: fn(&mut Canvas) -> bool) -> Result<bool> {
+        self.modify_canvas(canvas_id, |canvas| Ok(step(canvas))).await
+    }
+    
+    /// Applies `change` to the stored canvas and publishes `canvas.updated`
+    /// if it reports that something changed.
+    async fn modify_canvas(
+        &self,
+        canvas_id: Uuid,
+        change: impl FnOnce(&mut Canvas) -> Result<bool>,
+    ) -> Result<bool> {
+        let mut canvases = self.canvases.write().await;
+        let canvas = canvases
+            .get_mut(&canvas_id)
+            .ok_or_else(|| ResourceError::NotFound(format!("Canvas not found: {}", canvas_id)))?;
+        if !change(canvas)? {
+            return Ok(false);
+        }
+        let canvas_data = serde_json::to_value(&*canvas)?;
+        drop(canvases);
+        
+        let event = code_furnace_events::Event::new(
+            "canvas.updated",
+            "canvas-manager",
+            serde_json::json!({
+                "canvas_id": canvas_id,
+                "data": canvas_data
+            }),
+        );
+        self.event_bus.publish(event)?;
+        
+        Ok(true)
+    }
+    
+    /// Asks the `ui-designer` agent to turn a wireframe canvas into
+    /// `framework` markup and returns the code.
+    pub async fn generate_code(&self, canvas_id: Uuid, framework: CodeFramework) -> Result<String> {
+        let canvas = self
+            .get_canvas(canvas_id)
+            .await
+            .ok_or_else(|| ResourceError::NotFound(format!("Canvas not found: {}", canvas_id)))?;
+        let request = canvas.code_generation_request(framework)?;
+        let agent_bridge = self
+            .agent_bridge
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No agent is configured to generate code"))?;
+        
+        // Hold the bridge lock only while picking the provider
+        let (pending, registry) = {
+            let agent_bridge = agent_bridge.read().await;
+            (agent_bridge.begin_detached_request(request), agent_bridge.request_registry())
+        };
+        let response = pending.execute(&registry).await?;
+        if let Some(error) = response.error {
+            return Err(anyhow::anyhow!("Code generation failed: {}", error));
+        }
+        
+        let code = strip_code_fence(&response.content);
+        if code.is_empty() {
+            return Err(anyhow::anyhow!("The agent returned no code"));
+        }
+        
+        let event = code_furnace_events::Event::new(
+            "canvas.code_generated",
+            "canvas-manager",
+            serde_json::json!({
+                "canvas_id": canvas_id,
+                "framework": framework
+            }),
+        );
+        self.event_bus.publish(event)?;
+        
+        Ok(code)
+    }
+    
+    pub async fn save_canvas(&self, canvas_id: Uuid) -> Result<()> {
+        let canvas = self
+            .get_canvas(canvas_id)
+            .await
+            .ok_or_else(|| ResourceError::NotFound(format!("Canvas not found: {}", canvas_id)))?;
+        if self.storage_dir.read().await.is_none() {
+            return Err(anyhow::anyhow!("No project is open to save canvases in"));
+        }
+        self.persist(&canvas).await?;
+        
+        let event = code_furnace_events::Event::new(
+            "canvas.saved",
+            "canvas-manager",
+            serde_json::json!({
+                "canvas_id": canvas_id
+            }),
+        );
+        self.event_bus.publish(event)?;
+        
+        Ok(())
+    }
+    
+    pub async fn delete_canvas(&self, canvas_id: Uuid) -> Result<()> {
+        if self.canvases.write().await.remove(&canvas_id).is_none() {
+            return Err(ResourceError::NotFound(format!("Canvas not found: {}", canvas_id)).into());
+        }
+        
+        if let Some(storage_dir) = self.storage_dir.read().await.as_ref() {
+            let path = Self::canvas_path(storage_dir, canvas_id);
+            if path.exists() {
+                std::fs::remove_file(&path)?;
+            }
+        }
+        
+        let event = code_furnace_events::Event::new(
+            "canvas.deleted",
+            "canvas-manager",
+            serde_json::json!({
+                "canvas_id": canvas_id
+            }),
+        );
+        self.event_bus.publish(event)?;
+        
+        Ok(())
+    }
+    
+    fn canvas_path(storage_dir: &Path, canvas_id: Uuid) -> PathBuf {
+        storage_dir.join(format!("{}.json", canvas_id))
+    }
+    
+    /// Writes through a temporary file so a crash mid-write never leaves a
+    /// truncated canvas. Does nothing until a project is open.
+    async fn persist(&self, canvas: &Canvas) -> Result<()> {
+        let storage_dir = self.storage_dir.read().await;
+        let Some(storage_dir) = storage_dir.as_ref() else {
+            return Ok(());
+        };
+        
+        std::fs::create_dir_all(storage_dir)?;
+        let path = Self::canvas_path(storage_dir, canvas.id);
+        let temp_path = path.with_extension("json.tmp");
+        std::fs::write(&temp_path, canvas.export_to_json()?)?;
+        std::fs::rename(&temp_path, &path)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,761 @@
+use anyhow::Result;
+use std::collections::{BTreeSet, HashMap};
+use uuid::Uuid;
+
+use crate::{Canvas, CanvasElement, CanvasMode, ElementType, Point, Size};
+
+/// Freeform elements whose centres are at most this far apart are linked when
+/// nothing links them explicitly.
+const PROXIMITY_LINK_DISTANCE: f64 = 200.0;
+
+/// Mermaid node ids cannot contain `-`, so UUIDs are written without them.
+fn mermaid_id(id: Uuid) -> String {
+    format!("n{}", id.simple())
+}
+
+fn escape_label(label: &str) -> String {
+    label.replace('"', "#quot;")
+}
+
+fn label_of(element: &CanvasElement) -> String {
+    element
+        .get_property("label")
+        .or_else(|| element.get_property("text"))
+        .and_then(|v| v.as_str())
+        .map(|label| label.to_string())
+        .unwrap_or_else(|| format!("{:?}", element.element_type))
+}
+
+/// The ids an edge element joins, from its `from`/`to` properties.
+fn edge_endpoints(element: &CanvasElement) -> Option<(Uuid, Uuid)> {
+    let endpoint = |key: &str| {
+        element
+            .get_property(key)
+            .and_then(|v| v.as_str())
+            .and_then(|id| Uuid::parse_str(id).ok())
+    };
+    Some((endpoint("from")?, endpoint("to")?))
+}
+
+/// A `-->` line between two nodes, labelled with the edge's `label`.
+fn link_line(from: Uuid, to: Uuid, edge: &CanvasElement) -> String {
+    match edge.get_property("label").and_then(|v| v.as_str()) {
+        Some(label) => format!("    {} -->|\"{}\"| {}\n", mermaid_id(from), escape_label(label), mermaid_id(to)),
+        None => format!("    {} --> {}\n", mermaid_id(from), mermaid_id(to)),
+    }
+}
+
+fn is_edge(element: &CanvasElement) -> bool {
+    matches!(element.element_type, ElementType::FlowchartEdge) || edge_endpoints(element).is_some()
+}
+
+/// Whether `inner` lies entirely within `outer`, which must also be larger so
+/// that identical boxes never contain each other.
+fn contains(outer: &CanvasElement, inner: &CanvasElement) -> bool {
+    let area = |element: &CanvasElement| element.size.width * element.size.height;
+    outer.id != inner.id
+        && inner.position.x >= outer.position.x
+        && inner.position.y >= outer.position.y
+        && inner.position.x + inner.size.width <= outer.position.x + outer.size.width
+        && inner.position.y + inner.size.height <= outer.position.y + outer.size.height
+        && area(outer) > area(inner)
+}
+
+fn distance(a: &CanvasElement, b: &CanvasElement) -> f64 {
+    let dx = (a.position.x + a.size.width / 2.0) - (b.position.x + b.size.width / 2.0);
+    let dy = (a.position.y + a.size.height / 2.0) - (b.position.y + b.size.height / 2.0);
+    (dx * dx + dy * dy).sqrt()
+}
+
+impl Canvas {
+    /// Exports the canvas as a Mermaid diagram. Every mode, Flowchart
+    /// included, writes elements in draw order (`z_index`, ties in creation
+    /// order), so raising or lowering an element moves its line in the output
+    /// while repeated exports of an unchanged canvas stay identical.
+    pub fn export_to_mermaid(&self) -> Result<String> {
+        match self.mode {
+            CanvasMode::Flowchart => self.export_flowchart_to_mermaid(),
+            CanvasMode::SystemDesign => self.export_system_design_to_mermaid(),
+            CanvasMode::Wireframe => self.export_wireframe_to_mermaid(),
+            CanvasMode::Freeform => self.export_freeform_to_mermaid(),
+        }
+    }
+    
+    fn export_flowchart_to_mermaid(&self) -> Result<String> {
+        let mut mermaid = String::from("flowchart TD\n");
+        
+        for element in self.elements_in_draw_order() {
+            match element.element_type {
+                ElementType::FlowchartNode => {
+                    let label = element
+                        .get_property("label")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("Node");
+                    mermaid.push_str(&format!("    {}[\"{}\"]\n", mermaid_id(element.id), escape_label(label)));
+                }
+                ElementType::FlowchartEdge => {
+                    if let Some((from, to)) = edge_endpoints(element) {
+                        mermaid.push_str(&link_line(from, to, element));
+                    }
+                }
+                _ => {}
+            }
+        }
+        
+        Ok(mermaid)
+    }
+    
+    fn export_system_design_to_mermaid(&self) -> Result<String> {
+        let mut mermaid = String::from("graph TB\n");
+        
+        for element in self.elements_in_draw_order() {
+            if let Some(label) = element.get_property("label").and_then(|v| v.as_str()) {
+                mermaid.push_str(&format!("    {}[\"{}\"]\n", mermaid_id(element.id), escape_label(label)));
+            }
+        }
+        
+        Ok(mermaid)
+    }
+    
+    /// Boxes become nodes, and boxes that contain others become subgraphs
+    /// around them.
+    fn export_wireframe_to_mermaid(&self) -> Result<String> {
+        let mut mermaid = String::from("flowchart TB\n");
+        mermaid.push_str("    %% Wireframe export: only labels and nesting are kept, not positions, sizes or styling\n");
+        
+        let children = self.wireframe_tree();
+        for element in children.get(&None).into_iter().flatten() {
+            Self::write_wireframe_node(element, &children, 1, &mut mermaid);
+        }
+        self.write_explicit_links(&mut mermaid);
+        
+        Ok(mermaid)
+    }
+    
+    /// The boxes of the canvas keyed by the id of the smallest box containing
+    /// them, with top-level boxes under `None`. Edges are left out.
+    pub(crate) fn wireframe_tree(&self) -> HashMap<Option<Uuid>, Vec<&CanvasElement>> {
+        let elements: Vec<&CanvasElement> = self
+            .elements_in_draw_order()
+            .into_iter()
+            .filter(|element| !is_edge(element))
+            .collect();
+        
+        let mut children: HashMap<Option<Uuid>, Vec<&CanvasElement>> = HashMap::new();
+        for element in &elements {
+            let parent = elements
+                .iter()
+                .filter(|candidate| contains(candidate, element))
+                .min_by(|a, b| {
+                    let area = |e: &CanvasElement| e.size.width * e.size.height;
+                    area(a).total_cmp(&area(b))
+                })
+                .map(|parent| parent.id);
+            children.entry(parent).or_default().push(element);
+        }
+        children
+    }
+    
+    fn write_wireframe_node(
+        element: &CanvasElement,
+        children: &HashMap<Option<Uuid>, Vec<&CanvasElement>>,
+        depth: usize,
+        mermaid: &mut String,
+    ) {
+        let indent = "    ".repeat(depth);
+        let id = mermaid_id(element.id);
+        let label = escape_label(&label_of(element));
+        
+        match children.get(&Some(element.id)) {
+            Some(nested) => {
+                mermaid.push_str(&format!("{}subgraph {} [\"{}\"]\n", indent, id, label));
+                for child in nested {
+                    Self::write_wireframe_node(child, children, depth + 1, mermaid);
+                }
+                mermaid.push_str(&format!("{}end\n", indent));
+            }
+            None => mermaid.push_str(&format!("{}{}[\"{}\"]\n", indent, id, label)),
+        }
+    }
+    
+    /// Every element becomes a node. Edges come from `from`/`to` properties;
+    /// an element with none is joined to its nearest neighbour by a dotted
+    /// link if that neighbour is close enough.
+    fn export_freeform_to_mermaid(&self) -> Result<String> {
+        let mut mermaid = String::from("flowchart LR\n");
+        mermaid.push_str("    %% Freeform export is best effort: dotted links only mean elements were placed near each other\n");
+        
+        let nodes: Vec<&CanvasElement> = self
+            .elements_in_draw_order()
+            .into_iter()
+            .filter(|element| !is_edge(element))
+            .collect();
+        for node in &nodes {
+            mermaid.push_str(&format!("    {}[\"{}\"]\n", mermaid_id(node.id), escape_label(&label_of(node))));
+        }
+        
+        let linked = self.write_explicit_links(&mut mermaid);
+        
+        // Ordered pairs keep the output stable and list each link once
+        let mut proximity_links = BTreeSet::new();
+        for node in nodes.iter().filter(|node| !linked.contains(&node.id)) {
+            let nearest = nodes
+                .iter()
+                .filter(|other| other.id != node.id)
+                .map(|other| (distance(node, other), other))
+                .filter(|(distance, _)| *distance <= PROXIMITY_LINK_DISTANCE)
+                .min_by(|a, b| a.0.total_cmp(&b.0));
+            if let Some((_, other)) = nearest {
+                let pair = if node.created_at <= other.created_at { (node, other) } else { (other, node) };
+                proximity_links.insert(((pair.0.created_at, pair.0.id), (pair.1.created_at, pair.1.id)));
+            }
+        }
+        for ((_, a), (_, b)) in proximity_links {
+            mermaid.push_str(&format!("    {} -.- {}\n", mermaid_id(a), mermaid_id(b)));
+        }
+        
+        Ok(mermaid)
+    }
+    
+    /// Writes a link for each edge whose ends are both on the canvas and
+    /// returns the ids of the elements linked.
+    fn write_explicit_links(&self, mermaid: &mut String) -> BTreeSet<Uuid> {
+        let mut linked = BTreeSet::new();
+        
+        for element in self.elements_in_draw_order() {
+            let Some((from, to)) = edge_endpoints(element) else {
+                continue;
+            };
+            if !self.elements.contains_key(&from) || !self.elements.contains_key(&to) {
+                continue;
+            }
+            
+            mermaid.push_str(&link_line(from, to, element));
+            linked.insert(from);
+            linked.insert(to);
+        }
+        
+        linked
+    }
+}
+
+/// Width and height of nodes placed by `import_from_mermaid`.
+const IMPORT_NODE_SIZE: Size = Size { width: 160.0, height: 60.0 };
+/// Space between successive layers of imported nodes.
+const IMPORT_LAYER_GAP: f64 = 80.0;
+/// Space between imported nodes in the same layer.
+const IMPORT_NODE_GAP: f64 = 40.0;
+
+/// Characters that make up a link such as `-->`, `-.->` or `==>`.
+const LINK_CHARS: &str = "-=.<>";
+
+/// Statements that do not add nodes or edges and are skipped on import.
+const IGNORED_KEYWORDS: &[&str] = &["subgraph", "end", "direction", "classDef", "class", "style", "linkStyle", "click"];
+
+#[derive(Default)]
+struct ParsedFlowchart {
+    /// Node ids in order of first appearance, with their labels.
+    nodes: Vec<(String, Option<String>)>,
+    edges: Vec<(String, String, Option<String>)>,
+}
+
+impl ParsedFlowchart {
+    fn add_node(&mut self, id: &str, label: Option<String>) {
+        match self.nodes.iter_mut().find(|(existing, _)| existing == id) {
+            Some((_, existing_label)) => {
+                if label.is_some() {
+                    *existing_label = label;
+                }
+            }
+            None => self.nodes.push((id.to_string(), label)),
+        }
+    }
+    
+    /// Parses `A[Label] --> B --> C` style chains; returns false if nothing
+    /// could be read from the statement.
+    fn parse_statement(&mut self, statement: &str) -> bool {
+        let Some((id, label, mut rest)) = parse_node(statement) else {
+            return false;
+        };
+        self.add_node(id, label);
+        let mut previous = id;
+        
+        while !rest.trim().is_empty() {
+            let Some((link_label, after_link)) = parse_link(rest) else {
+                tracing::debug!("Ignoring unsupported Mermaid syntax: {}", rest.trim());
+                break;
+            };
+            let Some((id, label, after_node)) = parse_node(after_link) else {
+                break;
+            };
+            self.add_node(id, label);
+            self.edges.push((previous.to_string(), id.to_string(), link_label));
+            previous = id;
+            rest = after_node;
+        }
+        
+        true
+    }
+}
+
+/// Splits a line on the `;` separating statements, ignoring those inside
+/// quotes or shapes such as `#quot;` entities in labels.
+fn split_statements(line: &str) -> Vec<&str> {
+    let mut statements = Vec::new();
+    let (mut depth, mut quoted, mut start) = (0usize, false, 0);
+    
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '[' | '(' | '{' if !quoted => depth += 1,
+            ']' | ')' | '}' if !quoted => depth = depth.saturating_sub(1),
+            ';' if !quoted && depth == 0 => {
+                statements.push(&line[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    statements.push(&line[start..]);
+    statements
+}
+
+fn unescape_label(label: &str) -> String {
+    let label = label.trim();
+    let label = label
+        .strip_prefix('"')
+        .and_then(|label| label.strip_suffix('"'))
+        .unwrap_or(label);
+    label.replace("#quot;", "\"")
+}
+
+/// Reads a node id and an optional shape with a label, e.g. `A`, `A[Text]`,
+/// `B{"Choice"}` or `C((Circle))`.
+fn parse_node(input: &str) -> Option<(&str, Option<String>, &str)> {
+    let input = input.trim_start();
+    let id_end = input
+        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .unwrap_or(input.len());
+    if id_end == 0 {
+        return None;
+    }
+    let (id, rest) = input.split_at(id_end);
+    
+    let closer = |c: char| match c {
+        '[' | '>' => Some(']'),
+        '(' => Some(')'),
+        '{' => Some('}'),
+        '/' => Some('/'),
+        '\\' => Some('\\'),
+        _ => None,
+    };
+    let mut openers = rest.chars();
+    let Some(first) = openers.next().filter(|c| "[({>".contains(*c)) else {
+        return Some((id, None, rest));
+    };
+    let second = openers.next().filter(|c| first != '>' && "[({/\\".contains(*c));
+    let opener_len = 1 + second.map_or(0, |_| 1);
+    let closing: String = second.into_iter().chain([first]).filter_map(closer).collect();
+    
+    let body = &rest[opener_len..];
+    let label_end = if body.trim_start().starts_with('"') {
+        let quote = body.find('"')?;
+        quote + 1 + body[quote + 1..].find('"')? + 1
+    } else {
+        0
+    };
+    let close = label_end + body[label_end..].find(closing.as_str())?;
+    
+    Some((id, Some(unescape_label(&body[..close])), &body[close + closing.len()..]))
+}
+
+/// Reads a link such as `-->`, `---`, `-.->`, `==>`, `-->|text|` or
+/// `-- text -->`, returning its label.
+fn parse_link(input: &str) -> Option<(Option<String>, &str)> {
+    let input = input.trim_start();
+    let arrow_len = input.find(|c: char| !LINK_CHARS.contains(c)).unwrap_or(input.len());
+    if arrow_len < 2 {
+        return None;
+    }
+    let (arrow, mut rest) = input.split_at(arrow_len);
+    let mut label = None;
+    
+    if matches!(arrow, "--" | "==" | "-.") && !rest.trim_start().starts_with('|') {
+        // `-- text -->`: the label runs up to the rest of the arrow
+        let bytes = rest.as_bytes();
+        let end = (0..bytes.len().saturating_sub(1)).find(|&i| {
+            LINK_CHARS.contains(bytes[i] as char) && LINK_CHARS.contains(bytes[i + 1] as char)
+        })?;
+        label = Some(unescape_label(&rest[..end]));
+        let after = &rest[end..];
+        rest = &after[after.find(|c: char| !LINK_CHARS.contains(c)).unwrap_or(after.len())..];
+    }
+    
+    if let Some(piped) = rest.trim_start().strip_prefix('|') {
+        // A quoted label may itself contain `|`
+        let label_end = if piped.trim_start().starts_with('"') {
+            let quote = piped.find('"')?;
+            quote + 1 + piped[quote + 1..].find('"')? + 1
+        } else {
+            0
+        };
+        let end = label_end + piped[label_end..].find('|')?;
+        label = Some(unescape_label(&piped[..end]));
+        rest = &piped[end + 1..];
+    }
+    
+    Some((label.filter(|label| !label.is_empty()), rest))
+}
+
+/// Puts each node one layer below the deepest node linking to it. Links that
+/// close a cycle are ignored so looping flowcharts still lay out compactly.
+fn assign_layers(node_count: usize, edges: &[(usize, usize)]) -> Vec<usize> {
+    let mut outgoing = vec![Vec::new(); node_count];
+    for (edge, &(from, _)) in edges.iter().enumerate() {
+        outgoing[from].push(edge);
+    }
+    
+    // Depth-first search from each node in order of appearance, marking links
+    // back to a node still on the stack
+    let mut state = vec![0u8; node_count]; // 0 unvisited, 1 on stack, 2 done
+    let mut back_edges = vec![false; edges.len()];
+    for root in 0..node_count {
+        if state[root] != 0 {
+            continue;
+        }
+        state[root] = 1;
+        let mut stack = vec![(root, 0usize)];
+        while let Some((node, next)) = stack.last_mut() {
+            let node = *node;
+            match outgoing[node].get(*next) {
+                Some(&edge) => {
+                    *next += 1;
+                    let to = edges[edge].1;
+                    match state[to] {
+                        0 => {
+                            state[to] = 1;
+                            stack.push((to, 0));
+                        }
+                        1 => back_edges[edge] = true,
+                        _ => {}
+                    }
+                }
+                None => {
+                    state[node] = 2;
+                    stack.pop();
+                }
+            }
+        }
+    }
+    
+    let mut layers = vec![0usize; node_count];
+    for _ in 0..node_count {
+        let mut changed = false;
+        for (edge, &(from, to)) in edges.iter().enumerate() {
+            if !back_edges[edge] && layers[to] < layers[from] + 1 {
+                layers[to] = layers[from] + 1;
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+    layers
+}
+
+impl Canvas {
+    /// Builds a flowchart canvas from a Mermaid `flowchart`/`graph`
+    /// definition. Node labels and links (with their labels) are kept; other
+    /// statements such as subgraphs and styling are skipped. Nodes are laid
+    /// out in layers following the links, in the diagram's direction. The
+    /// whole import is a single undo step.
+    pub fn import_from_mermaid(src: &str) -> Result<Self> {
+        let mut statements = src
+            .lines()
+            .flat_map(split_statements)
+            .map(str::trim)
+            .filter(|statement| !statement.is_empty() && !statement.starts_with("%%"));
+        
+        let header = statements
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("Mermaid source is empty"))?;
+        let mut header_words = header.split_whitespace();
+        if !matches!(header_words.next(), Some("flowchart" | "graph")) {
+            return Err(anyhow::anyhow!("Only Mermaid flowchart and graph diagrams can be imported"));
+        }
+        let direction = header_words.next().unwrap_or("TD");
+        
+        let mut flowchart = ParsedFlowchart::default();
+        for statement in statements {
+            let keyword = statement.split_whitespace().next().unwrap_or_default();
+            if IGNORED_KEYWORDS.contains(&keyword) {
+                continue;
+            }
+            if !flowchart.parse_statement(statement) {
+                tracing::debug!("Skipping Mermaid statement: {}", statement);
+            }
+        }
+        if flowchart.nodes.is_empty() {
+            return Err(anyhow::anyhow!("No flowchart nodes found in Mermaid source"));
+        }
+        
+        let index: HashMap<&str, usize> = flowchart
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(i, (id, _))| (id.as_str(), i))
+            .collect();
+        let edges: Vec<(usize, usize)> = flowchart
+            .edges
+            .iter()
+            .map(|(from, to, _)| (index[from.as_str()], index[to.as_str()]))
+            .collect();
+        let layers = assign_layers(flowchart.nodes.len(), &edges);
+        
+        let horizontal = matches!(direction, "LR" | "RL");
+        let reversed = matches!(direction, "RL" | "BT");
+        let last_layer = layers.iter().copied().max().unwrap_or(0);
+        let (main_size, cross_size) = if horizontal {
+            (IMPORT_NODE_SIZE.width, IMPORT_NODE_SIZE.height)
+        } else {
+            (IMPORT_NODE_SIZE.height, IMPORT_NODE_SIZE.width)
+        };
+        
+        let mut canvas = Canvas::new("Mermaid import".to_string(), CanvasMode::Flowchart);
+        canvas.begin_undo_group();
+        let mut element_ids = HashMap::new();
+        let mut layer_counts = vec![0usize; last_layer + 1];
+        for ((id, label), layer) in flowchart.nodes.iter().zip(&layers) {
+            let slot = layer_counts[*layer];
+            layer_counts[*layer] += 1;
+            
+            let layer = if reversed { last_layer - layer } else { *layer };
+            let main = layer as f64 * (main_size + IMPORT_LAYER_GAP);
+            let cross = slot as f64 * (cross_size + IMPORT_NODE_GAP);
+            let position = if horizontal {
+                Point { x: main, y: cross }
+            } else {
+                Point { x: cross, y: main }
+            };
+            
+            let mut node = CanvasElement::new(ElementType::FlowchartNode, position, IMPORT_NODE_SIZE.clone());
+            node.set_property("label".to_string(), label.clone().unwrap_or_else(|| id.clone()).into());
+            element_ids.insert(id.as_str(), node.id);
+            canvas.add_element(node);
+        }
+        
+        for (from, to, label) in &flowchart.edges {
+            let (from, to) = (element_ids[from.as_str()], element_ids[to.as_str()]);
+            let position = canvas.elements[&from].position.clone();
+            let mut edge = CanvasElement::new(ElementType::FlowchartEdge, position, Size { width: 0.0, height: 0.0 });
+            edge.set_property("from".to_string(), from.to_string().into());
+            edge.set_property("to".to_string(), to.to_string().into());
+            if let Some(label) = label {
+                edge.set_property("label".to_string(), label.clone().into());
+            }
+            canvas.add_element(edge);
+        }
+        canvas.end_undo_group();
+        
+        Ok(canvas)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn node_by_label<'a>(canvas: &'a Canvas, label: &str) -> &'a CanvasElement {
+        canvas
+            .elements
+            .values()
+            .find(|element| {
+                element.element_type == ElementType::FlowchartNode
+                    && element.get_property("label").and_then(|v| v.as_str()) == Some(label)
+            })
+            .unwrap_or_else(|| panic!("no node labelled {}", label))
+    }
+    
+    /// Each edge as (from label, to label, edge label), sorted.
+    fn edges(canvas: &Canvas) -> Vec<(String, String, Option<String>)> {
+        let label = |id: Uuid| label_of(&canvas.elements[&id]);
+        let mut edges: Vec<_> = canvas
+            .elements
+            .values()
+            .filter(|element| element.element_type == ElementType::FlowchartEdge)
+            .map(|edge| {
+                let (from, to) = edge_endpoints(edge).unwrap();
+                let edge_label = edge.get_property("label").and_then(|v| v.as_str()).map(str::to_string);
+                (label(from), label(to), edge_label)
+            })
+            .collect();
+        edges.sort();
+        edges
+    }
+    
+    fn edge(from: &str, to: &str, label: Option<&str>) -> (String, String, Option<String>) {
+        (from.to_string(), to.to_string(), label.map(str::to_string))
+    }
+    
+    fn boxed(canvas: &mut Canvas, label: &str, x: f64, y: f64, width: f64, height: f64) -> Uuid {
+        let mut element = CanvasElement::new(ElementType::Rectangle, Point { x, y }, Size { width, height });
+        element.set_property("label".to_string(), label.into());
+        let id = element.id;
+        canvas.add_element(element);
+        id
+    }
+    
+    #[test]
+    fn repeated_exports_are_identical() {
+        for mode in [CanvasMode::Wireframe, CanvasMode::Freeform] {
+            let mut canvas = Canvas::new("Stable".to_string(), mode);
+            boxed(&mut canvas, "Page", 0.0, 0.0, 400.0, 400.0);
+            boxed(&mut canvas, "Header", 10.0, 10.0, 380.0, 50.0);
+            boxed(&mut canvas, "Footer", 10.0, 340.0, 380.0, 50.0);
+            boxed(&mut canvas, "Aside", 1000.0, 0.0, 100.0, 100.0);
+            
+            let first = canvas.export_to_mermaid().unwrap();
+            for _ in 0..5 {
+                assert_eq!(canvas.export_to_mermaid().unwrap(), first);
+            }
+        }
+    }
+    
+    #[test]
+    fn wireframe_boxes_nest_in_the_smallest_box_containing_them() {
+        let mut canvas = Canvas::new("Wireframe".to_string(), CanvasMode::Wireframe);
+        let page = boxed(&mut canvas, "Page", 0.0, 0.0, 400.0, 400.0);
+        let form = boxed(&mut canvas, "Form", 20.0, 20.0, 200.0, 200.0);
+        let button = boxed(&mut canvas, "Button", 40.0, 40.0, 50.0, 20.0);
+        let aside = boxed(&mut canvas, "Aside", 1000.0, 0.0, 100.0, 100.0);
+        
+        let children = canvas.wireframe_tree();
+        let ids = |parent: Option<Uuid>| -> Vec<Uuid> {
+            children.get(&parent).into_iter().flatten().map(|element| element.id).collect()
+        };
+        assert_eq!(ids(None), vec![page, aside]);
+        assert_eq!(ids(Some(page)), vec![form]);
+        assert_eq!(ids(Some(form)), vec![button]);
+        
+        let mermaid = canvas.export_to_mermaid().unwrap();
+        let expected = format!(
+            "    subgraph {} [\"Page\"]\n        subgraph {} [\"Form\"]\n            {}[\"Button\"]\n        end\n    end\n    {}[\"Aside\"]\n",
+            mermaid_id(page),
+            mermaid_id(form),
+            mermaid_id(button),
+            mermaid_id(aside),
+        );
+        assert!(mermaid.contains(&expected), "{}", mermaid);
+    }
+    
+    #[test]
+    fn freeform_links_only_nearby_unlinked_elements() {
+        let mut canvas = Canvas::new("Freeform".to_string(), CanvasMode::Freeform);
+        let a = boxed(&mut canvas, "A", 0.0, 0.0, 50.0, 50.0);
+        let b = boxed(&mut canvas, "B", 100.0, 0.0, 50.0, 50.0);
+        let far = boxed(&mut canvas, "Far", 1000.0, 1000.0, 50.0, 50.0);
+        let c = boxed(&mut canvas, "C", 2000.0, 0.0, 50.0, 50.0);
+        let d = boxed(&mut canvas, "D", 2100.0, 0.0, 50.0, 50.0);
+        let mut link = CanvasElement::new(ElementType::FlowchartEdge, Point { x: 0.0, y: 0.0 }, Size { width: 0.0, height: 0.0 });
+        link.set_property("from".to_string(), c.to_string().into());
+        link.set_property("to".to_string(), d.to_string().into());
+        canvas.add_element(link);
+        
+        let mermaid = canvas.export_to_mermaid().unwrap();
+        let dotted: Vec<&str> = mermaid.lines().filter(|line| line.contains("-.-")).collect();
+        assert_eq!(dotted, vec![format!("    {} -.- {}", mermaid_id(a), mermaid_id(b))]);
+        assert!(mermaid.contains(&format!("    {} --> {}\n", mermaid_id(c), mermaid_id(d))));
+        assert!(!mermaid.contains(&format!("{} -", mermaid_id(far))));
+        assert!(!mermaid.contains(&format!("- {}", mermaid_id(far))));
+    }
+    
+    #[test]
+    fn imports_each_link_form() {
+        let canvas = Canvas::import_from_mermaid(
+            "flowchart TD\n    A[Start] --> B\n    B -- yes --> C{\"Choice\"}\n    C -->|done| D((End))\n",
+        )
+        .unwrap();
+        
+        assert!(matches!(canvas.mode, CanvasMode::Flowchart));
+        assert_eq!(
+            edges(&canvas),
+            vec![
+                edge("B", "Choice", Some("yes")),
+                edge("Choice", "End", Some("done")),
+                edge("Start", "B", None),
+            ]
+        );
+    }
+    
+    #[test]
+    fn piped_labels_may_contain_pipes_when_quoted() {
+        let (label, rest) = parse_link(r#"-->|"a | b"| B"#).unwrap();
+        assert_eq!(label.as_deref(), Some("a | b"));
+        assert_eq!(rest, " B");
+    }
+    
+    #[test]
+    fn cycles_are_laid_out_in_order_of_appearance() {
+        assert_eq!(assign_layers(3, &[(0, 1), (1, 2), (2, 0)]), vec![0, 1, 2]);
+        
+        let canvas = Canvas::import_from_mermaid("graph TD\n    A --> B --> C --> A\n").unwrap();
+        let step = IMPORT_NODE_SIZE.height + IMPORT_LAYER_GAP;
+        assert_eq!(node_by_label(&canvas, "A").position.y, 0.0);
+        assert_eq!(node_by_label(&canvas, "B").position.y, step);
+        assert_eq!(node_by_label(&canvas, "C").position.y, 2.0 * step);
+        assert_eq!(edges(&canvas).len(), 3);
+    }
+    
+    #[test]
+    fn left_to_right_layers_run_along_x() {
+        let canvas = Canvas::import_from_mermaid("flowchart LR\n    A --> B\n    A --> C\n").unwrap();
+        let (a, b, c) = (node_by_label(&canvas, "A"), node_by_label(&canvas, "B"), node_by_label(&canvas, "C"));
+        
+        assert_eq!((a.position.x, a.position.y), (0.0, 0.0));
+        assert_eq!(b.position.x, IMPORT_NODE_SIZE.width + IMPORT_LAYER_GAP);
+        assert_eq!(b.position.y, 0.0);
+        assert_eq!(c.position.x, b.position.x);
+        assert_eq!(c.position.y, IMPORT_NODE_SIZE.height + IMPORT_NODE_GAP);
+    }
+    
+    #[test]
+    fn bottom_to_top_layers_are_reversed() {
+        let canvas = Canvas::import_from_mermaid("flowchart BT\n    A --> B\n").unwrap();
+        
+        assert_eq!(node_by_label(&canvas, "A").position.y, IMPORT_NODE_SIZE.height + IMPORT_LAYER_GAP);
+        assert_eq!(node_by_label(&canvas, "B").position.y, 0.0);
+    }
+    
+    #[test]
+    fn exported_flowcharts_import_back() {
+        let mut canvas = Canvas::new("Round trip".to_string(), CanvasMode::Flowchart);
+        let add_node = |canvas: &mut Canvas, label: &str| {
+            let mut node = CanvasElement::new(ElementType::FlowchartNode, Point { x: 0.0, y: 0.0 }, IMPORT_NODE_SIZE.clone());
+            node.set_property("label".to_string(), label.into());
+            let id = node.id;
+            canvas.add_element(node);
+            id
+        };
+        let start = add_node(&mut canvas, "Say \"hi\"");
+        let choice = add_node(&mut canvas, "a | b; c");
+        let end = add_node(&mut canvas, "End");
+        for (from, to, label) in [(start, choice, Some("x | \"y\"")), (choice, end, None)] {
+            let mut link = CanvasElement::new(ElementType::FlowchartEdge, Point { x: 0.0, y: 0.0 }, Size { width: 0.0, height: 0.0 });
+            link.set_property("from".to_string(), from.to_string().into());
+            link.set_property("to".to_string(), to.to_string().into());
+            if let Some(label) = label {
+                link.set_property("label".to_string(), label.into());
+            }
+            canvas.add_element(link);
+        }
+        
+        let imported = Canvas::import_from_mermaid(&canvas.export_to_mermaid().unwrap()).unwrap();
+        
+        assert_eq!(edges(&imported), edges(&canvas));
+        assert_eq!(
+            imported.elements.values().filter(|e| e.element_type == ElementType::FlowchartNode).count(),
+            3
+        );
+    }
+}
@@ -0,0 +1,183 @@
+use anyhow::Result;
+use ignore::WalkState;
+use nucleo_matcher::pattern::{CaseMatching, Normalization, Pattern};
+use nucleo_matcher::{Matcher, Utf32String};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::RwLock;
+
+use crate::FileTreeFilter;
+
+pub const DEFAULT_MAX_FILE_MATCHES: usize = 50;
+
+/// Files checked between looks at the cancellation flag.
+const CANCEL_CHECK_INTERVAL: usize = 1024;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileMatch {
+    pub path: PathBuf,
+    /// Path relative to the workspace root, with `/` separators.
+    pub relative_path: String,
+    pub score: u32,
+    /// Character positions in `relative_path` the query matched, for highlighting.
+    pub indices: Vec<u32>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FileMatches {
+    /// Best match first.
+    pub matches: Vec<FileMatch>,
+    pub cancelled: bool,
+}
+
+/// Every file under a workspace root that the file tree would show, kept
+/// ready for matching.
+pub struct FileIndex {
+    root: PathBuf,
+    files: Vec<Utf32String>,
+}
+
+impl FileIndex {
+    /// Walks `root` on all cores, giving up with `None` once `cancelled` is
+    /// set. Blocking; call from `spawn_blocking`.
+    pub fn build(root: &Path, filter: &FileTreeFilter, cancelled: &AtomicBool) -> Option<Self> {
+        let files = Mutex::new(Vec::new());
+        
+        filter.walker(root).build_parallel().run(|| {
+            Box::new(|entry| {
+                if cancelled.load(Ordering::Relaxed) {
+                    return WalkState::Quit;
+                }
+                let Ok(entry) = entry else {
+                    return WalkState::Continue;
+                };
+                if !entry.file_type().is_some_and(|file_type| file_type.is_file()) {
+                    return WalkState::Continue;
+                }
+                
+                if let Ok(relative) = entry.path().strip_prefix(root) {
+                    let relative: Vec<_> = relative.components().map(|part| part.as_os_str().to_string_lossy()).collect();
+                    files.lock().unwrap().push(relative.join("/"));
+                }
+                WalkState::Continue
+            })
+        });
+        
+        if cancelled.load(Ordering::Relaxed) {
+            return None;
+        }
+        
+        let mut files = files.into_inner().unwrap();
+        files.sort();
+        
+        Some(Self {
+            root: root.to_path_buf(),
+            files: files.into_iter().map(Utf32String::from).collect(),
+        })
+    }
+    
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+    
+    /// Ranks the indexed files against `query`, best first. Higher scores win,
+    /// then shorter paths. An empty query matches every file.
+    /// Blocking; call from `spawn_blocking`.
+    pub fn find(&self, query: &str, limit: usize, cancelled: &AtomicBool) -> FileMatches {
+        let mut matcher = Matcher::new(nucleo_matcher::Config::DEFAULT.match_paths());
+        let pattern = Pattern::parse(query, CaseMatching::Smart, Normalization::Smart);
+        
+        let mut scored = Vec::new();
+        for (position, file) in self.files.iter().enumerate() {
+            if position % CANCEL_CHECK_INTERVAL == 0 && cancelled.load(Ordering::Relaxed) {
+                return FileMatches {
+                    matches: Vec::new(),
+                    cancelled: true,
+                };
+            }
+            if let Some(score) = pattern.score(file.slice(..), &mut matcher) {
+                scored.push((score, position));
+            }
+        }
+        scored.sort_by(|(a_score, a), (b_score, b)| {
+            b_score
+                .cmp(a_score)
+                .then_with(|| self.files[*a].len().cmp(&self.files[*b].len()))
+                .then_with(|| a.cmp(b))
+        });
+        scored.truncate(limit);
+        
+        let matches = scored
+            .into_iter()
+            .map(|(score, position)| {
+                let file = &self.files[position];
+                let mut indices = Vec::new();
+                pattern.indices(file.slice(..), &mut matcher, &mut indices);
+                indices.sort_unstable();
+                indices.dedup();
+                
+                let relative_path = file.to_string();
+                FileMatch {
+                    path: self.root.join(&relative_path),
+                    relative_path,
+                    score,
+                    indices,
+                }
+            })
+            .collect();
+        
+        FileMatches {
+            matches,
+            cancelled: false,
+        }
+    }
+}
+
+/// The file index for the current workspace root, built on first use and
+/// dropped whenever files are created, deleted or renamed.
+#[derive(Default)]
+pub struct FileIndexCache {
+    index: RwLock<Option<Arc<FileIndex>>>,
+    /// Bumped on every invalidation so a build that started before it is
+    /// not cached.
+    generation: AtomicU64,
+}
+
+impl FileIndexCache {
+    pub async fn invalidate(&self) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        *self.index.write().await = None;
+    }
+    
+    /// The cached index for `root`, building it if there is none. `None`
+    /// when `cancelled` is set before the build finishes; nothing is cached
+    /// then.
+    pub async fn get_or_build(
+        &self,
+        root: PathBuf,
+        filter: FileTreeFilter,
+        cancelled: Arc<AtomicBool>,
+    ) -> Result<Option<Arc<FileIndex>>> {
+        if let Some(index) = self.index.read().await.as_ref().filter(|index| index.root == root) {
+            return Ok(Some(index.clone()));
+        }
+        
+        let generation = self.generation.load(Ordering::SeqCst);
+        let built = tokio::task::spawn_blocking(move || FileIndex::build(&root, &filter, &cancelled)).await?;
+        let Some(index) = built.map(Arc::new) else {
+            return Ok(None);
+        };
+        
+        let mut cached = self.index.write().await;
+        if self.generation.load(Ordering::SeqCst) == generation {
+            *cached = Some(index.clone());
+        }
+        Ok(Some(index))
+    }
+}
@@ -1,13 +1,53 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
 use uuid::Uuid;
+use code_furnace_utils::{Config, ResourceError};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
 
+pub mod finder;
 pub mod lsp;
-pub use lsp::{LSPManager, LSPDiagnostic, LSPCompletionItem, LSPHover};
+pub mod recent;
+pub mod search;
+pub mod watcher;
+pub use finder::{FileMatch, FileMatches};
+pub use lsp::{LSPManager, LSPDiagnostic, LSPCompletionItem, LSPHover, LSPLocation, LSPTextEdit, LSPDocumentSymbol, LSPSignatureHelp, LSPCodeAction, LSPRange, LSPWorkspaceSymbol};
+pub use recent::RecentFile;
+pub use search::{FileReplacement, PlannedEdit, ReplaceOptions, SearchMatch, SearchOptions, SearchResults};
+pub use watcher::FileWatcher;
+
+/// Entries hidden from the file tree regardless of `.gitignore`.
+const ALWAYS_IGNORED: &[&str] = &[".git", ".DS_Store"];
+
+const FILE_WATCH_DEBOUNCE: Duration = Duration::from_millis(200);
+
+fn content_hash(content: &str) -> u64 {
+    use std::hash::{Hash, Hasher};
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Bytes inspected when deciding whether a file is binary.
+const BINARY_SNIFF_LEN: usize = 8192;
+
+#[derive(Debug, thiserror::Error)]
+pub enum EditorError {
+    #[error("Cannot open binary file {path} ({file_type})")]
+    BinaryFile { path: PathBuf, file_type: String },
+    #[error("Buffer is read-only: {0}")]
+    ReadOnly(PathBuf),
+    #[error("Language server did not answer {method} within {} seconds", .timeout.as_secs())]
+    LspTimeout { method: String, timeout: Duration },
+    #[error("Language server failed {method}: {message}")]
+    LspRequestFailed { method: String, message: String },
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileBuffer {
@@ -18,23 +58,53 @@ pub struct FileBuffer {
     pub modified: bool,
     pub cursor_position: CursorPosition,
     pub last_modified: chrono::DateTime<chrono::Utc>,
+    /// Set for files too large to edit safely or that failed to decode cleanly.
+    #[serde(default)]
+    pub read_only: bool,
+    /// Line ending written back on save, detected from the file when opened.
+    #[serde(default)]
+    pub line_ending: LineEnding,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct CursorPosition {
-    pub line: usize,
-    pub column: usize,
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    CrLf,
 }
 
-impl Default for CursorPosition {
-    fn default() -> Self {
-        Self { line: 0, column: 0 }
+impl LineEnding {
+    /// Picks whichever ending occurs most often, defaulting to LF.
+    pub fn detect(content: &str) -> Self {
+        let crlf = content.matches("\r\n").count();
+        let lf = content.matches('\n').count() - crlf;
+        
+        if crlf > lf {
+            LineEnding::CrLf
+        } else {
+            LineEnding::Lf
+        }
     }
+    
+    pub fn apply(self, content: &str) -> String {
+        let normalized = content.replace("\r\n", "\n");
+        match self {
+            LineEnding::Lf => normalized,
+            LineEnding::CrLf => normalized.replace('\n', "\r\n"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CursorPosition {
+    pub line: usize,
+    pub column: usize,
 }
 
 impl FileBuffer {
     pub fn new(path: PathBuf, content: String) -> Self {
         let language = Self::detect_language(&path);
+        let line_ending = LineEnding::detect(&content);
         
         Self {
             id: Uuid::new_v4(),
@@ -44,15 +114,65 @@ impl FileBuffer {
             modified: false,
             cursor_position: CursorPosition::default(),
             last_modified: chrono::Utc::now(),
+            read_only: false,
+            line_ending,
         }
     }
     
-    pub fn from_file(path: PathBuf) -> Result<Self> {
-        let content = std::fs::read_to_string(&path)?;
-        Ok(Self::new(path, content))
+    /// Loads a text file, rejecting binary content with `EditorError::BinaryFile`.
+    /// Files over `large_file_threshold` bytes, or with a few undecodable bytes,
+    /// are opened read-only so saving can never rewrite them lossily.
+    pub fn from_file(path: PathBuf, large_file_threshold: u64) -> Result<Self> {
+        let bytes = std::fs::read(&path)?;
+        
+        if let Some(file_type) = Self::sniff_binary(&path, &bytes) {
+            return Err(EditorError::BinaryFile { path, file_type }.into());
+        }
+        
+        let too_large = bytes.len() as u64 > large_file_threshold;
+        let (content, lossy) = match String::from_utf8(bytes) {
+            Ok(content) => (content, false),
+            Err(e) => (String::from_utf8_lossy(e.as_bytes()).into_owned(), true),
+        };
+        
+        let mut buffer = Self::new(path, content);
+        buffer.read_only = too_large || lossy;
+        Ok(buffer)
     }
     
-    fn detect_language(path: &PathBuf) -> String {
+    /// Returns the detected type if the file looks binary: a known magic
+    /// number, any NUL byte, or more than 10% invalid UTF-8 in the sample.
+    fn sniff_binary(path: &Path, bytes: &[u8]) -> Option<String> {
+        let magic_types: [(&[u8], &str); 8] = [
+            (b"\x89PNG\r\n\x1a\n", "png"),
+            (b"\xff\xd8\xff", "jpeg"),
+            (b"GIF8", "gif"),
+            (b"%PDF", "pdf"),
+            (b"PK\x03\x04", "zip"),
+            (b"\x7fELF", "elf"),
+            (b"\x00asm", "wasm"),
+            (b"\x1f\x8b", "gzip"),
+        ];
+        if let Some((_, file_type)) = magic_types.iter().find(|(magic, _)| bytes.starts_with(magic)) {
+            return Some(file_type.to_string());
+        }
+        
+        let sample = &bytes[..bytes.len().min(BINARY_SNIFF_LEN)];
+        let looks_binary = sample.contains(&0) || {
+            let decoded = String::from_utf8_lossy(sample);
+            let invalid = decoded.chars().filter(|c| *c == char::REPLACEMENT_CHARACTER).count();
+            invalid * 10 > decoded.chars().count()
+        };
+        
+        if looks_binary {
+            let file_type = FileTreeNode::detect_file_type(path);
+            Some(if file_type == "text" { "binary".to_string() } else { file_type })
+        } else {
+            None
+        }
+    }
+    
+    fn detect_language(path: &Path) -> String {
         match path.extension().and_then(|ext| ext.to_str()) {
             Some("rs") => "rust".to_string(),
             Some("js") | Some("jsx") => "javascript".to_string(),
@@ -73,19 +193,124 @@ impl FileBuffer {
         self.last_modified = chrono::Utc::now();
     }
     
+    /// Content as it is written to disk, with the buffer's line ending applied.
+    pub fn disk_content(&self) -> String {
+        self.line_ending.apply(&self.content)
+    }
+    
+    /// Content with the on-save cleanups applied: trailing spaces and tabs
+    /// stripped from each line, and a newline added to the end of non-empty
+    /// content that lacks one. Line endings are left as they are.
+    pub fn normalized_content(&self, trim_trailing_whitespace: bool, insert_final_newline: bool) -> String {
+        let mut content = if trim_trailing_whitespace {
+            let lines: Vec<String> = self
+                .content
+                .split('\n')
+                .map(|line| {
+                    let (text, line_end) = match line.strip_suffix('\r') {
+                        Some(text) => (text, "\r"),
+                        None => (line, ""),
+                    };
+                    format!("{}{}", text.trim_end_matches([' ', '\t']), line_end)
+                })
+                .collect();
+            lines.join("\n")
+        } else {
+            self.content.clone()
+        };
+        
+        if insert_final_newline && !content.is_empty() && !content.ends_with('\n') {
+            content.push('\n');
+        }
+        content
+    }
+    
     pub fn save(&mut self) -> Result<()> {
-        std::fs::write(&self.path, &self.content)?;
+        if self.read_only {
+            return Err(EditorError::ReadOnly(self.path.clone()).into());
+        }
+        std::fs::write(&self.path, self.disk_content())?;
         self.modified = false;
         Ok(())
     }
 }
 
+/// Decides which entries are hidden from the file tree. An entry is hidden if
+/// any layer matches it: the always-ignored names, the user's
+/// `Config::ignore_patterns`, or `.gitignore` rules (the built-in list when
+/// `respect_gitignore` is off). No layer can re-show what another one hides.
+#[derive(Clone)]
+pub struct FileTreeFilter {
+    respect_gitignore: bool,
+    user_ignores: Gitignore,
+}
+
+impl FileTreeFilter {
+    pub fn new(root: &Path, config: &Config) -> Result<Self> {
+        let mut builder = GitignoreBuilder::new(root);
+        for pattern in &config.ignore_patterns {
+            builder.add_line(None, pattern)?;
+        }
+        
+        Ok(Self {
+            respect_gitignore: config.respect_gitignore,
+            user_ignores: builder.build()?,
+        })
+    }
+    
+    /// Walker over `root` that hides the same entries as the file tree. When
+    /// gitignore is respected, ignore files in `root`'s parents apply as well.
+    pub fn walker(&self, root: &Path) -> ignore::WalkBuilder {
+        let respect_gitignore = self.respect_gitignore;
+        let user_ignores = self.user_ignores.clone();
+        
+        let mut builder = ignore::WalkBuilder::new(root);
+        builder
+            .standard_filters(respect_gitignore)
+            .hidden(false)
+            .require_git(false)
+            .filter_entry(move |entry| {
+                if entry.depth() == 0 {
+                    return true;
+                }
+                
+                let name = entry.file_name().to_string_lossy();
+                if ALWAYS_IGNORED.contains(&name.as_ref()) {
+                    return false;
+                }
+                if !respect_gitignore && FileTreeNode::should_ignore(&name) {
+                    return false;
+                }
+                
+                let is_dir = entry.file_type().is_some_and(|file_type| file_type.is_dir());
+                !user_ignores.matched(entry.path(), is_dir).is_ignore()
+            });
+        builder
+    }
+    
+    /// Whether the tree shows `path` among its parent's entries. `path` must
+    /// exist; the ignore rules of every directory above it apply.
+    pub fn shows(&self, path: &Path) -> bool {
+        let Some(parent) = path.parent() else {
+            return true;
+        };
+        self.walker(parent)
+            .max_depth(Some(1))
+            .build()
+            .filter_map(|entry| entry.ok())
+            .any(|entry| entry.depth() == 1 && entry.path() == path)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileTreeNode {
     pub name: String,
     pub path: PathBuf,
     pub is_directory: bool,
     pub children: Vec<FileTreeNode>,
+    /// False for directories whose children have not been read yet.
+    #[serde(default)]
+    pub children_loaded: bool,
     pub expanded: bool,
     pub file_type: String,
     pub size: Option<u64>,
@@ -93,9 +318,17 @@ pub struct FileTreeNode {
 }
 
 impl FileTreeNode {
-    pub fn build_tree(root_path: PathBuf) -> Result<Self> {
-        let metadata = std::fs::metadata(&root_path)?;
-        let name = root_path
+    /// Builds a node with its immediate children only. Child directories are
+    /// left unloaded and are populated on demand with `load_children`.
+    pub fn build_tree(root_path: PathBuf, filter: &FileTreeFilter) -> Result<Self> {
+        let mut node = Self::from_path(root_path)?;
+        node.load_children(filter)?;
+        Ok(node)
+    }
+    
+    fn from_path(path: PathBuf) -> Result<Self> {
+        let metadata = std::fs::metadata(&path)?;
+        let name = path
             .file_name()
             .unwrap_or_default()
             .to_string_lossy()
@@ -104,7 +337,7 @@ impl FileTreeNode {
         let file_type = if metadata.is_dir() {
             "directory".to_string()
         } else {
-            Self::detect_file_type(&root_path)
+            Self::detect_file_type(&path)
         };
         
         let size = if metadata.is_file() {
@@ -118,54 +351,52 @@ impl FileTreeNode {
             .and_then(|time| {
                 time.duration_since(std::time::UNIX_EPOCH)
                     .ok()
-                    .map(|duration| chrono::DateTime::from_timestamp(duration.as_secs() as i64, 0))
-                    .flatten()
+                    .and_then(|duration| chrono::DateTime::from_timestamp(duration.as_secs() as i64, 0))
             });
         
-        let mut node = Self {
+        Ok(Self {
             name,
-            path: root_path.clone(),
+            path,
             is_directory: metadata.is_dir(),
             children: Vec::new(),
+            children_loaded: !metadata.is_dir(),
             expanded: false,
             file_type,
             size,
             modified,
-        };
+        })
+    }
+    
+    /// Reads one level of the directory into `children`.
+    pub fn load_children(&mut self, filter: &FileTreeFilter) -> Result<()> {
+        if !self.is_directory {
+            return Ok(());
+        }
         
-        if metadata.is_dir() {
-            let entries = std::fs::read_dir(&root_path)?;
-            for entry in entries {
-                let entry = entry?;
-                let child_path = entry.path();
-                
-                // Skip files based on ignore patterns
-                if let Some(name) = child_path.file_name() {
-                    let name_str = name.to_string_lossy();
-                    if Self::should_ignore(&name_str) {
-                        continue;
-                    }
-                }
-                
-                if let Ok(child_node) = Self::build_tree(child_path) {
-                    node.children.push(child_node);
-                }
+        self.children = filter
+            .walker(&self.path)
+            .max_depth(Some(1))
+            .build()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.depth() == 1)
+            .filter_map(|entry| Self::from_path(entry.into_path()).ok())
+            .collect();
+        
+        // Sort children: directories first, then files, both alphabetically
+        self.children.sort_by(|a, b| {
+            match (a.is_directory, b.is_directory) {
+                (true, false) => std::cmp::Ordering::Less,
+                (false, true) => std::cmp::Ordering::Greater,
+                _ => a.name.cmp(&b.name),
             }
-            
-            // Sort children: directories first, then files, both alphabetically
-            node.children.sort_by(|a, b| {
-                match (a.is_directory, b.is_directory) {
-                    (true, false) => std::cmp::Ordering::Less,
-                    (false, true) => std::cmp::Ordering::Greater,
-                    _ => a.name.cmp(&b.name),
-                }
-            });
-        }
+        });
         
-        Ok(node)
+        self.children_loaded = true;
+        
+        Ok(())
     }
     
-    fn detect_file_type(path: &PathBuf) -> String {
+    fn detect_file_type(path: &Path) -> String {
         match path.extension().and_then(|ext| ext.to_str()) {
             Some("rs") => "rust".to_string(),
             Some("js") | Some("jsx") => "javascript".to_string(),
@@ -272,23 +503,363 @@ pub struct EditorManager {
     event_bus: code_furnace_events::EventBus,
     lsp_manager: Arc<LSPManager>,
     document_versions: Arc<RwLock<HashMap<String, u64>>>,
+    config: Arc<RwLock<Config>>,
+    pending_auto_saves: Arc<RwLock<HashMap<Uuid, JoinHandle<()>>>>,
+    /// Held while a buffer is written to disk, so an auto-save that started
+    /// earlier can never overwrite a later save.
+    save_lock: Arc<tokio::sync::Mutex<()>>,
+    file_watcher: Arc<RwLock<Option<FileWatcher>>>,
+    /// Watches the workspace root so files added or removed outside the
+    /// editor drop the file index.
+    workspace_watcher: Arc<RwLock<Option<watcher::TreeWatcher>>>,
+    disk_hashes: Arc<RwLock<HashMap<Uuid, u64>>>,
+    active_searches: Arc<RwLock<HashMap<Uuid, Arc<AtomicBool>>>>,
+    file_index: Arc<finder::FileIndexCache>,
+    recent_files: Arc<recent::RecentFiles>,
+    recent_files_tracker: std::sync::OnceLock<JoinHandle<()>>,
 }
 
 impl EditorManager {
     pub fn new(event_bus: code_furnace_events::EventBus) -> Self {
+        Self::with_config(event_bus, Config::default())
+    }
+    
+    /// The recently opened list is restored from the app data directory, or
+    /// kept in memory only if that directory is unavailable.
+    pub fn with_config(event_bus: code_furnace_events::EventBus, config: Config) -> Self {
+        let data_dir = code_furnace_utils::paths::get_app_data_dir()
+            .map_err(|e| tracing::warn!("Recent files will not be saved: {}", e))
+            .ok();
+        Self::with_data_dir(event_bus, config, data_dir)
+    }
+    
+    /// Like `with_config`, but keeps the recently opened list in `data_dir`
+    /// instead of the app data directory, or in memory only without one.
+    pub fn with_data_dir(event_bus: code_furnace_events::EventBus, config: Config, data_dir: Option<PathBuf>) -> Self {
+        let recent_files_file = data_dir.map(|dir| dir.join(recent::RECENT_FILES_FILE));
+        
         Self {
             buffers: Arc::new(RwLock::new(HashMap::new())),
             active_buffer: Arc::new(RwLock::new(None)),
             workspace_root: Arc::new(RwLock::new(None)),
+            lsp_manager: Arc::new(LSPManager::with_overrides(&config.lsp_servers).with_event_bus(event_bus.clone())),
             event_bus,
-            lsp_manager: Arc::new(LSPManager::new()),
             document_versions: Arc::new(RwLock::new(HashMap::new())),
+            config: Arc::new(RwLock::new(config)),
+            pending_auto_saves: Arc::new(RwLock::new(HashMap::new())),
+            save_lock: Arc::new(tokio::sync::Mutex::new(())),
+            file_watcher: Arc::new(RwLock::new(None)),
+            workspace_watcher: Arc::new(RwLock::new(None)),
+            disk_hashes: Arc::new(RwLock::new(HashMap::new())),
+            active_searches: Arc::new(RwLock::new(HashMap::new())),
+            file_index: Arc::new(finder::FileIndexCache::default()),
+            recent_files: Arc::new(recent::RecentFiles::load(recent_files_file)),
+            recent_files_tracker: std::sync::OnceLock::new(),
+        }
+    }
+    
+    pub async fn update_config(&self, config: Config) {
+        let auto_save = config.auto_save;
+        let force_lf = config.force_lf_line_endings;
+        let lsp_servers = config.lsp_servers.clone();
+        *self.config.write().await = config;
+        // Ignore rules may have changed
+        self.file_index.invalidate().await;
+        
+        // Servers whose command changed were shut down; reopen their documents
+        // so the new servers start and know about them
+        let changed = self.lsp_manager.set_overrides(&lsp_servers).await;
+        if !changed.is_empty() {
+            let open: Vec<(PathBuf, String)> = self
+                .buffers
+                .read()
+                .await
+                .values()
+                .filter(|buffer| {
+                    buffer
+                        .path
+                        .extension()
+                        .and_then(|ext| ext.to_str())
+                        .and_then(|ext| self.lsp_manager.get_language_for_extension(ext))
+                        .is_some_and(|language| changed.contains(&language))
+                })
+                .map(|buffer| (buffer.path.clone(), buffer.content.clone()))
+                .collect();
+            for (path, content) in open {
+                self.announce_document(&path, content).await;
+            }
+        }
+        
+        if force_lf {
+            for buffer in self.buffers.write().await.values_mut() {
+                buffer.line_ending = LineEnding::Lf;
+            }
+        }
+        
+        if !auto_save {
+            let mut pending = self.pending_auto_saves.write().await;
+            for (_, handle) in pending.drain() {
+                handle.abort();
+            }
+        }
+    }
+    
+    /// Restarts the auto-save debounce timer for a buffer. Each call cancels the
+    /// previously scheduled save, so the buffer is written once edits go idle.
+    async fn schedule_auto_save(&self, buffer_id: Uuid) {
+        let delay = {
+            let config = self.config.read().await;
+            if !config.auto_save {
+                return;
+            }
+            Duration::from_millis(config.auto_save_delay_ms)
+        };
+        
+        let buffers = self.buffers.clone();
+        let pending_auto_saves = self.pending_auto_saves.clone();
+        let disk_hashes = self.disk_hashes.clone();
+        let save_lock = self.save_lock.clone();
+        let lsp_manager = self.lsp_manager.clone();
+        let event_bus = self.event_bus.clone();
+        
+        let mut pending = self.pending_auto_saves.write().await;
+        if let Some(previous) = pending.remove(&buffer_id) {
+            previous.abort();
+        }
+        
+        let handle = tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+            pending_auto_saves.write().await.remove(&buffer_id);
+            Self::auto_save(buffer_id, &buffers, &disk_hashes, &save_lock, &lsp_manager, &event_bus).await;
+        });
+        pending.insert(buffer_id, handle);
+    }
+    
+    /// Writes a modified buffer for auto-save, logging rather than returning
+    /// failures since no caller is waiting on them. The file is written from
+    /// a snapshot without holding the buffers lock; the buffer only counts as
+    /// saved if it was not edited in the meantime.
+    async fn auto_save(
+        buffer_id: Uuid,
+        buffers: &RwLock<HashMap<Uuid, FileBuffer>>,
+        disk_hashes: &RwLock<HashMap<Uuid, u64>>,
+        save_lock: &tokio::sync::Mutex<()>,
+        lsp_manager: &LSPManager,
+        event_bus: &code_furnace_events::EventBus,
+    ) {
+        let _saving = save_lock.lock().await;
+        let snapshot = buffers.read().await.get(&buffer_id).and_then(|buffer| {
+            (buffer.modified && !buffer.read_only)
+                .then(|| (buffer.path.clone(), buffer.content.clone(), buffer.disk_content()))
+        });
+        let Some((path, content, disk_content)) = snapshot else {
+            return;
+        };
+        
+        if let Err(e) = tokio::fs::write(&path, &disk_content).await {
+            tracing::warn!("Failed to auto-save {}: {}", path.display(), e);
+            return;
+        }
+        disk_hashes.write().await.insert(buffer_id, content_hash(&disk_content));
+        if let Some(buffer) = buffers.write().await.get_mut(&buffer_id) {
+            if buffer.path == path && buffer.content == content {
+                buffer.modified = false;
+            }
+        }
+        
+        if let Some(extension) = path.extension().and_then(|ext| ext.to_str()) {
+            if let Some(language) = lsp_manager.get_language_for_extension(extension) {
+                let uri = format!("file://{}", path.to_string_lossy());
+                if let Err(e) = lsp_manager.did_save_document(uri, language).await {
+                    tracing::warn!("Failed to notify LSP of document save: {}", e);
+                }
+            }
+        }
+        
+        let event = code_furnace_events::Event::new(
+            "editor.file.autosaved",
+            "editor-manager",
+            serde_json::json!({
+                "buffer_id": buffer_id,
+                "path": path
+            }),
+        );
+        let _ = event_bus.publish(event);
+    }
+    
+    /// Saves every buffer still waiting on its auto-save timer right away.
+    pub async fn flush_auto_saves(&self) {
+        let pending: Vec<Uuid> = self.pending_auto_saves.write().await
+            .drain()
+            .map(|(buffer_id, handle)| {
+                handle.abort();
+                buffer_id
+            })
+            .collect();
+        
+        for buffer_id in pending {
+            Self::auto_save(buffer_id, &self.buffers, &self.disk_hashes, &self.save_lock, &self.lsp_manager, &self.event_bus).await;
+        }
+    }
+    
+    /// Prepares for the app exiting: pending auto-saves are written and the
+    /// language servers are shut down. Buffers without a pending auto-save
+    /// are left as they are.
+    pub async fn shutdown(&self) {
+        self.flush_auto_saves().await;
+        if let Err(e) = self.lsp_manager.shutdown_all().await {
+            tracing::error!("Failed to shutdown LSP servers: {}", e);
+        }
+    }
+    
+    /// Starts watching a buffer's file for external changes, creating the
+    /// watcher and its event loop on first use.
+    async fn watch_buffer(&self, path: &Path) {
+        let mut file_watcher = self.file_watcher.write().await;
+        
+        if file_watcher.is_none() {
+            match FileWatcher::new(FILE_WATCH_DEBOUNCE) {
+                Ok((watcher, mut changes)) => {
+                    let buffers = self.buffers.clone();
+                    let disk_hashes = self.disk_hashes.clone();
+                    let lsp_manager = self.lsp_manager.clone();
+                    let document_versions = self.document_versions.clone();
+                    let event_bus = self.event_bus.clone();
+                    
+                    tokio::spawn(async move {
+                        while let Some(paths) = changes.recv().await {
+                            Self::handle_external_changes(
+                                paths,
+                                &buffers,
+                                &disk_hashes,
+                                &lsp_manager,
+                                &document_versions,
+                                &event_bus,
+                            ).await;
+                        }
+                    });
+                    
+                    *file_watcher = Some(watcher);
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to create file watcher: {}", e);
+                    return;
+                }
+            }
+        }
+        
+        if let Some(watcher) = file_watcher.as_mut() {
+            if let Err(e) = watcher.watch(path) {
+                tracing::warn!("Failed to watch {}: {}", path.display(), e);
+            }
+        }
+    }
+    
+    async fn unwatch_buffer(&self, path: &Path) {
+        if let Some(watcher) = self.file_watcher.write().await.as_mut() {
+            if let Err(e) = watcher.unwatch(path) {
+                tracing::warn!("Failed to unwatch {}: {}", path.display(), e);
+            }
+        }
+    }
+    
+    async fn handle_external_changes(
+        paths: Vec<PathBuf>,
+        buffers: &RwLock<HashMap<Uuid, FileBuffer>>,
+        disk_hashes: &RwLock<HashMap<Uuid, u64>>,
+        lsp_manager: &LSPManager,
+        document_versions: &RwLock<HashMap<String, u64>>,
+        event_bus: &code_furnace_events::EventBus,
+    ) {
+        let mut buffers = buffers.write().await;
+        let mut disk_hashes = disk_hashes.write().await;
+        
+        for (buffer_id, buffer) in buffers.iter_mut() {
+            let Some(key) = FileWatcher::watch_key(&buffer.path) else {
+                continue;
+            };
+            if !paths.contains(&key) {
+                continue;
+            }
+            
+            let Ok(disk_content) = std::fs::read_to_string(&buffer.path) else {
+                continue;
+            };
+            
+            // Writes we made ourselves leave the recorded hash unchanged
+            let disk_hash = content_hash(&disk_content);
+            if disk_hashes.get(buffer_id) == Some(&disk_hash) {
+                continue;
+            }
+            disk_hashes.insert(*buffer_id, disk_hash);
+            
+            if buffer.modified {
+                let event = code_furnace_events::Event::new(
+                    "editor.file.conflict",
+                    "editor-manager",
+                    serde_json::json!({
+                        "buffer_id": buffer_id,
+                        "path": buffer.path
+                    }),
+                );
+                let _ = event_bus.publish(event);
+                continue;
+            }
+            
+            buffer.content = disk_content;
+            buffer.last_modified = chrono::Utc::now();
+            
+            if let Some(extension) = buffer.path.extension().and_then(|ext| ext.to_str()) {
+                if let Some(language) = lsp_manager.get_language_for_extension(extension) {
+                    let uri = format!("file://{}", buffer.path.to_string_lossy());
+                    let version = {
+                        let mut versions = document_versions.write().await;
+                        let version = versions.entry(uri.clone()).or_insert(1);
+                        *version += 1;
+                        *version
+                    };
+                    
+                    if let Err(e) = lsp_manager.did_change_document(uri, language, buffer.content.clone(), None, version).await {
+                        tracing::warn!("Failed to notify LSP of reloaded document: {}", e);
+                    }
+                }
+            }
+            
+            let event = code_furnace_events::Event::new(
+                "editor.file.reloaded",
+                "editor-manager",
+                serde_json::json!({
+                    "buffer_id": buffer_id,
+                    "path": buffer.path
+                }),
+            );
+            let _ = event_bus.publish(event);
+        }
+    }
+    
+    pub async fn cancel_auto_save(&self, buffer_id: Uuid) {
+        if let Some(handle) = self.pending_auto_saves.write().await.remove(&buffer_id) {
+            handle.abort();
         }
     }
     
     pub async fn set_workspace_root(&self, root: PathBuf) -> Result<()> {
         let mut workspace_root = self.workspace_root.write().await;
         *workspace_root = Some(root.clone());
+        drop(workspace_root);
+        
+        self.watch_workspace(&root).await;
+        
+        // Index the new root in the background so the first quick-open is fast
+        self.file_index.invalidate().await;
+        let filter = FileTreeFilter::new(&root, &*self.config.read().await)?;
+        let file_index = self.file_index.clone();
+        let index_root = root.clone();
+        tokio::spawn(async move {
+            if let Err(e) = file_index.get_or_build(index_root, filter, Arc::new(AtomicBool::new(false))).await {
+                tracing::warn!("Failed to index workspace files: {}", e);
+            }
+        });
         
         let event = code_furnace_events::Event::new(
             "editor.workspace.changed",
@@ -300,15 +871,99 @@ impl EditorManager {
         Ok(())
     }
     
+    /// Replaces the workspace watcher with one on `root`. Without a watcher
+    /// the index is still dropped by the editor's own file operations.
+    async fn watch_workspace(&self, root: &Path) {
+        let mut workspace_watcher = self.workspace_watcher.write().await;
+        // Dropping the old watcher ends its invalidation task
+        *workspace_watcher = None;
+        
+        let filter = match FileTreeFilter::new(root, &*self.config.read().await) {
+            Ok(filter) => filter,
+            Err(e) => {
+                tracing::warn!("Failed to watch workspace {}: {}", root.display(), e);
+                return;
+            }
+        };
+        let watch_root = root.to_path_buf();
+        let watcher = tokio::task::spawn_blocking(move || watcher::TreeWatcher::new(&watch_root, filter, FILE_WATCH_DEBOUNCE))
+            .await
+            .map_err(anyhow::Error::from)
+            .and_then(|result| result);
+        match watcher {
+            Ok((watcher, mut changes)) => {
+                let file_index = self.file_index.clone();
+                tokio::spawn(async move {
+                    while changes.recv().await.is_some() {
+                        file_index.invalidate().await;
+                    }
+                });
+                *workspace_watcher = Some(watcher);
+            }
+            Err(e) => tracing::warn!("Failed to watch workspace {}: {}", root.display(), e),
+        }
+    }
+    
     pub async fn open_file(&self, path: PathBuf) -> Result<Uuid> {
-        let buffer = FileBuffer::from_file(path.clone())?;
+        let (large_file_threshold, force_lf) = {
+            let config = self.config.read().await;
+            (config.large_file_threshold_bytes, config.force_lf_line_endings)
+        };
+        let mut buffer = FileBuffer::from_file(path.clone(), large_file_threshold)?;
         let buffer_id = buffer.id;
         
+        if force_lf {
+            buffer.line_ending = LineEnding::Lf;
+        }
+        
+        if buffer.read_only {
+            tracing::warn!("Opened {} read-only", path.display());
+            let event = code_furnace_events::Event::new(
+                "editor.file.read_only",
+                "editor-manager",
+                serde_json::json!({
+                    "buffer_id": buffer_id,
+                    "path": path
+                }),
+            );
+            self.event_bus.publish(event)?;
+        }
+        
+        self.announce_document(&path, buffer.content.clone()).await;
+        
+        self.disk_hashes.write().await.insert(buffer_id, content_hash(&buffer.content));
+        self.watch_buffer(&path).await;
+        
+        let mut buffers = self.buffers.write().await;
+        buffers.insert(buffer_id, buffer);
+        
+        let mut active_buffer = self.active_buffer.write().await;
+        *active_buffer = Some(buffer_id);
+        
+        self.track_recent_files();
+        self.recent_files.record(&path);
+        
+        let event = code_furnace_events::Event::new(
+            "editor.file.opened",
+            "editor-manager",
+            serde_json::json!({
+                "buffer_id": buffer_id,
+                "path": path
+            }),
+        );
+        self.event_bus.publish(event)?;
+        
+        Ok(buffer_id)
+    }
+    
+    /// Starts the language server for `path` if needed and tells it the
+    /// document is open, starting its version count over.
+    async fn announce_document(&self, path: &Path, content: String) {
         // Get workspace root for LSP
         let workspace_root = {
             let root = self.workspace_root.read().await;
             root.clone().unwrap_or_else(|| {
-                path.parent().unwrap_or(&path).to_path_buf()
+                path.parent().unwrap_or(path).to_path_buf()
             })
         };
         
@@ -324,7 +979,7 @@ impl EditorManager {
                 if let Err(e) = self.lsp_manager.did_open_document(
                     uri.clone(),
                     language.clone(),
-                    buffer.content.clone()
+                    content
                 ).await {
                     tracing::warn!("Failed to notify LSP of opened document: {}", e);
                 }
@@ -334,24 +989,6 @@ impl EditorManager {
                 versions.insert(uri, 1);
             }
         }
-        
-        let mut buffers = self.buffers.write().await;
-        buffers.insert(buffer_id, buffer);
-        
-        let mut active_buffer = self.active_buffer.write().await;
-        *active_buffer = Some(buffer_id);
-        
-        let event = code_furnace_events::Event::new(
-            "editor.file.opened",
-            "editor-manager",
-            serde_json::json!({
-                "buffer_id": buffer_id,
-                "path": path
-            }),
-        );
-        self.event_bus.publish(event)?;
-        
-        Ok(buffer_id)
     }
     
     pub async fn get_buffer(&self, buffer_id: Uuid) -> Option<FileBuffer> {
@@ -363,6 +1000,10 @@ impl EditorManager {
         let mut buffers = self.buffers.write().await;
         
         if let Some(buffer) = buffers.get_mut(&buffer_id) {
+            if buffer.read_only {
+                return Err(EditorError::ReadOnly(buffer.path.clone()).into());
+            }
+            let edit = lsp::compute_edit(&buffer.content, &content);
             buffer.update_content(content.clone());
             
             // Notify LSP of document change
@@ -382,6 +1023,7 @@ impl EditorManager {
                         uri,
                         language,
                         content,
+                        Some(edit),
                         version
                     ).await {
                         tracing::warn!("Failed to notify LSP of document change: {}", e);
@@ -398,16 +1040,51 @@ impl EditorManager {
                 }),
             );
             self.event_bus.publish(event)?;
+            drop(buffers);
+            
+            self.schedule_auto_save(buffer_id).await;
         }
         
         Ok(())
     }
     
+    /// Saves a buffer, first applying `format_on_save`,
+    /// `trim_trailing_whitespace` and `insert_final_newline` as configured.
+    /// Their changes go through `update_buffer`, so the editor and language
+    /// server see them too. Auto-saves skip them, as they would rewrite lines
+    /// while they are being typed.
     pub async fn save_buffer(&self, buffer_id: Uuid) -> Result<()> {
+        let (format_on_save, trim_trailing_whitespace, insert_final_newline) = {
+            let config = self.config.read().await;
+            (config.format_on_save, config.trim_trailing_whitespace, config.insert_final_newline)
+        };
+        if format_on_save {
+            if let Err(e) = self.format_buffer(buffer_id).await {
+                tracing::warn!("Format on save failed: {}", e);
+            }
+        }
+        
+        let normalized = self.buffers.read().await.get(&buffer_id).and_then(|buffer| {
+            let content = buffer.normalized_content(trim_trailing_whitespace, insert_final_newline);
+            (!buffer.read_only && content != buffer.content).then_some(content)
+        });
+        if let Some(content) = normalized {
+            self.update_buffer(buffer_id, content).await?;
+        }
+        
+        self.cancel_auto_save(buffer_id).await;
+        let _saving = self.save_lock.lock().await;
         let mut buffers = self.buffers.write().await;
         
         if let Some(buffer) = buffers.get_mut(&buffer_id) {
             buffer.save()?;
+            self.disk_hashes.write().await.insert(buffer_id, content_hash(&buffer.disk_content()));
+            
+            if let Some((uri, language)) = self.lsp_document(&buffer.path) {
+                if let Err(e) = self.lsp_manager.did_save_document(uri, language).await {
+                    tracing::warn!("Failed to notify LSP of document save: {}", e);
+                }
+            }
             
             let event = code_furnace_events::Event::new(
                 "editor.file.saved",
@@ -423,33 +1100,298 @@ impl EditorManager {
         Ok(())
     }
     
+    /// Recently opened files that still exist, most recent first.
+    pub fn recent_files(&self) -> Vec<RecentFile> {
+        self.track_recent_files();
+        self.recent_files.list()
+    }
+    
+    pub fn clear_recent_files(&self) {
+        self.recent_files.clear();
+    }
+    
+    /// Starts dropping deleted and renamed files from the recently opened list.
+    fn track_recent_files(&self) {
+        self.recent_files_tracker.get_or_init(|| {
+            tokio::spawn(recent::track_removals(
+                Arc::downgrade(&self.recent_files),
+                self.event_bus.clone(),
+            ))
+        });
+    }
+    
+    /// Records where the cursor is in a buffer, so it can be put back there
+    /// when the session is restored.
+    pub async fn set_cursor_position(&self, buffer_id: Uuid, cursor_position: CursorPosition) -> Result<()> {
+        let mut buffers = self.buffers.write().await;
+        let buffer = buffers
+            .get_mut(&buffer_id)
+            .ok_or_else(|| ResourceError::NotFound(format!("Buffer not found: {}", buffer_id)))?;
+        buffer.cursor_position = cursor_position;
+        Ok(())
+    }
+    
+    /// The buffer most recently opened and not yet closed.
+    pub async fn active_buffer(&self) -> Option<Uuid> {
+        *self.active_buffer.read().await
+    }
+    
+    /// Removes a buffer and releases its LSP document state. Closing a buffer
+    /// that is already gone is a no-op.
+    pub async fn close_buffer(&self, buffer_id: Uuid) -> Result<()> {
+        self.cancel_auto_save(buffer_id).await;
+        
+        let Some(buffer) = self.buffers.write().await.remove(&buffer_id) else {
+            return Ok(());
+        };
+        
+        {
+            let mut active_buffer = self.active_buffer.write().await;
+            if *active_buffer == Some(buffer_id) {
+                *active_buffer = None;
+            }
+        }
+        
+        self.disk_hashes.write().await.remove(&buffer_id);
+        self.unwatch_buffer(&buffer.path).await;
+        
+        if let Some((uri, language)) = self.lsp_document(&buffer.path) {
+            self.document_versions.write().await.remove(&uri);
+            if let Err(e) = self.lsp_manager.did_close_document(uri, language).await {
+                tracing::warn!("Failed to notify LSP of document close: {}", e);
+            }
+        }
+        
+        for event_type in ["editor.file.closed", "editor.buffer.closed"] {
+            let event = code_furnace_events::Event::new(
+                event_type,
+                "editor-manager",
+                serde_json::json!({
+                    "buffer_id": buffer_id,
+                    "path": buffer.path
+                }),
+            );
+            self.event_bus.publish(event)?;
+        }
+        
+        Ok(())
+    }
+    
+    /// LSP document URI and language for a path, if a server handles its extension.
+    fn lsp_document(&self, path: &Path) -> Option<(String, String)> {
+        let extension = path.extension().and_then(|ext| ext.to_str())?;
+        let language = self.lsp_manager.get_language_for_extension(extension)?;
+        Some((format!("file://{}", path.to_string_lossy()), language))
+    }
+    
     pub async fn get_file_tree(&self) -> Result<Option<FileTreeNode>> {
         let workspace_root = self.workspace_root.read().await;
         
         if let Some(root) = workspace_root.as_ref() {
-            Ok(Some(FileTreeNode::build_tree(root.clone())?))
+            let config = self.config.read().await;
+            let filter = FileTreeFilter::new(root, &config)?;
+            Ok(Some(FileTreeNode::build_tree(root.clone(), &filter)?))
         } else {
             Ok(None)
         }
     }
     
+    /// Loads the next level below `path` and returns that directory's node,
+    /// expanded, for the caller to graft into its tree.
     pub async fn expand_directory(&self, path: PathBuf) -> Result<Option<FileTreeNode>> {
         let workspace_root = self.workspace_root.read().await;
         
         if let Some(root) = workspace_root.as_ref() {
-            let mut tree = FileTreeNode::build_tree(root.clone())?;
-            if let Some(node) = tree.find_by_path_mut(&path) {
-                node.expand();
+            let depth = path
+                .strip_prefix(root)
+                .map_err(|_| ResourceError::AccessDenied(format!("Path is outside the workspace: {}", path.display())))?
+                .components()
+                .count();
+            
+            let config = self.config.read().await;
+            if depth > config.file_tree_max_depth {
+                return Err(anyhow::anyhow!("Directory exceeds the maximum tree depth of {}", config.file_tree_max_depth));
             }
-            Ok(Some(tree))
+            
+            let filter = FileTreeFilter::new(root, &config)?;
+            let mut node = FileTreeNode::build_tree(path, &filter)?;
+            node.expand();
+            Ok(Some(node))
         } else {
             Ok(None)
         }
     }
     
+    pub async fn workspace_root(&self) -> Option<PathBuf> {
+        self.workspace_root.read().await.clone()
+    }
+    
+    pub async fn search_content(&self, query: &str, options: SearchOptions) -> Result<SearchResults> {
+        self.run_search(Uuid::new_v4(), query, options).await
+    }
+    
+    /// Searches file contents under the workspace root, honoring the same
+    /// ignore rules as the file tree. The search can be stopped early with
+    /// `cancel_search(search_id)`, in which case partial results are returned.
+    pub async fn run_search(&self, search_id: Uuid, query: &str, options: SearchOptions) -> Result<SearchResults> {
+        let root = self.workspace_root.read().await.clone()
+            .ok_or_else(|| anyhow::anyhow!("No workspace root set"))?;
+        let filter = FileTreeFilter::new(&root, &*self.config.read().await)?;
+        let matcher = search::build_matcher(query, &options)?;
+        let max_results = options.max_results.unwrap_or(search::DEFAULT_MAX_RESULTS);
+        
+        let cancelled = Arc::new(AtomicBool::new(false));
+        self.active_searches.write().await.insert(search_id, cancelled.clone());
+        
+        let result = tokio::task::spawn_blocking(move || {
+            search::search_workspace(&root, &filter, &matcher, max_results, &cancelled)
+        }).await;
+        
+        self.active_searches.write().await.remove(&search_id);
+        
+        Ok(result?)
+    }
+    
+    /// Replaces every match of `query` in the workspace. Open buffers are edited
+    /// in memory (and left dirty); other files are rewritten atomically on disk.
+    /// Files open read-only are not changed and are reported with `read_only`.
+    pub async fn replace_in_files(&self, query: &str, replacement: &str, options: ReplaceOptions) -> Result<Vec<FileReplacement>> {
+        let root = self.workspace_root.read().await.clone()
+            .ok_or_else(|| anyhow::anyhow!("No workspace root set"))?
+            .canonicalize()?;
+        let matcher = search::build_matcher(query, &options.search)?;
+        
+        let mut search_options = options.search.clone();
+        search_options.max_results = Some(search_options.max_results.unwrap_or(usize::MAX));
+        let results = self.search_content(query, search_options).await?;
+        
+        // Open buffers may hold unsaved matches the on-disk search can't see
+        let open_buffers: HashMap<PathBuf, (Uuid, String, bool)> = self.buffers.read().await
+            .values()
+            .filter_map(|buffer| {
+                let path = buffer.path.canonicalize().ok()?;
+                path.starts_with(&root).then(|| (path, (buffer.id, buffer.content.clone(), buffer.read_only)))
+            })
+            .collect();
+        
+        let mut candidates: Vec<PathBuf> = results.matches
+            .into_iter()
+            .filter_map(|found| found.path.canonicalize().ok())
+            .chain(open_buffers.keys().cloned())
+            .collect();
+        candidates.sort();
+        candidates.dedup();
+        
+        let mut planned = Vec::new();
+        for path in candidates {
+            let (buffer_id, content, read_only) = match open_buffers.get(&path) {
+                Some((buffer_id, content, read_only)) => (Some(*buffer_id), content.clone(), *read_only),
+                None => match std::fs::read_to_string(&path) {
+                    Ok(content) => (None, content, false),
+                    Err(_) => continue,
+                },
+            };
+            
+            let (new_content, replacements, edits) = search::replace_lines(
+                &content,
+                &matcher,
+                replacement,
+                options.search.regex,
+            );
+            if replacements == 0 {
+                continue;
+            }
+            
+            if !options.dry_run && !read_only {
+                match buffer_id {
+                    Some(buffer_id) => self.update_buffer(buffer_id, new_content).await?,
+                    None => search::write_atomic(&path, &new_content)?,
+                }
+                
+                let event = code_furnace_events::Event::new(
+                    "editor.file.modified",
+                    "editor-manager",
+                    serde_json::json!({
+                        "path": path,
+                        "buffer_id": buffer_id,
+                        "replacements": replacements
+                    }),
+                );
+                self.event_bus.publish(event)?;
+            }
+            
+            planned.push(FileReplacement {
+                path,
+                buffer_id,
+                replacements,
+                edits,
+                read_only,
+            });
+        }
+        
+        Ok(planned)
+    }
+    
+    pub async fn find_files(&self, query: &str, limit: usize) -> Result<FileMatches> {
+        self.run_find_files(Uuid::new_v4(), query, limit).await
+    }
+    
+    /// Fuzzy-matches `query` against the paths of every file under the
+    /// workspace root, with the same ignore rules as the file tree, for a
+    /// quick-open palette. The file list is cached between calls. A newer
+    /// query can stop this one with `cancel_search(search_id)`, in which case
+    /// no matches are returned.
+    pub async fn run_find_files(&self, search_id: Uuid, query: &str, limit: usize) -> Result<FileMatches> {
+        let root = self.workspace_root.read().await.clone()
+            .ok_or_else(|| anyhow::anyhow!("No workspace root set"))?;
+        let filter = FileTreeFilter::new(&root, &*self.config.read().await)?;
+        
+        let cancelled = Arc::new(AtomicBool::new(false));
+        self.active_searches.write().await.insert(search_id, cancelled.clone());
+        
+        // Cancelling also stops the walk when there is no index yet
+        let result = match self.file_index.get_or_build(root, filter, cancelled.clone()).await {
+            Ok(Some(index)) => {
+                let query = query.to_string();
+                tokio::task::spawn_blocking(move || index.find(&query, limit, &cancelled))
+                    .await
+                    .map_err(anyhow::Error::from)
+            }
+            Ok(None) => Ok(FileMatches {
+                matches: Vec::new(),
+                cancelled: true,
+            }),
+            Err(e) => Err(e),
+        };
+        
+        self.active_searches.write().await.remove(&search_id);
+        
+        result
+    }
+    
+    pub async fn cancel_search(&self, search_id: Uuid) -> bool {
+        if let Some(cancelled) = self.active_searches.read().await.get(&search_id) {
+            cancelled.store(true, Ordering::Relaxed);
+            true
+        } else {
+            false
+        }
+    }
+    
+    /// Writes a new file and opens it. Fails without touching anything if
+    /// `path` already exists.
     pub async fn create_file(&self, path: PathBuf, content: Option<String>) -> Result<()> {
+        use std::io::Write;
+        
         let content = content.unwrap_or_default();
-        std::fs::write(&path, content)?;
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .map_err(|e| anyhow::Error::new(e).context(format!("Cannot create {}", path.display())))?;
+        file.write_all(content.as_bytes())?;
+        drop(file);
+        self.file_index.invalidate().await;
         
         // Open the new file as a buffer
         let buffer_id = self.open_file(path.clone()).await?;
@@ -469,6 +1411,7 @@ impl EditorManager {
     
     pub async fn create_directory(&self, path: PathBuf) -> Result<()> {
         std::fs::create_dir_all(&path)?;
+        self.file_index.invalidate().await;
         
         let event = code_furnace_events::Event::new(
             "editor.directory.created",
@@ -483,16 +1426,16 @@ impl EditorManager {
     }
     
     pub async fn delete_file(&self, path: PathBuf) -> Result<()> {
-        // Close any open buffers for this file
-        let buffers = self.buffers.read().await;
-        let buffer_to_close = buffers.iter()
-            .find(|(_, buffer)| buffer.path == path)
-            .map(|(id, _)| *id);
-        drop(buffers);
+        // Close any open buffers for this file, or under this directory, so
+        // their language servers drop the documents too
+        let buffers_to_close: Vec<Uuid> = self.buffers.read().await
+            .iter()
+            .filter(|(_, buffer)| buffer.path.starts_with(&path))
+            .map(|(id, _)| *id)
+            .collect();
         
-        if let Some(buffer_id) = buffer_to_close {
-            let mut buffers = self.buffers.write().await;
-            buffers.remove(&buffer_id);
+        for buffer_id in buffers_to_close {
+            self.close_buffer(buffer_id).await?;
         }
         
         // Delete the file
@@ -501,6 +1444,7 @@ impl EditorManager {
         } else {
             std::fs::remove_dir_all(&path)?;
         }
+        self.file_index.invalidate().await;
         
         let event = code_furnace_events::Event::new(
             "editor.file.deleted",
@@ -514,18 +1458,27 @@ impl EditorManager {
         Ok(())
     }
     
+    /// Renames a file or directory, moving every open buffer at or below
+    /// `old_path` to the matching location under `new_path`.
     pub async fn rename_file(&self, old_path: PathBuf, new_path: PathBuf) -> Result<()> {
-        // Update any open buffers for this file
-        let mut buffers = self.buffers.write().await;
-        let buffer_to_update = buffers.iter_mut()
-            .find(|(_, buffer)| buffer.path == old_path);
+        let affected: Vec<(Uuid, PathBuf)> = self.buffers.read().await
+            .values()
+            .filter(|buffer| buffer.path.starts_with(&old_path))
+            .map(|buffer| (buffer.id, buffer.path.clone()))
+            .collect();
         
-        if let Some((_, buffer)) = buffer_to_update {
-            buffer.path = new_path.clone();
+        // Watch keys are resolved from the parent directory, which may be the one being renamed
+        for (_, path) in &affected {
+            self.unwatch_buffer(path).await;
         }
         
-        // Rename the file
-        std::fs::rename(&old_path, &new_path)?;
+        if let Err(e) = std::fs::rename(&old_path, &new_path) {
+            for (_, path) in &affected {
+                self.watch_buffer(path).await;
+            }
+            return Err(e.into());
+        }
+        self.file_index.invalidate().await;
         
         let event = code_furnace_events::Event::new(
             "editor.file.renamed",
@@ -537,6 +1490,48 @@ impl EditorManager {
         );
         self.event_bus.publish(event)?;
         
+        for (buffer_id, buffer_old_path) in affected {
+            let Ok(relative) = buffer_old_path.strip_prefix(&old_path) else {
+                continue;
+            };
+            let buffer_new_path = if relative.as_os_str().is_empty() {
+                new_path.clone()
+            } else {
+                new_path.join(relative)
+            };
+            
+            let content = match self.buffers.write().await.get_mut(&buffer_id) {
+                Some(buffer) => {
+                    buffer.path = buffer_new_path.clone();
+                    buffer.language = FileBuffer::detect_language(&buffer_new_path);
+                    buffer.content.clone()
+                }
+                None => continue,
+            };
+            self.watch_buffer(&buffer_new_path).await;
+            
+            // Language servers know documents by URI, so the old one is closed
+            // and the document opened again under the new path
+            if let Some((uri, language)) = self.lsp_document(&buffer_old_path) {
+                self.document_versions.write().await.remove(&uri);
+                if let Err(e) = self.lsp_manager.did_close_document(uri, language).await {
+                    tracing::warn!("Failed to notify LSP of document close: {}", e);
+                }
+            }
+            self.announce_document(&buffer_new_path, content).await;
+            
+            let event = code_furnace_events::Event::new(
+                "editor.buffer.renamed",
+                "editor-manager",
+                serde_json::json!({
+                    "buffer_id": buffer_id,
+                    "old_path": buffer_old_path,
+                    "new_path": buffer_new_path
+                }),
+            );
+            self.event_bus.publish(event)?;
+        }
+        
         Ok(())
     }
     
@@ -575,24 +1570,272 @@ impl EditorManager {
         Ok(None)
     }
     
-    pub async fn get_diagnostics(&self, buffer_id: Uuid) -> Option<Vec<LSPDiagnostic>> {
+    pub async fn get_definition(&self, buffer_id: Uuid, line: u32, character: u32) -> Result<Vec<LSPLocation>> {
         let buffers = self.buffers.read().await;
         
         if let Some(buffer) = buffers.get(&buffer_id) {
-            if let Some(extension) = buffer.path.extension().and_then(|ext| ext.to_str()) {
-                if let Some(language) = self.lsp_manager.get_language_for_extension(extension) {
-                    let uri = format!("file://{}", buffer.path.to_string_lossy());
-                    return self.lsp_manager.get_diagnostics(&language, &uri).await;
+            if let Some((uri, language)) = self.lsp_document(&buffer.path) {
+                return self.lsp_manager.get_definition(language, uri, line, character).await;
+            }
+        }
+        
+        Ok(Vec::new())
+    }
+    
+    pub async fn get_references(&self, buffer_id: Uuid, line: u32, character: u32, include_declaration: bool) -> Result<Vec<LSPLocation>> {
+        let buffers = self.buffers.read().await;
+        
+        if let Some(buffer) = buffers.get(&buffer_id) {
+            if let Some((uri, language)) = self.lsp_document(&buffer.path) {
+                return self.lsp_manager.get_references(language, uri, line, character, include_declaration).await;
+            }
+        }
+        
+        Ok(Vec::new())
+    }
+    
+    /// Formats a buffer with its language server and returns the new content.
+    /// The buffer is left unchanged when no server handles its language.
+    pub async fn format_buffer(&self, buffer_id: Uuid) -> Result<String> {
+        let (path, content) = {
+            let buffers = self.buffers.read().await;
+            let buffer = buffers.get(&buffer_id)
+                .ok_or_else(|| ResourceError::NotFound(format!("Buffer not found: {}", buffer_id)))?;
+            (buffer.path.clone(), buffer.content.clone())
+        };
+        
+        let Some((uri, language)) = self.lsp_document(&path) else {
+            return Ok(content);
+        };
+        
+        let (tab_size, insert_spaces) = {
+            let config = self.config.read().await;
+            (config.tab_size, config.insert_spaces)
+        };
+        
+        let edits = self.lsp_manager.format_document(language, uri, tab_size, insert_spaces).await?;
+        if edits.is_empty() {
+            return Ok(content);
+        }
+        
+        let formatted = lsp::apply_text_edits(&content, &edits)?;
+        if formatted != content {
+            self.update_buffer(buffer_id, formatted.clone()).await?;
+        }
+        
+        Ok(formatted)
+    }
+    
+    /// Renames the symbol at a position across the workspace and returns the
+    /// paths that were changed.
+    pub async fn rename_symbol(&self, buffer_id: Uuid, line: u32, character: u32, new_name: String) -> Result<Vec<PathBuf>> {
+        let path = {
+            let buffers = self.buffers.read().await;
+            buffers.get(&buffer_id)
+                .map(|buffer| buffer.path.clone())
+                .ok_or_else(|| ResourceError::NotFound(format!("Buffer not found: {}", buffer_id)))?
+        };
+        let (uri, language) = self.lsp_document(&path)
+            .ok_or_else(|| anyhow::anyhow!("No language server for {}", path.display()))?;
+        
+        let changes = self.lsp_manager.rename(language, uri, line, character, new_name).await?;
+        self.apply_workspace_edit(&changes).await
+    }
+    
+    /// Applies LSP text edits grouped by document URI. Open buffers are edited in
+    /// memory and other files are written atomically. All edits are computed
+    /// before anything is written, and files already changed are restored if a
+    /// later one fails.
+    async fn apply_workspace_edit(&self, changes: &HashMap<String, Vec<LSPTextEdit>>) -> Result<Vec<PathBuf>> {
+        let open_buffers: HashMap<PathBuf, (Uuid, String, bool)> = self.buffers.read().await
+            .values()
+            .filter_map(|buffer| {
+                let path = buffer.path.canonicalize().ok()?;
+                Some((path, (buffer.id, buffer.content.clone(), buffer.read_only)))
+            })
+            .collect();
+        
+        // Plan every file first so a bad edit fails before anything is touched
+        let mut planned = Vec::new();
+        for (document_uri, edits) in changes {
+            let path = lsp::uri_to_path(document_uri)?;
+            let canonical = path.canonicalize()?;
+            
+            let (buffer_id, original) = match open_buffers.get(&canonical) {
+                Some((_, _, true)) => return Err(EditorError::ReadOnly(path).into()),
+                Some((buffer_id, content, false)) => (Some(*buffer_id), content.clone()),
+                None => (None, std::fs::read_to_string(&canonical)?),
+            };
+            
+            let updated = lsp::apply_text_edits(&original, edits)?;
+            planned.push((canonical, buffer_id, original, updated));
+        }
+        
+        let mut applied: Vec<(PathBuf, Option<Uuid>, String)> = Vec::new();
+        for (path, buffer_id, original, updated) in planned {
+            let result = match buffer_id {
+                Some(buffer_id) => self.update_buffer(buffer_id, updated).await,
+                None => search::write_atomic(&path, &updated),
+            };
+            
+            if let Err(e) = result {
+                for (applied_path, applied_buffer, applied_original) in applied.into_iter().rev() {
+                    let restored = match applied_buffer {
+                        Some(applied_buffer) => self.update_buffer(applied_buffer, applied_original).await,
+                        None => search::write_atomic(&applied_path, &applied_original),
+                    };
+                    if let Err(restore_error) = restored {
+                        tracing::error!("Failed to roll back {}: {}", applied_path.display(), restore_error);
+                    }
                 }
+                return Err(anyhow::anyhow!("Failed to apply edit to {}: {}", path.display(), e));
             }
+            
+            applied.push((path, buffer_id, original));
         }
         
-        None
+        let mut changed = Vec::new();
+        for (path, buffer_id, _) in applied {
+            let event = code_furnace_events::Event::new(
+                "editor.file.modified",
+                "editor-manager",
+                serde_json::json!({
+                    "path": path,
+                    "buffer_id": buffer_id
+                }),
+            );
+            self.event_bus.publish(event)?;
+            changed.push(path);
+        }
+        
+        Ok(changed)
+    }
+    
+    pub async fn get_document_symbols(&self, buffer_id: Uuid) -> Result<Vec<LSPDocumentSymbol>> {
+        let buffers = self.buffers.read().await;
+        
+        if let Some(buffer) = buffers.get(&buffer_id) {
+            if let Some((uri, language)) = self.lsp_document(&buffer.path) {
+                return self.lsp_manager.get_document_symbols(language, uri).await;
+            }
+        }
+        
+        Ok(Vec::new())
+    }
+    
+    /// Symbols matching `query` across the workspace, for a symbol palette.
+    /// Only languages with a running server contribute.
+    pub async fn search_symbols(&self, query: &str) -> Result<Vec<LSPWorkspaceSymbol>> {
+        self.lsp_manager.workspace_symbols(query).await
+    }
+    
+    pub async fn get_signature_help(&self, buffer_id: Uuid, line: u32, character: u32) -> Result<Option<LSPSignatureHelp>> {
+        let buffers = self.buffers.read().await;
+        
+        if let Some(buffer) = buffers.get(&buffer_id) {
+            if let Some((uri, language)) = self.lsp_document(&buffer.path) {
+                return self.lsp_manager.get_signature_help(language, uri, line, character).await;
+            }
+        }
+        
+        Ok(None)
+    }
+    
+    /// Lists code actions for a range, passing along the diagnostics that overlap it.
+    pub async fn get_code_actions(&self, buffer_id: Uuid, range: LSPRange) -> Result<Vec<LSPCodeAction>> {
+        let path = match self.buffers.read().await.get(&buffer_id) {
+            Some(buffer) => buffer.path.clone(),
+            None => return Ok(Vec::new()),
+        };
+        let Some((uri, language)) = self.lsp_document(&path) else {
+            return Ok(Vec::new());
+        };
+        
+        let position = |p: &lsp::LSPPosition| (p.line, p.character);
+        let diagnostics: Vec<LSPDiagnostic> = self.lsp_manager.get_diagnostics(&language, &uri).await
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|diagnostic| {
+                position(&diagnostic.range.start) <= position(&range.end)
+                    && position(&diagnostic.range.end) >= position(&range.start)
+            })
+            .collect();
+        
+        self.lsp_manager.get_code_actions(language, uri, range, diagnostics).await
+    }
+    
+    /// Applies a code action from `get_code_actions`, resolving its edit first
+    /// if the server deferred it, then running its command if it has one.
+    pub async fn apply_code_action(&self, buffer_id: Uuid, action: LSPCodeAction) -> Result<Vec<PathBuf>> {
+        let path = self.buffers.read().await
+            .get(&buffer_id)
+            .map(|buffer| buffer.path.clone())
+            .ok_or_else(|| ResourceError::NotFound(format!("Buffer not found: {}", buffer_id)))?;
+        let (_, language) = self.lsp_document(&path)
+            .ok_or_else(|| anyhow::anyhow!("No language server for {}", path.display()))?;
+        
+        // Servers may leave the edit out until resolved, also on actions that
+        // carry a command; the resolve is skipped for servers that cannot do it
+        let action = if action.edit.is_none() {
+            let command = action.command.clone();
+            let mut resolved = self.lsp_manager.resolve_code_action(language.clone(), action).await?;
+            if resolved.command.is_none() {
+                resolved.command = command;
+            }
+            resolved
+        } else {
+            action
+        };
+        
+        let changed = match &action.edit {
+            Some(edit) => {
+                let changes = lsp::LSPServer::parse_workspace_edit(edit)?;
+                self.apply_workspace_edit(&changes).await?
+            }
+            None => Vec::new(),
+        };
+        
+        if let Some(command) = &action.command {
+            self.lsp_manager.execute_command(language, command.clone()).await?;
+        }
+        
+        Ok(changed)
+    }
+    
+    pub async fn get_lsp_log(&self, language: &str) -> Vec<String> {
+        self.lsp_manager.get_server_log(language).await
+    }
+    
+    /// The language server's diagnostics for a buffer followed by those of
+    /// the last build. None when neither has reported on it.
+    pub async fn get_diagnostics(&self, buffer_id: Uuid) -> Option<Vec<LSPDiagnostic>> {
+        let path = self.buffers.read().await.get(&buffer_id)?.path.clone();
+        let uri = format!("file://{}", path.to_string_lossy());
+        
+        let mut diagnostics = match self.lsp_document(&path) {
+            Some((uri, language)) => self.lsp_manager.get_diagnostics(&language, &uri).await,
+            None => None,
+        };
+        let build_diagnostics = self.lsp_manager.get_build_diagnostics(&uri).await;
+        if !build_diagnostics.is_empty() {
+            diagnostics.get_or_insert_with(Vec::new).extend(build_diagnostics);
+        }
+        diagnostics
+    }
+    
+    /// Replaces the diagnostics from the last run of kind `run`, keyed by
+    /// `file://` URI; see `LSPManager::set_build_diagnostics`.
+    pub async fn set_build_diagnostics(&self, run: &str, diagnostics: HashMap<String, Vec<LSPDiagnostic>>) {
+        self.lsp_manager.set_build_diagnostics(run, diagnostics).await;
     }
 }
 
 impl Drop for EditorManager {
     fn drop(&mut self) {
+        if let Some(tracker) = self.recent_files_tracker.get() {
+            tracker.abort();
+        }
+        
         // Shutdown LSP servers when the editor manager is dropped
         let lsp_manager = self.lsp_manager.clone();
         tokio::spawn(async move {
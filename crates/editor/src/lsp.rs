@@ -1,6 +1,6 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::path::PathBuf;
 use std::process::{Command, Stdio};
 use std::sync::Arc;
@@ -8,6 +8,28 @@ use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
 use tokio::process::{Child, ChildStdin, ChildStdout};
 use tokio::sync::{mpsc, oneshot, RwLock};
 use tokio::time::{timeout, Duration};
+use code_furnace_events::{Event, EventBus};
+use code_furnace_utils::LspServerConfig;
+
+/// Quiet period after a `publishDiagnostics` before the update is forwarded to the event bus.
+const DIAGNOSTICS_DEBOUNCE: Duration = Duration::from_millis(150);
+/// Lines of server stderr kept for `LSPManager::get_server_log`.
+const SERVER_LOG_LINES: usize = 500;
+/// Most symbols `LSPManager::workspace_symbols` returns across all servers.
+pub const MAX_WORKSPACE_SYMBOLS: usize = 500;
+/// How long a request waits for its response before failing with
+/// `EditorError::LspTimeout`.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+type ServerLog = Arc<RwLock<VecDeque<String>>>;
+/// Diagnostics from build and test runs, keyed by the kind of run and then by
+/// document URI, so each kind of run only replaces its own.
+type BuildDiagnostics = Arc<RwLock<BTreeMap<String, HashMap<String, Vec<LSPDiagnostic>>>>>;
+
+/// Every kind of run's diagnostics for `uri`, in order of the kind's name.
+fn build_diagnostics_for(runs: &BTreeMap<String, HashMap<String, Vec<LSPDiagnostic>>>, uri: &str) -> Vec<LSPDiagnostic> {
+    runs.values().filter_map(|by_uri| by_uri.get(uri)).flatten().cloned().collect()
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LSPDiagnostic {
@@ -30,6 +52,80 @@ pub struct LSPPosition {
     pub character: u32,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LSPLocation {
+    pub uri: String,
+    pub range: LSPRange,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LSPDocumentSymbol {
+    pub name: String,
+    pub kind: u32,
+    pub range: LSPRange,
+    pub children: Vec<LSPDocumentSymbol>,
+}
+
+/// A symbol found by a `workspace/symbol` query.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LSPWorkspaceSymbol {
+    pub name: String,
+    pub kind: u32,
+    pub uri: String,
+    pub range: LSPRange,
+    /// Name of the enclosing symbol, e.g. the class of a method.
+    pub container: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LSPSignatureHelp {
+    pub label: String,
+    pub documentation: Option<String>,
+    pub parameters: Vec<String>,
+    pub active_parameter: Option<u32>,
+}
+
+/// A code action or bare command offered by the server. `raw` keeps the
+/// server's original object so it can be sent back for `codeAction/resolve`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LSPCodeAction {
+    pub title: String,
+    pub kind: Option<String>,
+    pub is_preferred: bool,
+    pub edit: Option<serde_json::Value>,
+    pub command: Option<serde_json::Value>,
+    pub raw: serde_json::Value,
+}
+
+impl LSPCodeAction {
+    fn from_json(item: &serde_json::Value) -> Option<Self> {
+        let title = item.get("title")?.as_str()?.to_string();
+        
+        // A bare Command has a string `command` field instead of a nested object
+        let command = match item.get("command") {
+            Some(serde_json::Value::String(_)) => Some(item.clone()),
+            Some(command) => Some(command.clone()),
+            None => None,
+        };
+        
+        Some(Self {
+            title,
+            kind: item.get("kind").and_then(|k| k.as_str()).map(str::to_string),
+            is_preferred: item.get("isPreferred").and_then(|p| p.as_bool()).unwrap_or(false),
+            edit: item.get("edit").cloned(),
+            command,
+            raw: item.clone(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LSPTextEdit {
+    pub range: LSPRange,
+    #[serde(rename = "newText")]
+    pub new_text: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LSPCompletionItem {
     pub label: String,
@@ -73,9 +169,40 @@ pub struct LSPServer {
     diagnostics: Arc<RwLock<HashMap<String, Vec<LSPDiagnostic>>>>,
     response_sender: Option<mpsc::UnboundedSender<LSPRequest>>,
     pending_requests: Arc<RwLock<HashMap<u64, oneshot::Sender<serde_json::Value>>>>,
+    symbol_cache: Arc<RwLock<HashMap<String, Vec<LSPDocumentSymbol>>>>,
+    capabilities: Arc<RwLock<serde_json::Value>>,
+    stderr_log: ServerLog,
+    sync_kind: TextDocumentSyncKind,
+    event_bus: Option<EventBus>,
+    /// Shared with the manager; see `LSPManager::set_build_diagnostics`.
+    build_diagnostics: BuildDiagnostics,
+}
+
+/// How document changes are sent, as negotiated from the server's `textDocumentSync`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TextDocumentSyncKind {
+    None,
+    #[default]
+    Full,
+    Incremental,
+}
+
+impl TextDocumentSyncKind {
+    fn from_capabilities(capabilities: &serde_json::Value) -> Self {
+        let sync = capabilities.get("textDocumentSync");
+        let kind = sync
+            .and_then(|sync| sync.as_u64().or_else(|| sync.get("change").and_then(|c| c.as_u64())));
+        
+        match kind {
+            Some(0) => TextDocumentSyncKind::None,
+            Some(2) => TextDocumentSyncKind::Incremental,
+            _ => TextDocumentSyncKind::Full,
+        }
+    }
 }
 
 #[derive(Debug)]
+#[allow(dead_code)] // Requests are currently written directly; the channel is kept for routing
 struct LSPRequest {
     id: u64,
     method: String,
@@ -95,6 +222,12 @@ impl LSPServer {
             diagnostics: Arc::new(RwLock::new(HashMap::new())),
             response_sender: None,
             pending_requests: Arc::new(RwLock::new(HashMap::new())),
+            symbol_cache: Arc::new(RwLock::new(HashMap::new())),
+            capabilities: Arc::new(RwLock::new(serde_json::Value::Null)),
+            stderr_log: Arc::new(RwLock::new(VecDeque::new())),
+            sync_kind: TextDocumentSyncKind::default(),
+            event_bus: None,
+            build_diagnostics: Arc::new(RwLock::new(BTreeMap::new())),
         }
     }
     
@@ -108,6 +241,18 @@ impl LSPServer {
         let mut child = tokio::process::Command::from(cmd).spawn()?;
         let stdout = child.stdout.take().expect("Failed to get stdout");
         
+        if let Some(stderr) = child.stderr.take() {
+            let language = self.language.clone();
+            let stderr_log = self.stderr_log.clone();
+            tokio::spawn(async move {
+                let mut lines = BufReader::new(stderr).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    tracing::debug!("[{} LSP] {}", language, line);
+                    Self::append_log(&stderr_log, line).await;
+                }
+            });
+        }
+        
         // Create channels for handling requests and responses
         let (request_tx, request_rx) = mpsc::unbounded_channel::<LSPRequest>();
         self.response_sender = Some(request_tx);
@@ -115,22 +260,43 @@ impl LSPServer {
         // Spawn background task to handle LSP communication
         let pending_requests = self.pending_requests.clone();
         let diagnostics = self.diagnostics.clone();
+        let diagnostics_tx = self.event_bus.clone().map(|event_bus| {
+            let (diagnostics_tx, diagnostics_rx) = mpsc::unbounded_channel::<String>();
+            tokio::spawn(Self::publish_diagnostics(
+                self.language.clone(),
+                diagnostics.clone(),
+                self.build_diagnostics.clone(),
+                diagnostics_rx,
+                event_bus,
+            ));
+            diagnostics_tx
+        });
         tokio::spawn(async move {
-            Self::handle_lsp_communication(stdout, request_rx, pending_requests, diagnostics).await;
+            Self::handle_lsp_communication(stdout, request_rx, pending_requests, diagnostics, diagnostics_tx).await;
         });
         
         // Initialize the LSP server
         self.send_initialize(&mut child, workspace_root).await?;
+        self.sync_kind = TextDocumentSyncKind::from_capabilities(&*self.capabilities.read().await);
         
         self.process = Some(child);
         Ok(())
     }
     
+    async fn append_log(log: &RwLock<VecDeque<String>>, line: String) {
+        let mut log = log.write().await;
+        if log.len() == SERVER_LOG_LINES {
+            log.pop_front();
+        }
+        log.push_back(line);
+    }
+    
     async fn handle_lsp_communication(
         stdout: ChildStdout,
         mut request_rx: mpsc::UnboundedReceiver<LSPRequest>,
         pending_requests: Arc<RwLock<HashMap<u64, oneshot::Sender<serde_json::Value>>>>,
         diagnostics: Arc<RwLock<HashMap<String, Vec<LSPDiagnostic>>>>,
+        diagnostics_tx: Option<mpsc::UnboundedSender<String>>,
     ) {
         let mut reader = BufReader::new(stdout);
         let mut buffer = String::new();
@@ -146,7 +312,7 @@ impl LSPServer {
                 result = Self::read_lsp_message(&mut reader, &mut buffer) => {
                     match result {
                         Ok(Some(message)) => {
-                            Self::process_lsp_message(message, &pending_requests, &diagnostics).await;
+                            Self::process_lsp_message(message, &pending_requests, &diagnostics, diagnostics_tx.as_ref()).await;
                         }
                         Ok(None) => {
                             // EOF reached
@@ -203,13 +369,14 @@ impl LSPServer {
         message: serde_json::Value,
         pending_requests: &Arc<RwLock<HashMap<u64, oneshot::Sender<serde_json::Value>>>>,
         diagnostics: &Arc<RwLock<HashMap<String, Vec<LSPDiagnostic>>>>,
+        diagnostics_tx: Option<&mpsc::UnboundedSender<String>>,
     ) {
         if let Some(method) = message.get("method").and_then(|m| m.as_str()) {
             // Handle notifications
             match method {
                 "textDocument/publishDiagnostics" => {
                     if let Some(params) = message.get("params") {
-                        Self::handle_diagnostics(params, diagnostics).await;
+                        Self::handle_diagnostics(params, diagnostics, diagnostics_tx).await;
                     }
                 }
                 _ => {
@@ -228,6 +395,7 @@ impl LSPServer {
     async fn handle_diagnostics(
         params: &serde_json::Value,
         diagnostics: &Arc<RwLock<HashMap<String, Vec<LSPDiagnostic>>>>,
+        diagnostics_tx: Option<&mpsc::UnboundedSender<String>>,
     ) {
         if let (Some(uri), Some(diags_json)) = (
             params.get("uri").and_then(|u| u.as_str()),
@@ -243,6 +411,47 @@ impl LSPServer {
             
             let mut diag_map = diagnostics.write().await;
             diag_map.insert(uri.to_string(), parsed_diagnostics);
+            
+            if let Some(diagnostics_tx) = diagnostics_tx {
+                let _ = diagnostics_tx.send(uri.to_string());
+            }
+        }
+    }
+    
+    /// Publishes `editor.diagnostics.updated` once per document after a burst of
+    /// `publishDiagnostics` settles, carrying the latest diagnostics for each
+    /// followed by its build diagnostics.
+    async fn publish_diagnostics(
+        language: String,
+        diagnostics: Arc<RwLock<HashMap<String, Vec<LSPDiagnostic>>>>,
+        build_diagnostics: BuildDiagnostics,
+        mut diagnostics_rx: mpsc::UnboundedReceiver<String>,
+        event_bus: EventBus,
+    ) {
+        while let Some(first) = diagnostics_rx.recv().await {
+            let mut updated = std::collections::HashSet::new();
+            updated.insert(first);
+            
+            while let Ok(Some(uri)) = timeout(DIAGNOSTICS_DEBOUNCE, diagnostics_rx.recv()).await {
+                updated.insert(uri);
+            }
+            
+            let diag_map = diagnostics.read().await;
+            let build_runs = build_diagnostics.read().await;
+            for uri in updated {
+                let mut merged = diag_map.get(&uri).cloned().unwrap_or_default();
+                merged.extend(build_diagnostics_for(&build_runs, &uri));
+                let event = Event::new(
+                    "editor.diagnostics.updated",
+                    "editor-manager",
+                    serde_json::json!({
+                        "uri": uri,
+                        "language": language,
+                        "diagnostics": merged
+                    }),
+                );
+                let _ = event_bus.publish(event);
+            }
         }
     }
     
@@ -323,7 +532,11 @@ impl LSPServer {
                         "dynamicRegistration": true
                     },
                     "codeAction": {
-                        "dynamicRegistration": true
+                        "dynamicRegistration": true,
+                        "dataSupport": true,
+                        "resolveSupport": {
+                            "properties": ["edit"]
+                        }
                     },
                     "codeLens": {
                         "dynamicRegistration": true
@@ -349,15 +562,44 @@ impl LSPServer {
             }]
         });
         
+        let id = {
+            let mut request_id = self.request_id.write().await;
+            *request_id += 1;
+            *request_id
+        };
+        
         let request = serde_json::json!({
             "jsonrpc": "2.0",
-            "id": 1,
+            "id": id,
             "method": "initialize",
             "params": initialize_params
         });
         
+        let (response_tx, response_rx) = oneshot::channel();
+        {
+            let mut pending = self.pending_requests.write().await;
+            pending.insert(id, response_tx);
+        }
+        
         Self::send_message(stdin, &request).await?;
         
+        // Servers must not receive other requests before answering initialize
+        match timeout(REQUEST_TIMEOUT, response_rx).await {
+            Ok(Ok(response)) => {
+                if let Some(capabilities) = response.get("result").and_then(|r| r.get("capabilities")) {
+                    *self.capabilities.write().await = capabilities.clone();
+                }
+            }
+            Ok(Err(_)) => {
+                tracing::warn!("LSP initialize response channel closed");
+            }
+            Err(_) => {
+                tracing::warn!("LSP initialize request timed out");
+                let mut pending = self.pending_requests.write().await;
+                pending.remove(&id);
+            }
+        }
+        
         // Send initialized notification
         let initialized = serde_json::json!({
             "jsonrpc": "2.0",
@@ -404,7 +646,22 @@ impl LSPServer {
         Ok(())
     }
     
-    pub async fn did_change(&mut self, uri: String, content: String, version: u64) -> Result<()> {
+    /// Sends `edit` as an incremental change when the server supports it, and
+    /// the full `content` otherwise.
+    pub async fn did_change(&mut self, uri: String, content: String, edit: Option<LSPTextEdit>, version: u64) -> Result<()> {
+        self.symbol_cache.write().await.remove(&uri);
+        
+        let content_change = match (self.sync_kind, edit) {
+            (TextDocumentSyncKind::None, _) => return Ok(()),
+            (TextDocumentSyncKind::Incremental, Some(edit)) => serde_json::json!({
+                "range": edit.range,
+                "text": edit.new_text
+            }),
+            _ => serde_json::json!({
+                "text": content
+            }),
+        };
+        
         if let Some(child) = &mut self.process {
             let stdin = child.stdin.as_mut().unwrap();
             
@@ -416,9 +673,27 @@ impl LSPServer {
                         "uri": uri,
                         "version": version
                     },
-                    "contentChanges": [{
-                        "text": content
-                    }]
+                    "contentChanges": [content_change]
+                }
+            });
+            
+            Self::send_message(stdin, &notification).await?;
+        }
+        
+        Ok(())
+    }
+    
+    pub async fn did_save(&mut self, uri: String) -> Result<()> {
+        if let Some(child) = &mut self.process {
+            let stdin = child.stdin.as_mut().unwrap();
+            
+            let notification = serde_json::json!({
+                "jsonrpc": "2.0",
+                "method": "textDocument/didSave",
+                "params": {
+                    "textDocument": {
+                        "uri": uri
+                    }
                 }
             });
             
@@ -428,6 +703,496 @@ impl LSPServer {
         Ok(())
     }
     
+    pub async fn did_close(&mut self, uri: String) -> Result<()> {
+        self.symbol_cache.write().await.remove(&uri);
+        
+        if let Some(child) = &mut self.process {
+            let stdin = child.stdin.as_mut().unwrap();
+            
+            let notification = serde_json::json!({
+                "jsonrpc": "2.0",
+                "method": "textDocument/didClose",
+                "params": {
+                    "textDocument": {
+                        "uri": uri
+                    }
+                }
+            });
+            
+            Self::send_message(stdin, &notification).await?;
+        }
+        
+        Ok(())
+    }
+    
+    /// Sends a request and waits up to `REQUEST_TIMEOUT` for the response.
+    /// Returns `None` if the server isn't running or the channel closes, and
+    /// fails with `EditorError::LspTimeout` when no response arrives in time.
+    async fn send_request(&mut self, method: &str, params: serde_json::Value) -> Result<Option<serde_json::Value>> {
+        let Some(child) = &mut self.process else {
+            return Ok(None);
+        };
+        let stdin = child.stdin.as_mut().unwrap();
+        
+        let id = {
+            let mut request_id = self.request_id.write().await;
+            *request_id += 1;
+            *request_id
+        };
+        
+        let request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": method,
+            "params": params
+        });
+        
+        let (response_tx, response_rx) = oneshot::channel();
+        {
+            let mut pending = self.pending_requests.write().await;
+            pending.insert(id, response_tx);
+        }
+        
+        Self::send_message(stdin, &request).await?;
+        
+        match timeout(REQUEST_TIMEOUT, response_rx).await {
+            Ok(Ok(response)) => Ok(Some(response)),
+            Ok(Err(_)) => {
+                tracing::warn!("LSP {} response channel closed", method);
+                Ok(None)
+            }
+            Err(_) => {
+                let mut pending = self.pending_requests.write().await;
+                pending.remove(&id);
+                Err(Self::timed_out(method))
+            }
+        }
+    }
+    
+    fn timed_out(method: &str) -> anyhow::Error {
+        crate::EditorError::LspTimeout {
+            method: method.to_string(),
+            timeout: REQUEST_TIMEOUT,
+        }
+        .into()
+    }
+    
+    /// Turns a JSON-RPC `error` member into `EditorError::LspRequestFailed`,
+    /// so a failing server is not mistaken for one with no results.
+    fn check_response(method: &str, response: Option<serde_json::Value>) -> Result<Option<serde_json::Value>> {
+        match response.as_ref().and_then(|response| response.get("error")) {
+            Some(error) => Err(crate::EditorError::LspRequestFailed {
+                method: method.to_string(),
+                message: error
+                    .get("message")
+                    .and_then(|message| message.as_str())
+                    .map(str::to_string)
+                    .unwrap_or_else(|| error.to_string()),
+            }
+            .into()),
+            None => Ok(response),
+        }
+    }
+    
+    pub async fn definition(&mut self, uri: String, line: u32, character: u32) -> Result<Vec<LSPLocation>> {
+        let params = serde_json::json!({
+            "textDocument": {
+                "uri": uri
+            },
+            "position": {
+                "line": line,
+                "character": character
+            }
+        });
+        
+        let response = self.send_request("textDocument/definition", params).await?;
+        let response = Self::check_response("textDocument/definition", response)?;
+        Ok(response.map(Self::parse_locations).unwrap_or_default())
+    }
+    
+    pub async fn references(&mut self, uri: String, line: u32, character: u32, include_declaration: bool) -> Result<Vec<LSPLocation>> {
+        let params = serde_json::json!({
+            "textDocument": {
+                "uri": uri
+            },
+            "position": {
+                "line": line,
+                "character": character
+            },
+            "context": {
+                "includeDeclaration": include_declaration
+            }
+        });
+        
+        let response = self.send_request("textDocument/references", params).await?;
+        let response = Self::check_response("textDocument/references", response)?;
+        Ok(response.map(Self::parse_locations).unwrap_or_default())
+    }
+    
+    pub async fn format_document(&mut self, uri: String, tab_size: u32, insert_spaces: bool) -> Result<Vec<LSPTextEdit>> {
+        let params = serde_json::json!({
+            "textDocument": {
+                "uri": uri
+            },
+            "options": {
+                "tabSize": tab_size,
+                "insertSpaces": insert_spaces
+            }
+        });
+        
+        let Some(response) = self.send_request("textDocument/formatting", params).await? else {
+            return Ok(Vec::new());
+        };
+        
+        if let Some(error) = response.get("error") {
+            return Err(anyhow::anyhow!("LSP formatting failed: {}", error));
+        }
+        
+        match response.get("result") {
+            Some(serde_json::Value::Null) | None => Ok(Vec::new()),
+            Some(result) => Ok(serde_json::from_value(result.clone())?),
+        }
+    }
+    
+    /// Returns the rename's text edits grouped by document URI.
+    pub async fn rename(&mut self, uri: String, line: u32, character: u32, new_name: String) -> Result<HashMap<String, Vec<LSPTextEdit>>> {
+        let params = serde_json::json!({
+            "textDocument": {
+                "uri": uri
+            },
+            "position": {
+                "line": line,
+                "character": character
+            },
+            "newName": new_name
+        });
+        
+        let Some(response) = self.send_request("textDocument/rename", params).await? else {
+            return Err(anyhow::anyhow!("LSP rename request got no response"));
+        };
+        
+        if let Some(error) = response.get("error") {
+            return Err(anyhow::anyhow!("LSP rename failed: {}", error));
+        }
+        
+        match response.get("result") {
+            Some(serde_json::Value::Null) | None => Ok(HashMap::new()),
+            Some(result) => Self::parse_workspace_edit(result),
+        }
+    }
+    
+    /// Accepts both the `changes` map and the `documentChanges` array forms.
+    /// File create/rename/delete operations are rejected.
+    pub fn parse_workspace_edit(edit: &serde_json::Value) -> Result<HashMap<String, Vec<LSPTextEdit>>> {
+        let mut changes: HashMap<String, Vec<LSPTextEdit>> = HashMap::new();
+        
+        if let Some(document_changes) = edit.get("documentChanges").and_then(|d| d.as_array()) {
+            for change in document_changes {
+                if let Some(kind) = change.get("kind").and_then(|k| k.as_str()) {
+                    return Err(anyhow::anyhow!("Unsupported workspace edit operation: {}", kind));
+                }
+                
+                let uri = change.get("textDocument")
+                    .and_then(|doc| doc.get("uri"))
+                    .and_then(|uri| uri.as_str())
+                    .ok_or_else(|| anyhow::anyhow!("Text document edit is missing a URI"))?;
+                let edits: Vec<LSPTextEdit> = serde_json::from_value(change.get("edits").cloned().unwrap_or_default())?;
+                changes.entry(uri.to_string()).or_default().extend(edits);
+            }
+        } else if let Some(change_map) = edit.get("changes").and_then(|c| c.as_object()) {
+            for (uri, edits) in change_map {
+                let edits: Vec<LSPTextEdit> = serde_json::from_value(edits.clone())?;
+                changes.entry(uri.clone()).or_default().extend(edits);
+            }
+        }
+        
+        Ok(changes)
+    }
+    
+    /// Returns the document outline, served from cache until the document changes.
+    pub async fn document_symbols(&mut self, uri: String) -> Result<Vec<LSPDocumentSymbol>> {
+        if let Some(symbols) = self.symbol_cache.read().await.get(&uri) {
+            return Ok(symbols.clone());
+        }
+        
+        let params = serde_json::json!({
+            "textDocument": {
+                "uri": uri
+            }
+        });
+        
+        let Some(response) = self.send_request("textDocument/documentSymbol", params).await? else {
+            return Ok(Vec::new());
+        };
+        
+        // An error or null result is not cached, so the next request asks again
+        let Some(items) = response.get("result").and_then(|r| r.as_array()) else {
+            return Ok(Vec::new());
+        };
+        let symbols = Self::parse_document_symbols(items);
+        
+        self.symbol_cache.write().await.insert(uri, symbols.clone());
+        Ok(symbols)
+    }
+    
+    /// Hierarchical `DocumentSymbol[]` is used as-is; flat `SymbolInformation[]`
+    /// is nested by range containment.
+    fn parse_document_symbols(items: &[serde_json::Value]) -> Vec<LSPDocumentSymbol> {
+        fn parse_hierarchical(item: &serde_json::Value) -> Option<LSPDocumentSymbol> {
+            Some(LSPDocumentSymbol {
+                name: item.get("name")?.as_str()?.to_string(),
+                kind: item.get("kind")?.as_u64()? as u32,
+                range: serde_json::from_value(item.get("range")?.clone()).ok()?,
+                children: item.get("children")
+                    .and_then(|c| c.as_array())
+                    .map(|children| children.iter().filter_map(parse_hierarchical).collect())
+                    .unwrap_or_default(),
+            })
+        }
+        
+        if items.iter().any(|item| item.get("location").is_none()) {
+            return items.iter().filter_map(parse_hierarchical).collect();
+        }
+        
+        let mut flat: Vec<LSPDocumentSymbol> = items
+            .iter()
+            .filter_map(|item| {
+                Some(LSPDocumentSymbol {
+                    name: item.get("name")?.as_str()?.to_string(),
+                    kind: item.get("kind")?.as_u64()? as u32,
+                    range: serde_json::from_value(item.get("location")?.get("range")?.clone()).ok()?,
+                    children: Vec::new(),
+                })
+            })
+            .collect();
+        
+        // Outer symbols first so each one's container is already on the stack
+        let key = |position: &LSPPosition| (position.line, position.character);
+        flat.sort_by(|a, b| {
+            key(&a.range.start).cmp(&key(&b.range.start))
+                .then_with(|| key(&b.range.end).cmp(&key(&a.range.end)))
+        });
+        
+        fn attach(stack: &mut Vec<LSPDocumentSymbol>, roots: &mut Vec<LSPDocumentSymbol>) {
+            let symbol = stack.pop().expect("stack is not empty");
+            match stack.last_mut() {
+                Some(parent) => parent.children.push(symbol),
+                None => roots.push(symbol),
+            }
+        }
+        
+        let mut roots = Vec::new();
+        let mut stack: Vec<LSPDocumentSymbol> = Vec::new();
+        for symbol in flat {
+            while stack.last().is_some_and(|open| key(&open.range.end) < key(&symbol.range.end)) {
+                attach(&mut stack, &mut roots);
+            }
+            stack.push(symbol);
+        }
+        while !stack.is_empty() {
+            attach(&mut stack, &mut roots);
+        }
+        
+        roots
+    }
+    
+    /// Symbols across the whole workspace whose names match `query`, as the
+    /// server ranks them.
+    pub async fn workspace_symbols(&mut self, query: &str) -> Result<Vec<LSPWorkspaceSymbol>> {
+        if self.capabilities.read().await.get("workspaceSymbolProvider").is_none() {
+            return Ok(Vec::new());
+        }
+        
+        let params = serde_json::json!({
+            "query": query
+        });
+        
+        let Some(response) = self.send_request("workspace/symbol", params).await? else {
+            return Ok(Vec::new());
+        };
+        
+        if let Some(error) = response.get("error") {
+            return Err(anyhow::anyhow!("LSP workspace symbol search failed: {}", error));
+        }
+        
+        Ok(response.get("result")
+            .and_then(|r| r.as_array())
+            .map(|items| items.iter().filter_map(Self::parse_workspace_symbol).collect())
+            .unwrap_or_default())
+    }
+    
+    /// Accepts `SymbolInformation` and `WorkspaceSymbol`, whose location may
+    /// leave out the range; such symbols point at the start of the file.
+    fn parse_workspace_symbol(item: &serde_json::Value) -> Option<LSPWorkspaceSymbol> {
+        let location = item.get("location")?;
+        let range = match location.get("range") {
+            Some(range) => serde_json::from_value(range.clone()).ok()?,
+            None => LSPRange {
+                start: LSPPosition { line: 0, character: 0 },
+                end: LSPPosition { line: 0, character: 0 },
+            },
+        };
+        
+        Some(LSPWorkspaceSymbol {
+            name: item.get("name")?.as_str()?.to_string(),
+            kind: item.get("kind")?.as_u64()? as u32,
+            uri: location.get("uri")?.as_str()?.to_string(),
+            range,
+            container: item.get("containerName")
+                .and_then(|c| c.as_str())
+                .filter(|c| !c.is_empty())
+                .map(str::to_string),
+        })
+    }
+    
+    pub async fn signature_help(&mut self, uri: String, line: u32, character: u32) -> Result<Option<LSPSignatureHelp>> {
+        if self.capabilities.read().await.get("signatureHelpProvider").is_none() {
+            return Ok(None);
+        }
+        
+        let params = serde_json::json!({
+            "textDocument": {
+                "uri": uri
+            },
+            "position": {
+                "line": line,
+                "character": character
+            }
+        });
+        
+        let response = self.send_request("textDocument/signatureHelp", params).await?;
+        Ok(response.and_then(|response| Self::parse_signature_help(&response)))
+    }
+    
+    fn parse_signature_help(response: &serde_json::Value) -> Option<LSPSignatureHelp> {
+        let result = response.get("result")?;
+        let signatures = result.get("signatures")?.as_array()?;
+        let active_signature = result.get("activeSignature").and_then(|a| a.as_u64()).unwrap_or(0) as usize;
+        let signature = signatures.get(active_signature).or_else(|| signatures.first())?;
+        
+        let label = signature.get("label")?.as_str()?.to_string();
+        let label_utf16: Vec<u16> = label.encode_utf16().collect();
+        
+        // Parameter labels are either strings or [start, end) UTF-16 offsets into the label
+        let parameters = signature.get("parameters")
+            .and_then(|p| p.as_array())
+            .map(|parameters| {
+                parameters.iter().filter_map(|parameter| {
+                    match parameter.get("label")? {
+                        serde_json::Value::String(name) => Some(name.clone()),
+                        serde_json::Value::Array(offsets) => {
+                            let start = offsets.first()?.as_u64()? as usize;
+                            let end = offsets.get(1)?.as_u64()? as usize;
+                            Some(String::from_utf16_lossy(label_utf16.get(start..end)?))
+                        }
+                        _ => None,
+                    }
+                }).collect()
+            })
+            .unwrap_or_default();
+        
+        let documentation = signature.get("documentation").and_then(|doc| match doc {
+            serde_json::Value::String(text) => Some(text.clone()),
+            other => other.get("value").and_then(|v| v.as_str()).map(str::to_string),
+        });
+        
+        let active_parameter = signature.get("activeParameter")
+            .or_else(|| result.get("activeParameter"))
+            .and_then(|a| a.as_u64())
+            .map(|a| a as u32);
+        
+        Some(LSPSignatureHelp {
+            label,
+            documentation,
+            parameters,
+            active_parameter,
+        })
+    }
+    
+    pub async fn code_actions(&mut self, uri: String, range: LSPRange, diagnostics: Vec<LSPDiagnostic>) -> Result<Vec<LSPCodeAction>> {
+        let params = serde_json::json!({
+            "textDocument": {
+                "uri": uri
+            },
+            "range": range,
+            "context": {
+                "diagnostics": diagnostics
+            }
+        });
+        
+        let response = self.send_request("textDocument/codeAction", params).await?;
+        let response = Self::check_response("textDocument/codeAction", response)?;
+        let actions = response
+            .as_ref()
+            .and_then(|response| response.get("result"))
+            .and_then(|result| result.as_array())
+            .map(|items| items.iter().filter_map(LSPCodeAction::from_json).collect())
+            .unwrap_or_default();
+        
+        Ok(actions)
+    }
+    
+    /// Fills in a lazily-computed code action's edit. Returns the action unchanged
+    /// if the server doesn't support resolving.
+    pub async fn resolve_code_action(&mut self, action: LSPCodeAction) -> Result<LSPCodeAction> {
+        let supports_resolve = self.capabilities.read().await
+            .get("codeActionProvider")
+            .and_then(|provider| provider.get("resolveProvider"))
+            .and_then(|resolve| resolve.as_bool())
+            .unwrap_or(false);
+        if !supports_resolve {
+            return Ok(action);
+        }
+        
+        let response = self.send_request("codeAction/resolve", action.raw.clone()).await?;
+        Ok(response
+            .as_ref()
+            .and_then(|response| response.get("result"))
+            .and_then(LSPCodeAction::from_json)
+            .unwrap_or(action))
+    }
+    
+    pub async fn execute_command(&mut self, command: serde_json::Value) -> Result<()> {
+        let params = serde_json::json!({
+            "command": command.get("command").cloned().unwrap_or_default(),
+            "arguments": command.get("arguments").cloned().unwrap_or_else(|| serde_json::json!([]))
+        });
+        
+        if let Some(response) = self.send_request("workspace/executeCommand", params).await? {
+            if let Some(error) = response.get("error") {
+                return Err(anyhow::anyhow!("LSP command failed: {}", error));
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Accepts a single `Location`, a `Location[]`, a `LocationLink[]`, or null.
+    fn parse_locations(response: serde_json::Value) -> Vec<LSPLocation> {
+        let items = match response.get("result") {
+            Some(serde_json::Value::Array(items)) => items.clone(),
+            Some(serde_json::Value::Null) | None => Vec::new(),
+            Some(item) => vec![item.clone()],
+        };
+        
+        items
+            .iter()
+            .filter_map(|item| {
+                let (uri, range) = if let Some(target_uri) = item.get("targetUri") {
+                    let range = item.get("targetSelectionRange").or_else(|| item.get("targetRange"))?;
+                    (target_uri, range)
+                } else {
+                    (item.get("uri")?, item.get("range")?)
+                };
+                
+                Some(LSPLocation {
+                    uri: uri.as_str()?.to_string(),
+                    range: serde_json::from_value(range.clone()).ok()?,
+                })
+            })
+            .collect()
+    }
+    
     pub async fn completion(&mut self, uri: String, line: u32, character: u32) -> Result<Vec<LSPCompletionItem>> {
         if let Some(child) = &mut self.process {
             let stdin = child.stdin.as_mut().unwrap();
@@ -463,7 +1228,7 @@ impl LSPServer {
             Self::send_message(stdin, &request).await?;
             
             // Wait for response with timeout
-            match timeout(Duration::from_secs(5), response_rx).await {
+            match timeout(REQUEST_TIMEOUT, response_rx).await {
                 Ok(Ok(response)) => {
                     Self::parse_completion_response(response)
                 }
@@ -472,11 +1237,10 @@ impl LSPServer {
                     Ok(Vec::new())
                 }
                 Err(_) => {
-                    tracing::warn!("LSP completion request timed out");
                     // Clean up pending request
                     let mut pending = self.pending_requests.write().await;
                     pending.remove(&id);
-                    Ok(Vec::new())
+                    Err(Self::timed_out("textDocument/completion"))
                 }
             }
         } else {
@@ -542,7 +1306,7 @@ impl LSPServer {
             Self::send_message(stdin, &request).await?;
             
             // Wait for response with timeout
-            match timeout(Duration::from_secs(5), response_rx).await {
+            match timeout(REQUEST_TIMEOUT, response_rx).await {
                 Ok(Ok(response)) => {
                     Ok(Self::parse_hover_response(response)?)
                 }
@@ -551,11 +1315,10 @@ impl LSPServer {
                     Ok(None)
                 }
                 Err(_) => {
-                    tracing::warn!("LSP hover request timed out");
                     // Clean up pending request
                     let mut pending = self.pending_requests.write().await;
                     pending.remove(&id);
-                    Ok(None)
+                    Err(Self::timed_out("textDocument/hover"))
                 }
             }
         } else {
@@ -599,13 +1362,9 @@ impl LSPServer {
                     }
                     serde_json::Value::Object(obj) => {
                         // Handle single MarkupContent or MarkedString
-                        if let Some(kind) = obj.get("kind").and_then(|k| k.as_str()) {
+                        if obj.contains_key("kind") {
                             if let Some(value) = obj.get("value").and_then(|v| v.as_str()) {
-                                if kind == "markdown" {
-                                    contents.push(value.to_string());
-                                } else {
-                                    contents.push(value.to_string());
-                                }
+                                contents.push(value.to_string());
                             }
                         } else if let Some(language) = obj.get("language").and_then(|v| v.as_str()) {
                             if let Some(code_value) = obj.get("value").and_then(|v| v.as_str()) {
@@ -664,12 +1423,143 @@ impl LSPServer {
     }
 }
 
+/// Applies LSP text edits to `content`. Positions are in UTF-16 code units as
+/// the protocol specifies. Edits are applied from the end of the document
+/// backwards so earlier offsets stay valid.
+pub fn apply_text_edits(content: &str, edits: &[LSPTextEdit]) -> Result<String> {
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(content.match_indices('\n').map(|(index, _)| index + 1))
+        .collect();
+    
+    let mut ranges = Vec::with_capacity(edits.len());
+    for edit in edits {
+        let start = position_to_offset(content, &line_starts, &edit.range.start);
+        let end = position_to_offset(content, &line_starts, &edit.range.end);
+        if end < start {
+            return Err(anyhow::anyhow!("Invalid text edit range"));
+        }
+        ranges.push((start, end, edit.new_text.as_str()));
+    }
+    
+    // Stable sort keeps the server's order for edits sharing a start position
+    ranges.sort_by_key(|(start, _, _)| *start);
+    if ranges.windows(2).any(|pair| pair[0].1 > pair[1].0) {
+        return Err(anyhow::anyhow!("Overlapping text edits"));
+    }
+    
+    let mut result = content.to_string();
+    for (start, end, new_text) in ranges.into_iter().rev() {
+        result.replace_range(start..end, new_text);
+    }
+    
+    Ok(result)
+}
+
+/// Describes the change from `old` to `new` as a single replacement covering
+/// everything between their common prefix and suffix.
+pub fn compute_edit(old: &str, new: &str) -> LSPTextEdit {
+    let mut prefix = old
+        .char_indices()
+        .zip(new.chars())
+        .find(|((_, a), b)| a != b)
+        .map(|((index, _), _)| index)
+        .unwrap_or_else(|| old.len().min(new.len()));
+    // Never split a CRLF pair, so positions stay on line boundaries
+    if old[..prefix].ends_with('\r') {
+        prefix -= 1;
+    }
+    
+    let max_suffix = old.len().min(new.len()) - prefix;
+    let mut suffix = old[prefix..]
+        .chars()
+        .rev()
+        .zip(new[prefix..].chars().rev())
+        .take_while(|(a, b)| a == b)
+        .map(|(a, _)| a.len_utf8())
+        .scan(0, |total, len| {
+            *total += len;
+            Some(*total)
+        })
+        .take_while(|total| *total <= max_suffix)
+        .last()
+        .unwrap_or(0);
+    if old[old.len() - suffix..].starts_with('\n') && old[..old.len() - suffix].ends_with('\r') {
+        suffix -= 1;
+    }
+    
+    LSPTextEdit {
+        range: LSPRange {
+            start: offset_to_position(old, prefix),
+            end: offset_to_position(old, old.len() - suffix),
+        },
+        new_text: new[prefix..new.len() - suffix].to_string(),
+    }
+}
+
+fn offset_to_position(content: &str, offset: usize) -> LSPPosition {
+    let before = &content[..offset];
+    let line_start = before.rfind('\n').map(|index| index + 1).unwrap_or(0);
+    
+    LSPPosition {
+        line: before.matches('\n').count() as u32,
+        character: before[line_start..].encode_utf16().count() as u32,
+    }
+}
+
+/// Converts a `file://` URI to a path, decoding percent escapes.
+pub fn uri_to_path(uri: &str) -> Result<PathBuf> {
+    let encoded = uri
+        .strip_prefix("file://")
+        .ok_or_else(|| anyhow::anyhow!("Unsupported document URI: {}", uri))?;
+    
+    let bytes = encoded.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == b'%' {
+            let hex = bytes.get(index + 1..index + 3).and_then(|hex| std::str::from_utf8(hex).ok());
+            if let Some(byte) = hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                decoded.push(byte);
+                index += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[index]);
+        index += 1;
+    }
+    
+    Ok(PathBuf::from(String::from_utf8(decoded)?))
+}
+
+fn position_to_offset(content: &str, line_starts: &[usize], position: &LSPPosition) -> usize {
+    let Some(&line_start) = line_starts.get(position.line as usize) else {
+        return content.len();
+    };
+    
+    let line = content[line_start..].split('\n').next().unwrap_or("");
+    let line = line.strip_suffix('\r').unwrap_or(line);
+    
+    let mut units = 0;
+    for (index, ch) in line.char_indices() {
+        if units >= position.character {
+            return line_start + index;
+        }
+        units += ch.len_utf16() as u32;
+    }
+    
+    line_start + line.len()
+}
+
 pub struct LSPManager {
     servers: Arc<RwLock<HashMap<String, LSPServer>>>,
-    language_configs: HashMap<String, LSPConfig>,
+    language_configs: std::sync::RwLock<HashMap<String, LSPConfig>>,
+    server_logs: Arc<RwLock<HashMap<String, ServerLog>>>,
+    event_bus: Option<EventBus>,
+    /// Errors from the last build and the last test run.
+    build_diagnostics: BuildDiagnostics,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct LSPConfig {
     pub command: String,
     pub args: Vec<String>,
@@ -678,6 +1568,16 @@ pub struct LSPConfig {
 
 impl LSPManager {
     pub fn new() -> Self {
+        Self {
+            servers: Arc::new(RwLock::new(HashMap::new())),
+            language_configs: std::sync::RwLock::new(Self::builtin_configs()),
+            server_logs: Arc::new(RwLock::new(HashMap::new())),
+            event_bus: None,
+            build_diagnostics: Arc::new(RwLock::new(BTreeMap::new())),
+        }
+    }
+    
+    fn builtin_configs() -> HashMap<String, LSPConfig> {
         let mut language_configs = HashMap::new();
         
         // Rust LSP (rust-analyzer)
@@ -715,22 +1615,100 @@ impl LSPManager {
             file_extensions: vec!["go".to_string()],
         });
         
-        Self {
-            servers: Arc::new(RwLock::new(HashMap::new())),
-            language_configs,
+        language_configs
+    }
+    
+    /// Servers started after this publish their diagnostics on `event_bus`.
+    pub fn with_event_bus(mut self, event_bus: EventBus) -> Self {
+        self.event_bus = Some(event_bus);
+        self
+    }
+    
+    /// Built-in servers with the user's `Config::lsp_servers` applied on top.
+    pub fn with_overrides(overrides: &HashMap<String, LspServerConfig>) -> Self {
+        let manager = Self::new();
+        *manager.language_configs.write().unwrap_or_else(|e| e.into_inner()) = Self::configs_with_overrides(overrides);
+        manager
+    }
+    
+    fn configs_with_overrides(overrides: &HashMap<String, LspServerConfig>) -> HashMap<String, LSPConfig> {
+        let mut language_configs = Self::builtin_configs();
+        
+        for (language, server) in overrides {
+            if server.command.is_empty() {
+                language_configs.remove(language);
+                continue;
+            }
+            
+            let file_extensions = if server.file_extensions.is_empty() {
+                match language_configs.get(language) {
+                    Some(existing) => existing.file_extensions.clone(),
+                    None => {
+                        tracing::warn!("LSP server for {} has no file extensions; ignoring it", language);
+                        continue;
+                    }
+                }
+            } else {
+                server.file_extensions.clone()
+            };
+            
+            language_configs.insert(language.clone(), LSPConfig {
+                command: server.command.clone(),
+                args: server.args.clone(),
+                file_extensions,
+            });
+        }
+        
+        language_configs
+    }
+    
+    /// Replaces the user's server overrides, shutting down the running
+    /// servers whose configuration changed or was removed. Returns the
+    /// languages that changed; their documents need to be opened again to
+    /// start the new servers.
+    pub async fn set_overrides(&self, overrides: &HashMap<String, LspServerConfig>) -> Vec<String> {
+        let configs = Self::configs_with_overrides(overrides);
+        let changed: Vec<String> = {
+            let mut current = self.language_configs.write().unwrap_or_else(|e| e.into_inner());
+            let changed = current
+                .keys()
+                .chain(configs.keys())
+                .filter(|language| current.get(*language) != configs.get(*language))
+                .cloned()
+                .collect::<std::collections::HashSet<_>>()
+                .into_iter()
+                .collect();
+            *current = configs;
+            changed
+        };
+        
+        let mut servers = self.servers.write().await;
+        for language in &changed {
+            if let Some(mut server) = servers.remove(language) {
+                if let Err(e) = server.shutdown().await {
+                    tracing::warn!("Failed to shutdown LSP server for {}: {}", language, e);
+                }
+            }
         }
+        changed
     }
     
     pub async fn ensure_server(&self, language: &str, workspace_root: PathBuf) -> Result<()> {
         let mut servers = self.servers.write().await;
         
         if !servers.contains_key(language) {
-            if let Some(config) = self.language_configs.get(language) {
+            let config = self.language_configs.read().unwrap_or_else(|e| e.into_inner()).get(language).cloned();
+            if let Some(config) = config {
                 let mut server = LSPServer::new(
                     language.to_string(),
                     config.command.clone(),
                     config.args.clone(),
                 );
+                server.event_bus = self.event_bus.clone();
+                server.build_diagnostics = self.build_diagnostics.clone();
+                
+                // Keep the log even if startup fails so the failure can be inspected
+                self.server_logs.write().await.insert(language.to_string(), server.stderr_log.clone());
                 
                 // Try to start the server
                 match server.start(workspace_root).await {
@@ -740,6 +1718,8 @@ impl LSPManager {
                     }
                     Err(e) => {
                         tracing::warn!("Failed to start LSP server for {}: {}", language, e);
+                        let message = format!("Failed to start `{}`: {}", config.command, e);
+                        LSPServer::append_log(&server.stderr_log, message).await;
                     }
                 }
             }
@@ -758,11 +1738,31 @@ impl LSPManager {
         Ok(())
     }
     
-    pub async fn did_change_document(&self, uri: String, language: String, content: String, version: u64) -> Result<()> {
+    pub async fn did_change_document(&self, uri: String, language: String, content: String, edit: Option<LSPTextEdit>, version: u64) -> Result<()> {
         let mut servers = self.servers.write().await;
         
         if let Some(server) = servers.get_mut(&language) {
-            server.did_change(uri, content, version).await?;
+            server.did_change(uri, content, edit, version).await?;
+        }
+        
+        Ok(())
+    }
+    
+    pub async fn did_save_document(&self, uri: String, language: String) -> Result<()> {
+        let mut servers = self.servers.write().await;
+        
+        if let Some(server) = servers.get_mut(&language) {
+            server.did_save(uri).await?;
+        }
+        
+        Ok(())
+    }
+    
+    pub async fn did_close_document(&self, uri: String, language: String) -> Result<()> {
+        let mut servers = self.servers.write().await;
+        
+        if let Some(server) = servers.get_mut(&language) {
+            server.did_close(uri).await?;
         }
         
         Ok(())
@@ -788,6 +1788,134 @@ impl LSPManager {
         }
     }
     
+    pub async fn get_definition(&self, language: String, uri: String, line: u32, character: u32) -> Result<Vec<LSPLocation>> {
+        let mut servers = self.servers.write().await;
+        
+        if let Some(server) = servers.get_mut(&language) {
+            server.definition(uri, line, character).await
+        } else {
+            Ok(Vec::new())
+        }
+    }
+    
+    pub async fn get_references(&self, language: String, uri: String, line: u32, character: u32, include_declaration: bool) -> Result<Vec<LSPLocation>> {
+        let mut servers = self.servers.write().await;
+        
+        if let Some(server) = servers.get_mut(&language) {
+            server.references(uri, line, character, include_declaration).await
+        } else {
+            Ok(Vec::new())
+        }
+    }
+    
+    pub async fn format_document(&self, language: String, uri: String, tab_size: u32, insert_spaces: bool) -> Result<Vec<LSPTextEdit>> {
+        let mut servers = self.servers.write().await;
+        
+        if let Some(server) = servers.get_mut(&language) {
+            server.format_document(uri, tab_size, insert_spaces).await
+        } else {
+            Ok(Vec::new())
+        }
+    }
+    
+    pub async fn rename(&self, language: String, uri: String, line: u32, character: u32, new_name: String) -> Result<HashMap<String, Vec<LSPTextEdit>>> {
+        let mut servers = self.servers.write().await;
+        
+        if let Some(server) = servers.get_mut(&language) {
+            server.rename(uri, line, character, new_name).await
+        } else {
+            Err(anyhow::anyhow!("No language server running for {}", language))
+        }
+    }
+    
+    pub async fn get_document_symbols(&self, language: String, uri: String) -> Result<Vec<LSPDocumentSymbol>> {
+        let mut servers = self.servers.write().await;
+        
+        if let Some(server) = servers.get_mut(&language) {
+            server.document_symbols(uri).await
+        } else {
+            Ok(Vec::new())
+        }
+    }
+    
+    /// Asks every running server for symbols matching `query` and merges the
+    /// answers, dropping symbols more than one server reported, up to
+    /// `MAX_WORKSPACE_SYMBOLS`. A server that fails is skipped; with no
+    /// servers running the result is empty.
+    pub async fn workspace_symbols(&self, query: &str) -> Result<Vec<LSPWorkspaceSymbol>> {
+        let mut servers = self.servers.write().await;
+        
+        let mut seen = HashSet::new();
+        let mut symbols = Vec::new();
+        for (language, server) in servers.iter_mut() {
+            let found = match server.workspace_symbols(query).await {
+                Ok(found) => found,
+                Err(e) => {
+                    tracing::warn!("Workspace symbol search failed for {}: {}", language, e);
+                    continue;
+                }
+            };
+            
+            for symbol in found {
+                let key = (
+                    symbol.name.clone(),
+                    symbol.kind,
+                    symbol.uri.clone(),
+                    symbol.range.start.line,
+                    symbol.range.start.character,
+                );
+                if seen.insert(key) {
+                    symbols.push(symbol);
+                }
+                if symbols.len() >= MAX_WORKSPACE_SYMBOLS {
+                    return Ok(symbols);
+                }
+            }
+        }
+        
+        Ok(symbols)
+    }
+    
+    pub async fn get_signature_help(&self, language: String, uri: String, line: u32, character: u32) -> Result<Option<LSPSignatureHelp>> {
+        let mut servers = self.servers.write().await;
+        
+        if let Some(server) = servers.get_mut(&language) {
+            server.signature_help(uri, line, character).await
+        } else {
+            Ok(None)
+        }
+    }
+    
+    pub async fn get_code_actions(&self, language: String, uri: String, range: LSPRange, diagnostics: Vec<LSPDiagnostic>) -> Result<Vec<LSPCodeAction>> {
+        let mut servers = self.servers.write().await;
+        
+        if let Some(server) = servers.get_mut(&language) {
+            server.code_actions(uri, range, diagnostics).await
+        } else {
+            Ok(Vec::new())
+        }
+    }
+    
+    pub async fn resolve_code_action(&self, language: String, action: LSPCodeAction) -> Result<LSPCodeAction> {
+        let mut servers = self.servers.write().await;
+        
+        if let Some(server) = servers.get_mut(&language) {
+            server.resolve_code_action(action).await
+        } else {
+            Ok(action)
+        }
+    }
+    
+    pub async fn execute_command(&self, language: String, command: serde_json::Value) -> Result<()> {
+        let mut servers = self.servers.write().await;
+        
+        if let Some(server) = servers.get_mut(&language) {
+            server.execute_command(command).await?;
+        }
+        
+        Ok(())
+    }
+    
     pub async fn get_diagnostics(&self, language: &str, uri: &str) -> Option<Vec<LSPDiagnostic>> {
         let servers = self.servers.read().await;
         
@@ -798,6 +1926,61 @@ impl LSPManager {
         }
     }
     
+    /// The diagnostics every kind of run reported for `uri`.
+    pub async fn get_build_diagnostics(&self, uri: &str) -> Vec<LSPDiagnostic> {
+        build_diagnostics_for(&*self.build_diagnostics.read().await, uri)
+    }
+    
+    /// Replaces the diagnostics from the last run of kind `run` (such as
+    /// "build" or "test") with `diagnostics`, keyed by document URI, leaving
+    /// other kinds' alone. Publishes `editor.diagnostics.updated` for every
+    /// document that had or now has any from this kind of run. Those events,
+    /// like the ones the servers trigger, list the server's diagnostics before
+    /// those of the runs.
+    pub async fn set_build_diagnostics(&self, run: &str, diagnostics: HashMap<String, Vec<LSPDiagnostic>>) {
+        let previous = self
+            .build_diagnostics
+            .write()
+            .await
+            .insert(run.to_string(), diagnostics.clone())
+            .unwrap_or_default();
+        let Some(event_bus) = &self.event_bus else {
+            return;
+        };
+        
+        let uris: HashSet<&String> = previous.keys().chain(diagnostics.keys()).collect();
+        for uri in uris {
+            let language = std::path::Path::new(uri)
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .and_then(|extension| self.get_language_for_extension(extension));
+            let mut merged = match &language {
+                Some(language) => self.get_diagnostics(language, uri).await.unwrap_or_default(),
+                None => Vec::new(),
+            };
+            merged.extend(self.get_build_diagnostics(uri).await);
+            
+            let event = Event::new(
+                "editor.diagnostics.updated",
+                "editor-manager",
+                serde_json::json!({
+                    "uri": uri,
+                    "language": language,
+                    "diagnostics": merged
+                }),
+            );
+            let _ = event_bus.publish(event);
+        }
+    }
+    
+    /// Recent stderr output from a language's server, oldest first.
+    pub async fn get_server_log(&self, language: &str) -> Vec<String> {
+        match self.server_logs.read().await.get(language) {
+            Some(log) => log.read().await.iter().cloned().collect(),
+            None => Vec::new(),
+        }
+    }
+    
     pub async fn shutdown_all(&self) -> Result<()> {
         let mut servers = self.servers.write().await;
         
@@ -812,11 +1995,17 @@ impl LSPManager {
     }
     
     pub fn get_language_for_extension(&self, extension: &str) -> Option<String> {
-        for (language, config) in &self.language_configs {
+        for (language, config) in self.language_configs.read().unwrap_or_else(|e| e.into_inner()).iter() {
             if config.file_extensions.contains(&extension.to_string()) {
                 return Some(language.clone());
             }
         }
         None
     }
+}
+
+impl Default for LSPManager {
+    fn default() -> Self {
+        Self::new()
+    }
 }
\ No newline at end of file
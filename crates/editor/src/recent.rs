@@ -0,0 +1,126 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, Weak};
+
+/// Entries kept in the recently opened list; the oldest fall off.
+pub const MAX_RECENT_FILES: usize = 50;
+/// File in the app data directory holding the recently opened list.
+pub const RECENT_FILES_FILE: &str = "recent-files.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecentFile {
+    pub path: PathBuf,
+    pub opened_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Files opened in the editor, most recent first, saved after every change.
+pub struct RecentFiles {
+    entries: Mutex<Vec<RecentFile>>,
+    file: Option<PathBuf>,
+}
+
+impl RecentFiles {
+    /// Restores the list saved in `file`, starting empty if it cannot be
+    /// read. With no file the list is kept in memory only.
+    pub fn load(file: Option<PathBuf>) -> Self {
+        let entries = file
+            .as_deref()
+            .filter(|file| file.exists())
+            .map(|file| -> Result<Vec<RecentFile>> { Ok(serde_json::from_str(&std::fs::read_to_string(file)?)?) })
+            .transpose()
+            .unwrap_or_else(|e| {
+                tracing::warn!("Failed to load recent files: {}", e);
+                None
+            })
+            .unwrap_or_default();
+        
+        Self {
+            entries: Mutex::new(entries),
+            file,
+        }
+    }
+    
+    /// Moves `path` to the front of the list.
+    pub fn record(&self, path: &Path) {
+        self.update(|entries| {
+            entries.retain(|entry| entry.path != path);
+            entries.insert(0, RecentFile {
+                path: path.to_path_buf(),
+                opened_at: chrono::Utc::now(),
+            });
+            entries.truncate(MAX_RECENT_FILES);
+        });
+    }
+    
+    /// Drops `path` and, for a directory, everything below it.
+    pub fn remove(&self, path: &Path) {
+        self.update(|entries| entries.retain(|entry| !entry.path.starts_with(path)));
+    }
+    
+    pub fn clear(&self) {
+        self.update(Vec::clear);
+    }
+    
+    /// The recently opened files that still exist, most recent first.
+    pub fn list(&self) -> Vec<RecentFile> {
+        self.entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .filter(|entry| entry.path.is_file())
+            .cloned()
+            .collect()
+    }
+    
+    fn update(&self, change: impl FnOnce(&mut Vec<RecentFile>)) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        change(&mut entries);
+        self.persist(&entries);
+    }
+    
+    /// Writes the list through a temporary file. Failures are logged rather
+    /// than failing the editor operation that changed the list.
+    fn persist(&self, entries: &[RecentFile]) {
+        let Some(file) = &self.file else {
+            return;
+        };
+        
+        let result = serde_json::to_string_pretty(entries)
+            .map_err(anyhow::Error::from)
+            .and_then(|json| {
+                let temp_path = file.with_extension("json.tmp");
+                std::fs::write(&temp_path, json)?;
+                std::fs::rename(&temp_path, file)?;
+                Ok(())
+            });
+        if let Err(e) = result {
+            tracing::warn!("Failed to save recent files: {}", e);
+        }
+    }
+}
+
+/// Drops files from the list as `editor.file.deleted` and
+/// `editor.file.renamed` report them gone. Ends once the list is dropped.
+pub(crate) async fn track_removals(recent_files: Weak<RecentFiles>, event_bus: code_furnace_events::EventBus) {
+    let mut events = event_bus.subscribe(Some("editor.file.*"));
+    loop {
+        let event = match events.recv().await {
+            Ok(event) => event,
+            Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(tokio::sync::broadcast::error::RecvError::Closed) => return,
+        };
+        let removed = match event.event_type.as_str() {
+            "editor.file.deleted" => &event.data["path"],
+            "editor.file.renamed" => &event.data["old_path"],
+            _ => continue,
+        };
+        
+        let Some(recent_files) = recent_files.upgrade() else {
+            return;
+        };
+        if let Some(path) = removed.as_str() {
+            recent_files.remove(Path::new(path));
+        }
+    }
+}
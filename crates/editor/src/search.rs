@@ -0,0 +1,222 @@
+use anyhow::Result;
+use ignore::WalkState;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use crate::FileTreeFilter;
+
+pub const DEFAULT_MAX_RESULTS: usize = 1000;
+const MAX_PREVIEW_CHARS: usize = 200;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchOptions {
+    /// Treat the query as a regular expression instead of literal text.
+    pub regex: bool,
+    pub case_sensitive: bool,
+    pub whole_word: bool,
+    pub max_results: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchMatch {
+    pub path: PathBuf,
+    /// 1-based line number.
+    pub line: usize,
+    /// 1-based column, in characters.
+    pub column: usize,
+    pub preview: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SearchResults {
+    pub matches: Vec<SearchMatch>,
+    /// The result cap was reached before the walk finished.
+    pub truncated: bool,
+    pub cancelled: bool,
+}
+
+pub fn build_matcher(query: &str, options: &SearchOptions) -> Result<Regex> {
+    if query.is_empty() {
+        return Err(anyhow::anyhow!("Search query cannot be empty"));
+    }
+    
+    let mut pattern = if options.regex {
+        query.to_string()
+    } else {
+        regex::escape(query)
+    };
+    
+    if options.whole_word {
+        pattern = format!(r"\b(?:{})\b", pattern);
+    }
+    
+    Ok(RegexBuilder::new(&pattern)
+        .case_insensitive(!options.case_sensitive)
+        .build()?)
+}
+
+/// Walks `root` on all cores, stopping early once `max_results` matches are
+/// found or `cancelled` is set. Blocking; call from `spawn_blocking`.
+pub fn search_workspace(
+    root: &Path,
+    filter: &FileTreeFilter,
+    matcher: &Regex,
+    max_results: usize,
+    cancelled: &AtomicBool,
+) -> SearchResults {
+    let matches = Mutex::new(Vec::new());
+    let found = AtomicUsize::new(0);
+    let truncated = AtomicBool::new(false);
+    
+    filter.walker(root).build_parallel().run(|| {
+        Box::new(|entry| {
+            if cancelled.load(Ordering::Relaxed) || truncated.load(Ordering::Relaxed) {
+                return WalkState::Quit;
+            }
+            
+            let Ok(entry) = entry else {
+                return WalkState::Continue;
+            };
+            if !entry.file_type().is_some_and(|file_type| file_type.is_file()) {
+                return WalkState::Continue;
+            }
+            
+            // Binary and non-UTF-8 files are skipped
+            let Ok(content) = std::fs::read_to_string(entry.path()) else {
+                return WalkState::Continue;
+            };
+            
+            let mut file_matches = Vec::new();
+            for (index, line) in content.lines().enumerate() {
+                for found_match in matcher.find_iter(line) {
+                    file_matches.push(SearchMatch {
+                        path: entry.path().to_path_buf(),
+                        line: index + 1,
+                        column: line[..found_match.start()].chars().count() + 1,
+                        preview: line.trim().chars().take(MAX_PREVIEW_CHARS).collect(),
+                    });
+                }
+            }
+            
+            if file_matches.is_empty() {
+                return WalkState::Continue;
+            }
+            
+            let previous = found.fetch_add(file_matches.len(), Ordering::Relaxed);
+            if previous >= max_results {
+                truncated.store(true, Ordering::Relaxed);
+                return WalkState::Quit;
+            }
+            if previous + file_matches.len() > max_results {
+                file_matches.truncate(max_results - previous);
+                truncated.store(true, Ordering::Relaxed);
+            }
+            
+            matches.lock().unwrap().extend(file_matches);
+            WalkState::Continue
+        })
+    });
+    
+    let mut matches = matches.into_inner().unwrap();
+    matches.sort_by(|a, b| (&a.path, a.line, a.column).cmp(&(&b.path, b.line, b.column)));
+    
+    SearchResults {
+        matches,
+        truncated: truncated.into_inner(),
+        cancelled: cancelled.load(Ordering::Relaxed),
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReplaceOptions {
+    #[serde(flatten)]
+    pub search: SearchOptions,
+    /// Report the planned edits without changing any buffer or file.
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlannedEdit {
+    /// 1-based line number.
+    pub line: usize,
+    pub before: String,
+    pub after: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileReplacement {
+    pub path: PathBuf,
+    pub buffer_id: Option<uuid::Uuid>,
+    pub replacements: usize,
+    pub edits: Vec<PlannedEdit>,
+    /// The file is open read-only, so its matches were left as they are.
+    pub read_only: bool,
+}
+
+/// Applies `replacement` line by line so planned edits and the rewritten
+/// content always agree. `$1`/`${name}` capture references are expanded only
+/// for regex queries; literal queries insert the replacement verbatim.
+pub fn replace_lines(
+    content: &str,
+    matcher: &Regex,
+    replacement: &str,
+    expand_captures: bool,
+) -> (String, usize, Vec<PlannedEdit>) {
+    let mut output = String::with_capacity(content.len());
+    let mut replacements = 0;
+    let mut edits = Vec::new();
+    
+    for (index, segment) in content.split_inclusive('\n').enumerate() {
+        let body = segment.trim_end_matches(['\n', '\r']);
+        let ending = &segment[body.len()..];
+        
+        let count = matcher.find_iter(body).count();
+        if count == 0 {
+            output.push_str(segment);
+            continue;
+        }
+        
+        let replaced = if expand_captures {
+            matcher.replace_all(body, replacement)
+        } else {
+            matcher.replace_all(body, regex::NoExpand(replacement))
+        };
+        
+        replacements += count;
+        edits.push(PlannedEdit {
+            line: index + 1,
+            before: body.to_string(),
+            after: replaced.to_string(),
+        });
+        output.push_str(&replaced);
+        output.push_str(ending);
+    }
+    
+    (output, replacements, edits)
+}
+
+/// Writes through a temporary sibling file and a rename so readers never see a
+/// partially written file.
+pub fn write_atomic(path: &Path, content: &str) -> Result<()> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| anyhow::anyhow!("Invalid file path: {}", path.display()))?;
+    let temp_path = path.with_file_name(format!(".{}.tmp-{}", file_name.to_string_lossy(), uuid::Uuid::new_v4()));
+    
+    std::fs::write(&temp_path, content)?;
+    if let Ok(metadata) = std::fs::metadata(path) {
+        let _ = std::fs::set_permissions(&temp_path, metadata.permissions());
+    }
+    
+    if let Err(e) = std::fs::rename(&temp_path, path) {
+        let _ = std::fs::remove_file(&temp_path);
+        return Err(e.into());
+    }
+    
+    Ok(())
+}
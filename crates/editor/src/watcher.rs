@@ -0,0 +1,185 @@
+use anyhow::Result;
+use notify::event::ModifyKind;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
+use tokio::time::{timeout, Duration};
+
+use crate::FileTreeFilter;
+
+/// Watches the directories containing open buffers and reports changed files in
+/// debounced batches. Directories are watched rather than files so that tools
+/// which replace files via rename (git, many formatters) are still observed.
+pub struct FileWatcher {
+    watcher: RecommendedWatcher,
+    watched_dirs: HashMap<PathBuf, usize>,
+}
+
+impl FileWatcher {
+    pub fn new(debounce: Duration) -> Result<(Self, mpsc::UnboundedReceiver<Vec<PathBuf>>)> {
+        let (raw_tx, mut raw_rx) = mpsc::unbounded_channel::<PathBuf>();
+        let (batch_tx, batch_rx) = mpsc::unbounded_channel::<Vec<PathBuf>>();
+        
+        let watcher = notify::recommended_watcher(move |result: notify::Result<notify::Event>| {
+            match result {
+                Ok(event) => {
+                    if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                        for path in event.paths {
+                            let _ = raw_tx.send(path);
+                        }
+                    }
+                }
+                Err(e) => tracing::warn!("File watcher error: {}", e),
+            }
+        })?;
+        
+        // Coalesce bursts of events (editors and git often write several times)
+        tokio::spawn(async move {
+            while let Some(first) = raw_rx.recv().await {
+                let mut changed = HashSet::new();
+                changed.insert(first);
+                
+                while let Ok(Some(path)) = timeout(debounce, raw_rx.recv()).await {
+                    changed.insert(path);
+                }
+                
+                if batch_tx.send(changed.into_iter().collect()).is_err() {
+                    break;
+                }
+            }
+        });
+        
+        Ok((
+            Self {
+                watcher,
+                watched_dirs: HashMap::new(),
+            },
+            batch_rx,
+        ))
+    }
+    
+    /// Normalized path used to match watcher events against buffer paths.
+    pub fn watch_key(path: &Path) -> Option<PathBuf> {
+        let parent = path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+        let dir = parent.canonicalize().ok()?;
+        Some(dir.join(path.file_name()?))
+    }
+    
+    pub fn watch(&mut self, path: &Path) -> Result<()> {
+        let dir = Self::watch_key(path)
+            .and_then(|key| key.parent().map(Path::to_path_buf))
+            .ok_or_else(|| anyhow::anyhow!("Cannot watch path: {}", path.display()))?;
+        
+        let count = self.watched_dirs.entry(dir.clone()).or_insert(0);
+        if *count == 0 {
+            self.watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+        }
+        *count += 1;
+        
+        Ok(())
+    }
+    
+    pub fn unwatch(&mut self, path: &Path) -> Result<()> {
+        let Some(dir) = Self::watch_key(path).and_then(|key| key.parent().map(Path::to_path_buf)) else {
+            return Ok(());
+        };
+        
+        if let Some(count) = self.watched_dirs.get_mut(&dir) {
+            *count -= 1;
+            if *count == 0 {
+                self.watched_dirs.remove(&dir);
+                self.watcher.unwatch(&dir)?;
+            }
+        }
+        
+        Ok(())
+    }
+}
+
+/// Watches a whole directory tree and reports, in debounced bursts, that
+/// files were created, removed or renamed in it. Content changes are not
+/// reported. Only the directories `filter` shows in the file tree are
+/// watched, one by one, so ignored trees such as `target/` or
+/// `node_modules/` neither use up watches nor cause bursts, and new files the
+/// tree would hide are not reported either.
+pub struct TreeWatcher {
+    _watcher: Arc<Mutex<RecommendedWatcher>>,
+}
+
+impl TreeWatcher {
+    /// Walks the whole tree to set up the watches, so call it off the async
+    /// runtime's worker threads for large trees.
+    pub fn new(root: &Path, filter: FileTreeFilter, debounce: Duration) -> Result<(Self, mpsc::UnboundedReceiver<()>)> {
+        // Each changed path, and whether it may have just appeared
+        let (raw_tx, mut raw_rx) = mpsc::unbounded_channel::<(PathBuf, bool)>();
+        let (burst_tx, burst_rx) = mpsc::unbounded_channel::<()>();
+        
+        let watcher = notify::recommended_watcher(move |result: notify::Result<notify::Event>| {
+            match result {
+                Ok(event) => {
+                    let appeared = match event.kind {
+                        EventKind::Create(_) | EventKind::Modify(ModifyKind::Name(_)) => true,
+                        EventKind::Remove(_) => false,
+                        _ => return,
+                    };
+                    for path in event.paths {
+                        let _ = raw_tx.send((path, appeared));
+                    }
+                }
+                Err(e) => tracing::warn!("Workspace watcher error: {}", e),
+            }
+        })?;
+        let watcher = Arc::new(Mutex::new(watcher));
+        watch_shown_dirs(&watcher, &filter, root);
+        
+        // The task only holds the watcher weakly, so dropping the
+        // `TreeWatcher` stops the events and with them the task
+        let weak_watcher = Arc::downgrade(&watcher);
+        tokio::spawn(async move {
+            while let Some(first) = raw_rx.recv().await {
+                let mut changes = vec![first];
+                while let Ok(Some(change)) = timeout(debounce, raw_rx.recv()).await {
+                    changes.push(change);
+                }
+                
+                let mut shown = false;
+                for (path, appeared) in changes {
+                    if appeared && path.exists() {
+                        if !filter.shows(&path) {
+                            continue;
+                        }
+                        if path.is_dir() {
+                            if let Some(watcher) = weak_watcher.upgrade() {
+                                watch_shown_dirs(&watcher, &filter, &path);
+                            }
+                        }
+                    }
+                    shown = true;
+                }
+                
+                if shown && burst_tx.send(()).is_err() {
+                    break;
+                }
+            }
+        });
+        
+        Ok((Self { _watcher: watcher }, burst_rx))
+    }
+}
+
+/// Watches `dir` and every directory below it that `filter` shows.
+fn watch_shown_dirs(watcher: &Mutex<RecommendedWatcher>, filter: &FileTreeFilter, dir: &Path) {
+    let mut watcher = watcher.lock().unwrap();
+    let dirs = filter
+        .walker(dir)
+        .build()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_some_and(|file_type| file_type.is_dir()));
+    for entry in dirs {
+        if let Err(e) = watcher.watch(entry.path(), RecursiveMode::NonRecursive) {
+            tracing::warn!("Failed to watch {}: {}", entry.path().display(), e);
+        }
+    }
+}
@@ -0,0 +1,30 @@
+mod common;
+
+use code_furnace_editor::EditorManager;
+use code_furnace_events::EventBus;
+use code_furnace_utils::Config;
+use std::time::Duration;
+use common::TempDir;
+
+#[tokio::test]
+async fn edits_are_saved_once_they_go_idle() {
+    let dir = TempDir::new("auto-save");
+    let path = dir.join("notes.txt");
+    std::fs::write(&path, "first").unwrap();
+    
+    let config = Config {
+        auto_save: true,
+        auto_save_delay_ms: 50,
+        ..Config::default()
+    };
+    let event_bus = EventBus::new().with_event_log(20);
+    let editor = EditorManager::with_data_dir(event_bus.clone(), config, Some(dir.to_path_buf()));
+    let buffer_id = editor.open_file(path.clone()).await.unwrap();
+    editor.update_buffer(buffer_id, "second".to_string()).await.unwrap();
+    editor.update_buffer(buffer_id, "third".to_string()).await.unwrap();
+    
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "third");
+    assert!(!editor.get_buffer(buffer_id).await.unwrap().modified);
+    assert_eq!(event_bus.replay(None, Some("editor.file.autosaved")).len(), 1);
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use code_furnace_editor::lsp::LSPPosition;
+use code_furnace_editor::{EditorManager, LSPDiagnostic, LSPRange};
+use code_furnace_events::EventBus;
+use code_furnace_utils::Config;
+use std::collections::HashMap;
+use common::TempDir;
+
+fn diagnostic(message: &str) -> LSPDiagnostic {
+    LSPDiagnostic {
+        range: LSPRange {
+            start: LSPPosition { line: 0, character: 0 },
+            end: LSPPosition { line: 0, character: 1 },
+        },
+        severity: 1,
+        message: message.to_string(),
+        source: Some("cargo".to_string()),
+        code: None,
+    }
+}
+
+#[tokio::test]
+async fn build_diagnostics_show_until_replaced() {
+    let dir = TempDir::new("build");
+    let path = dir.join("notes.txt");
+    std::fs::write(&path, "x").unwrap();
+    let uri = format!("file://{}", path.to_string_lossy());
+    
+    let event_bus = EventBus::new().with_event_log(20);
+    let editor = EditorManager::with_data_dir(event_bus.clone(), Config::default(), Some(dir.to_path_buf()));
+    let buffer_id = editor.open_file(path).await.unwrap();
+    assert!(editor.get_diagnostics(buffer_id).await.is_none());
+    
+    editor.set_build_diagnostics("build", HashMap::from([(uri.clone(), vec![diagnostic("cannot find `x`")])])).await;
+    let diagnostics = editor.get_diagnostics(buffer_id).await.unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].source.as_deref(), Some("cargo"));
+    
+    editor.set_build_diagnostics("build", HashMap::new()).await;
+    assert!(editor.get_diagnostics(buffer_id).await.is_none());
+    
+    let events = event_bus.replay(None, Some("editor.diagnostics.updated"));
+    let counts: Vec<_> = events
+        .iter()
+        .map(|event| (event.data["uri"].as_str().unwrap(), event.data["diagnostics"].as_array().unwrap().len()))
+        .collect();
+    assert_eq!(counts, vec![(uri.as_str(), 1), (uri.as_str(), 0)]);
+}
+
+#[tokio::test]
+async fn test_runs_and_builds_keep_their_own_diagnostics() {
+    let dir = TempDir::new("build");
+    let path = dir.join("notes.txt");
+    std::fs::write(&path, "x").unwrap();
+    let uri = format!("file://{}", path.to_string_lossy());
+    
+    let event_bus = EventBus::new().with_event_log(20);
+    let editor = EditorManager::with_data_dir(event_bus.clone(), Config::default(), Some(dir.to_path_buf()));
+    let buffer_id = editor.open_file(path).await.unwrap();
+    
+    editor.set_build_diagnostics("build", HashMap::from([(uri.clone(), vec![diagnostic("unused variable")])])).await;
+    editor.set_build_diagnostics("test", HashMap::from([(uri.clone(), vec![diagnostic("assertion failed")])])).await;
+    let messages = |diagnostics: Vec<LSPDiagnostic>| diagnostics.into_iter().map(|d| d.message).collect::<Vec<_>>();
+    assert_eq!(
+        messages(editor.get_diagnostics(buffer_id).await.unwrap()),
+        vec!["unused variable", "assertion failed"]
+    );
+    
+    // A passing test run clears only what the last test run reported
+    editor.set_build_diagnostics("test", HashMap::new()).await;
+    assert_eq!(messages(editor.get_diagnostics(buffer_id).await.unwrap()), vec!["unused variable"]);
+    
+    let events = event_bus.replay(None, Some("editor.diagnostics.updated"));
+    let last = events.last().unwrap();
+    assert_eq!(last.data["uri"], serde_json::json!(uri));
+    assert_eq!(last.data["diagnostics"].as_array().unwrap().len(), 1);
+}
@@ -0,0 +1,35 @@
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+/// A fresh directory under the system temp dir, removed when dropped so a
+/// failing test does not leave it behind.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    /// Creates `code-furnace-{name}-{uuid}`.
+    pub fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("code-furnace-{}-{}", name, uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+    
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
@@ -0,0 +1,24 @@
+mod common;
+
+use code_furnace_editor::EditorManager;
+use code_furnace_events::EventBus;
+use code_furnace_utils::Config;
+use common::TempDir;
+
+#[tokio::test]
+async fn creating_a_file_never_replaces_one() {
+    let dir = TempDir::new("create");
+    let editor = EditorManager::with_data_dir(EventBus::new(), Config::default(), Some(dir.to_path_buf()));
+    
+    let path = dir.join("Canvas.tsx");
+    editor.create_file(path.clone(), Some("generated".to_string())).await.unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "generated");
+    
+    std::fs::write(&path, "user edits").unwrap();
+    let error = editor.create_file(path.clone(), Some("regenerated".to_string())).await.unwrap_err();
+    assert_eq!(
+        error.downcast_ref::<std::io::Error>().map(|e| e.kind()),
+        Some(std::io::ErrorKind::AlreadyExists)
+    );
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "user edits");
+}
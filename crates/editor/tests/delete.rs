@@ -0,0 +1,29 @@
+mod common;
+
+use code_furnace_editor::EditorManager;
+use code_furnace_events::EventBus;
+use code_furnace_utils::Config;
+use common::TempDir;
+
+#[tokio::test]
+async fn deleting_a_directory_closes_its_open_buffers() {
+    let dir = TempDir::new("delete");
+    std::fs::create_dir_all(dir.join("src/nested")).unwrap();
+    std::fs::write(dir.join("src/main.txt"), "main").unwrap();
+    std::fs::write(dir.join("src/nested/util.txt"), "util").unwrap();
+    std::fs::write(dir.join("outside.txt"), "outside").unwrap();
+    
+    let event_bus = EventBus::new().with_event_log(20);
+    let editor = EditorManager::with_data_dir(event_bus.clone(), Config::default(), Some(dir.to_path_buf()));
+    let main = editor.open_file(dir.join("src/main.txt")).await.unwrap();
+    let util = editor.open_file(dir.join("src/nested/util.txt")).await.unwrap();
+    let outside = editor.open_file(dir.join("outside.txt")).await.unwrap();
+    
+    editor.delete_file(dir.join("src")).await.unwrap();
+    
+    assert!(editor.get_buffer(main).await.is_none());
+    assert!(editor.get_buffer(util).await.is_none());
+    assert!(editor.get_buffer(outside).await.is_some());
+    assert_eq!(event_bus.replay(None, Some("editor.buffer.closed")).len(), 2);
+    assert!(!dir.join("src").exists());
+}
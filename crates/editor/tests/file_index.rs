@@ -0,0 +1,48 @@
+mod common;
+
+use code_furnace_editor::finder::FileIndexCache;
+use code_furnace_editor::{EditorManager, FileTreeFilter};
+use code_furnace_events::EventBus;
+use code_furnace_utils::Config;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::Duration;
+use common::TempDir;
+
+#[tokio::test]
+async fn files_added_outside_the_editor_show_up_in_quick_open() {
+    let dir = TempDir::new("index");
+    std::fs::create_dir_all(dir.join("src")).unwrap();
+    std::fs::write(dir.join("src/main.rs"), "").unwrap();
+    
+    let editor = EditorManager::with_data_dir(EventBus::new(), Config::default(), Some(dir.to_path_buf()));
+    editor.set_workspace_root(dir.to_path_buf()).await.unwrap();
+    assert_eq!(editor.find_files("helper", 10).await.unwrap().matches.len(), 0);
+    
+    // As a terminal or git would, without going through the editor
+    std::fs::write(dir.join("src/helper.rs"), "").unwrap();
+    let mut found = false;
+    for _ in 0..50 {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let matches = editor.find_files("helper", 10).await.unwrap().matches;
+        if matches.iter().any(|file| file.relative_path == "src/helper.rs") {
+            found = true;
+            break;
+        }
+    }
+    assert!(found, "the new file never appeared in the index");
+}
+
+#[tokio::test]
+async fn a_cancelled_build_returns_no_index() {
+    let dir = TempDir::new("index");
+    std::fs::write(dir.join("main.rs"), "").unwrap();
+    let filter = FileTreeFilter::new(&dir, &Config::default()).unwrap();
+    let cache = FileIndexCache::default();
+    
+    let cancelled = Arc::new(AtomicBool::new(true));
+    assert!(cache.get_or_build(dir.to_path_buf(), filter.clone(), cancelled).await.unwrap().is_none());
+    
+    let index = cache.get_or_build(dir.to_path_buf(), filter, Arc::new(AtomicBool::new(false))).await.unwrap();
+    assert_eq!(index.map(|index| index.len()), Some(1));
+}
@@ -0,0 +1,41 @@
+mod common;
+
+use code_furnace_editor::{EditorManager, FileTreeNode};
+use code_furnace_events::EventBus;
+use code_furnace_utils::Config;
+use common::TempDir;
+
+fn names(node: &FileTreeNode) -> Vec<&str> {
+    node.children.iter().map(|child| child.name.as_str()).collect()
+}
+
+#[tokio::test]
+async fn the_tree_loads_one_level_per_expansion() {
+    let dir = TempDir::new("tree");
+    let deepest = dir.join("l1/l2/l3/l4/l5");
+    std::fs::create_dir_all(&deepest).unwrap();
+    std::fs::write(deepest.join("leaf.txt"), "leaf").unwrap();
+    std::fs::write(dir.join("l1/l2/b.txt"), "").unwrap();
+    std::fs::write(dir.join("l1/l2/a.txt"), "").unwrap();
+    
+    let config = Config {
+        file_tree_max_depth: 4,
+        ..Config::default()
+    };
+    let editor = EditorManager::with_data_dir(EventBus::new(), config, Some(dir.to_path_buf()));
+    editor.set_workspace_root(dir.to_path_buf()).await.unwrap();
+    
+    let root = editor.get_file_tree().await.unwrap().unwrap();
+    assert_eq!(names(&root), vec!["l1"]);
+    assert!(!root.children[0].children_loaded);
+    assert!(root.children[0].children.is_empty());
+    
+    // Directories first, then files, each alphabetically
+    let l2 = editor.expand_directory(dir.join("l1/l2")).await.unwrap().unwrap();
+    assert_eq!(names(&l2), vec!["l3", "a.txt", "b.txt"]);
+    assert!(!l2.children[0].children_loaded);
+    
+    let l4 = editor.expand_directory(dir.join("l1/l2/l3/l4")).await.unwrap().unwrap();
+    assert_eq!(names(&l4), vec!["l5"]);
+    assert!(editor.expand_directory(dir.join("l1/l2/l3/l4/l5")).await.is_err());
+}
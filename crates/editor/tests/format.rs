@@ -0,0 +1,30 @@
+mod common;
+
+use code_furnace_editor::EditorManager;
+use code_furnace_events::EventBus;
+use code_furnace_utils::Config;
+use common::TempDir;
+
+/// Formats a misformatted Rust file through rust-analyzer. Run with
+/// `cargo test -- --ignored` where rust-analyzer is installed.
+#[tokio::test]
+#[ignore = "needs rust-analyzer on PATH"]
+async fn misformatted_rust_is_formatted_by_the_language_server() {
+    let dir = TempDir::new("format");
+    std::fs::create_dir_all(dir.join("src")).unwrap();
+    std::fs::write(
+        dir.join("Cargo.toml"),
+        "[package]\nname = \"formatted\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+    ).unwrap();
+    std::fs::write(dir.join("src/main.rs"), "fn main(){let x=1;println!(\"{}\",x);}\n").unwrap();
+    
+    let editor = EditorManager::with_data_dir(EventBus::new(), Config::default(), Some(dir.to_path_buf()));
+    editor.set_workspace_root(dir.to_path_buf()).await.unwrap();
+    let buffer_id = editor.open_file(dir.join("src/main.rs")).await.unwrap();
+    let formatted = editor.format_buffer(buffer_id).await.unwrap();
+    
+    assert_eq!(formatted, "fn main() {\n    let x = 1;\n    println!(\"{}\", x);\n}\n");
+    assert_eq!(editor.get_buffer(buffer_id).await.unwrap().content, formatted);
+    
+    editor.shutdown().await;
+}
@@ -0,0 +1,39 @@
+mod common;
+
+use code_furnace_editor::{EditorManager, LineEnding};
+use code_furnace_events::EventBus;
+use code_furnace_utils::Config;
+use common::TempDir;
+
+#[tokio::test]
+async fn crlf_files_keep_their_line_endings_through_an_edit() {
+    let dir = TempDir::new("line-endings");
+    let path = dir.join("notes.txt");
+    std::fs::write(&path, "one\r\ntwo\r\n").unwrap();
+    
+    let editor = EditorManager::with_data_dir(EventBus::new(), Config::default(), Some(dir.to_path_buf()));
+    let buffer_id = editor.open_file(path.clone()).await.unwrap();
+    assert_eq!(editor.get_buffer(buffer_id).await.unwrap().line_ending, LineEnding::CrLf);
+    
+    // The editing surface sends plain newlines back
+    editor.update_buffer(buffer_id, "one\ntwo\nthree\n".to_string()).await.unwrap();
+    editor.save_buffer(buffer_id).await.unwrap();
+    assert_eq!(std::fs::read(&path).unwrap(), b"one\r\ntwo\r\nthree\r\n");
+}
+
+#[tokio::test]
+async fn force_lf_converts_crlf_files_on_save() {
+    let dir = TempDir::new("line-endings");
+    let path = dir.join("notes.txt");
+    std::fs::write(&path, "one\r\ntwo\r\n").unwrap();
+    
+    let config = Config {
+        force_lf_line_endings: true,
+        ..Config::default()
+    };
+    let editor = EditorManager::with_data_dir(EventBus::new(), config, Some(dir.to_path_buf()));
+    let buffer_id = editor.open_file(path.clone()).await.unwrap();
+    editor.update_buffer(buffer_id, "one\r\ntwo\r\nthree\r\n".to_string()).await.unwrap();
+    editor.save_buffer(buffer_id).await.unwrap();
+    assert_eq!(std::fs::read(&path).unwrap(), b"one\ntwo\nthree\n");
+}
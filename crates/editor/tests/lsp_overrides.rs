@@ -0,0 +1,25 @@
+use code_furnace_editor::LSPManager;
+use code_furnace_utils::LspServerConfig;
+use std::collections::HashMap;
+
+#[tokio::test]
+async fn changed_overrides_apply_to_the_running_manager() {
+    let manager = LSPManager::new();
+    assert_eq!(manager.get_language_for_extension("py").as_deref(), Some("python"));
+    
+    let mut overrides = HashMap::new();
+    overrides.insert("python".to_string(), LspServerConfig::default());
+    overrides.insert("zig".to_string(), LspServerConfig {
+        command: "zls".to_string(),
+        args: Vec::new(),
+        file_extensions: vec!["zig".to_string()],
+    });
+    let mut changed = manager.set_overrides(&overrides).await;
+    changed.sort();
+    assert_eq!(changed, vec!["python", "zig"]);
+    assert_eq!(manager.get_language_for_extension("py"), None);
+    assert_eq!(manager.get_language_for_extension("zig").as_deref(), Some("zig"));
+    
+    // Setting the same overrides again leaves every server alone
+    assert!(manager.set_overrides(&overrides).await.is_empty());
+}
@@ -0,0 +1,42 @@
+mod common;
+
+use code_furnace_editor::{EditorError, EditorManager};
+use code_furnace_events::EventBus;
+use code_furnace_utils::Config;
+use common::TempDir;
+
+#[tokio::test]
+async fn png_files_are_refused_as_binary() {
+    let dir = TempDir::new("open");
+    // A PNG signature followed by the start of an IHDR chunk
+    let path = dir.join("image.dat");
+    std::fs::write(&path, b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0dIHDR\x00\x00\x00\x01").unwrap();
+    
+    let editor = EditorManager::with_data_dir(EventBus::new(), Config::default(), Some(dir.to_path_buf()));
+    let error = editor.open_file(path.clone()).await.unwrap_err();
+    match error.downcast_ref::<EditorError>() {
+        Some(EditorError::BinaryFile { path: error_path, file_type }) => {
+            assert_eq!(error_path, &path);
+            assert_eq!(file_type, "png");
+        }
+        other => panic!("expected a binary file error, got {:?}", other),
+    }
+    assert!(editor.list_buffers().await.is_empty());
+}
+
+#[tokio::test]
+async fn large_text_files_open_read_only() {
+    let dir = TempDir::new("open");
+    let path = dir.join("large.log");
+    let line = "2026-01-01T00:00:00Z INFO request handled in 12ms\n";
+    std::fs::write(&path, line.repeat(50 * 1024 * 1024 / line.len())).unwrap();
+    
+    let event_bus = EventBus::new().with_event_log(20);
+    let editor = EditorManager::with_data_dir(event_bus.clone(), Config::default(), Some(dir.to_path_buf()));
+    let buffer_id = editor.open_file(path.clone()).await.unwrap();
+    assert!(editor.get_buffer(buffer_id).await.unwrap().read_only);
+    assert_eq!(event_bus.replay(None, Some("editor.file.read_only")).len(), 1);
+    
+    let error = editor.update_buffer(buffer_id, "truncated".to_string()).await.unwrap_err();
+    assert!(matches!(error.downcast_ref::<EditorError>(), Some(EditorError::ReadOnly(_))));
+}
@@ -0,0 +1,73 @@
+mod common;
+
+use code_furnace_editor::recent::{RecentFiles, MAX_RECENT_FILES};
+use std::path::{Path, PathBuf};
+use common::TempDir;
+
+fn touch(dir: &Path, name: &str) -> PathBuf {
+    let path = dir.join(name);
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(&path, "").unwrap();
+    path
+}
+
+fn paths(recent_files: &RecentFiles) -> Vec<PathBuf> {
+    recent_files.list().into_iter().map(|entry| entry.path).collect()
+}
+
+#[test]
+fn reopening_a_file_moves_it_to_the_front() {
+    let dir = TempDir::new("recent");
+    let (a, b) = (touch(&dir, "a.rs"), touch(&dir, "b.rs"));
+    let recent_files = RecentFiles::load(None);
+
+    recent_files.record(&a);
+    recent_files.record(&b);
+    recent_files.record(&a);
+    assert_eq!(paths(&recent_files), vec![a, b]);
+}
+
+#[test]
+fn the_list_is_capped() {
+    let dir = TempDir::new("recent");
+    let recent_files = RecentFiles::load(None);
+    for i in 0..MAX_RECENT_FILES + 5 {
+        recent_files.record(&touch(&dir, &format!("{}.txt", i)));
+    }
+
+    let listed = paths(&recent_files);
+    assert_eq!(listed.len(), MAX_RECENT_FILES);
+    assert_eq!(listed[0], dir.join(format!("{}.txt", MAX_RECENT_FILES + 4)));
+}
+
+#[test]
+fn removed_and_missing_files_are_dropped() {
+    let dir = TempDir::new("recent");
+    let kept = touch(&dir, "kept.rs");
+    let nested = touch(&dir, "src/nested.rs");
+    let deleted = touch(&dir, "deleted.rs");
+    let recent_files = RecentFiles::load(None);
+    for path in [&kept, &nested, &deleted] {
+        recent_files.record(path);
+    }
+
+    recent_files.remove(&dir.join("src"));
+    std::fs::remove_file(&deleted).unwrap();
+    assert_eq!(paths(&recent_files), vec![kept]);
+
+    recent_files.clear();
+    assert!(recent_files.list().is_empty());
+}
+
+#[test]
+fn the_list_survives_a_reload() {
+    let dir = TempDir::new("recent");
+    let file = dir.join("recent-files.json");
+    let (a, b) = (touch(&dir, "a.rs"), touch(&dir, "b.rs"));
+
+    let recent_files = RecentFiles::load(Some(file.clone()));
+    recent_files.record(&a);
+    recent_files.record(&b);
+
+    assert_eq!(paths(&RecentFiles::load(Some(file))), vec![b, a]);
+}
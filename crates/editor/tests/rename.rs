@@ -0,0 +1,38 @@
+mod common;
+
+use code_furnace_editor::EditorManager;
+use code_furnace_events::EventBus;
+use code_furnace_utils::Config;
+use common::TempDir;
+
+#[tokio::test]
+async fn renaming_a_directory_moves_its_open_buffers() {
+    let dir = TempDir::new("rename");
+    std::fs::create_dir_all(dir.join("src/nested")).unwrap();
+    std::fs::write(dir.join("src/main.txt"), "main").unwrap();
+    std::fs::write(dir.join("src/nested/util.txt"), "util").unwrap();
+    std::fs::write(dir.join("outside.txt"), "outside").unwrap();
+    
+    let event_bus = EventBus::new().with_event_log(20);
+    let editor = EditorManager::with_data_dir(event_bus.clone(), Config::default(), Some(dir.to_path_buf()));
+    let main = editor.open_file(dir.join("src/main.txt")).await.unwrap();
+    let util = editor.open_file(dir.join("src/nested/util.txt")).await.unwrap();
+    let outside = editor.open_file(dir.join("outside.txt")).await.unwrap();
+    editor.update_buffer(util, "util edited".to_string()).await.unwrap();
+    
+    editor.rename_file(dir.join("src"), dir.join("lib")).await.unwrap();
+    
+    let main = editor.get_buffer(main).await.unwrap();
+    assert_eq!(main.path, dir.join("lib/main.txt"));
+    let util = editor.get_buffer(util).await.unwrap();
+    assert_eq!(util.path, dir.join("lib/nested/util.txt"));
+    assert_eq!(util.content, "util edited");
+    assert!(util.modified);
+    assert_eq!(editor.get_buffer(outside).await.unwrap().path, dir.join("outside.txt"));
+    assert_eq!(event_bus.replay(None, Some("editor.buffer.renamed")).len(), 2);
+    
+    // Saving writes to the new location
+    editor.save_buffer(util.id).await.unwrap();
+    assert_eq!(std::fs::read_to_string(dir.join("lib/nested/util.txt")).unwrap(), "util edited");
+    assert!(!dir.join("src").exists());
+}
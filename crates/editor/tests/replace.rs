@@ -0,0 +1,35 @@
+mod common;
+
+use code_furnace_editor::{EditorManager, ReplaceOptions};
+use code_furnace_events::EventBus;
+use code_furnace_utils::Config;
+use common::TempDir;
+
+#[tokio::test]
+async fn files_open_read_only_are_reported_but_not_rewritten() {
+    let dir = TempDir::new("replace");
+    std::fs::write(dir.join("small.txt"), "old\n").unwrap();
+    std::fs::write(dir.join("large.txt"), format!("old\n{}", "padding\n".repeat(20))).unwrap();
+    
+    let config = Config {
+        large_file_threshold_bytes: 64,
+        ..Config::default()
+    };
+    let editor = EditorManager::with_data_dir(EventBus::new(), config, Some(dir.to_path_buf()));
+    editor.set_workspace_root(dir.to_path_buf()).await.unwrap();
+    let large = editor.open_file(dir.join("large.txt")).await.unwrap();
+    assert!(editor.get_buffer(large).await.unwrap().read_only);
+    
+    let mut replaced = editor.replace_in_files("old", "new", ReplaceOptions::default()).await.unwrap();
+    replaced.sort_by(|a, b| a.path.cmp(&b.path));
+    
+    assert_eq!(replaced.len(), 2);
+    assert!(replaced[0].path.ends_with("large.txt"));
+    assert!(replaced[0].read_only);
+    assert_eq!(replaced[0].replacements, 1);
+    assert!(!replaced[1].read_only);
+    
+    assert!(std::fs::read_to_string(dir.join("large.txt")).unwrap().starts_with("old\n"));
+    assert!(editor.get_buffer(large).await.unwrap().content.starts_with("old\n"));
+    assert_eq!(std::fs::read_to_string(dir.join("small.txt")).unwrap(), "new\n");
+}
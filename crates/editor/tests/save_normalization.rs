@@ -0,0 +1,57 @@
+mod common;
+
+use code_furnace_editor::{EditorManager, FileBuffer};
+use code_furnace_events::EventBus;
+use code_furnace_utils::Config;
+use std::path::PathBuf;
+use common::TempDir;
+
+fn buffer(content: &str) -> FileBuffer {
+    FileBuffer::new(PathBuf::from("notes.txt"), content.to_string())
+}
+
+#[test]
+fn trailing_whitespace_is_trimmed_from_every_line() {
+    let buffer = buffer("fn main() {  \n\tlet x = 1;\t \n}\t");
+    assert_eq!(buffer.normalized_content(true, false), "fn main() {\n\tlet x = 1;\n}");
+}
+
+#[test]
+fn trimming_keeps_crlf_line_endings() {
+    let buffer = buffer("one \r\ntwo\t\r\n");
+    assert_eq!(buffer.normalized_content(true, false), "one\r\ntwo\r\n");
+}
+
+#[test]
+fn final_newline_is_inserted_once() {
+    assert_eq!(buffer("last line").normalized_content(false, true), "last line\n");
+    assert_eq!(buffer("last line\n").normalized_content(false, true), "last line\n");
+    assert_eq!(buffer("").normalized_content(false, true), "");
+}
+
+#[test]
+fn content_is_untouched_when_both_settings_are_off() {
+    let buffer = buffer("trailing  \nno newline");
+    assert_eq!(buffer.normalized_content(false, false), "trailing  \nno newline");
+}
+
+#[tokio::test]
+async fn saving_updates_the_file_and_the_buffer() {
+    let dir = TempDir::new("save");
+    let path = dir.join("notes.txt");
+    std::fs::write(&path, "a  \nb").unwrap();
+    
+    let config = Config {
+        trim_trailing_whitespace: true,
+        insert_final_newline: true,
+        ..Config::default()
+    };
+    let editor = EditorManager::with_data_dir(EventBus::new(), config, Some(dir.to_path_buf()));
+    let buffer_id = editor.open_file(path.clone()).await.unwrap();
+    editor.save_buffer(buffer_id).await.unwrap();
+    
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "a\nb\n");
+    let buffer = editor.get_buffer(buffer_id).await.unwrap();
+    assert_eq!(buffer.content, "a\nb\n");
+    assert!(!buffer.modified);
+}
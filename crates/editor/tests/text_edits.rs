@@ -0,0 +1,72 @@
+use code_furnace_editor::lsp::{apply_text_edits, compute_edit, LSPPosition};
+use code_furnace_editor::{LSPRange, LSPTextEdit};
+
+fn edit(start: (u32, u32), end: (u32, u32), new_text: &str) -> LSPTextEdit {
+    LSPTextEdit {
+        range: LSPRange {
+            start: LSPPosition { line: start.0, character: start.1 },
+            end: LSPPosition { line: end.0, character: end.1 },
+        },
+        new_text: new_text.to_string(),
+    }
+}
+
+/// The edit from `old` to `new` as (start, end, new text), after checking
+/// that applying it to `old` gives `new`.
+fn computed(old: &str, new: &str) -> ((u32, u32), (u32, u32), String) {
+    let edit = compute_edit(old, new);
+    assert_eq!(apply_text_edits(old, std::slice::from_ref(&edit)).unwrap(), new);
+    let LSPRange { start, end } = edit.range;
+    ((start.line, start.character), (end.line, end.character), edit.new_text)
+}
+
+#[test]
+fn edits_apply_in_document_order_whatever_order_they_arrive_in() {
+    let content = "fn main(){\nlet x=1;\n}\n";
+    let edits = [
+        edit((1, 0), (1, 0), "    "),
+        edit((0, 9), (0, 9), " "),
+        edit((1, 5), (1, 6), " = "),
+    ];
+    assert_eq!(apply_text_edits(content, &edits).unwrap(), "fn main() {\n    let x = 1;\n}\n");
+}
+
+#[test]
+fn inserts_at_the_same_position_keep_the_servers_order() {
+    let edits = [edit((0, 1), (0, 1), "b"), edit((0, 1), (0, 1), "c")];
+    assert_eq!(apply_text_edits("ad", &edits).unwrap(), "abcd");
+}
+
+#[test]
+fn columns_count_utf16_code_units() {
+    // "é" is one UTF-16 unit but two bytes and the emoji two units but four
+    // bytes, so `x` sits at column 14 but byte 17
+    let content = "let s = \"é😀\";x\r\nnext";
+    let edits = [edit((0, 14), (0, 15), "y"), edit((0, 10), (0, 12), "!")];
+    assert_eq!(apply_text_edits(content, &edits).unwrap(), "let s = \"é!\";y\r\nnext");
+}
+
+#[test]
+fn overlapping_or_reversed_edits_are_rejected() {
+    let overlapping = [edit((0, 0), (0, 3), ""), edit((0, 2), (0, 4), "")];
+    assert!(apply_text_edits("abcdef", &overlapping).is_err());
+    assert!(apply_text_edits("abcdef", &[edit((0, 3), (0, 1), "")]).is_err());
+}
+
+#[test]
+fn compute_edit_covers_only_what_changed() {
+    assert_eq!(computed("one\ntwo\n", "one\ntwo\nthree\n"), ((2, 0), (2, 0), "three\n".to_string()));
+    assert_eq!(computed("let x = 10;\n", "let x = 1;\n"), ((0, 9), (0, 10), String::new()));
+    assert_eq!(computed("let x = 1;", "let y = 1;"), ((0, 4), (0, 5), "y".to_string()));
+    
+    // No change is an empty edit, which servers accept as a no-op
+    let (start, end, new_text) = computed("same\n", "same\n");
+    assert_eq!(start, end);
+    assert!(new_text.is_empty());
+}
+
+#[test]
+fn compute_edit_keeps_crlf_pairs_whole_and_counts_utf16_columns() {
+    assert_eq!(computed("a\r\nb", "a\r\r\nb"), ((0, 1), (0, 1), "\r".to_string()));
+    assert_eq!(computed("é😀x", "é😀y"), ((0, 3), (0, 4), "y".to_string()));
+}
@@ -0,0 +1,36 @@
+mod common;
+
+use code_furnace_editor::watcher::TreeWatcher;
+use code_furnace_editor::FileTreeFilter;
+use code_furnace_utils::Config;
+use std::time::Duration;
+use tokio::sync::mpsc::UnboundedReceiver;
+use common::TempDir;
+
+async fn burst(bursts: &mut UnboundedReceiver<()>) -> bool {
+    tokio::time::timeout(Duration::from_millis(500), bursts.recv()).await.is_ok()
+}
+
+#[tokio::test]
+async fn changes_under_ignored_directories_are_not_reported() {
+    let dir = TempDir::new("tree-watch");
+    std::fs::create_dir_all(dir.join("target/debug")).unwrap();
+    std::fs::create_dir_all(dir.join("src")).unwrap();
+    std::fs::write(dir.join(".gitignore"), "target/\n*.log\n").unwrap();
+    
+    let filter = FileTreeFilter::new(&dir, &Config::default()).unwrap();
+    let (_watcher, mut bursts) = TreeWatcher::new(&dir, filter, Duration::from_millis(50)).unwrap();
+    
+    std::fs::write(dir.join("target/debug/app"), "").unwrap();
+    std::fs::write(dir.join("build.log"), "").unwrap();
+    assert!(!burst(&mut bursts).await, "an ignored file was reported");
+    
+    std::fs::write(dir.join("src/main.rs"), "").unwrap();
+    assert!(burst(&mut bursts).await, "a shown file was not reported");
+    
+    // Directories created later are watched too
+    std::fs::create_dir(dir.join("src/nested")).unwrap();
+    assert!(burst(&mut bursts).await);
+    std::fs::write(dir.join("src/nested/util.rs"), "").unwrap();
+    assert!(burst(&mut bursts).await, "a file in a new directory was not reported");
+}
@@ -1,6 +1,7 @@
 use dashmap::DashMap;
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
 use tokio::sync::broadcast;
 use uuid::Uuid;
 
@@ -28,51 +29,329 @@ impl Event {
 pub type EventSender = broadcast::Sender<Event>;
 pub type EventReceiver = broadcast::Receiver<Event>;
 
-#[derive(Clone)]
+/// Events the channel receiving every event buffers by default.
+pub const DEFAULT_GLOBAL_CAPACITY: usize = 1000;
+/// Events each per-type and pattern channel buffers by default.
+pub const DEFAULT_CHANNEL_CAPACITY: usize = 100;
+
+/// The prefix a subscription pattern such as `terminal.*` matches, or `None`
+/// for an exact event type.
+fn pattern_prefix(event_type: &str) -> Option<&str> {
+    event_type.strip_suffix('*')
+}
+
+/// Whether `event_type` is delivered to a `subscribe(filter)` receiver.
+fn filter_matches(filter: Option<&str>, event_type: &str) -> bool {
+    match filter {
+        None | Some("*") => true,
+        Some(filter) => match pattern_prefix(filter) {
+            Some(prefix) => event_type.starts_with(prefix),
+            None => event_type == filter,
+        },
+    }
+}
+
+/// The most recent events, oldest first, kept for `EventBus::replay`.
+#[derive(Debug)]
+struct EventLog {
+    events: VecDeque<Event>,
+    max_events: usize,
+}
+
+#[derive(Clone, Debug)]
 pub struct EventBus {
     senders: Arc<DashMap<String, EventSender>>,
+    /// Pattern subscriptions keyed by the prefix they match.
+    pattern_senders: Arc<DashMap<String, EventSender>>,
     global_sender: EventSender,
+    global_capacity: usize,
+    channel_capacity: usize,
+    /// Events pushed out of a full channel before its slowest receiver read
+    /// them, keyed by the channel's `subscribe` argument.
+    lagged: Arc<DashMap<String, u64>>,
+    event_log: Option<Arc<Mutex<EventLog>>>,
 }
 
 impl EventBus {
     pub fn new() -> Self {
-        let (global_sender, _) = broadcast::channel(1000);
+        Self::with_capacity(DEFAULT_GLOBAL_CAPACITY, DEFAULT_CHANNEL_CAPACITY)
+    }
+    
+    /// Sets how many unread events the global channel and each per-type or
+    /// pattern channel keep before slow receivers start missing them. Zero
+    /// is treated as one.
+    pub fn with_capacity(global_capacity: usize, channel_capacity: usize) -> Self {
+        let global_capacity = global_capacity.max(1);
+        let (global_sender, _) = broadcast::channel(global_capacity);
         
         Self {
             senders: Arc::new(DashMap::new()),
+            pattern_senders: Arc::new(DashMap::new()),
             global_sender,
+            global_capacity,
+            channel_capacity: channel_capacity.max(1),
+            lagged: Arc::new(DashMap::new()),
+            event_log: None,
         }
     }
+    
+    /// Keeps the last `max_events` published events for `replay`. Zero
+    /// leaves the log disabled, which is the default.
+    pub fn with_event_log(mut self, max_events: usize) -> Self {
+        self.event_log = (max_events > 0).then(|| {
+            Arc::new(Mutex::new(EventLog {
+                events: VecDeque::with_capacity(max_events),
+                max_events,
+            }))
+        });
+        self
+    }
 
+    /// Subscribes to one event type, or to every event with `None` or `"*"`.
+    /// A type ending in `*` is a prefix pattern: `terminal.*` receives
+    /// `terminal.command.executed` but not `editor.file.saved`. Dropping the
+    /// returned receiver unsubscribes; its channel is released once no
+    /// receivers remain.
     pub fn subscribe(&self, event_type: Option<&str>) -> EventReceiver {
         match event_type {
+            None | Some("*") => self.global_sender.subscribe(),
             Some(event_type) => {
-                let sender = self.senders
-                    .entry(event_type.to_string())
+                let (senders, key) = match pattern_prefix(event_type) {
+                    Some(prefix) => (&self.pattern_senders, prefix),
+                    None => (&self.senders, event_type),
+                };
+                let sender = senders
+                    .entry(key.to_string())
                     .or_insert_with(|| {
-                        let (sender, _) = broadcast::channel(100);
+                        let (sender, _) = broadcast::channel(self.channel_capacity);
                         sender
                     })
                     .clone();
                 sender.subscribe()
             }
-            None => self.global_sender.subscribe(),
         }
     }
 
     pub fn publish(&self, event: Event) -> Result<(), anyhow::Error> {
-        self.global_sender.send(event.clone()).ok();
+        if let Some(event_log) = &self.event_log {
+            let mut event_log = event_log.lock().unwrap_or_else(|e| e.into_inner());
+            if event_log.events.len() == event_log.max_events {
+                event_log.events.pop_front();
+            }
+            event_log.events.push_back(event.clone());
+        }
+        
+        self.send("*", &self.global_sender, self.global_capacity, event.clone());
         
-        if let Some(sender) = self.senders.get(&event.event_type) {
-            sender.send(event).ok();
+        // A failed send means every receiver was dropped, so the channel goes
+        let mut idle_patterns = Vec::new();
+        for entry in self.pattern_senders.iter() {
+            if !event.event_type.starts_with(entry.key().as_str()) {
+                continue;
+            }
+            let channel = format!("{}*", entry.key());
+            if !self.send(&channel, entry.value(), self.channel_capacity, event.clone()) {
+                idle_patterns.push(entry.key().clone());
+            }
+        }
+        for prefix in idle_patterns {
+            self.pattern_senders.remove_if(&prefix, |_, sender| sender.receiver_count() == 0);
+        }
+        
+        let idle = match self.senders.get(&event.event_type) {
+            Some(sender) => !self.send(&event.event_type, &sender, self.channel_capacity, event.clone()),
+            None => false,
+        };
+        if idle {
+            self.senders.remove_if(&event.event_type, |_, sender| sender.receiver_count() == 0);
         }
         
         Ok(())
     }
+    
+    /// Logged events published after `since`, oldest first, that a
+    /// `subscribe(filter)` receiver would have got. Empty when the event log
+    /// is disabled.
+    pub fn replay(&self, since: Option<chrono::DateTime<chrono::Utc>>, filter: Option<&str>) -> Vec<Event> {
+        let Some(event_log) = &self.event_log else {
+            return Vec::new();
+        };
+        let event_log = event_log.lock().unwrap_or_else(|e| e.into_inner());
+        event_log
+            .events
+            .iter()
+            .filter(|event| since.is_none_or(|since| event.timestamp > since))
+            .filter(|event| filter_matches(filter, &event.event_type))
+            .cloned()
+            .collect()
+    }
+    
+    /// Sends on one channel, counting the event a full channel drops. Returns
+    /// false if the channel has no receivers.
+    fn send(&self, channel: &str, sender: &EventSender, capacity: usize, event: Event) -> bool {
+        if sender.len() >= capacity {
+            *self.lagged.entry(channel.to_string()).or_insert(0) += 1;
+        }
+        sender.send(event).is_ok()
+    }
+    
+    /// Events each channel has dropped because a receiver fell behind, keyed
+    /// by the `subscribe` argument (`"*"` for the global channel). A growing
+    /// count means that subscriber cannot keep up.
+    pub fn lagged_counts(&self) -> HashMap<String, u64> {
+        self.lagged
+            .iter()
+            .map(|entry| (entry.key().clone(), *entry.value()))
+            .collect()
+    }
+    
+    /// Drops every per-type and pattern channel that has no receivers left,
+    /// including those for events that are never published. Returns how many
+    /// were removed.
+    pub fn prune_idle_channels(&self) -> usize {
+        let before = self.senders.len() + self.pattern_senders.len();
+        self.senders.retain(|_, sender| sender.receiver_count() > 0);
+        self.pattern_senders.retain(|_, sender| sender.receiver_count() > 0);
+        before - (self.senders.len() + self.pattern_senders.len())
+    }
+    
+    /// Receivers currently subscribed with exactly this argument to
+    /// `subscribe`; a pattern counts only its own subscribers.
+    pub fn subscriber_count(&self, event_type: Option<&str>) -> usize {
+        match event_type {
+            None | Some("*") => self.global_sender.receiver_count(),
+            Some(event_type) => {
+                let sender = match pattern_prefix(event_type) {
+                    Some(prefix) => self.pattern_senders.get(prefix),
+                    None => self.senders.get(event_type),
+                };
+                sender.map(|sender| sender.receiver_count()).unwrap_or(0)
+            }
+        }
+    }
 }
 
 impl Default for EventBus {
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn event(event_type: &str) -> Event {
+        Event::new(event_type, "test", serde_json::Value::Null)
+    }
+    
+    #[test]
+    fn prefix_pattern_receives_matching_events_only() {
+        let bus = EventBus::new();
+        let mut terminal = bus.subscribe(Some("terminal.*"));
+        
+        bus.publish(event("editor.file.saved")).unwrap();
+        bus.publish(event("terminal.command.executed")).unwrap();
+        
+        assert_eq!(terminal.try_recv().unwrap().event_type, "terminal.command.executed");
+        assert!(terminal.try_recv().is_err());
+    }
+    
+    #[test]
+    fn nested_prefix_pattern() {
+        let bus = EventBus::new();
+        let mut git = bus.subscribe(Some("workspace.git.*"));
+        
+        bus.publish(event("workspace.project.created")).unwrap();
+        bus.publish(event("workspace.git.branch_switched")).unwrap();
+        
+        assert_eq!(git.try_recv().unwrap().event_type, "workspace.git.branch_switched");
+        assert!(git.try_recv().is_err());
+    }
+    
+    #[test]
+    fn exact_and_global_subscriptions_are_unchanged() {
+        let bus = EventBus::new();
+        let mut exact = bus.subscribe(Some("terminal.output"));
+        let mut global = bus.subscribe(None);
+        let mut star = bus.subscribe(Some("*"));
+        
+        bus.publish(event("terminal.output.extra")).unwrap();
+        bus.publish(event("terminal.output")).unwrap();
+        
+        assert_eq!(exact.try_recv().unwrap().event_type, "terminal.output");
+        assert!(exact.try_recv().is_err());
+        for receiver in [&mut global, &mut star] {
+            assert_eq!(receiver.try_recv().unwrap().event_type, "terminal.output.extra");
+            assert_eq!(receiver.try_recv().unwrap().event_type, "terminal.output");
+        }
+    }
+    
+    #[test]
+    fn dropped_subscriptions_release_their_channels() {
+        let bus = EventBus::new();
+        let exact = bus.subscribe(Some("editor.file.saved"));
+        let pattern = bus.subscribe(Some("terminal.*"));
+        assert_eq!(bus.subscriber_count(Some("editor.file.saved")), 1);
+        assert_eq!(bus.subscriber_count(Some("terminal.*")), 1);
+        
+        drop(exact);
+        drop(pattern);
+        assert_eq!(bus.subscriber_count(Some("editor.file.saved")), 0);
+        bus.publish(event("editor.file.saved")).unwrap();
+        bus.publish(event("terminal.output")).unwrap();
+        assert!(bus.senders.is_empty());
+        assert!(bus.pattern_senders.is_empty());
+    }
+    
+    #[test]
+    fn full_channels_count_lagged_events() {
+        let bus = EventBus::with_capacity(4, 2);
+        let mut output = bus.subscribe(Some("terminal.output"));
+        let _global = bus.subscribe(None);
+        
+        for _ in 0..5 {
+            bus.publish(event("terminal.output")).unwrap();
+        }
+        
+        let lagged = bus.lagged_counts();
+        assert_eq!(lagged.get("terminal.output"), Some(&3));
+        assert_eq!(lagged.get("*"), Some(&1));
+        assert!(matches!(output.try_recv(), Err(broadcast::error::TryRecvError::Lagged(3))));
+    }
+    
+    #[test]
+    fn replay_returns_recent_matching_events() {
+        let bus = EventBus::new().with_event_log(3);
+        bus.publish(event("terminal.output")).unwrap();
+        let since = chrono::Utc::now();
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        for event_type in ["terminal.output", "editor.file.saved", "terminal.command.executed"] {
+            bus.publish(event(event_type)).unwrap();
+        }
+        
+        let types = |events: Vec<Event>| events.into_iter().map(|e| e.event_type).collect::<Vec<_>>();
+        assert_eq!(
+            types(bus.replay(None, None)),
+            ["terminal.output", "editor.file.saved", "terminal.command.executed"]
+        );
+        assert_eq!(
+            types(bus.replay(Some(since), Some("terminal.*"))),
+            ["terminal.output", "terminal.command.executed"]
+        );
+        assert_eq!(types(bus.replay(None, Some("editor.file.saved"))), ["editor.file.saved"]);
+        assert!(EventBus::new().replay(None, None).is_empty());
+    }
+    
+    #[test]
+    fn prune_removes_channels_never_published_to() {
+        let bus = EventBus::new();
+        let kept = bus.subscribe(Some("agent.response.done"));
+        drop(bus.subscribe(Some("canvas.updated")));
+        drop(bus.subscribe(Some("canvas.*")));
+        
+        assert_eq!(bus.prune_idle_channels(), 2);
+        assert_eq!(bus.subscriber_count(Some("agent.response.done")), 1);
+        drop(kept);
+    }
+}
@@ -0,0 +1,182 @@
+//! How the host passes JSON to plugin functions and reads their results.
+//!
+//! A plugin exports its `memory`, `allocate(len: i32) -> i32` and, optionally,
+//! `deallocate(ptr: i32, len: i32)`. The host serializes the arguments to
+//! JSON, copies them into a buffer from `allocate` and calls the function
+//! with `(ptr, len)`. The function returns where its JSON result is, either
+//! as two `i32`s `(ptr, len)` or packed into one `i64` as `ptr << 32 | len`.
+//! The host frees both buffers with `deallocate` once it has read the result,
+//! so the plugin must not free them itself. An empty result reads as `null`.
+//!
+//! Host functions work the other way round: they read their string arguments
+//! from `(ptr, len)` in the caller's memory and hand results back in a buffer
+//! from the plugin's `allocate`, which the plugin then owns.
+
+use anyhow::Result;
+use code_furnace_utils::ResourceError;
+use wasmtime::{AsContext, Caller, Instance, Memory, Store, Val};
+
+/// Largest result a plugin function may hand back.
+pub const MAX_RESULT_BYTES: usize = 16 * 1024 * 1024;
+
+/// Calls `function_name` on `instance` with `args`, following the convention
+/// above.
+pub(crate) fn call_json_function<T>(
+    store: &mut Store<T>,
+    instance: &Instance,
+    function_name: &str,
+    args: &serde_json::Value,
+) -> Result<serde_json::Value> {
+    let memory = instance
+        .get_memory(&mut *store, "memory")
+        .ok_or_else(|| anyhow::anyhow!("Plugin does not export its memory"))?;
+    let allocate = instance
+        .get_typed_func::<i32, i32>(&mut *store, "allocate")
+        .map_err(|_| anyhow::anyhow!("Plugin does not export allocate(len: i32) -> i32"))?;
+    let deallocate = instance.get_typed_func::<(i32, i32), ()>(&mut *store, "deallocate").ok();
+    let func = instance
+        .get_func(&mut *store, function_name)
+        .ok_or_else(|| ResourceError::NotFound(format!("Function '{}' not found in plugin", function_name)))?;
+    
+    let input = serde_json::to_vec(args)?;
+    let input_len = i32::try_from(input.len()).map_err(|_| anyhow::anyhow!("Plugin arguments are too large"))?;
+    let input_ptr = allocate.call(&mut *store, input_len)?;
+    memory
+        .write(&mut *store, input_ptr as u32 as usize, &input)
+        .map_err(|_| anyhow::anyhow!("Plugin allocated arguments outside its memory"))?;
+    
+    let mut results = vec![Val::I32(0); func.ty(&*store).results().len()];
+    func.call(&mut *store, &[Val::I32(input_ptr), Val::I32(input_len)], &mut results)?;
+    if let Some(deallocate) = &deallocate {
+        deallocate.call(&mut *store, (input_ptr, input_len))?;
+    }
+    
+    let (output_ptr, output_len) = match results.as_slice() {
+        [Val::I32(ptr), Val::I32(len)] => (*ptr as u32, *len as u32),
+        [Val::I64(packed)] => ((*packed as u64 >> 32) as u32, *packed as u32),
+        _ => {
+            return Err(anyhow::anyhow!(
+                "Function '{}' must return its result as (ptr: i32, len: i32) or one packed i64",
+                function_name
+            ))
+        }
+    };
+    let output = read_bytes(&memory, &*store, output_ptr, output_len)?;
+    if let Some(deallocate) = &deallocate {
+        deallocate.call(&mut *store, (output_ptr as i32, output_len as i32))?;
+    }
+    
+    if output.is_empty() {
+        return Ok(serde_json::Value::Null);
+    }
+    serde_json::from_slice(&output)
+        .map_err(|e| anyhow::anyhow!("Function '{}' returned invalid JSON: {}", function_name, e))
+}
+
+fn read_bytes(memory: &Memory, store: impl AsContext, ptr: u32, len: u32) -> Result<Vec<u8>> {
+    let len = len as usize;
+    if len > MAX_RESULT_BYTES {
+        return Err(anyhow::anyhow!("Plugin result of {} bytes exceeds the {} byte limit", len, MAX_RESULT_BYTES));
+    }
+    
+    let mut bytes = vec![0; len];
+    memory
+        .read(store, ptr as usize, &mut bytes)
+        .map_err(|_| anyhow::anyhow!("Plugin result at {} ({} bytes) is outside its memory", ptr, len))?;
+    Ok(bytes)
+}
+
+/// Reads a UTF-8 string argument a plugin passed to a host function.
+pub(crate) fn read_guest_string<T>(caller: &mut Caller<'_, T>, ptr: i32, len: i32) -> Result<String> {
+    let memory = guest_memory(caller)?;
+    let bytes = read_bytes(&memory, &*caller, ptr as u32, len as u32)?;
+    String::from_utf8(bytes).map_err(|_| anyhow::anyhow!("Plugin passed a string that is not UTF-8"))
+}
+
+/// Copies `bytes` into a buffer from the plugin's `allocate` and returns it
+/// packed as `ptr << 32 | len`.
+pub(crate) fn write_guest_bytes<T>(caller: &mut Caller<'_, T>, bytes: &[u8]) -> Result<i64> {
+    let memory = guest_memory(caller)?;
+    let allocate = caller
+        .get_export("allocate")
+        .and_then(|export| export.into_func())
+        .ok_or_else(|| anyhow::anyhow!("Plugin does not export allocate(len: i32) -> i32"))?
+        .typed::<i32, i32>(&*caller)?;
+    
+    let len = i32::try_from(bytes.len()).map_err(|_| anyhow::anyhow!("Host result is too large for the plugin"))?;
+    let ptr = allocate.call(&mut *caller, len)?;
+    memory
+        .write(&mut *caller, ptr as u32 as usize, bytes)
+        .map_err(|_| anyhow::anyhow!("Plugin allocated a result outside its memory"))?;
+    Ok(((ptr as u32 as i64) << 32) | len as i64)
+}
+
+fn guest_memory<T>(caller: &mut Caller<'_, T>) -> Result<Memory> {
+    caller
+        .get_export("memory")
+        .and_then(|export| export.into_memory())
+        .ok_or_else(|| anyhow::anyhow!("Plugin does not export its memory"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasmtime::{Config, Engine, Linker, Module};
+    
+    /// Echoes its arguments back, from a bump allocator that records what
+    /// was freed in `freed`.
+    const ECHO_PLUGIN: &str = r#"
+        (module
+            (memory (export "memory") 1)
+            (global $next (mut i32) (i32.const 1024))
+            (global $freed (export "freed") (mut i32) (i32.const 0))
+            (func (export "allocate") (param $len i32) (result i32)
+                (local $ptr i32)
+                (local.set $ptr (global.get $next))
+                (global.set $next (i32.add (global.get $next) (local.get $len)))
+                (local.get $ptr))
+            (func (export "deallocate") (param $ptr i32) (param $len i32)
+                (global.set $freed (i32.add (global.get $freed) (local.get $len))))
+            (func (export "echo") (param $ptr i32) (param $len i32) (result i32 i32)
+                (local $copy i32)
+                (local.set $copy (call 0 (local.get $len)))
+                (memory.copy (local.get $copy) (local.get $ptr) (local.get $len))
+                (local.get $copy) (local.get $len))
+            (func (export "packed_empty") (param i32 i32) (result i64)
+                (i64.const 0))
+            (func (export "out_of_bounds") (param i32 i32) (result i32 i32)
+                (i32.const 65530) (i32.const 100)))
+    "#;
+    
+    fn instantiate() -> (Store<()>, Instance) {
+        let engine = Engine::new(&Config::new()).unwrap();
+        let module = Module::new(&engine, ECHO_PLUGIN).unwrap();
+        let mut store = Store::new(&engine, ());
+        let instance = Linker::new(&engine).instantiate(&mut store, &module).unwrap();
+        (store, instance)
+    }
+    
+    #[test]
+    fn arguments_and_results_round_trip_as_json() {
+        let (mut store, instance) = instantiate();
+        let args = serde_json::json!({ "path": "src/main.rs", "lines": [1, 2, 3] });
+        
+        let result = call_json_function(&mut store, &instance, "echo", &args).unwrap();
+        assert_eq!(result, args);
+        let freed = instance.get_global(&mut store, "freed").unwrap().get(&mut store).unwrap_i32();
+        assert_eq!(freed as usize, 2 * serde_json::to_vec(&args).unwrap().len());
+        
+        let result = call_json_function(&mut store, &instance, "packed_empty", &args).unwrap();
+        assert_eq!(result, serde_json::Value::Null);
+    }
+    
+    #[test]
+    fn results_outside_plugin_memory_are_refused() {
+        let (mut store, instance) = instantiate();
+        let error = call_json_function(&mut store, &instance, "out_of_bounds", &serde_json::Value::Null).unwrap_err();
+        assert!(error.to_string().contains("outside its memory"));
+        
+        let error = call_json_function(&mut store, &instance, "missing", &serde_json::Value::Null).unwrap_err();
+        assert!(error.to_string().contains("not found"));
+    }
+}
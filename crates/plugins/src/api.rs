@@ -1,8 +1,12 @@
 use anyhow::Result;
+use code_furnace_utils::ResourceError;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
 use wasmtime::{Caller, Linker};
 
+use crate::abi;
+
 /// Plugin API surface that provides access to Code Furnace functionality
 #[derive(Debug, Clone)]
 pub struct PluginAPI {
@@ -11,6 +15,7 @@ pub struct PluginAPI {
     pub canvas_api: CanvasAPI,
     pub filesystem_api: FilesystemAPI,
     pub network_api: NetworkAPI,
+    pub storage_api: StorageAPI,
 }
 
 impl PluginAPI {
@@ -21,6 +26,7 @@ impl PluginAPI {
             canvas_api: CanvasAPI::new(),
             filesystem_api: FilesystemAPI::new(),
             network_api: NetworkAPI::new(),
+            storage_api: StorageAPI::default(),
         }
     }
 }
@@ -142,7 +148,7 @@ impl CanvasAPI {
 /// Filesystem operations available to plugins (with permission checks)
 #[derive(Debug, Clone)]
 pub struct FilesystemAPI {
-    allowed_paths: Vec<String>,
+    allowed_paths: Vec<PathBuf>,
 }
 
 impl FilesystemAPI {
@@ -152,27 +158,38 @@ impl FilesystemAPI {
         }
     }
     
+    /// Grants access to everything under `paths`. Roots are resolved once
+    /// here, so a root that does not exist yet grants nothing.
     pub fn set_allowed_paths(&mut self, paths: Vec<String>) {
-        self.allowed_paths = paths;
+        self.allowed_paths = paths
+            .iter()
+            .filter_map(|path| match std::fs::canonicalize(path) {
+                Ok(root) => Some(root),
+                Err(e) => {
+                    tracing::warn!("Ignoring plugin filesystem root {}: {}", path, e);
+                    None
+                }
+            })
+            .collect();
     }
     
     pub fn read_file(&self, path: &str) -> Result<String> {
         if !self.is_path_allowed(path) {
-            return Err(anyhow::anyhow!("Access denied to path: {}", path));
+            return Err(ResourceError::AccessDenied(format!("Access denied to path: {}", path)).into());
         }
         std::fs::read_to_string(path).map_err(Into::into)
     }
     
     pub fn write_file(&self, path: &str, content: &str) -> Result<()> {
         if !self.is_path_allowed(path) {
-            return Err(anyhow::anyhow!("Access denied to path: {}", path));
+            return Err(ResourceError::AccessDenied(format!("Access denied to path: {}", path)).into());
         }
         std::fs::write(path, content).map_err(Into::into)
     }
     
     pub fn list_directory(&self, path: &str) -> Result<Vec<String>> {
         if !self.is_path_allowed(path) {
-            return Err(anyhow::anyhow!("Access denied to path: {}", path));
+            return Err(ResourceError::AccessDenied(format!("Access denied to path: {}", path)).into());
         }
         
         let entries = std::fs::read_dir(path)?
@@ -188,9 +205,25 @@ impl FilesystemAPI {
             return false; // Default deny
         }
         
-        self.allowed_paths.iter().any(|allowed| {
-            path.starts_with(allowed)
-        })
+        match Self::resolve(Path::new(path)) {
+            Some(resolved) => self.allowed_paths.iter().any(|allowed| resolved.starts_with(allowed)),
+            None => false,
+        }
+    }
+    
+    /// Resolves symlinks in `path` so it can be compared component-wise with
+    /// the allowed roots. A file that does not exist yet is resolved through
+    /// its parent so it can still be written. Paths with `..` are refused.
+    fn resolve(path: &Path) -> Option<PathBuf> {
+        if path.components().any(|component| component == Component::ParentDir) {
+            return None;
+        }
+        if let Ok(resolved) = std::fs::canonicalize(path) {
+            return Some(resolved);
+        }
+        
+        let parent = std::fs::canonicalize(path.parent()?).ok()?;
+        Some(parent.join(path.file_name()?))
     }
 }
 
@@ -213,7 +246,7 @@ impl NetworkAPI {
     
     pub async fn http_get(&self, url: &str) -> Result<String> {
         if !self.is_domain_allowed(url) {
-            return Err(anyhow::anyhow!("Access denied to domain: {}", url));
+            return Err(ResourceError::AccessDenied(format!("Access denied to domain: {}", url)).into());
         }
         
         let client = reqwest::Client::new();
@@ -224,7 +257,7 @@ impl NetworkAPI {
     
     pub async fn http_post(&self, url: &str, body: &str) -> Result<String> {
         if !self.is_domain_allowed(url) {
-            return Err(anyhow::anyhow!("Access denied to domain: {}", url));
+            return Err(ResourceError::AccessDenied(format!("Access denied to domain: {}", url)).into());
         }
         
         let client = reqwest::Client::new();
@@ -254,6 +287,104 @@ impl NetworkAPI {
     }
 }
 
+/// Largest file of saved values a plugin may keep.
+pub const DEFAULT_STORAGE_QUOTA: usize = 1024 * 1024;
+
+/// Key-value storage that keeps a plugin's values between invocations, in a
+/// JSON file of its own so plugins cannot read each other's data. The file
+/// is named after the plugin's manifest name, so its values survive
+/// restarts and reinstalls, which give the plugin a new id.
+#[derive(Debug, Clone)]
+pub struct StorageAPI {
+    file: Option<PathBuf>,
+    quota_bytes: usize,
+}
+
+/// Storage that is unavailable; every call fails.
+impl Default for StorageAPI {
+    fn default() -> Self {
+        Self {
+            file: None,
+            quota_bytes: DEFAULT_STORAGE_QUOTA,
+        }
+    }
+}
+
+impl StorageAPI {
+    pub fn for_plugin(storage_dir: &Path, plugin_name: &str) -> Self {
+        Self {
+            file: Some(storage_dir.join(format!("{}.json", storage_file_stem(plugin_name)))),
+            quota_bytes: DEFAULT_STORAGE_QUOTA,
+        }
+    }
+    
+    pub fn with_quota(mut self, quota_bytes: usize) -> Self {
+        self.quota_bytes = quota_bytes;
+        self
+    }
+    
+    pub fn get(&self, key: &str) -> Result<Option<String>> {
+        Ok(self.load()?.remove(key))
+    }
+    
+    /// Saves `value` under `key`, refusing it if the plugin's file would grow
+    /// past its quota.
+    pub fn set(&self, key: &str, value: &str) -> Result<()> {
+        let file = self.file()?;
+        let mut values = self.load()?;
+        values.insert(key.to_string(), value.to_string());
+        
+        let json = serde_json::to_string(&values)?;
+        if json.len() > self.quota_bytes {
+            return Err(anyhow::anyhow!("Plugin storage quota of {} bytes exceeded", self.quota_bytes));
+        }
+        
+        if let Some(dir) = file.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let temp_path = file.with_extension("json.tmp");
+        std::fs::write(&temp_path, json)?;
+        std::fs::rename(&temp_path, file)?;
+        Ok(())
+    }
+    
+    /// Deletes every value the plugin saved.
+    pub fn clear(&self) -> Result<()> {
+        let file = self.file()?;
+        if file.exists() {
+            std::fs::remove_file(file)?;
+        }
+        Ok(())
+    }
+    
+    fn file(&self) -> Result<&Path> {
+        self.file.as_deref().ok_or_else(|| anyhow::anyhow!("Plugin storage is unavailable"))
+    }
+    
+    fn load(&self) -> Result<HashMap<String, String>> {
+        let file = self.file()?;
+        if !file.exists() {
+            return Ok(HashMap::new());
+        }
+        Ok(serde_json::from_str(&std::fs::read_to_string(file)?)?)
+    }
+}
+
+/// `name` as a file name: ASCII letters, digits, `-` and `_` are kept and
+/// every other byte is written as `%XX`, so no name can reach outside the
+/// storage directory and distinct names never share a file.
+fn storage_file_stem(name: &str) -> String {
+    let mut stem = String::with_capacity(name.len());
+    for byte in name.bytes() {
+        if byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_' {
+            stem.push(byte as char);
+        } else {
+            stem.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    stem
+}
+
 /// Memory management for WASM plugins
 #[derive(Debug)]
 pub struct WasmMemory {
@@ -293,97 +424,221 @@ impl WasmMemory {
     }
 }
 
-/// Host function bindings for WASM plugins
+/// Host function bindings for WASM plugins, kept as the data of each
+/// plugin's `Store` so the imports can reach the API it was granted.
+///
+/// String arguments arrive as `(ptr, len)` in the plugin's memory. Imports
+/// that produce a value return it as `ptr << 32 | len` in a buffer from the
+/// plugin's `allocate`, which the plugin must free; the rest return `0`.
+/// Either kind returns `-1` when the call is denied or fails.
 pub struct HostFunctions {
     pub api: PluginAPI,
-    pub memory: WasmMemory,
+    pub permissions: Vec<super::Permission>,
+    /// Commands the plugin asked to contribute during this call.
+    pub commands: Vec<CommandRegistration>,
+}
+
+/// A command a plugin contributes: the name users see and the export it runs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CommandRegistration {
+    pub name: String,
+    pub export: String,
 }
 
 impl HostFunctions {
     pub fn new() -> Self {
         Self {
             api: PluginAPI::new(),
-            memory: WasmMemory::new(1024 * 1024), // 1MB initial
+            permissions: Vec::new(),
+            commands: Vec::new(),
+        }
+    }
+    
+    /// Configures the API for what a plugin's manifest asks for.
+    pub fn with_permissions(permissions: &[super::Permission]) -> Self {
+        let mut host_funcs = Self::new();
+        for permission in permissions {
+            match permission {
+                super::Permission::FileSystem { paths } => {
+                    host_funcs.api.filesystem_api.set_allowed_paths(paths.clone());
+                }
+                super::Permission::Network { domains } => {
+                    host_funcs.api.network_api.set_allowed_domains(domains.clone());
+                }
+                _ => {}
+            }
+        }
+        host_funcs.permissions = permissions.to_vec();
+        host_funcs
+    }
+    
+    fn require(&self, granted: fn(&super::Permission) -> bool, name: &str) -> Result<()> {
+        if self.permissions.iter().any(granted) {
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!("Plugin lacks the {} permission", name))
         }
     }
     
-    pub fn add_to_linker(&mut self, linker: &mut Linker<()>) -> Result<()> {
+    pub fn add_to_linker(linker: &mut Linker<HostFunctions>) -> Result<()> {
         // Terminal functions
-        linker.func_wrap("env", "terminal_execute", |_caller: Caller<'_, ()>, cmd_ptr: i32, cmd_len: i32| -> i32 {
-            // In real implementation, we'd read from WASM memory and execute
-            tracing::info!("Plugin terminal execute: {} bytes at {}", cmd_len, cmd_ptr);
-            0 // Success
+        linker.func_wrap("env", "terminal_execute", |mut caller: Caller<'_, HostFunctions>, cmd_ptr: i32, cmd_len: i32| -> i64 {
+            respond(&mut caller, "terminal_execute", |caller| {
+                caller.data().require(|p| matches!(p, super::Permission::Terminal), "Terminal")?;
+                let command = abi::read_guest_string(caller, cmd_ptr, cmd_len)?;
+                caller.data().api.terminal_api.execute_command(&command)
+            })
         })?;
         
         // Editor functions
-        linker.func_wrap("env", "editor_get_content", |_caller: Caller<'_, ()>, path_ptr: i32, path_len: i32| -> i32 {
-            tracing::info!("Plugin editor get content: {} bytes at {}", path_len, path_ptr);
-            0
+        linker.func_wrap("env", "editor_get_content", |mut caller: Caller<'_, HostFunctions>, path_ptr: i32, path_len: i32| -> i64 {
+            respond(&mut caller, "editor_get_content", |caller| {
+                caller.data().require(|p| matches!(p, super::Permission::Editor), "Editor")?;
+                let path = abi::read_guest_string(caller, path_ptr, path_len)?;
+                caller.data().api.editor_api.get_file_content(&path)
+            })
         })?;
         
-        linker.func_wrap("env", "editor_set_content", |_caller: Caller<'_, ()>, path_ptr: i32, path_len: i32, content_ptr: i32, content_len: i32| -> i32 {
-            tracing::info!("Plugin editor set content: path={} bytes at {}, content={} bytes at {}", 
-                path_len, path_ptr, content_len, content_ptr);
-            0
+        linker.func_wrap("env", "editor_set_content", |mut caller: Caller<'_, HostFunctions>, path_ptr: i32, path_len: i32, content_ptr: i32, content_len: i32| -> i32 {
+            acknowledge(&mut caller, "editor_set_content", |caller| {
+                caller.data().require(|p| matches!(p, super::Permission::Editor), "Editor")?;
+                let path = abi::read_guest_string(caller, path_ptr, path_len)?;
+                let content = abi::read_guest_string(caller, content_ptr, content_len)?;
+                caller.data_mut().api.editor_api.set_file_content(&path, &content)
+            })
         })?;
         
         // Canvas functions
-        linker.func_wrap("env", "canvas_add_element", |_caller: Caller<'_, ()>, type_ptr: i32, type_len: i32, x: f64, y: f64| -> i32 {
-            tracing::info!("Plugin canvas add element: type={} bytes at {}, pos=({}, {})", 
-                type_len, type_ptr, x, y);
-            123 // Mock element ID
+        linker.func_wrap("env", "canvas_add_element", |mut caller: Caller<'_, HostFunctions>, type_ptr: i32, type_len: i32, x: f64, y: f64| -> i64 {
+            respond(&mut caller, "canvas_add_element", |caller| {
+                caller.data().require(|p| matches!(p, super::Permission::Canvas), "Canvas")?;
+                let element_type = abi::read_guest_string(caller, type_ptr, type_len)?;
+                caller.data().api.canvas_api.add_element(&element_type, x, y)
+            })
         })?;
         
-        linker.func_wrap("env", "canvas_set_mode", |_caller: Caller<'_, ()>, mode_ptr: i32, mode_len: i32| -> i32 {
-            tracing::info!("Plugin canvas set mode: {} bytes at {}", mode_len, mode_ptr);
-            0
+        linker.func_wrap("env", "canvas_set_mode", |mut caller: Caller<'_, HostFunctions>, mode_ptr: i32, mode_len: i32| -> i32 {
+            acknowledge(&mut caller, "canvas_set_mode", |caller| {
+                caller.data().require(|p| matches!(p, super::Permission::Canvas), "Canvas")?;
+                let mode = abi::read_guest_string(caller, mode_ptr, mode_len)?;
+                caller.data_mut().api.canvas_api.set_mode(&mode)
+            })
         })?;
         
-        // Filesystem functions
-        linker.func_wrap("env", "fs_read_file", |_caller: Caller<'_, ()>, path_ptr: i32, path_len: i32| -> i32 {
-            tracing::info!("Plugin fs read file: {} bytes at {}", path_len, path_ptr);
-            0
+        // Filesystem functions, limited to the paths the plugin was granted
+        linker.func_wrap("env", "fs_read_file", |mut caller: Caller<'_, HostFunctions>, path_ptr: i32, path_len: i32| -> i64 {
+            respond(&mut caller, "fs_read_file", |caller| {
+                let path = abi::read_guest_string(caller, path_ptr, path_len)?;
+                caller.data().api.filesystem_api.read_file(&path)
+            })
         })?;
         
-        linker.func_wrap("env", "fs_write_file", |_caller: Caller<'_, ()>, path_ptr: i32, path_len: i32, content_ptr: i32, content_len: i32| -> i32 {
-            tracing::info!("Plugin fs write file: path={} bytes at {}, content={} bytes at {}", 
-                path_len, path_ptr, content_len, content_ptr);
-            0
+        linker.func_wrap("env", "fs_write_file", |mut caller: Caller<'_, HostFunctions>, path_ptr: i32, path_len: i32, content_ptr: i32, content_len: i32| -> i32 {
+            acknowledge(&mut caller, "fs_write_file", |caller| {
+                let path = abi::read_guest_string(caller, path_ptr, path_len)?;
+                let content = abi::read_guest_string(caller, content_ptr, content_len)?;
+                caller.data().api.filesystem_api.write_file(&path, &content)
+            })
         })?;
         
-        // Network functions
-        linker.func_wrap("env", "net_http_get", |_caller: Caller<'_, ()>, url_ptr: i32, url_len: i32| -> i32 {
-            tracing::info!("Plugin http get: {} bytes at {}", url_len, url_ptr);
-            0
+        // Network functions, limited to the domains the plugin was granted.
+        // Plugins run on a blocking thread, so the request can wait on the
+        // runtime there.
+        linker.func_wrap("env", "net_http_get", |mut caller: Caller<'_, HostFunctions>, url_ptr: i32, url_len: i32| -> i64 {
+            respond(&mut caller, "net_http_get", |caller| {
+                let url = abi::read_guest_string(caller, url_ptr, url_len)?;
+                let runtime = tokio::runtime::Handle::try_current()?;
+                runtime.block_on(caller.data().api.network_api.http_get(&url))
+            })
         })?;
         
-        // Memory management
-        linker.func_wrap("env", "allocate", |_caller: Caller<'_, ()>, size: i32| -> i32 {
-            tracing::debug!("Plugin allocate: {} bytes", size);
-            size // Mock allocation - return same as size for now
+        // Storage functions; a missing key reads as an empty value
+        linker.func_wrap("env", "storage_get", |mut caller: Caller<'_, HostFunctions>, key_ptr: i32, key_len: i32| -> i64 {
+            respond(&mut caller, "storage_get", |caller| {
+                caller.data().require(|p| matches!(p, super::Permission::Storage), "Storage")?;
+                let key = abi::read_guest_string(caller, key_ptr, key_len)?;
+                Ok(caller.data().api.storage_api.get(&key)?.unwrap_or_default())
+            })
         })?;
         
-        linker.func_wrap("env", "deallocate", |_caller: Caller<'_, ()>, ptr: i32, size: i32| {
-            tracing::debug!("Plugin deallocate: {} bytes at {}", size, ptr);
+        linker.func_wrap("env", "storage_set", |mut caller: Caller<'_, HostFunctions>, key_ptr: i32, key_len: i32, value_ptr: i32, value_len: i32| -> i32 {
+            acknowledge(&mut caller, "storage_set", |caller| {
+                caller.data().require(|p| matches!(p, super::Permission::Storage), "Storage")?;
+                let key = abi::read_guest_string(caller, key_ptr, key_len)?;
+                let value = abi::read_guest_string(caller, value_ptr, value_len)?;
+                caller.data().api.storage_api.set(&key, &value)
+            })
+        })?;
+        
+        // Commands, honoured when registered from `on_load`
+        linker.func_wrap("env", "register_command", |mut caller: Caller<'_, HostFunctions>, name_ptr: i32, name_len: i32, export_ptr: i32, export_len: i32| -> i32 {
+            acknowledge(&mut caller, "register_command", |caller| {
+                let name = abi::read_guest_string(caller, name_ptr, name_len)?;
+                let export = abi::read_guest_string(caller, export_ptr, export_len)?;
+                if name.trim().is_empty() {
+                    return Err(anyhow::anyhow!("Command name cannot be empty"));
+                }
+                if caller.get_export(&export).and_then(|export| export.into_func()).is_none() {
+                    return Err(anyhow::anyhow!("Command {} names missing export {}", name, export));
+                }
+                caller.data_mut().commands.push(CommandRegistration { name, export });
+                Ok(())
+            })
         })?;
         
         // Logging functions
-        linker.func_wrap("env", "log_info", |_caller: Caller<'_, ()>, msg_ptr: i32, msg_len: i32| {
-            tracing::info!("Plugin log (info): {} bytes at {}", msg_len, msg_ptr);
+        linker.func_wrap("env", "log_info", |mut caller: Caller<'_, HostFunctions>, msg_ptr: i32, msg_len: i32| {
+            if let Ok(message) = abi::read_guest_string(&mut caller, msg_ptr, msg_len) {
+                tracing::info!("Plugin log: {}", message);
+            }
         })?;
         
-        linker.func_wrap("env", "log_warn", |_caller: Caller<'_, ()>, msg_ptr: i32, msg_len: i32| {
-            tracing::warn!("Plugin log (warn): {} bytes at {}", msg_len, msg_ptr);
+        linker.func_wrap("env", "log_warn", |mut caller: Caller<'_, HostFunctions>, msg_ptr: i32, msg_len: i32| {
+            if let Ok(message) = abi::read_guest_string(&mut caller, msg_ptr, msg_len) {
+                tracing::warn!("Plugin log: {}", message);
+            }
         })?;
         
-        linker.func_wrap("env", "log_error", |_caller: Caller<'_, ()>, msg_ptr: i32, msg_len: i32| {
-            tracing::error!("Plugin log (error): {} bytes at {}", msg_len, msg_ptr);
+        linker.func_wrap("env", "log_error", |mut caller: Caller<'_, HostFunctions>, msg_ptr: i32, msg_len: i32| {
+            if let Ok(message) = abi::read_guest_string(&mut caller, msg_ptr, msg_len) {
+                tracing::error!("Plugin log: {}", message);
+            }
         })?;
         
         Ok(())
     }
 }
 
+/// Runs a host function that produces a string and hands it to the plugin.
+fn respond(
+    caller: &mut Caller<'_, HostFunctions>,
+    name: &str,
+    call: impl FnOnce(&mut Caller<'_, HostFunctions>) -> Result<String>,
+) -> i64 {
+    match call(caller).and_then(|value| abi::write_guest_bytes(caller, value.as_bytes())) {
+        Ok(packed) => packed,
+        Err(e) => {
+            tracing::warn!("Plugin call to {} failed: {}", name, e);
+            -1
+        }
+    }
+}
+
+/// Runs a host function that only reports success.
+fn acknowledge(
+    caller: &mut Caller<'_, HostFunctions>,
+    name: &str,
+    call: impl FnOnce(&mut Caller<'_, HostFunctions>) -> Result<()>,
+) -> i32 {
+    match call(caller) {
+        Ok(()) => 0,
+        Err(e) => {
+            tracing::warn!("Plugin call to {} failed: {}", name, e);
+            -1
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PluginManifestV2 {
     pub name: String,
@@ -402,6 +657,10 @@ pub struct PluginManifestV2 {
     pub exports: Vec<PluginExport>,
 }
 
+/// A function a plugin exports. Exports with the `hook` function type are
+/// lifecycle hooks the runtime calls itself: `on_load` after the plugin is
+/// installed or enabled, and `on_unload` when it is disabled or
+/// uninstalled. Both are optional.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PluginExport {
     pub name: String,
@@ -417,4 +676,72 @@ pub struct PluginParameter {
     pub required: bool,
     pub description: String,
     pub default_value: Option<serde_json::Value>,
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn scratch_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("code-furnace-fs-api-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("proj")).unwrap();
+        std::fs::create_dir_all(dir.join("proj-evil")).unwrap();
+        std::fs::write(dir.join("proj").join("notes.txt"), "mine").unwrap();
+        std::fs::write(dir.join("proj-evil").join("secret.txt"), "not mine").unwrap();
+        dir
+    }
+    
+    fn api_for(root: &Path) -> FilesystemAPI {
+        let mut api = FilesystemAPI::new();
+        api.set_allowed_paths(vec![root.to_string_lossy().to_string()]);
+        api
+    }
+    
+    #[test]
+    fn allowed_roots_do_not_grant_sibling_prefixes() {
+        let dir = scratch_dir();
+        let api = api_for(&dir.join("proj"));
+        
+        assert_eq!(api.read_file(&dir.join("proj/notes.txt").to_string_lossy()).unwrap(), "mine");
+        assert!(api.read_file(&dir.join("proj-evil/secret.txt").to_string_lossy()).is_err());
+        assert!(api.write_file(&dir.join("proj-evil/new.txt").to_string_lossy(), "x").is_err());
+        api.write_file(&dir.join("proj/new.txt").to_string_lossy(), "ok").unwrap();
+        
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+    
+    #[test]
+    fn storage_is_kept_per_plugin_and_within_its_quota() {
+        let dir = scratch_dir();
+        let first = StorageAPI::for_plugin(&dir, "first").with_quota(64);
+        let second = StorageAPI::for_plugin(&dir, "first/../second");
+        
+        first.set("theme", "dark").unwrap();
+        assert_eq!(first.get("theme").unwrap().as_deref(), Some("dark"));
+        assert_eq!(second.get("theme").unwrap(), None);
+        
+        assert!(first.set("history", &"x".repeat(64)).is_err());
+        assert_eq!(first.get("history").unwrap(), None);
+        assert!(StorageAPI::default().get("theme").is_err());
+        
+        second.set("theme", "light").unwrap();
+        assert!(dir.join("first%2F%2E%2E%2Fsecond.json").exists());
+        assert_eq!(StorageAPI::for_plugin(&dir, "first").get("theme").unwrap().as_deref(), Some("dark"));
+        
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+    
+    #[test]
+    fn parent_directory_escapes_are_denied() {
+        let dir = scratch_dir();
+        let api = api_for(&dir.join("proj"));
+        
+        let escape = dir.join("proj/../proj-evil/secret.txt");
+        assert!(api.read_file(&escape.to_string_lossy()).is_err());
+        let inside = dir.join("proj/../proj/notes.txt");
+        assert!(api.read_file(&inside.to_string_lossy()).is_err());
+        assert!(api.list_directory(&dir.join("proj/..").to_string_lossy()).is_err());
+        
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,15 +1,29 @@
 use anyhow::Result;
+use code_furnace_utils::ResourceError;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use uuid::Uuid;
-use wasmtime::{Config, Engine, Linker, Module, Store};
+use wasmtime::{Config, Engine, Instance, Linker, Module, Store};
 
+mod abi;
 pub mod api;
 pub use api::*;
 
+/// Fuel given to a plugin call made through `execute_plugin_function`.
+pub const DEFAULT_PLUGIN_FUEL: u64 = 10_000_000_000;
+
+/// Optional export called when a plugin is installed or enabled.
+pub const ON_LOAD_HOOK: &str = "on_load";
+
+/// Optional export called when a plugin is disabled or uninstalled.
+pub const ON_UNLOAD_HOOK: &str = "on_unload";
+
+/// Directory under the app data directory holding each plugin's storage.
+const PLUGIN_STORAGE_DIR: &str = "plugin-storage";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PluginManifest {
     pub name: String,
@@ -28,6 +42,15 @@ pub enum Permission {
     Terminal,
     Editor,
     Canvas,
+    Storage,
+}
+
+/// Plugin failures callers may want to tell apart, returned inside
+/// `anyhow::Error`.
+#[derive(Debug, thiserror::Error)]
+pub enum PluginError {
+    #[error("Plugin ran out of fuel after {fuel_limit} units")]
+    FuelExhausted { fuel_limit: u64 },
 }
 
 #[derive(Debug, Clone)]
@@ -51,6 +74,16 @@ impl Plugin {
     }
 }
 
+/// A command a plugin registered from `on_load`, which users can invoke by
+/// name to run the plugin's export.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginCommand {
+    pub name: String,
+    pub plugin_id: Uuid,
+    pub plugin_name: String,
+    pub export: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PluginExecutionContext {
     pub plugin_id: Uuid,
@@ -65,6 +98,8 @@ pub struct PluginRuntime {
     event_bus: code_furnace_events::EventBus,
     host_functions: Arc<RwLock<HostFunctions>>,
     plugin_registry: Arc<RwLock<PluginRegistry>>,
+    storage_dir: Option<PathBuf>,
+    commands: Arc<RwLock<HashMap<String, PluginCommand>>>,
 }
 
 impl PluginRuntime {
@@ -77,6 +112,10 @@ impl PluginRuntime {
         config.consume_fuel(true); // Enable fuel consumption for resource limiting
         
         let engine = Engine::new(&config)?;
+        let storage_dir = code_furnace_utils::paths::get_app_data_dir()
+            .map_err(|e| tracing::warn!("Plugin storage will be unavailable: {}", e))
+            .ok()
+            .map(|dir| dir.join(PLUGIN_STORAGE_DIR));
         
         Ok(Self {
             engine,
@@ -84,9 +123,18 @@ impl PluginRuntime {
             event_bus,
             host_functions: Arc::new(RwLock::new(HostFunctions::new())),
             plugin_registry: Arc::new(RwLock::new(PluginRegistry::new())),
+            storage_dir,
+            commands: Arc::new(RwLock::new(HashMap::new())),
         })
     }
     
+    /// Keeps each plugin's `Permission::Storage` values under `dir` instead of
+    /// the app data directory.
+    pub fn with_storage_dir(mut self, dir: PathBuf) -> Self {
+        self.storage_dir = Some(dir);
+        self
+    }
+    
     pub async fn install_plugin(&self, manifest: PluginManifest, wasm_bytes: Vec<u8>, installation_path: PathBuf) -> Result<Uuid> {
         let plugin = Plugin::new(manifest, wasm_bytes, installation_path);
         let plugin_id = plugin.id;
@@ -94,31 +142,47 @@ impl PluginRuntime {
         // Validate the WASM module
         Module::new(&self.engine, &plugin.wasm_module)?;
         
-        let mut plugins = self.plugins.write().await;
-        plugins.insert(plugin_id, plugin);
+        self.plugins.write().await.insert(plugin_id, plugin.clone());
         
         let event = code_furnace_events::Event::new(
             "plugins.installed",
             "plugin-runtime",
             serde_json::json!({
                 "plugin_id": plugin_id,
-                "name": plugins.get(&plugin_id).unwrap().manifest.name
+                "name": plugin.manifest.name
             }),
         );
         self.event_bus.publish(event)?;
         
+        self.load_plugin(&plugin, "install").await;
         Ok(plugin_id)
     }
     
     pub async fn uninstall_plugin(&self, plugin_id: Uuid) -> Result<()> {
-        let mut plugins = self.plugins.write().await;
+        let plugin = self.plugins.write().await.remove(&plugin_id);
         
-        if let Some(plugin) = plugins.remove(&plugin_id) {
+        if let Some(plugin) = plugin {
+            if plugin.enabled {
+                self.unload_plugin(&plugin, "uninstall").await;
+            }
+            
             // Clean up plugin files
             if plugin.installation_path.exists() {
                 std::fs::remove_dir_all(&plugin.installation_path)?;
             }
             
+            // Its saved values go too, unless another install of the same
+            // plugin still uses them
+            let name_in_use = self
+                .plugins
+                .read()
+                .await
+                .values()
+                .any(|other| other.manifest.name == plugin.manifest.name);
+            if let (Some(dir), false) = (&self.storage_dir, name_in_use) {
+                StorageAPI::for_plugin(dir, &plugin.manifest.name).clear()?;
+            }
+            
             let event = code_furnace_events::Event::new(
                 "plugins.uninstalled",
                 "plugin-runtime",
@@ -137,35 +201,26 @@ impl PluginRuntime {
         &self,
         plugin_id: Uuid,
         function_name: &str,
-        _args: serde_json::Value,
+        args: serde_json::Value,
     ) -> Result<serde_json::Value> {
         let plugin = {
             let plugins = self.plugins.read().await;
             plugins.get(&plugin_id).cloned()
-                .ok_or_else(|| anyhow::anyhow!("Plugin not found: {}", plugin_id))?
+                .ok_or_else(|| ResourceError::NotFound(format!("Plugin not found: {}", plugin_id)))?
         };
         
         if !plugin.enabled {
             return Err(anyhow::anyhow!("Plugin is disabled"));
         }
         
-        let module = Module::new(&self.engine, &plugin.wasm_module)?;
-        let mut linker = Linker::new(&self.engine);
-        
-        // Add host functions that plugins can call
-        self.add_host_functions(&mut linker, &plugin)?;
-        
-        let mut store = Store::new(&self.engine, ());
-        let instance = linker.instantiate(&mut store, &module)?;
-        
-        // Get the function export
-        let func = instance
-            .get_typed_func::<(i32, i32), i32>(&mut store, function_name)
-            .map_err(|_| anyhow::anyhow!("Function '{}' not found in plugin", function_name))?;
+        let (mut store, instance) = self.instantiate_plugin(&plugin, DEFAULT_PLUGIN_FUEL)?;
         
-        // For now, return a placeholder result
-        // In a real implementation, we'd properly marshal arguments and results
-        let result = func.call(&mut store, (0, 0))?;
+        // Host functions may block, so keep the call off the async workers
+        let function = function_name.to_string();
+        let (result, fuel_consumed) = tokio::task::spawn_blocking(move || {
+            run_plugin_function(&mut store, &instance, &function, &args, DEFAULT_PLUGIN_FUEL)
+        }).await?;
+        let result = result?;
         
         let event = code_furnace_events::Event::new(
             "plugins.function.executed",
@@ -173,7 +228,8 @@ impl PluginRuntime {
             serde_json::json!({
                 "plugin_id": plugin_id,
                 "function_name": function_name,
-                "result": result
+                "result": result,
+                "fuel_consumed": fuel_consumed
             }),
         );
         self.event_bus.publish(event)?;
@@ -181,29 +237,23 @@ impl PluginRuntime {
         Ok(serde_json::json!({ "result": result }))
     }
     
-    fn add_host_functions(&self, linker: &mut Linker<()>, plugin: &Plugin) -> Result<()> {
-        // Configure host functions based on plugin permissions
-        let mut host_funcs = HostFunctions::new();
+    fn instantiate_plugin(&self, plugin: &Plugin, fuel: u64) -> Result<(Store<HostFunctions>, Instance)> {
+        let module = Module::new(&self.engine, &plugin.wasm_module)?;
+        let mut linker = Linker::new(&self.engine);
         
-        // Configure API access based on permissions
-        for permission in &plugin.manifest.permissions {
-            match permission {
-                Permission::FileSystem { paths } => {
-                    host_funcs.api.filesystem_api.set_allowed_paths(paths.clone());
-                }
-                Permission::Network { domains } => {
-                    host_funcs.api.network_api.set_allowed_domains(domains.clone());
-                }
-                _ => {
-                    // Other permissions are implicitly granted by having the permission
-                }
-            }
-        }
+        // Add host functions that plugins can call, backed by an API
+        // configured from the plugin's permissions
+        HostFunctions::add_to_linker(&mut linker)?;
         
-        // Add all host functions to the linker
-        host_funcs.add_to_linker(linker)?;
+        let mut host_funcs = HostFunctions::with_permissions(&plugin.manifest.permissions);
+        if let Some(dir) = &self.storage_dir {
+            host_funcs.api.storage_api = StorageAPI::for_plugin(dir, &plugin.manifest.name);
+        }
         
-        Ok(())
+        let mut store = Store::new(&self.engine, host_funcs);
+        store.set_fuel(fuel)?;
+        let instance = linker.instantiate(&mut store, &module)?;
+        Ok((store, instance))
     }
     
     pub async fn list_plugins(&self) -> Vec<Plugin> {
@@ -212,85 +262,183 @@ impl PluginRuntime {
     }
     
     pub async fn enable_plugin(&self, plugin_id: Uuid) -> Result<()> {
-        let mut plugins = self.plugins.write().await;
+        let enabled = self.set_enabled(plugin_id, true).await;
         
-        if let Some(plugin) = plugins.get_mut(&plugin_id) {
-            plugin.enabled = true;
-            
+        if let Some(plugin) = enabled {
             let event = code_furnace_events::Event::new(
                 "plugins.enabled",
                 "plugin-runtime",
-                serde_json::to_value(&plugin_id)?,
+                serde_json::to_value(plugin_id)?,
             );
             self.event_bus.publish(event)?;
+            
+            self.load_plugin(&plugin, "enable").await;
         }
         
         Ok(())
     }
     
     pub async fn disable_plugin(&self, plugin_id: Uuid) -> Result<()> {
-        let mut plugins = self.plugins.write().await;
+        let disabled = self.set_enabled(plugin_id, false).await;
         
-        if let Some(plugin) = plugins.get_mut(&plugin_id) {
-            plugin.enabled = false;
-            
+        if let Some(plugin) = disabled {
             let event = code_furnace_events::Event::new(
                 "plugins.disabled",
                 "plugin-runtime",    
-                serde_json::to_value(&plugin_id)?,
+                serde_json::to_value(plugin_id)?,
             );
             self.event_bus.publish(event)?;
+            
+            self.unload_plugin(&plugin, "disable").await;
         }
         
         Ok(())
     }
     
-    /// Execute a plugin function with enhanced error handling and fuel limiting
+    /// Flips a plugin's enabled flag, returning the plugin only if it changed.
+    async fn set_enabled(&self, plugin_id: Uuid, enabled: bool) -> Option<Plugin> {
+        let mut plugins = self.plugins.write().await;
+        let plugin = plugins.get_mut(&plugin_id).filter(|plugin| plugin.enabled != enabled)?;
+        plugin.enabled = enabled;
+        Some(plugin.clone())
+    }
+    
+    /// Runs `on_load` and takes on the commands it registered.
+    async fn load_plugin(&self, plugin: &Plugin, reason: &str) {
+        let registrations = self.run_lifecycle_hook(plugin, ON_LOAD_HOOK, reason).await;
+        let mut commands = self.commands.write().await;
+        
+        for registration in registrations {
+            if let Some(existing) = commands.get(&registration.name).filter(|command| command.plugin_id != plugin.id) {
+                tracing::warn!(
+                    "Plugin {} cannot register command {}: it belongs to {}",
+                    plugin.manifest.name, registration.name, existing.plugin_name
+                );
+                continue;
+            }
+            
+            let command = PluginCommand {
+                name: registration.name,
+                plugin_id: plugin.id,
+                plugin_name: plugin.manifest.name.clone(),
+                export: registration.export,
+            };
+            let event = code_furnace_events::Event::new(
+                "plugins.command.registered",
+                "plugin-runtime",
+                serde_json::to_value(&command).unwrap_or_default(),
+            );
+            if let Err(e) = self.event_bus.publish(event) {
+                tracing::warn!("Failed to publish plugin command registration: {}", e);
+            }
+            commands.insert(command.name.clone(), command);
+        }
+    }
+    
+    /// Runs `on_unload` and drops the plugin's commands.
+    async fn unload_plugin(&self, plugin: &Plugin, reason: &str) {
+        self.run_lifecycle_hook(plugin, ON_UNLOAD_HOOK, reason).await;
+        self.commands.write().await.retain(|_, command| command.plugin_id != plugin.id);
+    }
+    
+    /// Calls `hook` if the plugin exports it, with `{"plugin_id", "reason"}`
+    /// as its argument, returning the commands it registered. A failing hook
+    /// is logged and reported as a `plugins.hook.failed` event but never
+    /// stops the operation; its registrations are dropped.
+    async fn run_lifecycle_hook(&self, plugin: &Plugin, hook: &'static str, reason: &str) -> Vec<CommandRegistration> {
+        let args = serde_json::json!({ "plugin_id": plugin.id, "reason": reason });
+        let result = match self.instantiate_plugin(plugin, DEFAULT_PLUGIN_FUEL) {
+            Ok((mut store, instance)) => {
+                if instance.get_func(&mut store, hook).is_none() {
+                    return Vec::new();
+                }
+                tokio::task::spawn_blocking(move || {
+                    run_plugin_function(&mut store, &instance, hook, &args, DEFAULT_PLUGIN_FUEL)
+                        .0
+                        .map(|_| std::mem::take(&mut store.data_mut().commands))
+                })
+                .await
+                .map_err(Into::into)
+                .and_then(|result| result)
+            }
+            Err(e) => Err(e),
+        };
+        
+        match result {
+            Ok(registrations) => registrations,
+            Err(e) => {
+                tracing::warn!("Plugin {} {} hook failed: {}", plugin.manifest.name, hook, e);
+                let event = code_furnace_events::Event::new(
+                    "plugins.hook.failed",
+                    "plugin-runtime",
+                    serde_json::json!({
+                        "plugin_id": plugin.id,
+                        "hook": hook,
+                        "reason": reason,
+                        "error": e.to_string()
+                    }),
+                );
+                if let Err(e) = self.event_bus.publish(event) {
+                    tracing::warn!("Failed to publish plugin hook failure: {}", e);
+                }
+                Vec::new()
+            }
+        }
+    }
+    
+    /// Commands plugins have registered, sorted by name.
+    pub async fn list_commands(&self) -> Vec<PluginCommand> {
+        let mut commands: Vec<_> = self.commands.read().await.values().cloned().collect();
+        commands.sort_by(|a, b| a.name.cmp(&b.name));
+        commands
+    }
+    
+    /// Runs the export behind a registered command with `args`.
+    pub async fn invoke_command(&self, name: &str, args: serde_json::Value) -> Result<serde_json::Value> {
+        let command = self.commands.read().await.get(name).cloned()
+            .ok_or_else(|| anyhow::anyhow!("No plugin command named {}", name))?;
+        self.execute_plugin_function(command.plugin_id, &command.export, args).await
+    }
+    
+    /// Execute a plugin function with enhanced error handling and fuel limiting.
+    /// A plugin that burns through `fuel_limit` fails with
+    /// `PluginError::FuelExhausted`; other failures come back as `{"error"}`.
     pub async fn execute_plugin_function_safe(
         &self,
         plugin_id: Uuid,
         function_name: &str,
-        _args: serde_json::Value,
-        _fuel_limit: u64,
+        args: serde_json::Value,
+        fuel_limit: u64,
     ) -> Result<serde_json::Value> {
         let plugin = {
             let plugins = self.plugins.read().await;
             plugins.get(&plugin_id).cloned()
-                .ok_or_else(|| anyhow::anyhow!("Plugin not found: {}", plugin_id))?
+                .ok_or_else(|| ResourceError::NotFound(format!("Plugin not found: {}", plugin_id)))?
         };
         
         if !plugin.enabled {
             return Err(anyhow::anyhow!("Plugin is disabled"));
         }
         
-        let module = Module::new(&self.engine, &plugin.wasm_module)?;
-        let mut linker = Linker::new(&self.engine);
-        
-        // Add host functions
-        self.add_host_functions(&mut linker, &plugin)?;
-        
-        let mut store = Store::new(&self.engine, ());
-        // Note: Fuel consumption requires wasmtime features that might not be available
-        // For now, rely on timeout for resource limiting
-        
-        let instance = linker.instantiate(&mut store, &module)?;
+        let (mut store, instance) = self.instantiate_plugin(&plugin, fuel_limit)?;
         
-        // Try to get the function with proper error handling
-        let func = instance
-            .get_typed_func::<(i32, i32), i32>(&mut store, function_name)
-            .map_err(|e| anyhow::anyhow!("Function '{}' not found in plugin: {}", function_name, e))?;
+        if instance.get_func(&mut store, function_name).is_none() {
+            return Err(ResourceError::NotFound(format!("Function '{}' not found in plugin", function_name)).into());
+        }
         
-        // Execute with timeout for resource limiting
-        let result = tokio::time::timeout(
+        // Fuel bounds the work a plugin can do; the timeout is a wall-clock
+        // backstop for time spent blocked in host functions
+        let function = function_name.to_string();
+        let (result, fuel_consumed) = tokio::time::timeout(
             std::time::Duration::from_secs(30), 
             tokio::task::spawn_blocking(move || {
-                func.call(&mut store, (0, 0)).map_err(|e| anyhow::anyhow!("Plugin execution error: {}", e))
+                run_plugin_function(&mut store, &instance, &function, &args, fuel_limit)
             })
         ).await??;
         
         let (success, result_value) = match &result {
-            Ok(value) => (true, *value),
-            Err(_) => (false, -1)
+            Ok(value) => (true, value.clone()),
+            Err(_) => (false, serde_json::Value::Null)
         };
         
         let event = code_furnace_events::Event::new(
@@ -300,14 +448,16 @@ impl PluginRuntime {
                 "plugin_id": plugin_id,
                 "function_name": function_name,
                 "success": success,
-                "result": result_value
+                "result": result_value,
+                "fuel_consumed": fuel_consumed
             }),
         );
         self.event_bus.publish(event)?;
         
         match result {
             Ok(value) => Ok(serde_json::json!({ "result": value })),
-            Err(e) => Ok(serde_json::json!({ "error": e.to_string() }))
+            Err(e) if e.is::<PluginError>() => Err(e),
+            Err(e) => Ok(serde_json::json!({ "error": format!("Plugin execution error: {}", e) }))
         }
     }
     
@@ -317,6 +467,26 @@ impl PluginRuntime {
     }
 }
 
+/// Calls a plugin function on a store given `fuel_limit` fuel, returning its
+/// result alongside the fuel it burned.
+fn run_plugin_function(
+    store: &mut Store<HostFunctions>,
+    instance: &Instance,
+    function_name: &str,
+    args: &serde_json::Value,
+    fuel_limit: u64,
+) -> (Result<serde_json::Value>, u64) {
+    let result = abi::call_json_function(store, instance, function_name, args).map_err(|e| {
+        if e.downcast_ref::<wasmtime::Trap>() == Some(&wasmtime::Trap::OutOfFuel) {
+            PluginError::FuelExhausted { fuel_limit }.into()
+        } else {
+            e
+        }
+    });
+    let fuel_consumed = fuel_limit.saturating_sub(store.get_fuel().unwrap_or(0));
+    (result, fuel_consumed)
+}
+
 /// Plugin registry for managing plugin discovery and installation
 #[derive(Debug, Clone)]
 pub struct PluginRegistry {
@@ -0,0 +1,49 @@
+use code_furnace_events::EventBus;
+mod common;
+
+use code_furnace_plugins::PluginRuntime;
+
+/// Registers `Say hello` for `hello`, which returns `"hi"`, and tries to
+/// register a command for an export it does not have.
+const GREETER_PLUGIN: &str = r#"
+    (module
+        (import "env" "register_command" (func $register_command (param i32 i32 i32 i32) (result i32)))
+        (memory (export "memory") 1)
+        (data (i32.const 0) "Say hello")
+        (data (i32.const 16) "hello")
+        (data (i32.const 32) "Say bye")
+        (data (i32.const 48) "bye")
+        (data (i32.const 64) "\"hi\"")
+        (func (export "allocate") (param i32) (result i32)
+            (i32.const 1024))
+        (func (export "on_load") (param i32 i32) (result i64)
+            (drop (call $register_command (i32.const 0) (i32.const 9) (i32.const 16) (i32.const 5)))
+            (drop (call $register_command (i32.const 32) (i32.const 7) (i32.const 48) (i32.const 3)))
+            (i64.const 0))
+        (func (export "hello") (param i32 i32) (result i64)
+            (i64.const 274877906948)))
+"#;
+
+#[tokio::test]
+async fn commands_registered_on_load_run_their_export() {
+    let event_bus = EventBus::new().with_event_log(20);
+    let runtime = PluginRuntime::new(event_bus.clone()).unwrap();
+    let manifest = common::manifest("greeter", vec![]);
+    
+    let plugin_id = runtime
+        .install_plugin(manifest, GREETER_PLUGIN.as_bytes().to_vec(), std::env::temp_dir().join("greeter"))
+        .await
+        .unwrap();
+    let commands = runtime.list_commands().await;
+    assert_eq!(commands.len(), 1);
+    assert_eq!(commands[0].name, "Say hello");
+    assert_eq!(commands[0].export, "hello");
+    assert_eq!(event_bus.replay(None, Some("plugins.command.registered")).len(), 1);
+    
+    let result = runtime.invoke_command("Say hello", serde_json::Value::Null).await.unwrap();
+    assert_eq!(result, serde_json::json!({ "result": "hi" }));
+    
+    runtime.disable_plugin(plugin_id).await.unwrap();
+    assert!(runtime.list_commands().await.is_empty());
+    assert!(runtime.invoke_command("Say hello", serde_json::Value::Null).await.is_err());
+}
@@ -0,0 +1,15 @@
+use code_furnace_plugins::{Permission, PluginManifest};
+
+/// Manifest for a test plugin called `name`, loaded from `{name}.wasm`.
+/// Other fields can be overridden with struct update syntax.
+pub fn manifest(name: &str, permissions: Vec<Permission>) -> PluginManifest {
+    PluginManifest {
+        name: name.to_string(),
+        version: "0.1.0".to_string(),
+        description: format!("The {} test plugin", name),
+        author: "Code Furnace".to_string(),
+        entry_point: format!("{}.wasm", name),
+        permissions,
+        api_version: "1.0.0".to_string(),
+    }
+}
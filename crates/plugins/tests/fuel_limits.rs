@@ -0,0 +1,49 @@
+mod common;
+
+use code_furnace_events::EventBus;
+use code_furnace_plugins::{PluginError, PluginRuntime};
+
+/// `spin` never returns; `idle` returns `null` straight away.
+const BUSY_PLUGIN: &str = r#"
+    (module
+        (memory (export "memory") 1)
+        (func (export "allocate") (param i32) (result i32)
+            (i32.const 1024))
+        (func (export "spin") (param i32 i32) (result i64)
+            (loop $forever
+                (br $forever))
+            (i64.const 0))
+        (func (export "idle") (param i32 i32) (result i64)
+            (i64.const 0)))
+"#;
+
+#[tokio::test]
+async fn plugins_stop_when_their_fuel_runs_out() {
+    let event_bus = EventBus::new().with_event_log(10);
+    let runtime = PluginRuntime::new(event_bus.clone()).unwrap();
+    let plugin_id = runtime
+        .install_plugin(common::manifest("busy", vec![]), BUSY_PLUGIN.as_bytes().to_vec(), std::env::temp_dir().join("busy"))
+        .await
+        .unwrap();
+    
+    let error = runtime
+        .execute_plugin_function_safe(plugin_id, "spin", serde_json::Value::Null, 100_000)
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<PluginError>(),
+        Some(PluginError::FuelExhausted { fuel_limit: 100_000 })
+    ));
+    
+    let result = runtime
+        .execute_plugin_function_safe(plugin_id, "idle", serde_json::Value::Null, 100_000)
+        .await
+        .unwrap();
+    assert_eq!(result, serde_json::json!({ "result": null }));
+    
+    let events = event_bus.replay(None, Some("plugins.function.executed"));
+    let fuel: Vec<_> = events.iter().map(|event| event.data["fuel_consumed"].as_u64().unwrap()).collect();
+    assert_eq!(fuel.len(), 2);
+    assert_eq!(fuel[0], 100_000);
+    assert!(fuel[1] > 0 && fuel[1] < 100_000);
+}
@@ -0,0 +1,65 @@
+mod common;
+
+use code_furnace_events::EventBus;
+use code_furnace_plugins::{Permission, PluginRuntime};
+use std::path::PathBuf;
+
+/// Takes a JSON string holding a path and returns the file's contents as read
+/// through `fs_read_file`, or `null` when the host refuses.
+const READER_PLUGIN: &str = r#"
+    (module
+        (import "env" "fs_read_file" (func $fs_read_file (param i32 i32) (result i64)))
+        (memory (export "memory") 1)
+        (global $next (mut i32) (i32.const 1024))
+        (func (export "allocate") (param $len i32) (result i32)
+            (local $ptr i32)
+            (local.set $ptr (global.get $next))
+            (global.set $next (i32.add (global.get $next) (local.get $len)))
+            (local.get $ptr))
+        (func (export "read") (param $ptr i32) (param $len i32) (result i64)
+            (local $result i64)
+            (local.set $result
+                (call $fs_read_file
+                    (i32.add (local.get $ptr) (i32.const 1))
+                    (i32.sub (local.get $len) (i32.const 2))))
+            (select
+                (i64.const 0)
+                (local.get $result)
+                (i64.eq (local.get $result) (i64.const -1)))))
+"#;
+
+fn scratch_dir() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("code-furnace-plugins-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[tokio::test]
+async fn plugins_read_files_they_were_granted_through_the_host() {
+    let dir = scratch_dir();
+    let file = dir.join("settings.json");
+    std::fs::write(&file, r#"{"theme":"dark"}"#).unwrap();
+    let path = file.to_string_lossy().to_string();
+    
+    let runtime = PluginRuntime::new(EventBus::new()).unwrap();
+    let granted = runtime
+        .install_plugin(
+            common::manifest("reader", vec![Permission::FileSystem { paths: vec![dir.to_string_lossy().to_string()] }]),
+            READER_PLUGIN.as_bytes().to_vec(),
+            dir.join("granted"),
+        )
+        .await
+        .unwrap();
+    let denied = runtime
+        .install_plugin(common::manifest("reader", vec![]), READER_PLUGIN.as_bytes().to_vec(), dir.join("denied"))
+        .await
+        .unwrap();
+    
+    let result = runtime.execute_plugin_function(granted, "read", serde_json::json!(path)).await.unwrap();
+    assert_eq!(result, serde_json::json!({ "result": { "theme": "dark" } }));
+    
+    let result = runtime.execute_plugin_function(denied, "read", serde_json::json!(path)).await.unwrap();
+    assert_eq!(result, serde_json::json!({ "result": null }));
+    
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,76 @@
+mod common;
+
+use code_furnace_events::EventBus;
+use code_furnace_plugins::api::StorageAPI;
+use code_furnace_plugins::{Permission, PluginRuntime};
+
+/// `on_load` saves `"loaded" = true` to storage and `loaded` reads it back;
+/// `on_unload` traps.
+const HOOKED_PLUGIN: &str = r#"
+    (module
+        (import "env" "storage_get" (func $storage_get (param i32 i32) (result i64)))
+        (import "env" "storage_set" (func $storage_set (param i32 i32 i32 i32) (result i32)))
+        (memory (export "memory") 1)
+        (data (i32.const 0) "loaded")
+        (data (i32.const 8) "true")
+        (global $next (mut i32) (i32.const 1024))
+        (func (export "allocate") (param $len i32) (result i32)
+            (local $ptr i32)
+            (local.set $ptr (global.get $next))
+            (global.set $next (i32.add (global.get $next) (local.get $len)))
+            (local.get $ptr))
+        (func (export "on_load") (param i32 i32) (result i64)
+            (drop (call $storage_set (i32.const 0) (i32.const 6) (i32.const 8) (i32.const 4)))
+            (i64.const 0))
+        (func (export "on_unload") (param i32 i32) (result i64)
+            (unreachable))
+        (func (export "loaded") (param i32 i32) (result i64)
+            (call $storage_get (i32.const 0) (i32.const 6))))
+"#;
+
+#[tokio::test]
+async fn lifecycle_hooks_run_without_blocking_the_operation() {
+    let dir = std::env::temp_dir().join(format!("code-furnace-plugin-hooks-{}", uuid::Uuid::new_v4()));
+    let event_bus = EventBus::new().with_event_log(20);
+    let runtime = PluginRuntime::new(event_bus.clone()).unwrap().with_storage_dir(dir.clone());
+    let manifest = common::manifest("hooked", vec![Permission::Storage]);
+    
+    let plugin_id = runtime
+        .install_plugin(manifest, HOOKED_PLUGIN.as_bytes().to_vec(), dir.join("hooked"))
+        .await
+        .unwrap();
+    let result = runtime.execute_plugin_function(plugin_id, "loaded", serde_json::Value::Null).await.unwrap();
+    assert_eq!(result, serde_json::json!({ "result": true }));
+    assert!(event_bus.replay(None, Some("plugins.hook.failed")).is_empty());
+    
+    runtime.disable_plugin(plugin_id).await.unwrap();
+    assert!(!runtime.list_plugins().await[0].enabled);
+    let failures = event_bus.replay(None, Some("plugins.hook.failed"));
+    assert_eq!(failures.len(), 1);
+    assert_eq!(failures[0].data["hook"], "on_unload");
+    assert_eq!(failures[0].data["reason"], "disable");
+    
+    runtime.disable_plugin(plugin_id).await.unwrap();
+    assert_eq!(event_bus.replay(None, Some("plugins.hook.failed")).len(), 1);
+    
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn storage_follows_the_plugin_name_and_goes_with_uninstall() {
+    let dir = std::env::temp_dir().join(format!("code-furnace-plugin-storage-{}", uuid::Uuid::new_v4()));
+    let runtime = PluginRuntime::new(EventBus::new()).unwrap().with_storage_dir(dir.clone());
+    let manifest = common::manifest("hooked", vec![Permission::Storage]);
+    
+    let plugin_id = runtime
+        .install_plugin(manifest, HOOKED_PLUGIN.as_bytes().to_vec(), dir.join("hooked"))
+        .await
+        .unwrap();
+    let storage = StorageAPI::for_plugin(&dir, "hooked");
+    assert_eq!(storage.get("loaded").unwrap().as_deref(), Some("true"));
+    
+    runtime.uninstall_plugin(plugin_id).await.unwrap();
+    assert_eq!(storage.get("loaded").unwrap(), None);
+    
+    std::fs::remove_dir_all(&dir).unwrap();
+}
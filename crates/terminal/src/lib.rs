@@ -1,12 +1,19 @@
 use anyhow::Result;
+use code_furnace_utils::ResourceError;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::{mpsc, RwLock, Mutex};
-use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
 use tokio::process::{Child, Command};
 use uuid::Uuid;
 use std::process::Stdio;
+use std::time::Duration;
+
+pub const DEFAULT_COMMAND_TIMEOUT_SECS: u64 = 300;
+
+/// Exit code recorded for commands killed after exceeding their timeout.
+pub const TIMEOUT_EXIT_CODE: i32 = 124;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TerminalBlock {
@@ -41,6 +48,7 @@ pub struct TerminalSession {
     pub name: String,
     pub working_directory: std::path::PathBuf,
     pub shell: String,
+    pub environment: HashMap<String, String>,
     pub blocks: Vec<TerminalBlock>,
     pub active: bool,
     pub created_at: chrono::DateTime<chrono::Utc>,
@@ -52,13 +60,52 @@ impl TerminalSession {
             id: Uuid::new_v4(),
             name,
             working_directory,
-            shell: std::env::var("SHELL").unwrap_or_else(|_| "/bin/bash".to_string()),
+            shell: Self::default_shell(),
+            environment: HashMap::new(),
             blocks: Vec::new(),
             active: true,
             created_at: chrono::Utc::now(),
         }
     }
     
+    pub fn with_overrides(
+        name: String,
+        working_directory: std::path::PathBuf,
+        shell: Option<String>,
+        environment: HashMap<String, String>,
+    ) -> Self {
+        let mut session = Self::new(name, working_directory);
+        if let Some(shell) = shell {
+            session.shell = shell;
+        }
+        session.environment = environment;
+        session
+    }
+    
+    pub fn default_shell() -> String {
+        std::env::var("SHELL").unwrap_or_else(|_| "/bin/bash".to_string())
+    }
+    
+    /// Resolves a shell given either as a path or a bare name looked up on `$PATH`.
+    pub fn resolve_shell(shell: &str) -> Result<String> {
+        let shell_path = std::path::Path::new(shell);
+        if shell_path.components().count() > 1 {
+            if shell_path.is_file() {
+                return Ok(shell.to_string());
+            }
+            return Err(ResourceError::NotFound(format!("Shell not found: {}", shell)).into());
+        }
+        
+        std::env::var_os("PATH")
+            .and_then(|paths| {
+                std::env::split_paths(&paths)
+                    .map(|dir| dir.join(shell))
+                    .find(|candidate| candidate.is_file())
+            })
+            .map(|path| path.to_string_lossy().to_string())
+            .ok_or_else(|| ResourceError::NotFound(format!("Shell not found on PATH: {}", shell)).into())
+    }
+    
     pub fn add_block(&mut self, block: TerminalBlock) {
         self.blocks.push(block);
     }
@@ -72,6 +119,55 @@ impl TerminalSession {
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandSnippet {
+    pub name: String,
+    pub template: String,
+    pub description: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl CommandSnippet {
+    pub fn new(name: String, template: String, description: Option<String>) -> Result<Self> {
+        if name.is_empty() || name.chars().any(char::is_whitespace) {
+            return Err(anyhow::anyhow!("Snippet name must be a single non-empty word: {:?}", name));
+        }
+        if template.trim().is_empty() {
+            return Err(anyhow::anyhow!("Snippet template cannot be empty: {}", name));
+        }
+        
+        Ok(Self {
+            name,
+            template,
+            description,
+            created_at: chrono::Utc::now(),
+        })
+    }
+    
+    /// Substitutes every `${var}` placeholder in the template with its value from `args`.
+    pub fn expand(&self, args: &HashMap<String, String>) -> Result<String> {
+        let mut expanded = String::new();
+        let mut rest = self.template.as_str();
+        
+        while let Some(start) = rest.find("${") {
+            expanded.push_str(&rest[..start]);
+            let after = &rest[start + 2..];
+            let end = after.find('}').ok_or_else(|| {
+                anyhow::anyhow!("Unterminated placeholder in snippet '{}'", self.name)
+            })?;
+            let var = &after[..end];
+            let value = args.get(var).ok_or_else(|| {
+                anyhow::anyhow!("Missing value for '${{{}}}' in snippet '{}'", var, self.name)
+            })?;
+            expanded.push_str(value);
+            rest = &after[end + 1..];
+        }
+        expanded.push_str(rest);
+        
+        Ok(expanded)
+    }
+}
+
 #[derive(Debug)]
 pub struct ActiveTerminal {
     pub child: Arc<Mutex<Option<Child>>>,
@@ -84,19 +180,130 @@ pub struct TerminalManager {
     sessions: Arc<RwLock<HashMap<Uuid, TerminalSession>>>,
     active_terminals: Arc<RwLock<HashMap<Uuid, ActiveTerminal>>>,
     event_bus: code_furnace_events::EventBus,
+    default_timeout: Duration,
+    snippets: Arc<RwLock<HashMap<String, CommandSnippet>>>,
+    /// Where snippets are saved; they are kept in memory only without one.
+    snippets_file: Option<std::path::PathBuf>,
 }
 
 impl TerminalManager {
     pub fn new(event_bus: code_furnace_events::EventBus) -> Self {
+        Self::with_command_timeout(event_bus, Duration::from_secs(DEFAULT_COMMAND_TIMEOUT_SECS))
+    }
+    
+    pub fn with_command_timeout(event_bus: code_furnace_events::EventBus, default_timeout: Duration) -> Self {
+        let config_dir = code_furnace_utils::paths::get_config_dir()
+            .map_err(|e| tracing::warn!("Terminal snippets will not be saved: {}", e))
+            .ok();
+        Self::with_config_dir(event_bus, default_timeout, config_dir)
+    }
+    
+    /// Like `with_command_timeout`, but keeps snippets in `config_dir` instead
+    /// of the user's config directory, or in memory only without one.
+    pub fn with_config_dir(
+        event_bus: code_furnace_events::EventBus,
+        default_timeout: Duration,
+        config_dir: Option<std::path::PathBuf>,
+    ) -> Self {
+        let snippets_file = config_dir.map(|dir| dir.join("snippets.json"));
+        
         Self {
             sessions: Arc::new(RwLock::new(HashMap::new())),
             active_terminals: Arc::new(RwLock::new(HashMap::new())),
             event_bus,
+            default_timeout,
+            snippets: Arc::new(RwLock::new(Self::load_snippets(snippets_file.as_deref()))),
+            snippets_file,
+        }
+    }
+    
+    fn load_snippets(path: Option<&std::path::Path>) -> HashMap<String, CommandSnippet> {
+        let Some(path) = path.filter(|path| path.exists()) else {
+            return HashMap::new();
+        };
+        let snippets = std::fs::read_to_string(path)
+            .map_err(anyhow::Error::from)
+            .and_then(|content| Ok(serde_json::from_str::<HashMap<String, CommandSnippet>>(&content)?));
+        
+        snippets.unwrap_or_else(|e| {
+            tracing::warn!("Failed to load terminal snippets: {}", e);
+            HashMap::new()
+        })
+    }
+    
+    fn save_snippets(&self, snippets: &HashMap<String, CommandSnippet>) -> Result<()> {
+        let Some(path) = &self.snippets_file else {
+            return Ok(());
+        };
+        let content = serde_json::to_string_pretty(snippets)?;
+        std::fs::write(path, content)?;
+        Ok(())
+    }
+    
+    pub async fn register_snippet(&self, name: String, template: String, description: Option<String>) -> Result<()> {
+        let snippet = CommandSnippet::new(name.clone(), template, description)?;
+        
+        let mut snippets = self.snippets.write().await;
+        snippets.insert(name.clone(), snippet);
+        self.save_snippets(&snippets)?;
+        
+        let event = code_furnace_events::Event::new(
+            "terminal.snippet.registered",
+            "terminal-manager",
+            serde_json::json!({
+                "name": name
+            }),
+        );
+        self.event_bus.publish(event)?;
+        
+        Ok(())
+    }
+    
+    pub async fn list_snippets(&self) -> Vec<CommandSnippet> {
+        let snippets = self.snippets.read().await;
+        let mut snippet_list: Vec<CommandSnippet> = snippets.values().cloned().collect();
+        snippet_list.sort_by(|a, b| a.name.cmp(&b.name));
+        snippet_list
+    }
+    
+    /// Expands a leading snippet token, keeping any trailing words as extra arguments.
+    /// Commands that don't start with a snippet are returned unchanged, unless
+    /// snippet arguments were supplied for them.
+    pub async fn expand_command(&self, command: &str, args: &HashMap<String, String>) -> Result<String> {
+        let trimmed = command.trim_start();
+        let (token, remainder) = trimmed
+            .split_once(char::is_whitespace)
+            .unwrap_or((trimmed, ""));
+        
+        let snippets = self.snippets.read().await;
+        match snippets.get(token) {
+            Some(snippet) => {
+                let mut expanded = snippet.expand(args)?;
+                let remainder = remainder.trim();
+                if !remainder.is_empty() {
+                    expanded.push(' ');
+                    expanded.push_str(remainder);
+                }
+                Ok(expanded)
+            }
+            None if !args.is_empty() => {
+                Err(anyhow::anyhow!("Unknown snippet '{}': arguments were provided but no snippet with that name is registered", token))
+            }
+            None => Ok(command.to_string()),
         }
     }
     
-    pub async fn create_session(&self, name: String, working_directory: std::path::PathBuf) -> Result<Uuid> {
-        let session = TerminalSession::new(name, working_directory.clone());
+    pub async fn create_session(
+        &self,
+        name: String,
+        working_directory: std::path::PathBuf,
+        shell: Option<String>,
+        environment: HashMap<String, String>,
+    ) -> Result<Uuid> {
+        let shell = shell
+            .map(|shell| TerminalSession::resolve_shell(&shell))
+            .transpose()?;
+        let session = TerminalSession::with_overrides(name, working_directory.clone(), shell, environment);
         let session_id = session.id;
         
         // Set up communication channels
@@ -121,73 +328,104 @@ impl TerminalManager {
         let event = code_furnace_events::Event::new(
             "terminal.session.created",
             "terminal-manager",
-            serde_json::to_value(&session_id)?,
+            serde_json::to_value(session_id)?,
         );
         self.event_bus.publish(event)?;
         
         Ok(session_id)
     }
     
-    pub async fn execute_command(&self, session_id: Uuid, command: String) -> Result<Uuid> {
+    pub async fn execute_command(
+        &self,
+        session_id: Uuid,
+        command: String,
+        snippet_args: HashMap<String, String>,
+        timeout: Option<Duration>,
+    ) -> Result<Uuid> {
+        let command = self.expand_command(&command, &snippet_args).await?;
         let mut sessions = self.sessions.write().await;
         
         if let Some(session) = sessions.get_mut(&session_id) {
             let mut block = TerminalBlock::new(command.clone(), session.working_directory.clone());
+            block.environment.extend(session.environment.clone());
             let start_time = chrono::Utc::now();
+            let timeout = timeout.unwrap_or(self.default_timeout);
             
-            // Execute command using tokio process with better output handling
-            let shell = std::env::var("SHELL").unwrap_or_else(|_| "/bin/bash".to_string());
-            
-            let mut child = Command::new(&shell)
+            // Execute command using the session's shell and environment overrides
+            let mut child = Command::new(&session.shell)
                 .arg("-c")
                 .arg(&command)
                 .current_dir(&session.working_directory)
+                .envs(&session.environment)
                 .stdout(Stdio::piped())
                 .stderr(Stdio::piped())
+                .kill_on_drop(true)
                 .spawn()?;
             
             let stdout = child.stdout.take().ok_or_else(|| anyhow::anyhow!("Failed to get stdout"))?;
             let stderr = child.stderr.take().ok_or_else(|| anyhow::anyhow!("Failed to get stderr"))?;
             
-            // Read stdout and stderr concurrently
-            let stdout_task = async {
-                let mut stdout_reader = BufReader::new(stdout);
-                let mut output = String::new();
-                let mut line = String::new();
-                while stdout_reader.read_line(&mut line).await.unwrap_or(0) > 0 {
-                    output.push_str(&line);
-                    line.clear();
-                }
-                output
-            };
+            // Read stdout and stderr concurrently into shared buffers so partial
+            // output survives a timeout
+            let stdout_buffer = Arc::new(Mutex::new(String::new()));
+            let stderr_buffer = Arc::new(Mutex::new(String::new()));
+            let stdout_task = tokio::spawn(Self::capture_stream(stdout, stdout_buffer.clone()));
+            let stderr_task = tokio::spawn(Self::capture_stream(stderr, stderr_buffer.clone()));
             
-            let stderr_task = async {
-                let mut stderr_reader = BufReader::new(stderr);
-                let mut output = String::new();
-                let mut line = String::new();
-                while stderr_reader.read_line(&mut line).await.unwrap_or(0) > 0 {
-                    output.push_str(&line);
-                    line.clear();
+            let (exit_code, timed_out) = match tokio::time::timeout(timeout, child.wait()).await {
+                Ok(exit_status) => (exit_status?.code(), false),
+                Err(_) => {
+                    if let Err(e) = child.kill().await {
+                        tracing::warn!("Failed to kill timed out command: {}", e);
+                    }
+                    (Some(TIMEOUT_EXIT_CODE), true)
                 }
-                output
             };
             
-            // Wait for both output streams and process completion
-            let (stdout_output, stderr_output) = tokio::join!(stdout_task, stderr_task);
-            let exit_status = child.wait().await?;
+            // Give the readers a moment to drain; orphaned grandchildren may keep
+            // the pipes open after a kill
+            let drain_timeout = Duration::from_secs(1);
+            let (stdout_drained, stderr_drained) = tokio::join!(
+                tokio::time::timeout(drain_timeout, stdout_task),
+                tokio::time::timeout(drain_timeout, stderr_task),
+            );
+            if stdout_drained.is_err() || stderr_drained.is_err() {
+                tracing::debug!("Output reader still open after command exit");
+            }
             
             // Combine outputs
-            block.output = stdout_output;
+            block.output = stdout_buffer.lock().await.clone();
+            let stderr_output = stderr_buffer.lock().await.clone();
             if !stderr_output.is_empty() {
                 block.output.push_str(&format!("\nSTDERR:\n{}", stderr_output));
             }
+            if timed_out {
+                if !block.output.is_empty() && !block.output.ends_with('\n') {
+                    block.output.push('\n');
+                }
+                block.output.push_str(&format!("[command timed out after {}s]\n", timeout.as_secs()));
+            }
             
-            block.exit_code = exit_status.code();
+            block.exit_code = exit_code;
             block.duration = Some(chrono::Utc::now() - start_time);
             
             let block_id = block.id;
             session.add_block(block);
             
+            if timed_out {
+                let event = code_furnace_events::Event::new(
+                    "terminal.command.timeout",
+                    "terminal-manager",
+                    serde_json::json!({
+                        "session_id": session_id,
+                        "block_id": block_id,
+                        "command": command,
+                        "timeout_secs": timeout.as_secs()
+                    }),
+                );
+                self.event_bus.publish(event)?;
+            }
+            
             // Publish command executed event
             let event = code_furnace_events::Event::new(
                 "terminal.command.executed",
@@ -196,14 +434,23 @@ impl TerminalManager {
                     "session_id": session_id,
                     "block_id": block_id,
                     "command": command,
-                    "exit_code": exit_status.code()
+                    "exit_code": exit_code
                 }),
             );
             self.event_bus.publish(event)?;
             
             Ok(block_id)
         } else {
-            Err(anyhow::anyhow!("Session not found: {}", session_id))
+            Err(ResourceError::NotFound(format!("Session not found: {}", session_id)).into())
+        }
+    }
+    
+    async fn capture_stream<R: AsyncRead + Unpin>(stream: R, buffer: Arc<Mutex<String>>) {
+        let mut reader = BufReader::new(stream);
+        let mut line = String::new();
+        while reader.read_line(&mut line).await.unwrap_or(0) > 0 {
+            buffer.lock().await.push_str(&line);
+            line.clear();
         }
     }
     
@@ -225,7 +472,7 @@ impl TerminalManager {
             let _ = active_terminal.output_tx.send(format!("Input received: {}", input));
             Ok(())
         } else {
-            Err(anyhow::anyhow!("Active terminal not found: {}", session_id))
+            Err(ResourceError::NotFound(format!("Active terminal not found: {}", session_id)).into())
         }
     }
     
@@ -262,10 +509,76 @@ impl TerminalManager {
         let event = code_furnace_events::Event::new(
             "terminal.session.closed",
             "terminal-manager",
-            serde_json::to_value(&session_id)?,
+            serde_json::to_value(session_id)?,
         );
         self.event_bus.publish(event)?;
         
         Ok(())
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn manager(config_dir: Option<std::path::PathBuf>) -> TerminalManager {
+        TerminalManager::with_config_dir(
+            code_furnace_events::EventBus::new(),
+            Duration::from_secs(DEFAULT_COMMAND_TIMEOUT_SECS),
+            config_dir,
+        )
+    }
+    
+    fn args(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+    
+    #[tokio::test]
+    async fn snippets_expand_their_placeholders() {
+        let manager = manager(None);
+        manager
+            .register_snippet("push".to_string(), "git push ${remote} ${branch}".to_string(), None)
+            .await
+            .unwrap();
+        
+        let expanded = manager
+            .expand_command("push --force", &args(&[("remote", "origin"), ("branch", "main")]))
+            .await
+            .unwrap();
+        assert_eq!(expanded, "git push origin main --force");
+        
+        let missing = manager.expand_command("push", &args(&[("remote", "origin")])).await;
+        assert!(missing.unwrap_err().to_string().contains("${branch}"));
+    }
+    
+    #[tokio::test]
+    async fn unknown_snippets_run_as_plain_commands_unless_given_arguments() {
+        let manager = manager(None);
+        
+        assert_eq!(manager.expand_command("ls -la", &HashMap::new()).await.unwrap(), "ls -la");
+        let error = manager.expand_command("deploy", &args(&[("env", "prod")])).await.unwrap_err();
+        assert!(error.to_string().contains("Unknown snippet 'deploy'"));
+    }
+    
+    #[test]
+    fn unterminated_placeholders_are_rejected() {
+        let snippet = CommandSnippet::new("bad".to_string(), "echo ${name".to_string(), None).unwrap();
+        assert!(snippet.expand(&args(&[("name", "x")])).is_err());
+        assert!(CommandSnippet::new("two words".to_string(), "echo".to_string(), None).is_err());
+    }
+    
+    #[tokio::test]
+    async fn snippets_are_saved_in_the_config_dir() {
+        let dir = std::env::temp_dir().join(format!("code-furnace-snippets-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        
+        manager(Some(dir.clone()))
+            .register_snippet("hi".to_string(), "echo ${who}".to_string(), Some("Greets".to_string()))
+            .await
+            .unwrap();
+        let reloaded = manager(Some(dir.clone())).list_snippets().await;
+        assert_eq!(reloaded.len(), 1);
+        assert_eq!(reloaded[0].template, "echo ${who}");
+        
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,19 +1,211 @@
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
+/// Per-project settings file, read from a project's root directory.
+pub const PROJECT_CONFIG_FILE: &str = ".code-furnace.json";
+
+/// Failures every crate reports the same way, so the app can tell them apart
+/// without reading the message. Each carries the full message.
+#[derive(Debug, thiserror::Error)]
+pub enum ResourceError {
+    /// An id, name or path that does not refer to anything.
+    #[error("{0}")]
+    NotFound(String),
+    /// A path or domain outside what the caller may reach.
+    #[error("{0}")]
+    AccessDenied(String),
+}
+
+/// App settings. The global config.json sets them for every project; a
+/// project can override any of them in its own `.code-furnace.json` (see
+/// [`Config::merged_with_project`]), and whatever neither sets keeps its
+/// default.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Config {
-    pub agent_api_key: Option<String>,
-    pub agent_provider: AgentProvider,
+    /// Agent providers the user has set up, at most one per kind. Config
+    /// files from before this list existed have their single provider moved
+    /// into it on load.
+    pub providers: Vec<ProviderConfig>,
     pub log_level: String,
     pub workspace_path: Option<PathBuf>,
+    /// Forge tokens are kept in the OS keychain; older config files that
+    /// still hold them in plain text are migrated on load.
+    #[serde(skip_serializing)]
     pub github_token: Option<String>,
+    #[serde(skip_serializing)]
     pub gitlab_token: Option<String>,
+    #[serde(skip_serializing)]
     pub gitea_token: Option<String>,
+    /// Bitbucket access token, or app password when `bitbucket_username` is set.
+    #[serde(skip_serializing)]
+    pub bitbucket_token: Option<String>,
+    /// Secrets saved in the OS keychain, by the name they are stored under.
+    /// config.json only records which ones exist.
+    pub keychain_secrets: Vec<String>,
+    /// Secrets in `keychain_secrets` that could not be read on load. They
+    /// stay in the keychain and are tried again on the next load.
+    #[serde(skip)]
+    pub unreadable_secrets: Vec<String>,
+    /// Secrets the user removed since load; saving deletes them from the
+    /// keychain. A secret that is merely unset is never deleted.
+    #[serde(skip)]
+    pub cleared_secrets: Vec<String>,
+    pub bitbucket_username: Option<String>,
+    /// Pages a GitHub, GitLab or Gitea list call follows before returning
+    /// what it has so far.
+    pub forge_max_pages: u32,
     pub auto_save: bool,
+    /// Idle time after the last edit before a dirty buffer is auto-saved.
+    pub auto_save_delay_ms: u64,
     pub theme: String,
     pub font_size: u32,
     pub enable_lsp: bool,
+    /// Times a rate-limited or failed agent request is retried; 0 disables retrying.
+    pub agent_max_retries: u32,
+    /// Approximate tokens an agent request may use for its prompt, recent
+    /// history and attached files; older history and then the largest files
+    /// are dropped to stay under it.
+    pub agent_context_token_budget: usize,
+    /// Replace dropped history with a short summary line instead of omitting it.
+    pub agent_summarize_truncated_context: bool,
+    /// Agent tools the user has allowed to run, e.g. `read_file` or
+    /// `run_command`. Calls to any other tool that needs permission are refused.
+    pub agent_allowed_tools: Vec<String>,
+    /// Default time a terminal command may run before it is killed.
+    pub command_timeout_secs: u64,
+    /// Deepest directory level below the workspace root the file tree will expand.
+    pub file_tree_max_depth: usize,
+    /// Hide files matched by `.gitignore`; when false the built-in ignore list is used.
+    pub respect_gitignore: bool,
+    /// Extra gitignore-style patterns hidden from the file tree, e.g. `*.lock` or
+    /// `coverage/`. They apply on top of `.gitignore` and the always-ignored
+    /// entries; a `!pattern` here only re-includes what an earlier entry in this
+    /// list excluded.
+    pub ignore_patterns: Vec<String>,
+    /// Files larger than this are opened read-only.
+    pub large_file_threshold_bytes: u64,
+    /// Save every file with LF line endings instead of keeping each file's own.
+    pub force_lf_line_endings: bool,
+    pub tab_size: u32,
+    pub insert_spaces: bool,
+    /// Run the language server's formatter before each manual save.
+    pub format_on_save: bool,
+    /// Strip trailing spaces and tabs from every line on manual save.
+    pub trim_trailing_whitespace: bool,
+    /// End non-empty files with a newline on manual save.
+    pub insert_final_newline: bool,
+    /// Language server overrides keyed by language (e.g. "python"). Entries for
+    /// a built-in language replace its command and args, and its file extensions
+    /// when given; other entries add a new language. An empty command disables
+    /// the server for that language.
+    pub lsp_servers: HashMap<String, LspServerConfig>,
+    /// Unread events the channel delivering every event keeps before slow
+    /// subscribers start missing them.
+    pub event_bus_capacity: usize,
+    /// Unread events each per-type or pattern event channel keeps.
+    pub event_channel_capacity: usize,
+    /// Recent events kept so a newly opened panel can replay them; 0 keeps none.
+    pub event_log_size: usize,
+    /// Reopen the files, terminals and project that were open when the app
+    /// last exited.
+    pub restore_session: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LspServerConfig {
+    pub command: String,
+    pub args: Vec<String>,
+    pub file_extensions: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModelSettings {
+    pub model: Option<String>,
+    pub temperature: Option<f64>,
+    pub max_tokens: Option<u32>,
+}
+
+/// Largest `max_tokens` accepted for a single response.
+pub const MAX_RESPONSE_TOKENS: u32 = 200_000;
+
+impl ModelSettings {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.model.as_ref().is_some_and(|model| model.trim().is_empty()) {
+            return Err(anyhow::anyhow!("Model name cannot be empty"));
+        }
+        
+        if let Some(temperature) = self.temperature {
+            if !(0.0..=2.0).contains(&temperature) {
+                return Err(anyhow::anyhow!("Temperature must be between 0 and 2"));
+            }
+        }
+        
+        if let Some(max_tokens) = self.max_tokens {
+            if max_tokens == 0 || max_tokens > MAX_RESPONSE_TOKENS {
+                return Err(anyhow::anyhow!("Max tokens must be between 1 and {}", MAX_RESPONSE_TOKENS));
+            }
+        }
+        
+        Ok(())
+    }
+}
+
+/// One configured agent provider.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderConfig {
+    pub kind: AgentProvider,
+    /// Kept in the OS keychain, not in config.json.
+    #[serde(skip_serializing)]
+    pub api_key: Option<String>,
+    /// Model overrides; unset fields keep the provider's built-in defaults.
+    #[serde(default)]
+    pub model: ModelSettings,
+    /// Answers requests that do not name a provider.
+    #[serde(default)]
+    pub default: bool,
+}
+
+impl ProviderConfig {
+    pub fn new(kind: AgentProvider, api_key: Option<String>) -> Self {
+        Self {
+            kind,
+            api_key,
+            model: ModelSettings::default(),
+            default: false,
+        }
+    }
+    
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if let Some(ref api_key) = self.api_key {
+            if api_key.is_empty() {
+                return Err(anyhow::anyhow!("Agent API key cannot be empty"));
+            }
+            
+            match self.kind {
+                AgentProvider::Claude => {
+                    if !api_key.starts_with("sk-ant-") {
+                        return Err(anyhow::anyhow!("Invalid Claude API key format"));
+                    }
+                }
+                AgentProvider::OpenAI => {
+                    if !api_key.starts_with("sk-") {
+                        return Err(anyhow::anyhow!("Invalid OpenAI API key format"));
+                    }
+                }
+                AgentProvider::Ollama { .. } => {
+                    // Ollama typically doesn't require API keys for local instances
+                }
+            }
+        }
+        
+        self.model
+            .validate()
+            .map_err(|e| anyhow::anyhow!("Invalid model settings for {}: {}", self.kind.name(), e))
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -23,35 +215,107 @@ pub enum AgentProvider {
     Ollama { endpoint: String },
 }
 
+impl AgentProvider {
+    /// Name the provider is registered and configured under.
+    pub fn name(&self) -> &'static str {
+        match self {
+            AgentProvider::Claude => "claude",
+            AgentProvider::OpenAI => "openai",
+            AgentProvider::Ollama { .. } => "ollama",
+        }
+    }
+}
+
 impl Default for Config {
     fn default() -> Self {
         Self {
-            agent_api_key: None,
-            agent_provider: AgentProvider::Claude,
+            providers: Vec::new(),
             log_level: "info".to_string(),
             workspace_path: None,
             github_token: None,
             gitlab_token: None,
             gitea_token: None,
+            bitbucket_token: None,
+            keychain_secrets: Vec::new(),
+            unreadable_secrets: Vec::new(),
+            cleared_secrets: Vec::new(),
+            bitbucket_username: None,
+            forge_max_pages: 10,
             auto_save: true,
+            auto_save_delay_ms: 1000,
             theme: "dark".to_string(),
             font_size: 14,
             enable_lsp: true,
+            agent_max_retries: 3,
+            agent_context_token_budget: 100_000,
+            agent_summarize_truncated_context: true,
+            agent_allowed_tools: Vec::new(),
+            command_timeout_secs: 300,
+            file_tree_max_depth: 32,
+            respect_gitignore: true,
+            ignore_patterns: Vec::new(),
+            large_file_threshold_bytes: 10 * 1024 * 1024,
+            force_lf_line_endings: false,
+            tab_size: 4,
+            insert_spaces: true,
+            format_on_save: false,
+            trim_trailing_whitespace: false,
+            insert_final_newline: false,
+            lsp_servers: HashMap::new(),
+            event_bus_capacity: 1000,
+            event_channel_capacity: 100,
+            event_log_size: 0,
+            restore_session: true,
         }
     }
 }
 
 impl Config {
+    /// Reads config.json and fills in the secrets kept in the OS keychain.
+    /// Secrets an older config file still holds in plain text are moved into
+    /// the keychain and scrubbed from the file, and a single configured agent
+    /// provider is moved into `providers`.
     pub fn load() -> anyhow::Result<Self> {
-        let config_dir = dirs::config_dir()
-            .ok_or_else(|| anyhow::anyhow!("Could not find config directory"))?
-            .join("code-furnace");
+        let config_dir = paths::get_config_dir()?;
         
         let config_file = config_dir.join("config.json");
         
         if config_file.exists() {
             let content = std::fs::read_to_string(&config_file)?;
-            let config: Self = serde_json::from_str(&content)?;
+            let raw: serde_json::Value = serde_json::from_str(&content)?;
+            let mut config: Self = serde_json::from_value(raw.clone())?;
+            let keychain_secrets = config.keychain_secrets.clone();
+            
+            let mut needs_migration = false;
+            let mut unreadable_secrets = Vec::new();
+            for (name, secret) in config.secrets_mut() {
+                if secret.is_some() {
+                    needs_migration = true;
+                } else if keychain_secrets.contains(&name) {
+                    match secrets::get(&name) {
+                        Ok(stored) => *secret = stored,
+                        Err(e) => {
+                            tracing::warn!("Could not read {} from the OS keychain: {}", name, e);
+                            unreadable_secrets.push(name);
+                        }
+                    }
+                }
+            }
+            config.unreadable_secrets = unreadable_secrets;
+            
+            if config.providers.is_empty() {
+                if let Some(provider) = legacy_provider(&raw, &keychain_secrets) {
+                    config.providers.push(provider);
+                    needs_migration = true;
+                }
+            }
+            
+            if needs_migration {
+                match config.save() {
+                    Ok(()) => tracing::info!("Migrated config.json: secrets now live in the OS keychain"),
+                    Err(e) => tracing::warn!("Could not migrate config.json: {}", e),
+                }
+            }
             Ok(config)
         } else {
             let config = Self::default();
@@ -60,49 +324,84 @@ impl Config {
         }
     }
     
+    /// Writes config.json, keeping secrets in the OS keychain instead. Fails
+    /// without touching the file if a secret cannot be stored there. Only
+    /// `cleared_secrets` are deleted from the keychain.
     pub fn save(&self) -> anyhow::Result<()> {
-        let config_dir = dirs::config_dir()
-            .ok_or_else(|| anyhow::anyhow!("Could not find config directory"))?
-            .join("code-furnace");
+        let config_dir = paths::get_config_dir()?;
         
-        std::fs::create_dir_all(&config_dir)?;
+        let mut stored = self.clone();
+        let mut saved = Vec::new();
+        for (name, secret) in stored.secrets_mut() {
+            if let Some(secret) = secret {
+                secrets::set(&name, secret)
+                    .map_err(|e| anyhow::anyhow!("Could not store {} in the OS keychain: {}", name, e))?;
+                saved.push(name);
+            }
+        }
+        for name in self.cleared_secrets.iter().filter(|name| !saved.contains(name)) {
+            secrets::delete(name)?;
+        }
+        // Secrets without a value here, e.g. unreadable ones, stay in the keychain
+        let kept: Vec<String> = self
+            .keychain_secrets
+            .iter()
+            .filter(|name| !saved.contains(name) && !self.cleared_secrets.contains(name))
+            .cloned()
+            .collect();
+        saved.extend(kept);
+        stored.keychain_secrets = saved;
         
         let config_file = config_dir.join("config.json");
-        let content = serde_json::to_string_pretty(self)?;
+        let content = serde_json::to_string_pretty(&stored)?;
         std::fs::write(config_file, content)?;
         
         Ok(())
     }
     
+    /// Every secret field, by the name it is stored under in the keychain.
+    fn secrets_mut(&mut self) -> Vec<(String, &mut Option<String>)> {
+        let mut secrets = vec![
+            ("github_token".to_string(), &mut self.github_token),
+            ("gitlab_token".to_string(), &mut self.gitlab_token),
+            ("gitea_token".to_string(), &mut self.gitea_token),
+            ("bitbucket_token".to_string(), &mut self.bitbucket_token),
+        ];
+        for provider in &mut self.providers {
+            secrets.push((format!("agent_api_key.{}", provider.kind.name()), &mut provider.api_key));
+        }
+        secrets
+    }
+    
     pub fn validate(&self) -> anyhow::Result<()> {
         // Validate agent configuration
-        if let Some(ref api_key) = self.agent_api_key {
-            if api_key.is_empty() {
-                return Err(anyhow::anyhow!("Agent API key cannot be empty"));
-            }
-            
-            match self.agent_provider {
-                AgentProvider::Claude => {
-                    if !api_key.starts_with("sk-ant-") {
-                        return Err(anyhow::anyhow!("Invalid Claude API key format"));
-                    }
-                }
-                AgentProvider::OpenAI => {
-                    if !api_key.starts_with("sk-") {
-                        return Err(anyhow::anyhow!("Invalid OpenAI API key format"));
-                    }
-                }
-                AgentProvider::Ollama { .. } => {
-                    // Ollama typically doesn't require API keys for local instances
-                }
+        for (index, provider) in self.providers.iter().enumerate() {
+            provider.validate()?;
+            if self.providers[..index].iter().any(|other| other.kind.name() == provider.kind.name()) {
+                return Err(anyhow::anyhow!("The {} provider is configured more than once", provider.kind.name()));
             }
         }
+        if self.providers.iter().filter(|provider| provider.default).count() > 1 {
+            return Err(anyhow::anyhow!("Only one agent provider can be the default"));
+        }
         
         // Validate font size
         if self.font_size < 8 || self.font_size > 72 {
             return Err(anyhow::anyhow!("Font size must be between 8 and 72"));
         }
         
+        if self.command_timeout_secs == 0 {
+            return Err(anyhow::anyhow!("Command timeout must be greater than zero"));
+        }
+        
+        if self.forge_max_pages == 0 {
+            return Err(anyhow::anyhow!("Forge max pages must be greater than zero"));
+        }
+        
+        if self.event_bus_capacity == 0 || self.event_channel_capacity == 0 {
+            return Err(anyhow::anyhow!("Event channel capacities must be greater than zero"));
+        }
+        
         // Validate theme
         if !["dark", "light", "auto"].contains(&self.theme.as_str()) {
             return Err(anyhow::anyhow!("Theme must be 'dark', 'light', or 'auto'"));
@@ -111,26 +410,132 @@ impl Config {
         Ok(())
     }
     
+    /// This config with the overrides in `project_root`'s
+    /// `.code-furnace.json` laid over it, so a project setting wins over the
+    /// global one, which wins over the default. Objects are merged key by
+    /// key; any other value, lists included, replaces the global one.
+    /// Secrets and `lsp_servers` always come from the global config, never
+    /// from a project file: a cloned repository must not be able to choose
+    /// the commands the editor runs. Without a project file this is the
+    /// config unchanged.
+    pub fn merged_with_project(&self, project_root: &Path) -> anyhow::Result<Self> {
+        let project_file = project_root.join(PROJECT_CONFIG_FILE);
+        if !project_file.exists() {
+            return Ok(self.clone());
+        }
+        
+        let content = std::fs::read_to_string(&project_file)?;
+        let mut overrides: serde_json::Value = serde_json::from_str(&content)
+            .map_err(|e| anyhow::anyhow!("Could not parse {}: {}", project_file.display(), e))?;
+        let Some(fields) = overrides.as_object_mut() else {
+            return Err(anyhow::anyhow!("{} must hold a JSON object", project_file.display()));
+        };
+        if fields.remove("lsp_servers").is_some() {
+            tracing::warn!(
+                "Ignoring lsp_servers in {}; language servers can only be set in the global config",
+                project_file.display()
+            );
+        }
+        
+        let mut merged = serde_json::to_value(self)?;
+        merge_json(&mut merged, overrides);
+        let mut merged: Self = serde_json::from_value(merged)
+            .map_err(|e| anyhow::anyhow!("Invalid setting in {}: {}", project_file.display(), e))?;
+        
+        let mut global = self.clone();
+        let global_secrets: HashMap<String, Option<String>> = global
+            .secrets_mut()
+            .into_iter()
+            .map(|(name, secret)| (name, secret.clone()))
+            .collect();
+        for (name, secret) in merged.secrets_mut() {
+            *secret = global_secrets.get(&name).cloned().flatten();
+        }
+        merged.keychain_secrets = self.keychain_secrets.clone();
+        merged.unreadable_secrets = self.unreadable_secrets.clone();
+        merged.cleared_secrets = self.cleared_secrets.clone();
+        
+        merged
+            .validate()
+            .map_err(|e| anyhow::anyhow!("Invalid settings in {}: {}", project_file.display(), e))?;
+        Ok(merged)
+    }
+    
     pub fn has_agent_configured(&self) -> bool {
-        self.agent_api_key.is_some() && !self.agent_api_key.as_ref().unwrap().is_empty()
+        self.providers
+            .iter()
+            .any(|provider| provider.api_key.as_ref().is_some_and(|key| !key.is_empty()))
+    }
+    
+    /// The provider configured under `name` ("claude", "openai", "ollama").
+    pub fn provider(&self, name: &str) -> Option<&ProviderConfig> {
+        self.providers.iter().find(|provider| provider.kind.name() == name)
+    }
+    
+    /// The provider marked as default, or the first one if none is.
+    pub fn default_provider(&self) -> Option<&ProviderConfig> {
+        self.providers
+            .iter()
+            .find(|provider| provider.default)
+            .or_else(|| self.providers.first())
     }
     
     pub fn has_git_tokens(&self) -> bool {
-        self.github_token.is_some() || self.gitlab_token.is_some() || self.gitea_token.is_some()
+        self.github_token.is_some()
+            || self.gitlab_token.is_some()
+            || self.gitea_token.is_some()
+            || self.bitbucket_token.is_some()
     }
     
-    pub fn update_agent_config(&mut self, provider: AgentProvider, api_key: Option<String>) -> anyhow::Result<()> {
-        self.agent_provider = provider;
-        self.agent_api_key = api_key;
+    /// Sets the key, and the model settings when given, of the provider entry
+    /// for `provider`'s kind, adding the entry if there is none.
+    /// `make_default` also makes it the default provider.
+    pub fn update_agent_config(
+        &mut self,
+        provider: AgentProvider,
+        api_key: Option<String>,
+        model_settings: Option<ModelSettings>,
+        make_default: bool,
+    ) -> anyhow::Result<()> {
+        let index = match self.providers.iter().position(|entry| entry.kind.name() == provider.name()) {
+            Some(index) => index,
+            None => {
+                self.providers.push(ProviderConfig::new(provider.clone(), None));
+                self.providers.len() - 1
+            }
+        };
+        
+        let entry = &mut self.providers[index];
+        entry.kind = provider;
+        let name = format!("agent_api_key.{}", entry.kind.name());
+        replace_secret(&mut entry.api_key, api_key, &name, &mut self.cleared_secrets);
+        if let Some(settings) = model_settings {
+            entry.model = settings;
+        }
+        if make_default {
+            for (other, entry) in self.providers.iter_mut().enumerate() {
+                entry.default = other == index;
+            }
+        }
+        
         self.validate()?;
         self.save()?;
         Ok(())
     }
     
+    pub fn set_agent_tool_allowed(&mut self, tool: &str, allowed: bool) -> anyhow::Result<()> {
+        self.agent_allowed_tools.retain(|name| name != tool);
+        if allowed {
+            self.agent_allowed_tools.push(tool.to_string());
+        }
+        self.save()?;
+        Ok(())
+    }
+    
     pub fn update_git_tokens(&mut self, github: Option<String>, gitlab: Option<String>, gitea: Option<String>) -> anyhow::Result<()> {
-        self.github_token = github;
-        self.gitlab_token = gitlab;
-        self.gitea_token = gitea;
+        replace_secret(&mut self.github_token, github, "github_token", &mut self.cleared_secrets);
+        replace_secret(&mut self.gitlab_token, gitlab, "gitlab_token", &mut self.cleared_secrets);
+        replace_secret(&mut self.gitea_token, gitea, "gitea_token", &mut self.cleared_secrets);
         self.save()?;
         Ok(())
     }
@@ -146,9 +551,106 @@ impl Config {
     }
 }
 
+/// Sets a secret field, recording it in `cleared` when a value is removed
+/// so that saving deletes it from the keychain.
+fn replace_secret(secret: &mut Option<String>, value: Option<String>, name: &str, cleared: &mut Vec<String>) {
+    if secret.is_some() && value.is_none() {
+        cleared.push(name.to_string());
+    } else if value.is_some() {
+        cleared.retain(|cleared| cleared != name);
+    }
+    *secret = value;
+}
+
+/// Secrets in the OS keychain (Keychain on macOS, Credential Manager on
+/// Windows, the Secret Service on Linux), filed under one service name.
+pub mod secrets {
+    /// Service name Code Furnace's secrets are filed under.
+    pub const KEYCHAIN_SERVICE: &str = "code-furnace";
+    
+    pub fn get(name: &str) -> anyhow::Result<Option<String>> {
+        match keyring::Entry::new(KEYCHAIN_SERVICE, name)?.get_password() {
+            Ok(secret) => Ok(Some(secret)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+    
+    pub fn set(name: &str, secret: &str) -> anyhow::Result<()> {
+        keyring::Entry::new(KEYCHAIN_SERVICE, name)?.set_password(secret)?;
+        Ok(())
+    }
+    
+    /// Removes a secret; removing one that is not there is not an error.
+    pub fn delete(name: &str) -> anyhow::Result<()> {
+        match keyring::Entry::new(KEYCHAIN_SERVICE, name)?.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// The single agent provider an older config.json set up with
+/// `agent_provider`, `agent_api_key` and `agent_models`, if it had a key.
+fn legacy_provider(raw: &serde_json::Value, keychain_secrets: &[String]) -> Option<ProviderConfig> {
+    let kind: AgentProvider = raw
+        .get("agent_provider")
+        .and_then(|kind| serde_json::from_value(kind.clone()).ok())
+        .unwrap_or(AgentProvider::Claude);
+    let api_key = match raw.get("agent_api_key").and_then(|key| key.as_str()) {
+        Some(key) => Some(key.to_string()),
+        None if keychain_secrets.iter().any(|name| name == "agent_api_key") => {
+            secrets::get("agent_api_key").unwrap_or_else(|e| {
+                tracing::warn!("Could not read agent_api_key from the OS keychain: {}", e);
+                None
+            })
+        }
+        None => None,
+    }?;
+    let model = raw
+        .get("agent_models")
+        .and_then(|models| models.get(kind.name()))
+        .and_then(|model| serde_json::from_value(model.clone()).ok())
+        .unwrap_or_default();
+    
+    Some(ProviderConfig {
+        kind,
+        api_key: Some(api_key),
+        model,
+        default: true,
+    })
+}
+
+/// Lays `overrides` over `base`: objects merge key by key, anything else
+/// replaces what was there.
+fn merge_json(base: &mut serde_json::Value, overrides: serde_json::Value) {
+    match (base, overrides) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(overrides)) => {
+            for (key, value) in overrides {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_json(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overrides) => *base = overrides,
+    }
+}
+
 pub mod paths {
     use std::path::PathBuf;
     
+    pub fn get_config_dir() -> anyhow::Result<PathBuf> {
+        let config_dir = dirs::config_dir()
+            .ok_or_else(|| anyhow::anyhow!("Could not find config directory"))?
+            .join("code-furnace");
+        
+        std::fs::create_dir_all(&config_dir)?;
+        Ok(config_dir)
+    }
+    
     pub fn get_app_data_dir() -> anyhow::Result<PathBuf> {
         let data_dir = dirs::data_dir()
             .ok_or_else(|| anyhow::anyhow!("Could not find data directory"))?
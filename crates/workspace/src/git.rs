@@ -2,9 +2,27 @@ use anyhow::Result;
 use git2::{Repository, StatusOptions, Signature, DiffOptions, Branch, BranchType};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use chrono::{DateTime, Utc};
-use code_furnace_agents::{AgentProvider, ClaudeProvider, AgentRequest};
+use code_furnace_agents::AgentRequest;
+
+#[derive(Debug, thiserror::Error)]
+pub enum GitError {
+    #[error("Uncommitted changes would be overwritten by switching branches: {}", files.join(", "))]
+    DirtyWorkingTree { files: Vec<String> },
+}
+
+/// How `GitManager::switch_branch` left the working tree.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum SwitchOutcome {
+    /// The branch is checked out with any autostashed changes reapplied.
+    Switched,
+    /// The branch is checked out, but the autostashed changes did not apply
+    /// cleanly on it. The working tree holds them with conflict markers, and
+    /// they are also still in the stash, at index 0.
+    StashKept { error: String },
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GitRepository {
@@ -73,6 +91,8 @@ pub struct GitDiff {
     pub old_file: Option<String>,
     pub new_file: Option<String>,
     pub hunks: Vec<GitDiffHunk>,
+    pub additions: u32,
+    pub deletions: u32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -100,6 +120,30 @@ pub enum GitDiffLineType {
     Deletion,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitBlameLine {
+    /// None for lines with uncommitted changes.
+    pub commit_hash: Option<String>,
+    pub author: String,
+    /// 1-based, in the working tree version of the file.
+    pub line_number: usize,
+    pub content: String,
+}
+
+/// Author shown for lines that differ from HEAD.
+pub const NOT_COMMITTED_YET: &str = "Not Committed Yet";
+/// Lines past this are left out of blame results.
+pub const MAX_BLAME_LINES: usize = 10_000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitStash {
+    /// Position in the stash list, 0 being the most recent.
+    pub index: usize,
+    pub message: String,
+    pub commit: String,
+    pub timestamp: DateTime<Utc>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum GitPlatform {
     GitHub,
@@ -154,9 +198,7 @@ impl GitManager {
         };
         
         // Get remote URL
-        let remote_url = repo.find_remote("origin")
-            .ok()
-            .and_then(|remote| remote.url().map(|s| s.to_string()));
+        let remote_url = Self::origin_url(&repo);
         
         // Detect platform
         let platform = remote_url.as_ref()
@@ -179,6 +221,18 @@ impl GitManager {
         Ok(git_repo)
     }
     
+    fn origin_url(repo: &Repository) -> Option<String> {
+        repo.find_remote("origin")
+            .ok()
+            .and_then(|remote| remote.url().map(|s| s.to_string()))
+    }
+    
+    /// URL of the `origin` remote, if the repository has one.
+    pub fn remote_url(&self, repo_path: &PathBuf) -> Result<Option<String>> {
+        let repo = Repository::open(repo_path)?;
+        Ok(Self::origin_url(&repo))
+    }
+    
     pub fn get_status(&self, repo: &Repository) -> Result<GitStatus> {
         let mut opts = StatusOptions::new();
         opts.include_untracked(true);
@@ -327,35 +381,82 @@ impl GitManager {
                 break;
             }
             
-            let commit_id = commit_id?;
-            let commit = repo.find_commit(commit_id)?;
-            
-            let author = commit.author();
-            let committer = commit.committer();
+            let commit = repo.find_commit(commit_id?)?;
+            commits.push(Self::to_git_commit(&commit));
+        }
+        
+        Ok(commits)
+    }
+    
+    /// Commits reachable from HEAD that changed `file_path`, newest first.
+    /// The path may also be a directory. Renames are not followed, and a merge
+    /// is only listed if the path differs from every one of its parents.
+    pub fn get_file_history(&self, repo_path: &PathBuf, file_path: &str, limit: Option<usize>) -> Result<Vec<GitCommit>> {
+        let repo = Repository::open(repo_path)?;
+        let path = std::path::Path::new(file_path);
+        
+        let mut revwalk = repo.revwalk()?;
+        revwalk.push_head()?;
+        revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME)?;
+        
+        let entry_id = |commit: &git2::Commit| -> Result<Option<git2::Oid>> {
+            Ok(commit.tree()?.get_path(path).ok().map(|entry| entry.id()))
+        };
+        
+        let mut commits = Vec::new();
+        let max_commits = limit.unwrap_or(100);
+        
+        for commit_id in revwalk {
+            if commits.len() >= max_commits {
+                break;
+            }
             
-            let git_commit = GitCommit {
-                hash: commit_id.to_string(),
-                short_hash: format!("{:.7}", commit_id.to_string()),
-                message: commit.message().unwrap_or("").to_string(),
-                author: GitAuthor {
-                    name: author.name().unwrap_or("").to_string(),
-                    email: author.email().unwrap_or("").to_string(),
-                },
-                committer: GitAuthor {
-                    name: committer.name().unwrap_or("").to_string(),
-                    email: committer.email().unwrap_or("").to_string(),
-                },
-                timestamp: DateTime::from_timestamp(commit.time().seconds(), 0)
-                    .unwrap_or_else(|| Utc::now()),
-                parents: commit.parent_ids().map(|id| id.to_string()).collect(),
+            let commit = repo.find_commit(commit_id?)?;
+            let current = entry_id(&commit)?;
+            let touched = if commit.parent_count() == 0 {
+                current.is_some()
+            } else {
+                let mut touched = true;
+                for parent in commit.parents() {
+                    if entry_id(&parent)? == current {
+                        touched = false;
+                        break;
+                    }
+                }
+                touched
             };
             
-            commits.push(git_commit);
+            if touched {
+                commits.push(Self::to_git_commit(&commit));
+            }
         }
         
         Ok(commits)
     }
     
+    fn to_git_commit(commit: &git2::Commit) -> GitCommit {
+        let commit_id = commit.id();
+        let author = commit.author();
+        let committer = commit.committer();
+        
+        GitCommit {
+            hash: commit_id.to_string(),
+            short_hash: format!("{:.7}", commit_id.to_string()),
+            message: commit.message().unwrap_or("").to_string(),
+            author: GitAuthor {
+                name: author.name().unwrap_or("").to_string(),
+                email: author.email().unwrap_or("").to_string(),
+            },
+            committer: GitAuthor {
+                name: committer.name().unwrap_or("").to_string(),
+                email: committer.email().unwrap_or("").to_string(),
+            },
+            timestamp: DateTime::from_timestamp(commit.time().seconds(), 0)
+                .unwrap_or_else(|| Utc::now()),
+            parents: commit.parent_ids().map(|id| id.to_string()).collect(),
+        }
+    }
+    
     pub fn get_branches(&self, repo_path: &PathBuf) -> Result<Vec<GitBranch>> {
         let repo = Repository::open(repo_path)?;
         
@@ -416,87 +517,393 @@ impl GitManager {
         Ok(branches)
     }
     
-    pub fn create_branch(&mut self, repo_path: &PathBuf, branch_name: &str, from_head: bool) -> Result<()> {
+    /// Creates `branch_name` at `start_point`, which may be a commit hash or
+    /// any ref such as a branch or tag name, or at HEAD when it is None.
+    pub fn create_branch(&mut self, repo_path: &PathBuf, branch_name: &str, start_point: Option<&str>) -> Result<()> {
         let repo = Repository::open(repo_path)?;
         
-        let target_commit = if from_head {
-            repo.head()?.peel_to_commit()?
-        } else {
-            return Err(anyhow::anyhow!("Creating branch from specific commit not yet implemented"));
+        let target_commit = match start_point {
+            None => repo.head()?.peel_to_commit()?,
+            Some(start_point) => repo
+                .revparse_single(start_point)
+                .and_then(|object| object.peel_to_commit())
+                .map_err(|_| anyhow::anyhow!("Start point '{}' does not name a commit", start_point))?,
         };
         
         repo.branch(branch_name, &target_commit, false)?;
         Ok(())
     }
     
-    pub fn switch_branch(&mut self, repo_path: &PathBuf, branch_name: &str) -> Result<()> {
+    /// Checks out `branch_name`. Uncommitted changes to files that differ
+    /// between the branches fail with `GitError::DirtyWorkingTree`, unless
+    /// `autostash` is set, in which case all changes are stashed first and
+    /// reapplied on the new branch. Other changes are carried over as is.
+    /// Stashed changes that do not apply on the new branch stay in the stash
+    /// and the switch still succeeds, reporting `SwitchOutcome::StashKept`.
+    pub fn switch_branch(&mut self, repo_path: &PathBuf, branch_name: &str, autostash: bool) -> Result<SwitchOutcome> {
         let repo = Repository::open(repo_path)?;
         
         let branch_ref = format!("refs/heads/{}", branch_name);
         let reference = repo.find_reference(&branch_ref)?;
         let commit = reference.peel_to_commit()?;
         
+        let conflicts = Self::switch_conflicts(&repo, &commit.tree()?)?;
+        let stashed = if conflicts.is_empty() {
+            false
+        } else if autostash {
+            self.stash_save(repo_path, Some(&format!("Autostash before switching to {}", branch_name)), true)?;
+            true
+        } else {
+            return Err(GitError::DirtyWorkingTree { files: conflicts }.into());
+        };
+        
         // Checkout the branch
         let mut checkout_builder = git2::build::CheckoutBuilder::new();
         checkout_builder.safe();
         
-        repo.checkout_tree(commit.as_object(), Some(&mut checkout_builder))?;
-        repo.set_head(&branch_ref)?;
+        let switched = repo
+            .checkout_tree(commit.as_object(), Some(&mut checkout_builder))
+            .and_then(|_| repo.set_head(&branch_ref));
+        if !stashed {
+            switched?;
+            return Ok(SwitchOutcome::Switched);
+        }
         
+        // Put the changes back either way; on failure they return to the old branch
+        let popped = Self::reapply_autostash(repo_path);
+        match (switched, popped) {
+            (Ok(()), Ok(())) => Ok(SwitchOutcome::Switched),
+            (Ok(()), Err(e)) => Ok(SwitchOutcome::StashKept { error: e.to_string() }),
+            (Err(e), Ok(())) => Err(e.into()),
+            (Err(e), Err(pop_error)) => {
+                let message = format!(
+                    "Could not switch to {}: {}. The stashed changes could not be reapplied either and remain in the stash: {}",
+                    branch_name, e, pop_error
+                );
+                Err(anyhow::Error::new(e).context(message))
+            }
+        }
+    }
+    
+    /// Applies the newest stash and drops it, unless applying it conflicted;
+    /// `stash_pop` would drop it regardless.
+    fn reapply_autostash(repo_path: &PathBuf) -> Result<()> {
+        let mut repo = Repository::open(repo_path)?;
+        repo.stash_apply(0, None)?;
+        
+        let mut conflicts = Vec::new();
+        for conflict in repo.index()?.conflicts()? {
+            let conflict = conflict?;
+            if let Some(entry) = conflict.our.or(conflict.their).or(conflict.ancestor) {
+                conflicts.push(String::from_utf8_lossy(&entry.path).into_owned());
+            }
+        }
+        if !conflicts.is_empty() {
+            return Err(anyhow::anyhow!("Reapplying the changes conflicted in {}", conflicts.join(", ")));
+        }
+        
+        repo.stash_drop(0)?;
         Ok(())
     }
     
+    /// Paths with uncommitted changes that a checkout of `target` would
+    /// overwrite: tracked files that differ between HEAD and `target`, and
+    /// untracked files that `target` contains.
+    fn switch_conflicts(repo: &Repository, target: &git2::Tree) -> Result<Vec<String>> {
+        let head_tree = repo.head()?.peel_to_tree()?;
+        let entry_id = |tree: &git2::Tree, path: &std::path::Path| tree.get_path(path).ok().map(|entry| entry.id());
+        
+        let mut status_opts = StatusOptions::new();
+        status_opts.include_untracked(true).recurse_untracked_dirs(true).include_ignored(false);
+        
+        let mut conflicts = Vec::new();
+        for entry in repo.statuses(Some(&mut status_opts))?.iter() {
+            let Some(file_path) = entry.path() else {
+                continue;
+            };
+            let path = std::path::Path::new(file_path);
+            let conflicting = if entry.status() == git2::Status::WT_NEW {
+                entry_id(target, path).is_some()
+            } else {
+                entry_id(&head_tree, path) != entry_id(target, path)
+            };
+            if conflicting {
+                conflicts.push(file_path.to_string());
+            }
+        }
+        
+        Ok(conflicts)
+    }
+    
     pub fn get_diff(&self, repo_path: &PathBuf, staged: bool) -> Result<Vec<GitDiff>> {
+        Self::diff_paths(repo_path, None, staged)
+    }
+    
+    /// The diff of a single path, relative to the repository root. Empty if
+    /// the file is unchanged.
+    pub fn get_file_diff(&self, repo_path: &PathBuf, file_path: &str, staged: bool) -> Result<Option<GitDiff>> {
+        Ok(Self::diff_paths(repo_path, Some(file_path), staged)?.into_iter().next())
+    }
+    
+    fn diff_paths(repo_path: &PathBuf, file_path: Option<&str>, staged: bool) -> Result<Vec<GitDiff>> {
         let repo = Repository::open(repo_path)?;
         
         let mut diff_opts = DiffOptions::new();
         diff_opts.context_lines(3);
+        if let Some(file_path) = file_path {
+            diff_opts.pathspec(file_path).disable_pathspec_match(true);
+        }
         
         let diff = if staged {
             // Diff between HEAD and index (staged changes)
-            let head_tree = repo.head()?.peel_to_tree()?;
-            repo.diff_tree_to_index(Some(&head_tree), None, Some(&mut diff_opts))?
+            let head_tree = head_tree(&repo)?;
+            repo.diff_tree_to_index(head_tree.as_ref(), None, Some(&mut diff_opts))?
         } else {
             // Diff between index and working directory (unstaged changes)
             repo.diff_index_to_workdir(None, Some(&mut diff_opts))?
         };
         
-        let mut git_diffs = Vec::new();
+        Self::collect_diffs(&diff)
+    }
+    
+    /// Parses unified diff text, such as a pull request's patch from a forge,
+    /// into the same per-file diffs as local changes.
+    pub fn diffs_from_patch(patch: &str) -> Result<Vec<GitDiff>> {
+        if patch.trim().is_empty() {
+            return Ok(Vec::new());
+        }
+        let diff = git2::Diff::from_buffer(patch.as_bytes())?;
+        Self::collect_diffs(&diff)
+    }
+    
+    /// Throws away the unstaged changes of one file by restoring it from the
+    /// index, or from HEAD if it is not in the index. Untracked files are
+    /// left alone.
+    pub fn discard_file_changes(&mut self, repo_path: &PathBuf, file_path: &str) -> Result<()> {
+        let repo = Repository::open(repo_path)?;
         
-        // Use print() to get the raw diff text for now
-        // This is a simpler approach that avoids the complex foreach callback issues
-        let mut diff_text = Vec::new();
-        diff.print(git2::DiffFormat::Patch, |_delta, _hunk, line| {
-            diff_text.extend_from_slice(line.content());
-            true
-        })?;
+        // Checkout paths are pathspecs; escape them so only this exact file matches
+        let literal_path: String = file_path
+            .chars()
+            .flat_map(|c| match c {
+                '*' | '?' | '[' | ']' | '\\' => vec!['\\', c],
+                c => vec![c],
+            })
+            .collect();
+        let mut checkout_builder = git2::build::CheckoutBuilder::new();
+        checkout_builder.path(literal_path);
+        checkout_builder.force();
+        
+        if let Some(entry) = repo.index()?.get_path(std::path::Path::new(file_path), 0) {
+            // Regular files and symlinks; anything else, such as a submodule, is not a file
+            let file_type = entry.mode & 0o170000;
+            if file_type != 0o100000 && file_type != 0o120000 {
+                return Err(anyhow::anyhow!("{} is not a file", file_path));
+            }
+            repo.checkout_index(None, Some(&mut checkout_builder))?;
+            return Ok(());
+        }
         
-        // For now, return a simplified diff structure
-        // In a full implementation, you'd parse the diff text to create proper GitDiff structures
-        let diff_string = String::from_utf8_lossy(&diff_text);
-        if !diff_string.is_empty() {
+        let head_tree = repo.head()?.peel_to_tree()?;
+        match head_tree.get_path(std::path::Path::new(file_path)) {
+            Ok(entry) if entry.kind() == Some(git2::ObjectType::Blob) => {}
+            Ok(_) => return Err(anyhow::anyhow!("{} is not a file", file_path)),
+            Err(_) => return Err(anyhow::anyhow!("{} is not tracked", file_path)),
+        }
+        repo.checkout_tree(head_tree.as_object(), Some(&mut checkout_builder))?;
+        Ok(())
+    }
+    
+    /// One `GitDiff` per changed file, with its hunks and numbered lines.
+    /// Files without text changes, such as binaries, have no hunks.
+    fn collect_diffs(diff: &git2::Diff) -> Result<Vec<GitDiff>> {
+        let mut git_diffs = Vec::new();
+        
+        for (index, delta) in diff.deltas().enumerate() {
+            let path_of = |file: git2::DiffFile| file.path().map(|path| path.to_string_lossy().to_string());
+            let old_file = match delta.status() {
+                git2::Delta::Added | git2::Delta::Untracked => None,
+                _ => path_of(delta.old_file()),
+            };
+            let new_file = match delta.status() {
+                git2::Delta::Deleted => None,
+                _ => path_of(delta.new_file()),
+            };
+            
+            let mut hunks = Vec::new();
+            if let Some(patch) = git2::Patch::from_diff(diff, index)? {
+                for hunk_index in 0..patch.num_hunks() {
+                    let (hunk, line_count) = patch.hunk(hunk_index)?;
+                    let mut lines = Vec::with_capacity(line_count);
+                    for line_index in 0..line_count {
+                        let line = patch.line_in_hunk(hunk_index, line_index)?;
+                        let line_type = match line.origin() {
+                            '+' => GitDiffLineType::Addition,
+                            '-' => GitDiffLineType::Deletion,
+                            ' ' => GitDiffLineType::Context,
+                            // "No newline at end of file" markers
+                            _ => continue,
+                        };
+                        lines.push(GitDiffLine {
+                            line_type,
+                            content: String::from_utf8_lossy(line.content()).trim_end_matches(['\n', '\r']).to_string(),
+                            old_line_no: line.old_lineno(),
+                            new_line_no: line.new_lineno(),
+                        });
+                    }
+                    
+                    hunks.push(GitDiffHunk {
+                        old_start: hunk.old_start(),
+                        old_lines: hunk.old_lines(),
+                        new_start: hunk.new_start(),
+                        new_lines: hunk.new_lines(),
+                        header: String::from_utf8_lossy(hunk.header()).trim_end().to_string(),
+                        lines,
+                    });
+                }
+            }
+            
+            let count = |line_type: fn(&GitDiffLineType) -> bool| {
+                hunks.iter().flat_map(|hunk| &hunk.lines).filter(|line| line_type(&line.line_type)).count() as u32
+            };
+            let additions = count(|line_type| matches!(line_type, GitDiffLineType::Addition));
+            let deletions = count(|line_type| matches!(line_type, GitDiffLineType::Deletion));
             git_diffs.push(GitDiff {
-                old_file: Some("modified files".to_string()),
-                new_file: Some("modified files".to_string()),
-                hunks: vec![GitDiffHunk {
-                    old_start: 0,
-                    old_lines: 0,
-                    new_start: 0,
-                    new_lines: 0,
-                    header: "Diff".to_string(),
-                    lines: vec![GitDiffLine {
-                        line_type: GitDiffLineType::Context,
-                        content: diff_string.to_string(),
-                        old_line_no: None,
-                        new_line_no: None,
-                    }],
-                }],
+                old_file,
+                new_file,
+                hunks,
+                additions,
+                deletions,
             });
         }
         
         Ok(git_diffs)
     }
     
+    /// Blames the working tree version of a file, at most `MAX_BLAME_LINES`
+    /// lines of it. Lines changed since HEAD, and every line of a file that
+    /// has never been committed, are attributed to `NOT_COMMITTED_YET`.
+    pub fn blame_file(&self, repo_path: &PathBuf, file_path: &str) -> Result<Vec<GitBlameLine>> {
+        let repo = Repository::open(repo_path)?;
+        let path = std::path::Path::new(file_path);
+        
+        let contents = std::fs::read(repo_path.join(path))?;
+        let text = String::from_utf8_lossy(&contents);
+        
+        let committed = repo
+            .head()
+            .and_then(|head| head.peel_to_tree())
+            .map(|tree| tree.get_path(path).is_ok())
+            .unwrap_or(false);
+        let head_blame = if committed {
+            Some(repo.blame_file(path, None)?)
+        } else {
+            None
+        };
+        // Re-blame against the working tree so edited lines show as uncommitted
+        let blame = head_blame
+            .as_ref()
+            .map(|head_blame| head_blame.blame_buffer(&contents))
+            .transpose()?;
+        
+        // Buffer blame hunks carry no signatures, so authors come from the commits
+        let mut authors: HashMap<git2::Oid, String> = HashMap::new();
+        let mut lines = Vec::new();
+        for (index, content) in text.lines().take(MAX_BLAME_LINES).enumerate() {
+            let line_number = index + 1;
+            let commit_id = blame
+                .as_ref()
+                .and_then(|blame| blame.get_line(line_number))
+                .map(|hunk| hunk.final_commit_id())
+                .filter(|commit_id| !commit_id.is_zero());
+            let (commit_hash, author) = match commit_id {
+                Some(commit_id) => {
+                    let author = match authors.get(&commit_id) {
+                        Some(author) => author.clone(),
+                        None => {
+                            let author = repo.find_commit(commit_id)?.author().name().unwrap_or("").to_string();
+                            authors.insert(commit_id, author.clone());
+                            author
+                        }
+                    };
+                    (Some(commit_id.to_string()), author)
+                }
+                None => (None, NOT_COMMITTED_YET.to_string()),
+            };
+            lines.push(GitBlameLine {
+                commit_hash,
+                author,
+                line_number,
+                content: content.to_string(),
+            });
+        }
+        
+        Ok(lines)
+    }
+    
+    /// Stashes the changes to tracked files, and new files too if
+    /// `include_untracked` is set, leaving a clean working tree. Returns the
+    /// id of the stash commit.
+    pub fn stash_save(&mut self, repo_path: &PathBuf, message: Option<&str>, include_untracked: bool) -> Result<String> {
+        let mut repo = Repository::open(repo_path)?;
+        
+        let signature = repo
+            .signature()
+            .or_else(|_| Signature::now("Code Furnace", "code-furnace@localhost"))?;
+        let mut flags = git2::StashFlags::DEFAULT;
+        if include_untracked {
+            flags |= git2::StashFlags::INCLUDE_UNTRACKED;
+        }
+        
+        let stash_id = repo.stash_save2(&signature, message, Some(flags))?;
+        Ok(stash_id.to_string())
+    }
+    
+    /// Most recent stash first.
+    pub fn stash_list(&self, repo_path: &PathBuf) -> Result<Vec<GitStash>> {
+        let mut repo = Repository::open(repo_path)?;
+        
+        let mut entries = Vec::new();
+        repo.stash_foreach(|index, message, stash_id| {
+            entries.push((index, message.to_string(), *stash_id));
+            true
+        })?;
+        
+        entries
+            .into_iter()
+            .map(|(index, message, stash_id)| {
+                let commit = repo.find_commit(stash_id)?;
+                Ok(GitStash {
+                    index,
+                    message,
+                    commit: stash_id.to_string(),
+                    timestamp: DateTime::from_timestamp(commit.time().seconds(), 0)
+                        .unwrap_or_else(Utc::now),
+                })
+            })
+            .collect()
+    }
+    
+    /// Reapplies a stash, keeping it in the list.
+    pub fn stash_apply(&mut self, repo_path: &PathBuf, index: usize) -> Result<()> {
+        let mut repo = Repository::open(repo_path)?;
+        repo.stash_apply(index, None)?;
+        Ok(())
+    }
+    
+    /// Reapplies a stash and removes it from the list if that succeeded.
+    pub fn stash_pop(&mut self, repo_path: &PathBuf, index: usize) -> Result<()> {
+        let mut repo = Repository::open(repo_path)?;
+        repo.stash_pop(index, None)?;
+        Ok(())
+    }
+    
+    pub fn stash_drop(&mut self, repo_path: &PathBuf, index: usize) -> Result<()> {
+        let mut repo = Repository::open(repo_path)?;
+        repo.stash_drop(index)?;
+        Ok(())
+    }
+    
     pub fn push(&self, repo_path: &PathBuf, remote: &str, branch: &str) -> Result<()> {
         let repo = Repository::open(repo_path)?;
         
@@ -540,50 +947,14 @@ impl GitManager {
         Ok(())
     }
     
-    pub async fn generate_ai_commit_message(&self, repo_path: &PathBuf, staged_files: &[String]) -> Result<String> {
-        // Get git status and diffs for AI analysis
+    /// Builds a `git-assistant` request for a commit message. The request
+    /// carries `repo_path` in its context so the agent bridge attaches the
+    /// staged diff.
+    pub fn commit_message_request(&self, repo_path: &PathBuf, staged_files: &[String]) -> Result<AgentRequest> {
         let status = self.get_status(&Repository::open(repo_path)?)?;
-        let staged_diffs = self.get_diff(repo_path, true)?;
-        
-        let mut diff_context = String::new();
-        for diff in &staged_diffs {
-            let unknown = "unknown".to_string();
-            let file_path = diff.new_file.as_ref().or(diff.old_file.as_ref()).unwrap_or(&unknown);
-            diff_context.push_str(&format!("File: {}\n", file_path));
-            
-            // Determine status based on old_file and new_file
-            let status = match (&diff.old_file, &diff.new_file) {
-                (None, Some(_)) => "added",
-                (Some(_), None) => "deleted",
-                (Some(_), Some(_)) => "modified",
-                (None, None) => "unknown",
-            };
-            diff_context.push_str(&format!("Status: {}\n", status));
-            
-            // Add hunk content
-            for hunk in &diff.hunks {
-                diff_context.push_str(&format!("Hunk: {}\n", hunk.header));
-                for line in &hunk.lines {
-                    diff_context.push_str(&format!("{}\n", line.content));
-                }
-            }
-            diff_context.push_str("\n");
-        }
-        
-        // Load config to check if AI is configured
-        let config = code_furnace_utils::Config::load().unwrap_or_default();
-        if !config.has_agent_configured() {
-            // Fallback to a smart but simple commit message
-            return Ok(self.generate_simple_commit_message(staged_files, &status));
-        }
-        
-        // Create AI agent for commit message generation
-        let claude_provider = code_furnace_agents::ClaudeProvider::new(
-            config.agent_api_key.unwrap_or_default()
-        );
         
         let prompt = format!(
-            "Analyze the following git changes and generate a concise, descriptive commit message. \
+            "Analyze the staged git changes and generate a concise, descriptive commit message. \
             Follow conventional commit format when appropriate (feat:, fix:, docs:, etc.). \
             The message should be under 50 characters for the title.\n\n\
             Staged files: {:?}\n\n\
@@ -591,42 +962,33 @@ impl GitManager {
             - Staged files: {}\n\
             - Unstaged files: {}\n\
             - Untracked files: {}\n\n\
-            Diff details:\n{}\n\n\
             Respond with ONLY the commit message, no explanation or quotes.",
             staged_files,
             status.staged.len(),
             status.unstaged.len(),
             status.untracked.len(),
-            if diff_context.len() > 2000 {
-                format!("{}...\n[truncated]", &diff_context[..2000])
-            } else {
-                diff_context
-            }
         );
         
-        let request = code_furnace_agents::AgentRequest {
+        Ok(AgentRequest {
             id: uuid::Uuid::new_v4(),
             agent_type: "git-assistant".to_string(),
             prompt,
-            context: std::collections::HashMap::new(),
+            context: std::collections::HashMap::from([(
+                "repo_path".to_string(),
+                serde_json::Value::String(repo_path.to_string_lossy().to_string()),
+            )]),
             files: Vec::new(),
-        };
-        
-        match claude_provider.process_request(&request).await {
-            Ok(response) if response.error.is_none() => {
-                let commit_message = response.content.trim().to_string();
-                // Ensure the commit message is reasonable
-                if commit_message.len() > 5 && commit_message.len() < 200 {
-                    Ok(commit_message)
-                } else {
-                    Ok(self.generate_simple_commit_message(staged_files, &status))
-                }
-            }
-            _ => {
-                // Fallback to simple commit message
-                Ok(self.generate_simple_commit_message(staged_files, &status))
-            }
-        }
+            tools: Vec::new(),
+            tool_turns: Vec::new(),
+            model: None,
+        })
+    }
+    
+    /// Commit message guessed from the kinds of changes, for when no agent
+    /// is available.
+    pub fn fallback_commit_message(&self, repo_path: &PathBuf, staged_files: &[String]) -> Result<String> {
+        let status = self.get_status(&Repository::open(repo_path)?)?;
+        Ok(self.generate_simple_commit_message(staged_files, &status))
     }
     
     fn generate_simple_commit_message(&self, staged_files: &[String], status: &GitStatus) -> String {
@@ -664,11 +1026,456 @@ impl GitManager {
     }
 }
 
+/// The trimmed commit message an agent answered with, if its subject line is
+/// of a sensible length. Any body after the subject is kept as written.
+pub(crate) fn usable_commit_message(response: &str) -> Option<&str> {
+    let message = response.trim();
+    let subject = message.lines().next().unwrap_or_default().trim();
+    (subject.len() > 5 && subject.len() < 200).then_some(message)
+}
+
+/// The tree HEAD points at, or `None` before the first commit, when diffing
+/// against no tree compares with an empty one.
+fn head_tree(repo: &Repository) -> Result<Option<git2::Tree<'_>>> {
+    match repo.head() {
+        Ok(head) => Ok(Some(head.peel_to_tree()?)),
+        Err(error) if error.code() == git2::ErrorCode::UnbornBranch => Ok(None),
+        Err(error) => Err(error.into()),
+    }
+}
+
+impl code_furnace_agents::StagedDiffSource for GitManager {
+    /// Staged changes as a unified patch, with the `+`/`-`/` ` line markers
+    /// the model needs to tell additions from removals.
+    fn staged_diff(&self, repo_path: &std::path::Path) -> Result<String> {
+        let repo = Repository::open(repo_path)?;
+        let head_tree = head_tree(&repo)?;
+        let diff = repo.diff_tree_to_index(head_tree.as_ref(), None, None)?;
+        
+        let mut patch = Vec::new();
+        diff.print(git2::DiffFormat::Patch, |_delta, _hunk, line| {
+            if matches!(line.origin(), '+' | '-' | ' ') {
+                patch.push(line.origin() as u8);
+            }
+            patch.extend_from_slice(line.content());
+            true
+        })?;
+        
+        Ok(String::from_utf8_lossy(&patch).into_owned())
+    }
+}
+
 // Git platform API integrations
 pub mod github;
 pub mod gitlab;
 pub mod gitea;
+pub mod bitbucket;
+pub mod forge;
 
 pub use github::GitHubClient;
 pub use gitlab::GitLabClient;  
-pub use gitea::GiteaClient;
\ No newline at end of file
+pub use gitea::GiteaClient;
+pub use bitbucket::BitbucketClient;
+pub use forge::{
+    ForgeComment, ForgeError, ForgeIssue, ForgeListState, ForgeMergeMethod, ForgeMergeResult, ForgePullRequest,
+    ForgeRelease, ForgeRemote, ForgeRepository, ForgeState, ForgeUser, GitForgeProvider, NewIssue, NewPullRequest,
+    NewRelease,
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn commit_all(repo: &Repository, message: &str) {
+        let mut index = repo.index().unwrap();
+        index.add_all(["*"], git2::IndexAddOption::DEFAULT, None).unwrap();
+        index.update_all(["*"], None).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = Signature::now("Test", "test@example.com").unwrap();
+        let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+        repo.commit(Some("HEAD"), &signature, &signature, message, &tree, &parents).unwrap();
+    }
+    
+    /// A repository with a committed `modified.txt` and `deleted.txt`, whose
+    /// working tree then modifies the first, deletes the second and adds
+    /// `added.txt`.
+    fn repo_with_changes() -> (PathBuf, Repository) {
+        let path = std::env::temp_dir().join(format!("code-furnace-git-{}", uuid::Uuid::new_v4()));
+        let repo = Repository::init(&path).unwrap();
+        std::fs::write(path.join("modified.txt"), "one\ntwo\nthree\nfour\n").unwrap();
+        std::fs::write(path.join("deleted.txt"), "gone\n").unwrap();
+        commit_all(&repo, "Initial commit");
+        
+        std::fs::write(path.join("modified.txt"), "one\n2\nthree\nfour\nfive\n").unwrap();
+        std::fs::remove_file(path.join("deleted.txt")).unwrap();
+        std::fs::write(path.join("added.txt"), "new\nfile\n").unwrap();
+        (path, repo)
+    }
+    
+    fn find_diff<'a>(diffs: &'a [GitDiff], file: &str) -> &'a GitDiff {
+        diffs
+            .iter()
+            .find(|diff| diff.new_file.as_deref() == Some(file) || diff.old_file.as_deref() == Some(file))
+            .unwrap()
+    }
+    
+    fn line_summary(hunk: &GitDiffHunk) -> Vec<(char, Option<u32>, Option<u32>, &str)> {
+        hunk.lines
+            .iter()
+            .map(|line| {
+                let origin = match line.line_type {
+                    GitDiffLineType::Context => ' ',
+                    GitDiffLineType::Addition => '+',
+                    GitDiffLineType::Deletion => '-',
+                };
+                (origin, line.old_line_no, line.new_line_no, line.content.as_str())
+            })
+            .collect()
+    }
+    
+    #[test]
+    fn staged_diff_has_a_hunk_per_change() {
+        let (path, repo) = repo_with_changes();
+        let mut index = repo.index().unwrap();
+        index.add_all(["*"], git2::IndexAddOption::DEFAULT, None).unwrap();
+        index.update_all(["*"], None).unwrap();
+        index.write().unwrap();
+        
+        let diffs = GitManager::new().get_diff(&path, true).unwrap();
+        assert_eq!(diffs.len(), 3);
+        
+        let added = find_diff(&diffs, "added.txt");
+        assert_eq!(added.old_file, None);
+        assert_eq!(added.hunks.len(), 1);
+        assert_eq!((added.hunks[0].old_start, added.hunks[0].old_lines), (0, 0));
+        assert_eq!((added.hunks[0].new_start, added.hunks[0].new_lines), (1, 2));
+        assert_eq!(
+            line_summary(&added.hunks[0]),
+            vec![('+', None, Some(1), "new"), ('+', None, Some(2), "file")]
+        );
+        
+        let modified = find_diff(&diffs, "modified.txt");
+        assert_eq!(modified.old_file.as_deref(), Some("modified.txt"));
+        assert_eq!(modified.hunks.len(), 1);
+        let hunk = &modified.hunks[0];
+        assert_eq!((hunk.old_start, hunk.old_lines, hunk.new_start, hunk.new_lines), (1, 4, 1, 5));
+        assert!(hunk.header.starts_with("@@ -1,4 +1,5 @@"));
+        assert_eq!(
+            line_summary(hunk),
+            vec![
+                (' ', Some(1), Some(1), "one"),
+                ('-', Some(2), None, "two"),
+                ('+', None, Some(2), "2"),
+                (' ', Some(3), Some(3), "three"),
+                (' ', Some(4), Some(4), "four"),
+                ('+', None, Some(5), "five"),
+            ]
+        );
+        
+        let deleted = find_diff(&diffs, "deleted.txt");
+        assert_eq!(deleted.new_file, None);
+        assert_eq!(line_summary(&deleted.hunks[0]), vec![('-', Some(1), None, "gone")]);
+        
+        std::fs::remove_dir_all(path).unwrap();
+    }
+    
+    #[test]
+    fn file_diff_and_discard() {
+        let (path, _repo) = repo_with_changes();
+        let mut git_manager = GitManager::new();
+        
+        let diff = git_manager.get_file_diff(&path, "modified.txt", false).unwrap().unwrap();
+        assert_eq!(diff.new_file.as_deref(), Some("modified.txt"));
+        assert_eq!(diff.hunks.len(), 1);
+        assert!(git_manager.get_file_diff(&path, "added.txt", false).unwrap().is_none());
+        
+        git_manager.discard_file_changes(&path, "modified.txt").unwrap();
+        git_manager.discard_file_changes(&path, "deleted.txt").unwrap();
+        assert_eq!(std::fs::read_to_string(path.join("modified.txt")).unwrap(), "one\ntwo\nthree\nfour\n");
+        assert!(path.join("deleted.txt").exists());
+        assert!(git_manager.get_diff(&path, false).unwrap().is_empty());
+        assert!(git_manager.discard_file_changes(&path, "added.txt").is_err());
+        
+        std::fs::remove_dir_all(path).unwrap();
+    }
+    
+    #[test]
+    fn discard_only_takes_a_single_file() {
+        let path = std::env::temp_dir().join(format!("code-furnace-git-{}", uuid::Uuid::new_v4()));
+        let repo = Repository::init(&path).unwrap();
+        std::fs::create_dir_all(path.join("src")).unwrap();
+        std::fs::write(path.join("src/lib.rs"), "committed\n").unwrap();
+        std::fs::write(path.join("main.rs"), "committed\n").unwrap();
+        std::fs::write(path.join("[m]ain.rs"), "committed\n").unwrap();
+        commit_all(&repo, "Initial commit");
+        std::fs::write(path.join("src/lib.rs"), "edited\n").unwrap();
+        std::fs::write(path.join("main.rs"), "edited\n").unwrap();
+        std::fs::write(path.join("[m]ain.rs"), "edited\n").unwrap();
+        
+        let mut git_manager = GitManager::new();
+        assert!(git_manager.discard_file_changes(&path, "src").is_err());
+        assert!(git_manager.discard_file_changes(&path, "*.rs").is_err());
+        assert_eq!(std::fs::read_to_string(path.join("src/lib.rs")).unwrap(), "edited\n");
+        assert_eq!(std::fs::read_to_string(path.join("main.rs")).unwrap(), "edited\n");
+        
+        git_manager.discard_file_changes(&path, "src/lib.rs").unwrap();
+        git_manager.discard_file_changes(&path, "[m]ain.rs").unwrap();
+        assert_eq!(std::fs::read_to_string(path.join("src/lib.rs")).unwrap(), "committed\n");
+        assert_eq!(std::fs::read_to_string(path.join("[m]ain.rs")).unwrap(), "committed\n");
+        assert_eq!(std::fs::read_to_string(path.join("main.rs")).unwrap(), "edited\n");
+        
+        std::fs::remove_dir_all(path).unwrap();
+    }
+    
+    #[test]
+    fn create_branch_at_start_point() {
+        let (path, repo) = repo_with_changes();
+        let first_commit = repo.head().unwrap().peel_to_commit().unwrap().id();
+        commit_all(&repo, "Second commit");
+        let mut git_manager = GitManager::new();
+        
+        git_manager.create_branch(&path, "from-hash", Some(&first_commit.to_string()[..7])).unwrap();
+        git_manager.create_branch(&path, "from-ref", Some("from-hash")).unwrap();
+        git_manager.create_branch(&path, "from-head", None).unwrap();
+        
+        let branch_commit = |name: &str| {
+            repo.find_branch(name, BranchType::Local).unwrap().get().peel_to_commit().unwrap().id()
+        };
+        assert_eq!(branch_commit("from-hash"), first_commit);
+        assert_eq!(branch_commit("from-ref"), first_commit);
+        assert_ne!(branch_commit("from-head"), first_commit);
+        
+        let error = git_manager.create_branch(&path, "nowhere", Some("no-such-ref")).unwrap_err();
+        assert!(error.to_string().contains("no-such-ref"));
+        
+        std::fs::remove_dir_all(path).unwrap();
+    }
+    
+    #[test]
+    fn blame_marks_uncommitted_lines() {
+        let (path, repo) = repo_with_changes();
+        let head = repo.head().unwrap().peel_to_commit().unwrap().id().to_string();
+        let git_manager = GitManager::new();
+        
+        let blame = git_manager.blame_file(&path, "modified.txt").unwrap();
+        let summary: Vec<_> = blame
+            .iter()
+            .map(|line| (line.line_number, line.commit_hash.as_deref(), line.content.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (1, Some(head.as_str()), "one"),
+                (2, None, "2"),
+                (3, Some(head.as_str()), "three"),
+                (4, Some(head.as_str()), "four"),
+                (5, None, "five"),
+            ]
+        );
+        assert_eq!(blame[0].author, "Test");
+        assert_eq!(blame[1].author, NOT_COMMITTED_YET);
+        
+        let blame = git_manager.blame_file(&path, "added.txt").unwrap();
+        assert!(blame.iter().all(|line| line.commit_hash.is_none()));
+        
+        std::fs::remove_dir_all(path).unwrap();
+    }
+    
+    #[test]
+    fn file_history_lists_commits_touching_the_file() {
+        let (path, repo) = repo_with_changes();
+        let first_commit = repo.head().unwrap().peel_to_commit().unwrap().id().to_string();
+        commit_all(&repo, "Change everything");
+        std::fs::write(path.join("added.txt"), "changed\n").unwrap();
+        commit_all(&repo, "Change added.txt");
+        let git_manager = GitManager::new();
+        
+        let messages = |file: &str| -> Vec<String> {
+            git_manager
+                .get_file_history(&path, file, None)
+                .unwrap()
+                .into_iter()
+                .map(|commit| commit.message)
+                .collect()
+        };
+        assert_eq!(messages("modified.txt"), vec!["Change everything", "Initial commit"]);
+        assert_eq!(messages("added.txt"), vec!["Change added.txt", "Change everything"]);
+        assert_eq!(messages("deleted.txt"), vec!["Change everything", "Initial commit"]);
+        
+        let history = git_manager.get_file_history(&path, "modified.txt", Some(1)).unwrap();
+        assert_eq!(history.len(), 1);
+        assert_ne!(history[0].hash, first_commit);
+        
+        std::fs::remove_dir_all(path).unwrap();
+    }
+    
+    #[test]
+    fn switching_branch_with_dirty_file() {
+        let path = std::env::temp_dir().join(format!("code-furnace-git-{}", uuid::Uuid::new_v4()));
+        let repo = Repository::init(&path).unwrap();
+        std::fs::write(path.join("shared.txt"), "1\n2\n3\n4\n5\n6\n7\n8\n").unwrap();
+        std::fs::write(path.join("notes.txt"), "notes\n").unwrap();
+        commit_all(&repo, "Initial commit");
+        let mut git_manager = GitManager::new();
+        git_manager.create_branch(&path, "other", None).unwrap();
+        let original_branch = repo.head().unwrap().shorthand().unwrap().to_string();
+        std::fs::write(path.join("shared.txt"), "one\n2\n3\n4\n5\n6\n7\n8\n").unwrap();
+        commit_all(&repo, "Spell out one");
+        
+        // A dirty file the branches agree on does not block the switch
+        std::fs::write(path.join("notes.txt"), "more notes\n").unwrap();
+        git_manager.switch_branch(&path, "other", false).unwrap();
+        assert_eq!(repo.head().unwrap().shorthand(), Some("other"));
+        assert_eq!(std::fs::read_to_string(path.join("notes.txt")).unwrap(), "more notes\n");
+        git_manager.switch_branch(&path, &original_branch, false).unwrap();
+        
+        std::fs::write(path.join("shared.txt"), "one\n2\n3\n4\n5\n6\n7\neight\n").unwrap();
+        let error = git_manager.switch_branch(&path, "other", false).unwrap_err();
+        match error.downcast_ref::<GitError>() {
+            Some(GitError::DirtyWorkingTree { files }) => assert_eq!(files, &vec!["shared.txt".to_string()]),
+            None => panic!("unexpected error: {}", error),
+        }
+        assert_eq!(repo.head().unwrap().shorthand(), Some(original_branch.as_str()));
+        
+        git_manager.switch_branch(&path, "other", true).unwrap();
+        assert_eq!(repo.head().unwrap().shorthand(), Some("other"));
+        assert_eq!(
+            std::fs::read_to_string(path.join("shared.txt")).unwrap(),
+            "1\n2\n3\n4\n5\n6\n7\neight\n"
+        );
+        assert_eq!(std::fs::read_to_string(path.join("notes.txt")).unwrap(), "more notes\n");
+        assert!(git_manager.stash_list(&path).unwrap().is_empty());
+        
+        std::fs::remove_dir_all(path).unwrap();
+    }
+    
+    #[test]
+    fn autostash_that_does_not_apply_stays_in_the_stash() {
+        let path = std::env::temp_dir().join(format!("code-furnace-git-{}", uuid::Uuid::new_v4()));
+        let repo = Repository::init(&path).unwrap();
+        std::fs::write(path.join("shared.txt"), "1\n2\n3\n").unwrap();
+        commit_all(&repo, "Initial commit");
+        let mut git_manager = GitManager::new();
+        git_manager.create_branch(&path, "other", None).unwrap();
+        std::fs::write(path.join("shared.txt"), "one\n2\n3\n").unwrap();
+        commit_all(&repo, "Spell out one");
+        
+        // The stash changes the line the two branches disagree on
+        std::fs::write(path.join("shared.txt"), "uno\n2\n3\n").unwrap();
+        let outcome = git_manager.switch_branch(&path, "other", true).unwrap();
+        match outcome {
+            SwitchOutcome::StashKept { error } => assert!(error.contains("shared.txt"), "{}", error),
+            SwitchOutcome::Switched => panic!("the stash applied cleanly"),
+        }
+        assert_eq!(repo.head().unwrap().shorthand(), Some("other"));
+        assert!(std::fs::read_to_string(path.join("shared.txt")).unwrap().contains("uno"));
+        assert_eq!(git_manager.stash_list(&path).unwrap().len(), 1);
+        
+        std::fs::remove_dir_all(path).unwrap();
+    }
+    
+    #[test]
+    fn stash_round_trip() {
+        let (path, _repo) = repo_with_changes();
+        let mut git_manager = GitManager::new();
+        
+        git_manager.stash_save(&path, Some("work in progress"), true).unwrap();
+        assert!(git_manager.get_diff(&path, false).unwrap().is_empty());
+        assert!(!path.join("added.txt").exists());
+        
+        let stashes = git_manager.stash_list(&path).unwrap();
+        assert_eq!(stashes.len(), 1);
+        assert_eq!(stashes[0].index, 0);
+        assert!(stashes[0].message.contains("work in progress"));
+        
+        git_manager.stash_pop(&path, 0).unwrap();
+        assert!(git_manager.stash_list(&path).unwrap().is_empty());
+        assert!(path.join("added.txt").exists());
+        assert_eq!(git_manager.get_diff(&path, false).unwrap().len(), 2);
+        
+        std::fs::remove_dir_all(path).unwrap();
+    }
+    
+    #[test]
+    fn unstaged_diff_covers_tracked_files_only() {
+        let (path, _repo) = repo_with_changes();
+        
+        let diffs = GitManager::new().get_diff(&path, false).unwrap();
+        let mut files: Vec<_> = diffs
+            .iter()
+            .map(|diff| diff.new_file.clone().or(diff.old_file.clone()).unwrap())
+            .collect();
+        files.sort();
+        assert_eq!(files, vec!["deleted.txt", "modified.txt"]);
+        assert_eq!(find_diff(&diffs, "modified.txt").hunks[0].lines.len(), 6);
+        
+        std::fs::remove_dir_all(path).unwrap();
+    }
+    
+    #[test]
+    fn staged_diff_before_the_first_commit_adds_every_file() {
+        use code_furnace_agents::StagedDiffSource;
+        
+        let path = std::env::temp_dir().join(format!("code-furnace-git-{}", uuid::Uuid::new_v4()));
+        let repo = Repository::init(&path).unwrap();
+        std::fs::write(path.join("first.txt"), "hello\n").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(std::path::Path::new("first.txt")).unwrap();
+        index.write().unwrap();
+        
+        let diffs = GitManager::new().get_diff(&path, true).unwrap();
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(line_summary(&diffs[0].hunks[0]), vec![('+', None, Some(1), "hello")]);
+        assert!(GitManager::new().staged_diff(&path).unwrap().contains("+hello"));
+        
+        std::fs::remove_dir_all(path).unwrap();
+    }
+    
+    #[test]
+    fn patch_text_parses_like_a_local_diff() {
+        let (path, repo) = repo_with_changes();
+        let mut index = repo.index().unwrap();
+        index.add_all(["*"], git2::IndexAddOption::DEFAULT, None).unwrap();
+        index.update_all(["*"], None).unwrap();
+        index.write().unwrap();
+        
+        let head_tree = repo.head().unwrap().peel_to_tree().unwrap();
+        let diff = repo.diff_tree_to_index(Some(&head_tree), None, None).unwrap();
+        let mut patch = String::new();
+        diff.print(git2::DiffFormat::Patch, |_, _, line| {
+            if matches!(line.origin(), '+' | '-' | ' ') {
+                patch.push(line.origin());
+            }
+            patch.push_str(&String::from_utf8_lossy(line.content()));
+            true
+        })
+        .unwrap();
+        
+        let local = GitManager::new().get_diff(&path, true).unwrap();
+        let parsed = GitManager::diffs_from_patch(&patch).unwrap();
+        assert_eq!(parsed.len(), local.len());
+        for file in ["added.txt", "modified.txt", "deleted.txt"] {
+            let (local, parsed) = (find_diff(&local, file), find_diff(&parsed, file));
+            assert_eq!((&parsed.old_file, &parsed.new_file), (&local.old_file, &local.new_file), "{}", file);
+            assert_eq!(line_summary(&parsed.hunks[0]), line_summary(&local.hunks[0]), "{}", file);
+        }
+        let modified = find_diff(&parsed, "modified.txt");
+        assert_eq!((modified.additions, modified.deletions), (2, 1));
+        assert!(GitManager::diffs_from_patch("").unwrap().is_empty());
+        
+        std::fs::remove_dir_all(path).unwrap();
+    }
+    
+    #[test]
+    fn commit_messages_are_judged_by_their_subject() {
+        let body = "- explain why\n".repeat(30);
+        let message = format!("fix: handle empty input\n\n{}", body);
+        assert_eq!(usable_commit_message(&format!("\n{}\n", message)), Some(message.trim()));
+        
+        assert_eq!(usable_commit_message("fix\n\nA body cannot make up for a short subject"), None);
+        assert_eq!(usable_commit_message(&"x".repeat(250)), None);
+        assert_eq!(usable_commit_message("  "), None);
+    }
+}
@@ -0,0 +1,446 @@
+use anyhow::Result;
+use reqwest::{Client, StatusCode};
+use serde::{Deserialize, Serialize};
+
+use super::forge::{
+    error_message, merge_error, send_rate_limited, with_query, ForgeError, ForgeMergeMethod, NewPullRequest,
+    DEFAULT_MAX_PAGES,
+};
+
+/// Items requested per page; Bitbucket caps `pagelen` at 50 for most lists.
+const PAGE_SIZE: usize = 50;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BitbucketLink {
+    pub href: String,
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BitbucketLinks {
+    #[serde(default)]
+    pub html: Option<BitbucketLink>,
+    #[serde(default)]
+    pub avatar: Option<BitbucketLink>,
+    /// Clone URLs, named "https" and "ssh".
+    #[serde(default)]
+    pub clone: Vec<BitbucketLink>,
+}
+
+impl BitbucketLinks {
+    pub fn html_url(&self) -> String {
+        self.html.as_ref().map(|link| link.href.clone()).unwrap_or_default()
+    }
+    
+    pub fn clone_url(&self, name: &str) -> String {
+        self.clone
+            .iter()
+            .find(|link| link.name.as_deref() == Some(name))
+            .map(|link| link.href.clone())
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BitbucketRepository {
+    pub uuid: String,
+    pub name: String,
+    pub full_name: String,
+    #[serde(default)]
+    pub description: String,
+    pub is_private: bool,
+    #[serde(default)]
+    pub language: String,
+    pub mainbranch: Option<BitbucketBranch>,
+    pub links: BitbucketLinks,
+    pub created_on: String,
+    pub updated_on: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BitbucketPullRequest {
+    pub id: u32,
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    pub state: String, // OPEN, MERGED, DECLINED, SUPERSEDED
+    #[serde(default)]
+    pub draft: bool,
+    pub author: BitbucketUser,
+    pub source: BitbucketEndpoint,
+    pub destination: BitbucketEndpoint,
+    #[serde(default)]
+    pub merge_commit: Option<BitbucketCommit>,
+    #[serde(default)]
+    pub comment_count: u32,
+    pub links: BitbucketLinks,
+    pub created_on: String,
+    pub updated_on: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BitbucketIssue {
+    pub id: u32,
+    pub title: String,
+    pub content: BitbucketContent,
+    pub state: String, // new, open, on hold, resolved, duplicate, invalid, wontfix, closed
+    pub kind: String,
+    pub priority: String,
+    /// None for issues reported anonymously.
+    pub reporter: Option<BitbucketUser>,
+    pub assignee: Option<BitbucketUser>,
+    pub links: BitbucketLinks,
+    pub created_on: String,
+    pub updated_on: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BitbucketComment {
+    pub id: u64,
+    pub content: BitbucketContent,
+    pub user: BitbucketUser,
+    pub links: BitbucketLinks,
+    pub created_on: String,
+    pub updated_on: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BitbucketUser {
+    pub display_name: String,
+    #[serde(default)]
+    pub nickname: Option<String>,
+    #[serde(default)]
+    pub account_id: Option<String>,
+    pub links: BitbucketLinks,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BitbucketEndpoint {
+    pub branch: BitbucketBranch,
+    pub commit: Option<BitbucketCommit>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BitbucketBranch {
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BitbucketCommit {
+    pub hash: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BitbucketContent {
+    #[serde(default)]
+    pub raw: String,
+}
+
+/// One page of a Bitbucket list; `next` is the URL of the following page.
+#[derive(Deserialize)]
+struct BitbucketPage<T> {
+    values: Vec<T>,
+    next: Option<String>,
+}
+
+/// Bitbucket Cloud client. It signs in with an access token, or with a
+/// username and app password when `username` is set.
+pub struct BitbucketClient {
+    client: Client,
+    username: Option<String>,
+    token: Option<String>,
+    base_url: String,
+    max_pages: u32,
+}
+
+impl BitbucketClient {
+    pub fn new(token: Option<String>) -> Self {
+        Self {
+            client: Client::new(),
+            username: None,
+            token,
+            base_url: "https://api.bitbucket.org/2.0".to_string(),
+            max_pages: DEFAULT_MAX_PAGES,
+        }
+    }
+    
+    pub fn with_app_password(username: String, app_password: String) -> Self {
+        Self {
+            username: Some(username),
+            ..Self::new(Some(app_password))
+        }
+    }
+    
+    pub fn with_custom_url(token: Option<String>, base_url: String) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            ..Self::new(token)
+        }
+    }
+    
+    /// Caps how many pages the `list_all_*` methods fetch.
+    pub fn with_max_pages(mut self, max_pages: u32) -> Self {
+        self.max_pages = max_pages;
+        self
+    }
+    
+    fn authorize(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        let request = request.header("User-Agent", "Code-Furnace/1.0");
+        match (&self.username, &self.token) {
+            (Some(username), Some(password)) => request.basic_auth(username, Some(password)),
+            (None, Some(token)) => request.bearer_auth(token),
+            _ => request,
+        }
+    }
+    
+    async fn make_request<T>(&self, endpoint: &str) -> Result<T>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        let url = format!("{}/{}", self.base_url, endpoint);
+        let request = self.authorize(self.client.get(&url));
+        
+        let response = send_rate_limited("Bitbucket", request).await?;
+        
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!("Bitbucket API error: {}", response.status()));
+        }
+        
+        let json: T = response.json().await?;
+        Ok(json)
+    }
+    
+    async fn make_post_request<T, B>(&self, endpoint: &str, body: &B) -> Result<T>
+    where
+        T: for<'de> Deserialize<'de>,
+        B: Serialize,
+    {
+        let url = format!("{}/{}", self.base_url, endpoint);
+        let request = self.authorize(self.client.post(&url)).json(body);
+        
+        let response = send_rate_limited("Bitbucket", request).await?;
+        
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!("Bitbucket API error: {}", response.status()));
+        }
+        
+        let json: T = response.json().await?;
+        Ok(json)
+    }
+    
+    async fn make_text_request(&self, endpoint: &str) -> Result<String> {
+        let url = format!("{}/{}", self.base_url, endpoint);
+        let request = self.authorize(self.client.get(&url));
+        
+        let response = send_rate_limited("Bitbucket", request).await?;
+        
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!("Bitbucket API error: {}", response.status()));
+        }
+        
+        Ok(response.text().await?)
+    }
+    
+    /// Follows each page's `next` URL, collecting every item.
+    async fn make_paginated_request<T>(&self, endpoint: &str) -> Result<Vec<T>>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        let mut next_url = Some(format!("{}/{}", self.base_url, with_query(endpoint, &format!("pagelen={}", PAGE_SIZE))));
+        let mut items = Vec::new();
+        let mut pages = 0;
+        
+        while let Some(url) = next_url.take() {
+            let response = send_rate_limited("Bitbucket", self.authorize(self.client.get(&url))).await?;
+            
+            if !response.status().is_success() {
+                return Err(anyhow::anyhow!("Bitbucket API error: {}", response.status()));
+            }
+            
+            let page: BitbucketPage<T> = response.json().await?;
+            next_url = page.next;
+            items.extend(page.values);
+            pages += 1;
+            
+            if pages >= self.max_pages && next_url.is_some() {
+                tracing::warn!("Stopped listing {} after {} pages", endpoint, pages);
+                break;
+            }
+        }
+        
+        Ok(items)
+    }
+    
+    pub async fn get_repository(&self, workspace: &str, repo_slug: &str) -> Result<BitbucketRepository> {
+        let endpoint = format!("repositories/{}/{}", workspace, repo_slug);
+        self.make_request(&endpoint).await
+    }
+    
+    /// `states` are OPEN, MERGED, DECLINED or SUPERSEDED; Bitbucket lists
+    /// only open pull requests when none are given.
+    pub async fn list_pull_requests(&self, workspace: &str, repo_slug: &str, states: &[&str]) -> Result<Vec<BitbucketPullRequest>> {
+        let page: BitbucketPage<BitbucketPullRequest> =
+            self.make_request(&Self::pull_requests_endpoint(workspace, repo_slug, states)).await?;
+        Ok(page.values)
+    }
+    
+    pub async fn list_all_pull_requests(
+        &self,
+        workspace: &str,
+        repo_slug: &str,
+        states: &[&str]
+    ) -> Result<Vec<BitbucketPullRequest>> {
+        self.make_paginated_request(&Self::pull_requests_endpoint(workspace, repo_slug, states)).await
+    }
+    
+    fn pull_requests_endpoint(workspace: &str, repo_slug: &str, states: &[&str]) -> String {
+        let mut endpoint = format!("repositories/{}/{}/pullrequests", workspace, repo_slug);
+        
+        if !states.is_empty() {
+            let params: Vec<String> = states.iter().map(|state| format!("state={}", state)).collect();
+            endpoint.push('?');
+            endpoint.push_str(&params.join("&"));
+        }
+        
+        endpoint
+    }
+    
+    pub async fn get_pull_request(&self, workspace: &str, repo_slug: &str, id: u32) -> Result<BitbucketPullRequest> {
+        let endpoint = format!("repositories/{}/{}/pullrequests/{}", workspace, repo_slug, id);
+        self.make_request(&endpoint).await
+    }
+    
+    /// The pull request's changes as unified diff text.
+    pub async fn get_pull_request_diff(&self, workspace: &str, repo_slug: &str, id: u32) -> Result<String> {
+        let endpoint = format!("repositories/{}/{}/pullrequests/{}/diff", workspace, repo_slug, id);
+        self.make_text_request(&endpoint).await
+    }
+    
+    pub async fn create_pull_request(
+        &self,
+        workspace: &str,
+        repo_slug: &str,
+        pull_request: &NewPullRequest
+    ) -> Result<BitbucketPullRequest> {
+        let endpoint = format!("repositories/{}/{}/pullrequests", workspace, repo_slug);
+        
+        let pr_data = serde_json::json!({
+            "title": pull_request.title,
+            "description": pull_request.body.as_deref().unwrap_or_default(),
+            "source": { "branch": { "name": pull_request.source_branch } },
+            "destination": { "branch": { "name": pull_request.target_branch } },
+            "draft": pull_request.draft,
+        });
+        
+        self.make_post_request(&endpoint, &pr_data).await
+    }
+    
+    /// Rebasing uses Bitbucket's `rebase_fast_forward` strategy. Fails with
+    /// `ForgeError::NotMergeable` or `ForgeError::MergeConflict` when
+    /// Bitbucket refuses the merge.
+    pub async fn merge_pull_request(
+        &self,
+        workspace: &str,
+        repo_slug: &str,
+        id: u32,
+        method: ForgeMergeMethod,
+        commit_message: Option<&str>
+    ) -> Result<BitbucketPullRequest> {
+        let url = format!("{}/repositories/{}/{}/pullrequests/{}/merge", self.base_url, workspace, repo_slug, id);
+        
+        let strategy = match method {
+            ForgeMergeMethod::Merge => "merge_commit",
+            ForgeMergeMethod::Squash => "squash",
+            ForgeMergeMethod::Rebase => "rebase_fast_forward",
+        };
+        let mut merge_data = serde_json::json!({ "type": "pullrequest", "merge_strategy": strategy });
+        
+        if let Some(commit_message) = commit_message {
+            merge_data["message"] = serde_json::Value::String(commit_message.to_string());
+        }
+        
+        let request = self.authorize(self.client.post(&url)).json(&merge_data);
+        let response = send_rate_limited("Bitbucket", request).await?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            // Bitbucket answers an unmergeable pull request with a plain 400
+            if status == StatusCode::BAD_REQUEST {
+                return Err(ForgeError::NotMergeable(error_message(&body)).into());
+            }
+            return Err(merge_error("Bitbucket", status, &body));
+        }
+        
+        Ok(response.json().await?)
+    }
+    
+    /// `query` is a Bitbucket filter such as `state="open"`.
+    pub async fn list_issues(&self, workspace: &str, repo_slug: &str, query: Option<&str>) -> Result<Vec<BitbucketIssue>> {
+        let page: BitbucketPage<BitbucketIssue> =
+            self.make_request(&Self::issues_endpoint(workspace, repo_slug, query)).await?;
+        Ok(page.values)
+    }
+    
+    pub async fn list_all_issues(&self, workspace: &str, repo_slug: &str, query: Option<&str>) -> Result<Vec<BitbucketIssue>> {
+        self.make_paginated_request(&Self::issues_endpoint(workspace, repo_slug, query)).await
+    }
+    
+    fn issues_endpoint(workspace: &str, repo_slug: &str, query: Option<&str>) -> String {
+        let mut endpoint = format!("repositories/{}/{}/issues", workspace, repo_slug);
+        
+        if let Some(query) = query {
+            endpoint.push_str(&format!("?q={}", urlencoding::encode(query)));
+        }
+        
+        endpoint
+    }
+    
+    pub async fn get_issue(&self, workspace: &str, repo_slug: &str, id: u32) -> Result<BitbucketIssue> {
+        let endpoint = format!("repositories/{}/{}/issues/{}", workspace, repo_slug, id);
+        self.make_request(&endpoint).await
+    }
+    
+    pub async fn create_issue(
+        &self,
+        workspace: &str,
+        repo_slug: &str,
+        title: &str,
+        content: Option<&str>
+    ) -> Result<BitbucketIssue> {
+        let endpoint = format!("repositories/{}/{}/issues", workspace, repo_slug);
+        
+        let mut issue_data = serde_json::json!({ "title": title });
+        
+        if let Some(content) = content {
+            issue_data["content"] = serde_json::json!({ "raw": content });
+        }
+        
+        self.make_post_request(&endpoint, &issue_data).await
+    }
+    
+    pub async fn create_issue_comment(&self, workspace: &str, repo_slug: &str, id: u32, body: &str) -> Result<BitbucketComment> {
+        let endpoint = format!("repositories/{}/{}/issues/{}/comments", workspace, repo_slug, id);
+        let comment_data = serde_json::json!({ "content": { "raw": body } });
+        self.make_post_request(&endpoint, &comment_data).await
+    }
+    
+    pub async fn create_pr_review_comment(&self, workspace: &str, repo_slug: &str, id: u32, body: &str) -> Result<BitbucketComment> {
+        let endpoint = format!("repositories/{}/{}/pullrequests/{}/comments", workspace, repo_slug, id);
+        let comment_data = serde_json::json!({ "content": { "raw": body } });
+        self.make_post_request(&endpoint, &comment_data).await
+    }
+    
+    pub fn extract_owner_repo_from_url(url: &str) -> Option<(String, String)> {
+        if url.contains("bitbucket.org") {
+            let parts: Vec<&str> = url.trim_end_matches('/').split('/').collect();
+            if parts.len() >= 5 {
+                let owner = parts[parts.len() - 2].to_string();
+                let repo = parts[parts.len() - 1].trim_end_matches(".git").to_string();
+                
+                return Some((owner, repo));
+            }
+        }
+        None
+    }
+}
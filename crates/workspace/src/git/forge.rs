@@ -0,0 +1,1626 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use reqwest::header::HeaderMap;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use super::bitbucket::{
+    BitbucketComment, BitbucketIssue, BitbucketPullRequest, BitbucketRepository, BitbucketUser,
+};
+use super::github::{GitHubComment, GitHubIssue, GitHubPullRequest, GitHubRelease, GitHubRepository, GitHubUser};
+use super::gitea::{GiteaComment, GiteaIssue, GiteaPullRequest, GiteaRelease, GiteaRepository, GiteaUser};
+use super::gitlab::{GitLabIssue, GitLabMergeRequest, GitLabNote, GitLabProject, GitLabRelease, GitLabUser};
+use super::{BitbucketClient, GitDiff, GitHubClient, GitLabClient, GitManager, GitPlatform, GiteaClient};
+
+/// State of a pull request or issue. GitLab merge requests are pull requests
+/// here, and their `iid` is their number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ForgeState {
+    Open,
+    Closed,
+    Merged,
+}
+
+/// Which pull requests or issues to list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ForgeListState {
+    #[default]
+    Open,
+    Closed,
+    All,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForgeUser {
+    pub username: String,
+    pub display_name: Option<String>,
+    pub avatar_url: String,
+    pub web_url: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForgeRepository {
+    pub name: String,
+    pub full_name: String,
+    pub description: Option<String>,
+    pub private: bool,
+    pub web_url: String,
+    pub clone_url: String,
+    pub ssh_url: String,
+    pub default_branch: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForgePullRequest {
+    pub number: u32,
+    pub title: String,
+    pub body: Option<String>,
+    pub state: ForgeState,
+    pub draft: bool,
+    pub web_url: String,
+    pub author: ForgeUser,
+    pub source_branch: String,
+    pub target_branch: String,
+    pub mergeable: Option<bool>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForgeIssue {
+    pub number: u32,
+    pub title: String,
+    pub body: Option<String>,
+    pub state: ForgeState,
+    pub web_url: String,
+    pub author: ForgeUser,
+    pub assignees: Vec<ForgeUser>,
+    pub labels: Vec<String>,
+    pub comments: u32,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForgeComment {
+    pub id: u64,
+    pub body: String,
+    /// GitLab has no web page for a note in its API responses.
+    pub web_url: Option<String>,
+    pub author: ForgeUser,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForgeRelease {
+    pub tag_name: String,
+    pub name: String,
+    pub body: Option<String>,
+    pub draft: bool,
+    pub prerelease: bool,
+    /// GitLab has no web page for a release in its API responses.
+    pub web_url: Option<String>,
+    pub published_at: Option<String>,
+    pub author: Option<ForgeUser>,
+}
+
+/// How a pull request's commits land on the target branch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ForgeMergeMethod {
+    #[default]
+    Merge,
+    Squash,
+    Rebase,
+}
+
+impl ForgeMergeMethod {
+    /// The value GitHub and Gitea take for this method.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ForgeMergeMethod::Merge => "merge",
+            ForgeMergeMethod::Squash => "squash",
+            ForgeMergeMethod::Rebase => "rebase",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForgeMergeResult {
+    pub merged: bool,
+    /// The commit the pull request landed as, when the forge reports it.
+    pub sha: Option<String>,
+    pub state: ForgeState,
+    pub message: Option<String>,
+}
+
+/// Forge failures callers may want to tell apart, returned inside
+/// `anyhow::Error`.
+#[derive(Debug, thiserror::Error)]
+pub enum ForgeError {
+    #[error("Pull request cannot be merged: {0}")]
+    NotMergeable(String),
+    #[error("Pull request has conflicts or its branch changed: {0}")]
+    MergeConflict(String),
+    #[error("{platform} does not support the {method:?} merge method here")]
+    UnsupportedMergeMethod { platform: String, method: ForgeMergeMethod },
+    #[error("{platform} API rate limit exceeded{}", reset_note(.reset_at))]
+    RateLimited { platform: String, reset_at: Option<DateTime<Utc>> },
+}
+
+fn reset_note(reset_at: &Option<DateTime<Utc>>) -> String {
+    reset_at.map(|at| format!("; it resets at {}", at.to_rfc3339())).unwrap_or_default()
+}
+
+/// Longest rate-limit reset a request waits out before failing with
+/// `ForgeError::RateLimited` instead.
+pub const MAX_RATE_LIMIT_WAIT: Duration = Duration::from_secs(30);
+
+/// Sends `request`, logging the quota the forge reports as left. A request
+/// refused for its rate limit is retried once if the limit resets within
+/// `MAX_RATE_LIMIT_WAIT`, and otherwise fails with `ForgeError::RateLimited`.
+pub(crate) async fn send_rate_limited(platform: &str, request: reqwest::RequestBuilder) -> Result<reqwest::Response> {
+    let retry = request.try_clone();
+    let response = request.send().await?;
+    log_quota(platform, response.headers());
+    if !rate_limited(response.status(), response.headers()) {
+        return Ok(response);
+    }
+    
+    let reset_at = rate_limit_reset(response.headers(), Utc::now());
+    let wait = reset_at.map(|reset_at| (reset_at - Utc::now()).to_std().unwrap_or_default());
+    let rate_limited_error = |reset_at| ForgeError::RateLimited { platform: platform.to_string(), reset_at };
+    match (wait, retry) {
+        (Some(wait), Some(retry)) if wait <= MAX_RATE_LIMIT_WAIT => {
+            tracing::warn!("{} API rate limit exceeded; retrying in {:?}", platform, wait);
+            tokio::time::sleep(wait).await;
+            let response = retry.send().await?;
+            log_quota(platform, response.headers());
+            if rate_limited(response.status(), response.headers()) {
+                return Err(rate_limited_error(rate_limit_reset(response.headers(), Utc::now())).into());
+            }
+            Ok(response)
+        }
+        _ => Err(rate_limited_error(reset_at).into()),
+    }
+}
+
+/// GitHub and Gitea send `X-RateLimit-*` headers, GitLab `RateLimit-*`.
+fn header_value<'a>(headers: &'a HeaderMap, names: &[&str]) -> Option<&'a str> {
+    names
+        .iter()
+        .find_map(|name| headers.get(*name))
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+}
+
+fn log_quota(platform: &str, headers: &HeaderMap) {
+    if let Some(remaining) = header_value(headers, &["x-ratelimit-remaining", "ratelimit-remaining"]) {
+        tracing::debug!("{} API quota: {} requests remaining", platform, remaining);
+    }
+}
+
+/// GitHub answers an exhausted quota with 403 rather than 429.
+fn rate_limited(status: StatusCode, headers: &HeaderMap) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS
+        || (status == StatusCode::FORBIDDEN
+            && header_value(headers, &["x-ratelimit-remaining", "ratelimit-remaining"]) == Some("0"))
+}
+
+/// When the limit lifts, from `Retry-After` seconds or the epoch seconds of
+/// the rate-limit reset header.
+fn rate_limit_reset(headers: &HeaderMap, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    if let Some(seconds) = header_value(headers, &["retry-after"]).and_then(|value| value.parse::<i64>().ok()) {
+        return Some(now + chrono::Duration::seconds(seconds));
+    }
+    header_value(headers, &["x-ratelimit-reset", "ratelimit-reset"])
+        .and_then(|value| value.parse::<i64>().ok())
+        .and_then(|seconds| DateTime::from_timestamp(seconds, 0))
+}
+
+/// Pages a `list_all_*` call fetches before returning what it has so far.
+pub const DEFAULT_MAX_PAGES: u32 = 10;
+
+/// Appends `query` to an endpoint that may already have parameters.
+pub(crate) fn with_query(endpoint: &str, query: &str) -> String {
+    let separator = if endpoint.contains('?') { '&' } else { '?' };
+    format!("{}{}{}", endpoint, separator, query)
+}
+
+/// The `rel="next"` target of a response's `Link` header.
+pub(crate) fn next_link(headers: &HeaderMap) -> Option<String> {
+    let link = headers.get(reqwest::header::LINK)?.to_str().ok()?;
+    link.split(',').find_map(|entry| {
+        let (target, params) = entry.split_once(';')?;
+        params
+            .split(';')
+            .any(|param| param.trim() == r#"rel="next""#)
+            .then(|| target.trim().trim_start_matches('<').trim_end_matches('>').to_string())
+    })
+}
+
+/// Turns an unsuccessful merge response into a `ForgeError` where the status
+/// says why, using the `message` of a JSON error body when there is one.
+pub(crate) fn merge_error(platform: &str, status: StatusCode, body: &str) -> anyhow::Error {
+    let message = error_message(body);
+    match status.as_u16() {
+        405 => ForgeError::NotMergeable(message).into(),
+        406 | 409 => ForgeError::MergeConflict(message).into(),
+        _ => anyhow::anyhow!("{} API error: {} {}", platform, status, message),
+    }
+}
+
+/// The `message` of a JSON error body (Bitbucket nests it under `error`), or
+/// the body itself.
+pub(crate) fn error_message(body: &str) -> String {
+    serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|json| {
+            let message = json.get("message").or_else(|| json.pointer("/error/message"))?;
+            Some(match message.as_str() {
+                Some(text) => text.to_string(),
+                None => message.to_string(),
+            })
+        })
+        .unwrap_or_else(|| body.trim().to_string())
+}
+
+/// Splits a commit message into the title and body the merge APIs take.
+pub(crate) fn split_commit_message(message: &str) -> (&str, Option<&str>) {
+    match message.split_once('\n') {
+        Some((title, body)) => (title.trim(), Some(body.trim()).filter(|body| !body.is_empty())),
+        None => (message.trim(), None),
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewPullRequest {
+    pub title: String,
+    pub body: Option<String>,
+    /// Branch with the changes.
+    pub source_branch: String,
+    /// Branch the changes are merged into.
+    pub target_branch: String,
+    #[serde(default)]
+    pub draft: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewIssue {
+    pub title: String,
+    pub body: Option<String>,
+    #[serde(default)]
+    pub labels: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewRelease {
+    pub tag_name: String,
+    pub name: String,
+    pub body: Option<String>,
+    #[serde(default)]
+    pub draft: bool,
+    #[serde(default)]
+    pub prerelease: bool,
+}
+
+/// The operations GitHub, GitLab, Gitea and Bitbucket have in common.
+/// Repositories are named by `owner` and `repo`; for GitLab, `owner` may
+/// include subgroups, and for Bitbucket it is the workspace.
+/// List methods fetch every page, up to the client's page cap.
+/// Platform-specific features stay on the concrete clients.
+#[async_trait::async_trait]
+pub trait GitForgeProvider: Send + Sync {
+    fn platform(&self) -> GitPlatform;
+    
+    async fn get_repository(&self, owner: &str, repo: &str) -> Result<ForgeRepository>;
+    
+    async fn list_pull_requests(&self, owner: &str, repo: &str, state: ForgeListState) -> Result<Vec<ForgePullRequest>>;
+    
+    async fn get_pull_request(&self, owner: &str, repo: &str, number: u32) -> Result<ForgePullRequest>;
+    
+    /// The pull request's changes, parsed like local diffs.
+    async fn get_pull_request_diff(&self, owner: &str, repo: &str, number: u32) -> Result<Vec<GitDiff>>;
+    
+    async fn create_pull_request(&self, owner: &str, repo: &str, pull_request: &NewPullRequest) -> Result<ForgePullRequest>;
+    
+    /// Merges a pull request, failing with `ForgeError` if it cannot be
+    /// merged. `commit_message` replaces the forge's default; its first line
+    /// is the title.
+    async fn merge_pull_request(
+        &self,
+        owner: &str,
+        repo: &str,
+        number: u32,
+        method: ForgeMergeMethod,
+        commit_message: Option<&str>,
+    ) -> Result<ForgeMergeResult>;
+    
+    async fn list_issues(&self, owner: &str, repo: &str, state: ForgeListState) -> Result<Vec<ForgeIssue>>;
+    
+    async fn get_issue(&self, owner: &str, repo: &str, number: u32) -> Result<ForgeIssue>;
+    
+    async fn create_issue(&self, owner: &str, repo: &str, issue: &NewIssue) -> Result<ForgeIssue>;
+    
+    async fn create_issue_comment(&self, owner: &str, repo: &str, number: u32, body: &str) -> Result<ForgeComment>;
+    
+    /// Comments on the pull request's conversation rather than a diff line.
+    async fn create_pr_review_comment(&self, owner: &str, repo: &str, number: u32, body: &str) -> Result<ForgeComment>;
+    
+    /// Most recent first.
+    async fn list_releases(&self, owner: &str, repo: &str) -> Result<Vec<ForgeRelease>>;
+    
+    async fn create_release(&self, owner: &str, repo: &str, release: &NewRelease) -> Result<ForgeRelease>;
+}
+
+/// A ready-to-use client for the forge hosting a repository's remote, and
+/// the name of the repository there.
+pub struct ForgeRemote {
+    pub provider: Box<dyn GitForgeProvider>,
+    pub owner: String,
+    pub repo: String,
+}
+
+impl ForgeRemote {
+    /// Picks the client for `remote_url` by `GitPlatform::detect_from_url`,
+    /// pointing self-hosted GitLab and Gitea clients at the remote's host, and
+    /// gives it the platform's token from `config`.
+    pub fn from_remote_url(remote_url: &str, config: &code_furnace_utils::Config) -> Result<Self> {
+        let url = https_remote_url(remote_url);
+        let base_url = base_url(&url).ok_or_else(|| anyhow::anyhow!("Unrecognized remote URL: {}", remote_url))?;
+        let platform = GitPlatform::detect_from_url(&url);
+        let unparseable = || anyhow::anyhow!("Cannot find the repository name in remote URL: {}", remote_url);
+        let missing_token = |platform: &str, setting: &str| {
+            anyhow::anyhow!("No {} token is configured; set {} to use the {} API", platform, setting, platform)
+        };
+        
+        let (provider, (owner, repo)): (Box<dyn GitForgeProvider>, _) = match platform {
+            GitPlatform::GitHub => {
+                let token = config.github_token.clone().ok_or_else(|| missing_token("GitHub", "github_token"))?;
+                let name = GitHubClient::extract_owner_repo_from_url(&url).ok_or_else(unparseable)?;
+                (Box::new(GitHubClient::new(Some(token)).with_max_pages(config.forge_max_pages)), name)
+            }
+            GitPlatform::GitLab => {
+                let token = config.gitlab_token.clone().ok_or_else(|| missing_token("GitLab", "gitlab_token"))?;
+                let name = GitLabClient::extract_project_id_from_url(&url)
+                    .and_then(|project_id| {
+                        project_id
+                            .rsplit_once('/')
+                            .map(|(owner, repo)| (owner.to_string(), repo.to_string()))
+                    })
+                    .ok_or_else(unparseable)?;
+                let client = if base_url == "https://gitlab.com" {
+                    GitLabClient::new(Some(token))
+                } else {
+                    GitLabClient::with_custom_url(Some(token), base_url)
+                };
+                (Box::new(client.with_max_pages(config.forge_max_pages)), name)
+            }
+            GitPlatform::Gitea | GitPlatform::Forgejo => {
+                let token = config.gitea_token.clone().ok_or_else(|| missing_token("Gitea", "gitea_token"))?;
+                let name = GiteaClient::extract_owner_repo_from_url(&url)
+                    .or_else(|| owner_repo_from_path(&url))
+                    .ok_or_else(unparseable)?;
+                let instance = GiteaClient::detect_gitea_instance(&url).unwrap_or(base_url);
+                (Box::new(GiteaClient::new(instance, Some(token)).with_max_pages(config.forge_max_pages)), name)
+            }
+            GitPlatform::Bitbucket => {
+                let token = config.bitbucket_token.clone().ok_or_else(|| missing_token("Bitbucket", "bitbucket_token"))?;
+                let name = BitbucketClient::extract_owner_repo_from_url(&url).ok_or_else(unparseable)?;
+                let client = match config.bitbucket_username.clone() {
+                    Some(username) => BitbucketClient::with_app_password(username, token),
+                    None => BitbucketClient::new(Some(token)),
+                };
+                (Box::new(client.with_max_pages(config.forge_max_pages)), name)
+            }
+            GitPlatform::Generic => {
+                return Err(anyhow::anyhow!("No supported forge hosts remote {}", remote_url));
+            }
+        };
+        
+        Ok(Self { provider, owner, repo })
+    }
+}
+
+/// Rewrites SSH remotes such as `git@host:owner/repo.git` and
+/// `ssh://git@host:22/owner/repo.git` as `https://host/owner/repo.git`, the
+/// form the URL helpers of the clients understand.
+fn https_remote_url(url: &str) -> String {
+    if let Some(rest) = url.strip_prefix("ssh://") {
+        let rest = rest.split_once('@').map_or(rest, |(_, rest)| rest);
+        let (host, path) = rest.split_once('/').unwrap_or((rest, ""));
+        let host = host.split_once(':').map_or(host, |(host, _)| host);
+        return format!("https://{}/{}", host, path);
+    }
+    if !url.contains("://") {
+        if let Some((user_host, path)) = url.split_once(':') {
+            let host = user_host.split_once('@').map_or(user_host, |(_, host)| host);
+            return format!("https://{}/{}", host, path);
+        }
+    }
+    url.to_string()
+}
+
+/// `scheme://host` of a URL.
+fn base_url(url: &str) -> Option<String> {
+    let (scheme, rest) = url.split_once("://")?;
+    let host = rest.split('/').next().filter(|host| !host.is_empty())?;
+    let host = host.split_once('@').map_or(host, |(_, host)| host);
+    Some(format!("{}://{}", scheme, host))
+}
+
+/// The last two path segments of a URL, without a `.git` suffix.
+fn owner_repo_from_path(url: &str) -> Option<(String, String)> {
+    let mut segments = url.trim_end_matches('/').rsplit('/');
+    let repo = segments.next()?.trim_end_matches(".git");
+    let owner = segments.next()?;
+    if owner.is_empty() || repo.is_empty() || owner.contains(':') {
+        return None;
+    }
+    Some((owner.to_string(), repo.to_string()))
+}
+
+impl ForgeListState {
+    /// The `state` query value of GitHub and Gitea.
+    fn as_query(self) -> &'static str {
+        match self {
+            ForgeListState::Open => "open",
+            ForgeListState::Closed => "closed",
+            ForgeListState::All => "all",
+        }
+    }
+}
+
+fn non_empty(text: String) -> Option<String> {
+    if text.is_empty() {
+        None
+    } else {
+        Some(text)
+    }
+}
+
+fn label_refs(labels: &[String]) -> Option<Vec<&str>> {
+    if labels.is_empty() {
+        None
+    } else {
+        Some(labels.iter().map(String::as_str).collect())
+    }
+}
+
+impl From<GitHubUser> for ForgeUser {
+    fn from(user: GitHubUser) -> Self {
+        Self {
+            username: user.login,
+            display_name: None,
+            avatar_url: user.avatar_url,
+            web_url: user.html_url,
+        }
+    }
+}
+
+impl From<GitHubRepository> for ForgeRepository {
+    fn from(repo: GitHubRepository) -> Self {
+        Self {
+            name: repo.name,
+            full_name: repo.full_name,
+            description: repo.description,
+            private: repo.private,
+            web_url: repo.html_url,
+            clone_url: repo.clone_url,
+            ssh_url: repo.ssh_url,
+            default_branch: repo.default_branch,
+        }
+    }
+}
+
+impl From<GitHubPullRequest> for ForgePullRequest {
+    fn from(pr: GitHubPullRequest) -> Self {
+        let state = if pr.merged {
+            ForgeState::Merged
+        } else if pr.state == "open" {
+            ForgeState::Open
+        } else {
+            ForgeState::Closed
+        };
+        Self {
+            number: pr.number,
+            title: pr.title,
+            body: pr.body,
+            state,
+            draft: pr.draft,
+            web_url: pr.html_url,
+            author: pr.user.into(),
+            source_branch: pr.head.r#ref,
+            target_branch: pr.base.r#ref,
+            mergeable: pr.mergeable,
+            created_at: pr.created_at,
+            updated_at: pr.updated_at,
+        }
+    }
+}
+
+impl From<GitHubIssue> for ForgeIssue {
+    fn from(issue: GitHubIssue) -> Self {
+        Self {
+            number: issue.number,
+            title: issue.title,
+            body: issue.body,
+            state: if issue.state == "open" { ForgeState::Open } else { ForgeState::Closed },
+            web_url: issue.html_url,
+            author: issue.user.into(),
+            assignees: issue.assignees.into_iter().map(Into::into).collect(),
+            labels: issue.labels.into_iter().map(|label| label.name).collect(),
+            comments: issue.comments,
+            created_at: issue.created_at,
+            updated_at: issue.updated_at,
+        }
+    }
+}
+
+impl From<GitHubComment> for ForgeComment {
+    fn from(comment: GitHubComment) -> Self {
+        Self {
+            id: comment.id,
+            body: comment.body,
+            web_url: Some(comment.html_url),
+            author: comment.user.into(),
+            created_at: comment.created_at,
+            updated_at: comment.updated_at,
+        }
+    }
+}
+
+impl From<GitHubRelease> for ForgeRelease {
+    fn from(release: GitHubRelease) -> Self {
+        Self {
+            tag_name: release.tag_name,
+            name: release.name,
+            body: release.body,
+            draft: release.draft,
+            prerelease: release.prerelease,
+            web_url: Some(release.html_url),
+            published_at: release.published_at,
+            author: Some(release.author.into()),
+        }
+    }
+}
+
+impl From<GitLabUser> for ForgeUser {
+    fn from(user: GitLabUser) -> Self {
+        Self {
+            username: user.username,
+            display_name: Some(user.name),
+            avatar_url: user.avatar_url,
+            web_url: user.web_url,
+        }
+    }
+}
+
+impl From<GitLabProject> for ForgeRepository {
+    fn from(project: GitLabProject) -> Self {
+        Self {
+            name: project.name,
+            full_name: project.path_with_namespace,
+            description: project.description,
+            private: project.visibility != "public",
+            web_url: project.web_url,
+            clone_url: project.http_url_to_repo,
+            ssh_url: project.ssh_url_to_repo,
+            default_branch: project.default_branch,
+        }
+    }
+}
+
+impl From<GitLabMergeRequest> for ForgePullRequest {
+    fn from(mr: GitLabMergeRequest) -> Self {
+        let state = match mr.state.as_str() {
+            "opened" => ForgeState::Open,
+            "merged" => ForgeState::Merged,
+            _ => ForgeState::Closed,
+        };
+        let mergeable = match mr.merge_status.as_str() {
+            "can_be_merged" => Some(true),
+            "cannot_be_merged" => Some(false),
+            _ => None,
+        };
+        Self {
+            number: mr.iid,
+            title: mr.title,
+            body: mr.description,
+            state,
+            draft: mr.draft,
+            web_url: mr.web_url,
+            author: mr.author.into(),
+            source_branch: mr.source_branch,
+            target_branch: mr.target_branch,
+            mergeable,
+            created_at: mr.created_at,
+            updated_at: mr.updated_at,
+        }
+    }
+}
+
+impl From<GitLabIssue> for ForgeIssue {
+    fn from(issue: GitLabIssue) -> Self {
+        Self {
+            number: issue.iid,
+            title: issue.title,
+            body: issue.description,
+            state: if issue.state == "opened" { ForgeState::Open } else { ForgeState::Closed },
+            web_url: issue.web_url,
+            author: issue.author.into(),
+            assignees: issue.assignees.into_iter().map(Into::into).collect(),
+            labels: issue.labels,
+            comments: issue.user_notes_count,
+            created_at: issue.created_at,
+            updated_at: issue.updated_at,
+        }
+    }
+}
+
+impl From<GitLabNote> for ForgeComment {
+    fn from(note: GitLabNote) -> Self {
+        Self {
+            id: note.id,
+            body: note.body,
+            web_url: None,
+            author: note.author.into(),
+            created_at: note.created_at,
+            updated_at: note.updated_at,
+        }
+    }
+}
+
+impl From<GitLabRelease> for ForgeRelease {
+    fn from(release: GitLabRelease) -> Self {
+        Self {
+            tag_name: release.tag_name,
+            name: release.name,
+            body: release.description,
+            draft: false,
+            prerelease: false,
+            web_url: None,
+            published_at: release.released_at,
+            author: Some(release.author.into()),
+        }
+    }
+}
+
+impl From<GiteaUser> for ForgeUser {
+    fn from(user: GiteaUser) -> Self {
+        Self {
+            username: user.login,
+            display_name: non_empty(user.full_name),
+            avatar_url: user.avatar_url,
+            web_url: user.html_url,
+        }
+    }
+}
+
+impl From<GiteaRepository> for ForgeRepository {
+    fn from(repo: GiteaRepository) -> Self {
+        Self {
+            name: repo.name,
+            full_name: repo.full_name,
+            description: non_empty(repo.description),
+            private: repo.private,
+            web_url: repo.html_url,
+            clone_url: repo.clone_url,
+            ssh_url: repo.ssh_url,
+            default_branch: repo.default_branch,
+        }
+    }
+}
+
+impl From<GiteaPullRequest> for ForgePullRequest {
+    fn from(pr: GiteaPullRequest) -> Self {
+        let state = if pr.merged {
+            ForgeState::Merged
+        } else if pr.state == "open" {
+            ForgeState::Open
+        } else {
+            ForgeState::Closed
+        };
+        // Gitea marks drafts by title prefix
+        let draft = pr.title.starts_with("WIP:") || pr.title.starts_with("[WIP]");
+        Self {
+            number: pr.number,
+            title: pr.title,
+            body: non_empty(pr.body),
+            state,
+            draft,
+            web_url: pr.html_url,
+            author: pr.user.into(),
+            source_branch: pr.head.r#ref,
+            target_branch: pr.base.r#ref,
+            mergeable: Some(pr.mergeable),
+            created_at: pr.created_at,
+            updated_at: pr.updated_at,
+        }
+    }
+}
+
+impl From<GiteaIssue> for ForgeIssue {
+    fn from(issue: GiteaIssue) -> Self {
+        Self {
+            number: issue.number,
+            title: issue.title,
+            body: non_empty(issue.body),
+            state: if issue.state == "open" { ForgeState::Open } else { ForgeState::Closed },
+            web_url: issue.html_url,
+            author: issue.user.into(),
+            assignees: issue.assignees.into_iter().map(Into::into).collect(),
+            labels: issue.labels.into_iter().map(|label| label.name).collect(),
+            comments: issue.comments,
+            created_at: issue.created_at,
+            updated_at: issue.updated_at,
+        }
+    }
+}
+
+impl From<GiteaComment> for ForgeComment {
+    fn from(comment: GiteaComment) -> Self {
+        Self {
+            id: comment.id,
+            body: comment.body,
+            web_url: Some(comment.html_url),
+            author: comment.user.into(),
+            created_at: comment.created_at,
+            updated_at: comment.updated_at,
+        }
+    }
+}
+
+impl From<GiteaRelease> for ForgeRelease {
+    fn from(release: GiteaRelease) -> Self {
+        Self {
+            tag_name: release.tag_name,
+            name: release.name,
+            body: non_empty(release.body),
+            draft: release.draft,
+            prerelease: release.prerelease,
+            web_url: Some(release.html_url),
+            published_at: non_empty(release.published_at),
+            author: Some(release.author.into()),
+        }
+    }
+}
+
+impl From<BitbucketUser> for ForgeUser {
+    fn from(user: BitbucketUser) -> Self {
+        Self {
+            username: user.nickname.unwrap_or_else(|| user.display_name.clone()),
+            display_name: Some(user.display_name),
+            web_url: user.links.html_url(),
+            avatar_url: user.links.avatar.map(|link| link.href).unwrap_or_default(),
+        }
+    }
+}
+
+impl From<BitbucketRepository> for ForgeRepository {
+    fn from(repo: BitbucketRepository) -> Self {
+        Self {
+            web_url: repo.links.html_url(),
+            clone_url: repo.links.clone_url("https"),
+            ssh_url: repo.links.clone_url("ssh"),
+            name: repo.name,
+            full_name: repo.full_name,
+            description: non_empty(repo.description),
+            private: repo.is_private,
+            default_branch: repo.mainbranch.map(|branch| branch.name).unwrap_or_default(),
+        }
+    }
+}
+
+impl From<BitbucketPullRequest> for ForgePullRequest {
+    fn from(pr: BitbucketPullRequest) -> Self {
+        let state = match pr.state.as_str() {
+            "OPEN" => ForgeState::Open,
+            "MERGED" => ForgeState::Merged,
+            _ => ForgeState::Closed,
+        };
+        Self {
+            number: pr.id,
+            web_url: pr.links.html_url(),
+            title: pr.title,
+            body: non_empty(pr.description),
+            state,
+            draft: pr.draft,
+            author: pr.author.into(),
+            source_branch: pr.source.branch.name,
+            target_branch: pr.destination.branch.name,
+            mergeable: None,
+            created_at: pr.created_on,
+            updated_at: pr.updated_on,
+        }
+    }
+}
+
+/// Bitbucket issue states that still need work.
+const BITBUCKET_OPEN_ISSUE_STATES: [&str; 3] = ["new", "open", "on hold"];
+
+impl From<BitbucketIssue> for ForgeIssue {
+    fn from(issue: BitbucketIssue) -> Self {
+        let state = if BITBUCKET_OPEN_ISSUE_STATES.contains(&issue.state.as_str()) {
+            ForgeState::Open
+        } else {
+            ForgeState::Closed
+        };
+        let author = issue.reporter.map(Into::into).unwrap_or_else(|| ForgeUser {
+            username: "Anonymous".to_string(),
+            display_name: None,
+            avatar_url: String::new(),
+            web_url: String::new(),
+        });
+        Self {
+            number: issue.id,
+            web_url: issue.links.html_url(),
+            title: issue.title,
+            body: non_empty(issue.content.raw),
+            state,
+            author,
+            assignees: issue.assignee.into_iter().map(Into::into).collect(),
+            labels: vec![issue.kind],
+            comments: 0,
+            created_at: issue.created_on,
+            updated_at: issue.updated_on,
+        }
+    }
+}
+
+impl From<BitbucketComment> for ForgeComment {
+    fn from(comment: BitbucketComment) -> Self {
+        Self {
+            id: comment.id,
+            body: comment.content.raw,
+            web_url: Some(comment.links.html_url()),
+            author: comment.user.into(),
+            updated_at: comment.updated_on.unwrap_or_else(|| comment.created_on.clone()),
+            created_at: comment.created_on,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl GitForgeProvider for GitHubClient {
+    fn platform(&self) -> GitPlatform {
+        GitPlatform::GitHub
+    }
+    
+    async fn get_repository(&self, owner: &str, repo: &str) -> Result<ForgeRepository> {
+        Ok(GitHubClient::get_repository(self, owner, repo).await?.into())
+    }
+    
+    async fn list_pull_requests(&self, owner: &str, repo: &str, state: ForgeListState) -> Result<Vec<ForgePullRequest>> {
+        let pull_requests = GitHubClient::list_all_pull_requests(self, owner, repo, Some(state.as_query()), None).await?;
+        Ok(pull_requests.into_iter().map(Into::into).collect())
+    }
+    
+    async fn get_pull_request(&self, owner: &str, repo: &str, number: u32) -> Result<ForgePullRequest> {
+        Ok(GitHubClient::get_pull_request(self, owner, repo, number).await?.into())
+    }
+    
+    async fn get_pull_request_diff(&self, owner: &str, repo: &str, number: u32) -> Result<Vec<GitDiff>> {
+        let patch = GitHubClient::get_pull_request_diff(self, owner, repo, number).await?;
+        GitManager::diffs_from_patch(&patch)
+    }
+    
+    async fn create_pull_request(&self, owner: &str, repo: &str, pull_request: &NewPullRequest) -> Result<ForgePullRequest> {
+        let created = GitHubClient::create_pull_request(
+            self,
+            owner,
+            repo,
+            &pull_request.title,
+            pull_request.body.as_deref(),
+            &pull_request.source_branch,
+            &pull_request.target_branch,
+            pull_request.draft,
+        )
+        .await?;
+        Ok(created.into())
+    }
+    
+    async fn merge_pull_request(
+        &self,
+        owner: &str,
+        repo: &str,
+        number: u32,
+        method: ForgeMergeMethod,
+        commit_message: Option<&str>,
+    ) -> Result<ForgeMergeResult> {
+        let result = GitHubClient::merge_pull_request(self, owner, repo, number, method, commit_message).await?;
+        Ok(ForgeMergeResult {
+            merged: result.merged,
+            sha: result.sha,
+            state: if result.merged { ForgeState::Merged } else { ForgeState::Open },
+            message: result.message,
+        })
+    }
+    
+    async fn list_issues(&self, owner: &str, repo: &str, state: ForgeListState) -> Result<Vec<ForgeIssue>> {
+        let issues = GitHubClient::list_all_issues(self, owner, repo, Some(state.as_query()), None).await?;
+        Ok(issues.into_iter().map(Into::into).collect())
+    }
+    
+    async fn get_issue(&self, owner: &str, repo: &str, number: u32) -> Result<ForgeIssue> {
+        Ok(GitHubClient::get_issue(self, owner, repo, number).await?.into())
+    }
+    
+    async fn create_issue(&self, owner: &str, repo: &str, issue: &NewIssue) -> Result<ForgeIssue> {
+        let created = GitHubClient::create_issue(
+            self,
+            owner,
+            repo,
+            &issue.title,
+            issue.body.as_deref(),
+            label_refs(&issue.labels),
+            None,
+        )
+        .await?;
+        Ok(created.into())
+    }
+    
+    async fn create_issue_comment(&self, owner: &str, repo: &str, number: u32, body: &str) -> Result<ForgeComment> {
+        let comment = GitHubClient::create_issue_comment(self, owner, repo, number, body).await?;
+        Ok(comment.into())
+    }
+    
+    async fn create_pr_review_comment(&self, owner: &str, repo: &str, number: u32, body: &str) -> Result<ForgeComment> {
+        let comment = GitHubClient::create_pr_review_comment(self, owner, repo, number, body).await?;
+        Ok(comment.into())
+    }
+    
+    async fn list_releases(&self, owner: &str, repo: &str) -> Result<Vec<ForgeRelease>> {
+        let releases = GitHubClient::list_all_releases(self, owner, repo).await?;
+        Ok(releases.into_iter().map(Into::into).collect())
+    }
+    
+    async fn create_release(&self, owner: &str, repo: &str, release: &NewRelease) -> Result<ForgeRelease> {
+        let created = GitHubClient::create_release(
+            self,
+            owner,
+            repo,
+            &release.tag_name,
+            &release.name,
+            release.body.as_deref(),
+            release.draft,
+            release.prerelease,
+        )
+        .await?;
+        Ok(created.into())
+    }
+}
+
+/// GitLab names projects by their full path.
+fn gitlab_project_id(owner: &str, repo: &str) -> String {
+    format!("{}/{}", owner, repo)
+}
+
+#[async_trait::async_trait]
+impl GitForgeProvider for GitLabClient {
+    fn platform(&self) -> GitPlatform {
+        GitPlatform::GitLab
+    }
+    
+    async fn get_repository(&self, owner: &str, repo: &str) -> Result<ForgeRepository> {
+        Ok(self.get_project(&gitlab_project_id(owner, repo)).await?.into())
+    }
+    
+    async fn list_pull_requests(&self, owner: &str, repo: &str, state: ForgeListState) -> Result<Vec<ForgePullRequest>> {
+        let state = match state {
+            ForgeListState::Open => "opened",
+            ForgeListState::Closed => "closed",
+            ForgeListState::All => "all",
+        };
+        let merge_requests = self.list_all_merge_requests(&gitlab_project_id(owner, repo), Some(state), None).await?;
+        Ok(merge_requests.into_iter().map(Into::into).collect())
+    }
+    
+    async fn get_pull_request(&self, owner: &str, repo: &str, number: u32) -> Result<ForgePullRequest> {
+        Ok(self.get_merge_request(&gitlab_project_id(owner, repo), number).await?.into())
+    }
+    
+    async fn get_pull_request_diff(&self, owner: &str, repo: &str, number: u32) -> Result<Vec<GitDiff>> {
+        let patch = self.get_merge_request_diff(&gitlab_project_id(owner, repo), number).await?;
+        GitManager::diffs_from_patch(&patch)
+    }
+    
+    async fn create_pull_request(&self, owner: &str, repo: &str, pull_request: &NewPullRequest) -> Result<ForgePullRequest> {
+        let created = self
+            .create_merge_request(
+                &gitlab_project_id(owner, repo),
+                &pull_request.source_branch,
+                &pull_request.target_branch,
+                &pull_request.title,
+                pull_request.body.as_deref(),
+                pull_request.draft,
+            )
+            .await?;
+        Ok(created.into())
+    }
+    
+    async fn merge_pull_request(
+        &self,
+        owner: &str,
+        repo: &str,
+        number: u32,
+        method: ForgeMergeMethod,
+        commit_message: Option<&str>,
+    ) -> Result<ForgeMergeResult> {
+        let merge_request = self
+            .merge_merge_request(&gitlab_project_id(owner, repo), number, method, commit_message)
+            .await?;
+        let sha = merge_request.merge_commit_sha.clone().or_else(|| merge_request.squash_commit_sha.clone());
+        let merge_request = ForgePullRequest::from(merge_request);
+        Ok(ForgeMergeResult {
+            merged: merge_request.state == ForgeState::Merged,
+            sha,
+            state: merge_request.state,
+            message: None,
+        })
+    }
+    
+    async fn list_issues(&self, owner: &str, repo: &str, state: ForgeListState) -> Result<Vec<ForgeIssue>> {
+        let state = match state {
+            ForgeListState::Open => "opened",
+            ForgeListState::Closed => "closed",
+            ForgeListState::All => "all",
+        };
+        let issues = GitLabClient::list_all_issues(self, &gitlab_project_id(owner, repo), Some(state), None).await?;
+        Ok(issues.into_iter().map(Into::into).collect())
+    }
+    
+    async fn get_issue(&self, owner: &str, repo: &str, number: u32) -> Result<ForgeIssue> {
+        Ok(GitLabClient::get_issue(self, &gitlab_project_id(owner, repo), number).await?.into())
+    }
+    
+    async fn create_issue(&self, owner: &str, repo: &str, issue: &NewIssue) -> Result<ForgeIssue> {
+        let created = GitLabClient::create_issue(
+            self,
+            &gitlab_project_id(owner, repo),
+            &issue.title,
+            issue.body.as_deref(),
+            label_refs(&issue.labels),
+            None,
+        )
+        .await?;
+        Ok(created.into())
+    }
+    
+    async fn create_issue_comment(&self, owner: &str, repo: &str, number: u32, body: &str) -> Result<ForgeComment> {
+        let comment = GitLabClient::create_issue_comment(self, &gitlab_project_id(owner, repo), number, body).await?;
+        Ok(comment.into())
+    }
+    
+    async fn create_pr_review_comment(&self, owner: &str, repo: &str, number: u32, body: &str) -> Result<ForgeComment> {
+        let comment = GitLabClient::create_pr_review_comment(self, &gitlab_project_id(owner, repo), number, body).await?;
+        Ok(comment.into())
+    }
+    
+    async fn list_releases(&self, owner: &str, repo: &str) -> Result<Vec<ForgeRelease>> {
+        let releases = GitLabClient::list_all_releases(self, &gitlab_project_id(owner, repo)).await?;
+        Ok(releases.into_iter().map(Into::into).collect())
+    }
+    
+    /// GitLab has no draft or pre-release flags, so those are ignored.
+    async fn create_release(&self, owner: &str, repo: &str, release: &NewRelease) -> Result<ForgeRelease> {
+        let created = GitLabClient::create_release(
+            self,
+            &gitlab_project_id(owner, repo),
+            &release.tag_name,
+            &release.name,
+            release.body.as_deref(),
+        )
+        .await?;
+        Ok(created.into())
+    }
+}
+
+#[async_trait::async_trait]
+impl GitForgeProvider for GiteaClient {
+    fn platform(&self) -> GitPlatform {
+        GitPlatform::Gitea
+    }
+    
+    async fn get_repository(&self, owner: &str, repo: &str) -> Result<ForgeRepository> {
+        Ok(GiteaClient::get_repository(self, owner, repo).await?.into())
+    }
+    
+    async fn list_pull_requests(&self, owner: &str, repo: &str, state: ForgeListState) -> Result<Vec<ForgePullRequest>> {
+        let pull_requests = GiteaClient::list_all_pull_requests(self, owner, repo, Some(state.as_query())).await?;
+        Ok(pull_requests.into_iter().map(Into::into).collect())
+    }
+    
+    async fn get_pull_request(&self, owner: &str, repo: &str, number: u32) -> Result<ForgePullRequest> {
+        Ok(GiteaClient::get_pull_request(self, owner, repo, number).await?.into())
+    }
+    
+    async fn get_pull_request_diff(&self, owner: &str, repo: &str, number: u32) -> Result<Vec<GitDiff>> {
+        let patch = GiteaClient::get_pull_request_diff(self, owner, repo, number).await?;
+        GitManager::diffs_from_patch(&patch)
+    }
+    
+    /// Gitea has no draft flag; drafts get the `WIP:` title prefix it
+    /// recognizes instead.
+    async fn create_pull_request(&self, owner: &str, repo: &str, pull_request: &NewPullRequest) -> Result<ForgePullRequest> {
+        let title = if pull_request.draft && !pull_request.title.starts_with("WIP:") {
+            format!("WIP: {}", pull_request.title)
+        } else {
+            pull_request.title.clone()
+        };
+        let created = GiteaClient::create_pull_request(
+            self,
+            owner,
+            repo,
+            &title,
+            pull_request.body.as_deref(),
+            &pull_request.source_branch,
+            &pull_request.target_branch,
+        )
+        .await?;
+        Ok(created.into())
+    }
+    
+    async fn merge_pull_request(
+        &self,
+        owner: &str,
+        repo: &str,
+        number: u32,
+        method: ForgeMergeMethod,
+        commit_message: Option<&str>,
+    ) -> Result<ForgeMergeResult> {
+        let pull_request = GiteaClient::merge_pull_request(self, owner, repo, number, method, commit_message).await?;
+        let sha = pull_request.merge_commit_sha.clone();
+        let pull_request = ForgePullRequest::from(pull_request);
+        Ok(ForgeMergeResult {
+            merged: pull_request.state == ForgeState::Merged,
+            sha,
+            state: pull_request.state,
+            message: None,
+        })
+    }
+    
+    async fn list_issues(&self, owner: &str, repo: &str, state: ForgeListState) -> Result<Vec<ForgeIssue>> {
+        let issues = GiteaClient::list_all_issues(self, owner, repo, Some(state.as_query()), None).await?;
+        Ok(issues.into_iter().map(Into::into).collect())
+    }
+    
+    async fn get_issue(&self, owner: &str, repo: &str, number: u32) -> Result<ForgeIssue> {
+        Ok(GiteaClient::get_issue(self, owner, repo, number).await?.into())
+    }
+    
+    async fn create_issue(&self, owner: &str, repo: &str, issue: &NewIssue) -> Result<ForgeIssue> {
+        let created = GiteaClient::create_issue(
+            self,
+            owner,
+            repo,
+            &issue.title,
+            issue.body.as_deref(),
+            label_refs(&issue.labels),
+            None,
+        )
+        .await?;
+        Ok(created.into())
+    }
+    
+    async fn create_issue_comment(&self, owner: &str, repo: &str, number: u32, body: &str) -> Result<ForgeComment> {
+        let comment = GiteaClient::create_issue_comment(self, owner, repo, number, body).await?;
+        Ok(comment.into())
+    }
+    
+    async fn create_pr_review_comment(&self, owner: &str, repo: &str, number: u32, body: &str) -> Result<ForgeComment> {
+        let comment = GiteaClient::create_pr_review_comment(self, owner, repo, number, body).await?;
+        Ok(comment.into())
+    }
+    
+    async fn list_releases(&self, owner: &str, repo: &str) -> Result<Vec<ForgeRelease>> {
+        let releases = GiteaClient::list_all_releases(self, owner, repo).await?;
+        Ok(releases.into_iter().map(Into::into).collect())
+    }
+    
+    async fn create_release(&self, owner: &str, repo: &str, release: &NewRelease) -> Result<ForgeRelease> {
+        let created = GiteaClient::create_release(
+            self,
+            owner,
+            repo,
+            &release.tag_name,
+            &release.name,
+            release.body.as_deref(),
+            release.draft,
+            release.prerelease,
+        )
+        .await?;
+        Ok(created.into())
+    }
+}
+
+/// Bitbucket's issue filter for `state`.
+fn bitbucket_issue_query(state: ForgeListState) -> Option<String> {
+    let states: Vec<String> = match state {
+        ForgeListState::Open => BITBUCKET_OPEN_ISSUE_STATES.iter().map(|state| format!("state=\"{}\"", state)).collect(),
+        ForgeListState::Closed => BITBUCKET_OPEN_ISSUE_STATES.iter().map(|state| format!("state!=\"{}\"", state)).collect(),
+        ForgeListState::All => return None,
+    };
+    let joiner = if state == ForgeListState::Open { " OR " } else { " AND " };
+    Some(states.join(joiner))
+}
+
+/// Bitbucket Cloud has no release API, so the release methods fail.
+#[async_trait::async_trait]
+impl GitForgeProvider for BitbucketClient {
+    fn platform(&self) -> GitPlatform {
+        GitPlatform::Bitbucket
+    }
+    
+    async fn get_repository(&self, owner: &str, repo: &str) -> Result<ForgeRepository> {
+        Ok(BitbucketClient::get_repository(self, owner, repo).await?.into())
+    }
+    
+    async fn list_pull_requests(&self, owner: &str, repo: &str, state: ForgeListState) -> Result<Vec<ForgePullRequest>> {
+        let states: &[&str] = match state {
+            ForgeListState::Open => &["OPEN"],
+            ForgeListState::Closed => &["MERGED", "DECLINED", "SUPERSEDED"],
+            ForgeListState::All => &["OPEN", "MERGED", "DECLINED", "SUPERSEDED"],
+        };
+        let pull_requests = BitbucketClient::list_all_pull_requests(self, owner, repo, states).await?;
+        Ok(pull_requests.into_iter().map(Into::into).collect())
+    }
+    
+    async fn get_pull_request(&self, owner: &str, repo: &str, number: u32) -> Result<ForgePullRequest> {
+        Ok(BitbucketClient::get_pull_request(self, owner, repo, number).await?.into())
+    }
+    
+    async fn get_pull_request_diff(&self, owner: &str, repo: &str, number: u32) -> Result<Vec<GitDiff>> {
+        let patch = BitbucketClient::get_pull_request_diff(self, owner, repo, number).await?;
+        GitManager::diffs_from_patch(&patch)
+    }
+    
+    async fn create_pull_request(&self, owner: &str, repo: &str, pull_request: &NewPullRequest) -> Result<ForgePullRequest> {
+        let created = BitbucketClient::create_pull_request(self, owner, repo, pull_request).await?;
+        Ok(created.into())
+    }
+    
+    async fn merge_pull_request(
+        &self,
+        owner: &str,
+        repo: &str,
+        number: u32,
+        method: ForgeMergeMethod,
+        commit_message: Option<&str>,
+    ) -> Result<ForgeMergeResult> {
+        let pull_request = BitbucketClient::merge_pull_request(self, owner, repo, number, method, commit_message).await?;
+        let sha = pull_request.merge_commit.as_ref().map(|commit| commit.hash.clone());
+        let pull_request = ForgePullRequest::from(pull_request);
+        Ok(ForgeMergeResult {
+            merged: pull_request.state == ForgeState::Merged,
+            sha,
+            state: pull_request.state,
+            message: None,
+        })
+    }
+    
+    async fn list_issues(&self, owner: &str, repo: &str, state: ForgeListState) -> Result<Vec<ForgeIssue>> {
+        let query = bitbucket_issue_query(state);
+        let issues = BitbucketClient::list_all_issues(self, owner, repo, query.as_deref()).await?;
+        Ok(issues.into_iter().map(Into::into).collect())
+    }
+    
+    async fn get_issue(&self, owner: &str, repo: &str, number: u32) -> Result<ForgeIssue> {
+        Ok(BitbucketClient::get_issue(self, owner, repo, number).await?.into())
+    }
+    
+    /// Bitbucket issues have a kind and priority but no labels, so labels
+    /// are ignored.
+    async fn create_issue(&self, owner: &str, repo: &str, issue: &NewIssue) -> Result<ForgeIssue> {
+        let created = BitbucketClient::create_issue(self, owner, repo, &issue.title, issue.body.as_deref()).await?;
+        Ok(created.into())
+    }
+    
+    async fn create_issue_comment(&self, owner: &str, repo: &str, number: u32, body: &str) -> Result<ForgeComment> {
+        let comment = BitbucketClient::create_issue_comment(self, owner, repo, number, body).await?;
+        Ok(comment.into())
+    }
+    
+    async fn create_pr_review_comment(&self, owner: &str, repo: &str, number: u32, body: &str) -> Result<ForgeComment> {
+        let comment = BitbucketClient::create_pr_review_comment(self, owner, repo, number, body).await?;
+        Ok(comment.into())
+    }
+    
+    async fn list_releases(&self, _owner: &str, _repo: &str) -> Result<Vec<ForgeRelease>> {
+        Err(anyhow::anyhow!("Bitbucket has no releases"))
+    }
+    
+    async fn create_release(&self, _owner: &str, _repo: &str, _release: &NewRelease) -> Result<ForgeRelease> {
+        Err(anyhow::anyhow!("Bitbucket has no releases"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+    
+    fn config_with_tokens() -> code_furnace_utils::Config {
+        code_furnace_utils::Config {
+            github_token: Some("github".to_string()),
+            gitlab_token: Some("gitlab".to_string()),
+            gitea_token: Some("gitea".to_string()),
+            bitbucket_token: Some("bitbucket".to_string()),
+            ..Default::default()
+        }
+    }
+    
+    #[test]
+    fn ssh_remotes_are_rewritten_as_https() {
+        assert_eq!(https_remote_url("git@github.com:owner/repo.git"), "https://github.com/owner/repo.git");
+        assert_eq!(
+            https_remote_url("ssh://git@gitlab.example.com:2222/group/project.git"),
+            "https://gitlab.example.com/group/project.git"
+        );
+        assert_eq!(https_remote_url("https://codeberg.org/owner/repo"), "https://codeberg.org/owner/repo");
+    }
+    
+    #[test]
+    fn remote_picks_the_matching_client() {
+        let config = config_with_tokens();
+        let remotes = [
+            ("git@github.com:owner/repo.git", "GitHub", "owner", "repo"),
+            ("https://gitlab.example.com/group/project.git", "GitLab", "group", "project"),
+            ("https://gitea.example.com/owner/repo.git", "Gitea", "owner", "repo"),
+            ("git@forgejo.example.com:owner/repo.git", "Gitea", "owner", "repo"),
+            ("git@bitbucket.org:workspace/repo.git", "Bitbucket", "workspace", "repo"),
+            ("https://user@bitbucket.org/workspace/repo.git", "Bitbucket", "workspace", "repo"),
+        ];
+        for (url, platform, owner, repo) in remotes {
+            let remote = ForgeRemote::from_remote_url(url, &config).unwrap();
+            assert_eq!(format!("{:?}", remote.provider.platform()), platform, "{}", url);
+            assert_eq!((remote.owner.as_str(), remote.repo.as_str()), (owner, repo), "{}", url);
+        }
+    }
+    
+    #[test]
+    fn remote_without_token_or_forge_is_an_error() {
+        let config = code_furnace_utils::Config::default();
+        let error = ForgeRemote::from_remote_url("https://github.com/owner/repo", &config).err().unwrap();
+        assert!(error.to_string().contains("github_token"));
+        
+        let error = ForgeRemote::from_remote_url("https://example.com/owner/repo", &config_with_tokens()).err().unwrap();
+        assert!(error.to_string().contains("No supported forge"));
+    }
+    
+    #[test]
+    fn merge_refusals_become_typed_errors() {
+        let error = merge_error("GitHub", reqwest::StatusCode::METHOD_NOT_ALLOWED, r#"{"message":"Pull Request is not mergeable"}"#);
+        assert!(matches!(
+            error.downcast_ref::<ForgeError>(),
+            Some(ForgeError::NotMergeable(message)) if message == "Pull Request is not mergeable"
+        ));
+        
+        let error = merge_error("GitLab", reqwest::StatusCode::NOT_ACCEPTABLE, "Branch cannot be merged");
+        assert!(matches!(error.downcast_ref::<ForgeError>(), Some(ForgeError::MergeConflict(_))));
+        
+        let error = merge_error("Gitea", reqwest::StatusCode::INTERNAL_SERVER_ERROR, "");
+        assert!(error.downcast_ref::<ForgeError>().is_none());
+        
+        assert_eq!(split_commit_message("Title\n\nBody"), ("Title", Some("Body")));
+        assert_eq!(split_commit_message("Title only"), ("Title only", None));
+    }
+    
+    /// Serves one canned response per connection on a local port, in order,
+    /// and records the path each request asked for. `responses` gets the
+    /// server's base URL and returns each response's status, extra header
+    /// lines and body.
+    fn mock_server(responses: impl FnOnce(&str) -> Vec<(u16, String, String)>) -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let responses = responses(&base);
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let seen = requests.clone();
+        std::thread::spawn(move || {
+            for (status, headers, body) in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap() > 2 {
+                    line.clear();
+                }
+                seen.lock().unwrap().push(request_line.split_whitespace().nth(1).unwrap().to_string());
+                write!(
+                    stream,
+                    "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n{}\r\n{}",
+                    status,
+                    body.len(),
+                    headers,
+                    body
+                )
+                .unwrap();
+            }
+        });
+        (base, requests)
+    }
+    
+    fn github_release(tag: &str) -> serde_json::Value {
+        serde_json::json!({
+            "id": 1, "tag_name": tag, "name": tag, "body": null, "draft": false, "prerelease": false,
+            "html_url": "", "published_at": null,
+            "author": { "id": 1, "login": "owner", "avatar_url": "", "html_url": "" },
+        })
+    }
+    
+    #[tokio::test]
+    async fn github_follows_link_headers_up_to_the_page_cap() {
+        let (base, requests) = mock_server(|base| {
+            vec![
+                (
+                    200,
+                    format!("Link: <{0}/page2>; rel=\"next\", <{0}/page3>; rel=\"last\"\r\n", base),
+                    serde_json::json!([github_release("v3"), github_release("v2")]).to_string(),
+                ),
+                (200, format!("Link: <{}/page3>; rel=\"next\"\r\n", base), serde_json::json!([github_release("v1")]).to_string()),
+                (200, String::new(), serde_json::json!([github_release("v0")]).to_string()),
+            ]
+        });
+        let client = GitHubClient::with_enterprise_url(None, base);
+        let releases = client.list_all_releases("owner", "repo").await.unwrap();
+        let tags: Vec<_> = releases.iter().map(|release| release.tag_name.as_str()).collect();
+        assert_eq!(tags, ["v3", "v2", "v1", "v0"]);
+        assert_eq!(*requests.lock().unwrap(), ["/repos/owner/repo/releases?per_page=100", "/page2", "/page3"]);
+        
+        let (base, requests) = mock_server(|base| {
+            (1..=2)
+                .map(|page| {
+                    (
+                        200,
+                        format!("Link: <{}/page{}>; rel=\"next\"\r\n", base, page + 1),
+                        serde_json::json!([github_release(&format!("v{}", page))]).to_string(),
+                    )
+                })
+                .collect()
+        });
+        let client = GitHubClient::with_enterprise_url(None, base).with_max_pages(2);
+        assert_eq!(client.list_all_releases("owner", "repo").await.unwrap().len(), 2);
+        assert_eq!(requests.lock().unwrap().len(), 2);
+    }
+    
+    #[tokio::test]
+    async fn gitlab_and_gitea_request_pages_until_the_list_ends() {
+        let release = |tag: &str| {
+            serde_json::json!({
+                "tag_name": tag, "name": tag, "description": null, "created_at": "", "released_at": null,
+                "author": { "id": 1, "name": "Owner", "username": "owner", "avatar_url": "", "web_url": "" },
+                "_links": { "self": "", "edit_url": "" },
+            })
+        };
+        let (base, requests) = mock_server(|_| {
+            vec![
+                (200, "X-Next-Page: 2\r\n".to_string(), serde_json::json!([release("v2")]).to_string()),
+                (200, "X-Next-Page: \r\n".to_string(), serde_json::json!([release("v1")]).to_string()),
+            ]
+        });
+        let client = GitLabClient::with_custom_url(None, base);
+        assert_eq!(client.list_all_releases("owner/repo").await.unwrap().len(), 2);
+        assert_eq!(
+            *requests.lock().unwrap(),
+            [
+                "/api/v4/projects/owner%2Frepo/releases?per_page=100&page=1",
+                "/api/v4/projects/owner%2Frepo/releases?per_page=100&page=2",
+            ]
+        );
+        
+        let release = |tag: &str| {
+            serde_json::json!({
+                "id": 1, "tag_name": tag, "name": tag, "body": "", "draft": false, "prerelease": false,
+                "html_url": "", "created_at": "", "published_at": "",
+                "author": { "id": 1, "login": "owner", "full_name": "", "email": "", "avatar_url": "", "html_url": "" },
+            })
+        };
+        let (base, requests) = mock_server(|_| {
+            vec![
+                (200, "X-Total-Count: 2\r\n".to_string(), serde_json::json!([release("v2")]).to_string()),
+                (200, "X-Total-Count: 2\r\n".to_string(), serde_json::json!([release("v1")]).to_string()),
+            ]
+        });
+        let client = GiteaClient::new(base, None);
+        assert_eq!(client.list_all_releases("owner", "repo").await.unwrap().len(), 2);
+        assert_eq!(
+            *requests.lock().unwrap(),
+            [
+                "/api/v1/repos/owner/repo/releases?limit=50&page=1",
+                "/api/v1/repos/owner/repo/releases?limit=50&page=2",
+            ]
+        );
+    }
+    
+    #[tokio::test]
+    async fn rate_limited_requests_wait_briefly_or_fail_with_the_reset_time() {
+        let (base, requests) = mock_server(|_| {
+            vec![
+                (429, "Retry-After: 0\r\n".to_string(), String::new()),
+                (200, "X-RateLimit-Remaining: 59\r\n".to_string(), serde_json::json!([github_release("v1")]).to_string()),
+            ]
+        });
+        let client = GitHubClient::with_enterprise_url(None, base);
+        assert_eq!(client.list_releases("owner", "repo").await.unwrap().len(), 1);
+        assert_eq!(requests.lock().unwrap().len(), 2);
+        
+        let (base, requests) = mock_server(|_| {
+            vec![(403, "X-RateLimit-Remaining: 0\r\nX-RateLimit-Reset: 4102444800\r\n".to_string(), String::new())]
+        });
+        let client = GitHubClient::with_enterprise_url(None, base);
+        let error = client.list_releases("owner", "repo").await.err().unwrap();
+        assert!(matches!(
+            error.downcast_ref::<ForgeError>(),
+            Some(ForgeError::RateLimited { reset_at: Some(reset_at), .. }) if reset_at.timestamp() == 4102444800
+        ));
+        assert_eq!(requests.lock().unwrap().len(), 1);
+    }
+    
+    #[tokio::test]
+    async fn bitbucket_pull_requests_follow_next_links() {
+        let pull_request = |id: u32, state: &str| {
+            let user = serde_json::json!({ "display_name": "Owner", "nickname": "owner", "links": {} });
+            serde_json::json!({
+                "id": id, "title": format!("Change {}", id), "description": "", "state": state,
+                "author": user, "source": { "branch": { "name": "feature" } }, "destination": { "branch": { "name": "main" } },
+                "links": { "html": { "href": format!("https://bitbucket.org/workspace/repo/pull-requests/{}", id) } },
+                "created_on": "", "updated_on": "",
+            })
+        };
+        let (base, requests) = mock_server(|base| {
+            vec![
+                (200, String::new(), serde_json::json!({ "values": [pull_request(2, "OPEN")], "next": format!("{}/page2", base) }).to_string()),
+                (200, String::new(), serde_json::json!({ "values": [pull_request(1, "MERGED")] }).to_string()),
+            ]
+        });
+        let client = BitbucketClient::with_custom_url(None, base);
+        let pull_requests = GitForgeProvider::list_pull_requests(&client, "workspace", "repo", ForgeListState::All).await.unwrap();
+        let summary: Vec<_> = pull_requests.iter().map(|pr| (pr.number, pr.state, pr.author.username.as_str())).collect();
+        assert_eq!(summary, [(2, ForgeState::Open, "owner"), (1, ForgeState::Merged, "owner")]);
+        assert_eq!(
+            *requests.lock().unwrap(),
+            [
+                "/repositories/workspace/repo/pullrequests?state=OPEN&state=MERGED&state=DECLINED&state=SUPERSEDED&pagelen=50",
+                "/page2",
+            ]
+        );
+    }
+    
+    #[tokio::test]
+    async fn gitlab_changes_are_parsed_into_file_diffs() {
+        let changes = serde_json::json!({ "changes": [
+            {
+                "old_path": "src/lib.rs", "new_path": "src/lib.rs", "new_file": false, "renamed_file": false, "deleted_file": false,
+                "diff": "@@ -1,2 +1,2 @@\n fn main() {}\n-old\n+new\n",
+            },
+            {
+                "old_path": "README.md", "new_path": "README.md", "new_file": true, "renamed_file": false, "deleted_file": false,
+                "diff": "@@ -0,0 +1,2 @@\n+# Title\n+Text\n",
+            },
+            {
+                "old_path": "logo.png", "new_path": "logo.png", "new_file": false, "renamed_file": false, "deleted_file": false,
+                "diff": "",
+            },
+        ]});
+        let (base, _) = mock_server(|_| vec![(200, String::new(), changes.to_string())]);
+        let client = GitLabClient::with_custom_url(None, base);
+        let diffs = GitForgeProvider::get_pull_request_diff(&client, "group", "project", 7).await.unwrap();
+        
+        let summary: Vec<_> = diffs
+            .iter()
+            .map(|diff| (diff.old_file.as_deref(), diff.new_file.as_deref(), diff.additions, diff.deletions))
+            .collect();
+        assert_eq!(
+            summary,
+            [(Some("src/lib.rs"), Some("src/lib.rs"), 1, 1), (None, Some("README.md"), 2, 0)]
+        );
+        assert_eq!(diffs[0].hunks[0].lines[2].new_line_no, Some(2));
+    }
+}
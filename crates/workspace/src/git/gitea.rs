@@ -3,6 +3,13 @@ use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use super::forge::{
+    merge_error, send_rate_limited, split_commit_message, with_query, ForgeMergeMethod, DEFAULT_MAX_PAGES,
+};
+
+/// Items requested per page; Gitea caps `limit` at 50 by default.
+const PAGE_SIZE: usize = 50;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GiteaRepository {
     pub id: u64,
@@ -38,6 +45,8 @@ pub struct GiteaPullRequest {
     pub mergeable: bool,
     pub merged: bool,
     pub merged_at: Option<String>,
+    #[serde(default)]
+    pub merge_commit_sha: Option<String>,
     pub comments: u32,
     pub additions: u32,
     pub deletions: u32,
@@ -61,6 +70,16 @@ pub struct GiteaIssue {
     pub comments: u32,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GiteaComment {
+    pub id: u64,
+    pub body: String,
+    pub html_url: String,
+    pub user: GiteaUser,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GiteaUser {
     pub id: u64,
@@ -126,6 +145,7 @@ pub struct GiteaClient {
     client: Client,
     token: Option<String>,
     base_url: String,
+    max_pages: u32,
 }
 
 impl GiteaClient {
@@ -140,6 +160,7 @@ impl GiteaClient {
             client: Client::new(),
             token,
             base_url: api_url,
+            max_pages: DEFAULT_MAX_PAGES,
         }
     }
     
@@ -152,6 +173,12 @@ impl GiteaClient {
         Self::new("https://codeberg.org".to_string(), token)
     }
     
+    /// Caps how many pages the `list_all_*` methods fetch.
+    pub fn with_max_pages(mut self, max_pages: u32) -> Self {
+        self.max_pages = max_pages;
+        self
+    }
+    
     async fn make_request<T>(&self, endpoint: &str) -> Result<T>
     where
         T: for<'de> Deserialize<'de>,
@@ -165,7 +192,7 @@ impl GiteaClient {
         
         request = request.header("User-Agent", "Code-Furnace/1.0");
         
-        let response = request.send().await?;
+        let response = send_rate_limited("Gitea", request).await?;
         
         if !response.status().is_success() {
             return Err(anyhow::anyhow!("Gitea API error: {}", response.status()));
@@ -192,7 +219,7 @@ impl GiteaClient {
             .header("Content-Type", "application/json")
             .json(body);
         
-        let response = request.send().await?;
+        let response = send_rate_limited("Gitea", request).await?;
         
         if !response.status().is_success() {
             return Err(anyhow::anyhow!("Gitea API error: {}", response.status()));
@@ -202,6 +229,94 @@ impl GiteaClient {
         Ok(json)
     }
     
+    async fn make_text_request(&self, endpoint: &str) -> Result<String> {
+        let url = format!("{}/{}", self.base_url, endpoint);
+        let mut request = self.client.get(&url);
+        
+        if let Some(token) = &self.token {
+            request = request.header("Authorization", format!("token {}", token));
+        }
+        
+        request = request.header("User-Agent", "Code-Furnace/1.0");
+        
+        let response = send_rate_limited("Gitea", request).await?;
+        
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!("Gitea API error: {}", response.status()));
+        }
+        
+        Ok(response.text().await?)
+    }
+    
+    /// Requests page after page until `X-Total-Count` items have arrived, or
+    /// a short page shows the list has ended, collecting every item.
+    async fn make_paginated_request<T>(&self, endpoint: &str) -> Result<Vec<T>>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        let mut items = Vec::new();
+        let mut page = 1;
+        
+        loop {
+            let url = format!("{}/{}", self.base_url, with_query(endpoint, &format!("limit={}&page={}", PAGE_SIZE, page)));
+            let mut request = self.client.get(&url);
+            
+            if let Some(token) = &self.token {
+                request = request.header("Authorization", format!("token {}", token));
+            }
+            
+            let response = send_rate_limited("Gitea", request.header("User-Agent", "Code-Furnace/1.0")).await?;
+            
+            if !response.status().is_success() {
+                return Err(anyhow::anyhow!("Gitea API error: {}", response.status()));
+            }
+            
+            let total = response
+                .headers()
+                .get("X-Total-Count")
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.trim().parse::<usize>().ok());
+            let batch: Vec<T> = response.json().await?;
+            let batch_len = batch.len();
+            items.extend(batch);
+            
+            let finished = match total {
+                Some(total) => batch_len == 0 || items.len() >= total,
+                None => batch_len < PAGE_SIZE,
+            };
+            if finished {
+                break;
+            }
+            if page >= self.max_pages {
+                tracing::warn!("Stopped listing {} after {} pages", endpoint, page);
+                break;
+            }
+            page += 1;
+        }
+        
+        Ok(items)
+    }
+    
+    /// Sends `body` without checking the response status.
+    async fn send_json<B>(&self, method: reqwest::Method, endpoint: &str, body: &B) -> Result<reqwest::Response>
+    where
+        B: Serialize,
+    {
+        let url = format!("{}/{}", self.base_url, endpoint);
+        let mut request = self.client.request(method, &url);
+        
+        if let Some(token) = &self.token {
+            request = request.header("Authorization", format!("token {}", token));
+        }
+        
+        request = request
+            .header("User-Agent", "Code-Furnace/1.0")
+            .header("Content-Type", "application/json")
+            .json(body);
+        
+        send_rate_limited("Gitea", request).await
+    }
+    
     pub async fn get_repository(&self, owner: &str, repo: &str) -> Result<GiteaRepository> {
         let endpoint = format!("repos/{}/{}", owner, repo);
         self.make_request(&endpoint).await
@@ -213,13 +328,26 @@ impl GiteaClient {
         repo: &str,
         state: Option<&str>
     ) -> Result<Vec<GiteaPullRequest>> {
+        self.make_request(&Self::pull_requests_endpoint(owner, repo, state)).await
+    }
+    
+    pub async fn list_all_pull_requests(
+        &self,
+        owner: &str,
+        repo: &str,
+        state: Option<&str>
+    ) -> Result<Vec<GiteaPullRequest>> {
+        self.make_paginated_request(&Self::pull_requests_endpoint(owner, repo, state)).await
+    }
+    
+    fn pull_requests_endpoint(owner: &str, repo: &str, state: Option<&str>) -> String {
         let mut endpoint = format!("repos/{}/{}/pulls", owner, repo);
         
         if let Some(state) = state {
             endpoint.push_str(&format!("?state={}", state));
         }
         
-        self.make_request(&endpoint).await
+        endpoint
     }
     
     pub async fn get_pull_request(&self, owner: &str, repo: &str, number: u32) -> Result<GiteaPullRequest> {
@@ -227,6 +355,12 @@ impl GiteaClient {
         self.make_request(&endpoint).await
     }
     
+    /// The pull request's changes as unified diff text.
+    pub async fn get_pull_request_diff(&self, owner: &str, repo: &str, number: u32) -> Result<String> {
+        let endpoint = format!("repos/{}/{}/pulls/{}.diff", owner, repo, number);
+        self.make_text_request(&endpoint).await
+    }
+    
     pub async fn create_pull_request(
         &self,
         owner: &str,
@@ -250,6 +384,39 @@ impl GiteaClient {
         self.make_post_request(&endpoint, &pr_data).await
     }
     
+    /// Gitea answers a merge with no body, so the merged pull request is
+    /// fetched afterwards. Fails with `ForgeError::NotMergeable` or
+    /// `ForgeError::MergeConflict` when Gitea refuses the merge.
+    pub async fn merge_pull_request(
+        &self,
+        owner: &str,
+        repo: &str,
+        number: u32,
+        method: ForgeMergeMethod,
+        commit_message: Option<&str>
+    ) -> Result<GiteaPullRequest> {
+        let endpoint = format!("repos/{}/{}/pulls/{}/merge", owner, repo, number);
+        
+        let mut merge_data = HashMap::new();
+        merge_data.insert("Do", method.as_str());
+        
+        if let Some(commit_message) = commit_message {
+            let (title, body) = split_commit_message(commit_message);
+            merge_data.insert("MergeTitleField", title);
+            if let Some(body) = body {
+                merge_data.insert("MergeMessageField", body);
+            }
+        }
+        
+        let response = self.send_json(reqwest::Method::POST, &endpoint, &merge_data).await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(merge_error("Gitea", status, &response.text().await.unwrap_or_default()));
+        }
+        
+        self.get_pull_request(owner, repo, number).await
+    }
+    
     pub async fn list_issues(
         &self,
         owner: &str,
@@ -257,6 +424,20 @@ impl GiteaClient {
         state: Option<&str>,
         labels: Option<&str>
     ) -> Result<Vec<GiteaIssue>> {
+        self.make_request(&Self::issues_endpoint(owner, repo, state, labels)).await
+    }
+    
+    pub async fn list_all_issues(
+        &self,
+        owner: &str,
+        repo: &str,
+        state: Option<&str>,
+        labels: Option<&str>
+    ) -> Result<Vec<GiteaIssue>> {
+        self.make_paginated_request(&Self::issues_endpoint(owner, repo, state, labels)).await
+    }
+    
+    fn issues_endpoint(owner: &str, repo: &str, state: Option<&str>, labels: Option<&str>) -> String {
         let mut endpoint = format!("repos/{}/{}/issues", owner, repo);
         let mut params = Vec::new();
         
@@ -273,7 +454,7 @@ impl GiteaClient {
             endpoint.push_str(&params.join("&"));
         }
         
-        self.make_request(&endpoint).await
+        endpoint
     }
     
     pub async fn get_issue(&self, owner: &str, repo: &str, number: u32) -> Result<GiteaIssue> {
@@ -314,11 +495,31 @@ impl GiteaClient {
         self.make_post_request(&endpoint, &issue_data).await
     }
     
+    pub async fn create_issue_comment(&self, owner: &str, repo: &str, number: u32, body: &str) -> Result<GiteaComment> {
+        let endpoint = format!("repos/{}/{}/issues/{}/comments", owner, repo, number);
+        
+        let mut comment_data = HashMap::new();
+        comment_data.insert("body", body);
+        
+        self.make_post_request(&endpoint, &comment_data).await
+    }
+    
+    /// Comments on the pull request's conversation, which Gitea keeps with
+    /// its issue comments.
+    pub async fn create_pr_review_comment(&self, owner: &str, repo: &str, number: u32, body: &str) -> Result<GiteaComment> {
+        self.create_issue_comment(owner, repo, number, body).await
+    }
+    
     pub async fn list_releases(&self, owner: &str, repo: &str) -> Result<Vec<GiteaRelease>> {
         let endpoint = format!("repos/{}/{}/releases", owner, repo);
         self.make_request(&endpoint).await
     }
     
+    pub async fn list_all_releases(&self, owner: &str, repo: &str) -> Result<Vec<GiteaRelease>> {
+        let endpoint = format!("repos/{}/{}/releases", owner, repo);
+        self.make_paginated_request(&endpoint).await
+    }
+    
     pub async fn get_latest_release(&self, owner: &str, repo: &str) -> Result<GiteaRelease> {
         let endpoint = format!("repos/{}/{}/releases/latest", owner, repo);
         self.make_request(&endpoint).await
@@ -3,6 +3,10 @@ use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use super::forge::{
+    merge_error, next_link, send_rate_limited, split_commit_message, with_query, ForgeMergeMethod, DEFAULT_MAX_PAGES,
+};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GitHubRepository {
     pub id: u64,
@@ -44,6 +48,13 @@ pub struct GitHubPullRequest {
     pub changed_files: u32,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitHubMergeResult {
+    pub sha: Option<String>,
+    pub merged: bool,
+    pub message: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GitHubIssue {
     pub id: u64,
@@ -61,6 +72,16 @@ pub struct GitHubIssue {
     pub comments: u32,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitHubComment {
+    pub id: u64,
+    pub body: String,
+    pub html_url: String,
+    pub user: GitHubUser,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GitHubUser {
     pub id: u64,
@@ -126,6 +147,7 @@ pub struct GitHubClient {
     client: Client,
     token: Option<String>,
     base_url: String,
+    max_pages: u32,
 }
 
 impl GitHubClient {
@@ -134,6 +156,7 @@ impl GitHubClient {
             client: Client::new(),
             token,
             base_url: "https://api.github.com".to_string(),
+            max_pages: DEFAULT_MAX_PAGES,
         }
     }
     
@@ -142,9 +165,16 @@ impl GitHubClient {
             client: Client::new(),
             token,
             base_url,
+            max_pages: DEFAULT_MAX_PAGES,
         }
     }
     
+    /// Caps how many pages the `list_all_*` methods fetch.
+    pub fn with_max_pages(mut self, max_pages: u32) -> Self {
+        self.max_pages = max_pages;
+        self
+    }
+    
     async fn make_request<T>(&self, endpoint: &str) -> Result<T>
     where
         T: for<'de> Deserialize<'de>,
@@ -158,7 +188,7 @@ impl GitHubClient {
         
         request = request.header("User-Agent", "Code-Furnace/1.0");
         
-        let response = request.send().await?;
+        let response = send_rate_limited("GitHub", request).await?;
         
         if !response.status().is_success() {
             return Err(anyhow::anyhow!("GitHub API error: {}", response.status()));
@@ -185,7 +215,7 @@ impl GitHubClient {
             .header("Content-Type", "application/json")
             .json(body);
         
-        let response = request.send().await?;
+        let response = send_rate_limited("GitHub", request).await?;
         
         if !response.status().is_success() {
             return Err(anyhow::anyhow!("GitHub API error: {}", response.status()));
@@ -195,6 +225,82 @@ impl GitHubClient {
         Ok(json)
     }
     
+    /// Fetches `endpoint` as text in the media type given by `accept`.
+    async fn make_text_request(&self, endpoint: &str, accept: &str) -> Result<String> {
+        let url = format!("{}/{}", self.base_url, endpoint);
+        let mut request = self.client.get(&url);
+        
+        if let Some(token) = &self.token {
+            request = request.header("Authorization", format!("token {}", token));
+        }
+        
+        request = request.header("User-Agent", "Code-Furnace/1.0").header("Accept", accept);
+        
+        let response = send_rate_limited("GitHub", request).await?;
+        
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!("GitHub API error: {}", response.status()));
+        }
+        
+        Ok(response.text().await?)
+    }
+    
+    /// Follows the `Link` header from page to page, collecting every item.
+    async fn make_paginated_request<T>(&self, endpoint: &str) -> Result<Vec<T>>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        let mut next_url = Some(format!("{}/{}", self.base_url, with_query(endpoint, "per_page=100")));
+        let mut items = Vec::new();
+        let mut pages = 0;
+        
+        while let Some(url) = next_url.take() {
+            let mut request = self.client.get(&url);
+            
+            if let Some(token) = &self.token {
+                request = request.header("Authorization", format!("token {}", token));
+            }
+            
+            let response = send_rate_limited("GitHub", request.header("User-Agent", "Code-Furnace/1.0")).await?;
+            
+            if !response.status().is_success() {
+                return Err(anyhow::anyhow!("GitHub API error: {}", response.status()));
+            }
+            
+            next_url = next_link(response.headers());
+            let page: Vec<T> = response.json().await?;
+            items.extend(page);
+            pages += 1;
+            
+            if pages >= self.max_pages && next_url.is_some() {
+                tracing::warn!("Stopped listing {} after {} pages", endpoint, pages);
+                break;
+            }
+        }
+        
+        Ok(items)
+    }
+    
+    /// Sends `body` without checking the response status.
+    async fn send_json<B>(&self, method: reqwest::Method, endpoint: &str, body: &B) -> Result<reqwest::Response>
+    where
+        B: Serialize,
+    {
+        let url = format!("{}/{}", self.base_url, endpoint);
+        let mut request = self.client.request(method, &url);
+        
+        if let Some(token) = &self.token {
+            request = request.header("Authorization", format!("token {}", token));
+        }
+        
+        request = request
+            .header("User-Agent", "Code-Furnace/1.0")
+            .header("Content-Type", "application/json")
+            .json(body);
+        
+        send_rate_limited("GitHub", request).await
+    }
+    
     pub async fn get_repository(&self, owner: &str, repo: &str) -> Result<GitHubRepository> {
         let endpoint = format!("repos/{}/{}", owner, repo);
         self.make_request(&endpoint).await
@@ -207,6 +313,20 @@ impl GitHubClient {
         state: Option<&str>, 
         base: Option<&str>
     ) -> Result<Vec<GitHubPullRequest>> {
+        self.make_request(&Self::pull_requests_endpoint(owner, repo, state, base)).await
+    }
+    
+    pub async fn list_all_pull_requests(
+        &self, 
+        owner: &str, 
+        repo: &str, 
+        state: Option<&str>, 
+        base: Option<&str>
+    ) -> Result<Vec<GitHubPullRequest>> {
+        self.make_paginated_request(&Self::pull_requests_endpoint(owner, repo, state, base)).await
+    }
+    
+    fn pull_requests_endpoint(owner: &str, repo: &str, state: Option<&str>, base: Option<&str>) -> String {
         let mut endpoint = format!("repos/{}/{}/pulls", owner, repo);
         let mut params = Vec::new();
         
@@ -223,7 +343,7 @@ impl GitHubClient {
             endpoint.push_str(&params.join("&"));
         }
         
-        self.make_request(&endpoint).await
+        endpoint
     }
     
     pub async fn get_pull_request(&self, owner: &str, repo: &str, number: u32) -> Result<GitHubPullRequest> {
@@ -231,6 +351,12 @@ impl GitHubClient {
         self.make_request(&endpoint).await
     }
     
+    /// The pull request's changes as unified diff text.
+    pub async fn get_pull_request_diff(&self, owner: &str, repo: &str, number: u32) -> Result<String> {
+        let endpoint = format!("repos/{}/{}/pulls/{}", owner, repo, number);
+        self.make_text_request(&endpoint, "application/vnd.github.v3.diff").await
+    }
+    
     pub async fn create_pull_request(
         &self, 
         owner: &str, 
@@ -259,6 +385,38 @@ impl GitHubClient {
         self.make_post_request(&endpoint, &pr_data).await
     }
     
+    /// Fails with `ForgeError::NotMergeable` or `ForgeError::MergeConflict`
+    /// when GitHub refuses the merge.
+    pub async fn merge_pull_request(
+        &self,
+        owner: &str,
+        repo: &str,
+        number: u32,
+        method: ForgeMergeMethod,
+        commit_message: Option<&str>
+    ) -> Result<GitHubMergeResult> {
+        let endpoint = format!("repos/{}/{}/pulls/{}/merge", owner, repo, number);
+        
+        let mut merge_data = HashMap::new();
+        merge_data.insert("merge_method", method.as_str());
+        
+        if let Some(commit_message) = commit_message {
+            let (title, body) = split_commit_message(commit_message);
+            merge_data.insert("commit_title", title);
+            if let Some(body) = body {
+                merge_data.insert("commit_message", body);
+            }
+        }
+        
+        let response = self.send_json(reqwest::Method::PUT, &endpoint, &merge_data).await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(merge_error("GitHub", status, &response.text().await.unwrap_or_default()));
+        }
+        
+        Ok(response.json().await?)
+    }
+    
     pub async fn list_issues(
         &self, 
         owner: &str, 
@@ -266,6 +424,20 @@ impl GitHubClient {
         state: Option<&str>,
         labels: Option<&str>
     ) -> Result<Vec<GitHubIssue>> {
+        self.make_request(&Self::issues_endpoint(owner, repo, state, labels)).await
+    }
+    
+    pub async fn list_all_issues(
+        &self, 
+        owner: &str, 
+        repo: &str, 
+        state: Option<&str>,
+        labels: Option<&str>
+    ) -> Result<Vec<GitHubIssue>> {
+        self.make_paginated_request(&Self::issues_endpoint(owner, repo, state, labels)).await
+    }
+    
+    fn issues_endpoint(owner: &str, repo: &str, state: Option<&str>, labels: Option<&str>) -> String {
         let mut endpoint = format!("repos/{}/{}/issues", owner, repo);
         let mut params = Vec::new();
         
@@ -282,7 +454,7 @@ impl GitHubClient {
             endpoint.push_str(&params.join("&"));
         }
         
-        self.make_request(&endpoint).await
+        endpoint
     }
     
     pub async fn get_issue(&self, owner: &str, repo: &str, number: u32) -> Result<GitHubIssue> {
@@ -323,6 +495,21 @@ impl GitHubClient {
         self.make_post_request(&endpoint, &issue_data).await
     }
     
+    pub async fn create_issue_comment(&self, owner: &str, repo: &str, number: u32, body: &str) -> Result<GitHubComment> {
+        let endpoint = format!("repos/{}/{}/issues/{}/comments", owner, repo, number);
+        
+        let mut comment_data = HashMap::new();
+        comment_data.insert("body", body);
+        
+        self.make_post_request(&endpoint, &comment_data).await
+    }
+    
+    /// Comments on the pull request's conversation, which GitHub keeps with
+    /// its issue comments.
+    pub async fn create_pr_review_comment(&self, owner: &str, repo: &str, number: u32, body: &str) -> Result<GitHubComment> {
+        self.create_issue_comment(owner, repo, number, body).await
+    }
+    
     pub async fn list_workflow_runs(&self, owner: &str, repo: &str, branch: Option<&str>) -> Result<Vec<GitHubWorkflowRun>> {
         let mut endpoint = format!("repos/{}/{}/actions/runs", owner, repo);
         
@@ -344,6 +531,11 @@ impl GitHubClient {
         self.make_request(&endpoint).await
     }
     
+    pub async fn list_all_releases(&self, owner: &str, repo: &str) -> Result<Vec<GitHubRelease>> {
+        let endpoint = format!("repos/{}/{}/releases", owner, repo);
+        self.make_paginated_request(&endpoint).await
+    }
+    
     pub async fn get_latest_release(&self, owner: &str, repo: &str) -> Result<GitHubRelease> {
         let endpoint = format!("repos/{}/{}/releases/latest", owner, repo);
         self.make_request(&endpoint).await
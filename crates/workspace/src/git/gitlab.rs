@@ -3,6 +3,8 @@ use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use super::forge::{merge_error, send_rate_limited, with_query, ForgeError, ForgeMergeMethod, DEFAULT_MAX_PAGES};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GitLabProject {
     pub id: u64,
@@ -39,12 +41,56 @@ pub struct GitLabMergeRequest {
     pub updated_at: String,
     pub merged_at: Option<String>,
     pub merge_status: String,
+    #[serde(default)]
+    pub merge_commit_sha: Option<String>,
+    #[serde(default)]
+    pub squash_commit_sha: Option<String>,
     pub user_notes_count: u32,
     pub upvotes: u32,
     pub downvotes: u32,
     pub changes_count: String,
 }
 
+/// One file of a merge request's `/changes`. `diff` holds only the hunks.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitLabChange {
+    pub old_path: String,
+    pub new_path: String,
+    pub new_file: bool,
+    pub renamed_file: bool,
+    pub deleted_file: bool,
+    pub diff: String,
+}
+
+impl GitLabChange {
+    /// The change as a `git diff` section, with the file headers GitLab
+    /// leaves out. Empty for files without a textual change.
+    pub fn to_patch(&self) -> String {
+        if self.diff.is_empty() && !self.new_file && !self.deleted_file && !self.renamed_file {
+            return String::new();
+        }
+        
+        let mut patch = format!("diff --git a/{} b/{}\n", self.old_path, self.new_path);
+        if self.new_file {
+            patch.push_str("new file mode 100644\n");
+        } else if self.deleted_file {
+            patch.push_str("deleted file mode 100644\n");
+        } else if self.renamed_file {
+            patch.push_str(&format!("rename from {}\nrename to {}\n", self.old_path, self.new_path));
+        }
+        
+        if !self.diff.is_empty() {
+            let old = if self.new_file { "/dev/null".to_string() } else { format!("a/{}", self.old_path) };
+            let new = if self.deleted_file { "/dev/null".to_string() } else { format!("b/{}", self.new_path) };
+            patch.push_str(&format!("--- {}\n+++ {}\n{}", old, new, self.diff));
+            if !self.diff.ends_with('\n') {
+                patch.push('\n');
+            }
+        }
+        patch
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GitLabIssue {
     pub id: u64,
@@ -64,6 +110,15 @@ pub struct GitLabIssue {
     pub downvotes: u32,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitLabNote {
+    pub id: u64,
+    pub body: String,
+    pub author: GitLabUser,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GitLabUser {
     pub id: u64,
@@ -139,6 +194,7 @@ pub struct GitLabClient {
     client: Client,
     token: Option<String>,
     base_url: String,
+    max_pages: u32,
 }
 
 impl GitLabClient {
@@ -147,6 +203,7 @@ impl GitLabClient {
             client: Client::new(),
             token,
             base_url: "https://gitlab.com/api/v4".to_string(),
+            max_pages: DEFAULT_MAX_PAGES,
         }
     }
     
@@ -161,9 +218,16 @@ impl GitLabClient {
             client: Client::new(),
             token,
             base_url: api_url,
+            max_pages: DEFAULT_MAX_PAGES,
         }
     }
     
+    /// Caps how many pages the `list_all_*` methods fetch.
+    pub fn with_max_pages(mut self, max_pages: u32) -> Self {
+        self.max_pages = max_pages;
+        self
+    }
+    
     async fn make_request<T>(&self, endpoint: &str) -> Result<T>
     where
         T: for<'de> Deserialize<'de>,
@@ -177,7 +241,7 @@ impl GitLabClient {
         
         request = request.header("User-Agent", "Code-Furnace/1.0");
         
-        let response = request.send().await?;
+        let response = send_rate_limited("GitLab", request).await?;
         
         if !response.status().is_success() {
             return Err(anyhow::anyhow!("GitLab API error: {}", response.status()));
@@ -204,7 +268,7 @@ impl GitLabClient {
             .header("Content-Type", "application/json")
             .json(body);
         
-        let response = request.send().await?;
+        let response = send_rate_limited("GitLab", request).await?;
         
         if !response.status().is_success() {
             return Err(anyhow::anyhow!("GitLab API error: {}", response.status()));
@@ -214,6 +278,68 @@ impl GitLabClient {
         Ok(json)
     }
     
+    /// Requests page after page until `X-Next-Page` comes back empty,
+    /// collecting every item.
+    async fn make_paginated_request<T>(&self, endpoint: &str) -> Result<Vec<T>>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        let mut next_page = Some(1);
+        let mut items = Vec::new();
+        let mut pages = 0;
+        
+        while let Some(page) = next_page.take() {
+            let url = format!("{}/{}", self.base_url, with_query(endpoint, &format!("per_page=100&page={}", page)));
+            let mut request = self.client.get(&url);
+            
+            if let Some(token) = &self.token {
+                request = request.header("Private-Token", token);
+            }
+            
+            let response = send_rate_limited("GitLab", request.header("User-Agent", "Code-Furnace/1.0")).await?;
+            
+            if !response.status().is_success() {
+                return Err(anyhow::anyhow!("GitLab API error: {}", response.status()));
+            }
+            
+            next_page = response
+                .headers()
+                .get("X-Next-Page")
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.trim().parse::<u32>().ok());
+            let page: Vec<T> = response.json().await?;
+            items.extend(page);
+            pages += 1;
+            
+            if pages >= self.max_pages && next_page.is_some() {
+                tracing::warn!("Stopped listing {} after {} pages", endpoint, pages);
+                break;
+            }
+        }
+        
+        Ok(items)
+    }
+    
+    /// Sends `body` without checking the response status.
+    async fn send_json<B>(&self, method: reqwest::Method, endpoint: &str, body: &B) -> Result<reqwest::Response>
+    where
+        B: Serialize,
+    {
+        let url = format!("{}/{}", self.base_url, endpoint);
+        let mut request = self.client.request(method, &url);
+        
+        if let Some(token) = &self.token {
+            request = request.header("Private-Token", token);
+        }
+        
+        request = request
+            .header("User-Agent", "Code-Furnace/1.0")
+            .header("Content-Type", "application/json")
+            .json(body);
+        
+        send_rate_limited("GitLab", request).await
+    }
+    
     pub async fn get_project(&self, project_id: &str) -> Result<GitLabProject> {
         let endpoint = format!("projects/{}", urlencoding::encode(project_id));
         self.make_request(&endpoint).await
@@ -225,6 +351,19 @@ impl GitLabClient {
         state: Option<&str>,
         target_branch: Option<&str>
     ) -> Result<Vec<GitLabMergeRequest>> {
+        self.make_request(&Self::merge_requests_endpoint(project_id, state, target_branch)).await
+    }
+    
+    pub async fn list_all_merge_requests(
+        &self,
+        project_id: &str,
+        state: Option<&str>,
+        target_branch: Option<&str>
+    ) -> Result<Vec<GitLabMergeRequest>> {
+        self.make_paginated_request(&Self::merge_requests_endpoint(project_id, state, target_branch)).await
+    }
+    
+    fn merge_requests_endpoint(project_id: &str, state: Option<&str>, target_branch: Option<&str>) -> String {
         let mut endpoint = format!("projects/{}/merge_requests", urlencoding::encode(project_id));
         let mut params = Vec::new();
         
@@ -241,7 +380,7 @@ impl GitLabClient {
             endpoint.push_str(&params.join("&"));
         }
         
-        self.make_request(&endpoint).await
+        endpoint
     }
     
     pub async fn get_merge_request(&self, project_id: &str, mr_iid: u32) -> Result<GitLabMergeRequest> {
@@ -249,6 +388,20 @@ impl GitLabClient {
         self.make_request(&endpoint).await
     }
     
+    /// The merge request's changes as unified diff text, assembled from
+    /// its `/changes`.
+    pub async fn get_merge_request_diff(&self, project_id: &str, mr_iid: u32) -> Result<String> {
+        let endpoint = format!("projects/{}/merge_requests/{}/changes", urlencoding::encode(project_id), mr_iid);
+        
+        #[derive(Deserialize)]
+        struct MergeRequestChanges {
+            changes: Vec<GitLabChange>,
+        }
+        
+        let response: MergeRequestChanges = self.make_request(&endpoint).await?;
+        Ok(response.changes.iter().map(GitLabChange::to_patch).collect())
+    }
+    
     pub async fn create_merge_request(
         &self,
         project_id: &str,
@@ -282,12 +435,62 @@ impl GitLabClient {
         self.make_post_request(&endpoint, &mr_data).await
     }
     
+    /// Merges with a merge commit or squashed. Whether GitLab rebases is a
+    /// project setting, so `ForgeMergeMethod::Rebase` is refused with
+    /// `ForgeError::UnsupportedMergeMethod`.
+    pub async fn merge_merge_request(
+        &self,
+        project_id: &str,
+        mr_iid: u32,
+        method: ForgeMergeMethod,
+        commit_message: Option<&str>
+    ) -> Result<GitLabMergeRequest> {
+        if method == ForgeMergeMethod::Rebase {
+            return Err(ForgeError::UnsupportedMergeMethod {
+                platform: "GitLab".to_string(),
+                method,
+            }
+            .into());
+        }
+        let endpoint = format!("projects/{}/merge_requests/{}/merge", urlencoding::encode(project_id), mr_iid);
+        
+        let squash = method == ForgeMergeMethod::Squash;
+        let mut merge_data = HashMap::new();
+        merge_data.insert("squash", serde_json::Value::Bool(squash));
+        
+        if let Some(commit_message) = commit_message {
+            let field = if squash { "squash_commit_message" } else { "merge_commit_message" };
+            merge_data.insert(field, serde_json::Value::String(commit_message.to_string()));
+        }
+        
+        let response = self.send_json(reqwest::Method::PUT, &endpoint, &merge_data).await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(merge_error("GitLab", status, &response.text().await.unwrap_or_default()));
+        }
+        
+        Ok(response.json().await?)
+    }
+    
     pub async fn list_issues(
         &self,
         project_id: &str,
         state: Option<&str>,
         labels: Option<&str>
     ) -> Result<Vec<GitLabIssue>> {
+        self.make_request(&Self::issues_endpoint(project_id, state, labels)).await
+    }
+    
+    pub async fn list_all_issues(
+        &self,
+        project_id: &str,
+        state: Option<&str>,
+        labels: Option<&str>
+    ) -> Result<Vec<GitLabIssue>> {
+        self.make_paginated_request(&Self::issues_endpoint(project_id, state, labels)).await
+    }
+    
+    fn issues_endpoint(project_id: &str, state: Option<&str>, labels: Option<&str>) -> String {
         let mut endpoint = format!("projects/{}/issues", urlencoding::encode(project_id));
         let mut params = Vec::new();
         
@@ -304,7 +507,7 @@ impl GitLabClient {
             endpoint.push_str(&params.join("&"));
         }
         
-        self.make_request(&endpoint).await
+        endpoint
     }
     
     pub async fn get_issue(&self, project_id: &str, issue_iid: u32) -> Result<GitLabIssue> {
@@ -342,6 +545,24 @@ impl GitLabClient {
         self.make_post_request(&endpoint, &issue_data).await
     }
     
+    pub async fn create_issue_comment(&self, project_id: &str, issue_iid: u32, body: &str) -> Result<GitLabNote> {
+        let endpoint = format!("projects/{}/issues/{}/notes", urlencoding::encode(project_id), issue_iid);
+        
+        let mut note_data = HashMap::new();
+        note_data.insert("body", body);
+        
+        self.make_post_request(&endpoint, &note_data).await
+    }
+    
+    pub async fn create_pr_review_comment(&self, project_id: &str, mr_iid: u32, body: &str) -> Result<GitLabNote> {
+        let endpoint = format!("projects/{}/merge_requests/{}/notes", urlencoding::encode(project_id), mr_iid);
+        
+        let mut note_data = HashMap::new();
+        note_data.insert("body", body);
+        
+        self.make_post_request(&endpoint, &note_data).await
+    }
+    
     pub async fn list_pipelines(&self, project_id: &str, ref_name: Option<&str>) -> Result<Vec<GitLabPipeline>> {
         let mut endpoint = format!("projects/{}/pipelines", urlencoding::encode(project_id));
         
@@ -372,6 +593,11 @@ impl GitLabClient {
         self.make_request(&endpoint).await
     }
     
+    pub async fn list_all_releases(&self, project_id: &str) -> Result<Vec<GitLabRelease>> {
+        let endpoint = format!("projects/{}/releases", urlencoding::encode(project_id));
+        self.make_paginated_request(&endpoint).await
+    }
+    
     pub async fn get_release(&self, project_id: &str, tag_name: &str) -> Result<GitLabRelease> {
         let endpoint = format!("projects/{}/releases/{}", urlencoding::encode(project_id), urlencoding::encode(tag_name));
         self.make_request(&endpoint).await
@@ -0,0 +1,154 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use crate::{BackgroundProcess, ProcessStatus};
+
+/// Longest a single connection attempt or request may take.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How to tell that a started process is ready, checked against its port.
+/// Until the check passes the process stays `Starting`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthCheck {
+    /// Requested with `GET http://localhost:{port}{path}`. Without a path,
+    /// accepting a TCP connection is enough.
+    #[serde(default)]
+    pub path: Option<String>,
+    /// Status the request must answer with; any 2xx when unset.
+    #[serde(default)]
+    pub expected_status: Option<u16>,
+    #[serde(default = "default_interval_ms")]
+    pub interval_ms: u64,
+    /// Time after starting before the process is given up on as unhealthy.
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+fn default_interval_ms() -> u64 {
+    500
+}
+
+fn default_timeout_ms() -> u64 {
+    30_000
+}
+
+impl Default for HealthCheck {
+    fn default() -> Self {
+        Self {
+            path: None,
+            expected_status: None,
+            interval_ms: default_interval_ms(),
+            timeout_ms: default_timeout_ms(),
+        }
+    }
+}
+
+impl HealthCheck {
+    /// Checks once whether something healthy answers on `port`.
+    pub async fn probe(&self, port: u16) -> Result<()> {
+        let Some(path) = &self.path else {
+            tokio::time::timeout(PROBE_TIMEOUT, tokio::net::TcpStream::connect(("localhost", port))).await??;
+            return Ok(());
+        };
+        
+        let url = format!("http://localhost:{}{}", port, path);
+        let status = reqwest::Client::new()
+            .get(&url)
+            .timeout(PROBE_TIMEOUT)
+            .send()
+            .await?
+            .status();
+        let healthy = match self.expected_status {
+            Some(expected) => status.as_u16() == expected,
+            None => status.is_success(),
+        };
+        if !healthy {
+            return Err(anyhow::anyhow!("{} answered {}", url, status));
+        }
+        Ok(())
+    }
+}
+
+/// Probes a process launched at `launched_at` every `interval_ms` until the
+/// check passes, which marks it `Running` and publishes
+/// `workspace.process.ready`, or `timeout_ms` runs out, which marks it
+/// `Unhealthy` and publishes `workspace.process.unhealthy`; the process is
+/// left running for the user to stop or restart. Stops early once the
+/// process exits, is stopped or is launched again.
+pub(crate) async fn await_healthy(
+    process_id: Uuid,
+    launched_at: chrono::DateTime<chrono::Utc>,
+    port: u16,
+    check: HealthCheck,
+    background_processes: Arc<RwLock<HashMap<Uuid, BackgroundProcess>>>,
+    event_bus: code_furnace_events::EventBus,
+) {
+    let deadline = tokio::time::Instant::now() + Duration::from_millis(check.timeout_ms);
+    let interval = Duration::from_millis(check.interval_ms.max(1));
+    
+    // Updates the status only while this launch is still waiting on the check
+    let settle = |status: ProcessStatus| async {
+        let mut processes = background_processes.write().await;
+        match processes.get_mut(&process_id) {
+            Some(process) if process.started_at == launched_at && matches!(process.status, ProcessStatus::Starting) => {
+                process.status = status;
+                true
+            }
+            _ => false,
+        }
+    };
+    
+    let error = loop {
+        let still_starting = background_processes
+            .read()
+            .await
+            .get(&process_id)
+            .is_some_and(|process| process.started_at == launched_at && matches!(process.status, ProcessStatus::Starting));
+        if !still_starting {
+            return;
+        }
+        
+        let result = check.probe(port).await;
+        match result {
+            Ok(()) => {
+                if settle(ProcessStatus::Running).await {
+                    publish_ready(&event_bus, process_id, Some(port));
+                }
+                return;
+            }
+            Err(e) if tokio::time::Instant::now() + interval >= deadline => break e,
+            Err(_) => tokio::time::sleep(interval).await,
+        }
+    };
+    
+    if settle(ProcessStatus::Unhealthy).await {
+        tracing::warn!("Process {} did not become healthy on port {}: {}", process_id, port, error);
+        let event = code_furnace_events::Event::new(
+            "workspace.process.unhealthy",
+            "workspace-manager",
+            serde_json::json!({
+                "process_id": process_id,
+                "port": port,
+                "error": error.to_string()
+            }),
+        );
+        let _ = event_bus.publish(event);
+    }
+}
+
+pub(crate) fn publish_ready(event_bus: &code_furnace_events::EventBus, process_id: Uuid, port: Option<u16>) {
+    let event = code_furnace_events::Event::new(
+        "workspace.process.ready",
+        "workspace-manager",
+        serde_json::json!({
+            "process_id": process_id,
+            "port": port
+        }),
+    );
+    let _ = event_bus.publish(event);
+}
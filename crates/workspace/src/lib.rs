@@ -1,16 +1,31 @@
 use anyhow::Result;
+use code_furnace_utils::ResourceError;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use uuid::Uuid;
-use tokio::process::{Child, Command};
+use tokio::process::{Child, Command, ChildStderr, ChildStdout};
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
 use tokio::io::{BufReader, AsyncBufReadExt};
 use std::process::Stdio;
 
 pub mod git;
+pub mod health;
+pub mod metrics;
+mod process_group;
+pub mod process_logs;
+pub mod stack;
+pub mod tasks;
 pub use git::*;
+pub use health::*;
+pub use metrics::*;
+pub use process_logs::*;
+pub use stack::*;
+pub use tasks::*;
+use process_group::ProcessGroup;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Project {
@@ -19,8 +34,16 @@ pub struct Project {
     pub path: PathBuf,
     pub project_type: ProjectType,
     pub config: ProjectConfig,
+    /// Project files found at the root and in its immediate subfolders,
+    /// relative to `path`. The first one decided `project_type`.
+    #[serde(default)]
+    pub detected_markers: Vec<PathBuf>,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub last_opened: chrono::DateTime<chrono::Utc>,
+    /// The project folder no longer exists. Stale projects stay listed so
+    /// the user can remove them, but cannot be opened.
+    #[serde(default)]
+    pub stale: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -28,9 +51,31 @@ pub enum ProjectType {
     RustCargo,
     NodeJs,
     Python,
+    Go,
+    Deno,
+    JavaMaven,
+    Gradle,
     Generic,
 }
 
+/// Files that identify a project type. When several are in the same folder
+/// the first listed wins.
+const PROJECT_MARKERS: &[(&str, ProjectType)] = &[
+    ("Cargo.toml", ProjectType::RustCargo),
+    ("go.mod", ProjectType::Go),
+    ("deno.json", ProjectType::Deno),
+    ("deno.jsonc", ProjectType::Deno),
+    ("package.json", ProjectType::NodeJs),
+    ("pom.xml", ProjectType::JavaMaven),
+    ("build.gradle", ProjectType::Gradle),
+    ("build.gradle.kts", ProjectType::Gradle),
+    ("pyproject.toml", ProjectType::Python),
+    ("requirements.txt", ProjectType::Python),
+];
+
+/// Subfolders never searched for project markers.
+const IGNORED_MARKER_DIRS: &[&str] = &["node_modules", "target", "build", "dist", "vendor"];
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProjectConfig {
     pub dev_command: Option<String>,
@@ -38,8 +83,24 @@ pub struct ProjectConfig {
     pub test_command: Option<String>,
     pub env_vars: HashMap<String, String>,
     pub ports: Vec<u16>,
+    /// Named commands that can be started as background processes. The
+    /// single-command fields above show up here as `dev`, `build` and `test`.
+    #[serde(default)]
+    pub run_configs: Vec<RunConfig>,
+    /// Read `.env` and `.env.local` from the project folder when starting
+    /// its processes.
+    #[serde(default = "default_load_env_files")]
+    pub load_env_files: bool,
+}
+
+fn default_load_env_files() -> bool {
+    true
 }
 
+/// Environment files read from the project folder, later ones overriding
+/// earlier ones.
+const ENV_FILES: &[&str] = &[".env", ".env.local"];
+
 impl Default for ProjectConfig {
     fn default() -> Self {
         Self {
@@ -48,14 +109,89 @@ impl Default for ProjectConfig {
             test_command: None,
             env_vars: HashMap::new(),
             ports: Vec::new(),
+            run_configs: Vec::new(),
+            load_env_files: true,
+        }
+    }
+}
+
+impl ProjectConfig {
+    /// Adds a run configuration for each single-command field that has no
+    /// configuration of the same name yet.
+    pub fn populate_run_configs(&mut self) {
+        let legacy = [
+            ("dev", &self.dev_command),
+            ("build", &self.build_command),
+            ("test", &self.test_command),
+        ];
+        for (name, command_line) in legacy {
+            if self.run_configs.iter().any(|config| config.name == name) {
+                continue;
+            }
+            if let Some(config) = command_line.as_deref().and_then(|line| RunConfig::from_command_line(name, line)) {
+                self.run_configs.push(config);
+            }
         }
     }
+    
+    pub fn run_config(&self, name: &str) -> Option<&RunConfig> {
+        self.run_configs.iter().find(|config| config.name == name)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunConfig {
+    pub name: String,
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Added on top of the project's `env_vars`.
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// Relative to the project folder, which is used when unset.
+    #[serde(default)]
+    pub cwd: Option<PathBuf>,
+    /// Port the process serves on, which `health_check` probes.
+    #[serde(default)]
+    pub port: Option<u16>,
+    #[serde(default)]
+    pub health_check: Option<HealthCheck>,
+    /// Names of the configurations that must be ready before this one
+    /// starts as part of the project stack.
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+}
+
+impl RunConfig {
+    /// Splits a command line such as `npm run dev` on whitespace. Returns
+    /// None for a blank line.
+    pub fn from_command_line(name: &str, command_line: &str) -> Option<Self> {
+        let mut parts = command_line.split_whitespace().map(str::to_string);
+        Some(Self {
+            name: name.to_string(),
+            command: parts.next()?,
+            args: parts.collect(),
+            env: HashMap::new(),
+            cwd: None,
+            port: None,
+            health_check: None,
+            depends_on: Vec::new(),
+        })
+    }
 }
 
 impl Project {
     pub fn new(name: String, path: PathBuf) -> Self {
-        let project_type = Self::detect_project_type(&path);
-        let config = Self::generate_default_config(&project_type, &path);
+        let detected_markers = Self::detect_markers(&path);
+        let (project_type, project_dir) = match detected_markers.first() {
+            Some(marker) => (
+                Self::marker_type(marker),
+                marker.parent().map(PathBuf::from).unwrap_or_default(),
+            ),
+            None => (ProjectType::Generic, PathBuf::new()),
+        };
+        let config = Self::generate_default_config(&project_type, &path.join(&project_dir));
+        let config = Self::relocate_run_configs(config, &project_dir);
         
         let now = chrono::Utc::now();
         Self {
@@ -64,21 +200,63 @@ impl Project {
             path,
             project_type,
             config,
+            detected_markers,
             created_at: now,
             last_opened: now,
+            stale: false,
         }
     }
     
-    fn detect_project_type(path: &PathBuf) -> ProjectType {
-        if path.join("Cargo.toml").exists() {
-            ProjectType::RustCargo
-        } else if path.join("package.json").exists() {
-            ProjectType::NodeJs
-        } else if path.join("requirements.txt").exists() || path.join("pyproject.toml").exists() {
-            ProjectType::Python
-        } else {
-            ProjectType::Generic
+    fn marker_type(marker: &Path) -> ProjectType {
+        let file_name = marker.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+        PROJECT_MARKERS
+            .iter()
+            .find(|(marker_name, _)| *marker_name == file_name)
+            .map(|(_, project_type)| project_type.clone())
+            .unwrap_or(ProjectType::Generic)
+    }
+    
+    /// Markers at the root of `path` come first, then those of its
+    /// subfolders by name, so a monorepo is typed by its top-level build.
+    fn detect_markers(path: &Path) -> Vec<PathBuf> {
+        let markers_in = |dir: &Path| -> Vec<PathBuf> {
+            PROJECT_MARKERS
+                .iter()
+                .map(|(marker_name, _)| PathBuf::from(marker_name))
+                .filter(|marker| path.join(dir).join(marker).is_file())
+                .map(|marker| dir.join(marker))
+                .collect()
+        };
+        
+        let mut markers = markers_in(Path::new(""));
+        let mut subdirs: Vec<PathBuf> = std::fs::read_dir(path)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok())
+                    .filter(|entry| entry.file_type().map(|kind| kind.is_dir()).unwrap_or(false))
+                    .map(|entry| PathBuf::from(entry.file_name()))
+                    .filter(|dir| {
+                        let name = dir.to_string_lossy();
+                        !name.starts_with('.') && !IGNORED_MARKER_DIRS.contains(&name.as_ref())
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        subdirs.sort();
+        for dir in subdirs {
+            markers.extend(markers_in(&dir));
         }
+        markers
+    }
+    
+    /// Runs the default commands of a project found in a subfolder there.
+    fn relocate_run_configs(mut config: ProjectConfig, project_dir: &Path) -> ProjectConfig {
+        if !project_dir.as_os_str().is_empty() {
+            for run_config in &mut config.run_configs {
+                run_config.cwd = Some(project_dir.to_path_buf());
+            }
+        }
+        config
     }
     
     fn generate_default_config(project_type: &ProjectType, path: &PathBuf) -> ProjectConfig {
@@ -116,14 +294,72 @@ impl Project {
                 config.dev_command = Some("python main.py".to_string());
                 config.test_command = Some("pytest".to_string());
             }
+            ProjectType::Go => {
+                config.dev_command = Some("go run .".to_string());
+                config.build_command = Some("go build ./...".to_string());
+                config.test_command = Some("go test ./...".to_string());
+            }
+            ProjectType::Deno => {
+                // Prefer the tasks declared in deno.json; comments in deno.jsonc make it unreadable here
+                let tasks = std::fs::read_to_string(path.join("deno.json"))
+                    .ok()
+                    .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+                    .and_then(|deno_json| deno_json.get("tasks").and_then(|t| t.as_object()).cloned())
+                    .unwrap_or_default();
+                config.dev_command = Some(if tasks.contains_key("dev") {
+                    "deno task dev".to_string()
+                } else if tasks.contains_key("start") {
+                    "deno task start".to_string()
+                } else {
+                    "deno run --allow-all main.ts".to_string()
+                });
+                if tasks.contains_key("build") {
+                    config.build_command = Some("deno task build".to_string());
+                }
+                config.test_command = Some("deno test".to_string());
+            }
+            ProjectType::JavaMaven => {
+                let mvn = if path.join("mvnw").exists() { "./mvnw" } else { "mvn" };
+                config.dev_command = Some(format!("{} compile exec:java", mvn));
+                config.build_command = Some(format!("{} package", mvn));
+                config.test_command = Some(format!("{} test", mvn));
+            }
+            ProjectType::Gradle => {
+                let gradle = if path.join("gradlew").exists() { "./gradlew" } else { "gradle" };
+                config.dev_command = Some(format!("{} run", gradle));
+                config.build_command = Some(format!("{} build", gradle));
+                config.test_command = Some(format!("{} test", gradle));
+            }
             ProjectType::Generic => {
                 // No default commands for generic projects
             }
         }
         
+        config.populate_run_configs();
         config
     }
     
+    /// Variables for the project's processes: those of its environment files
+    /// unless `load_env_files` is off, overridden by the configured `env_vars`.
+    /// Unreadable files are skipped.
+    pub fn environment(&self) -> HashMap<String, String> {
+        let mut env_vars = HashMap::new();
+        if self.config.load_env_files {
+            for file_name in ENV_FILES {
+                let path = self.path.join(file_name);
+                if !path.is_file() {
+                    continue;
+                }
+                match dotenvy::from_path_iter(&path).and_then(|vars| vars.collect::<Result<Vec<_>, _>>()) {
+                    Ok(vars) => env_vars.extend(vars),
+                    Err(e) => tracing::warn!("Skipping unreadable {}: {}", path.display(), e),
+                }
+            }
+        }
+        env_vars.extend(self.config.env_vars.clone());
+        env_vars
+    }
+    
     pub fn update_last_opened(&mut self) {
         self.last_opened = chrono::Utc::now();
     }
@@ -145,6 +381,14 @@ pub struct BackgroundProcess {
     pub stopped_at: Option<chrono::DateTime<chrono::Utc>>,
     pub auto_restart: bool,
     pub restart_count: u32,
+    /// Latest CPU reading while running, as a share of one core.
+    pub cpu_percent: Option<f32>,
+    /// Latest resident memory reading while running.
+    pub memory_bytes: Option<u64>,
+    /// Keeps the process `Starting` after each launch until it passes.
+    /// Needs `port`.
+    #[serde(default)]
+    pub health_check: Option<HealthCheck>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -154,49 +398,193 @@ pub struct LogEntry {
     pub message: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Ordered from least to most severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum LogLevel {
+    Debug,
     Info,
     Warning,
     Error,
-    Debug,
+}
+
+impl LogLevel {
+    /// Reads the level from a tag such as `ERROR`, `[warn]` or `info:` among
+    /// the first few words of `line`, which covers most loggers including
+    /// those that print a timestamp first. Returns `default` if there is none.
+    pub fn infer(line: &str, default: LogLevel) -> LogLevel {
+        line.split_whitespace()
+            .take(3)
+            .find_map(|word| {
+                let tag = word
+                    .trim_matches(|c: char| !c.is_ascii_alphabetic())
+                    .to_ascii_uppercase();
+                match tag.as_str() {
+                    "ERROR" | "ERR" | "FATAL" | "PANIC" => Some(LogLevel::Error),
+                    "WARN" | "WARNING" => Some(LogLevel::Warning),
+                    "INFO" => Some(LogLevel::Info),
+                    "DEBUG" | "TRACE" => Some(LogLevel::Debug),
+                    _ => None,
+                }
+            })
+            .unwrap_or(default)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ProcessStatus {
     Starting,
     Running,
+    /// Still running, but it did not pass its health check in time.
+    Unhealthy,
     Stopped,
     Error,
 }
 
+/// File in the app data directory holding the project list.
+const PROJECTS_FILE: &str = "projects.json";
+/// Directory in the app data directory holding background process logs.
+const PROCESS_LOGS_DIR: &str = "process-logs";
+/// Log entries kept in memory per background process.
+const MAX_IN_MEMORY_LOGS: usize = 1000;
+
+/// Reads a saved project list, marking projects whose folder is gone as stale.
+fn load_projects(path: &Path) -> Result<HashMap<Uuid, Project>> {
+    if !path.exists() {
+        return Ok(HashMap::new());
+    }
+    
+    let projects: Vec<Project> = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+    Ok(projects
+        .into_iter()
+        .map(|mut project| {
+            // Lists saved before run configurations existed only have the single commands
+            project.config.populate_run_configs();
+            project.stale = !project.path.is_dir();
+            if project.stale {
+                tracing::warn!("Project {} is stale: {} no longer exists", project.name, project.path.display());
+            }
+            (project.id, project)
+        })
+        .collect())
+}
+
+/// Consecutive crashes after which an auto-restarting process is left down.
+pub const MAX_AUTO_RESTARTS: u32 = 5;
+/// Delay before the first automatic restart; it doubles after each crash.
+const AUTO_RESTART_BASE_DELAY: std::time::Duration = std::time::Duration::from_secs(1);
+const AUTO_RESTART_MAX_DELAY: std::time::Duration = std::time::Duration::from_secs(30);
+/// A process that ran this long before crashing has its crash count reset.
+const AUTO_RESTART_STABLE_RUN: std::time::Duration = std::time::Duration::from_secs(60);
+/// Time a stopped process group gets to exit before it is killed.
+const STOP_GRACE_PERIOD: std::time::Duration = std::time::Duration::from_secs(3);
+
+/// A spawned background process. Its supervisor task owns the child;
+/// sending on `kill` makes it kill the child, and `supervisor` finishes once
+/// the child is gone.
+struct RunningProcess {
+    kill: oneshot::Sender<()>,
+    supervisor: JoinHandle<()>,
+}
+
 pub struct WorkspaceManager {
     projects: Arc<RwLock<HashMap<Uuid, Project>>>,
+    projects_file: Option<PathBuf>,
     active_project: Arc<RwLock<Option<Uuid>>>,
     background_processes: Arc<RwLock<HashMap<Uuid, BackgroundProcess>>>,
-    running_processes: Arc<RwLock<HashMap<Uuid, Child>>>,
+    running_processes: Arc<RwLock<HashMap<Uuid, RunningProcess>>>,
+    metrics_sampler: std::sync::OnceLock<JoinHandle<()>>,
+    log_store: Arc<ProcessLogStore>,
     git_manager: Arc<RwLock<GitManager>>,
+    task_runner: TaskRunner,
+    agent_bridge: Option<Arc<RwLock<code_furnace_agents::AgentBridge>>>,
     event_bus: code_furnace_events::EventBus,
 }
 
 impl WorkspaceManager {
+    /// Restores the project list saved under the app data directory, starting
+    /// empty if it cannot be read.
     pub fn new(event_bus: code_furnace_events::EventBus) -> Self {
+        let app_data_dir = code_furnace_utils::paths::get_app_data_dir()
+            .map_err(|e| tracing::warn!("Projects and process logs will not be saved: {}", e))
+            .ok();
+        Self::with_data_dir(event_bus, app_data_dir)
+    }
+    
+    /// Like `new`, but keeps projects and process logs in `app_data_dir`
+    /// instead of the app data directory, or saves nothing without one.
+    pub fn with_data_dir(event_bus: code_furnace_events::EventBus, app_data_dir: Option<PathBuf>) -> Self {
+        let projects_file = app_data_dir.as_ref().map(|dir| dir.join(PROJECTS_FILE));
+        let log_store = match &app_data_dir {
+            Some(dir) => ProcessLogStore::new(dir.join(PROCESS_LOGS_DIR)),
+            None => ProcessLogStore::disabled(),
+        };
+        let projects = projects_file
+            .as_deref()
+            .map(load_projects)
+            .transpose()
+            .unwrap_or_else(|e| {
+                tracing::warn!("Failed to load saved projects: {}", e);
+                None
+            })
+            .unwrap_or_default();
+        
         Self {
-            projects: Arc::new(RwLock::new(HashMap::new())),
+            projects: Arc::new(RwLock::new(projects)),
+            projects_file,
             active_project: Arc::new(RwLock::new(None)),
             background_processes: Arc::new(RwLock::new(HashMap::new())),
             running_processes: Arc::new(RwLock::new(HashMap::new())),
+            metrics_sampler: std::sync::OnceLock::new(),
+            log_store: Arc::new(log_store),
             git_manager: Arc::new(RwLock::new(GitManager::new())),
+            task_runner: TaskRunner::new(event_bus.clone()),
+            agent_bridge: None,
             event_bus,
         }
     }
     
+    /// Lets `generate_ai_commit_message` ask the `git-assistant` agent.
+    pub fn with_agent_bridge(mut self, agent_bridge: Arc<RwLock<code_furnace_agents::AgentBridge>>) -> Self {
+        self.agent_bridge = Some(agent_bridge);
+        self
+    }
+    
+    /// Writes the project list now. It is also saved after every change.
+    pub async fn save_projects(&self) {
+        let projects = self.projects.read().await;
+        self.persist_projects(&projects);
+    }
+    
+    /// Writes the project list through a temporary file. Failures are logged
+    /// rather than failing the project operation itself.
+    fn persist_projects(&self, projects: &HashMap<Uuid, Project>) {
+        let Some(projects_file) = &self.projects_file else {
+            return;
+        };
+        
+        let mut list: Vec<&Project> = projects.values().collect();
+        list.sort_by_key(|project| project.created_at);
+        let result = serde_json::to_string_pretty(&list)
+            .map_err(anyhow::Error::from)
+            .and_then(|json| {
+                let temp_path = projects_file.with_extension("json.tmp");
+                std::fs::write(&temp_path, json)?;
+                std::fs::rename(&temp_path, projects_file)?;
+                Ok(())
+            });
+        if let Err(e) = result {
+            tracing::warn!("Failed to save projects: {}", e);
+        }
+    }
+    
     pub async fn create_project(&self, name: String, path: PathBuf) -> Result<Uuid> {
         let project = Project::new(name, path);
         let project_id = project.id;
         
         let mut projects = self.projects.write().await;
         projects.insert(project_id, project);
+        self.persist_projects(&projects);
+        drop(projects);
         
         let event = code_furnace_events::Event::new(
             "workspace.project.created",
@@ -211,11 +599,15 @@ impl WorkspaceManager {
     pub async fn open_project(&self, project_id: Uuid) -> Result<()> {
         {
             let mut projects = self.projects.write().await;
-            if let Some(project) = projects.get_mut(&project_id) {
-                project.update_last_opened();
-            } else {
-                return Err(anyhow::anyhow!("Project not found: {}", project_id));
+            let project = projects
+                .get_mut(&project_id)
+                .ok_or_else(|| ResourceError::NotFound(format!("Project not found: {}", project_id)))?;
+            project.stale = !project.path.is_dir();
+            if project.stale {
+                return Err(anyhow::anyhow!("Project folder no longer exists: {}", project.path.display()));
             }
+            project.update_last_opened();
+            self.persist_projects(&projects);
         }
         
         let mut active_project = self.active_project.write().await;
@@ -231,6 +623,42 @@ impl WorkspaceManager {
         Ok(())
     }
     
+    /// Forgets a project; its files are left untouched.
+    pub async fn remove_project(&self, project_id: Uuid) -> Result<()> {
+        {
+            let mut projects = self.projects.write().await;
+            if projects.remove(&project_id).is_none() {
+                return Err(ResourceError::NotFound(format!("Project not found: {}", project_id)).into());
+            }
+            self.persist_projects(&projects);
+        }
+        
+        {
+            let mut active_project = self.active_project.write().await;
+            if *active_project == Some(project_id) {
+                *active_project = None;
+            }
+        }
+        
+        let event = code_furnace_events::Event::new(
+            "workspace.project.removed",
+            "workspace-manager",
+            serde_json::to_value(project_id)?,
+        );
+        self.event_bus.publish(event)?;
+        
+        Ok(())
+    }
+    
+    /// The project rooted at `path`, if one was already added.
+    pub async fn find_project_by_path(&self, path: &Path) -> Option<Uuid> {
+        let projects = self.projects.read().await;
+        projects
+            .values()
+            .find(|project| project.path == path)
+            .map(|project| project.id)
+    }
+    
     pub async fn get_project(&self, project_id: Uuid) -> Option<Project> {
         let projects = self.projects.read().await;
         projects.get(&project_id).cloned()
@@ -266,33 +694,14 @@ impl WorkspaceManager {
         env_vars: HashMap<String, String>,
         auto_restart: bool,
     ) -> Result<Uuid> {
-        let process_id = Uuid::new_v4();
-        
-        // Parse command and arguments
-        let mut cmd = Command::new(&command);
-        cmd.args(&args)
-           .current_dir(&working_directory)
-           .stdout(Stdio::piped())
-           .stderr(Stdio::piped())
-           .stdin(Stdio::null());
-        
-        // Set environment variables
-        for (key, value) in &env_vars {
-            cmd.env(key, value);
-        }
-        
-        // Start the process
-        let child = cmd.spawn()?;
-        let pid = child.id();
-        
-        let process = BackgroundProcess {
-            id: process_id,
-            name: name.clone(),
-            command: command.clone(),
-            args: args.clone(),
-            working_directory: working_directory.clone(),
-            status: ProcessStatus::Running,
-            pid,
+        self.start_process(BackgroundProcess {
+            id: Uuid::new_v4(),
+            name,
+            command,
+            args,
+            working_directory,
+            status: ProcessStatus::Starting,
+            pid: None,
             port,
             env_vars,
             logs: Vec::new(),
@@ -300,7 +709,19 @@ impl WorkspaceManager {
             stopped_at: None,
             auto_restart,
             restart_count: 0,
-        };
+            cpu_percent: None,
+            memory_bytes: None,
+            health_check: None,
+        }).await
+    }
+    
+    /// Stores `process` and launches it. It becomes ready once its health
+    /// check passes, or right away without one.
+    async fn start_process(&self, process: BackgroundProcess) -> Result<Uuid> {
+        let process_id = process.id;
+        let name = process.name.clone();
+        let command = process.command.clone();
+        let args = process.args.clone();
         
         // Store the process
         {
@@ -308,14 +729,13 @@ impl WorkspaceManager {
             background_processes.insert(process_id, process);
         }
         
-        // Store the running child process
-        {
-            let mut running_processes = self.running_processes.write().await;
-            running_processes.insert(process_id, child);
-        }
-        
-        // Start monitoring the process output
-        self.monitor_process_output(process_id).await;
+        let pid = match self.launch_process(process_id).await {
+            Ok(pid) => pid,
+            Err(e) => {
+                self.background_processes.write().await.remove(&process_id);
+                return Err(e);
+            }
+        };
         
         let event = code_furnace_events::Event::new(
             "workspace.process.started",
@@ -329,28 +749,278 @@ impl WorkspaceManager {
             }),
         );
         self.event_bus.publish(event)?;
+        self.watch_readiness(process_id).await;
         
         Ok(process_id)
     }
     
-    async fn monitor_process_output(&self, process_id: Uuid) {
-        let (stdout, stderr) = {
-            let mut running_processes = self.running_processes.write().await;
-            if let Some(child) = running_processes.get_mut(&process_id) {
-                let stdout = child.stdout.take();
-                let stderr = child.stderr.take();
-                (stdout, stderr)
-            } else {
-                return;
-            }
+    /// Spawns the command of a stored process and starts the task that
+    /// supervises it. Returns the new pid.
+    async fn launch_process(&self, process_id: Uuid) -> Result<Option<u32>> {
+        let mut background_processes = self.background_processes.write().await;
+        let process = background_processes
+            .get_mut(&process_id)
+            .ok_or_else(|| ResourceError::NotFound(format!("Process not found: {}", process_id)))?;
+        
+        let mut child = Self::spawn_child(process)?;
+        process.status = Self::launch_status(process);
+        process.pid = child.id();
+        process.started_at = chrono::Utc::now();
+        process.stopped_at = None;
+        let pid = process.pid;
+        drop(background_processes);
+        
+        self.metrics_sampler.get_or_init(|| {
+            tokio::spawn(sample_process_metrics(
+                Arc::downgrade(&self.background_processes),
+                self.event_bus.clone(),
+            ))
+        });
+        
+        // Start monitoring the process output and its exit
+        Self::monitor_process_output(
+            process_id,
+            child.stdout.take(),
+            child.stderr.take(),
+            self.background_processes.clone(),
+            self.log_store.clone(),
+            self.event_bus.clone(),
+        );
+        
+        // Hold the lock until the supervisor is registered so a fast exit still finds it
+        let mut running_processes = self.running_processes.write().await;
+        let (kill_tx, kill_rx) = oneshot::channel();
+        let supervisor = tokio::spawn(Self::supervise_process(
+            process_id,
+            child,
+            kill_rx,
+            self.background_processes.clone(),
+            self.running_processes.clone(),
+            self.log_store.clone(),
+            self.event_bus.clone(),
+        ));
+        running_processes.insert(process_id, RunningProcess { kill: kill_tx, supervisor });
+        
+        Ok(pid)
+    }
+    
+    /// Status a freshly spawned process starts in: `Starting` while it has a
+    /// health check to pass, `Running` otherwise.
+    fn launch_status(process: &BackgroundProcess) -> ProcessStatus {
+        if process.health_check.is_some() && process.port.is_some() {
+            ProcessStatus::Starting
+        } else {
+            ProcessStatus::Running
+        }
+    }
+    
+    async fn watch_readiness(&self, process_id: Uuid) {
+        Self::watch_launch_readiness(process_id, &self.background_processes, &self.event_bus).await;
+    }
+    
+    /// Starts health checking the latest launch of a process, or reports it
+    /// ready straight away if it has no health check.
+    async fn watch_launch_readiness(
+        process_id: Uuid,
+        background_processes: &Arc<RwLock<HashMap<Uuid, BackgroundProcess>>>,
+        event_bus: &code_furnace_events::EventBus,
+    ) {
+        let Some(process) = background_processes.read().await.get(&process_id).cloned() else {
+            return;
         };
         
-        let background_processes = self.background_processes.clone();
-        let event_bus = self.event_bus.clone();
+        match (process.health_check, process.port) {
+            (Some(check), Some(port)) => {
+                tokio::spawn(health::await_healthy(
+                    process_id,
+                    process.started_at,
+                    port,
+                    check,
+                    background_processes.clone(),
+                    event_bus.clone(),
+                ));
+            }
+            (check, port) => {
+                if check.is_some() {
+                    tracing::warn!("Process {} has a health check but no port; treating it as ready", process_id);
+                }
+                if matches!(process.status, ProcessStatus::Running) {
+                    health::publish_ready(event_bus, process_id, port);
+                }
+            }
+        }
+    }
+    
+    fn spawn_child(process: &BackgroundProcess) -> Result<Child> {
+        // Parse command and arguments
+        let mut cmd = Command::new(&process.command);
+        cmd.args(&process.args)
+           .current_dir(&process.working_directory)
+           .stdout(Stdio::piped())
+           .stderr(Stdio::piped())
+           .stdin(Stdio::null());
+        process_group::configure(&mut cmd);
+        
+        // Set environment variables
+        for (key, value) in &process.env_vars {
+            cmd.env(key, value);
+        }
+        
+        Ok(cmd.spawn()?)
+    }
+    
+    /// Owns `child` until it is killed through `kill_rx` or exits. An exit
+    /// marks the process `Stopped` if it was clean and `Error` otherwise, and
+    /// publishes `workspace.process.exited`. A crashed process with
+    /// `auto_restart` is then respawned after a growing delay, up to
+    /// `MAX_AUTO_RESTARTS` times in a row.
+    async fn supervise_process(
+        process_id: Uuid,
+        mut child: Child,
+        mut kill_rx: oneshot::Receiver<()>,
+        background_processes: Arc<RwLock<HashMap<Uuid, BackgroundProcess>>>,
+        running_processes: Arc<RwLock<HashMap<Uuid, RunningProcess>>>,
+        log_store: Arc<ProcessLogStore>,
+        event_bus: code_furnace_events::EventBus,
+    ) {
+        let mut crash_restarts = 0;
+        let mut launched_at = std::time::Instant::now();
+        let mut group = ProcessGroup::attach(&child);
         
+        loop {
+            let exit_status = tokio::select! {
+                exit_status = child.wait() => exit_status,
+                _ = &mut kill_rx => {
+                    group.terminate();
+                    if tokio::time::timeout(STOP_GRACE_PERIOD, child.wait()).await.is_err() {
+                        let _ = child.kill().await;
+                    }
+                    group.kill();
+                    return;
+                }
+            };
+            // Nothing the process started may outlive it
+            group.kill();
+            
+            // An explicit stop removes the entry first and reports on its own
+            if !running_processes.read().await.contains_key(&process_id) {
+                return;
+            }
+            
+            let (success, exit_code) = match &exit_status {
+                Ok(exit_status) => (exit_status.success(), exit_status.code()),
+                Err(e) => {
+                    tracing::warn!("Failed to wait for process {}: {}", process_id, e);
+                    (false, None)
+                }
+            };
+            let auto_restart = {
+                let mut processes = background_processes.write().await;
+                match processes.get_mut(&process_id) {
+                    Some(process) => {
+                        process.status = if success { ProcessStatus::Stopped } else { ProcessStatus::Error };
+                        process.stopped_at = Some(chrono::Utc::now());
+                        process.auto_restart
+                    }
+                    None => false,
+                }
+            };
+            
+            let event = code_furnace_events::Event::new(
+                "workspace.process.exited",
+                "workspace-manager",
+                serde_json::json!({
+                    "process_id": process_id,
+                    "exit_code": exit_code,
+                    "success": success
+                }),
+            );
+            let _ = event_bus.publish(event);
+            
+            // A process that stayed up for a while starts a fresh crash streak
+            if launched_at.elapsed() >= AUTO_RESTART_STABLE_RUN {
+                crash_restarts = 0;
+            }
+            if success || !auto_restart || crash_restarts >= MAX_AUTO_RESTARTS {
+                if !success && auto_restart {
+                    tracing::warn!("Process {} keeps crashing, giving up after {} restarts", process_id, crash_restarts);
+                }
+                running_processes.write().await.remove(&process_id);
+                return;
+            }
+            
+            let delay = AUTO_RESTART_BASE_DELAY
+                .saturating_mul(1 << crash_restarts)
+                .min(AUTO_RESTART_MAX_DELAY);
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => {}
+                _ = &mut kill_rx => return,
+            }
+            crash_restarts += 1;
+            
+            let restarted = {
+                let mut processes = background_processes.write().await;
+                let Some(process) = processes.get_mut(&process_id) else {
+                    running_processes.write().await.remove(&process_id);
+                    return;
+                };
+                Self::spawn_child(process).map(|new_child| {
+                    process.status = Self::launch_status(process);
+                    process.pid = new_child.id();
+                    process.started_at = chrono::Utc::now();
+                    process.stopped_at = None;
+                    process.restart_count += 1;
+                    (new_child, process.pid, process.restart_count)
+                })
+            };
+            let (new_child, pid, restart_count) = match restarted {
+                Ok(restarted) => restarted,
+                Err(e) => {
+                    tracing::warn!("Failed to restart process {}: {}", process_id, e);
+                    running_processes.write().await.remove(&process_id);
+                    return;
+                }
+            };
+            
+            child = new_child;
+            group = ProcessGroup::attach(&child);
+            launched_at = std::time::Instant::now();
+            Self::monitor_process_output(
+                process_id,
+                child.stdout.take(),
+                child.stderr.take(),
+                background_processes.clone(),
+                log_store.clone(),
+                event_bus.clone(),
+            );
+            
+            let event = code_furnace_events::Event::new(
+                "workspace.process.restarted",
+                "workspace-manager",
+                serde_json::json!({
+                    "process_id": process_id,
+                    "pid": pid,
+                    "restart_count": restart_count,
+                    "automatic": true
+                }),
+            );
+            let _ = event_bus.publish(event);
+            Self::watch_launch_readiness(process_id, &background_processes, &event_bus).await;
+        }
+    }
+    
+    fn monitor_process_output(
+        process_id: Uuid,
+        stdout: Option<ChildStdout>,
+        stderr: Option<ChildStderr>,
+        background_processes: Arc<RwLock<HashMap<Uuid, BackgroundProcess>>>,
+        log_store: Arc<ProcessLogStore>,
+        event_bus: code_furnace_events::EventBus,
+    ) {
         // Monitor stdout
         if let Some(stdout) = stdout {
             let background_processes_clone = background_processes.clone();
+            let log_store_clone = log_store.clone();
             let event_bus_clone = event_bus.clone();
             
             tokio::spawn(async move {
@@ -364,7 +1034,7 @@ impl WorkspaceManager {
                     
                     let log_entry = LogEntry {
                         timestamp: chrono::Utc::now(),
-                        level: LogLevel::Info,
+                        level: LogLevel::infer(&line, LogLevel::Info),
                         message: line.trim().to_string(),
                     };
                     
@@ -374,12 +1044,15 @@ impl WorkspaceManager {
                         if let Some(process) = processes.get_mut(&process_id) {
                             process.logs.push(log_entry.clone());
                             
-                            // Keep only last 1000 log entries
-                            if process.logs.len() > 1000 {
-                                process.logs.drain(0..process.logs.len() - 1000);
+                            // Keep only the most recent entries in memory
+                            if process.logs.len() > MAX_IN_MEMORY_LOGS {
+                                process.logs.drain(0..process.logs.len() - MAX_IN_MEMORY_LOGS);
                             }
                         }
                     }
+                    if let Err(e) = log_store_clone.append(process_id, &log_entry) {
+                        tracing::warn!("Failed to write log of process {}: {}", process_id, e);
+                    }
                     
                     // Publish log event
                     let event = code_furnace_events::Event::new(
@@ -394,6 +1067,7 @@ impl WorkspaceManager {
                     
                     line.clear();
                 }
+                log_store_clone.close(process_id);
             });
         }
         
@@ -410,7 +1084,7 @@ impl WorkspaceManager {
                     
                     let log_entry = LogEntry {
                         timestamp: chrono::Utc::now(),
-                        level: LogLevel::Error,
+                        level: LogLevel::infer(&line, LogLevel::Error),
                         message: line.trim().to_string(),
                     };
                     
@@ -420,12 +1094,15 @@ impl WorkspaceManager {
                         if let Some(process) = processes.get_mut(&process_id) {
                             process.logs.push(log_entry.clone());
                             
-                            // Keep only last 1000 log entries
-                            if process.logs.len() > 1000 {
-                                process.logs.drain(0..process.logs.len() - 1000);
+                            // Keep only the most recent entries in memory
+                            if process.logs.len() > MAX_IN_MEMORY_LOGS {
+                                process.logs.drain(0..process.logs.len() - MAX_IN_MEMORY_LOGS);
                             }
                         }
                     }
+                    if let Err(e) = log_store.append(process_id, &log_entry) {
+                        tracing::warn!("Failed to write log of process {}: {}", process_id, e);
+                    }
                     
                     // Publish log event
                     let event = code_furnace_events::Event::new(
@@ -440,17 +1117,20 @@ impl WorkspaceManager {
                     
                     line.clear();
                 }
+                log_store.close(process_id);
             });
         }
     }
     
+    /// Stops a process and everything it started, and keeps it from being
+    /// restarted automatically. The processes get `STOP_GRACE_PERIOD` to exit
+    /// before they are killed.
     pub async fn stop_background_process(&self, process_id: Uuid) -> Result<()> {
-        // Kill the actual process
-        {
-            let mut running_processes = self.running_processes.write().await;
-            if let Some(mut child) = running_processes.remove(&process_id) {
-                let _ = child.kill().await;
-            }
+        // Kill the actual process and wait for it to go
+        let running = self.running_processes.write().await.remove(&process_id);
+        if let Some(running) = running {
+            let _ = running.kill.send(());
+            let _ = running.supervisor.await;
         }
         
         // Update process status
@@ -472,23 +1152,26 @@ impl WorkspaceManager {
         Ok(())
     }
     
-    pub async fn restart_background_process(&self, process_id: Uuid) -> Result<()> {
-        let (name, command, args, working_directory, port, env_vars, auto_restart) = {
-            let background_processes = self.background_processes.read().await;
-            if let Some(process) = background_processes.get(&process_id) {
-                (
-                    process.name.clone(),
-                    process.command.clone(), 
-                    process.args.clone(),
-                    process.working_directory.clone(),
-                    process.port,
-                    process.env_vars.clone(),
-                    process.auto_restart,
-                )
-            } else {
-                return Err(anyhow::anyhow!("Process not found: {}", process_id));
+    /// Stops every running background process at once, e.g. when the app
+    /// exits, so the whole batch takes about one `STOP_GRACE_PERIOD`.
+    pub async fn stop_all_background_processes(&self) {
+        let process_ids: Vec<Uuid> = self.running_processes.read().await.keys().copied().collect();
+        let results = futures::future::join_all(
+            process_ids.iter().map(|process_id| self.stop_background_process(*process_id)),
+        ).await;
+        
+        for (process_id, result) in process_ids.iter().zip(results) {
+            if let Err(e) = result {
+                tracing::warn!("Failed to stop process {}: {}", process_id, e);
             }
-        };
+        }
+    }
+    
+    /// Stops the process and starts its command again under the same id.
+    pub async fn restart_background_process(&self, process_id: Uuid) -> Result<()> {
+        if self.background_processes.read().await.get(&process_id).is_none() {
+            return Err(ResourceError::NotFound(format!("Process not found: {}", process_id)).into());
+        }
         
         // Stop the current process
         self.stop_background_process(process_id).await?;
@@ -501,34 +1184,108 @@ impl WorkspaceManager {
             }
         }
         
-        // Start a new process with the same configuration
-        self.start_background_process(
-            name,
-            command,
-            args,
-            working_directory,
-            port,
-            env_vars,
-            auto_restart,
-        ).await?;
+        // Start it again with the same configuration
+        let pid = self.launch_process(process_id).await?;
+        let restart_count = self
+            .background_processes
+            .read()
+            .await
+            .get(&process_id)
+            .map(|process| process.restart_count)
+            .unwrap_or_default();
+        
+        let event = code_furnace_events::Event::new(
+            "workspace.process.restarted",
+            "workspace-manager",
+            serde_json::json!({
+                "process_id": process_id,
+                "pid": pid,
+                "restart_count": restart_count,
+                "automatic": false
+            }),
+        );
+        self.event_bus.publish(event)?;
+        self.watch_readiness(process_id).await;
         
         Ok(())
     }
     
-    pub async fn get_process_logs(&self, process_id: Uuid, limit: Option<usize>) -> Vec<LogEntry> {
+    /// Latest CPU and memory readings of the running background processes.
+    pub async fn get_process_metrics(&self) -> Vec<ProcessMetrics> {
         let background_processes = self.background_processes.read().await;
-        if let Some(process) = background_processes.get(&process_id) {
-            let logs = &process.logs;
-            match limit {
-                Some(n) => {
-                    let start = if logs.len() > n { logs.len() - n } else { 0 };
-                    logs[start..].to_vec()
-                }
-                None => logs.clone(),
-            }
+        background_processes
+            .values()
+            .filter_map(|process| {
+                Some(ProcessMetrics {
+                    process_id: process.id,
+                    pid: process.pid?,
+                    cpu_percent: process.cpu_percent?,
+                    memory_bytes: process.memory_bytes?,
+                })
+            })
+            .collect()
+    }
+    
+    /// The last `limit` log entries of a process that match `filter`, oldest
+    /// first. Only the in-memory tail is searched unless `include_history` is
+    /// set, in which case entries are read back from the process's log files,
+    /// which also cover its earlier runs under the same id. Ids are new on
+    /// every start, so logs of earlier sessions stay on disk only until
+    /// `MAX_LOG_AGE` prunes them.
+    pub async fn get_process_logs(
+        &self,
+        process_id: Uuid,
+        limit: Option<usize>,
+        include_history: bool,
+        filter: &LogFilter,
+    ) -> Vec<LogEntry> {
+        let mut logs = if include_history {
+            let log_store = self.log_store.clone();
+            tokio::task::spawn_blocking(move || log_store.read(process_id))
+                .await
+                .map_err(anyhow::Error::from)
+                .and_then(|logs| logs)
+                .unwrap_or_else(|e| {
+                    tracing::warn!("Failed to read log files of process {}: {}", process_id, e);
+                    Vec::new()
+                })
         } else {
             Vec::new()
+        };
+        
+        // The files hold everything in memory too; fall back to memory when they are missing
+        if logs.is_empty() {
+            let background_processes = self.background_processes.read().await;
+            if let Some(process) = background_processes.get(&process_id) {
+                logs = process.logs.clone();
+            }
         }
+        
+        logs.retain(|entry| filter.matches(entry));
+        if let Some(n) = limit {
+            let start = logs.len().saturating_sub(n);
+            logs.drain(..start);
+        }
+        logs
+    }
+    
+    /// Forgets the log of a process, both in memory and on disk.
+    pub async fn clear_process_logs(&self, process_id: Uuid) -> Result<()> {
+        if let Some(process) = self.background_processes.write().await.get_mut(&process_id) {
+            process.logs.clear();
+        }
+        self.log_store.clear(process_id)?;
+        
+        let event = code_furnace_events::Event::new(
+            "workspace.process.logs_cleared",
+            "workspace-manager",
+            serde_json::json!({
+                "process_id": process_id
+            }),
+        );
+        self.event_bus.publish(event)?;
+        
+        Ok(())
     }
     
     pub async fn get_project_processes(&self, project_id: Uuid) -> Vec<BackgroundProcess> {
@@ -560,6 +1317,19 @@ impl WorkspaceManager {
         git_manager.open_repository(path)
     }
     
+    /// An API client for the forge hosting the `origin` remote of the
+    /// repository at `repo_path`, using the token configured for it.
+    pub async fn forge_client_for(&self, repo_path: &PathBuf) -> Result<ForgeRemote> {
+        let remote_url = self
+            .git_manager
+            .read()
+            .await
+            .remote_url(repo_path)?
+            .ok_or_else(|| anyhow::anyhow!("Repository {} has no origin remote", repo_path.display()))?;
+        let config = code_furnace_utils::Config::load()?;
+        ForgeRemote::from_remote_url(&remote_url, &config)
+    }
+    
     pub async fn get_git_status(&self, repo_path: &PathBuf) -> Result<GitStatus> {
         let git_manager = self.git_manager.read().await;
         let repo = git2::Repository::open(repo_path)?;
@@ -600,19 +1370,28 @@ impl WorkspaceManager {
         git_manager.get_commit_history(repo_path, limit)
     }
     
+    pub async fn git_get_file_history(&self, repo_path: &PathBuf, file_path: &str, limit: Option<usize>) -> Result<Vec<GitCommit>> {
+        let git_manager = self.git_manager.read().await;
+        git_manager.get_file_history(repo_path, file_path, limit)
+    }
+    
     pub async fn git_get_branches(&self, repo_path: &PathBuf) -> Result<Vec<GitBranch>> {
         let git_manager = self.git_manager.read().await;
         git_manager.get_branches(repo_path)
     }
     
-    pub async fn git_create_branch(&self, repo_path: &PathBuf, branch_name: &str, from_head: bool) -> Result<()> {
+    pub async fn git_create_branch(&self, repo_path: &PathBuf, branch_name: &str, start_point: Option<&str>) -> Result<()> {
         let mut git_manager = self.git_manager.write().await;
-        git_manager.create_branch(repo_path, branch_name, from_head)
+        git_manager.create_branch(repo_path, branch_name, start_point)
     }
     
-    pub async fn git_switch_branch(&self, repo_path: &PathBuf, branch_name: &str) -> Result<()> {
+    /// See `GitManager::switch_branch`. The event is only published once the
+    /// new branch is checked out, also when autostashed changes stayed in
+    /// the stash.
+    pub async fn git_switch_branch(&self, repo_path: &PathBuf, branch_name: &str, autostash: bool) -> Result<SwitchOutcome> {
         let mut git_manager = self.git_manager.write().await;
-        git_manager.switch_branch(repo_path, branch_name)?;
+        let outcome = git_manager.switch_branch(repo_path, branch_name, autostash)?;
+        drop(git_manager);
         
         // Publish branch switch event
         let event = code_furnace_events::Event::new(
@@ -620,12 +1399,14 @@ impl WorkspaceManager {
             "workspace-manager",
             serde_json::json!({
                 "repo_path": repo_path,
-                "branch_name": branch_name
+                "branch_name": branch_name,
+                "autostash": autostash,
+                "stash_kept": matches!(outcome, SwitchOutcome::StashKept { .. })
             }),
         );
         self.event_bus.publish(event)?;
         
-        Ok(())
+        Ok(outcome)
     }
     
     pub async fn git_get_diff(&self, repo_path: &PathBuf, staged: bool) -> Result<Vec<GitDiff>> {
@@ -633,6 +1414,73 @@ impl WorkspaceManager {
         git_manager.get_diff(repo_path, staged)
     }
     
+    pub async fn git_get_file_diff(&self, repo_path: &PathBuf, file_path: &str, staged: bool) -> Result<Option<GitDiff>> {
+        let git_manager = self.git_manager.read().await;
+        git_manager.get_file_diff(repo_path, file_path, staged)
+    }
+    
+    pub async fn git_discard_file_changes(&self, repo_path: &PathBuf, file_path: &str) -> Result<()> {
+        self.git_manager.write().await.discard_file_changes(repo_path, file_path)?;
+        
+        let event = code_furnace_events::Event::new(
+            "workspace.git.changes_discarded",
+            "workspace-manager",
+            serde_json::json!({
+                "repo_path": repo_path,
+                "file_path": file_path
+            }),
+        );
+        self.event_bus.publish(event)?;
+        
+        Ok(())
+    }
+    
+    pub async fn git_blame(&self, repo_path: &PathBuf, file_path: &str) -> Result<Vec<GitBlameLine>> {
+        let git_manager = self.git_manager.read().await;
+        git_manager.blame_file(repo_path, file_path)
+    }
+    
+    pub async fn git_stash_save(&self, repo_path: &PathBuf, message: Option<&str>, include_untracked: bool) -> Result<String> {
+        let stash_id = self.git_manager.write().await.stash_save(repo_path, message, include_untracked)?;
+        self.publish_stash_event(repo_path, "saved", serde_json::json!(stash_id))?;
+        Ok(stash_id)
+    }
+    
+    pub async fn git_stash_list(&self, repo_path: &PathBuf) -> Result<Vec<GitStash>> {
+        let git_manager = self.git_manager.read().await;
+        git_manager.stash_list(repo_path)
+    }
+    
+    pub async fn git_stash_apply(&self, repo_path: &PathBuf, index: usize) -> Result<()> {
+        self.git_manager.write().await.stash_apply(repo_path, index)?;
+        self.publish_stash_event(repo_path, "applied", serde_json::json!(index))
+    }
+    
+    pub async fn git_stash_pop(&self, repo_path: &PathBuf, index: usize) -> Result<()> {
+        self.git_manager.write().await.stash_pop(repo_path, index)?;
+        self.publish_stash_event(repo_path, "popped", serde_json::json!(index))
+    }
+    
+    pub async fn git_stash_drop(&self, repo_path: &PathBuf, index: usize) -> Result<()> {
+        self.git_manager.write().await.stash_drop(repo_path, index)?;
+        self.publish_stash_event(repo_path, "dropped", serde_json::json!(index))
+    }
+    
+    /// `stash` is the new stash id when saving and the stash index otherwise.
+    fn publish_stash_event(&self, repo_path: &Path, action: &str, stash: serde_json::Value) -> Result<()> {
+        let event = code_furnace_events::Event::new(
+            "workspace.git.stash",
+            "workspace-manager",
+            serde_json::json!({
+                "repo_path": repo_path,
+                "action": action,
+                "stash": stash
+            }),
+        );
+        self.event_bus.publish(event)?;
+        Ok(())
+    }
+    
     pub async fn git_push(&self, repo_path: &PathBuf, remote: &str, branch: &str) -> Result<()> {
         let git_manager = self.git_manager.read().await;
         git_manager.push(repo_path, remote, branch)
@@ -643,16 +1491,46 @@ impl WorkspaceManager {
         git_manager.pull(repo_path, remote, branch)
     }
     
+    /// Asks the `git-assistant` agent for a commit message, falling back to a
+    /// heuristic one if no agent is configured or its answer is unusable.
     pub async fn generate_ai_commit_message(&self, repo_path: &PathBuf, staged_files: &[String]) -> Result<String> {
-        // Use the AI-powered commit message generation in GitManager
+        if let Some(agent_bridge) = &self.agent_bridge {
+            let request = self.git_manager.read().await.commit_message_request(repo_path, staged_files)?;
+            
+            // Hold the bridge lock only while picking the provider
+            let (pending, registry) = {
+                let agent_bridge = agent_bridge.read().await;
+                (agent_bridge.begin_detached_request(request), agent_bridge.request_registry())
+            };
+            
+            match pending.execute(&registry).await {
+                Ok(response) if response.error.is_none() => {
+                    if let Some(commit_message) = git::usable_commit_message(&response.content) {
+                        return Ok(commit_message.to_string());
+                    }
+                    tracing::debug!("Discarding unusable AI commit message: {:?}", response.content);
+                }
+                Ok(response) => {
+                    tracing::debug!("AI commit message unavailable: {}", response.error.unwrap_or_default());
+                }
+                Err(e) => tracing::warn!("AI commit message request failed: {}", e),
+            }
+        }
+        
         let git_manager = self.git_manager.read().await;
-        git_manager.generate_ai_commit_message(repo_path, staged_files).await
+        git_manager.fallback_commit_message(repo_path, staged_files)
     }
     
     // Quick action to start common project processes
     pub async fn start_project_dev_server(&self, project_id: Uuid) -> Result<Uuid> {
         let project = self.get_project(project_id).await
-            .ok_or_else(|| anyhow::anyhow!("Project not found"))?;
+            .ok_or_else(|| ResourceError::NotFound("Project not found".to_string()))?;
+        
+        // A project detected in a subfolder runs its dev server there
+        let working_directory = match project.config.run_config("dev").and_then(|config| config.cwd.as_ref()) {
+            Some(cwd) => project.path.join(cwd),
+            None => project.path.clone(),
+        };
         
         if let Some(dev_command) = &project.config.dev_command {
             let parts: Vec<&str> = dev_command.split_whitespace().collect();
@@ -661,9 +1539,9 @@ impl WorkspaceManager {
                     format!("{} Dev Server", project.name),
                     command.to_string(),
                     args.iter().map(|s| s.to_string()).collect(),
-                    project.path.clone(),
+                    working_directory,
                     project.config.ports.first().copied(),
-                    project.config.env_vars.clone(),
+                    project.environment(),
                     true, // auto-restart
                 ).await
             } else {
@@ -673,4 +1551,394 @@ impl WorkspaceManager {
             Err(anyhow::anyhow!("No dev command configured for project"))
         }
     }
-}
\ No newline at end of file
+    
+    pub async fn list_run_configs(&self, project_id: Uuid) -> Result<Vec<RunConfig>> {
+        let project = self.get_project(project_id).await
+            .ok_or_else(|| ResourceError::NotFound("Project not found".to_string()))?;
+        Ok(project.config.run_configs)
+    }
+    
+    /// Runs a project's test command and parses the results; see `TaskRunner`.
+    pub async fn run_tests(&self, project_id: Uuid) -> Result<TaskOutput> {
+        self.run_task(project_id, TaskKind::Test).await
+    }
+    
+    /// Runs a project's build command and parses its diagnostics.
+    pub async fn run_build(&self, project_id: Uuid) -> Result<TaskOutput> {
+        self.run_task(project_id, TaskKind::Build).await
+    }
+    
+    async fn run_task(&self, project_id: Uuid, kind: TaskKind) -> Result<TaskOutput> {
+        let project = self.get_project(project_id).await
+            .ok_or_else(|| ResourceError::NotFound("Project not found".to_string()))?;
+        self.task_runner.run(&project, kind).await
+    }
+    
+    /// Starts the run configuration `config_name` of a project as a
+    /// background process.
+    pub async fn start_project_process(&self, project_id: Uuid, config_name: &str) -> Result<Uuid> {
+        let project = self.get_project(project_id).await
+            .ok_or_else(|| ResourceError::NotFound("Project not found".to_string()))?;
+        let config = project
+            .config
+            .run_config(config_name)
+            .ok_or_else(|| anyhow::anyhow!("No run configuration named '{}' in project {}", config_name, project.name))?;
+        
+        self.start_process(Self::run_config_process(&project, config)).await
+    }
+    
+    /// Starts the project's stack, as ordered by `stack_order`, one
+    /// configuration at a time once the one before it is ready. If one fails
+    /// to start, exits or fails its health check, the rest are not started
+    /// and those already running are stopped again. Returns the process ids
+    /// in start order.
+    pub async fn start_project_stack(&self, project_id: Uuid) -> Result<Vec<Uuid>> {
+        let project = self.get_project(project_id).await
+            .ok_or_else(|| ResourceError::NotFound("Project not found".to_string()))?;
+        let order = stack_order(&project.config.run_configs)?;
+        if order.is_empty() {
+            return Err(anyhow::anyhow!("No run configuration in project {} declares depends_on", project.name));
+        }
+        
+        let names: Vec<&str> = order.iter().map(|config| config.name.as_str()).collect();
+        let event = code_furnace_events::Event::new(
+            "workspace.stack.starting",
+            "workspace-manager",
+            serde_json::json!({
+                "project_id": project_id,
+                "configs": names
+            }),
+        );
+        self.event_bus.publish(event)?;
+        
+        let mut started = Vec::new();
+        for config in &order {
+            match self.start_when_ready(Self::run_config_process(&project, config)).await {
+                Ok(process_id) => {
+                    started.push(process_id);
+                    let event = code_furnace_events::Event::new(
+                        "workspace.stack.progress",
+                        "workspace-manager",
+                        serde_json::json!({
+                            "project_id": project_id,
+                            "config": config.name,
+                            "process_id": process_id,
+                            "ready": started.len(),
+                            "total": order.len()
+                        }),
+                    );
+                    self.event_bus.publish(event)?;
+                }
+                Err(e) => {
+                    for process_id in started.iter().rev() {
+                        if let Err(stop_error) = self.stop_background_process(*process_id).await {
+                            tracing::warn!("Failed to stop process {}: {}", process_id, stop_error);
+                        }
+                    }
+                    
+                    let event = code_furnace_events::Event::new(
+                        "workspace.stack.failed",
+                        "workspace-manager",
+                        serde_json::json!({
+                            "project_id": project_id,
+                            "config": config.name,
+                            "error": e.to_string()
+                        }),
+                    );
+                    self.event_bus.publish(event)?;
+                    return Err(e.context(format!("Stack of project {} aborted at '{}'", project.name, config.name)));
+                }
+            }
+        }
+        
+        let event = code_furnace_events::Event::new(
+            "workspace.stack.ready",
+            "workspace-manager",
+            serde_json::json!({
+                "project_id": project_id,
+                "process_ids": started
+            }),
+        );
+        self.event_bus.publish(event)?;
+        
+        Ok(started)
+    }
+    
+    /// Starts `process` and waits for `workspace.process.ready`. A process
+    /// that exits or fails its health check first is stopped and reported
+    /// as an error.
+    async fn start_when_ready(&self, process: BackgroundProcess) -> Result<Uuid> {
+        let process_id = process.id;
+        let name = process.name.clone();
+        // Subscribe first: a process without a health check is ready as soon as it starts
+        let mut events = self.event_bus.subscribe(Some("workspace.process.*"));
+        self.start_process(process).await?;
+        
+        let failure = loop {
+            let event = match events.recv().await {
+                Ok(event) => event,
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => {
+                    let status = self.background_processes.read().await.get(&process_id).map(|process| process.status.clone());
+                    match status {
+                        Some(ProcessStatus::Running) => return Ok(process_id),
+                        Some(ProcessStatus::Starting) => continue,
+                        _ => break format!("{} stopped before it was ready", name),
+                    }
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => break "event bus closed".to_string(),
+            };
+            // `workspace.process.stopped` carries just the id
+            let id = event.data.get("process_id").unwrap_or(&event.data);
+            if *id != serde_json::json!(process_id) {
+                continue;
+            }
+            match event.event_type.as_str() {
+                "workspace.process.ready" => return Ok(process_id),
+                "workspace.process.unhealthy" => {
+                    break format!("{} failed its health check: {}", name, event.data["error"].as_str().unwrap_or_default());
+                }
+                "workspace.process.exited" | "workspace.process.stopped" => break format!("{} stopped before it was ready", name),
+                _ => {}
+            }
+        };
+        
+        self.stop_background_process(process_id).await?;
+        Err(anyhow::anyhow!(failure))
+    }
+    
+    /// A process for `config`, run in the project folder with the project
+    /// environment.
+    fn run_config_process(project: &Project, config: &RunConfig) -> BackgroundProcess {
+        let working_directory = match &config.cwd {
+            Some(cwd) => project.path.join(cwd),
+            None => project.path.clone(),
+        };
+        let mut env_vars = project.environment();
+        env_vars.extend(config.env.clone());
+        
+        BackgroundProcess {
+            id: Uuid::new_v4(),
+            name: format!("{} ({})", project.name, config.name),
+            command: config.command.clone(),
+            args: config.args.clone(),
+            working_directory,
+            status: ProcessStatus::Starting,
+            pid: None,
+            port: config.port,
+            env_vars,
+            logs: Vec::new(),
+            started_at: chrono::Utc::now(),
+            stopped_at: None,
+            auto_restart: false,
+            restart_count: 0,
+            cpu_percent: None,
+            memory_bytes: None,
+            health_check: config.health_check.clone(),
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    
+    fn temp_data_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("code-furnace-workspace-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+    
+    fn is_running(pid: i32) -> bool {
+        // Exited processes may linger as zombies until they are reaped
+        std::fs::read_to_string(format!("/proc/{}/stat", pid))
+            .map(|stat| !stat.rsplit(')').next().unwrap_or_default().trim_start().starts_with('Z'))
+            .unwrap_or(false)
+    }
+    
+    #[tokio::test]
+    async fn stopping_a_process_kills_its_children() {
+        let data_dir = temp_data_dir();
+        let manager = WorkspaceManager::with_data_dir(code_furnace_events::EventBus::new(), Some(data_dir.clone()));
+        let process_id = manager
+            .start_background_process(
+                "shell".to_string(),
+                "sh".to_string(),
+                vec!["-c".to_string(), "sleep 60 & echo $!; wait".to_string()],
+                std::env::temp_dir(),
+                None,
+                HashMap::new(),
+                false,
+            )
+            .await
+            .unwrap();
+        
+        let mut child_pid = None;
+        for _ in 0..50 {
+            child_pid = manager
+                .get_process_logs(process_id, None, false, &LogFilter::default())
+                .await
+                .first()
+                .and_then(|log| log.message.parse::<i32>().ok());
+            if child_pid.is_some() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+        let child_pid = child_pid.expect("shell did not report its child");
+        assert!(is_running(child_pid));
+        
+        manager.stop_background_process(process_id).await.unwrap();
+        
+        for _ in 0..50 {
+            if !is_running(child_pid) {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+        assert!(!is_running(child_pid), "child process {} survived stop", child_pid);
+        
+        std::fs::remove_dir_all(&data_dir).unwrap();
+    }
+    
+    #[tokio::test]
+    async fn stopping_all_processes_stops_each_one() {
+        let data_dir = temp_data_dir();
+        let manager = WorkspaceManager::with_data_dir(code_furnace_events::EventBus::new(), Some(data_dir.clone()));
+        for name in ["first", "second"] {
+            manager
+                .start_background_process(
+                    name.to_string(),
+                    "sleep".to_string(),
+                    vec!["60".to_string()],
+                    std::env::temp_dir(),
+                    None,
+                    HashMap::new(),
+                    true,
+                )
+                .await
+                .unwrap();
+        }
+        
+        manager.stop_all_background_processes().await;
+        
+        let processes = manager.list_background_processes().await;
+        assert_eq!(processes.len(), 2);
+        for process in processes {
+            assert!(matches!(process.status, ProcessStatus::Stopped));
+            assert!(!is_running(process.pid.unwrap() as i32));
+        }
+        assert!(manager.running_processes.read().await.is_empty());
+        
+        std::fs::remove_dir_all(&data_dir).unwrap();
+    }
+    
+    fn sleeper(port: u16, timeout_ms: u64) -> BackgroundProcess {
+        BackgroundProcess {
+            id: Uuid::new_v4(),
+            name: "server".to_string(),
+            command: "sleep".to_string(),
+            args: vec!["60".to_string()],
+            working_directory: std::env::temp_dir(),
+            status: ProcessStatus::Starting,
+            pid: None,
+            port: Some(port),
+            env_vars: HashMap::new(),
+            logs: Vec::new(),
+            started_at: chrono::Utc::now(),
+            stopped_at: None,
+            auto_restart: false,
+            restart_count: 0,
+            cpu_percent: None,
+            memory_bytes: None,
+            health_check: Some(HealthCheck {
+                interval_ms: 50,
+                timeout_ms,
+                ..Default::default()
+            }),
+        }
+    }
+    
+    #[tokio::test]
+    async fn health_check_decides_when_a_process_is_running() {
+        let event_bus = code_furnace_events::EventBus::new().with_event_log(20);
+        let data_dir = temp_data_dir();
+        let manager = WorkspaceManager::with_data_dir(event_bus.clone(), Some(data_dir.clone()));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let open_port = listener.local_addr().unwrap().port();
+        let closed_port = {
+            let unused = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            unused.local_addr().unwrap().port()
+        };
+        
+        let healthy = manager.start_process(sleeper(open_port, 5000)).await.unwrap();
+        let unhealthy = manager.start_process(sleeper(closed_port, 300)).await.unwrap();
+        
+        let status = |process_id| {
+            let manager = &manager;
+            async move { manager.background_processes.read().await[&process_id].status.clone() }
+        };
+        for _ in 0..50 {
+            if matches!(status(healthy).await, ProcessStatus::Running) && matches!(status(unhealthy).await, ProcessStatus::Unhealthy) {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+        assert!(matches!(status(healthy).await, ProcessStatus::Running));
+        assert!(matches!(status(unhealthy).await, ProcessStatus::Unhealthy));
+        
+        let ready = event_bus.replay(None, Some("workspace.process.ready"));
+        assert_eq!(ready.len(), 1);
+        assert_eq!(ready[0].data["process_id"], serde_json::json!(healthy));
+        let failed = event_bus.replay(None, Some("workspace.process.unhealthy"));
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].data["process_id"], serde_json::json!(unhealthy));
+        
+        manager.stop_all_background_processes().await;
+        
+        std::fs::remove_dir_all(&data_dir).unwrap();
+    }
+    
+    #[tokio::test]
+    async fn a_failing_dependency_aborts_the_stack() {
+        let event_bus = code_furnace_events::EventBus::new().with_event_log(50);
+        let data_dir = temp_data_dir();
+        let manager = WorkspaceManager::with_data_dir(event_bus.clone(), Some(data_dir.clone()));
+        let closed_port = {
+            let unused = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            unused.local_addr().unwrap().port()
+        };
+        
+        let mut project = Project::new("stack".to_string(), std::env::temp_dir());
+        let db = RunConfig::from_command_line("db", "sleep 60").unwrap();
+        let mut api = RunConfig::from_command_line("api", "sleep 60").unwrap();
+        api.depends_on = vec!["db".to_string()];
+        api.port = Some(closed_port);
+        api.health_check = Some(HealthCheck {
+            interval_ms: 50,
+            timeout_ms: 200,
+            ..Default::default()
+        });
+        let mut frontend = RunConfig::from_command_line("frontend", "sleep 60").unwrap();
+        frontend.depends_on = vec!["api".to_string()];
+        project.config.run_configs = vec![frontend, api, db];
+        let project_id = project.id;
+        manager.projects.write().await.insert(project_id, project);
+        
+        let error = manager.start_project_stack(project_id).await.unwrap_err();
+        assert!(format!("{:#}", error).contains("failed its health check"), "{:#}", error);
+        
+        let processes = manager.list_background_processes().await;
+        assert_eq!(processes.len(), 2);
+        assert!(processes.iter().all(|process| matches!(process.status, ProcessStatus::Stopped)));
+        assert!(manager.running_processes.read().await.is_empty());
+        
+        let progress = event_bus.replay(None, Some("workspace.stack.progress"));
+        assert_eq!(progress.len(), 1);
+        assert_eq!(progress[0].data["config"], "db");
+        let failed = event_bus.replay(None, Some("workspace.stack.failed"));
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].data["config"], "api");
+        
+        std::fs::remove_dir_all(&data_dir).unwrap();
+    }
+}
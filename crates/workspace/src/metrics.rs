@@ -0,0 +1,182 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Weak;
+use std::time::Duration;
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use crate::{BackgroundProcess, ProcessStatus};
+
+/// How often running background processes are sampled.
+pub const PROCESS_METRICS_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Seconds a process may appear to have started before we spawned it; more
+/// than this and its pid has been reused by another process.
+const START_TIME_TOLERANCE_SECS: i64 = 2;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessMetrics {
+    pub process_id: Uuid,
+    pub pid: u32,
+    /// Share of one CPU core, summed with the processes it started; can
+    /// exceed 100.
+    pub cpu_percent: f32,
+    /// Resident memory of the process and the processes it started.
+    pub memory_bytes: u64,
+}
+
+fn is_running(process: &BackgroundProcess) -> bool {
+    matches!(process.status, ProcessStatus::Running | ProcessStatus::Unhealthy)
+}
+
+/// The child processes of every process, leaving out threads.
+fn children_by_parent(system: &System) -> HashMap<Pid, Vec<Pid>> {
+    let mut children: HashMap<Pid, Vec<Pid>> = HashMap::new();
+    for (child, info) in system.processes() {
+        if let Some(parent) = info.parent().filter(|_| info.thread_kind().is_none()) {
+            children.entry(parent).or_default().push(*child);
+        }
+    }
+    children
+}
+
+/// `pid` followed by every live process it started, directly or through
+/// others, such as the `node` server behind `npm run dev`.
+fn process_tree(children: &HashMap<Pid, Vec<Pid>>, pid: Pid) -> Vec<Pid> {
+    let mut tree = vec![pid];
+    let mut next = 0;
+    while let Some(pid) = tree.get(next).copied() {
+        tree.extend(children.get(&pid).into_iter().flatten());
+        next += 1;
+    }
+    tree
+}
+
+/// CPU and memory of the process started as `pid` and everything it
+/// started, or `None` if the pid is gone or now belongs to another program.
+fn sample(
+    system: &System,
+    children: &HashMap<Pid, Vec<Pid>>,
+    process_id: Uuid,
+    pid: u32,
+    started_at: i64,
+) -> Option<ProcessMetrics> {
+    let leader = system.process(Pid::from_u32(pid))?;
+    if leader.start_time() as i64 + START_TIME_TOLERANCE_SECS < started_at {
+        return None;
+    }
+    
+    let (cpu_percent, memory_bytes) = process_tree(children, Pid::from_u32(pid))
+        .into_iter()
+        .filter_map(|pid| system.process(pid))
+        .fold((0.0, 0), |(cpu, memory), info| (cpu + info.cpu_usage(), memory + info.memory()));
+    Some(ProcessMetrics {
+        process_id,
+        pid,
+        cpu_percent,
+        memory_bytes,
+    })
+}
+
+/// Samples CPU and memory of every running background process, together
+/// with the processes it started, storing the readings on it and publishing
+/// them as `workspace.process.metrics`, until the process table is dropped.
+/// A process whose pid is gone or now belongs to another program has its
+/// readings cleared.
+pub(crate) async fn sample_process_metrics(
+    background_processes: Weak<RwLock<HashMap<Uuid, BackgroundProcess>>>,
+    event_bus: code_furnace_events::EventBus,
+) {
+    let mut system = System::new();
+    let mut interval = tokio::time::interval(PROCESS_METRICS_INTERVAL);
+    
+    loop {
+        interval.tick().await;
+        let Some(background_processes) = background_processes.upgrade() else {
+            return;
+        };
+        
+        // Scan the system without holding the process table, which starting
+        // and stopping processes needs
+        let tracked: Vec<(Uuid, u32, i64)> = background_processes
+            .read()
+            .await
+            .values()
+            .filter(|process| is_running(process))
+            .filter_map(|process| Some((process.id, process.pid?, process.started_at.timestamp())))
+            .collect();
+        let mut samples = Vec::new();
+        if !tracked.is_empty() {
+            // Every process is refreshed so that children can be found
+            system.refresh_processes_specifics(
+                ProcessesToUpdate::All,
+                true,
+                ProcessRefreshKind::nothing().with_cpu().with_memory(),
+            );
+            let children = children_by_parent(&system);
+            samples = tracked
+                .into_iter()
+                .filter_map(|(process_id, pid, started_at)| sample(&system, &children, process_id, pid, started_at))
+                .collect();
+        }
+        
+        let mut published = Vec::new();
+        let mut processes = background_processes.write().await;
+        for process in processes.values_mut() {
+            // A process that stopped or restarted during the scan gets no
+            // reading from its old pid
+            let reading = samples
+                .iter()
+                .find(|sample| sample.process_id == process.id)
+                .filter(|sample| is_running(process) && process.pid == Some(sample.pid));
+            process.cpu_percent = reading.map(|sample| sample.cpu_percent);
+            process.memory_bytes = reading.map(|sample| sample.memory_bytes);
+            published.extend(reading.cloned());
+        }
+        drop(processes);
+        
+        if !published.is_empty() {
+            let event = code_furnace_events::Event::new(
+                "workspace.process.metrics",
+                "workspace-manager",
+                serde_json::json!({
+                    "processes": published
+                }),
+            );
+            let _ = event_bus.publish(event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[cfg(unix)]
+    #[test]
+    fn samples_include_the_processes_a_process_started() {
+        let mut child = std::process::Command::new("sh")
+            .args(["-c", "sleep 30 & sleep 30 & wait"])
+            .spawn()
+            .unwrap();
+        let started_at = chrono::Utc::now().timestamp();
+        std::thread::sleep(Duration::from_millis(200));
+        
+        let mut system = System::new();
+        system.refresh_processes_specifics(ProcessesToUpdate::All, true, ProcessRefreshKind::nothing().with_memory());
+        let children = children_by_parent(&system);
+        let tree = process_tree(&children, Pid::from_u32(child.id()));
+        let sampled = sample(&system, &children, Uuid::new_v4(), child.id(), started_at);
+        
+        let _ = std::process::Command::new("pkill").args(["-P", &child.id().to_string()]).status();
+        let _ = child.kill();
+        let _ = child.wait();
+        
+        assert_eq!(tree.len(), 3);
+        let leader_memory = system.process(Pid::from_u32(child.id())).unwrap().memory();
+        assert!(sampled.unwrap().memory_bytes > leader_memory);
+        // A process that started after the one we spawned is not ours
+        assert!(sample(&system, &children, Uuid::new_v4(), child.id(), started_at + 60).is_none());
+    }
+}
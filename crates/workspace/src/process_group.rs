@@ -0,0 +1,101 @@
+//! Background processes run in their own process group on Unix and in a job
+//! object on Windows, so stopping one also stops everything it started, such
+//! as the `vite` server behind `npm run dev`.
+
+use tokio::process::{Child, Command};
+
+/// Makes the spawned process lead a new process group.
+pub(crate) fn configure(cmd: &mut Command) {
+    #[cfg(unix)]
+    cmd.process_group(0);
+    #[cfg(not(unix))]
+    let _ = cmd;
+}
+
+/// The processes started by one spawned child.
+pub(crate) struct ProcessGroup {
+    #[cfg(unix)]
+    pgid: Option<nix::unistd::Pid>,
+    #[cfg(windows)]
+    job: Option<JobObject>,
+}
+
+impl ProcessGroup {
+    pub(crate) fn attach(child: &Child) -> Self {
+        Self {
+            #[cfg(unix)]
+            pgid: child.id().map(|pid| nix::unistd::Pid::from_raw(pid as i32)),
+            #[cfg(windows)]
+            job: JobObject::assign(child),
+        }
+    }
+    
+    /// Asks every process in the group to exit. Windows has no equivalent,
+    /// so there this does nothing.
+    pub(crate) fn terminate(&self) {
+        #[cfg(unix)]
+        if let Some(pgid) = self.pgid {
+            let _ = nix::sys::signal::killpg(pgid, nix::sys::signal::Signal::SIGTERM);
+        }
+    }
+    
+    /// Kills every process still in the group.
+    pub(crate) fn kill(&self) {
+        #[cfg(unix)]
+        if let Some(pgid) = self.pgid {
+            let _ = nix::sys::signal::killpg(pgid, nix::sys::signal::Signal::SIGKILL);
+        }
+        #[cfg(windows)]
+        if let Some(job) = &self.job {
+            job.terminate();
+        }
+    }
+}
+
+#[cfg(windows)]
+pub(crate) struct JobObject(windows_sys::Win32::Foundation::HANDLE);
+
+// The handle is only passed to thread-safe Win32 calls
+#[cfg(windows)]
+unsafe impl Send for JobObject {}
+#[cfg(windows)]
+unsafe impl Sync for JobObject {}
+
+#[cfg(windows)]
+impl JobObject {
+    /// Puts `child` in a new job object. Processes it started before this
+    /// call are not part of the job.
+    fn assign(child: &Child) -> Option<Self> {
+        use windows_sys::Win32::System::JobObjects::{AssignProcessToJobObject, CreateJobObjectW};
+        
+        let process = child.raw_handle()?;
+        unsafe {
+            let job = CreateJobObjectW(std::ptr::null(), std::ptr::null());
+            if job.is_null() {
+                tracing::warn!("Failed to create a job object: {}", std::io::Error::last_os_error());
+                return None;
+            }
+            let job = JobObject(job);
+            if AssignProcessToJobObject(job.0, process as _) == 0 {
+                tracing::warn!("Failed to assign process to job object: {}", std::io::Error::last_os_error());
+                return None;
+            }
+            Some(job)
+        }
+    }
+    
+    fn terminate(&self) {
+        unsafe {
+            windows_sys::Win32::System::JobObjects::TerminateJobObject(self.0, 1);
+        }
+    }
+}
+
+#[cfg(windows)]
+impl Drop for JobObject {
+    fn drop(&mut self) {
+        unsafe {
+            windows_sys::Win32::Foundation::CloseHandle(self.0);
+        }
+    }
+}
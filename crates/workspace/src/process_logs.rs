@@ -0,0 +1,232 @@
+use anyhow::Result;
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::{LogEntry, LogLevel};
+
+/// A log file is rotated once it grows past this size.
+pub const MAX_LOG_FILE_BYTES: u64 = 5 * 1024 * 1024;
+/// Rotated files kept per process besides the current one.
+pub const MAX_ROTATED_LOG_FILES: usize = 3;
+/// Log files not written to for this long are deleted when the store opens.
+pub const MAX_LOG_AGE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Narrows the entries returned by `get_process_logs`.
+#[derive(Debug, Clone, Default)]
+pub struct LogFilter {
+    /// Keeps entries at this level or more severe.
+    pub level: Option<LogLevel>,
+    /// Keeps entries whose message contains this text, ignoring case.
+    pub contains: Option<String>,
+}
+
+impl LogFilter {
+    pub fn matches(&self, entry: &LogEntry) -> bool {
+        if self.level.is_some_and(|level| entry.level < level) {
+            return false;
+        }
+        match &self.contains {
+            Some(text) => entry.message.to_lowercase().contains(&text.to_lowercase()),
+            None => true,
+        }
+    }
+}
+
+/// Writes background process output to `<process id>.log` files, one JSON
+/// `LogEntry` per line. A full file is renamed to `.log.1`, shifting older
+/// ones up to `.log.N` and deleting the oldest. A file stays open only
+/// while its process is producing output.
+pub struct ProcessLogStore {
+    dir: Option<PathBuf>,
+    open_files: Mutex<HashMap<Uuid, (File, u64)>>,
+}
+
+impl ProcessLogStore {
+    /// Uses `dir`, first deleting the files older than `MAX_LOG_AGE`.
+    pub fn new(dir: PathBuf) -> Self {
+        if let Err(e) = Self::prune(&dir, MAX_LOG_AGE) {
+            tracing::warn!("Failed to delete old process logs: {}", e);
+        }
+        Self {
+            dir: Some(dir),
+            open_files: Mutex::new(HashMap::new()),
+        }
+    }
+    
+    /// A store that keeps nothing, for when there is no data directory.
+    pub fn disabled() -> Self {
+        Self {
+            dir: None,
+            open_files: Mutex::new(HashMap::new()),
+        }
+    }
+    
+    fn log_path(dir: &Path, process_id: Uuid, generation: usize) -> PathBuf {
+        match generation {
+            0 => dir.join(format!("{}.log", process_id)),
+            n => dir.join(format!("{}.log.{}", process_id, n)),
+        }
+    }
+    
+    pub fn append(&self, process_id: Uuid, entry: &LogEntry) -> Result<()> {
+        let Some(dir) = &self.dir else {
+            return Ok(());
+        };
+        
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+        
+        let mut open_files = self.open_files.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((_, size)) = open_files.get(&process_id) {
+            if size + line.len() as u64 > MAX_LOG_FILE_BYTES {
+                open_files.remove(&process_id);
+                Self::rotate(dir, process_id)?;
+            }
+        }
+        let (file, size) = match open_files.entry(process_id) {
+            std::collections::hash_map::Entry::Occupied(entry) => entry.into_mut(),
+            std::collections::hash_map::Entry::Vacant(entry) => {
+                std::fs::create_dir_all(dir)?;
+                let file = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(Self::log_path(dir, process_id, 0))?;
+                let size = file.metadata()?.len();
+                entry.insert((file, size))
+            }
+        };
+        file.write_all(line.as_bytes())?;
+        *size += line.len() as u64;
+        Ok(())
+    }
+    
+    /// Releases the open log file of a process, e.g. once its output ends.
+    /// A later `append` opens it again.
+    pub fn close(&self, process_id: Uuid) {
+        self.open_files.lock().unwrap_or_else(|e| e.into_inner()).remove(&process_id);
+    }
+    
+    fn prune(dir: &Path, max_age: Duration) -> Result<()> {
+        if !dir.exists() {
+            return Ok(());
+        }
+        
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            if !entry.file_name().to_string_lossy().contains(".log") {
+                continue;
+            }
+            let age = entry.metadata()?.modified()?.elapsed().unwrap_or_default();
+            if age > max_age {
+                std::fs::remove_file(entry.path())?;
+            }
+        }
+        Ok(())
+    }
+    
+    fn rotate(dir: &Path, process_id: Uuid) -> Result<()> {
+        let oldest = Self::log_path(dir, process_id, MAX_ROTATED_LOG_FILES);
+        if oldest.exists() {
+            std::fs::remove_file(&oldest)?;
+        }
+        for generation in (0..MAX_ROTATED_LOG_FILES).rev() {
+            let path = Self::log_path(dir, process_id, generation);
+            if path.exists() {
+                std::fs::rename(&path, Self::log_path(dir, process_id, generation + 1))?;
+            }
+        }
+        Ok(())
+    }
+    
+    /// Every entry still on disk, oldest first. Lines that fail to parse are
+    /// skipped.
+    pub fn read(&self, process_id: Uuid) -> Result<Vec<LogEntry>> {
+        let Some(dir) = &self.dir else {
+            return Ok(Vec::new());
+        };
+        
+        let mut entries = Vec::new();
+        for generation in (0..=MAX_ROTATED_LOG_FILES).rev() {
+            let path = Self::log_path(dir, process_id, generation);
+            let Ok(file) = File::open(&path) else {
+                continue;
+            };
+            for line in BufReader::new(file).lines() {
+                if let Ok(entry) = serde_json::from_str(&line?) {
+                    entries.push(entry);
+                }
+            }
+        }
+        Ok(entries)
+    }
+    
+    /// Deletes the log files of a process.
+    pub fn clear(&self, process_id: Uuid) -> Result<()> {
+        let Some(dir) = &self.dir else {
+            return Ok(());
+        };
+        
+        self.open_files.lock().unwrap_or_else(|e| e.into_inner()).remove(&process_id);
+        for generation in 0..=MAX_ROTATED_LOG_FILES {
+            let path = Self::log_path(dir, process_id, generation);
+            if path.exists() {
+                std::fs::remove_file(&path)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn entry(message: &str) -> LogEntry {
+        LogEntry {
+            timestamp: chrono::Utc::now(),
+            level: LogLevel::Info,
+            message: message.to_string(),
+        }
+    }
+    
+    #[test]
+    fn closed_logs_reopen_on_append() {
+        let dir = std::env::temp_dir().join(format!("code-furnace-logs-{}", Uuid::new_v4()));
+        let store = ProcessLogStore::new(dir.clone());
+        let process_id = Uuid::new_v4();
+        
+        store.append(process_id, &entry("first")).unwrap();
+        store.close(process_id);
+        assert!(store.open_files.lock().unwrap().is_empty());
+        store.append(process_id, &entry("second")).unwrap();
+        
+        let messages: Vec<_> = store.read(process_id).unwrap().into_iter().map(|entry| entry.message).collect();
+        assert_eq!(messages, vec!["first", "second"]);
+        
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+    
+    #[test]
+    fn old_logs_are_pruned_on_open() {
+        let dir = std::env::temp_dir().join(format!("code-furnace-logs-{}", Uuid::new_v4()));
+        let (old, recent) = (Uuid::new_v4(), Uuid::new_v4());
+        {
+            let store = ProcessLogStore::new(dir.clone());
+            store.append(old, &entry("old")).unwrap();
+            store.append(recent, &entry("recent")).unwrap();
+        }
+        let old_file = File::options().write(true).open(dir.join(format!("{}.log", old))).unwrap();
+        old_file.set_modified(std::time::SystemTime::now() - MAX_LOG_AGE * 2).unwrap();
+        
+        let store = ProcessLogStore::new(dir.clone());
+        assert!(store.read(old).unwrap().is_empty());
+        assert_eq!(store.read(recent).unwrap().len(), 1);
+        
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
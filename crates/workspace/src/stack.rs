@@ -0,0 +1,106 @@
+use anyhow::Result;
+use std::collections::HashMap;
+
+use crate::RunConfig;
+
+/// The run configurations making up a project's stack, each after
+/// everything it depends on. The stack is every configuration that declares
+/// `depends_on` together with the configurations those depend on; otherwise
+/// configurations keep their declared order. Fails on an unknown dependency
+/// or a dependency cycle.
+pub fn stack_order(configs: &[RunConfig]) -> Result<Vec<&RunConfig>> {
+    let by_name: HashMap<&str, &RunConfig> = configs.iter().map(|config| (config.name.as_str(), config)).collect();
+    for config in configs {
+        for dependency in &config.depends_on {
+            if !by_name.contains_key(dependency.as_str()) {
+                return Err(anyhow::anyhow!(
+                    "Run configuration '{}' depends on '{}', which does not exist",
+                    config.name,
+                    dependency
+                ));
+            }
+        }
+    }
+    
+    let mut order = Vec::new();
+    let mut visiting = Vec::new();
+    for config in configs.iter().filter(|config| !config.depends_on.is_empty()) {
+        visit(config, &by_name, &mut visiting, &mut order)?;
+    }
+    Ok(order)
+}
+
+/// Depth-first: appends the dependencies of `config`, then `config` itself.
+/// `visiting` holds the chain being followed, which is the cycle once a
+/// configuration shows up in it again.
+fn visit<'a>(
+    config: &'a RunConfig,
+    by_name: &HashMap<&str, &'a RunConfig>,
+    visiting: &mut Vec<&'a str>,
+    order: &mut Vec<&'a RunConfig>,
+) -> Result<()> {
+    if order.iter().any(|done| done.name == config.name) {
+        return Ok(());
+    }
+    if let Some(start) = visiting.iter().position(|name| *name == config.name) {
+        let mut cycle = visiting[start..].to_vec();
+        cycle.push(&config.name);
+        return Err(anyhow::anyhow!(
+            "Run configurations depend on each other in a cycle: {}",
+            cycle.join(" -> ")
+        ));
+    }
+    
+    visiting.push(&config.name);
+    for dependency in &config.depends_on {
+        visit(by_name[dependency.as_str()], by_name, visiting, order)?;
+    }
+    visiting.pop();
+    order.push(config);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn config(name: &str, depends_on: &[&str]) -> RunConfig {
+        let mut config = RunConfig::from_command_line(name, "true").unwrap();
+        config.depends_on = depends_on.iter().map(|name| name.to_string()).collect();
+        config
+    }
+    
+    fn names(configs: Vec<&RunConfig>) -> Vec<&str> {
+        configs.into_iter().map(|config| config.name.as_str()).collect()
+    }
+    
+    #[test]
+    fn dependencies_come_first() {
+        let configs = [
+            config("frontend", &["api"]),
+            config("test", &[]),
+            config("api", &["db", "cache"]),
+            config("cache", &[]),
+            config("db", &[]),
+        ];
+        assert_eq!(names(stack_order(&configs).unwrap()), vec!["db", "cache", "api", "frontend"]);
+    }
+    
+    #[test]
+    fn cycles_are_reported() {
+        let configs = [
+            config("db", &[]),
+            config("api", &["db", "worker"]),
+            config("worker", &["api"]),
+        ];
+        let error = stack_order(&configs).unwrap_err().to_string();
+        assert!(error.ends_with("api -> worker -> api"), "{}", error);
+    }
+    
+    #[test]
+    fn unknown_dependencies_are_reported() {
+        let configs = [config("api", &["db"])];
+        let error = stack_order(&configs).unwrap_err().to_string();
+        assert!(error.contains("'api' depends on 'db'"), "{}", error);
+    }
+}
@@ -0,0 +1,776 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::Command;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use crate::Project;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskKind {
+    Test,
+    Build,
+}
+
+impl TaskKind {
+    /// The run configuration the task runs.
+    pub fn name(self) -> &'static str {
+        match self {
+            TaskKind::Test => "test",
+            TaskKind::Build => "build",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TestStatus {
+    Passed,
+    Failed,
+    Ignored,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestResult {
+    pub name: String,
+    pub status: TestStatus,
+    /// What the runner printed about a failure, when it printed anything.
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TestSummary {
+    pub passed: usize,
+    pub failed: usize,
+    /// Skipped, ignored and todo tests.
+    pub ignored: usize,
+}
+
+impl TestSummary {
+    fn add(&mut self, other: &TestSummary) {
+        self.passed += other.passed;
+        self.failed += other.failed;
+        self.ignored += other.ignored;
+    }
+    
+    fn count(tests: &[TestResult]) -> Self {
+        let count = |status| tests.iter().filter(|test| test.status == status).count();
+        Self {
+            passed: count(TestStatus::Passed),
+            failed: count(TestStatus::Failed),
+            ignored: count(TestStatus::Ignored),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiagnosticSeverity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BuildDiagnostic {
+    /// The tool that reported it: `cargo`, `tsc` or `pytest`.
+    pub source: String,
+    pub severity: DiagnosticSeverity,
+    pub message: String,
+    /// Compiler error code such as `E0425`, or the lint name.
+    pub code: Option<String>,
+    pub file: Option<PathBuf>,
+    /// 1-based, as the compiler prints them.
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskOutput {
+    pub task_id: Uuid,
+    pub kind: TaskKind,
+    pub command: String,
+    pub success: bool,
+    pub exit_code: Option<i32>,
+    pub duration_ms: u64,
+    /// Empty for builds, and for test runners whose output is not recognised.
+    pub tests: Vec<TestResult>,
+    /// Totals from the runner's summary lines, or counted from `tests` when
+    /// it printed none.
+    pub summary: Option<TestSummary>,
+    pub diagnostics: Vec<BuildDiagnostic>,
+    /// Everything the command printed, stdout and stderr interleaved.
+    pub log: String,
+}
+
+/// Runs a project's `test` or `build` run configuration to completion and
+/// parses what it printed. Publishes `workspace.task.started`, a
+/// `workspace.task.output` per line, and `workspace.task.finished`.
+pub struct TaskRunner {
+    event_bus: code_furnace_events::EventBus,
+}
+
+impl TaskRunner {
+    pub fn new(event_bus: code_furnace_events::EventBus) -> Self {
+        Self { event_bus }
+    }
+    
+    pub async fn run(&self, project: &Project, kind: TaskKind) -> Result<TaskOutput> {
+        let run_config = project
+            .config
+            .run_config(kind.name())
+            .ok_or_else(|| anyhow::anyhow!("No {} command configured for project", kind.name()))?;
+        let working_directory = match &run_config.cwd {
+            Some(cwd) => project.path.join(cwd),
+            None => project.path.clone(),
+        };
+        let command_line = std::iter::once(&run_config.command)
+            .chain(&run_config.args)
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(" ");
+        
+        let mut cmd = Command::new(&run_config.command);
+        cmd.args(&run_config.args)
+            .current_dir(&working_directory)
+            .envs(project.environment())
+            .envs(&run_config.env)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .stdin(Stdio::null())
+            .kill_on_drop(true);
+        
+        let task_id = Uuid::new_v4();
+        let started_at = std::time::Instant::now();
+        let mut child = cmd.spawn()?;
+        
+        self.publish("workspace.task.started", serde_json::json!({
+            "task_id": task_id,
+            "project_id": project.id,
+            "kind": kind,
+            "command": command_line
+        }))?;
+        
+        let (line_tx, mut line_rx) = mpsc::unbounded_channel();
+        if let Some(stdout) = child.stdout.take() {
+            tokio::spawn(forward_lines(stdout, "stdout", line_tx.clone()));
+        }
+        if let Some(stderr) = child.stderr.take() {
+            tokio::spawn(forward_lines(stderr, "stderr", line_tx));
+        }
+        
+        let mut log = String::new();
+        while let Some((stream, line)) = line_rx.recv().await {
+            let line = strip_ansi(&line);
+            log.push_str(&line);
+            log.push('\n');
+            self.publish("workspace.task.output", serde_json::json!({
+                "task_id": task_id,
+                "stream": stream,
+                "line": line
+            }))?;
+        }
+        let status = child.wait().await?;
+        
+        let (tests, summary) = match kind {
+            TaskKind::Test => parse_test_output(&log),
+            TaskKind::Build => (Vec::new(), None),
+        };
+        let mut diagnostics = parse_build_output(&log);
+        for diagnostic in &mut diagnostics {
+            if let Some(file) = &mut diagnostic.file {
+                *file = working_directory.join(&*file);
+            }
+        }
+        
+        let output = TaskOutput {
+            task_id,
+            kind,
+            command: command_line,
+            success: status.success(),
+            exit_code: status.code(),
+            duration_ms: started_at.elapsed().as_millis() as u64,
+            tests,
+            summary,
+            diagnostics,
+            log,
+        };
+        
+        let count = |severity| output.diagnostics.iter().filter(|d| d.severity == severity).count();
+        self.publish("workspace.task.finished", serde_json::json!({
+            "task_id": task_id,
+            "project_id": project.id,
+            "kind": kind,
+            "success": output.success,
+            "exit_code": output.exit_code,
+            "duration_ms": output.duration_ms,
+            "summary": output.summary,
+            "errors": count(DiagnosticSeverity::Error),
+            "warnings": count(DiagnosticSeverity::Warning)
+        }))?;
+        
+        Ok(output)
+    }
+    
+    fn publish(&self, event_type: &str, data: serde_json::Value) -> Result<()> {
+        let event = code_furnace_events::Event::new(event_type, "workspace-manager", data);
+        self.event_bus.publish(event)
+    }
+}
+
+async fn forward_lines(
+    reader: impl AsyncRead + Unpin,
+    stream: &'static str,
+    line_tx: mpsc::UnboundedSender<(&'static str, String)>,
+) {
+    let mut lines = BufReader::new(reader).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        if line_tx.send((stream, line)).is_err() {
+            return;
+        }
+    }
+}
+
+/// Removes terminal colour and cursor sequences, which some runners print
+/// even when their output is not a terminal.
+fn strip_ansi(line: &str) -> String {
+    let mut stripped = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            stripped.push(c);
+            continue;
+        }
+        if chars.next() == Some('[') {
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        }
+    }
+    stripped
+}
+
+/// Picks test results out of cargo test, jest and pytest output. Summary
+/// lines are added up, since cargo prints one per test binary.
+pub fn parse_test_output(log: &str) -> (Vec<TestResult>, Option<TestSummary>) {
+    let mut tests = Vec::new();
+    let mut summary: Option<TestSummary> = None;
+    // A cargo `---- name stdout ----` section and the lines in it so far
+    let mut failure: Option<(&str, Vec<&str>)> = None;
+    
+    for line in log.lines() {
+        let line = line.trim_end();
+        
+        if let Some(name) = line.strip_prefix("---- ").and_then(|rest| rest.strip_suffix(" stdout ----")) {
+            if let Some((name, output)) = failure.replace((name, Vec::new())) {
+                record_failure(&mut tests, name, &output.join("\n"));
+            }
+            continue;
+        }
+        if let Some((name, output)) = &mut failure {
+            if line != "failures:" && !line.starts_with("test result:") {
+                output.push(line);
+                continue;
+            }
+            record_failure(&mut tests, name, &output.join("\n"));
+            failure = None;
+        }
+        
+        if let Some(test) = parse_cargo_test(line)
+            .or_else(|| parse_jest_test(line))
+            .or_else(|| parse_pytest_test(line))
+        {
+            tests.push(test);
+        } else if let Some((name, message)) = parse_pytest_failure(line) {
+            record_failure(&mut tests, name, message);
+        } else if let Some(counts) = parse_cargo_summary(line)
+            .or_else(|| parse_jest_summary(line))
+            .or_else(|| parse_pytest_summary(line))
+        {
+            summary.get_or_insert_with(TestSummary::default).add(&counts);
+        }
+    }
+    if let Some((name, output)) = failure {
+        record_failure(&mut tests, name, &output.join("\n"));
+    }
+    
+    let summary = summary.or_else(|| (!tests.is_empty()).then(|| TestSummary::count(&tests)));
+    (tests, summary)
+}
+
+/// Attaches `message` to the failed test `name`, adding the test if its
+/// result line was not printed.
+fn record_failure(tests: &mut Vec<TestResult>, name: &str, message: &str) {
+    let message = Some(message.trim().to_string()).filter(|message| !message.is_empty());
+    match tests.iter_mut().find(|test| test.name == name) {
+        Some(test) => {
+            test.status = TestStatus::Failed;
+            test.message = message.or(test.message.take());
+        }
+        None => tests.push(TestResult {
+            name: name.to_string(),
+            status: TestStatus::Failed,
+            message,
+        }),
+    }
+}
+
+fn test_result(name: &str, status: TestStatus) -> TestResult {
+    TestResult {
+        name: name.to_string(),
+        status,
+        message: None,
+    }
+}
+
+/// `test module::name ... ok`, `... FAILED` or `... ignored`.
+fn parse_cargo_test(line: &str) -> Option<TestResult> {
+    let (name, outcome) = line.strip_prefix("test ")?.rsplit_once(" ... ")?;
+    let status = match outcome {
+        "ok" => TestStatus::Passed,
+        "FAILED" => TestStatus::Failed,
+        outcome if outcome.starts_with("ignored") => TestStatus::Ignored,
+        _ => return None,
+    };
+    Some(test_result(name, status))
+}
+
+/// `✓ name (3 ms)`, `✕ name` or `○ skipped name`.
+fn parse_jest_test(line: &str) -> Option<TestResult> {
+    let line = line.trim_start();
+    let mut chars = line.chars();
+    let status = match chars.next()? {
+        '✓' | '√' => TestStatus::Passed,
+        '✕' | '×' => TestStatus::Failed,
+        '○' => TestStatus::Ignored,
+        _ => return None,
+    };
+    let name = chars.as_str().strip_prefix(' ')?;
+    let name = match status {
+        TestStatus::Ignored => name.strip_prefix("skipped ").or_else(|| name.strip_prefix("todo ")).unwrap_or(name),
+        _ => name.rsplit_once(" (").filter(|(_, time)| time.ends_with(" ms)")).map_or(name, |(name, _)| name),
+    };
+    Some(test_result(name, status))
+}
+
+/// `tests/test_math.py::test_add PASSED  [ 50%]`, printed with `-v`.
+fn parse_pytest_test(line: &str) -> Option<TestResult> {
+    let mut parts = line.split_whitespace();
+    let name = parts.next().filter(|name| name.contains("::"))?;
+    let status = match parts.next()? {
+        "PASSED" | "XPASS" => TestStatus::Passed,
+        "FAILED" | "ERROR" => TestStatus::Failed,
+        "SKIPPED" | "XFAIL" => TestStatus::Ignored,
+        _ => return None,
+    };
+    Some(test_result(name, status))
+}
+
+/// `FAILED tests/test_math.py::test_sub - assert 1 == 2` from pytest's
+/// short summary.
+fn parse_pytest_failure(line: &str) -> Option<(&str, &str)> {
+    let rest = line.strip_prefix("FAILED ").or_else(|| line.strip_prefix("ERROR "))?;
+    let (name, message) = rest.split_once(" - ").unwrap_or((rest, ""));
+    name.contains("::").then_some((name, message))
+}
+
+/// Counts in lists such as `3 passed; 1 failed` or `1 failed, 2 passed`.
+/// Parts that are not counts are skipped.
+fn parse_counts(text: &str) -> Option<TestSummary> {
+    let mut summary = TestSummary::default();
+    let mut found = false;
+    for part in text.split([',', ';']) {
+        let mut words = part.split_whitespace();
+        let (Some(count), Some(label)) = (words.next(), words.next()) else {
+            continue;
+        };
+        let Ok(count) = count.parse::<usize>() else {
+            continue;
+        };
+        match label {
+            "passed" => summary.passed += count,
+            "failed" | "error" | "errors" => summary.failed += count,
+            "ignored" | "skipped" | "todo" | "xfailed" => summary.ignored += count,
+            _ => continue,
+        }
+        found = true;
+    }
+    found.then_some(summary)
+}
+
+/// `test result: ok. 3 passed; 0 failed; 1 ignored; ...`
+fn parse_cargo_summary(line: &str) -> Option<TestSummary> {
+    let (_, counts) = line.strip_prefix("test result: ")?.split_once(". ")?;
+    parse_counts(counts)
+}
+
+/// `Tests:       1 failed, 5 passed, 6 total`
+fn parse_jest_summary(line: &str) -> Option<TestSummary> {
+    parse_counts(line.trim_start().strip_prefix("Tests:")?)
+}
+
+/// `===== 1 failed, 3 passed in 0.12s =====`
+fn parse_pytest_summary(line: &str) -> Option<TestSummary> {
+    let text = line.strip_prefix('=')?.trim_matches(|c| c == '=' || c == ' ');
+    let (counts, _) = text.rsplit_once(" in ")?;
+    parse_counts(counts)
+}
+
+/// Picks compiler errors out of build and test output: rustc and cargo
+/// diagnostics with their primary location, tsc errors, and the assertion
+/// locations of failed pytest tests. Cargo's closing "could not compile"
+/// and warning-count lines are left out.
+pub fn parse_build_output(log: &str) -> Vec<BuildDiagnostic> {
+    let mut diagnostics: Vec<BuildDiagnostic> = Vec::new();
+    let mut expecting_location = false;
+    // The `E   ...` lines pytest prints above a failure's location
+    let mut pytest_error: Vec<&str> = Vec::new();
+    
+    for line in log.lines() {
+        let line = line.trim_end();
+        if let Some(diagnostic) = parse_rustc_header(line) {
+            diagnostics.push(diagnostic);
+            expecting_location = true;
+            continue;
+        }
+        if expecting_location {
+            if let Some((file, line, column)) = line.trim_start().strip_prefix("--> ").and_then(parse_location) {
+                if let Some(diagnostic) = diagnostics.last_mut() {
+                    diagnostic.file = Some(file);
+                    diagnostic.line = Some(line);
+                    diagnostic.column = Some(column);
+                }
+            }
+        }
+        expecting_location = false;
+        
+        if let Some(diagnostic) = parse_tsc_error(line) {
+            diagnostics.push(diagnostic);
+        } else if let Some(error) = line.strip_prefix("E ") {
+            pytest_error.push(error.trim());
+        } else if let Some(mut diagnostic) = parse_pytest_location(line) {
+            if !pytest_error.is_empty() {
+                diagnostic.message = pytest_error.join("\n");
+            }
+            pytest_error.clear();
+            diagnostics.push(diagnostic);
+        } else if !line.is_empty() {
+            pytest_error.clear();
+        }
+    }
+    diagnostics
+}
+
+/// `error[E0425]: message`, `warning: message`.
+fn parse_rustc_header(line: &str) -> Option<BuildDiagnostic> {
+    let (severity, rest) = match line.strip_prefix("error") {
+        Some(rest) => (DiagnosticSeverity::Error, rest),
+        None => (DiagnosticSeverity::Warning, line.strip_prefix("warning")?),
+    };
+    let (code, message) = match rest.strip_prefix('[') {
+        Some(rest) => {
+            let (code, message) = rest.split_once("]: ")?;
+            (Some(code.to_string()), message)
+        }
+        None => (None, rest.strip_prefix(": ")?),
+    };
+    
+    let is_cargo_summary = message.starts_with("aborting due to")
+        || message.starts_with("could not compile")
+        || message.contains(") generated ");
+    if is_cargo_summary {
+        return None;
+    }
+    
+    Some(BuildDiagnostic {
+        source: "cargo".to_string(),
+        severity,
+        message: message.to_string(),
+        code,
+        file: None,
+        line: None,
+        column: None,
+    })
+}
+
+/// `src/app.ts(3,7): error TS2322: message`, or with `--pretty`,
+/// `src/app.ts:3:7 - error TS2322: message`.
+fn parse_tsc_error(line: &str) -> Option<BuildDiagnostic> {
+    let (file, line_number, column, rest) = match line.split_once(" - ") {
+        Some((location, rest)) if !location.contains(' ') => {
+            let (file, line_number, column) = parse_location(location)?;
+            (file, line_number, column, rest)
+        }
+        _ => {
+            let (location, rest) = line.split_once("): ")?;
+            let (file, position) = location.rsplit_once('(')?;
+            let (line_number, column) = position.split_once(',')?;
+            (PathBuf::from(file), line_number.parse().ok()?, column.parse().ok()?, rest)
+        }
+    };
+    let (severity, rest) = match rest.strip_prefix("error ") {
+        Some(rest) => (DiagnosticSeverity::Error, rest),
+        None => (DiagnosticSeverity::Warning, rest.strip_prefix("warning ")?),
+    };
+    let (code, message) = rest.split_once(": ")?;
+    if !code.starts_with("TS") {
+        return None;
+    }
+    
+    Some(BuildDiagnostic {
+        source: "tsc".to_string(),
+        severity,
+        message: message.to_string(),
+        code: Some(code.to_string()),
+        file: Some(file),
+        line: Some(line_number),
+        column: Some(column),
+    })
+}
+
+/// `tests/test_math.py:5: AssertionError`, where a failed test raised.
+fn parse_pytest_location(line: &str) -> Option<BuildDiagnostic> {
+    let (location, exception) = line.split_once(": ")?;
+    let (file, line_number) = location.rsplit_once(':')?;
+    let is_exception = exception.ends_with("Error") || exception.ends_with("Exception");
+    if !file.ends_with(".py") || !is_exception || exception.contains(' ') {
+        return None;
+    }
+    
+    Some(BuildDiagnostic {
+        source: "pytest".to_string(),
+        severity: DiagnosticSeverity::Error,
+        message: exception.to_string(),
+        code: None,
+        file: Some(PathBuf::from(file)),
+        line: Some(line_number.parse().ok()?),
+        column: None,
+    })
+}
+
+/// `src/main.rs:2:13`
+fn parse_location(location: &str) -> Option<(PathBuf, u32, u32)> {
+    let mut parts = location.rsplitn(3, ':');
+    let column = parts.next()?.parse().ok()?;
+    let line = parts.next()?.parse().ok()?;
+    let file = Path::new(parts.next()?).to_path_buf();
+    Some((file, line, column))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn parses_cargo_test_output() {
+        let log = "\
+running 3 tests
+test math::adds ... ok
+test math::subtracts ... FAILED
+test math::slow ... ignored, takes a minute
+
+failures:
+
+---- math::subtracts stdout ----
+thread 'math::subtracts' panicked at src/math.rs:10:5:
+assertion `left == right` failed
+
+failures:
+    math::subtracts
+
+test result: FAILED. 1 passed; 1 failed; 1 ignored; 0 measured; 0 filtered out; finished in 0.01s
+
+running 1 test
+test src/lib.rs - doc (line 3) ... ok
+
+test result: ok. 1 passed; 0 failed; 0 ignored; 0 measured; 0 filtered out; finished in 0.20s
+";
+        let (tests, summary) = parse_test_output(log);
+        
+        assert_eq!(summary, Some(TestSummary { passed: 2, failed: 1, ignored: 1 }));
+        let statuses: Vec<_> = tests.iter().map(|test| (test.name.as_str(), test.status)).collect();
+        assert_eq!(statuses, vec![
+            ("math::adds", TestStatus::Passed),
+            ("math::subtracts", TestStatus::Failed),
+            ("math::slow", TestStatus::Ignored),
+            ("src/lib.rs - doc (line 3)", TestStatus::Passed),
+        ]);
+        assert_eq!(
+            tests[1].message.as_deref(),
+            Some("thread 'math::subtracts' panicked at src/math.rs:10:5:\nassertion `left == right` failed")
+        );
+    }
+    
+    #[test]
+    fn parses_jest_output() {
+        let log = "\
+PASS src/math.test.js
+  math
+    ✓ adds (3 ms)
+    ✕ subtracts (1 ms)
+    ○ skipped divides
+
+Tests:       1 failed, 1 skipped, 1 passed, 3 total
+";
+        let (tests, summary) = parse_test_output(log);
+        
+        assert_eq!(summary, Some(TestSummary { passed: 1, failed: 1, ignored: 1 }));
+        let statuses: Vec<_> = tests.iter().map(|test| (test.name.as_str(), test.status)).collect();
+        assert_eq!(statuses, vec![
+            ("adds", TestStatus::Passed),
+            ("subtracts", TestStatus::Failed),
+            ("divides", TestStatus::Ignored),
+        ]);
+    }
+    
+    #[test]
+    fn parses_pytest_output() {
+        let log = "\
+============================= test session starts ==============================
+tests/test_math.py::test_add PASSED                                      [ 50%]
+tests/test_math.py::test_sub FAILED                                      [100%]
+=========================== short test summary info ============================
+FAILED tests/test_math.py::test_sub - assert 1 == 2
+========================= 1 failed, 1 passed in 0.12s ==========================
+";
+        let (tests, summary) = parse_test_output(log);
+        
+        assert_eq!(summary, Some(TestSummary { passed: 1, failed: 1, ignored: 0 }));
+        assert_eq!(tests.len(), 2);
+        assert_eq!(tests[1].name, "tests/test_math.py::test_sub");
+        assert_eq!(tests[1].status, TestStatus::Failed);
+        assert_eq!(tests[1].message.as_deref(), Some("assert 1 == 2"));
+    }
+    
+    #[test]
+    fn counts_results_when_there_is_no_summary() {
+        let (_, summary) = parse_test_output("test a ... ok\ntest b ... FAILED\n");
+        assert_eq!(summary, Some(TestSummary { passed: 1, failed: 1, ignored: 0 }));
+        
+        let (tests, summary) = parse_test_output("Compiling app v0.1.0\n");
+        assert!(tests.is_empty());
+        assert_eq!(summary, None);
+    }
+    
+    #[test]
+    fn parses_rustc_diagnostics() {
+        let log = "\
+   Compiling app v0.1.0 (/work/app)
+warning: unused variable: `y`
+ --> src/main.rs:3:9
+  |
+3 |     let y = 2;
+  |         ^ help: prefix it with an underscore: `_y`
+  |
+error[E0425]: cannot find value `x` in this scope
+  --> src/lib.rs:12:13
+   |
+12 |     println!(\"{}\", x);
+   |                    ^ not found in this scope
+error: linking with `cc` failed: exit status: 1
+  |
+  = note: some linker output
+warning: `app` (bin \"app\") generated 1 warning
+error: could not compile `app` (bin \"app\") due to 1 previous error
+";
+        let diagnostics = parse_build_output(log);
+        
+        assert_eq!(diagnostics.len(), 3);
+        assert_eq!(diagnostics[0].source, "cargo");
+        assert_eq!(diagnostics[0].severity, DiagnosticSeverity::Warning);
+        assert_eq!(diagnostics[0].message, "unused variable: `y`");
+        assert_eq!(diagnostics[0].file, Some(PathBuf::from("src/main.rs")));
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (Some(3), Some(9)));
+        
+        assert_eq!(diagnostics[1].severity, DiagnosticSeverity::Error);
+        assert_eq!(diagnostics[1].code.as_deref(), Some("E0425"));
+        assert_eq!(diagnostics[1].file, Some(PathBuf::from("src/lib.rs")));
+        assert_eq!((diagnostics[1].line, diagnostics[1].column), (Some(12), Some(13)));
+        
+        assert_eq!(diagnostics[2].message, "linking with `cc` failed: exit status: 1");
+        assert_eq!(diagnostics[2].file, None);
+    }
+    
+    #[test]
+    fn parses_tsc_errors() {
+        let log = "\
+src/app.ts(3,7): error TS2322: Type 'string' is not assignable to type 'number'.
+src/util.ts:10:1 - error TS2304: Cannot find name 'x'.
+
+10 x;
+   ~
+Found 2 errors in 2 files.
+";
+        let diagnostics = parse_build_output(log);
+        
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].source, "tsc");
+        assert_eq!(diagnostics[0].code.as_deref(), Some("TS2322"));
+        assert_eq!(diagnostics[0].message, "Type 'string' is not assignable to type 'number'.");
+        assert_eq!(diagnostics[0].file, Some(PathBuf::from("src/app.ts")));
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (Some(3), Some(7)));
+        assert_eq!(diagnostics[1].file, Some(PathBuf::from("src/util.ts")));
+        assert_eq!((diagnostics[1].line, diagnostics[1].column), (Some(10), Some(1)));
+    }
+    
+    #[test]
+    fn parses_pytest_failure_locations() {
+        let log = "\
+    def test_sub():
+>       assert 1 == 2
+E       assert 1 == 2
+
+tests/test_math.py:5: AssertionError
+FAILED tests/test_math.py::test_sub - assert 1 == 2
+";
+        let diagnostics = parse_build_output(log);
+        
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].source, "pytest");
+        assert_eq!(diagnostics[0].message, "assert 1 == 2");
+        assert_eq!(diagnostics[0].file, Some(PathBuf::from("tests/test_math.py")));
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (Some(5), None));
+    }
+    
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn runs_the_test_command_and_reports_progress() {
+        let event_bus = code_furnace_events::EventBus::new().with_event_log(20);
+        let mut project = Project::new("tasks".to_string(), std::env::temp_dir());
+        project.config.run_configs = vec![crate::RunConfig {
+            name: "test".to_string(),
+            command: "sh".to_string(),
+            args: vec![
+                "-c".to_string(),
+                "echo 'test a ... ok'; echo 'test result: ok. 1 passed; 0 failed' >&2; exit 3".to_string(),
+            ],
+            env: Default::default(),
+            cwd: None,
+            port: None,
+            health_check: None,
+            depends_on: Vec::new(),
+        }];
+        
+        let output = TaskRunner::new(event_bus.clone()).run(&project, TaskKind::Test).await.unwrap();
+        
+        assert!(!output.success);
+        assert_eq!(output.exit_code, Some(3));
+        assert_eq!(output.summary, Some(TestSummary { passed: 1, failed: 0, ignored: 0 }));
+        assert!(output.log.contains("test a ... ok\n"));
+        assert_eq!(event_bus.replay(None, Some("workspace.task.output")).len(), 2);
+        assert_eq!(event_bus.replay(None, Some("workspace.task.finished")).len(), 1);
+        
+        let error = TaskRunner::new(event_bus).run(&project, TaskKind::Build).await.unwrap_err();
+        assert_eq!(error.to_string(), "No build command configured for project");
+    }
+    
+    #[test]
+    fn strips_colour_codes() {
+        assert_eq!(strip_ansi("\x1b[32m✓\x1b[0m adds"), "✓ adds");
+    }
+}
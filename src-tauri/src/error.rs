@@ -0,0 +1,154 @@
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+
+use crate::{editor, plugins, utils, workspace};
+
+/// Error returned by Tauri commands. It reaches the frontend as
+/// `{ "code": "not_found", "message": "..." }`, so the UI can react to the
+/// kind of failure instead of parsing the message.
+#[derive(Debug, thiserror::Error)]
+pub enum CommandError {
+    #[error("{0}")]
+    NotFound(String),
+    #[error("{0}")]
+    PermissionDenied(String),
+    #[error("{0}")]
+    AlreadyExists(String),
+    /// The arguments were malformed, e.g. an id that is not a UUID.
+    #[error("{0}")]
+    InvalidInput(String),
+    #[error("{0}")]
+    ReadOnly(String),
+    /// The target changed underneath the request, e.g. a merge conflict.
+    #[error("{0}")]
+    Conflict(String),
+    #[error("{0}")]
+    RateLimited(String),
+    #[error("{0}")]
+    Timeout(String),
+    #[error("{0}")]
+    Unsupported(String),
+    #[error("{0}")]
+    Internal(String),
+}
+
+impl CommandError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::NotFound(_) => "not_found",
+            Self::PermissionDenied(_) => "permission_denied",
+            Self::AlreadyExists(_) => "already_exists",
+            Self::InvalidInput(_) => "invalid_input",
+            Self::ReadOnly(_) => "read_only",
+            Self::Conflict(_) => "conflict",
+            Self::RateLimited(_) => "rate_limited",
+            Self::Timeout(_) => "timeout",
+            Self::Unsupported(_) => "unsupported",
+            Self::Internal(_) => "internal",
+        }
+    }
+    
+    pub fn message(&self) -> &str {
+        match self {
+            Self::NotFound(message)
+            | Self::PermissionDenied(message)
+            | Self::AlreadyExists(message)
+            | Self::InvalidInput(message)
+            | Self::ReadOnly(message)
+            | Self::Conflict(message)
+            | Self::RateLimited(message)
+            | Self::Timeout(message)
+            | Self::Unsupported(message)
+            | Self::Internal(message) => message,
+        }
+    }
+    
+    fn from_io(kind: std::io::ErrorKind, message: String) -> Self {
+        match kind {
+            std::io::ErrorKind::NotFound => Self::NotFound(message),
+            std::io::ErrorKind::PermissionDenied => Self::PermissionDenied(message),
+            std::io::ErrorKind::AlreadyExists => Self::AlreadyExists(message),
+            std::io::ErrorKind::TimedOut => Self::Timeout(message),
+            std::io::ErrorKind::InvalidInput | std::io::ErrorKind::InvalidData => Self::InvalidInput(message),
+            _ => Self::Internal(message),
+        }
+    }
+}
+
+impl Serialize for CommandError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut error = serializer.serialize_struct("CommandError", 2)?;
+        error.serialize_field("code", self.code())?;
+        error.serialize_field("message", self.message())?;
+        error.end()
+    }
+}
+
+/// Classifies by the first error in the chain that says what went wrong:
+/// the typed errors the crates return, then I/O, timeout and parse errors.
+/// Anything else is internal.
+impl From<anyhow::Error> for CommandError {
+    fn from(error: anyhow::Error) -> Self {
+        let message = error.to_string();
+        for cause in error.chain() {
+            if let Some(error) = cause.downcast_ref::<utils::ResourceError>() {
+                return match error {
+                    utils::ResourceError::NotFound(_) => Self::NotFound(message),
+                    utils::ResourceError::AccessDenied(_) => Self::PermissionDenied(message),
+                };
+            }
+            if let Some(error) = cause.downcast_ref::<editor::EditorError>() {
+                return match error {
+                    editor::EditorError::ReadOnly(_) => Self::ReadOnly(message),
+                    editor::EditorError::BinaryFile { .. } => Self::Unsupported(message),
+                    editor::EditorError::LspTimeout { .. } => Self::Timeout(message),
+                    editor::EditorError::LspRequestFailed { .. } => Self::Internal(message),
+                };
+            }
+            if let Some(error) = cause.downcast_ref::<workspace::ForgeError>() {
+                return match error {
+                    workspace::ForgeError::NotMergeable(_) | workspace::ForgeError::MergeConflict(_) => {
+                        Self::Conflict(message)
+                    }
+                    workspace::ForgeError::UnsupportedMergeMethod { .. } => Self::Unsupported(message),
+                    workspace::ForgeError::RateLimited { .. } => Self::RateLimited(message),
+                };
+            }
+            if let Some(workspace::GitError::DirtyWorkingTree { .. }) = cause.downcast_ref::<workspace::GitError>() {
+                return Self::Conflict(message);
+            }
+            if let Some(plugins::PluginError::FuelExhausted { .. }) = cause.downcast_ref::<plugins::PluginError>() {
+                return Self::Timeout(message);
+            }
+            if let Some(error) = cause.downcast_ref::<std::io::Error>() {
+                return Self::from_io(error.kind(), message);
+            }
+            if cause.is::<tokio::time::error::Elapsed>() {
+                return Self::Timeout(message);
+            }
+            if cause.is::<uuid::Error>() || cause.is::<serde_json::Error>() {
+                return Self::InvalidInput(message);
+            }
+        }
+        
+        Self::Internal(message)
+    }
+}
+
+impl From<std::io::Error> for CommandError {
+    fn from(error: std::io::Error) -> Self {
+        Self::from_io(error.kind(), error.to_string())
+    }
+}
+
+impl From<uuid::Error> for CommandError {
+    fn from(error: uuid::Error) -> Self {
+        Self::InvalidInput(error.to_string())
+    }
+}
+
+impl From<serde_json::Error> for CommandError {
+    fn from(error: serde_json::Error) -> Self {
+        Self::InvalidInput(error.to_string())
+    }
+}
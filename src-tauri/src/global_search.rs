@@ -0,0 +1,211 @@
+//! One search over everything in the workspace: file names, file contents,
+//! language server symbols and recent commits, for a "search everything"
+//! palette.
+
+use serde::Serialize;
+use std::path::Path;
+use std::time::Duration;
+use tokio::time::Instant;
+
+use crate::{editor, workspace};
+
+/// How long the backends get before the results gathered so far are returned.
+pub const GLOBAL_SEARCH_DEADLINE: Duration = Duration::from_millis(1500);
+pub const DEFAULT_MAX_GLOBAL_RESULTS: usize = 100;
+/// Results kept from each backend before merging.
+const MAX_RESULTS_PER_SOURCE: usize = 50;
+/// Recent commits whose messages are searched.
+const COMMITS_SEARCHED: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchSource {
+    Files,
+    Content,
+    Symbols,
+    Commits,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum GlobalSearchItem {
+    File(editor::FileMatch),
+    Content(editor::SearchMatch),
+    Symbol(editor::LSPWorkspaceSymbol),
+    Commit(workspace::GitCommit),
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GlobalSearchResult {
+    /// Higher is better: how closely the result's name matches the query,
+    /// then its kind, then its rank within its own backend.
+    pub score: u32,
+    #[serde(flatten)]
+    pub item: GlobalSearchItem,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct GlobalSearchResults {
+    /// Best first.
+    pub results: Vec<GlobalSearchResult>,
+    /// Backends that could not run, e.g. with no workspace open or outside a
+    /// git repository.
+    pub unavailable: Vec<SearchSource>,
+    /// Backends the deadline cut short; their results may be missing or partial.
+    pub timed_out: Vec<SearchSource>,
+}
+
+/// Runs every backend at once against `query` and merges what they find by
+/// `GlobalSearchResult::score`, keeping at most `limit`. The searches share
+/// `GLOBAL_SEARCH_DEADLINE`: content and file searches are cancelled then and
+/// keep their partial results, the others are dropped.
+pub async fn global_search(
+    editor_manager: &editor::EditorManager,
+    workspace_manager: &workspace::WorkspaceManager,
+    query: &str,
+    limit: usize,
+) -> GlobalSearchResults {
+    let mut search = GlobalSearchResults::default();
+    let query = query.trim();
+    if query.is_empty() {
+        return search;
+    }
+    
+    let deadline = Instant::now() + GLOBAL_SEARCH_DEADLINE;
+    let files_search_id = uuid::Uuid::new_v4();
+    let content_search_id = uuid::Uuid::new_v4();
+    let cancel_at_deadline = async {
+        tokio::time::sleep_until(deadline).await;
+        editor_manager.cancel_search(files_search_id).await;
+        editor_manager.cancel_search(content_search_id).await;
+    };
+    
+    let content_options = editor::SearchOptions {
+        max_results: Some(MAX_RESULTS_PER_SOURCE),
+        ..Default::default()
+    };
+    let searches = async {
+        tokio::join!(
+            editor_manager.run_find_files(files_search_id, query, MAX_RESULTS_PER_SOURCE),
+            editor_manager.run_search(content_search_id, query, content_options),
+            tokio::time::timeout_at(deadline, editor_manager.search_symbols(query)),
+            tokio::time::timeout_at(deadline, search_commits(editor_manager, workspace_manager, query)),
+        )
+    };
+    tokio::pin!(searches);
+    let (files, content, symbols, commits) = tokio::select! {
+        results = &mut searches => results,
+        // Cancelled searches return what they found so far
+        _ = cancel_at_deadline => searches.await,
+    };
+    
+    let mut ranked = Vec::new();
+    match files {
+        Ok(files) => {
+            if files.cancelled {
+                search.timed_out.push(SearchSource::Files);
+            }
+            ranked.extend(files.matches.into_iter().map(|file| {
+                let name = Path::new(&file.relative_path).file_name().map(|name| name.to_string_lossy().into_owned());
+                (name.unwrap_or_default(), GlobalSearchItem::File(file))
+            }));
+        }
+        Err(e) => search.skip(SearchSource::Files, e),
+    }
+    match content {
+        Ok(content) => {
+            if content.cancelled {
+                search.timed_out.push(SearchSource::Content);
+            }
+            ranked.extend(content.matches.into_iter().map(|found| (found.preview.trim().to_string(), GlobalSearchItem::Content(found))));
+        }
+        Err(e) => search.skip(SearchSource::Content, e),
+    }
+    match symbols {
+        Ok(Ok(symbols)) => ranked.extend(
+            symbols
+                .into_iter()
+                .take(MAX_RESULTS_PER_SOURCE)
+                .map(|symbol| (symbol.name.clone(), GlobalSearchItem::Symbol(symbol))),
+        ),
+        Ok(Err(e)) => search.skip(SearchSource::Symbols, e),
+        Err(_) => search.timed_out.push(SearchSource::Symbols),
+    }
+    match commits {
+        Ok(Ok(commits)) => ranked.extend(commits.into_iter().map(|commit| {
+            let summary = commit.message.lines().next().unwrap_or_default().to_string();
+            (summary, GlobalSearchItem::Commit(commit))
+        })),
+        Ok(Err(e)) => search.skip(SearchSource::Commits, e),
+        Err(_) => search.timed_out.push(SearchSource::Commits),
+    }
+    
+    search.results = rank(query, ranked, limit);
+    search
+}
+
+impl GlobalSearchResults {
+    fn skip(&mut self, source: SearchSource, error: anyhow::Error) {
+        tracing::debug!("Global search skipped {:?}: {}", source, error);
+        self.unavailable.push(source);
+    }
+}
+
+/// Commits among the last `COMMITS_SEARCHED` in the workspace repository
+/// whose message contains `query` or whose hash starts with it.
+async fn search_commits(
+    editor_manager: &editor::EditorManager,
+    workspace_manager: &workspace::WorkspaceManager,
+    query: &str,
+) -> anyhow::Result<Vec<workspace::GitCommit>> {
+    let root = editor_manager
+        .workspace_root()
+        .await
+        .ok_or_else(|| anyhow::anyhow!("No workspace root set"))?;
+    let query = query.to_lowercase();
+    
+    let commits = workspace_manager.git_get_commit_history(&root, Some(COMMITS_SEARCHED)).await?;
+    Ok(commits
+        .into_iter()
+        .filter(|commit| commit.message.to_lowercase().contains(&query) || commit.hash.starts_with(&query))
+        .take(MAX_RESULTS_PER_SOURCE)
+        .collect())
+}
+
+/// Orders results from every backend, given with the name their match is
+/// judged on, each backend's own list being best first.
+fn rank(query: &str, found: Vec<(String, GlobalSearchItem)>, limit: usize) -> Vec<GlobalSearchResult> {
+    let query = query.to_lowercase();
+    let mut rank_in_source = std::collections::HashMap::new();
+    
+    let mut results: Vec<GlobalSearchResult> = found
+        .into_iter()
+        .map(|(name, item)| {
+            let name = name.to_lowercase();
+            let name_match = if name == query {
+                3
+            } else if name.starts_with(&query) {
+                2
+            } else if name.contains(&query) {
+                1
+            } else {
+                0
+            };
+            let kind = match item {
+                GlobalSearchItem::Symbol(_) => 3,
+                GlobalSearchItem::File(_) => 2,
+                GlobalSearchItem::Content(_) => 1,
+                GlobalSearchItem::Commit(_) => 0,
+            };
+            let rank: &mut u32 = rank_in_source.entry(kind).or_default();
+            let score = name_match * 1000 + kind * 100 + 99u32.saturating_sub(*rank);
+            *rank += 1;
+            GlobalSearchResult { score, item }
+        })
+        .collect();
+    
+    // Stable, so equal scores keep the order the backends returned
+    results.sort_by_key(|result| std::cmp::Reverse(result.score));
+    results.truncate(limit);
+    results
+}
@@ -1,8 +1,13 @@
 use std::sync::Arc;
 use tauri::{Manager, State, Emitter};
-use tracing::{info, error};
+use tracing::{info, warn, error};
 use tokio::sync::broadcast;
 
+mod error;
+mod global_search;
+mod session;
+pub use error::CommandError;
+
 // Re-export our crates for easier access
 pub use code_furnace_agents as agents;
 pub use code_furnace_terminal as terminal;
@@ -13,6 +18,12 @@ pub use code_furnace_workspace as workspace;
 pub use code_furnace_plugins as plugins;
 pub use code_furnace_utils as utils;
 
+/// Longest the app waits for `AppState::shutdown` before exiting anyway.
+const SHUTDOWN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Running frontend forwarders, one per subscribed event type or pattern.
+pub type EventForwarders = Arc<std::sync::Mutex<std::collections::HashMap<String, tokio::task::JoinHandle<()>>>>;
+
 // Application state that will be shared across all managers
 #[derive(Clone)]
 pub struct AppState {
@@ -20,85 +31,376 @@ pub struct AppState {
     pub agent_bridge: Arc<tokio::sync::RwLock<agents::AgentBridge>>,
     pub terminal_manager: Arc<terminal::TerminalManager>,
     pub editor_manager: Arc<editor::EditorManager>,
+    pub canvas_manager: Arc<canvas::CanvasManager>,
     pub workspace_manager: Arc<workspace::WorkspaceManager>,
     pub plugin_runtime: Arc<tokio::sync::RwLock<plugins::PluginRuntime>>,
+    pub event_forwarders: EventForwarders,
+    shutdown_started: Arc<std::sync::atomic::AtomicBool>,
 }
 
 impl AppState {
     pub async fn new() -> anyhow::Result<Self> {
-        let event_bus = events::EventBus::new();
+        let config = utils::Config::load().unwrap_or_else(|e| {
+            error!("Failed to load configuration, using defaults: {}", e);
+            utils::Config::default()
+        });
+        let event_bus = events::EventBus::with_capacity(config.event_bus_capacity, config.event_channel_capacity)
+            .with_event_log(config.event_log_size);
         
         // Initialize managers with shared event bus
-        let mut agent_bridge = agents::AgentBridge::new();
-        let terminal_manager = terminal::TerminalManager::new(event_bus.clone());
-        let editor_manager = editor::EditorManager::new(event_bus.clone());
-        let workspace_manager = workspace::WorkspaceManager::new(event_bus.clone());
+        let mut agent_bridge = agents::AgentBridge::new()
+            .with_event_bus(event_bus.clone())
+            .with_diff_source(Arc::new(workspace::GitManager::new()));
+        agent_bridge.set_context_budget(
+            config.agent_context_token_budget,
+            config.agent_summarize_truncated_context,
+        );
+        let terminal_manager = Arc::new(terminal::TerminalManager::with_command_timeout(
+            event_bus.clone(),
+            std::time::Duration::from_secs(config.command_timeout_secs),
+        ));
+        let editor_manager = editor::EditorManager::with_config(event_bus.clone(), config.clone());
+        let canvas_manager = canvas::CanvasManager::new(event_bus.clone());
+        if let Some(workspace_path) = &config.workspace_path {
+            if let Err(e) = canvas_manager.set_project_dir(workspace_path).await {
+                error!("Failed to load canvases: {}", e);
+            }
+        }
         let plugin_runtime = plugins::PluginRuntime::new(event_bus.clone())?;
         
-        // Load configuration and set up agent providers
-        if let Ok(config) = utils::Config::load() {
-            if let Some(api_key) = config.agent_api_key {
-                match config.agent_provider {
-                    utils::AgentProvider::Claude => {
-                        let base_claude = agents::ClaudeProvider::new(api_key.clone());
-                        agent_bridge.register_provider("claude".to_string(), Box::new(base_claude));
-                        agent_bridge.set_default_provider("claude".to_string());
-                        
-                        // Register specialized agents
-                        let agent_types = vec![
-                            agents::AgentType::CodeExplainer,
-                            agents::AgentType::CodeReviewer,
-                            agents::AgentType::TestGenerator,
-                            agents::AgentType::GitAssistant,
-                            agents::AgentType::UIDesigner,
-                            agents::AgentType::SystemArchitect,
-                            agents::AgentType::DocumentationWriter,
-                            agents::AgentType::Debugger,
-                        ];
-                        
-                        for agent_type in agent_types {
-                            let claude_provider = agents::ClaudeProvider::new(api_key.clone());
-                            agent_bridge.register_specialized_agent(agent_type, Box::new(claude_provider));
-                        }
-                    }
-                    utils::AgentProvider::OpenAI => {
-                        let base_openai = agents::OpenAIProvider::new(api_key.clone());
-                        agent_bridge.register_provider("openai".to_string(), Box::new(base_openai));
-                        agent_bridge.set_default_provider("openai".to_string());
-                        
-                        // Register specialized agents
-                        let agent_types = vec![
-                            agents::AgentType::CodeExplainer,
-                            agents::AgentType::CodeReviewer,
-                            agents::AgentType::TestGenerator,
-                            agents::AgentType::GitAssistant,
-                            agents::AgentType::UIDesigner,
-                            agents::AgentType::SystemArchitect,
-                            agents::AgentType::DocumentationWriter,
-                            agents::AgentType::Debugger,
-                        ];
-                        
-                        for agent_type in agent_types {
-                            let openai_provider = agents::OpenAIProvider::new(api_key.clone());
-                            agent_bridge.register_specialized_agent(agent_type, Box::new(openai_provider));
-                        }
-                    }
-                    _ => {
-                        info!("Ollama and other agent providers not yet implemented");
-                    }
-                }
-            }
+        register_agent_tools(&mut agent_bridge, &terminal_manager, None, &config);
+        
+        // Set up agent providers from configuration
+        register_agent_providers(&mut agent_bridge, &config, &event_bus);
+        
+        let agent_bridge = Arc::new(tokio::sync::RwLock::new(agent_bridge));
+        let workspace_manager = workspace::WorkspaceManager::new(event_bus.clone())
+            .with_agent_bridge(agent_bridge.clone());
+        let canvas_manager = canvas_manager.with_agent_bridge(agent_bridge.clone());
+        let editor_manager = Arc::new(editor_manager);
+        let workspace_manager = Arc::new(workspace_manager);
+        
+        // Pick up edits to config.json, whether made here or by hand
+        if let Err(e) = watch_config_file(
+            agent_bridge.clone(),
+            terminal_manager.clone(),
+            editor_manager.clone(),
+            workspace_manager.clone(),
+            event_bus.clone(),
+        ) {
+            error!("Failed to watch the configuration file: {}", e);
         }
         
         Ok(Self {
             event_bus,
-            agent_bridge: Arc::new(tokio::sync::RwLock::new(agent_bridge)),
-            terminal_manager: Arc::new(terminal_manager),
-            editor_manager: Arc::new(editor_manager),
-            workspace_manager: Arc::new(workspace_manager),
+            agent_bridge,
+            terminal_manager,
+            editor_manager,
+            canvas_manager: Arc::new(canvas_manager),
+            workspace_manager,
             plugin_runtime: Arc::new(tokio::sync::RwLock::new(plugin_runtime)),
+            event_forwarders: EventForwarders::default(),
+            shutdown_started: Arc::default(),
         })
     }
+    
+    /// Saves what would be lost and stops what would outlive the app: the
+    /// session snapshot and pending auto-saves are written, conversations and
+    /// projects saved, background processes stopped and language servers shut
+    /// down. Only the first call does anything, and it gives up after
+    /// `SHUTDOWN_TIMEOUT` so exiting cannot hang.
+    pub async fn shutdown(&self) {
+        if self.shutdown_started.swap(true, std::sync::atomic::Ordering::SeqCst) {
+            return;
+        }
+        
+        let shutdown = async {
+            let snapshot = session::SessionSnapshot::capture(
+                &self.editor_manager,
+                &self.terminal_manager,
+                &self.workspace_manager,
+            ).await;
+            if let Err(e) = snapshot.save() {
+                error!("Failed to save the session: {}", e);
+            }
+            if let Err(e) = self.agent_bridge.read().await.save_conversations() {
+                error!("Failed to save conversations on exit: {}", e);
+            }
+            self.workspace_manager.save_projects().await;
+            tokio::join!(
+                self.editor_manager.shutdown(),
+                self.workspace_manager.stop_all_background_processes(),
+            );
+        };
+        match tokio::time::timeout(SHUTDOWN_TIMEOUT, shutdown).await {
+            Ok(()) => info!("Shut down cleanly"),
+            Err(_) => error!("Shutdown did not finish within {:?}; exiting anyway", SHUTDOWN_TIMEOUT),
+        }
+    }
+    
+    /// Reopens what was open when the app last exited, if
+    /// `Config::restore_session` is set. Files that no longer exist are
+    /// skipped and listed in the `session.restored` event.
+    pub async fn restore_session(&self) -> anyhow::Result<()> {
+        if !utils::Config::load()?.restore_session {
+            return Ok(());
+        }
+        let Some(snapshot) = session::SessionSnapshot::load()? else {
+            return Ok(());
+        };
+        let mut restored = session::RestoredSession::default();
+        
+        let project = match snapshot.active_project {
+            Some(project_id) => self.workspace_manager.get_project(project_id).await,
+            None => None,
+        };
+        if let Some(project) = project {
+            match self.activate_project(&project).await {
+                Ok(()) => restored.project = Some(project.id),
+                Err(e) => error!("Failed to reopen project {}: {}", project.name, e),
+            }
+        }
+        
+        for buffer in snapshot.buffers {
+            if !buffer.path.is_file() {
+                warn!("Not reopening {}: the file no longer exists", buffer.path.display());
+                restored.missing_files.push(buffer.path);
+                continue;
+            }
+            match self.editor_manager.open_file(buffer.path.clone()).await {
+                Ok(buffer_id) => {
+                    self.editor_manager.set_cursor_position(buffer_id, buffer.cursor_position).await?;
+                    restored.buffers += 1;
+                }
+                Err(e) => error!("Failed to reopen {}: {}", buffer.path.display(), e),
+            }
+        }
+        
+        for terminal in snapshot.terminals {
+            let name = terminal.name.clone();
+            match self
+                .terminal_manager
+                .create_session(terminal.name, terminal.working_directory, Some(terminal.shell), Default::default())
+                .await
+            {
+                Ok(_) => restored.terminals += 1,
+                Err(e) => error!("Failed to reopen terminal {}: {}", name, e),
+            }
+        }
+        
+        info!(
+            "Restored the previous session: {} files, {} terminals",
+            restored.buffers, restored.terminals
+        );
+        let event = events::Event::new("session.restored", "app", serde_json::to_value(&restored)?);
+        self.event_bus.publish(event)?;
+        Ok(())
+    }
+    
+    /// Makes `project` the active one everywhere: the workspace, the editor's
+    /// root, the canvas directory and the project's config overrides.
+    /// Nothing changes when the project's `.code-furnace.json` is invalid.
+    async fn activate_project(&self, project: &workspace::Project) -> anyhow::Result<()> {
+        check_project_config(&project.path)?;
+        self.workspace_manager.open_project(project.id).await?;
+        self.editor_manager.set_workspace_root(project.path.clone()).await?;
+        self.canvas_manager.set_project_dir(&project.path).await?;
+        self.reload_config().await?;
+        Ok(())
+    }
+    
+    /// Reloads the config for the active project; see `reload_config_from_disk`.
+    async fn reload_config(&self) -> anyhow::Result<utils::Config> {
+        reload_config_from_disk(
+            &self.agent_bridge,
+            &self.terminal_manager,
+            &self.editor_manager,
+            &self.workspace_manager,
+            &self.event_bus,
+        ).await
+    }
+}
+
+/// Fails when the project's `.code-furnace.json` cannot be laid over the
+/// global config, so it is reported before anything switches to the project.
+fn check_project_config(project_root: &std::path::Path) -> anyhow::Result<()> {
+    utils::Config::load()?.merged_with_project(project_root)?;
+    Ok(())
+}
+
+/// Registers every provider `config` sets up, with the specialized agents
+/// built on the default one.
+fn register_agent_providers(
+    agent_bridge: &mut agents::AgentBridge,
+    config: &utils::Config,
+    event_bus: &events::EventBus,
+) {
+    let retry_policy = agents::RetryPolicy::with_max_retries(config.agent_max_retries);
+    let mut registered_providers = Vec::new();
+    for provider_config in &config.providers {
+        let name = provider_config.kind.name();
+        match build_agent_provider(provider_config, &retry_policy, event_bus) {
+            Some(provider) => {
+                agent_bridge.register_provider(name.to_string(), provider);
+                registered_providers.push(name);
+            }
+            None => info!("Skipping the {} agent provider: it is not set up or not yet implemented", name),
+        }
+    }
+    
+    // Specialized agents are keyed by their type, so they all build on
+    // the default provider
+    let default_provider = config
+        .default_provider()
+        .filter(|provider_config| registered_providers.contains(&provider_config.kind.name()));
+    if let Some(provider_config) = default_provider {
+        agent_bridge.set_default_provider(provider_config.kind.name().to_string());
+        
+        // Register specialized agents
+        let agent_types = vec![
+            agents::AgentType::CodeExplainer,
+            agents::AgentType::CodeReviewer,
+            agents::AgentType::TestGenerator,
+            agents::AgentType::GitAssistant,
+            agents::AgentType::UIDesigner,
+            agents::AgentType::SystemArchitect,
+            agents::AgentType::DocumentationWriter,
+            agents::AgentType::Debugger,
+            agents::AgentType::SecurityAuditor,
+            agents::AgentType::PerformanceOptimizer,
+        ];
+        
+        for agent_type in agent_types {
+            if let Some(provider) = build_agent_provider(provider_config, &retry_policy, event_bus) {
+                agent_bridge.register_specialized_agent(agent_type, provider);
+            }
+        }
+    }
+}
+
+/// Agent tools run in `project_root`, or without a project in the
+/// configured workspace or the current directory, and only once the user
+/// allows them. Registering again moves the tools to the new root and
+/// resets their permissions to `config.agent_allowed_tools`.
+fn register_agent_tools(
+    agent_bridge: &mut agents::AgentBridge,
+    terminal_manager: &Arc<terminal::TerminalManager>,
+    project_root: Option<std::path::PathBuf>,
+    config: &utils::Config,
+) {
+    let tool_root = project_root
+        .or_else(|| config.workspace_path.clone())
+        .or_else(|| std::env::current_dir().ok())
+        .unwrap_or_default();
+    agent_bridge.register_tool(agents::ReadFileTool::tool(tool_root.clone()));
+    agent_bridge.register_tool(agents::RunCommandTool::tool(terminal_manager.clone(), tool_root));
+    
+    let names: Vec<String> = agent_bridge.list_tools().into_iter().map(|tool| tool.name).collect();
+    for name in &names {
+        let allowed = config.agent_allowed_tools.contains(name);
+        if let Err(e) = agent_bridge.set_tool_allowed(name, allowed) {
+            error!("Failed to set permission for agent tool {}: {}", name, e);
+        }
+    }
+    for tool in config.agent_allowed_tools.iter().filter(|tool| !names.contains(tool)) {
+        error!("Ignoring unknown allowed agent tool: {}", tool);
+    }
+}
+
+/// Re-reads config.json, with the active project's `.code-furnace.json`
+/// laid over it, and applies it to the running app: agent providers and
+/// tools are registered afresh, the tools rooted at the active project, and
+/// the editor picks up its settings. Publishes `config.reloaded` with the
+/// new config. An invalid config is not applied.
+async fn reload_config_from_disk(
+    agent_bridge: &tokio::sync::RwLock<agents::AgentBridge>,
+    terminal_manager: &Arc<terminal::TerminalManager>,
+    editor_manager: &editor::EditorManager,
+    workspace_manager: &workspace::WorkspaceManager,
+    event_bus: &events::EventBus,
+) -> anyhow::Result<utils::Config> {
+    let mut config = utils::Config::load()?;
+    let project_root = workspace_manager.get_active_project().await.map(|project| project.path);
+    if let Some(project_root) = &project_root {
+        config = config.merged_with_project(project_root)?;
+    }
+    config.validate()?;
+    
+    {
+        let mut agent_bridge = agent_bridge.write().await;
+        agent_bridge.clear_providers();
+        agent_bridge.set_context_budget(
+            config.agent_context_token_budget,
+            config.agent_summarize_truncated_context,
+        );
+        register_agent_providers(&mut agent_bridge, &config, event_bus);
+        register_agent_tools(&mut agent_bridge, terminal_manager, project_root, &config);
+    }
+    editor_manager.update_config(config.clone()).await;
+    
+    let event = events::Event::new("config.reloaded", "app", serde_json::to_value(&config)?);
+    event_bus.publish(event)?;
+    Ok(config)
+}
+
+/// Reloads the config whenever config.json changes on disk, including edits
+/// made outside the app.
+fn watch_config_file(
+    agent_bridge: Arc<tokio::sync::RwLock<agents::AgentBridge>>,
+    terminal_manager: Arc<terminal::TerminalManager>,
+    editor_manager: Arc<editor::EditorManager>,
+    workspace_manager: Arc<workspace::WorkspaceManager>,
+    event_bus: events::EventBus,
+) -> anyhow::Result<()> {
+    let config_file = utils::paths::get_config_dir()?.join("config.json");
+    let (mut watcher, mut changes) = editor::FileWatcher::new(std::time::Duration::from_millis(300))?;
+    watcher.watch(&config_file)?;
+    
+    tokio::spawn(async move {
+        // The watcher stops when dropped, so it lives as long as this task
+        let _watcher = watcher;
+        while let Some(changed) = changes.recv().await {
+            if !changed.iter().any(|path| path.file_name() == config_file.file_name()) {
+                continue;
+            }
+            let reloaded = reload_config_from_disk(
+                &agent_bridge,
+                &terminal_manager,
+                &editor_manager,
+                &workspace_manager,
+                &event_bus,
+            ).await;
+            if let Err(e) = reloaded {
+                error!("Failed to reload configuration: {}", e);
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Builds the agent provider a config entry describes, or `None` if it has
+/// no key or its kind is not implemented yet.
+fn build_agent_provider(
+    provider_config: &utils::ProviderConfig,
+    retry_policy: &agents::RetryPolicy,
+    event_bus: &events::EventBus,
+) -> Option<Box<dyn agents::AgentProvider>> {
+    let api_key = provider_config.api_key.clone()?;
+    match provider_config.kind {
+        utils::AgentProvider::Claude => Some(Box::new(
+            agents::ClaudeProvider::new(api_key)
+                .with_model_settings(&provider_config.model)
+                .with_retry_policy(retry_policy.clone())
+                .with_event_bus(event_bus.clone()),
+        )),
+        utils::AgentProvider::OpenAI => Some(Box::new(
+            agents::OpenAIProvider::new(api_key)
+                .with_model_settings(&provider_config.model)
+                .with_retry_policy(retry_policy.clone())
+                .with_event_bus(event_bus.clone()),
+        )),
+        utils::AgentProvider::Ollama { .. } => None,
+    }
 }
 
 // Tauri command handlers
@@ -107,11 +409,13 @@ async fn create_terminal_session(
     state: State<'_, AppState>,
     name: String,
     working_directory: String,
-) -> Result<String, String> {
+    shell: Option<String>,
+    env: Option<std::collections::HashMap<String, String>>,
+) -> Result<String, CommandError> {
     let working_dir = std::path::PathBuf::from(working_directory);
-    match state.terminal_manager.create_session(name, working_dir).await {
+    match state.terminal_manager.create_session(name, working_dir, shell, env.unwrap_or_default()).await {
         Ok(session_id) => Ok(session_id.to_string()),
-        Err(e) => Err(e.to_string()),
+        Err(e) => Err(e.into()),
     }
 }
 
@@ -120,20 +424,40 @@ async fn execute_terminal_command(
     state: State<'_, AppState>,
     session_id: String,
     command: String,
-) -> Result<String, String> {
-    let session_uuid = uuid::Uuid::parse_str(&session_id).map_err(|e| e.to_string())?;
-    match state.terminal_manager.execute_command(session_uuid, command).await {
+    args: Option<std::collections::HashMap<String, String>>,
+    timeout_secs: Option<u64>,
+) -> Result<String, CommandError> {
+    let session_uuid = uuid::Uuid::parse_str(&session_id)?;
+    let timeout = timeout_secs.map(std::time::Duration::from_secs);
+    match state.terminal_manager.execute_command(session_uuid, command, args.unwrap_or_default(), timeout).await {
         Ok(block_id) => Ok(block_id.to_string()),
-        Err(e) => Err(e.to_string()),
+        Err(e) => Err(e.into()),
     }
 }
 
+#[tauri::command]
+async fn register_terminal_snippet(
+    state: State<'_, AppState>,
+    name: String,
+    template: String,
+    description: Option<String>,
+) -> Result<(), CommandError> {
+    state.terminal_manager.register_snippet(name, template, description).await.map_err(CommandError::from)
+}
+
+#[tauri::command]
+async fn list_terminal_snippets(
+    state: State<'_, AppState>,
+) -> Result<Vec<terminal::CommandSnippet>, CommandError> {
+    Ok(state.terminal_manager.list_snippets().await)
+}
+
 #[tauri::command]
 async fn get_terminal_session(
     state: State<'_, AppState>,
     session_id: String,
-) -> Result<Option<terminal::TerminalSession>, String> {
-    let session_uuid = uuid::Uuid::parse_str(&session_id).map_err(|e| e.to_string())?;
+) -> Result<Option<terminal::TerminalSession>, CommandError> {
+    let session_uuid = uuid::Uuid::parse_str(&session_id)?;
     Ok(state.terminal_manager.get_session(session_uuid).await)
 }
 
@@ -141,11 +465,11 @@ async fn get_terminal_session(
 async fn open_file(
     state: State<'_, AppState>,
     file_path: String,
-) -> Result<String, String> {
+) -> Result<String, CommandError> {
     let path = std::path::PathBuf::from(file_path);
     match state.editor_manager.open_file(path).await {
         Ok(buffer_id) => Ok(buffer_id.to_string()),
-        Err(e) => Err(e.to_string()),
+        Err(e) => Err(e.into()),
     }
 }
 
@@ -153,8 +477,8 @@ async fn open_file(
 async fn get_file_buffer(
     state: State<'_, AppState>,
     buffer_id: String,
-) -> Result<Option<editor::FileBuffer>, String> {
-    let buffer_uuid = uuid::Uuid::parse_str(&buffer_id).map_err(|e| e.to_string())?;
+) -> Result<Option<editor::FileBuffer>, CommandError> {
+    let buffer_uuid = uuid::Uuid::parse_str(&buffer_id)?;
     Ok(state.editor_manager.get_buffer(buffer_uuid).await)
 }
 
@@ -163,34 +487,129 @@ async fn update_file_buffer(
     state: State<'_, AppState>,
     buffer_id: String,
     content: String,
-) -> Result<(), String> {
-    let buffer_uuid = uuid::Uuid::parse_str(&buffer_id).map_err(|e| e.to_string())?;
-    state.editor_manager.update_buffer(buffer_uuid, content).await.map_err(|e| e.to_string())
+) -> Result<(), CommandError> {
+    let buffer_uuid = uuid::Uuid::parse_str(&buffer_id)?;
+    state.editor_manager.update_buffer(buffer_uuid, content).await.map_err(CommandError::from)
 }
 
 #[tauri::command]
 async fn save_file_buffer(
     state: State<'_, AppState>,
     buffer_id: String,
-) -> Result<(), String> {
-    let buffer_uuid = uuid::Uuid::parse_str(&buffer_id).map_err(|e| e.to_string())?;
-    state.editor_manager.save_buffer(buffer_uuid).await.map_err(|e| e.to_string())
+) -> Result<(), CommandError> {
+    let buffer_uuid = uuid::Uuid::parse_str(&buffer_id)?;
+    state.editor_manager.save_buffer(buffer_uuid).await.map_err(CommandError::from)
+}
+
+#[tauri::command]
+async fn set_cursor_position(
+    state: State<'_, AppState>,
+    buffer_id: String,
+    line: usize,
+    column: usize,
+) -> Result<(), CommandError> {
+    let buffer_uuid = uuid::Uuid::parse_str(&buffer_id)?;
+    let cursor_position = editor::CursorPosition { line, column };
+    state.editor_manager.set_cursor_position(buffer_uuid, cursor_position).await.map_err(CommandError::from)
+}
+
+#[tauri::command]
+async fn close_file_buffer(
+    state: State<'_, AppState>,
+    buffer_id: String,
+) -> Result<(), CommandError> {
+    let buffer_uuid = uuid::Uuid::parse_str(&buffer_id)?;
+    state.editor_manager.close_buffer(buffer_uuid).await.map_err(CommandError::from)
 }
 
 #[tauri::command]
 async fn get_file_tree(
     state: State<'_, AppState>,
-) -> Result<Option<editor::FileTreeNode>, String> {
-    state.editor_manager.get_file_tree().await.map_err(|e| e.to_string())
+) -> Result<Option<editor::FileTreeNode>, CommandError> {
+    state.editor_manager.get_file_tree().await.map_err(CommandError::from)
 }
 
 #[tauri::command]
 async fn expand_directory(
     state: State<'_, AppState>,
     path: String,
-) -> Result<Option<editor::FileTreeNode>, String> {
+) -> Result<Option<editor::FileTreeNode>, CommandError> {
     let path_buf = std::path::PathBuf::from(path);
-    state.editor_manager.expand_directory(path_buf).await.map_err(|e| e.to_string())
+    state.editor_manager.expand_directory(path_buf).await.map_err(CommandError::from)
+}
+
+#[tauri::command]
+async fn search_workspace(
+    state: State<'_, AppState>,
+    query: String,
+    options: Option<editor::SearchOptions>,
+    search_id: Option<String>,
+) -> Result<editor::SearchResults, CommandError> {
+    let search_uuid = match search_id {
+        Some(id) => uuid::Uuid::parse_str(&id)?,
+        None => uuid::Uuid::new_v4(),
+    };
+    state.editor_manager.run_search(search_uuid, &query, options.unwrap_or_default()).await.map_err(CommandError::from)
+}
+
+/// Quick-open lookup. Pass a `search_id` to be able to stop a query that
+/// newer typing has made stale with `cancel_workspace_search`.
+#[tauri::command]
+async fn find_files(
+    state: State<'_, AppState>,
+    query: String,
+    limit: Option<usize>,
+    search_id: Option<String>,
+) -> Result<editor::FileMatches, CommandError> {
+    let search_uuid = match search_id {
+        Some(id) => uuid::Uuid::parse_str(&id)?,
+        None => uuid::Uuid::new_v4(),
+    };
+    let limit = limit.unwrap_or(editor::finder::DEFAULT_MAX_FILE_MATCHES);
+    state.editor_manager.run_find_files(search_uuid, &query, limit).await.map_err(CommandError::from)
+}
+
+/// Searches file names, file contents, workspace symbols and recent commits
+/// at once; see `global_search::global_search`.
+#[tauri::command]
+async fn global_search(
+    state: State<'_, AppState>,
+    query: String,
+    limit: Option<usize>,
+) -> Result<global_search::GlobalSearchResults, CommandError> {
+    let limit = limit.unwrap_or(global_search::DEFAULT_MAX_GLOBAL_RESULTS);
+    Ok(global_search::global_search(&state.editor_manager, &state.workspace_manager, &query, limit).await)
+}
+
+/// Recently opened files that still exist, most recent first.
+#[tauri::command]
+async fn get_recent_files(state: State<'_, AppState>) -> Result<Vec<editor::RecentFile>, CommandError> {
+    Ok(state.editor_manager.recent_files())
+}
+
+#[tauri::command]
+async fn clear_recent_files(state: State<'_, AppState>) -> Result<(), CommandError> {
+    state.editor_manager.clear_recent_files();
+    Ok(())
+}
+
+#[tauri::command]
+async fn replace_in_workspace(
+    state: State<'_, AppState>,
+    query: String,
+    replacement: String,
+    options: Option<editor::ReplaceOptions>,
+) -> Result<Vec<editor::FileReplacement>, CommandError> {
+    state.editor_manager.replace_in_files(&query, &replacement, options.unwrap_or_default()).await.map_err(CommandError::from)
+}
+
+#[tauri::command]
+async fn cancel_workspace_search(
+    state: State<'_, AppState>,
+    search_id: String,
+) -> Result<bool, CommandError> {
+    let search_uuid = uuid::Uuid::parse_str(&search_id)?;
+    Ok(state.editor_manager.cancel_search(search_uuid).await)
 }
 
 #[tauri::command]
@@ -198,27 +617,27 @@ async fn create_file(
     state: State<'_, AppState>,
     path: String,
     content: Option<String>,
-) -> Result<(), String> {
+) -> Result<(), CommandError> {
     let path_buf = std::path::PathBuf::from(path);
-    state.editor_manager.create_file(path_buf, content).await.map_err(|e| e.to_string())
+    state.editor_manager.create_file(path_buf, content).await.map_err(CommandError::from)
 }
 
 #[tauri::command]
 async fn create_directory(
     state: State<'_, AppState>,
     path: String,
-) -> Result<(), String> {
+) -> Result<(), CommandError> {
     let path_buf = std::path::PathBuf::from(path);
-    state.editor_manager.create_directory(path_buf).await.map_err(|e| e.to_string())
+    state.editor_manager.create_directory(path_buf).await.map_err(CommandError::from)
 }
 
 #[tauri::command]
 async fn delete_file(
     state: State<'_, AppState>,
     path: String,
-) -> Result<(), String> {
+) -> Result<(), CommandError> {
     let path_buf = std::path::PathBuf::from(path);
-    state.editor_manager.delete_file(path_buf).await.map_err(|e| e.to_string())
+    state.editor_manager.delete_file(path_buf).await.map_err(CommandError::from)
 }
 
 #[tauri::command]
@@ -226,26 +645,31 @@ async fn rename_file(
     state: State<'_, AppState>,
     old_path: String,
     new_path: String,
-) -> Result<(), String> {
+) -> Result<(), CommandError> {
     let old_path_buf = std::path::PathBuf::from(old_path);
     let new_path_buf = std::path::PathBuf::from(new_path);
-    state.editor_manager.rename_file(old_path_buf, new_path_buf).await.map_err(|e| e.to_string())
+    state.editor_manager.rename_file(old_path_buf, new_path_buf).await.map_err(CommandError::from)
 }
 
 #[tauri::command]
 async fn set_workspace_root(
     state: State<'_, AppState>,
     root_path: String,
-) -> Result<(), String> {
+) -> Result<(), CommandError> {
     let path = std::path::PathBuf::from(root_path);
-    state.editor_manager.set_workspace_root(path.clone()).await.map_err(|e| e.to_string())?;
+    state.editor_manager.set_workspace_root(path.clone()).await?;
+    state.canvas_manager.set_project_dir(&path).await?;
     // Also create/open project in workspace manager
     let project_name = path.file_name()
         .unwrap_or_default()
         .to_string_lossy()
         .to_string();
-    let project_id = state.workspace_manager.create_project(project_name, path).await.map_err(|e| e.to_string())?;
-    state.workspace_manager.open_project(project_id).await.map_err(|e| e.to_string())?;
+    let project_id = match state.workspace_manager.find_project_by_path(&path).await {
+        Some(project_id) => project_id,
+        None => state.workspace_manager.create_project(project_name, path).await?,
+    };
+    state.workspace_manager.open_project(project_id).await?;
+    state.reload_config().await?;
     Ok(())
 }
 
@@ -255,24 +679,128 @@ async fn ask_agent(
     prompt: String,
     context_files: Vec<String>,
     agent_type: Option<String>,
-) -> Result<agents::AgentResponse, String> {
-    let mut agent_bridge = state.agent_bridge.write().await;
+    repo_path: Option<String>,
+) -> Result<agents::AgentResponse, CommandError> {
+    let mut context = std::collections::HashMap::new();
+    if let Some(repo_path) = repo_path {
+        context.insert("repo_path".to_string(), serde_json::Value::String(repo_path));
+    }
+    let request = agents::AgentRequest {
+        id: uuid::Uuid::new_v4(),
+        agent_type: agent_type.unwrap_or_default(),
+        prompt,
+        context,
+        files: context_files,
+        tools: Vec::new(),
+        tool_turns: Vec::new(),
+        model: None,
+    };
     
+    // Hold the bridge lock only while recording, not for the provider call
+    let (pending, registry) = {
+        let mut agent_bridge = state.agent_bridge.write().await;
+        let pending = agent_bridge.begin_request(request)?;
+        (pending, agent_bridge.request_registry())
+    };
+    
+    let response = pending.execute(&registry).await?;
+    state.agent_bridge.write().await.finish_request(&pending, &response)?;
+    
+    Ok(response)
+}
+
+/// Starts a streamed agent request and returns its id right away; the response
+/// arrives as `agent.response.chunk` events followed by `agent.response.done`.
+#[tauri::command]
+async fn ask_agent_streaming(
+    state: State<'_, AppState>,
+    prompt: String,
+    context_files: Vec<String>,
+    agent_type: Option<String>,
+    repo_path: Option<String>,
+) -> Result<String, CommandError> {
+    let mut context = std::collections::HashMap::new();
+    if let Some(repo_path) = repo_path {
+        context.insert("repo_path".to_string(), serde_json::Value::String(repo_path));
+    }
     let request = agents::AgentRequest {
         id: uuid::Uuid::new_v4(),
         agent_type: agent_type.unwrap_or_default(),
         prompt,
-        context: std::collections::HashMap::new(),
+        context,
         files: context_files,
+        tools: Vec::new(),
+        tool_turns: Vec::new(),
+        model: None,
+    };
+    let request_id = request.id;
+    
+    let (pending, registry) = {
+        let mut agent_bridge = state.agent_bridge.write().await;
+        let pending = agent_bridge.begin_request(request)?;
+        (pending, agent_bridge.request_registry())
     };
     
-    agent_bridge.process_request(request).await.map_err(|e| e.to_string())
+    let agent_bridge = state.agent_bridge.clone();
+    let event_bus = state.event_bus.clone();
+    tokio::spawn(async move {
+        let result = match pending.execute_streaming(&registry, &event_bus).await {
+            Ok(response) => agent_bridge.write().await.finish_request(&pending, &response),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            error!("Streaming agent request {} failed: {}", request_id, e);
+        }
+    });
+    
+    Ok(request_id.to_string())
+}
+
+/// Returns false if the request already finished or was never started.
+#[tauri::command]
+async fn cancel_agent_request(
+    state: State<'_, AppState>,
+    request_id: String,
+) -> Result<bool, CommandError> {
+    let id = uuid::Uuid::parse_str(&request_id)?;
+    Ok(state.agent_bridge.read().await.cancel_request(id))
+}
+
+#[tauri::command]
+async fn list_agent_tools(
+    state: State<'_, AppState>,
+) -> Result<Vec<serde_json::Value>, CommandError> {
+    let agent_bridge = state.agent_bridge.read().await;
+    Ok(agent_bridge
+        .list_tools()
+        .into_iter()
+        .map(|tool| {
+            serde_json::json!({
+                "name": tool.name,
+                "description": tool.description,
+                "allowed": agent_bridge.is_tool_allowed(&tool.name)
+            })
+        })
+        .collect())
+}
+
+/// Lets the agent call `tool` (or stops it) and remembers the choice.
+#[tauri::command]
+async fn set_agent_tool_permission(
+    state: State<'_, AppState>,
+    tool: String,
+    allowed: bool,
+) -> Result<(), CommandError> {
+    state.agent_bridge.write().await.set_tool_allowed(&tool, allowed)?;
+    
+    let mut config = utils::Config::load()?;
+    config.set_agent_tool_allowed(&tool, allowed).map_err(CommandError::from)
 }
 
 #[tauri::command]
 async fn list_terminal_sessions(
     state: State<'_, AppState>,
-) -> Result<Vec<terminal::TerminalSession>, String> {
+) -> Result<Vec<terminal::TerminalSession>, CommandError> {
     Ok(state.terminal_manager.list_sessions().await)
 }
 
@@ -281,9 +809,9 @@ async fn send_terminal_input(
     state: State<'_, AppState>,
     session_id: String,
     input: String,
-) -> Result<(), String> {
-    let session_uuid = uuid::Uuid::parse_str(&session_id).map_err(|e| e.to_string())?;
-    state.terminal_manager.send_input(session_uuid, input).await.map_err(|e| e.to_string())
+) -> Result<(), CommandError> {
+    let session_uuid = uuid::Uuid::parse_str(&session_id)?;
+    state.terminal_manager.send_input(session_uuid, input).await.map_err(CommandError::from)
 }
 
 #[tauri::command]
@@ -292,31 +820,31 @@ async fn resize_terminal(
     session_id: String,
     cols: u16,
     rows: u16,
-) -> Result<(), String> {
-    let session_uuid = uuid::Uuid::parse_str(&session_id).map_err(|e| e.to_string())?;
-    state.terminal_manager.resize_terminal(session_uuid, cols, rows).await.map_err(|e| e.to_string())
+) -> Result<(), CommandError> {
+    let session_uuid = uuid::Uuid::parse_str(&session_id)?;
+    state.terminal_manager.resize_terminal(session_uuid, cols, rows).await.map_err(CommandError::from)
 }
 
 #[tauri::command]
 async fn close_terminal_session(
     state: State<'_, AppState>,
     session_id: String,
-) -> Result<(), String> {
-    let session_uuid = uuid::Uuid::parse_str(&session_id).map_err(|e| e.to_string())?;
-    state.terminal_manager.close_session(session_uuid).await.map_err(|e| e.to_string())
+) -> Result<(), CommandError> {
+    let session_uuid = uuid::Uuid::parse_str(&session_id)?;
+    state.terminal_manager.close_session(session_uuid).await.map_err(CommandError::from)
 }
 
 #[tauri::command]
 async fn list_file_buffers(
     state: State<'_, AppState>,
-) -> Result<Vec<editor::FileBuffer>, String> {
+) -> Result<Vec<editor::FileBuffer>, CommandError> {
     Ok(state.editor_manager.list_buffers().await)
 }
 
 #[tauri::command]
 async fn list_projects(
     state: State<'_, AppState>,
-) -> Result<Vec<workspace::Project>, String> {
+) -> Result<Vec<workspace::Project>, CommandError> {
     Ok(state.workspace_manager.list_projects().await)
 }
 
@@ -325,9 +853,9 @@ async fn create_project(
     state: State<'_, AppState>,
     name: String,
     path: String,
-) -> Result<String, String> {
+) -> Result<String, CommandError> {
     let project_id = state.workspace_manager.create_project(name, std::path::PathBuf::from(path))
-        .await.map_err(|e| e.to_string())?;
+        .await?;
     Ok(project_id.to_string())
 }
 
@@ -335,15 +863,36 @@ async fn create_project(
 async fn open_project(
     state: State<'_, AppState>,
     project_id: String,
-) -> Result<(), String> {
-    let uuid = uuid::Uuid::parse_str(&project_id).map_err(|e| e.to_string())?;
-    state.workspace_manager.open_project(uuid).await.map_err(|e| e.to_string())
+) -> Result<(), CommandError> {
+    let uuid = uuid::Uuid::parse_str(&project_id)?;
+    let project = state
+        .workspace_manager
+        .get_project(uuid)
+        .await
+        .ok_or_else(|| CommandError::NotFound(format!("Project not found: {}", uuid)))?;
+    check_project_config(&project.path)?;
+    state.workspace_manager.open_project(uuid).await?;
+    state.canvas_manager.set_project_dir(&project.path).await?;
+    
+    // Apply the project's own settings, if it has any
+    state.reload_config().await?;
+    Ok(())
+}
+
+/// Removes a project from the list without touching its files.
+#[tauri::command]
+async fn remove_project(
+    state: State<'_, AppState>,
+    project_id: String,
+) -> Result<(), CommandError> {
+    let uuid = uuid::Uuid::parse_str(&project_id)?;
+    state.workspace_manager.remove_project(uuid).await.map_err(CommandError::from)
 }
 
 #[tauri::command]
 async fn get_active_project(
     state: State<'_, AppState>,
-) -> Result<Option<workspace::Project>, String> {
+) -> Result<Option<workspace::Project>, CommandError> {
     Ok(state.workspace_manager.get_active_project().await)
 }
 
@@ -358,11 +907,11 @@ async fn start_background_process(
     port: Option<u16>,
     env_vars: std::collections::HashMap<String, String>,
     auto_restart: bool,
-) -> Result<String, String> {
+) -> Result<String, CommandError> {
     let process_id = state.workspace_manager.start_background_process(
         name, command, args, std::path::PathBuf::from(working_directory), 
         port, env_vars, auto_restart
-    ).await.map_err(|e| e.to_string())?;
+    ).await?;
     Ok(process_id.to_string())
 }
 
@@ -370,24 +919,33 @@ async fn start_background_process(
 async fn stop_background_process(
     state: State<'_, AppState>,
     process_id: String,
-) -> Result<(), String> {
-    let uuid = uuid::Uuid::parse_str(&process_id).map_err(|e| e.to_string())?;
-    state.workspace_manager.stop_background_process(uuid).await.map_err(|e| e.to_string())
+) -> Result<(), CommandError> {
+    let uuid = uuid::Uuid::parse_str(&process_id)?;
+    state.workspace_manager.stop_background_process(uuid).await.map_err(CommandError::from)
 }
 
 #[tauri::command]
 async fn restart_background_process(
     state: State<'_, AppState>,
     process_id: String,
-) -> Result<(), String> {
-    let uuid = uuid::Uuid::parse_str(&process_id).map_err(|e| e.to_string())?;
-    state.workspace_manager.restart_background_process(uuid).await.map_err(|e| e.to_string())
+) -> Result<(), CommandError> {
+    let uuid = uuid::Uuid::parse_str(&process_id)?;
+    state.workspace_manager.restart_background_process(uuid).await.map_err(CommandError::from)
+}
+
+/// Latest CPU and memory readings of running background processes, refreshed
+/// every few seconds.
+#[tauri::command]
+async fn get_process_metrics(
+    state: State<'_, AppState>,
+) -> Result<Vec<workspace::ProcessMetrics>, CommandError> {
+    Ok(state.workspace_manager.get_process_metrics().await)
 }
 
 #[tauri::command]
 async fn list_background_processes(
     state: State<'_, AppState>,
-) -> Result<Vec<workspace::BackgroundProcess>, String> {
+) -> Result<Vec<workspace::BackgroundProcess>, CommandError> {
     Ok(state.workspace_manager.list_background_processes().await)
 }
 
@@ -396,38 +954,152 @@ async fn get_process_logs(
     state: State<'_, AppState>,
     process_id: String,
     limit: Option<usize>,
-) -> Result<Vec<workspace::LogEntry>, String> {
-    let uuid = uuid::Uuid::parse_str(&process_id).map_err(|e| e.to_string())?;
-    Ok(state.workspace_manager.get_process_logs(uuid, limit).await)
+    include_history: Option<bool>,
+    level: Option<workspace::LogLevel>,
+    contains: Option<String>,
+) -> Result<Vec<workspace::LogEntry>, CommandError> {
+    let uuid = uuid::Uuid::parse_str(&process_id)?;
+    let filter = workspace::LogFilter { level, contains };
+    Ok(state
+        .workspace_manager
+        .get_process_logs(uuid, limit, include_history.unwrap_or(false), &filter)
+        .await)
+}
+
+#[tauri::command]
+async fn clear_process_logs(
+    state: State<'_, AppState>,
+    process_id: String,
+) -> Result<(), CommandError> {
+    let uuid = uuid::Uuid::parse_str(&process_id)?;
+    state.workspace_manager.clear_process_logs(uuid).await.map_err(CommandError::from)
 }
 
 #[tauri::command]
 async fn start_project_dev_server(
     state: State<'_, AppState>,
     project_id: String,
-) -> Result<String, String> {
-    let uuid = uuid::Uuid::parse_str(&project_id).map_err(|e| e.to_string())?;
-    let process_id = state.workspace_manager.start_project_dev_server(uuid).await.map_err(|e| e.to_string())?;
+) -> Result<String, CommandError> {
+    let uuid = uuid::Uuid::parse_str(&project_id)?;
+    let process_id = state.workspace_manager.start_project_dev_server(uuid).await?;
     Ok(process_id.to_string())
 }
 
+#[tauri::command]
+async fn list_run_configs(
+    state: State<'_, AppState>,
+    project_id: String,
+) -> Result<Vec<workspace::RunConfig>, CommandError> {
+    let uuid = uuid::Uuid::parse_str(&project_id)?;
+    state.workspace_manager.list_run_configs(uuid).await.map_err(CommandError::from)
+}
+
+/// Groups a task's diagnostics by `file://` URI in the form the editor shows
+/// inline. Those without a file are left out, and those without a column
+/// mark their whole line.
+fn build_diagnostics_by_uri(
+    diagnostics: &[workspace::BuildDiagnostic],
+) -> std::collections::HashMap<String, Vec<editor::LSPDiagnostic>> {
+    let mut by_uri: std::collections::HashMap<String, Vec<editor::LSPDiagnostic>> = Default::default();
+    for diagnostic in diagnostics {
+        let Some(file) = &diagnostic.file else {
+            continue;
+        };
+        // Compilers count lines and columns from 1, LSP from 0
+        let line = diagnostic.line.unwrap_or(1).saturating_sub(1);
+        let (start, end) = match diagnostic.column {
+            Some(column) => (column.saturating_sub(1), column),
+            None => (0, u32::MAX),
+        };
+        by_uri.entry(format!("file://{}", file.to_string_lossy())).or_default().push(editor::LSPDiagnostic {
+            range: editor::LSPRange {
+                start: editor::lsp::LSPPosition { line, character: start },
+                end: editor::lsp::LSPPosition { line, character: end },
+            },
+            severity: match diagnostic.severity {
+                workspace::DiagnosticSeverity::Error => 1,
+                workspace::DiagnosticSeverity::Warning => 2,
+            },
+            message: diagnostic.message.clone(),
+            source: Some(diagnostic.source.clone()),
+            code: diagnostic.code.clone().map(serde_json::Value::from),
+        });
+    }
+    by_uri
+}
+
+/// Runs the project's test command to completion; progress arrives as
+/// `workspace.task.*` events. Compiler errors and failed assertions replace
+/// the editor's diagnostics from the previous test run; those from the last
+/// build stay.
+#[tauri::command]
+async fn run_tests(
+    state: State<'_, AppState>,
+    project_id: String,
+) -> Result<workspace::TaskOutput, CommandError> {
+    let uuid = uuid::Uuid::parse_str(&project_id)?;
+    let output = state.workspace_manager.run_tests(uuid).await?;
+    state.editor_manager.set_build_diagnostics("test", build_diagnostics_by_uri(&output.diagnostics)).await;
+    Ok(output)
+}
+
+/// Like `run_tests`, for the build command. A clean build clears the
+/// diagnostics of the previous build but not those of the last test run.
+#[tauri::command]
+async fn run_build(
+    state: State<'_, AppState>,
+    project_id: String,
+) -> Result<workspace::TaskOutput, CommandError> {
+    let uuid = uuid::Uuid::parse_str(&project_id)?;
+    let output = state.workspace_manager.run_build(uuid).await?;
+    state.editor_manager.set_build_diagnostics("build", build_diagnostics_by_uri(&output.diagnostics)).await;
+    Ok(output)
+}
+
+#[tauri::command]
+async fn start_project_process(
+    state: State<'_, AppState>,
+    project_id: String,
+    config_name: String,
+) -> Result<String, CommandError> {
+    let uuid = uuid::Uuid::parse_str(&project_id)?;
+    let process_id = state
+        .workspace_manager
+        .start_project_process(uuid, &config_name)
+        .await?;
+    Ok(process_id.to_string())
+}
+
+#[tauri::command]
+async fn start_project_stack(
+    state: State<'_, AppState>,
+    project_id: String,
+) -> Result<Vec<String>, CommandError> {
+    let uuid = uuid::Uuid::parse_str(&project_id)?;
+    let process_ids = state
+        .workspace_manager
+        .start_project_stack(uuid)
+        .await?;
+    Ok(process_ids.iter().map(|process_id| process_id.to_string()).collect())
+}
+
 // Git Commands
 #[tauri::command]
 async fn open_git_repository(
     state: State<'_, AppState>,
     path: String,
-) -> Result<workspace::GitRepository, String> {
+) -> Result<workspace::GitRepository, CommandError> {
     state.workspace_manager.open_git_repository(std::path::PathBuf::from(path))
-        .await.map_err(|e| e.to_string())
+        .await.map_err(CommandError::from)
 }
 
 #[tauri::command]
 async fn get_git_status(
     state: State<'_, AppState>,
     repo_path: String,
-) -> Result<workspace::GitStatus, String> {
+) -> Result<workspace::GitStatus, CommandError> {
     state.workspace_manager.get_git_status(&std::path::PathBuf::from(repo_path))
-        .await.map_err(|e| e.to_string())
+        .await.map_err(CommandError::from)
 }
 
 #[tauri::command]
@@ -435,9 +1107,9 @@ async fn git_stage_file(
     state: State<'_, AppState>,
     repo_path: String,
     file_path: String,
-) -> Result<(), String> {
+) -> Result<(), CommandError> {
     state.workspace_manager.git_stage_file(&std::path::PathBuf::from(repo_path), &file_path)
-        .await.map_err(|e| e.to_string())
+        .await.map_err(CommandError::from)
 }
 
 #[tauri::command]
@@ -445,9 +1117,9 @@ async fn git_unstage_file(
     state: State<'_, AppState>,
     repo_path: String,
     file_path: String,
-) -> Result<(), String> {
+) -> Result<(), CommandError> {
     state.workspace_manager.git_unstage_file(&std::path::PathBuf::from(repo_path), &file_path)
-        .await.map_err(|e| e.to_string())
+        .await.map_err(CommandError::from)
 }
 
 #[tauri::command]
@@ -457,9 +1129,9 @@ async fn git_commit(
     message: String,
     author_name: String,
     author_email: String,
-) -> Result<String, String> {
+) -> Result<String, CommandError> {
     state.workspace_manager.git_commit(&std::path::PathBuf::from(repo_path), &message, &author_name, &author_email)
-        .await.map_err(|e| e.to_string())
+        .await.map_err(CommandError::from)
 }
 
 #[tauri::command]
@@ -467,18 +1139,29 @@ async fn git_get_commit_history(
     state: State<'_, AppState>,
     repo_path: String,
     limit: Option<usize>,
-) -> Result<Vec<workspace::GitCommit>, String> {
+) -> Result<Vec<workspace::GitCommit>, CommandError> {
     state.workspace_manager.git_get_commit_history(&std::path::PathBuf::from(repo_path), limit)
-        .await.map_err(|e| e.to_string())
+        .await.map_err(CommandError::from)
+}
+
+#[tauri::command]
+async fn git_get_file_history(
+    state: State<'_, AppState>,
+    repo_path: String,
+    file_path: String,
+    limit: Option<usize>,
+) -> Result<Vec<workspace::GitCommit>, CommandError> {
+    state.workspace_manager.git_get_file_history(&std::path::PathBuf::from(repo_path), &file_path, limit)
+        .await.map_err(CommandError::from)
 }
 
 #[tauri::command]
 async fn git_get_branches(
     state: State<'_, AppState>,
     repo_path: String,
-) -> Result<Vec<workspace::GitBranch>, String> {
+) -> Result<Vec<workspace::GitBranch>, CommandError> {
     state.workspace_manager.git_get_branches(&std::path::PathBuf::from(repo_path))
-        .await.map_err(|e| e.to_string())
+        .await.map_err(CommandError::from)
 }
 
 #[tauri::command]
@@ -486,10 +1169,10 @@ async fn git_create_branch(
     state: State<'_, AppState>,
     repo_path: String,
     branch_name: String,
-    from_head: bool,
-) -> Result<(), String> {
-    state.workspace_manager.git_create_branch(&std::path::PathBuf::from(repo_path), &branch_name, from_head)
-        .await.map_err(|e| e.to_string())
+    start_point: Option<String>,
+) -> Result<(), CommandError> {
+    state.workspace_manager.git_create_branch(&std::path::PathBuf::from(repo_path), &branch_name, start_point.as_deref())
+        .await.map_err(CommandError::from)
 }
 
 #[tauri::command]
@@ -497,9 +1180,92 @@ async fn git_switch_branch(
     state: State<'_, AppState>,
     repo_path: String,
     branch_name: String,
-) -> Result<(), String> {
-    state.workspace_manager.git_switch_branch(&std::path::PathBuf::from(repo_path), &branch_name)
-        .await.map_err(|e| e.to_string())
+    autostash: Option<bool>,
+) -> Result<workspace::SwitchOutcome, CommandError> {
+    state.workspace_manager.git_switch_branch(&std::path::PathBuf::from(repo_path), &branch_name, autostash.unwrap_or(false))
+        .await.map_err(CommandError::from)
+}
+
+#[tauri::command]
+async fn git_get_file_diff(
+    state: State<'_, AppState>,
+    repo_path: String,
+    file_path: String,
+    staged: bool,
+) -> Result<Option<workspace::GitDiff>, CommandError> {
+    state.workspace_manager.git_get_file_diff(&std::path::PathBuf::from(repo_path), &file_path, staged)
+        .await.map_err(CommandError::from)
+}
+
+#[tauri::command]
+async fn git_discard_file_changes(
+    state: State<'_, AppState>,
+    repo_path: String,
+    file_path: String,
+) -> Result<(), CommandError> {
+    state.workspace_manager.git_discard_file_changes(&std::path::PathBuf::from(repo_path), &file_path)
+        .await.map_err(CommandError::from)
+}
+
+/// Per-line blame of a file, cut off after `workspace::MAX_BLAME_LINES` lines.
+#[tauri::command]
+async fn git_blame(
+    state: State<'_, AppState>,
+    repo_path: String,
+    file_path: String,
+) -> Result<Vec<workspace::GitBlameLine>, CommandError> {
+    state.workspace_manager.git_blame(&std::path::PathBuf::from(repo_path), &file_path)
+        .await.map_err(CommandError::from)
+}
+
+#[tauri::command]
+async fn git_stash_save(
+    state: State<'_, AppState>,
+    repo_path: String,
+    message: Option<String>,
+    include_untracked: bool,
+) -> Result<String, CommandError> {
+    state.workspace_manager.git_stash_save(&std::path::PathBuf::from(repo_path), message.as_deref(), include_untracked)
+        .await.map_err(CommandError::from)
+}
+
+#[tauri::command]
+async fn git_stash_list(
+    state: State<'_, AppState>,
+    repo_path: String,
+) -> Result<Vec<workspace::GitStash>, CommandError> {
+    state.workspace_manager.git_stash_list(&std::path::PathBuf::from(repo_path))
+        .await.map_err(CommandError::from)
+}
+
+#[tauri::command]
+async fn git_stash_apply(
+    state: State<'_, AppState>,
+    repo_path: String,
+    index: usize,
+) -> Result<(), CommandError> {
+    state.workspace_manager.git_stash_apply(&std::path::PathBuf::from(repo_path), index)
+        .await.map_err(CommandError::from)
+}
+
+#[tauri::command]
+async fn git_stash_pop(
+    state: State<'_, AppState>,
+    repo_path: String,
+    index: usize,
+) -> Result<(), CommandError> {
+    state.workspace_manager.git_stash_pop(&std::path::PathBuf::from(repo_path), index)
+        .await.map_err(CommandError::from)
+}
+
+#[tauri::command]
+async fn git_stash_drop(
+    state: State<'_, AppState>,
+    repo_path: String,
+    index: usize,
+) -> Result<(), CommandError> {
+    state.workspace_manager.git_stash_drop(&std::path::PathBuf::from(repo_path), index)
+        .await.map_err(CommandError::from)
 }
 
 #[tauri::command]
@@ -507,9 +1273,9 @@ async fn git_get_diff(
     state: State<'_, AppState>,
     repo_path: String,
     staged: bool,
-) -> Result<Vec<workspace::GitDiff>, String> {
+) -> Result<Vec<workspace::GitDiff>, CommandError> {
     state.workspace_manager.git_get_diff(&std::path::PathBuf::from(repo_path), staged)
-        .await.map_err(|e| e.to_string())
+        .await.map_err(CommandError::from)
 }
 
 #[tauri::command]
@@ -518,9 +1284,9 @@ async fn git_push(
     repo_path: String,
     remote: String,
     branch: String,
-) -> Result<(), String> {
+) -> Result<(), CommandError> {
     state.workspace_manager.git_push(&std::path::PathBuf::from(repo_path), &remote, &branch)
-        .await.map_err(|e| e.to_string())
+        .await.map_err(CommandError::from)
 }
 
 #[tauri::command]
@@ -529,9 +1295,9 @@ async fn git_pull(
     repo_path: String,
     remote: String,
     branch: String,
-) -> Result<(), String> {
+) -> Result<(), CommandError> {
     state.workspace_manager.git_pull(&std::path::PathBuf::from(repo_path), &remote, &branch)
-        .await.map_err(|e| e.to_string())
+        .await.map_err(CommandError::from)
 }
 
 #[tauri::command]
@@ -539,9 +1305,9 @@ async fn generate_ai_commit_message(
     state: State<'_, AppState>,
     repo_path: String,
     staged_files: Vec<String>,
-) -> Result<String, String> {
+) -> Result<String, CommandError> {
     state.workspace_manager.generate_ai_commit_message(&std::path::PathBuf::from(repo_path), &staged_files)
-        .await.map_err(|e| e.to_string())
+        .await.map_err(CommandError::from)
 }
 
 // LSP Commands
@@ -551,9 +1317,9 @@ async fn get_completion(
     buffer_id: String,
     line: u32,
     character: u32,
-) -> Result<Vec<editor::LSPCompletionItem>, String> {
-    let buffer_uuid = uuid::Uuid::parse_str(&buffer_id).map_err(|e| e.to_string())?;
-    state.editor_manager.get_completion(buffer_uuid, line, character).await.map_err(|e| e.to_string())
+) -> Result<Vec<editor::LSPCompletionItem>, CommandError> {
+    let buffer_uuid = uuid::Uuid::parse_str(&buffer_id)?;
+    state.editor_manager.get_completion(buffer_uuid, line, character).await.map_err(CommandError::from)
 }
 
 #[tauri::command]
@@ -562,17 +1328,117 @@ async fn get_hover_info(
     buffer_id: String,
     line: u32,
     character: u32,
-) -> Result<Option<editor::LSPHover>, String> {
-    let buffer_uuid = uuid::Uuid::parse_str(&buffer_id).map_err(|e| e.to_string())?;
-    state.editor_manager.get_hover(buffer_uuid, line, character).await.map_err(|e| e.to_string())
+) -> Result<Option<editor::LSPHover>, CommandError> {
+    let buffer_uuid = uuid::Uuid::parse_str(&buffer_id)?;
+    state.editor_manager.get_hover(buffer_uuid, line, character).await.map_err(CommandError::from)
+}
+
+#[tauri::command]
+async fn get_definition(
+    state: State<'_, AppState>,
+    buffer_id: String,
+    line: u32,
+    character: u32,
+) -> Result<Vec<editor::LSPLocation>, CommandError> {
+    let buffer_uuid = uuid::Uuid::parse_str(&buffer_id)?;
+    state.editor_manager.get_definition(buffer_uuid, line, character).await.map_err(CommandError::from)
+}
+
+#[tauri::command]
+async fn get_references(
+    state: State<'_, AppState>,
+    buffer_id: String,
+    line: u32,
+    character: u32,
+    include_declaration: Option<bool>,
+) -> Result<Vec<editor::LSPLocation>, CommandError> {
+    let buffer_uuid = uuid::Uuid::parse_str(&buffer_id)?;
+    state.editor_manager.get_references(buffer_uuid, line, character, include_declaration.unwrap_or(true)).await.map_err(CommandError::from)
+}
+
+#[tauri::command]
+async fn format_buffer(
+    state: State<'_, AppState>,
+    buffer_id: String,
+) -> Result<String, CommandError> {
+    let buffer_uuid = uuid::Uuid::parse_str(&buffer_id)?;
+    state.editor_manager.format_buffer(buffer_uuid).await.map_err(CommandError::from)
+}
+
+#[tauri::command]
+async fn rename_symbol(
+    state: State<'_, AppState>,
+    buffer_id: String,
+    line: u32,
+    character: u32,
+    new_name: String,
+) -> Result<Vec<std::path::PathBuf>, CommandError> {
+    let buffer_uuid = uuid::Uuid::parse_str(&buffer_id)?;
+    state.editor_manager.rename_symbol(buffer_uuid, line, character, new_name).await.map_err(CommandError::from)
+}
+
+#[tauri::command]
+async fn get_document_symbols(
+    state: State<'_, AppState>,
+    buffer_id: String,
+) -> Result<Vec<editor::LSPDocumentSymbol>, CommandError> {
+    let buffer_uuid = uuid::Uuid::parse_str(&buffer_id)?;
+    state.editor_manager.get_document_symbols(buffer_uuid).await.map_err(CommandError::from)
+}
+
+#[tauri::command]
+async fn search_symbols(
+    state: State<'_, AppState>,
+    query: String,
+) -> Result<Vec<editor::LSPWorkspaceSymbol>, CommandError> {
+    state.editor_manager.search_symbols(&query).await.map_err(CommandError::from)
+}
+
+#[tauri::command]
+async fn get_signature_help(
+    state: State<'_, AppState>,
+    buffer_id: String,
+    line: u32,
+    character: u32,
+) -> Result<Option<editor::LSPSignatureHelp>, CommandError> {
+    let buffer_uuid = uuid::Uuid::parse_str(&buffer_id)?;
+    state.editor_manager.get_signature_help(buffer_uuid, line, character).await.map_err(CommandError::from)
+}
+
+#[tauri::command]
+async fn get_code_actions(
+    state: State<'_, AppState>,
+    buffer_id: String,
+    range: editor::LSPRange,
+) -> Result<Vec<editor::LSPCodeAction>, CommandError> {
+    let buffer_uuid = uuid::Uuid::parse_str(&buffer_id)?;
+    state.editor_manager.get_code_actions(buffer_uuid, range).await.map_err(CommandError::from)
+}
+
+#[tauri::command]
+async fn apply_code_action(
+    state: State<'_, AppState>,
+    buffer_id: String,
+    action: editor::LSPCodeAction,
+) -> Result<Vec<std::path::PathBuf>, CommandError> {
+    let buffer_uuid = uuid::Uuid::parse_str(&buffer_id)?;
+    state.editor_manager.apply_code_action(buffer_uuid, action).await.map_err(CommandError::from)
+}
+
+#[tauri::command]
+async fn get_lsp_log(
+    state: State<'_, AppState>,
+    language: String,
+) -> Result<Vec<String>, CommandError> {
+    Ok(state.editor_manager.get_lsp_log(&language).await)
 }
 
 #[tauri::command]
 async fn get_diagnostics(
     state: State<'_, AppState>,
     buffer_id: String,
-) -> Result<Option<Vec<editor::LSPDiagnostic>>, String> {
-    let buffer_uuid = uuid::Uuid::parse_str(&buffer_id).map_err(|e| e.to_string())?;
+) -> Result<Option<Vec<editor::LSPDiagnostic>>, CommandError> {
+    let buffer_uuid = uuid::Uuid::parse_str(&buffer_id)?;
     Ok(state.editor_manager.get_diagnostics(buffer_uuid).await)
 }
 
@@ -581,7 +1447,7 @@ async fn get_diagnostics(
 async fn create_conversation(
     state: State<'_, AppState>,
     name: String,
-) -> Result<String, String> {
+) -> Result<String, CommandError> {
     let mut agent_bridge = state.agent_bridge.write().await;
     let conversation_id = agent_bridge.create_conversation(name);
     Ok(conversation_id.to_string())
@@ -590,7 +1456,7 @@ async fn create_conversation(
 #[tauri::command]
 async fn list_conversations(
     state: State<'_, AppState>,
-) -> Result<Vec<agents::ConversationThread>, String> {
+) -> Result<Vec<agents::ConversationThread>, CommandError> {
     let agent_bridge = state.agent_bridge.read().await;
     let conversations = agent_bridge.list_conversations();
     Ok(conversations.into_iter().cloned().collect())
@@ -600,27 +1466,75 @@ async fn list_conversations(
 async fn get_conversation(
     state: State<'_, AppState>,
     conversation_id: String,
-) -> Result<Option<agents::ConversationThread>, String> {
+) -> Result<Option<agents::ConversationThread>, CommandError> {
     let agent_bridge = state.agent_bridge.read().await;
-    let id = uuid::Uuid::parse_str(&conversation_id).map_err(|e| e.to_string())?;
+    let id = uuid::Uuid::parse_str(&conversation_id)?;
     Ok(agent_bridge.get_conversation(id).cloned())
 }
 
+#[tauri::command]
+async fn export_conversation(
+    state: State<'_, AppState>,
+    conversation_id: String,
+    format: agents::ExportFormat,
+    output_path: String,
+) -> Result<(), CommandError> {
+    let agent_bridge = state.agent_bridge.read().await;
+    let id = uuid::Uuid::parse_str(&conversation_id)?;
+    let content = agent_bridge.export_conversation(id, format)?;
+    tokio::fs::write(&output_path, content).await.map_err(CommandError::from)
+}
+
+#[tauri::command]
+async fn get_conversation_usage(
+    state: State<'_, AppState>,
+    conversation_id: String,
+) -> Result<Option<agents::TokenUsage>, CommandError> {
+    let agent_bridge = state.agent_bridge.read().await;
+    let id = uuid::Uuid::parse_str(&conversation_id)?;
+    Ok(agent_bridge.get_conversation_usage(id))
+}
+
 #[tauri::command]
 async fn set_active_conversation(
     state: State<'_, AppState>,
     conversation_id: String,
-) -> Result<(), String> {
+) -> Result<(), CommandError> {
+    let mut agent_bridge = state.agent_bridge.write().await;
+    let id = uuid::Uuid::parse_str(&conversation_id)?;
+    agent_bridge.set_active_conversation(id).map_err(CommandError::from)
+}
+
+/// Pins a provider and/or model to a conversation; omit both to go back to
+/// the defaults.
+#[tauri::command]
+async fn set_conversation_provider(
+    state: State<'_, AppState>,
+    conversation_id: String,
+    provider: Option<String>,
+    model: Option<String>,
+) -> Result<(), CommandError> {
     let mut agent_bridge = state.agent_bridge.write().await;
-    let id = uuid::Uuid::parse_str(&conversation_id).map_err(|e| e.to_string())?;
-    agent_bridge.set_active_conversation(id).map_err(|e| e.to_string())
+    let id = uuid::Uuid::parse_str(&conversation_id)?;
+    agent_bridge.set_conversation_provider(id, provider, model).map_err(CommandError::from)
 }
 
 #[tauri::command]
 async fn search_conversations(
     state: State<'_, AppState>,
     query: String,
-) -> Result<Vec<agents::ConversationThread>, String> {
+    similarity: Option<f32>,
+) -> Result<Vec<agents::ConversationThread>, CommandError> {
+    // A similarity threshold asks for embedding search, ranked best first.
+    // The embedding calls run without the bridge lock.
+    if let Some(similarity) = similarity {
+        let search = state.agent_bridge.read().await.begin_semantic_search(&query);
+        let embeddings = search.embed().await;
+        let mut agent_bridge = state.agent_bridge.write().await;
+        let conversations = agent_bridge.finish_semantic_search(&query, similarity, embeddings);
+        return Ok(conversations.into_iter().cloned().collect());
+    }
+    
     let agent_bridge = state.agent_bridge.read().await;
     let conversations = agent_bridge.search_conversations(&query);
     Ok(conversations.into_iter().cloned().collect())
@@ -629,23 +1543,27 @@ async fn search_conversations(
 #[tauri::command]
 async fn list_available_agents(
     state: State<'_, AppState>,
-) -> Result<Vec<String>, String> {
+) -> Result<Vec<String>, CommandError> {
     let agent_bridge = state.agent_bridge.read().await;
     Ok(agent_bridge.list_available_agents())
 }
 
 // Configuration Commands
 #[tauri::command]
-async fn get_config() -> Result<utils::Config, String> {
-    utils::Config::load().map_err(|e| e.to_string())
+async fn get_config() -> Result<utils::Config, CommandError> {
+    utils::Config::load().map_err(CommandError::from)
 }
 
 #[tauri::command]
 async fn update_agent_config(
     provider: String,
     api_key: Option<String>,
-) -> Result<(), String> {
-    let mut config = utils::Config::load().map_err(|e| e.to_string())?;
+    model: Option<String>,
+    temperature: Option<f64>,
+    max_tokens: Option<u32>,
+    make_default: Option<bool>,
+) -> Result<(), CommandError> {
+    let mut config = utils::Config::load()?;
     
     let agent_provider = match provider.as_str() {
         "claude" => utils::AgentProvider::Claude,
@@ -653,10 +1571,21 @@ async fn update_agent_config(
         "ollama" => utils::AgentProvider::Ollama { 
             endpoint: "http://localhost:11434".to_string() 
         },
-        _ => return Err("Invalid agent provider".to_string()),
+        _ => return Err(CommandError::InvalidInput(format!("Invalid agent provider: {}", provider))),
     };
     
-    config.update_agent_config(agent_provider, api_key).map_err(|e| e.to_string())
+    // Leave stored model settings alone when none are given
+    let model_settings = (model.is_some() || temperature.is_some() || max_tokens.is_some()).then(|| {
+        utils::ModelSettings {
+            model,
+            temperature,
+            max_tokens,
+        }
+    });
+    
+    config
+        .update_agent_config(agent_provider, api_key, model_settings, make_default.unwrap_or(false))
+        .map_err(CommandError::from)
 }
 
 #[tauri::command]
@@ -664,39 +1593,58 @@ async fn update_git_tokens(
     github_token: Option<String>,
     gitlab_token: Option<String>,
     gitea_token: Option<String>,
-) -> Result<(), String> {
-    let mut config = utils::Config::load().map_err(|e| e.to_string())?;
-    config.update_git_tokens(github_token, gitlab_token, gitea_token).map_err(|e| e.to_string())
+) -> Result<(), CommandError> {
+    let mut config = utils::Config::load()?;
+    config.update_git_tokens(github_token, gitlab_token, gitea_token).map_err(CommandError::from)
 }
 
 #[tauri::command]
 async fn update_ui_preferences(
+    state: State<'_, AppState>,
     theme: String,
     font_size: u32,
     auto_save: bool,
     enable_lsp: bool,
-) -> Result<(), String> {
-    let mut config = utils::Config::load().map_err(|e| e.to_string())?;
-    config.update_ui_preferences(theme, font_size, auto_save, enable_lsp).map_err(|e| e.to_string())
+) -> Result<(), CommandError> {
+    let mut config = utils::Config::load()?;
+    config.update_ui_preferences(theme, font_size, auto_save, enable_lsp)?;
+    state.editor_manager.update_config(config).await;
+    Ok(())
+}
+
+#[tauri::command]
+async fn reload_config(state: State<'_, AppState>) -> Result<utils::Config, CommandError> {
+    state.reload_config().await.map_err(CommandError::from)
 }
 
 #[tauri::command]
-async fn validate_config() -> Result<(), String> {
-    let config = utils::Config::load().map_err(|e| e.to_string())?;
-    config.validate().map_err(|e| e.to_string())
+async fn validate_config() -> Result<(), CommandError> {
+    let config = utils::Config::load()?;
+    config.validate().map_err(CommandError::from)
 }
 
 #[tauri::command]
 async fn test_agent_connection(
     provider: String,
-    api_key: String,
-) -> Result<bool, String> {
-    // Create a test agent provider and attempt a simple request
-    let test_provider: Box<dyn agents::AgentProvider> = match provider.as_str() {
-        "claude" => Box::new(agents::ClaudeProvider::new(api_key)),
-        "openai" => Box::new(agents::OpenAIProvider::new(api_key)),
-        _ => return Err("Unsupported provider for testing".to_string()),
+    api_key: Option<String>,
+) -> Result<bool, CommandError> {
+    // Test the configured entry for this provider, with `api_key` in place of
+    // its stored key when one is given
+    let config = utils::Config::load()?;
+    let mut provider_config = match (config.provider(&provider), provider.as_str()) {
+        (Some(provider_config), _) => provider_config.clone(),
+        (None, "claude") => utils::ProviderConfig::new(utils::AgentProvider::Claude, None),
+        (None, "openai") => utils::ProviderConfig::new(utils::AgentProvider::OpenAI, None),
+        _ => return Err(CommandError::Unsupported(format!("Unsupported provider for testing: {}", provider))),
     };
+    if api_key.is_some() {
+        provider_config.api_key = api_key;
+    }
+    
+    // Create a test agent provider and attempt a simple request
+    let retry_policy = agents::RetryPolicy::default();
+    let test_provider = build_agent_provider(&provider_config, &retry_policy, &events::EventBus::new())
+        .ok_or_else(|| CommandError::InvalidInput(format!("No API key configured for {}", provider)))?;
     
     let test_request = agents::AgentRequest {
         id: uuid::Uuid::new_v4(),
@@ -704,6 +1652,9 @@ async fn test_agent_connection(
         prompt: "Say 'OK' if you can read this".to_string(),
         context: std::collections::HashMap::new(),
         files: Vec::new(),
+        tools: Vec::new(),
+        tool_turns: Vec::new(),
+        model: None,
     };
     
     match test_provider.process_request(&test_request).await {
@@ -718,7 +1669,7 @@ async fn create_canvas(
     state: State<'_, AppState>,
     name: String,
     mode: String,
-) -> Result<String, String> {
+) -> Result<String, CommandError> {
     let canvas_mode = match mode.as_str() {
         "freeform" => canvas::CanvasMode::Freeform,
         "wireframe" => canvas::CanvasMode::Wireframe,
@@ -727,11 +1678,7 @@ async fn create_canvas(
         _ => canvas::CanvasMode::Freeform,
     };
     
-    let canvas = canvas::Canvas::new(name, canvas_mode);
-    let canvas_id = canvas.id;
-    
-    // Store canvas in workspace manager (canvases are project-specific)
-    // For now, we'll just return the ID - in a full implementation this would be persisted
+    let canvas_id = state.canvas_manager.create_canvas(name, canvas_mode).await?;
     Ok(canvas_id.to_string())
 }
 
@@ -739,10 +1686,9 @@ async fn create_canvas(
 async fn get_canvas(
     state: State<'_, AppState>,
     canvas_id: String,
-) -> Result<Option<canvas::Canvas>, String> {
-    // In a full implementation, this would retrieve from storage
-    // For now, return a placeholder
-    Ok(None)
+) -> Result<Option<canvas::Canvas>, CommandError> {
+    let uuid = uuid::Uuid::parse_str(&canvas_id)?;
+    Ok(state.canvas_manager.get_canvas(uuid).await)
 }
 
 #[tauri::command]
@@ -750,61 +1696,113 @@ async fn update_canvas(
     state: State<'_, AppState>,
     canvas_id: String,
     canvas_data: serde_json::Value,
-) -> Result<(), String> {
-    // In a full implementation, this would update the stored canvas
-    // Publish canvas update event
-    let event = events::Event::new(
-        "canvas.updated",
-        "canvas-manager",
-        serde_json::json!({
-            "canvas_id": canvas_id,
-            "data": canvas_data
-        }),
-    );
-    state.event_bus.publish(event).map_err(|e| e.to_string())?;
-    Ok(())
+) -> Result<(), CommandError> {
+    let uuid = uuid::Uuid::parse_str(&canvas_id)?;
+    state.canvas_manager.update_canvas(uuid, canvas_data).await.map_err(CommandError::from)
 }
 
 #[tauri::command]
 async fn save_canvas(
     state: State<'_, AppState>,
     canvas_id: String,
-) -> Result<(), String> {
-    // In a full implementation, this would persist the canvas to disk
-    let event = events::Event::new(
-        "canvas.saved",
-        "canvas-manager",
-        serde_json::json!({
-            "canvas_id": canvas_id
-        }),
-    );
-    state.event_bus.publish(event).map_err(|e| e.to_string())?;
-    Ok(())
+) -> Result<(), CommandError> {
+    let uuid = uuid::Uuid::parse_str(&canvas_id)?;
+    state.canvas_manager.save_canvas(uuid).await.map_err(CommandError::from)
+}
+
+/// Returns false when there is nothing to undo.
+#[tauri::command]
+async fn undo_canvas(
+    state: State<'_, AppState>,
+    canvas_id: String,
+) -> Result<bool, CommandError> {
+    let uuid = uuid::Uuid::parse_str(&canvas_id)?;
+    state.canvas_manager.undo_canvas(uuid).await.map_err(CommandError::from)
+}
+
+/// Returns false when there is nothing to redo.
+#[tauri::command]
+async fn redo_canvas(
+    state: State<'_, AppState>,
+    canvas_id: String,
+) -> Result<bool, CommandError> {
+    let uuid = uuid::Uuid::parse_str(&canvas_id)?;
+    state.canvas_manager.redo_canvas(uuid).await.map_err(CommandError::from)
+}
+
+/// `reorder` is one of "front", "back", "forward" or "backward". Returns false
+/// when the element was already in place.
+#[tauri::command]
+async fn reorder_canvas_element(
+    state: State<'_, AppState>,
+    canvas_id: String,
+    element_id: String,
+    reorder: canvas::Reorder,
+) -> Result<bool, CommandError> {
+    let canvas_uuid = uuid::Uuid::parse_str(&canvas_id)?;
+    let element_uuid = uuid::Uuid::parse_str(&element_id)?;
+    state
+        .canvas_manager
+        .reorder_element(canvas_uuid, element_uuid, reorder)
+        .await
+        .map_err(CommandError::from)
 }
 
 #[tauri::command]
 async fn list_canvases(
     state: State<'_, AppState>,
-) -> Result<Vec<canvas::Canvas>, String> {
-    // In a full implementation, this would return all stored canvases
-    Ok(Vec::new())
+) -> Result<Vec<canvas::Canvas>, CommandError> {
+    Ok(state.canvas_manager.list_canvases().await)
 }
 
 #[tauri::command]
 async fn delete_canvas(
     state: State<'_, AppState>,
     canvas_id: String,
-) -> Result<(), String> {
-    // In a full implementation, this would delete the canvas
-    let event = events::Event::new(
-        "canvas.deleted",
-        "canvas-manager",
-        serde_json::json!({
-            "canvas_id": canvas_id
-        }),
-    );
-    state.event_bus.publish(event).map_err(|e| e.to_string())?;
-    Ok(())
+) -> Result<(), CommandError> {
+    let uuid = uuid::Uuid::parse_str(&canvas_id)?;
+    state.canvas_manager.delete_canvas(uuid).await.map_err(CommandError::from)
+}
+
+/// Creates a flowchart canvas from a Mermaid `flowchart`/`graph` definition.
+#[tauri::command]
+async fn import_canvas_mermaid(
+    state: State<'_, AppState>,
+    name: String,
+    source: String,
+) -> Result<String, CommandError> {
+    let mut canvas = canvas::Canvas::import_from_mermaid(&source)?;
+    canvas.name = name;
+    
+    let canvas_id = state.canvas_manager.add_canvas(canvas).await?;
+    Ok(canvas_id.to_string())
+}
+
+/// Generates `framework` code ("react", "html" or "svelte") from a wireframe
+/// canvas. With `output_path`, the code is also written there and opened in
+/// the editor.
+#[tauri::command]
+async fn generate_canvas_code(
+    state: State<'_, AppState>,
+    canvas_id: String,
+    framework: canvas::CodeFramework,
+    output_path: Option<String>,
+) -> Result<String, CommandError> {
+    let uuid = uuid::Uuid::parse_str(&canvas_id)?;
+    let code = state
+        .canvas_manager
+        .generate_code(uuid, framework)
+        .await?;
+    
+    // Fails rather than overwrite a file that is already at `output_path`
+    if let Some(output_path) = output_path {
+        state
+            .editor_manager
+            .create_file(std::path::PathBuf::from(output_path), Some(code.clone()))
+            .await?;
+    }
+    
+    Ok(code)
 }
 
 #[tauri::command]
@@ -812,27 +1810,83 @@ async fn export_canvas(
     state: State<'_, AppState>,
     canvas_id: String,
     format: String,
-) -> Result<String, String> {
-    // In a full implementation, this would export the canvas in the specified format
+    scale: Option<f32>,
+    background: Option<String>,
+) -> Result<String, CommandError> {
+    let uuid = uuid::Uuid::parse_str(&canvas_id)?;
+    let canvas = state.canvas_manager.get_canvas(uuid).await
+        .ok_or_else(|| CommandError::NotFound(format!("Canvas not found: {}", canvas_id)))?;
+    
     match format.as_str() {
-        "json" => Ok(serde_json::json!({"canvas_id": canvas_id, "format": "json"}).to_string()),
-        "mermaid" => Ok(format!("graph TD\n  A[Canvas {}] --> B[Exported]", canvas_id)),
-        "svg" => Ok(format!("<svg><text>Canvas {}</text></svg>", canvas_id)),
-        _ => Err("Unsupported export format".to_string()),
+        "json" => canvas.export_to_json().map_err(CommandError::from),
+        "mermaid" => canvas.export_to_mermaid().map_err(CommandError::from),
+        "svg" => canvas.export_to_svg().map_err(CommandError::from),
+        // PNG bytes are returned base64-encoded
+        "png" => {
+            use base64::Engine;
+            let png = canvas
+                .export_to_png(scale.unwrap_or(1.0), background.as_deref())?;
+            Ok(base64::engine::general_purpose::STANDARD.encode(png))
+        }
+        _ => Err(CommandError::Unsupported(format!("Unsupported export format: {}", format))),
     }
 }
 
+// Plugin Commands
+
+#[tauri::command]
+async fn list_plugin_commands(
+    state: State<'_, AppState>,
+) -> Result<Vec<plugins::PluginCommand>, CommandError> {
+    Ok(state.plugin_runtime.read().await.list_commands().await)
+}
+
+#[tauri::command]
+async fn invoke_plugin_command(
+    state: State<'_, AppState>,
+    name: String,
+    args: Option<serde_json::Value>,
+) -> Result<serde_json::Value, CommandError> {
+    state
+        .plugin_runtime
+        .read()
+        .await
+        .invoke_command(&name, args.unwrap_or(serde_json::Value::Null))
+        .await
+        .map_err(CommandError::from)
+}
+
 // Event System Commands - for real-time frontend updates
+
+/// How closely `subscription` matches `event_type`: an exact type beats a
+/// longer pattern, which beats a shorter one and then "*".
+fn subscription_rank(subscription: &str, event_type: &str) -> Option<usize> {
+    if subscription == event_type {
+        return Some(usize::MAX);
+    }
+    let prefix = subscription.strip_suffix('*')?;
+    event_type.starts_with(prefix).then_some(prefix.len())
+}
+
+/// Forwards events to the frontend as `event:<type>` for each subscribed type
+/// or pattern ("terminal.*", or "*" for everything). Subscribing again to a
+/// type that is already forwarded reuses its forwarder. Each event is emitted
+/// once, under the most specific subscription matching it.
 #[tauri::command]
 async fn subscribe_to_events(
     app: tauri::AppHandle,
     state: State<'_, AppState>,
     event_types: Vec<String>,
-) -> Result<(), String> {
+) -> Result<(), CommandError> {
     let event_bus = state.event_bus.clone();
+    let mut forwarders = state.event_forwarders.lock().unwrap_or_else(|e| e.into_inner());
     
     // Create receivers for each event type
     for event_type in event_types {
+        if forwarders.get(&event_type).is_some_and(|forwarder| !forwarder.is_finished()) {
+            continue;
+        }
+        
         let receiver = if event_type == "*" {
             event_bus.subscribe(None)
         } else {
@@ -841,13 +1895,29 @@ async fn subscribe_to_events(
         
         let app_handle = app.clone();
         let event_type_clone = event_type.clone();
+        let all_forwarders = state.event_forwarders.clone();
         
         // Spawn a task to listen for events and emit them to frontend
-        tokio::spawn(async move {
+        let forwarder = tokio::spawn(async move {
             let mut rx = receiver;
             loop {
                 match rx.recv().await {
                     Ok(event) => {
+                        // Leave the event to a more specific forwarder if there is one
+                        let owner = {
+                            let forwarders = all_forwarders.lock().unwrap_or_else(|e| e.into_inner());
+                            forwarders
+                                .keys()
+                                .filter_map(|subscription| {
+                                    subscription_rank(subscription, &event.event_type).map(|rank| (rank, subscription))
+                                })
+                                .max()
+                                .map(|(_, subscription)| subscription.clone())
+                        };
+                        if owner.is_some_and(|owner| owner != event_type_clone) {
+                            continue;
+                        }
+                        
                         // Emit event to frontend
                         if let Err(e) = app_handle.emit(&format!("event:{}", event_type_clone), &event) {
                             tracing::error!("Failed to emit event to frontend: {}", e);
@@ -865,11 +1935,52 @@ async fn subscribe_to_events(
                 }
             }
         });
+        
+        if let Some(previous) = forwarders.insert(event_type, forwarder) {
+            previous.abort();
+        }
     }
     
     Ok(())
 }
 
+/// Stops forwarding the given event types; types that are not forwarded are
+/// ignored.
+#[tauri::command]
+async fn unsubscribe_from_events(
+    state: State<'_, AppState>,
+    event_types: Vec<String>,
+) -> Result<(), CommandError> {
+    let mut forwarders = state.event_forwarders.lock().unwrap_or_else(|e| e.into_inner());
+    for event_type in event_types {
+        if let Some(forwarder) = forwarders.remove(&event_type) {
+            forwarder.abort();
+        }
+    }
+    Ok(())
+}
+
+/// Logged events after `since`, oldest first, filtered like a subscription
+/// (an event type, a pattern such as "terminal.*", or "*"). Empty unless
+/// `event_log_size` is configured.
+#[tauri::command]
+async fn get_recent_events(
+    state: State<'_, AppState>,
+    since: Option<chrono::DateTime<chrono::Utc>>,
+    event_type: Option<String>,
+) -> Result<Vec<events::Event>, CommandError> {
+    Ok(state.event_bus.replay(since, event_type.as_deref()))
+}
+
+/// Events each channel has dropped because a subscriber fell behind, keyed by
+/// event type, pattern, or "*" for the channel carrying every event.
+#[tauri::command]
+async fn get_event_lag_counts(
+    state: State<'_, AppState>,
+) -> Result<std::collections::HashMap<String, u64>, CommandError> {
+    Ok(state.event_bus.lagged_counts())
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
@@ -886,8 +1997,11 @@ pub fn run() {
             tauri::async_runtime::spawn(async move {
                 match AppState::new().await {
                     Ok(state) => {
-                        handle.manage(state);
+                        handle.manage(state.clone());
                         log::info!("Code Furnace application state initialized successfully");
+                        if let Err(e) = state.restore_session().await {
+                            log::error!("Failed to restore the previous session: {}", e);
+                        }
                     }
                     Err(e) => {
                         log::error!("Failed to initialize application state: {}", e);
@@ -907,12 +2021,23 @@ pub fn run() {
             send_terminal_input,
             resize_terminal,
             close_terminal_session,
+            register_terminal_snippet,
+            list_terminal_snippets,
             open_file,
             get_file_buffer,
             update_file_buffer,
             save_file_buffer,
+            set_cursor_position,
+            close_file_buffer,
             get_file_tree,
             expand_directory,
+            search_workspace,
+            find_files,
+            global_search,
+            get_recent_files,
+            clear_recent_files,
+            replace_in_workspace,
+            cancel_workspace_search,
             create_file,
             create_directory,
             delete_file,
@@ -920,53 +2045,106 @@ pub fn run() {
             set_workspace_root,
             list_file_buffers,
             ask_agent,
+            ask_agent_streaming,
+            cancel_agent_request,
+            list_agent_tools,
+            set_agent_tool_permission,
             list_projects,
             create_project,
             open_project,
+            remove_project,
             get_active_project,
             start_background_process,
             stop_background_process,
             restart_background_process,
+            get_process_metrics,
             list_background_processes,
             get_process_logs,
+            clear_process_logs,
             start_project_dev_server,
+            list_run_configs,
+            run_tests,
+            run_build,
+            start_project_process,
+            start_project_stack,
             open_git_repository,
             get_git_status,
             git_stage_file,
             git_unstage_file,
             git_commit,
             git_get_commit_history,
+            git_get_file_history,
             git_get_branches,
             git_create_branch,
             git_switch_branch,
             git_get_diff,
+            git_get_file_diff,
+            git_discard_file_changes,
+            git_blame,
+            git_stash_save,
+            git_stash_list,
+            git_stash_apply,
+            git_stash_pop,
+            git_stash_drop,
             git_push,
             git_pull,
             generate_ai_commit_message,
             get_completion,
             get_hover_info,
+            get_definition,
+            get_references,
+            format_buffer,
+            rename_symbol,
+            get_document_symbols,
+            search_symbols,
+            get_signature_help,
+            get_code_actions,
+            apply_code_action,
+            get_lsp_log,
             get_diagnostics,
             create_conversation,
             list_conversations,
             get_conversation,
+            get_conversation_usage,
+            export_conversation,
             set_active_conversation,
+            set_conversation_provider,
             search_conversations,
             list_available_agents,
             get_config,
             update_agent_config,
             update_git_tokens,
             update_ui_preferences,
+            reload_config,
             validate_config,
             test_agent_connection,
             subscribe_to_events,
+            unsubscribe_from_events,
+            get_event_lag_counts,
+            get_recent_events,
             create_canvas,
             get_canvas,
             update_canvas,
             save_canvas,
+            undo_canvas,
+            redo_canvas,
+            reorder_canvas_element,
             list_canvases,
             delete_canvas,
             export_canvas,
+            import_canvas_mermaid,
+            generate_canvas_code,
+            list_plugin_commands,
+            invoke_plugin_command,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app_handle, event| {
+            if let tauri::RunEvent::Exit = event {
+                if let Some(state) = app_handle.try_state::<AppState>() {
+                    let state = state.inner().clone();
+                    tauri::async_runtime::block_on(async move { state.shutdown().await });
+                }
+            }
+        });
 }
\ No newline at end of file
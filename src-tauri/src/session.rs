@@ -0,0 +1,98 @@
+//! What was open when the app last exited: the active project, the open
+//! files with their cursors, and the terminal sessions. Saved on shutdown
+//! and reopened on the next start when `Config::restore_session` is set.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::{editor, terminal, utils, workspace};
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionSnapshot {
+    pub active_project: Option<uuid::Uuid>,
+    /// Open files, the active one last so it is active again once reopened.
+    pub buffers: Vec<BufferSnapshot>,
+    pub terminals: Vec<TerminalSnapshot>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BufferSnapshot {
+    pub path: PathBuf,
+    pub cursor_position: editor::CursorPosition,
+}
+
+/// Terminal environments are not kept, since they may hold secrets.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TerminalSnapshot {
+    pub name: String,
+    pub working_directory: PathBuf,
+    pub shell: String,
+}
+
+/// What restoring a session reopened, and the files it had to skip because
+/// they no longer exist.
+#[derive(Debug, Default, Serialize)]
+pub struct RestoredSession {
+    pub project: Option<uuid::Uuid>,
+    pub buffers: usize,
+    pub terminals: usize,
+    pub missing_files: Vec<PathBuf>,
+}
+
+impl SessionSnapshot {
+    pub async fn capture(
+        editor_manager: &editor::EditorManager,
+        terminal_manager: &terminal::TerminalManager,
+        workspace_manager: &workspace::WorkspaceManager,
+    ) -> Self {
+        let active_buffer = editor_manager.active_buffer().await;
+        let mut buffers = editor_manager.list_buffers().await;
+        buffers.sort_by(|a, b| (Some(a.id) == active_buffer, &a.path).cmp(&(Some(b.id) == active_buffer, &b.path)));
+        
+        let mut terminals = terminal_manager.list_sessions().await;
+        terminals.sort_by_key(|session| session.created_at);
+        
+        Self {
+            active_project: workspace_manager.get_active_project().await.map(|project| project.id),
+            buffers: buffers
+                .into_iter()
+                .map(|buffer| BufferSnapshot {
+                    path: buffer.path,
+                    cursor_position: buffer.cursor_position,
+                })
+                .collect(),
+            terminals: terminals
+                .into_iter()
+                .map(|session| TerminalSnapshot {
+                    name: session.name,
+                    working_directory: session.working_directory,
+                    shell: session.shell,
+                })
+                .collect(),
+        }
+    }
+    
+    fn file() -> Result<PathBuf> {
+        Ok(utils::paths::get_app_data_dir()?.join("session.json"))
+    }
+    
+    /// The snapshot saved when the app last exited, if there is one.
+    pub fn load() -> Result<Option<Self>> {
+        let file = Self::file()?;
+        if !file.exists() {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_str(&std::fs::read_to_string(file)?)?))
+    }
+    
+    /// Writes the snapshot through a temporary file.
+    pub fn save(&self) -> Result<()> {
+        let file = Self::file()?;
+        let temp_path = file.with_extension("json.tmp");
+        std::fs::write(&temp_path, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&temp_path, file)?;
+        Ok(())
+    }
+}